@@ -0,0 +1,62 @@
+fn main() {
+    #[cfg(feature = "bundled-db")]
+    bundle::embed();
+}
+
+/// Copies the compressed database into OUT_DIR for `include_bytes!`, together with the
+/// SHA-256 of the decompressed file, which `connect_bundled` checks the extracted copy against.
+#[cfg(feature = "bundled-db")]
+mod bundle {
+    use sha2::{Digest, Sha256};
+    use std::fs::{self, File};
+    use std::io::{self, BufReader, Write};
+    use std::path::PathBuf;
+
+    /// Overrides the compressed database that gets embedded.
+    const BUNDLE_ENV: &str = "JMDICTDB_BUNDLE";
+
+    /// Hashes everything written to it.
+    struct HashWriter(Sha256);
+
+    impl Write for HashWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.update(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    pub fn embed() {
+        let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+        let src = match std::env::var_os(BUNDLE_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap()).join("data/jmdict_e.db.zst"),
+        };
+        println!("cargo:rerun-if-env-changed={}", BUNDLE_ENV);
+        println!("cargo:rerun-if-changed={}", src.display());
+
+        let bundle = out_dir.join("bundle.db.zst");
+        let checksum = out_dir.join("bundle.sha256");
+        if !src.exists() {
+            // An empty bundle; connect_bundled reports it at runtime
+            println!(
+                "cargo:warning=bundled-db: {} not found, set {} to a zstd-compressed database",
+                src.display(),
+                BUNDLE_ENV
+            );
+            fs::write(&bundle, b"").unwrap();
+            fs::write(&checksum, "").unwrap();
+            return;
+        }
+
+        fs::copy(&src, &bundle).unwrap();
+        let mut hasher = HashWriter(Sha256::new());
+        zstd::stream::copy_decode(BufReader::new(File::open(&bundle).unwrap()), &mut hasher)
+            .unwrap_or_else(|err| panic!("bundled-db: {} is not a valid zstd file: {}", src.display(), err));
+        let hex: String = hasher.0.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+        fs::write(&checksum, hex).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::kana::is_kana;
+use crate::{is_kanji, DictDb, Result};
+
+/// A piece of annotated text, see `DictDb::annotate_with_furigana`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RubySegment {
+    /// The text as written in the input.
+    pub surface: String,
+    /// The reading of `surface`, None for text that needs none (kana, ASCII, punctuation)
+    /// and for kanji no dictionary word was found for.
+    pub ruby: Option<String>,
+}
+
+/// Text split into `RubySegment`s, the segments put together give the input back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RubyText {
+    pub segments: Vec<RubySegment>,
+}
+
+/// Escapes text for HTML element content and attribute values.
+pub(crate) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl RubyText {
+    /// The input text.
+    pub fn surface(&self) -> String {
+        self.segments.iter().map(|s| s.surface.as_str()).collect()
+    }
+
+    /// HTML with a `<ruby>` element per segment with a reading, 食<rt>た</rt> for 食[た];
+    /// the rest is escaped text.
+    pub fn to_html_ruby(&self) -> String {
+        let mut out = String::new();
+        for s in &self.segments {
+            match &s.ruby {
+                Some(ruby) => {
+                    out.push_str(&format!("<ruby>{}<rt>{}</rt></ruby>", escape_html(&s.surface), escape_html(ruby)))
+                }
+                None => out.push_str(&escape_html(&s.surface)),
+            }
+        }
+        out
+    }
+
+    /// Anki's furigana syntax, 食[た]べ 物[もの]: the reading in brackets after the text it
+    /// belongs to, which starts after the previous space, so one is put before every
+    /// segment with a reading that doesn't start the text.
+    pub fn to_anki_format(&self) -> String {
+        let mut out = String::new();
+        for s in &self.segments {
+            if let Some(ruby) = &s.ruby {
+                if !out.is_empty() && !out.ends_with(' ') {
+                    out.push(' ');
+                }
+                out.push_str(&format!("{}[{}]", s.surface, ruby));
+            } else {
+                out.push_str(&s.surface);
+            }
+        }
+        out
+    }
+
+    /// Appends a segment, joining text without a reading to the previous such segment.
+    fn push(&mut self, surface: &str, ruby: Option<String>) {
+        match (self.segments.last_mut(), &ruby) {
+            (Some(last), None) if last.ruby.is_none() => last.surface.push_str(surface),
+            _ => self.segments.push(RubySegment { surface: surface.to_string(), ruby }),
+        }
+    }
+}
+
+/// A headword starting with some kanji: the entry, the keb and the reading to use.
+struct Headword {
+    ent_seq: i64,
+    keb: Vec<char>,
+    reb: String,
+}
+
+/// How much of `text` a headword covers: the common start, if the rest of the headword is
+/// kana only, so that 食べる covers 食べ of 食べています. The kanji must all be matched.
+fn covered(keb: &[char], text: &[char]) -> Option<usize> {
+    let common = keb.iter().zip(text).take_while(|(a, b)| a == b).count();
+    (common > 0 && keb[common..].iter().all(|&c| is_kana(c))).then_some(common)
+}
+
+impl DictDb {
+    /// Splits text into segments with furigana, e.g. for `<ruby>` markup.
+    ///
+    /// Kanji are matched against the kebs of the dictionary, longest match first. A keb
+    /// also matches when only its trailing kana differ, so conjugated verbs get the reading
+    /// of their kanji. Readings come from the furigana table when it was loaded and from
+    /// `align_furigana` otherwise. Everything that isn't kanji (kana, ASCII, punctuation)
+    /// passes through without a reading, as do kanji no word was found for.
+    pub async fn annotate_with_furigana(&self, text: &str) -> Result<RubyText> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut headwords: HashMap<char, Vec<Headword>> = HashMap::new();
+        let mut out = RubyText::default();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if !is_kanji(c) {
+                out.push(&c.to_string(), None);
+                i += 1;
+                continue;
+            }
+            let candidates = match headwords.entry(c) {
+                Entry::Occupied(found) => found.into_mut(),
+                Entry::Vacant(slot) => slot.insert(self.headwords_starting_with(c).await?),
+            };
+            // Longest first, then whole kebs, then the more common words
+            let rest = &chars[i..];
+            let best = candidates
+                .iter()
+                .enumerate()
+                .filter_map(|(rank, h)| covered(&h.keb, rest).map(|len| (len, len == h.keb.len(), rank, h)))
+                .max_by_key(|&(len, whole, rank, _)| (len, whole, std::cmp::Reverse(rank)));
+            let Some((len, whole, _, headword)) = best else {
+                out.push(&c.to_string(), None);
+                i += 1;
+                continue;
+            };
+            let keb: String = headword.keb.iter().collect();
+            let surface: String = rest[..len].iter().collect();
+            match self.furigana_for(headword.ent_seq, &keb, &headword.reb).await? {
+                Some(segments) => {
+                    // Only the part of the keb that is in the text
+                    let mut left = len;
+                    for s in segments {
+                        let n = s.text.chars().count();
+                        if left == 0 {
+                            break;
+                        }
+                        if n <= left || s.ruby.is_some() {
+                            out.push(&s.text, s.ruby);
+                            left = left.saturating_sub(n);
+                        } else {
+                            out.push(&s.text.chars().take(left).collect::<String>(), None);
+                            left = 0;
+                        }
+                    }
+                }
+                None => out.push(&surface, whole.then(|| headword.reb.clone())),
+            }
+            i += len;
+        }
+        Ok(out)
+    }
+
+    /// The kebs starting with the kanji `ch`, most frequent entries first, with the first
+    /// reading of their entry.
+    async fn headwords_starting_with(&self, ch: char) -> Result<Vec<Headword>> {
+        let rows: Vec<(i64, String, String)> = sqlx::query_as(
+            r#"
+            SELECT e.ent_seq, je.value, COALESCE(
+                -- The first reb not restricted to other kebs, as EntryParsed::reading_for
+                (SELECT r.value FROM json_each(e.rebs) r
+                 WHERE NOT EXISTS (SELECT 1 FROM json_each(e.re_restr, '$[' || r.key || ']'))
+                    OR EXISTS (SELECT 1 FROM json_each(e.re_restr, '$[' || r.key || ']') x WHERE x.value = je.value)
+                 ORDER BY r.key LIMIT 1),
+                json_extract(e.rebs, '$[0]'))
+            FROM kanji_index k JOIN entries e ON e.ent_seq = k.ent_seq, json_each(e.kebs) je
+            WHERE k.ch = ?1 AND substr(je.value, 1, 1) = ?1
+            ORDER BY e.nf_bucket IS NULL, e.nf_bucket, e.ent_seq, je.key
+            "#,
+        )
+        .bind(ch.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(ent_seq, keb, reb)| Headword { ent_seq, keb: keb.chars().collect(), reb })
+            .collect())
+    }
+}
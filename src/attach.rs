@@ -0,0 +1,207 @@
+use sqlx::pool::PoolConnection;
+use sqlx::{Sqlite, SqliteConnection};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::dict_db::mark_applicable;
+use crate::guards::non_empty;
+use crate::{DictDb, DictError, EntryParsed, EntryRow, Result};
+
+/// SQLite's default SQLITE_MAX_ATTACHED.
+pub const MAX_ATTACHED: usize = 10;
+
+/// The databases that should be attached to every connection, shared with the guards.
+pub(crate) type AttachRegistry = Arc<Mutex<Vec<AttachedDb>>>;
+
+#[derive(Debug, Clone)]
+pub(crate) struct AttachedDb {
+    alias: String,
+    path: String,
+}
+
+/// Turns a file path into the URI filename given to ATTACH.
+///
+/// The attached database inherits the open flags of the main one, so attaching a plain path to
+/// an in-memory database would open an empty in-memory database; `mode=rw` resets that.
+fn attach_uri(path: &str) -> String {
+    let escaped = path.replace('%', "%25").replace('?', "%3f").replace('#', "%23");
+    format!("file:{}?mode=rw", escaped)
+}
+
+/// Keeps a database attached, see `DictDb::attach`. Dropping it detaches the database.
+#[derive(Debug)]
+pub struct Attachment {
+    alias: String,
+    registry: AttachRegistry,
+}
+
+impl Attachment {
+    /// The alias the database is attached as.
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+}
+
+impl Drop for Attachment {
+    fn drop(&mut self) {
+        // Connections detach it the next time they are used
+        if let Ok(mut registry) = self.registry.lock() {
+            registry.retain(|a| a.alias != self.alias);
+        }
+    }
+}
+
+/// Aliases end up in SQL as identifiers, so only plain names are allowed.
+fn valid_alias(alias: &str) -> bool {
+    let mut chars = alias.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !alias.eq_ignore_ascii_case("main")
+        && !alias.eq_ignore_ascii_case("temp")
+}
+
+impl DictDb {
+    /// Attaches another dictionary database, e.g. a file with custom entries, under `alias`.
+    ///
+    /// The database must have been created by this crate. Attached databases are searched
+    /// together with the main one by `search_all_by_reading` and `search_all_by_kanji`,
+    /// which keeps the main database untouched when user data lives elsewhere.
+    /// The database stays attached until the returned guard is dropped.
+    ///
+    /// # Errors
+    /// `DictError::TooManyAttachments` past `MAX_ATTACHED` databases, `DictError::AliasInUse`
+    /// and `DictError::InvalidAlias` for unusable aliases.
+    pub async fn attach(&self, path: impl AsRef<Path>, alias: &str) -> Result<Attachment> {
+        if !valid_alias(alias) {
+            return Err(DictError::InvalidAlias(alias.to_string()).into());
+        }
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(format!("{} does not exist", path.display()).into());
+        }
+        let path = attach_uri(path.to_str().ok_or("database path is not valid UTF-8")?);
+
+        {
+            let mut registry = self.attachments.lock().map_err(|_| "attachment registry poisoned")?;
+            if registry.iter().any(|a| a.alias.eq_ignore_ascii_case(alias)) {
+                return Err(DictError::AliasInUse(alias.to_string()).into());
+            }
+            if registry.len() >= MAX_ATTACHED {
+                return Err(DictError::TooManyAttachments { limit: MAX_ATTACHED }.into());
+            }
+            registry.push(AttachedDb { alias: alias.to_string(), path });
+        }
+        let attachment = Attachment { alias: alias.to_string(), registry: self.attachments.clone() };
+
+        // Attach right away so a bad file is reported here; the guard undoes the registration
+        let mut conn = self.attached_connection().await?;
+        let tables: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT name FROM {}.sqlite_master WHERE type = 'table' AND name IN ('entries', 'senses', 'glosses', 'frequency', 'jlpt')",
+            alias
+        ))
+        .fetch_all(&mut *conn)
+        .await?;
+        if tables.len() < 5 {
+            return Err(format!("the database attached as {} is not a jmdictdb database", alias).into());
+        }
+        Ok(attachment)
+    }
+
+    /// Returns the aliases of the attached databases, in the order they were attached.
+    pub fn attached(&self) -> Vec<String> {
+        self.attachments
+            .lock()
+            .map(|registry| registry.iter().map(|a| a.alias.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Acquires a connection and brings its attached databases in line with the registry.
+    pub(crate) async fn attached_connection(&self) -> Result<PoolConnection<Sqlite>> {
+        let wanted: Vec<AttachedDb> = self.attachments.lock().map_err(|_| "attachment registry poisoned")?.clone();
+        let mut conn = self.pool.acquire().await?;
+
+        let current: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_database_list WHERE name NOT IN ('main', 'temp')")
+                .fetch_all(&mut *conn)
+                .await?;
+        for alias in &current {
+            if !wanted.iter().any(|a| &a.alias == alias) {
+                sqlx::query(&format!("DETACH DATABASE {}", alias)).execute(&mut *conn).await?;
+            }
+        }
+        for a in &wanted {
+            if !current.contains(&a.alias) {
+                sqlx::query(&format!("ATTACH DATABASE ?1 AS {}", a.alias))
+                    .bind(&a.path)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+        Ok(conn)
+    }
+
+    /// Searches the main and all attached databases for entries with the reading (reb),
+    /// main database first.
+    pub async fn search_all_by_reading(&self, reading: &str) -> Result<Vec<EntryParsed>> {
+        self.observe("search_all_by_reading", reading, String::new, async {
+            non_empty(reading)?;
+            let mut entries = self.search_all(
+                "EXISTS (SELECT 1 FROM json_each(e.rebs) je WHERE je.value = ?1)",
+                reading,
+            )
+            .await?;
+            mark_applicable(&mut entries, None, Some(reading));
+            Ok(entries)
+        })
+        .await
+    }
+
+    /// Searches the main and all attached databases for entries with the kanji (keb),
+    /// main database first.
+    pub async fn search_all_by_kanji(&self, kanji: &str) -> Result<Vec<EntryParsed>> {
+        self.observe("search_all_by_kanji", kanji, String::new, async {
+            non_empty(kanji)?;
+            let mut entries = self.search_all(
+                "e.kebs IS NOT NULL AND EXISTS (SELECT 1 FROM json_each(e.kebs) je WHERE je.value = ?1)",
+                kanji,
+            )
+            .await?;
+            mark_applicable(&mut entries, Some(kanji), None);
+            Ok(entries)
+        })
+        .await
+    }
+
+    /// "main" followed by the aliases attached on `conn`, in the order they were attached.
+    pub(crate) async fn schemas(conn: &mut SqliteConnection) -> Result<Vec<String>> {
+        let mut schemas = vec!["main".to_string()];
+        schemas.extend(
+            sqlx::query_scalar::<_, String>("SELECT name FROM pragma_database_list WHERE name NOT IN ('main', 'temp') ORDER BY seq")
+                .fetch_all(&mut *conn)
+                .await?,
+        );
+        Ok(schemas)
+    }
+
+    /// Runs `condition` (on the entries table as `e`, with `?1` bound to `value`) in every database.
+    async fn search_all(&self, condition: &str, value: &str) -> Result<Vec<EntryParsed>> {
+        let mut conn = self.attached_connection().await?;
+        let schemas = Self::schemas(&mut conn).await?;
+
+        let mut results = Vec::new();
+        for schema in &schemas {
+            let rows = sqlx::query_as::<_, EntryRow>(&format!(
+                "SELECT e.* FROM {}.entries e WHERE {} ORDER BY e.ent_seq",
+                schema, condition
+            ))
+            .bind(value)
+            .fetch_all(&mut *conn)
+            .await?;
+            let conn: &mut SqliteConnection = &mut conn;
+            for row in rows {
+                results.push(Self::hydrate_entry_in(conn, schema, row).await?);
+            }
+        }
+        Ok(results)
+    }
+}
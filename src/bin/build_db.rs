@@ -1,81 +1,152 @@
-use regex::Regex;
-use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::BufReader;
+use std::path::Path;
 use std::time::Instant;
-use jmdictdb::{DictDb, JMDict, DB_URL};
+use jmdictdb::{default_db_path, default_db_url, BuildLock, BuildOptions, DictDb};
+use sqlx::{Sqlite, migrate::MigrateDatabase};
 
 /// The URL to the JMdict XML file.
 pub const DICT_URL: &str = "data/JMdict_e";
 
+/// The URL to the optional JmdictFurigana JSON file, loaded if present.
+pub const FURIGANA_URL: &str = "data/JmdictFurigana.json";
 
 
+
+/// Pass `--normalize-glosses` to run the gloss normalization pass after inserting.
+pub const NORMALIZE_GLOSSES_FLAG: &str = "--normalize-glosses";
+
+/// Pass `--report <path>` to write the `BuildReport` as JSON to `<path>`.
+pub const REPORT_FLAG: &str = "--report";
+
+/// Pass `--store-raw-xml` to keep the XML of each entry, see `DictDb::raw_xml`; needs the
+/// `raw-xml` feature.
+pub const STORE_RAW_XML_FLAG: &str = "--store-raw-xml";
+
+/// Exit codes: 0 for a clean build, 2 when entries were skipped (see `BuildReport::exit_code`),
+/// 1 on a fatal error.
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut normalize_glosses = false;
+    let mut report_path = None;
+    #[allow(unused_mut)]
+    let mut build_opts = BuildOptions::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            NORMALIZE_GLOSSES_FLAG => normalize_glosses = true,
+            REPORT_FLAG => report_path = Some(args.next().ok_or("--report needs a path")?),
+            #[cfg(feature = "raw-xml")]
+            STORE_RAW_XML_FLAG => build_opts.store_raw_xml = true,
+            #[cfg(not(feature = "raw-xml"))]
+            STORE_RAW_XML_FLAG => return Err("--store-raw-xml needs build_db built with the raw-xml feature".into()),
+            _ => return Err(format!("unknown argument {}", arg).into()),
+        }
+    }
     let total_start_time = Instant::now();
     println!("--- JMDict Parser Started ---");
+    // Opened first, a missing file leaves the existing database alone
+    let input = BufReader::new(File::open(DICT_URL)?);
 
-    // --- 1. Load XML file into memory ---
-    println!("\n[1/4] Loading JMdict file into memory...");
-    let load_start = Instant::now();
-    let file = File::open(DICT_URL)?;
-    let mut reader = BufReader::new(file);
-    let mut xml_content = String::new();
-    reader.read_to_string(&mut xml_content)?;
-    println!("     File loaded successfully. (took: {:.2?})", load_start.elapsed());
-
-    // --- 2. Pre-process XML to handle custom entities ---
-    println!("\n[2/4] Replacing XML entities using regex...");
-    let replace_start = Instant::now();
-
-    // Regex to find all <!ENTITY name "value"> definitions in the DTD.
-    // This is necessary because the quick-xml parser doesn't handle DTDs by default.
-    // It captures the entity name (e.g., "n") and its value (e.g., "noun").
-    let entity_re = Regex::new(r#"<!ENTITY\s+([^\s]+)\s+"([^"]+)">"#)?;
-    
-    // Create a map to store entities for fast lookup, e.g., {"&n;" -> "noun"}
-    let mut entity_map = HashMap::new();
-    for cap in entity_re.captures_iter(&xml_content) {
-        let name = &cap[1];
-        let value = &cap[2];
-        // We format it into the &name; format for replacement.
-        entity_map.insert(format!("&{};", name), value.to_string());
+    let db_path = default_db_path();
+    if let Some(dir) = db_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
     }
+    let db_url = default_db_url();
+    println!("     Database: {}", db_path.display());
+    // Held until the end, through the reset and the restores; released on any exit from here
+    let lock = match BuildLock::acquire(&db_path) {
+        Ok(lock) => lock,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
-    // Replace all found entities in the XML content.
-    // We clone the content to perform the replacements, keeping the original intact.
-    let mut xml = xml_content; // No need to clone, we can consume xml_content
-    for (entity, value) in &entity_map {
-        xml = xml.replace(entity, value);
+    // Keep the user's custom entries (and word lists) across the rebuild
+    let mut custom_entries = Vec::new();
+    #[cfg(feature = "userdata")]
+    let mut wordlists = Vec::new();
+    if Sqlite::database_exists(&db_url).await? {
+        let old_db = DictDb::connect(&db_url).await?;
+        custom_entries = old_db.list_custom_entries().await?;
+        #[cfg(feature = "userdata")]
+        for list in old_db.wordlists().await? {
+            let items = old_db.list_items(list.id).await?;
+            wordlists.push((list, items));
+        }
+        old_db.close().await;
     }
-    println!("     Found and replaced {} entities. (took: {:.2?})", entity_map.len(), replace_start.elapsed());
-    
-    // --- 3. Deserialize the cleaned XML into Rust structs ---
-    println!("\n[3/4] Parsing XML into structs...");
-    let parse_start = Instant::now();
-    let dict: JMDict = quick_xml::de::from_str(&xml)?;
-    println!("     Parsing complete. (took: {:.2?})", parse_start.elapsed());
-
-    // --- 4. Print summary ---
-    println!("Successfully parsed {} entries.", dict.entry.len());
-
-    if let Some(entry) = dict.entry.get(0) {
-        println!("First entry example: {:?}", entry);
+
+    // Reset the database if it exists
+    DictDb::reset_database(&db_url).await?;
+    let mut db = DictDb::connect(&db_url).await?;
+    db.hold_build_lock(lock);
+
+    // --- 1. Parse the XML, expanding the entities of its DTD, and insert the entries ---
+    println!("\n[1/2] Parsing {} and inserting the entries...", DICT_URL);
+    let mut report = db.build_from_reader_with(input, &build_opts).await?;
+    println!(
+        "     Parsed {} entries, inserted {}, replaced {} entities.",
+        report.entries_parsed, report.entries_inserted, report.entities_replaced
+    );
+    for skipped in &report.skipped {
+        println!("     Skipped entry {}: {}", skipped.ent_seq, skipped.reason);
     }
-    
-    println!("\nTotal time taken: {:.2?}", total_start_time.elapsed());
 
-    // --- 5. Insert entries into the database ---
+    // --- 2. Post-processing ---
+    println!("\n[2/2] Post-processing...");
+    if normalize_glosses {
+        let normalize_start = Instant::now();
+        let normalized = db.normalize_glosses().await?;
+        println!(
+            "     Normalized the glosses of {} senses, removed {} duplicates. (took: {:.2?})",
+            normalized.senses,
+            normalized.duplicates_removed,
+            normalize_start.elapsed()
+        );
+        report.push_duration("normalize_glosses", normalize_start.elapsed());
+    }
 
-    println!("\n[4/4] Inserting entries into database, reset db if needed...");
-    // Reset the database if it exists
-    DictDb::reset_database(DB_URL).await?;
-    let db = DictDb::connect(DB_URL).await?;
-    let insert_start = Instant::now();
+    if !custom_entries.is_empty() {
+        db.restore_custom_entries(&custom_entries).await?;
+        println!("     Restored {} custom entries.", custom_entries.len());
+    }
 
-    db.insert_entries(&dict.entry).await?;
+    #[cfg(feature = "userdata")]
+    if !wordlists.is_empty() {
+        for (list, items) in &wordlists {
+            db.restore_list(list, items).await?;
+        }
+        println!("     Restored {} word lists.", wordlists.len());
+    }
 
-    println!("     Inserted {} entries. (took: {:.2?})", dict.entry.len(), insert_start.elapsed());
+    // Load furigana alignment data if available
+    if Path::new(FURIGANA_URL).exists() {
+        println!("     Loading furigana data from {}...", FURIGANA_URL);
+        let furigana_start = Instant::now();
+        let furigana = db.load_furigana(BufReader::new(File::open(FURIGANA_URL)?)).await?;
+        println!(
+            "     Stored {} furigana rows, {} records unmatched. (took: {:.2?})",
+            furigana.rows_stored,
+            furigana.unmatched,
+            furigana_start.elapsed()
+        );
+        report.push_duration("furigana", furigana_start.elapsed());
+    }
+    report.output_size = db.database_size().await?;
+    println!("\n{}", db.size_report().await?);
+    db.close().await;
+    // Releases the build lock, process::exit below would skip it
+    drop(db);
 
+    println!("\nTotal time taken: {:.2?}", total_start_time.elapsed());
+    if let Some(path) = report_path {
+        serde_json::to_writer_pretty(File::create(&path)?, &report)?;
+        println!("     Wrote the build report to {}", path);
+    }
+    if !report.is_clean() {
+        std::process::exit(report.exit_code());
+    }
     Ok(())
 }
\ No newline at end of file
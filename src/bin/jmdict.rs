@@ -0,0 +1,279 @@
+use jmdictdb::render::{terminal_with_furigana, TerminalOptions};
+use jmdictdb::{
+    default_db_url, Collation, DictDb, EntryParsed, EreaderFormat, EreaderOptions, ExportFormat, FlatRowOptions, IntegrityReport, JsonStyle, PartOfSpeech, RankOptions, ScoreBreakdown,
+    ScoredEntry, SCHEMA_VERSION, StarDictOptions, Xref,
+};
+
+const USAGE: &str = "\
+Usage: jmdict [--db <url>] <command>
+
+Commands:
+  backup <dest>   Write a consistent copy of the database to <dest>
+  browse          List the entries with a part of speech, page by page (needs --pos)
+  check           Run an integrity check on the database
+  dump            Write the database as SQL text to stdout, the same for the same data
+  export          Write one line per gloss as CSV or TSV to stdout, or the dictionary for
+                  an e-reader with --format kobo or kindle-src
+  health          Check that the database answers and has this version's schema, for
+                  readiness probes; exits with 1 if not
+  restore <file>  Replace the contents of the database with a SQL dump
+  schema          Print the JSON Schema of the search results
+  stardict <dir>  Write the dictionary as jmdict.ifo, .idx and .dict into <dir>, for
+                  StarDict readers such as GoldenDict or KOReader
+  search <query>  Ranked search over kanji and readings; <word>#<n> shows sense n of the word,
+                  numbered from 1 as in JMdict cross-references, e.g. 見る#2
+  stats           Print the number of entries and senses, the size of each table, and
+                  the number of senses per tag
+
+Options:
+  --db <url>      The database URL, default from JMDICTDB_PATH or the data directory
+  --explain       With search, show how each result's score is made up
+  --pretty        With search and browse, show each entry with furigana and numbered
+                  senses, in color when printing to a terminal (unless NO_COLOR is set)
+  --format <fmt>  With export, csv or tsv (default csv), kobo for a Kobo dicthtml zip, or
+                  kindle-src for the OPF and HTML to build a Kindle dictionary from
+  --out <path>    With export to kobo or kindle-src, the zip file or directory to write
+                  (default dicthtml-ja-en.zip or jmdict-kindle)
+  --all-kebs      With export, repeat each entry's lines for every keb
+  --collation <c> With export to csv or tsv, order the lines by gloss: binary (by bytes),
+                  nocase (ASCII case ignored) or casefold (case, accents and leading
+                  notes such as \"(of a person)\" ignored)
+  --pos <code>    With browse, the part of speech, e.g. adj-na or vs
+  --page <n>      With browse, the page to show, from 1 (default 1)
+  --page-size <n> With browse, entries per page (default 50)
+  --camel         With schema, the camelCase field names of JsonStyle::Camel";
+
+/// Characters of glosses shown per entry in result lists.
+const SUMMARY_CHARS: usize = 80;
+
+/// The headword with its readings, "食べる【たべる】".
+fn headword(e: &EntryParsed) -> String {
+    let form = e.display_form();
+    if e.rebs.iter().any(|r| r == form) {
+        e.rebs.join("、")
+    } else {
+        format!("{}【{}】", form, e.rebs.join("、"))
+    }
+}
+
+/// The headword with its readings and the glosses, "食べる【たべる】 (1) to eat (2) to live on …".
+fn describe(e: &EntryParsed) -> String {
+    format!("{}  {}", headword(e), e.summary(SUMMARY_CHARS))
+}
+
+/// Prints a search result, "1. 1358280  score 107.23  食べる【たべる】 to eat".
+fn print_result(n: usize, result: &ScoredEntry) {
+    println!("{:>2}. {}  score {:.2}  {}", n, result.entry.ent_seq, result.score, describe(&result.entry));
+}
+
+/// Prints a score breakdown as a small table under its result.
+fn print_breakdown(b: &ScoreBreakdown) {
+    let kind = format!("{:?}", b.match_kind).to_lowercase();
+    let bucket = b.nf_bucket.map_or_else(|| "-".to_string(), |n| format!("nf{:02}", n));
+    println!("      match      {:>8}  {:>7.2}", kind, b.match_component);
+    println!("      frequency  {:>8}  {:>7.2}", "", b.frequency_component);
+    println!("      total      {:>8}  {:>7.2}", "", b.total);
+    println!("      ties       {:>8}  priority {}", bucket, b.priority_component);
+}
+
+/// Prints an entry with `terminal_with_furigana`, with the stored furigana of its display
+/// form if there are any.
+async fn print_pretty(db: &DictDb, e: &EntryParsed, opts: &TerminalOptions) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let form = e.display_form();
+    let furigana = match e.reading_for(form) {
+        Some(reading) => db.furigana_for(e.ent_seq, form, reading).await?,
+        None => None,
+    };
+    println!("{}", terminal_with_furigana(e, furigana.as_deref(), opts));
+    Ok(())
+}
+
+/// Parses "見る#2" as a reference to sense 2 of 見る, None for other queries.
+fn sense_query(query: &str) -> Option<Xref> {
+    let (word, number) = query.rsplit_once('#')?;
+    let mut xref: Xref = word.parse().ok()?;
+    xref.sense = Some(number.parse().ok()?);
+    Some(xref)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut args = std::env::args().skip(1);
+    let mut db_url = default_db_url();
+    let mut rest = Vec::new();
+    let mut explain = false;
+    let mut pretty = false;
+    let mut format = ExportFormat::Csv;
+    let mut ereader = None;
+    let mut out = None;
+    let mut all_kebs = false;
+    let mut collation = None;
+    let mut pos = None;
+    let mut page = 1;
+    let mut page_size = 50;
+    let mut style = JsonStyle::Snake;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => db_url = args.next().ok_or("--db needs a value")?,
+            "--explain" => explain = true,
+            "--pretty" => pretty = true,
+            "--format" => {
+                let value = args.next().ok_or("--format needs a value")?;
+                match (ExportFormat::parse(&value), EreaderFormat::parse(&value)) {
+                    (Some(f), _) => format = f,
+                    (None, Some(f)) => ereader = Some(f),
+                    _ => return Err(format!("unknown format {}, expected csv, tsv, kobo or kindle-src", value).into()),
+                }
+            }
+            "--out" => out = Some(args.next().ok_or("--out needs a value")?),
+            "--all-kebs" => all_kebs = true,
+            "--collation" => {
+                let value = args.next().ok_or("--collation needs a value")?;
+                let parsed = Collation::parse(&value);
+                collation = Some(parsed.ok_or_else(|| format!("unknown collation {}, expected binary, nocase or casefold", value))?);
+            }
+            "--pos" => pos = Some(PartOfSpeech::parse(&args.next().ok_or("--pos needs a value")?)),
+            "--page" => page = args.next().ok_or("--page needs a value")?.parse()?,
+            "--page-size" => page_size = args.next().ok_or("--page-size needs a value")?.parse()?,
+            "--camel" => style = JsonStyle::Camel,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => rest.push(arg),
+        }
+    }
+
+    match rest.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["schema"] => println!("{}", serde_json::to_string_pretty(&jmdictdb::schema_with(style))?),
+        ["backup", dest] => {
+            let db = DictDb::connect(&db_url).await?;
+            db.backup_to(dest).await?;
+            println!("Backed up {} to {}", db_url, dest);
+        }
+        ["dump"] => {
+            let db = DictDb::connect(&db_url).await?;
+            db.dump_sql(std::io::BufWriter::new(std::io::stdout().lock())).await?;
+        }
+        ["restore", src] => {
+            let db = DictDb::connect(&db_url).await?;
+            let rows = db.restore_sql(std::io::BufReader::new(std::fs::File::open(src)?)).await?;
+            println!("Restored {} rows from {}", rows, src);
+        }
+        ["search", query] if sense_query(query).is_some() => {
+            let db = DictDb::connect(&db_url).await?;
+            let xref = sense_query(query).ok_or("not a sense query")?;
+            match db.resolve_xref(&xref).await? {
+                Some(target) => {
+                    let sense = target.sense.ok_or("no sense number")?;
+                    let number = xref.sense.unwrap_or_default();
+                    println!("{}  {}  ({}) {}", target.entry.ent_seq, headword(&target.entry), number, sense.summary(usize::MAX));
+                }
+                None => println!("No sense {} of {}", xref.sense.unwrap_or_default(), xref.headword()),
+            }
+        }
+        ["search", query] => {
+            let db = DictDb::connect(&db_url).await?;
+            let opts = RankOptions { explain, ..RankOptions::default() };
+            let outcome = db.search_ranked_outcome(query, &opts).await?;
+            if outcome.query_changed() {
+                println!("Showing results for {}", outcome.effective_query);
+            }
+            let results = outcome.results;
+            let terminal = TerminalOptions::detect();
+            for (n, result) in results.iter().enumerate() {
+                if pretty {
+                    print_pretty(&db, &result.entry, &terminal).await?;
+                } else {
+                    print_result(n + 1, result);
+                }
+                if let Some(breakdown) = &result.explain {
+                    print_breakdown(breakdown);
+                }
+            }
+            if results.is_empty() {
+                for c in db.suggest_corrections(query, 5).await? {
+                    println!("Did you mean {} ({} results)?", c.suggestion, c.result_count);
+                }
+            }
+        }
+        ["browse"] => {
+            let pos = pos.ok_or("browse needs --pos, e.g. --pos adj-na")?;
+            let db = DictDb::connect(&db_url).await?;
+            let total = db.count_by_pos(&pos).await?;
+            let pages = (total as usize).div_ceil(page_size.max(1));
+            println!("{} ({}): {} entries, page {} of {}", pos.code(), pos.description(), total, page, pages);
+            let terminal = TerminalOptions::detect();
+            for e in db.entries_by_pos(&pos, page, page_size).await? {
+                if pretty {
+                    print_pretty(&db, &e, &terminal).await?;
+                } else {
+                    println!("{:>8}  {}", e.ent_seq, describe(&e));
+                }
+            }
+        }
+        ["export"] if ereader.is_some() => {
+            let format = ereader.ok_or("no e-reader format")?;
+            let db = DictDb::connect(&db_url).await?;
+            let path = out.unwrap_or_else(|| format.default_path().to_string());
+            let report = db.export_ereader(format, &path, &EreaderOptions::default()).await?;
+            println!("Wrote {} entries with {} inflected forms to {}", report.entries, report.inflections, report.path.display());
+        }
+        ["export"] => {
+            let db = DictDb::connect(&db_url).await?;
+            let opts = FlatRowOptions { all_kebs, collation, ..FlatRowOptions::default() };
+            db.export_flat(&opts, format, std::io::stdout().lock()).await?;
+        }
+        ["stardict", dir] => {
+            let db = DictDb::connect(&db_url).await?;
+            let report = db.export_stardict(dir, &StarDictOptions::default()).await?;
+            println!("Wrote {} entries under {} words to {}", report.entries, report.words, report.ifo_path.display());
+        }
+        ["stats"] => {
+            let db = DictDb::connect(&db_url).await?;
+            println!("entries: {}", db.entry_count().await?);
+            println!("senses:  {}", db.sense_count().await?);
+            println!("\nsize:\n{}", db.size_report().await?);
+            let tags = [
+                ("parts of speech", db.distinct_pos().await?),
+                ("misc", db.distinct_misc().await?),
+                ("fields", db.distinct_fields().await?),
+                ("dialects", db.distinct_dialects().await?),
+            ];
+            for (title, counts) in tags {
+                println!("\n{}:", title);
+                for (tag, count) in counts {
+                    println!("{:>8}  {}", count, tag);
+                }
+            }
+        }
+        ["health"] => {
+            let db = DictDb::connect(&db_url).await?;
+            let health = db.health_check().await?;
+            let version = health.schema_version.map_or("none".to_string(), |v| v.to_string());
+            println!("{} entries, schema version {}, {:?}", health.entry_count, version, health.latency);
+            if !health.ok {
+                eprintln!("schema version {} expected", SCHEMA_VERSION);
+                std::process::exit(1);
+            }
+            println!("ok");
+        }
+        ["check"] => {
+            let db = DictDb::connect(&db_url).await?;
+            match db.integrity_check().await? {
+                IntegrityReport::Ok => println!("ok"),
+                IntegrityReport::Corrupt(messages) => {
+                    for m in &messages {
+                        eprintln!("{}", m);
+                    }
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use crate::{DictDb, EntryParsed, EntryRow, PartOfSpeech, Result};
+
+// Entries with at least one sense of the part of speech, stored expanded or as the bare code
+const HAS_POS_SQL: &str = r#"
+    EXISTS (
+        SELECT 1 FROM senses s, json_each(s.pos) je
+        WHERE s.ent_seq = e.ent_seq AND je.value IN (?1, ?2)
+    )
+"#;
+
+/// The order `DictDb::entries_by_pos_ordered` pages through the entries in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BrowseOrder {
+    /// By ent_seq, the order of JMdict.
+    #[default]
+    EntSeq,
+    /// Simplest first by `complexity_score`, then by ent_seq.
+    Complexity,
+}
+
+impl BrowseOrder {
+    fn order_by(&self) -> &'static str {
+        match self {
+            BrowseOrder::EntSeq => "e.ent_seq",
+            BrowseOrder::Complexity => "e.complexity IS NULL, e.complexity, e.ent_seq",
+        }
+    }
+}
+
+impl DictDb {
+    /// One page of the entries with a sense of this part of speech, by ent_seq, e.g. all
+    /// な-adjectives with `PartOfSpeech::AdjNa`. Pages start at 1 (0 is the same as 1).
+    ///
+    /// Each entry appears once, however many of its senses have the part of speech.
+    pub async fn entries_by_pos(&self, pos: &PartOfSpeech, page: usize, page_size: usize) -> Result<Vec<EntryParsed>> {
+        self.entries_by_pos_ordered(pos, BrowseOrder::EntSeq, page, page_size).await
+    }
+
+    /// `entries_by_pos` in another order, e.g. the 100 simplest Ichidan verbs:
+    /// `entries_by_pos_ordered(&PartOfSpeech::V1, BrowseOrder::Complexity, 1, 100)`.
+    pub async fn entries_by_pos_ordered(&self, pos: &PartOfSpeech, order: BrowseOrder, page: usize, page_size: usize) -> Result<Vec<EntryParsed>> {
+        let offset = page.saturating_sub(1).saturating_mul(page_size);
+        let rows = sqlx::query_as::<_, EntryRow>(&format!(
+            "SELECT e.* FROM entries e WHERE {} ORDER BY {} LIMIT ?3 OFFSET ?4",
+            HAS_POS_SQL,
+            order.order_by()
+        ))
+        .bind(pos.description())
+        .bind(pos.code())
+        .bind(page_size as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        self.hydrate_entries(rows).await
+    }
+
+    /// The number of entries `entries_by_pos` pages through.
+    pub async fn count_by_pos(&self, pos: &PartOfSpeech) -> Result<i64> {
+        Ok(sqlx::query_scalar(&format!("SELECT COUNT(*) FROM entries e WHERE {}", HAS_POS_SQL))
+            .bind(pos.description())
+            .bind(pos.code())
+            .fetch_one(&self.pool)
+            .await?)
+    }
+}
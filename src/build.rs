@@ -0,0 +1,226 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "raw-xml")]
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use crate::content_hash::fnv1a_64;
+use crate::entities::parse_dtd_entities;
+use crate::parse::expand_entities;
+use crate::{DictDb, Entry, Result, SOURCE_JMDICT};
+
+// The ent_seq of an entry that may not parse
+static ENT_SEQ_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<ent_seq>\s*([^<]*?)\s*</ent_seq>").unwrap());
+
+/// An entry `DictDb::build_from_reader` left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedEntry {
+    /// The ent_seq as written in the XML, "?" if there is none.
+    pub ent_seq: String,
+    /// Why the entry was left out, e.g. the XML error.
+    pub reason: String,
+}
+
+/// How long one phase of the build, or of `DictDb::warmup`, took.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseDuration {
+    /// "read", "parse", "insert", "raw_xml" (see `BuildOptions::store_raw_xml`), or a phase
+    /// added by the caller; for warmup the table or index read.
+    pub phase: String,
+    pub millis: u64,
+}
+
+/// The outcome of `DictDb::build_from_reader`, written as JSON by `build_db --report`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildReport {
+    /// Entries read from the XML without errors.
+    pub entries_parsed: usize,
+    /// Entries written to the database, without the ones stored unchanged already.
+    pub entries_inserted: usize,
+    /// Entity references such as `&v1;` replaced by their values.
+    pub entities_replaced: usize,
+    /// Entries that could not be parsed or stored, in document order.
+    pub skipped: Vec<SkippedEntry>,
+    /// The phases of the build in the order they ran.
+    pub durations: Vec<PhaseDuration>,
+    /// The 64-bit FNV-1a hash of the input as 16 hex digits, to tell which file was built.
+    pub input_hash: String,
+    /// The size of the database in bytes after the build, see `DictDb::database_size`.
+    pub output_size: u64,
+}
+
+impl BuildReport {
+    /// Records how long a phase took.
+    pub fn push_duration(&mut self, phase: &str, duration: Duration) {
+        self.durations.push(PhaseDuration { phase: phase.to_string(), millis: duration.as_millis() as u64 });
+    }
+
+    /// Returns true if no entry was skipped.
+    pub fn is_clean(&self) -> bool {
+        self.skipped.is_empty()
+    }
+
+    /// The exit code of `build_db` for this build: 0 if it is clean, 2 if entries were
+    /// skipped. Fatal errors exit with 1 before there is a report.
+    pub fn exit_code(&self) -> i32 {
+        if self.is_clean() {
+            0
+        } else {
+            2
+        }
+    }
+}
+
+/// Skips an entry, taking its ent_seq from the XML.
+fn skip(xml: &str, reason: impl ToString) -> SkippedEntry {
+    let ent_seq = ENT_SEQ_RE.captures(xml).map_or_else(|| "?".to_string(), |cap| cap[1].to_string());
+    SkippedEntry { ent_seq, reason: reason.to_string() }
+}
+
+/// Splits a document into its `<entry>` elements, Err for an element without its closing
+/// tag (up to the next `<entry>`).
+pub(crate) fn entry_elements(xml: &str) -> Vec<std::result::Result<&str, &str>> {
+    const OPEN: &str = "<entry>";
+    const CLOSE: &str = "</entry>";
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(OPEN) {
+        let element = &rest[start..];
+        let next = element[OPEN.len()..].find(OPEN).map(|i| i + OPEN.len());
+        let end = match element.find(CLOSE) {
+            Some(end) if next.is_none_or(|next| end < next) => {
+                let end = end + CLOSE.len();
+                elements.push(Ok(&element[..end]));
+                end
+            }
+            _ => {
+                let end = next.unwrap_or(element.len());
+                elements.push(Err(&element[..end]));
+                end
+            }
+        };
+        rest = &element[end..];
+    }
+    elements
+}
+
+/// The ent_seq of an `<entry>` element, None if it has none or it isn't a number.
+#[cfg(feature = "raw-xml")]
+pub(crate) fn element_ent_seq(element: &str) -> Option<i64> {
+    ENT_SEQ_RE.captures(element)?[1].parse().ok()
+}
+
+/// Parses each `<entry>` element of the expanded document on its own, so that one
+/// malformed entry only loses itself.
+fn parse_entries(xml: &str, skipped: &mut Vec<SkippedEntry>) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    for element in entry_elements(xml) {
+        match element {
+            Ok(element) => match quick_xml::de::from_str::<Entry>(element) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => skipped.push(skip(element, err)),
+            },
+            Err(element) => skipped.push(skip(element, "entry without its closing tag")),
+        }
+    }
+    entries
+}
+
+/// Settings for `DictDb::build_from_reader_with`; `Default` is what `build_from_reader` uses.
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Keep the `<entry>` element of each stored entry as the XML has it, before the
+    /// entities are replaced, see `DictDb::raw_xml`. Off by default, it adds the
+    /// compressed XML to the file.
+    #[cfg(feature = "raw-xml")]
+    pub store_raw_xml: bool,
+}
+
+impl DictDb {
+    /// Builds the database from a complete JMdict XML document, like `build_db` does.
+    ///
+    /// Entries that cannot be parsed or stored (e.g. a non-numeric ent_seq) are skipped and
+    /// listed in the report rather than failing the build; I/O and database errors are
+    /// still errors. The entries are stored in one transaction.
+    ///
+    /// The build runs under the `BuildLock` of the database file, unless the DictDb holds
+    /// it already (see `hold_build_lock`), so a second build of the same file fails with
+    /// `DictError::BuildInProgress` instead of interleaving its writes.
+    pub async fn build_from_reader<R: BufRead>(&self, reader: R) -> Result<BuildReport> {
+        self.build_from_reader_with(reader, &BuildOptions::default()).await
+    }
+
+    /// `build_from_reader` with settings, see `BuildOptions`.
+    pub async fn build_from_reader_with<R: BufRead>(&self, mut reader: R, opts: &BuildOptions) -> Result<BuildReport> {
+        let _lock = self.lock_for_build()?;
+        let mut report = BuildReport::default();
+
+        let started = Instant::now();
+        let mut xml = String::new();
+        reader.read_to_string(&mut xml)?;
+        report.input_hash = format!("{:016x}", fnv1a_64(xml.as_bytes()));
+        report.push_duration("read", started.elapsed());
+        // Taken before the entities are replaced, as the file has them
+        #[cfg(feature = "raw-xml")]
+        let raw = opts.store_raw_xml.then(|| entry_elements(&xml).into_iter().flatten().map(str::to_string).collect::<Vec<_>>());
+        #[cfg(not(feature = "raw-xml"))]
+        let _ = opts;
+
+        let started = Instant::now();
+        let (xml, replaced) = expand_entities(&xml, &parse_dtd_entities(&xml));
+        report.entities_replaced = replaced;
+        let entries = parse_entries(&xml, &mut report.skipped);
+        report.entries_parsed = entries.len();
+        report.push_duration("parse", started.elapsed());
+
+        let started = Instant::now();
+        let entries = &entries;
+        let (inserted, rejected) = self
+            .write_entries(move || async move {
+                let mut tx = self.pool.begin().await?;
+                let mut inserted = 0;
+                let mut rejected = Vec::new();
+                for e in entries {
+                    match Self::upsert_entry_tx(&mut tx, e, SOURCE_JMDICT).await {
+                        Ok(written) => inserted += usize::from(written),
+                        // Checked before anything is written for the entry
+                        Err(err) if err.downcast_ref::<sqlx::Error>().is_none() => {
+                            rejected.push(SkippedEntry { ent_seq: e.ent_seq.clone(), reason: err.to_string() })
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+                tx.commit().await?;
+                Ok((inserted, rejected))
+            })
+            .await?;
+        report.entries_inserted = inserted;
+        report.skipped.extend(rejected);
+        report.push_duration("insert", started.elapsed());
+
+        // The fragments of an earlier build would no longer match the entries
+        #[cfg(feature = "raw-xml")]
+        let raw_stored = raw.is_some();
+        #[cfg(not(feature = "raw-xml"))]
+        let raw_stored = false;
+        self.set_raw_xml_available(raw_stored).await?;
+        #[cfg(feature = "raw-xml")]
+        if let Some(raw) = raw {
+            let started = Instant::now();
+            let rejected: HashSet<&str> = report.skipped.iter().map(|s| s.ent_seq.as_str()).collect();
+            let stored: HashSet<i64> =
+                entries.iter().filter(|e| !rejected.contains(e.ent_seq.as_str())).filter_map(|e| e.ent_seq.parse().ok()).collect();
+            let fragments = raw
+                .iter()
+                .filter_map(|element| Some((element_ent_seq(element).filter(|seq| stored.contains(seq))?, element.as_str())))
+                .collect::<Vec<_>>();
+            self.store_raw_xml(&fragments).await?;
+            report.push_duration("raw_xml", started.elapsed());
+        }
+
+        report.output_size = self.database_size().await?;
+        Ok(report)
+    }
+}
@@ -0,0 +1,97 @@
+//! Keeping two builds of the same database from running at once, see `BuildLock`.
+
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{DictDb, DictError, Result};
+
+/// Appended to the database path for the lock file of `BuildLock`.
+pub const BUILD_LOCK_SUFFIX: &str = ".build-lock";
+
+/// An advisory lock on building a database file, held while the value lives.
+///
+/// The lock is a file next to the database, created only if it doesn't exist yet, with the
+/// pid and start time of the build in it. Dropping the lock removes the file, also when
+/// the build fails or panics. A build killed outright leaves the file behind: remove it by
+/// hand once no build is running.
+#[derive(Debug)]
+pub struct BuildLock {
+    db_path: PathBuf,
+    lock_path: PathBuf,
+}
+
+/// The lock file of the database at `db_path`.
+pub fn build_lock_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(BUILD_LOCK_SUFFIX);
+    PathBuf::from(name)
+}
+
+impl BuildLock {
+    /// Takes the lock of the database at `db_path`, which need not exist yet.
+    ///
+    /// # Errors
+    /// `DictError::BuildInProgress` with the pid and start of the other build if the lock
+    /// is taken, I/O errors if the lock file can't be written.
+    pub fn acquire(db_path: impl AsRef<Path>) -> Result<BuildLock> {
+        let db_path = db_path.as_ref().to_path_buf();
+        let lock_path = build_lock_path(&db_path);
+        let started_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        match OpenOptions::new().write(true).create_new(true).open(&lock_path) {
+            Ok(mut file) => {
+                let lock = BuildLock { db_path, lock_path };
+                // Dropped on error, which removes the file again
+                writeln!(file, "pid={}\nstarted_at={}", std::process::id(), started_at)?;
+                Ok(lock)
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                // Unreadable while the other build is still writing it
+                let held = std::fs::read_to_string(&lock_path).unwrap_or_default();
+                let field = |key: &str| {
+                    held.lines().find_map(|l| l.strip_prefix(key)?.strip_prefix('=')?.trim().parse().ok()).unwrap_or(0)
+                };
+                Err(DictError::BuildInProgress { pid: field("pid") as u32, started_at: field("started_at"), lock: lock_path }.into())
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// The database the lock is for.
+    pub fn db_path(&self) -> &Path {
+        &self.db_path
+    }
+
+    /// The lock file.
+    pub fn lock_path(&self) -> &Path {
+        &self.lock_path
+    }
+}
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.lock_path);
+    }
+}
+
+impl DictDb {
+    /// Keeps `lock` until the DictDb is dropped, for a build that takes the lock before
+    /// connecting, e.g. to reset the file first as `build_db` does; `build_from_reader`
+    /// then runs under it instead of taking the lock itself.
+    pub fn hold_build_lock(&mut self, lock: BuildLock) {
+        self.build_lock = Some(lock);
+    }
+
+    /// The lock `build_from_reader` runs under: the held one if it is for this database,
+    /// else a new one; None for in-memory databases.
+    pub(crate) fn lock_for_build(&self) -> Result<Option<BuildLock>> {
+        let Some(path) = self.generation.db_path() else {
+            return Ok(None);
+        };
+        if self.build_lock.as_ref().is_some_and(|held| held.db_path() == path) {
+            return Ok(None);
+        }
+        BuildLock::acquire(path).map(Some)
+    }
+}
@@ -0,0 +1,184 @@
+use futures_util::future::BoxFuture;
+use futures_util::stream::{self, Stream};
+use sqlx::pool::PoolConnection;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::kana::{normalize_kana, READING_NORMALIZATION};
+use crate::{extract_kanji, DictDb, Result};
+
+/// Terms inserted per statement, three parameters each.
+const BULK_INSERT_TERMS: usize = 500;
+
+/// Terms looked up per query by `bulk_lookup`.
+const BULK_BATCH_TERMS: i64 = 500;
+
+/// Connections that may still have a bulk_terms table, from `bulk_lookup` streams dropped
+/// early. `release_connection` drops the table when they go back to the pool.
+static LEFTOVER_TABLES: AtomicUsize = AtomicUsize::new(0);
+
+/// The temporary table of one `bulk_lookup` call and the connection it lives on.
+struct TermTable {
+    conn: PoolConnection<Sqlite>,
+    dropped: bool,
+}
+
+impl Drop for TermTable {
+    fn drop(&mut self) {
+        if !self.dropped {
+            LEFTOVER_TABLES.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl TermTable {
+    /// Creates the table on a connection of its own and fills it with the terms, a few
+    /// hundred at a time. Terms are stored with what the indexes are searched by: the
+    /// normalized reading for kana, the first kanji otherwise.
+    async fn create(db: &DictDb, terms: impl Iterator<Item = String>) -> Result<Self> {
+        let mut conn = db.pool.acquire().await?;
+        sqlx::query("DROP TABLE IF EXISTS temp.bulk_terms").execute(&mut *conn).await?;
+        sqlx::query("CREATE TEMP TABLE bulk_terms (term TEXT PRIMARY KEY, norm TEXT NULL, ch TEXT NULL) WITHOUT ROWID")
+            .execute(&mut *conn)
+            .await?;
+        let mut table = Self { conn, dropped: false };
+        let mut terms = terms.filter(|t| !t.is_empty());
+        loop {
+            let chunk: Vec<String> = terms.by_ref().take(BULK_INSERT_TERMS).collect();
+            if chunk.is_empty() {
+                return Ok(table);
+            }
+            let mut qb = QueryBuilder::<Sqlite>::new("INSERT OR IGNORE INTO temp.bulk_terms (term, norm, ch) ");
+            qb.push_values(&chunk, |mut row, term| {
+                let ch = extract_kanji(term).first().map(char::to_string);
+                let norm = ch.is_none().then(|| normalize_kana(term, READING_NORMALIZATION));
+                row.push_bind(term).push_bind(norm).push_bind(ch);
+            });
+            qb.build().execute(&mut *table.conn).await?;
+        }
+    }
+
+    /// The matches of the next BULK_BATCH_TERMS terms after `after`, and the last of them.
+    /// None once all terms were looked up.
+    async fn batch(&mut self, after: &str) -> Result<Option<(String, Vec<(String, i64)>)>> {
+        let last: Option<String> = sqlx::query_scalar(
+            "SELECT MAX(term) FROM (SELECT term FROM temp.bulk_terms WHERE term > ?1 ORDER BY term LIMIT ?2)",
+        )
+        .bind(after)
+        .bind(BULK_BATCH_TERMS)
+        .fetch_one(&mut *self.conn)
+        .await?;
+        let Some(last) = last else {
+            return Ok(None);
+        };
+        // The indexes narrow down the candidates, the forms themselves decide
+        let pairs = sqlx::query_as(
+            r#"
+            SELECT t.term, e.ent_seq FROM temp.bulk_terms t
+            JOIN reading_index r ON r.norm = t.norm
+            JOIN entries e ON e.ent_seq = r.ent_seq
+            WHERE t.term > ?1 AND t.term <= ?2
+              AND EXISTS (SELECT 1 FROM json_each(e.rebs) je WHERE je.value = t.term)
+            UNION
+            SELECT t.term, e.ent_seq FROM temp.bulk_terms t
+            JOIN kanji_index k ON k.ch = t.ch
+            JOIN entries e ON e.ent_seq = k.ent_seq
+            WHERE t.term > ?1 AND t.term <= ?2
+              AND EXISTS (SELECT 1 FROM json_each(e.kebs) je WHERE je.value = t.term)
+            ORDER BY 1, 2
+            "#,
+        )
+        .bind(after)
+        .bind(&last)
+        .fetch_all(&mut *self.conn)
+        .await?;
+        Ok(Some((last, pairs)))
+    }
+
+    /// Drops the table, the connection goes back to the pool with `self`.
+    async fn drop_table(mut self) -> Result<()> {
+        sqlx::query("DROP TABLE temp.bulk_terms").execute(&mut *self.conn).await?;
+        self.dropped = true;
+        Ok(())
+    }
+}
+
+/// Where `bulk_lookup` is: the terms still to be stored, or the table and the last term
+/// looked up.
+struct BulkCursor<I> {
+    terms: Option<I>,
+    table: Option<TermTable>,
+    buffered: VecDeque<(String, i64)>,
+    after: String,
+}
+
+/// The pool's `after_release` hook: drops the bulk_terms table a dropped `bulk_lookup`
+/// stream left on the connection.
+pub(crate) fn release_connection(conn: &mut SqliteConnection) -> BoxFuture<'_, std::result::Result<bool, sqlx::Error>> {
+    Box::pin(async move {
+        if LEFTOVER_TABLES.load(Ordering::Relaxed) == 0 {
+            return Ok(true);
+        }
+        let leftover: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM temp.sqlite_master WHERE name = 'bulk_terms')")
+            .fetch_one(&mut *conn)
+            .await?;
+        if leftover {
+            LEFTOVER_TABLES.fetch_sub(1, Ordering::Relaxed);
+            sqlx::query("DROP TABLE temp.bulk_terms").execute(&mut *conn).await?;
+        }
+        Ok(true)
+    })
+}
+
+impl DictDb {
+    /// Looks up many terms at once, e.g. every distinct surface form of a book, returning the
+    /// `(term, ent_seq)` of each entry with a keb or reb equal to a term, ordered by term and
+    /// ent_seq. Terms without entries are left out; hydrate the matches with `entry_by_seq`
+    /// as needed.
+    ///
+    /// The terms go to a temporary table on the first poll, then are looked up a few hundred
+    /// at a time through the kanji and reading indexes, so memory use stays the same whatever
+    /// the number of terms. The stream holds a connection of the pool until it ends or is
+    /// dropped, and the table is removed either way.
+    ///
+    /// Kebs without a kanji, e.g. ＣＤ, are only found as readings.
+    pub fn bulk_lookup<'a, I>(&'a self, terms: I) -> impl Stream<Item = Result<(String, i64)>> + Send + 'a
+    where
+        I: Iterator<Item = String> + Send + 'a,
+    {
+        let cursor = BulkCursor { terms: Some(terms), table: None, buffered: VecDeque::new(), after: String::new() };
+        stream::unfold(cursor, move |mut cursor| async move {
+            loop {
+                if let Some(pair) = cursor.buffered.pop_front() {
+                    return Some((Ok(pair), cursor));
+                }
+                if let Some(terms) = cursor.terms.take() {
+                    match TermTable::create(self, terms).await {
+                        Ok(table) => cursor.table = Some(table),
+                        Err(err) => return Some((Err(err), cursor)),
+                    }
+                }
+                let table = cursor.table.as_mut()?;
+                match table.batch(&cursor.after).await {
+                    Ok(Some((last, pairs))) => {
+                        cursor.after = last;
+                        cursor.buffered = pairs.into();
+                    }
+                    Ok(None) => {
+                        let table = cursor.table.take()?;
+                        return match table.drop_table().await {
+                            Ok(()) => None,
+                            Err(err) => Some((Err(err), cursor)),
+                        };
+                    }
+                    Err(err) => {
+                        // Ends the stream, the table goes with the connection
+                        cursor.table = None;
+                        return Some((Err(err), cursor));
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,228 @@
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::{cache_dir, DictDb, Result};
+
+/// The zstd-compressed database embedded by build.rs, empty if none was found at build time.
+const BUNDLE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/bundle.db.zst"));
+
+/// The hex SHA-256 of the decompressed database.
+const BUNDLE_SHA256: &str = include_str!(concat!(env!("OUT_DIR"), "/bundle.sha256"));
+
+/// Returns the hex SHA-256 of a file.
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut BufReader::new(File::open(path)?), &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Extracts the bundled database into the cache directory unless an intact copy is already there.
+fn extract_bundle() -> Result<PathBuf> {
+    if BUNDLE.is_empty() {
+        return Err("jmdictdb was built without a bundled database: put a zstd-compressed database at \
+                    data/jmdict_e.db.zst (or set JMDICTDB_BUNDLE) and rebuild"
+            .into());
+    }
+    let dir = cache_dir().ok_or("can't determine the cache directory")?;
+    extract(BUNDLE, BUNDLE_SHA256, &dir)
+}
+
+/// The size and modification time of a file, which change when it is truncated or written.
+fn file_stamp(path: &Path) -> io::Result<String> {
+    let meta = fs::metadata(path)?;
+    let modified = meta.modified()?.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+    Ok(format!("{} {}", meta.len(), modified))
+}
+
+/// The file that records a verified copy: its checksum and `file_stamp` when it was checked.
+fn marker_path(path: &Path) -> PathBuf {
+    path.with_extension("db.verified")
+}
+
+/// Whether the copy at `path` is the database with this checksum. Checked against the
+/// marker first, so only a copy that changed since it was verified is hashed again.
+fn is_intact(path: &Path, sha256: &str) -> Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let marker = format!("{} {}", sha256, file_stamp(path)?);
+    if fs::read_to_string(marker_path(path)).is_ok_and(|found| found == marker) {
+        return Ok(true);
+    }
+    if sha256_file(path)? != sha256 {
+        return Ok(false);
+    }
+    fs::write(marker_path(path), marker)?;
+    Ok(true)
+}
+
+/// Extracts `bundle`, a zstd-compressed database, into `dir` unless an intact copy of it is
+/// already there, and returns the path of the copy.
+fn extract(bundle: &[u8], sha256: &str, dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+
+    // Named after the checksum, so a new bundle never reuses an old copy
+    let path = dir.join(format!("bundled-{}.db", &sha256[..16]));
+    if is_intact(&path, sha256)? {
+        return Ok(path);
+    }
+
+    // A missing, truncated or corrupted copy is replaced; extract next to it, then rename
+    for stale in [path.with_extension("db-wal"), path.with_extension("db-shm"), marker_path(&path)] {
+        if stale.exists() {
+            fs::remove_file(stale)?;
+        }
+    }
+    let part = path.with_extension("db.part");
+    zstd::stream::copy_decode(bundle, File::create(&part)?)?;
+    if sha256_file(&part)? != sha256 {
+        fs::remove_file(&part)?;
+        return Err("the bundled database does not match its checksum".into());
+    }
+    fs::rename(&part, &path)?;
+    fs::write(marker_path(&path), format!("{} {}", sha256, file_stamp(&path)?))?;
+    Ok(path)
+}
+
+/// Opens an extracted copy, read-only so that it stays intact.
+async fn connect_copy(path: &Path) -> Result<DictDb> {
+    DictDb::connect(&format!("sqlite:{}?mode=ro", path.display())).await
+}
+
+impl DictDb {
+    /// Connects to the prebuilt database embedded with the `bundled-db` feature.
+    ///
+    /// On first use the database is decompressed into `cache_dir()` and checked against the
+    /// embedded checksum. Later calls reuse the copy as long as its size and modification
+    /// time are the ones recorded then, and hash it again otherwise, extracting it anew if
+    /// it is damaged. The database is opened read-only so the copy stays intact; attach a
+    /// separate database for custom entries (see `attach`).
+    pub async fn connect_bundled() -> Result<Self> {
+        let path = crate::rt::spawn_blocking(extract_bundle).await?;
+        connect_copy(&path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    use crate::{Entry, KEle, REle, Sense};
+
+    /// A fresh directory for one test to extract into.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jmdictdb-bundled-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// A dictionary of 食べる built into a file and compressed, with its checksum.
+    async fn sample_bundle(dir: &Path) -> (Vec<u8>, String) {
+        fs::create_dir_all(dir).unwrap();
+        let db_path = dir.join("source.db");
+        let db = DictDb::connect(&format!("sqlite:{}?mode=rwc", db_path.display())).await.unwrap();
+        let taberu = Entry {
+            ent_seq: "1358280".into(),
+            k_ele: Some(vec![KEle { keb: "食べる".into(), ..Default::default() }]),
+            r_ele: vec![REle { reb: "たべる".into(), ..Default::default() }],
+            sense: vec![Sense { gloss: vec!["to eat".into()], ..Default::default() }],
+        };
+        db.insert_entries(&[taberu]).await.unwrap();
+        db.close().await;
+        let bundle = zstd::stream::encode_all(File::open(&db_path).unwrap(), 3).unwrap();
+        (bundle, sha256_file(&db_path).unwrap())
+    }
+
+    fn set_modified(path: &Path, secs_ago: u64) {
+        let time = SystemTime::now() - Duration::from_secs(secs_ago);
+        File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    #[tokio::test]
+    async fn extracts_once_and_opens_read_only() {
+        let dir = test_dir("first");
+        let (bundle, sha256) = sample_bundle(&dir).await;
+        let cache = dir.join("cache");
+        let path = extract(&bundle, &sha256, &cache).unwrap();
+        assert_eq!(path, cache.join(format!("bundled-{}.db", &sha256[..16])));
+        assert_eq!(sha256_file(&path).unwrap(), sha256);
+        assert!(!path.with_extension("db.part").exists());
+
+        let db = connect_copy(&path).await.unwrap();
+        let found = db.search_entries_with_senses_by_reading("たべる").await.unwrap();
+        assert_eq!(found[0].ent_seq, 1358280);
+        db.close().await;
+        // Reading it left it as it was
+        assert_eq!(sha256_file(&path).unwrap(), sha256);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn an_intact_copy_is_reused_without_hashing() {
+        let dir = test_dir("reuse");
+        let (bundle, sha256) = sample_bundle(&dir).await;
+        let path = extract(&bundle, &sha256, &dir).unwrap();
+        let stamp = file_stamp(&path).unwrap();
+        assert_eq!(extract(&bundle, &sha256, &dir).unwrap(), path);
+        assert_eq!(file_stamp(&path).unwrap(), stamp);
+
+        // Trusted on its marker without hashing it: a change that keeps the size and the
+        // modification time goes unnoticed
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let data = fs::read(&path).unwrap();
+        let mut changed = data.clone();
+        changed[100] ^= 0xff;
+        fs::write(&path, &changed).unwrap();
+        File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        assert!(is_intact(&path, &sha256).unwrap());
+        fs::write(&path, &data).unwrap();
+        File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+
+        // Without the marker it is hashed, found intact and marked again
+        let marker = marker_path(&path);
+        fs::remove_file(&marker).unwrap();
+        assert_eq!(extract(&bundle, &sha256, &dir).unwrap(), path);
+        assert_eq!(file_stamp(&path).unwrap(), stamp);
+        assert_eq!(fs::read_to_string(&marker).unwrap(), format!("{} {}", sha256, stamp));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_damaged_copy_is_extracted_again() {
+        let dir = test_dir("damaged");
+        let (bundle, sha256) = sample_bundle(&dir).await;
+        let path = extract(&bundle, &sha256, &dir).unwrap();
+
+        // Truncated
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() / 2]).unwrap();
+        assert!(!is_intact(&path, &sha256).unwrap());
+        extract(&bundle, &sha256, &dir).unwrap();
+        assert_eq!(sha256_file(&path).unwrap(), sha256);
+
+        // Corrupted in place, same size, an older modification time
+        let mut corrupted = data.clone();
+        corrupted[100] ^= 0xff;
+        fs::write(&path, &corrupted).unwrap();
+        set_modified(&path, 3600);
+        extract(&bundle, &sha256, &dir).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), data);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_bundle_that_does_not_match_its_checksum_is_an_error() {
+        let dir = test_dir("mismatch");
+        let (bundle, _) = sample_bundle(&dir).await;
+        let wrong = "0".repeat(64);
+        let err = extract(&bundle, &wrong, &dir).unwrap_err();
+        assert_eq!(err.to_string(), "the bundled database does not match its checksum");
+        let name = format!("bundled-{}", &wrong[..16]);
+        assert!(fs::read_dir(&dir).unwrap().all(|e| !e.unwrap().file_name().to_string_lossy().starts_with(&name)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
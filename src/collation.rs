@@ -0,0 +1,80 @@
+//! Orders for English glosses, the same on every platform: the exports and reverse lookups
+//! sort with these in Rust instead of relying on the collations of the SQLite build.
+
+use std::cmp::Ordering;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::fold_case;
+
+/// How glosses are ordered, see `FlatRowOptions::collation`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Collation {
+    /// By the bytes of the UTF-8, SQLite's BINARY: "Zebra" before "apple".
+    #[default]
+    Binary,
+    /// ASCII letters compared without case, like SQLite's NOCASE; other characters by
+    /// their bytes, so "Éclair" still comes after "zebra".
+    CaseInsensitive,
+    /// Without case (see `fold_case`) or accents, and from the first letter or digit, so a
+    /// leading note like "(of a person)" or a quote doesn't decide where a gloss goes:
+    /// "apple", "Éclair", "(of a person) tall", "Zebra".
+    UnicodeCasefold,
+}
+
+impl Collation {
+    /// Parses "binary", "nocase" or "casefold".
+    pub fn parse(s: &str) -> Option<Collation> {
+        match s.to_ascii_lowercase().as_str() {
+            "binary" => Some(Collation::Binary),
+            "nocase" => Some(Collation::CaseInsensitive),
+            "casefold" => Some(Collation::UnicodeCasefold),
+            _ => None,
+        }
+    }
+
+    /// The string the collation compares in place of `s`.
+    ///
+    /// For `UnicodeCasefold` that is `s` without its leading parenthesized notes and
+    /// punctuation, decomposed with the combining marks left out, then case-folded. A
+    /// gloss that is all note is kept whole.
+    pub fn sort_key(self, s: &str) -> String {
+        match self {
+            Collation::Binary => s.to_string(),
+            Collation::CaseInsensitive => s.to_ascii_lowercase(),
+            Collation::UnicodeCasefold => {
+                let start = strip_leading_notes(s);
+                let start = if start.is_empty() { s } else { start };
+                fold_case(&start.nfd().filter(|c| !is_combining_mark(*c)).collect::<String>())
+            }
+        }
+    }
+
+    /// Compares by `sort_key`, then by the bytes so that the order is total: "Eclair",
+    /// "eclair" and "éclair" are equal under `UnicodeCasefold` but always in this order.
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        self.sort_key(a).cmp(&self.sort_key(b)).then_with(|| a.cmp(b))
+    }
+
+    /// Sorts `items` by the string `key` gives for each, stable for equal strings, e.g. the
+    /// results of `DictDb::search_gloss_matches` by their `matched_gloss`.
+    pub fn sort_by<T>(self, items: &mut [T], key: impl Fn(&T) -> &str) {
+        items.sort_by_cached_key(|item| {
+            let s = key(item);
+            (self.sort_key(s), s.to_string())
+        });
+    }
+}
+
+/// `s` from its first letter or digit outside of parentheses at the start.
+fn strip_leading_notes(s: &str) -> &str {
+    let mut rest = s;
+    loop {
+        rest = rest.trim_start_matches(|c: char| !c.is_alphanumeric() && c != '(');
+        let Some(inner) = rest.strip_prefix('(') else {
+            return rest;
+        };
+        // An unclosed parenthesis is only punctuation
+        rest = inner.split_once(')').map_or(inner, |(_, after)| after);
+    }
+}
@@ -0,0 +1,93 @@
+//! How hard an entry is to learn, see `complexity_score`, for lists of simple words.
+
+use crate::models::SenseTags;
+use crate::{extract_kanji, DictDb, Entry, EntryParsed, Result, USUALLY_KANA, USUALLY_KANA_CODE};
+
+/// Points per sense: each one is another meaning to learn.
+pub const SENSE_POINTS: u32 = 4;
+/// Points per gloss, see `EntryParsed::total_gloss_count`.
+pub const GLOSS_POINTS: u32 = 2;
+/// Points per character of the headword.
+pub const CHAR_POINTS: u32 = 1;
+/// Points per kanji of the headword, on top of `CHAR_POINTS`.
+pub const KANJI_POINTS: u32 = 2;
+
+/// The complexity of an entry with this many senses and glosses and this headword, lower is
+/// simpler: the points of the consts above added up. 猫 (one sense, one gloss) scores 9,
+/// 食べる (two senses, four glosses) 21.
+///
+/// Stored with each entry for `SearchFilter::max_complexity` and `BrowseOrder::Complexity`,
+/// so new weights only apply to the entries stored after the change.
+pub fn complexity_score(senses: usize, glosses: usize, headword: &str) -> u32 {
+    let senses = senses as u32 * SENSE_POINTS;
+    let glosses = glosses as u32 * GLOSS_POINTS;
+    let chars = headword.chars().count() as u32 * CHAR_POINTS;
+    let kanji = extract_kanji(headword).len() as u32 * KANJI_POINTS;
+    senses + glosses + chars + kanji
+}
+
+impl EntryParsed {
+    /// The number of senses, as loaded: `QueryOptions::max_senses` may have cut them.
+    pub fn sense_count(&self) -> usize {
+        self.senses.len()
+    }
+
+    /// The number of glosses of all senses, in the language `SenseParsed::default_glosses`
+    /// shows.
+    pub fn total_gloss_count(&self) -> usize {
+        self.senses.iter().map(|s| s.default_glosses().len()).sum()
+    }
+
+    /// `complexity_score` of the senses, glosses and `display_form`.
+    pub fn complexity_score(&self) -> u32 {
+        complexity_score(self.sense_count(), self.total_gloss_count(), self.display_form())
+    }
+}
+
+/// `EntryParsed::complexity_score` of an entry as it is being stored, with the tags its
+/// senses get from `inherited_sense_tags`.
+pub(crate) fn entry_complexity(e: &Entry, tags: &[SenseTags<'_>]) -> u32 {
+    let glosses = e
+        .sense
+        .iter()
+        .map(|s| match s.other_glosses.first() {
+            Some(first) if s.gloss.is_empty() => s.other_glosses.iter().filter(|g| g.lang == first.lang).count(),
+            _ => s.gloss.len(),
+        })
+        .sum();
+    let usually_kana = tags.first().is_some_and(|t| t.misc.iter().any(|m| m == USUALLY_KANA || m == USUALLY_KANA_CODE));
+    let reading = e.r_ele.first().map_or("", |r| r.reb.as_str());
+    let headword = match e.k_ele.iter().flatten().next() {
+        Some(k) if !usually_kana => k.keb.as_str(),
+        _ => reading,
+    };
+    complexity_score(e.sense.len(), glosses, headword)
+}
+
+/// Entries hydrated at a time by `fill_complexity`.
+const FILL_CHUNK: i64 = 500;
+
+impl DictDb {
+    /// Computes the complexity of entries stored without it, by older versions, on connect.
+    pub(crate) async fn fill_complexity(&self) -> Result<()> {
+        loop {
+            let rows = sqlx::query_as("SELECT * FROM entries WHERE complexity IS NULL ORDER BY ent_seq LIMIT ?1")
+                .bind(FILL_CHUNK)
+                .fetch_all(&self.pool)
+                .await?;
+            if rows.is_empty() {
+                return Ok(());
+            }
+            let entries = self.hydrate_entries(rows).await?;
+            let mut tx = self.pool.begin().await?;
+            for entry in &entries {
+                sqlx::query("UPDATE entries SET complexity = ?1 WHERE ent_seq = ?2")
+                    .bind(entry.complexity_score() as i64)
+                    .bind(entry.ent_seq)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use sqlx::FromRow;
+
+use crate::kana::is_kana_str;
+use crate::ranking::priority_score;
+use crate::{DictDb, EntryParsed, Result};
+
+/// An entry with a form containing the word, before ranking.
+#[derive(FromRow)]
+struct CompoundCandidate {
+    ent_seq: i64,
+    priority: String,
+    nf_bucket: Option<i64>,
+    /// The length of the shortest form containing the word.
+    len: i64,
+}
+
+impl DictDb {
+    /// Entries with a keb strictly longer than `word` that contains it, e.g. 置き手紙 and
+    /// 手紙を書く for 手紙. If `word` is kana, the rebs are searched as well.
+    ///
+    /// Entries with a form identical to `word`, i.e. the word itself, are left out. The best
+    /// `limit` are returned, ranked by priority: `priority_score` first, then the nfNN bucket
+    /// (entries without one last), then shorter compounds before longer ones, then ent_seq.
+    /// Shorter words than the `QueryGuards` allow are `DictError::QueryTooShort`.
+    pub async fn compounds_containing(&self, word: &str, limit: usize) -> Result<Vec<EntryParsed>> {
+        self.observe("compounds_containing", word, || format!("limit {}", limit), async {
+            let word = self.substring_query(word)?;
+            if limit == 0 {
+                return Ok(Vec::new());
+            }
+            let readings = if is_kana_str(word) {
+                "UNION ALL SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.rebs) je"
+            } else {
+                ""
+            };
+            let sql = format!(
+                r#"
+                SELECT e.ent_seq, e.priority, e.nf_bucket, MIN(length(f.form)) AS len
+                FROM (
+                    SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.kebs) je
+                    WHERE e.kebs IS NOT NULL
+                    {}
+                ) f
+                JOIN entries e ON e.ent_seq = f.ent_seq
+                WHERE instr(f.form, ?1) > 0 AND length(f.form) > length(?1)
+                  AND NOT EXISTS (SELECT 1 FROM json_each(e.kebs) k WHERE k.value = ?1)
+                  AND NOT EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE r.value = ?1)
+                GROUP BY e.ent_seq
+                "#,
+                readings
+            );
+            let candidates = sqlx::query_as::<_, CompoundCandidate>(&sql).bind(word).fetch_all(&self.pool).await?;
+
+            let mut ranked = candidates
+                .into_iter()
+                .map(|c| Ok((priority_score(&serde_json::from_str::<Vec<String>>(&c.priority)?), c)))
+                .collect::<Result<Vec<_>>>()?;
+            ranked.sort_by(|(a_score, a), (b_score, b)| {
+                b_score
+                    .cmp(a_score)
+                    .then(a.nf_bucket.unwrap_or(i64::MAX).cmp(&b.nf_bucket.unwrap_or(i64::MAX)))
+                    .then(a.len.cmp(&b.len))
+                    .then(a.ent_seq.cmp(&b.ent_seq))
+            });
+            ranked.truncate(limit);
+
+            let mut entries = Vec::with_capacity(ranked.len());
+            for (_, candidate) in ranked {
+                if let Some(entry) = self.entry_by_seq(candidate.ent_seq).await? {
+                    entries.push(entry);
+                }
+            }
+            Ok(entries)
+        })
+        .await
+    }
+}
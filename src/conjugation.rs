@@ -0,0 +1,419 @@
+use crate::{EntryParsed, PartOfSpeech};
+
+/// A conjugated form that can be generated for a verb or adjective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConjugationForm {
+    /// Dictionary form, e.g. 食べる
+    NonPast,
+    /// e.g. 食べます
+    NonPastPolite,
+    /// e.g. 食べない
+    Negative,
+    /// e.g. 食べません
+    NegativePolite,
+    /// e.g. 食べた
+    Past,
+    /// e.g. 食べました
+    PastPolite,
+    /// e.g. 食べなかった
+    PastNegative,
+    /// e.g. 食べて
+    TeForm,
+    /// e.g. 食べられる
+    Potential,
+    /// e.g. 食べられる
+    Passive,
+    /// e.g. 食べさせる
+    Causative,
+    /// e.g. 食べよう
+    Volitional,
+    /// e.g. 食べろ
+    Imperative,
+}
+
+/// A single conjugated form, with the headword and reading conjugated in parallel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conjugated {
+    /// The conjugated headword (kanji form, or the reading for kana-only words).
+    pub kanji: String,
+    /// The conjugated reading.
+    pub kana: String,
+}
+
+/// All forms generated for one headword/reading pair.
+///
+/// Forms that do not apply to the word class (e.g. imperative for adjectives) are left out,
+/// and the table is empty when the part of speech does not conjugate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConjugationTable {
+    /// The part of speech the table was generated for.
+    pub pos: PartOfSpeech,
+    /// The generated forms, in `ConjugationForm` declaration order.
+    pub forms: Vec<(ConjugationForm, Conjugated)>,
+}
+
+impl ConjugationTable {
+    /// Returns the given form, if it applies to this word.
+    pub fn get(&self, form: ConjugationForm) -> Option<&Conjugated> {
+        self.forms.iter().find(|(f, _)| *f == form).map(|(_, c)| c)
+    }
+
+    /// Returns true if no forms were generated.
+    pub fn is_empty(&self) -> bool {
+        self.forms.is_empty()
+    }
+}
+
+/// The conjugation classes supported by `conjugate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Ichidan,
+    Kureru,
+    Zuru,
+    /// Godan verb, with the dictionary-form ending (う, く, ぐ, ...).
+    Godan(char),
+    /// 行く/いく
+    Iku,
+    /// ある/有る
+    Aru,
+    /// なさる, いらっしゃる, ...
+    Aru5,
+    /// 問う, 請う, ...
+    USpecial,
+    Kuru,
+    /// Noun taking する, the headword does not include it.
+    SuruNoun,
+    /// The headword ends in する.
+    Suru,
+    AdjI,
+    /// いい/よい
+    AdjIx,
+    AdjNa,
+}
+
+impl Class {
+    fn from_pos(pos: &PartOfSpeech) -> Option<Self> {
+        use PartOfSpeech::*;
+        Some(match pos {
+            V1 => Class::Ichidan,
+            V1S => Class::Kureru,
+            Vz => Class::Zuru,
+            V5u => Class::Godan('う'),
+            V5k => Class::Godan('く'),
+            V5g => Class::Godan('ぐ'),
+            V5s => Class::Godan('す'),
+            V5t => Class::Godan('つ'),
+            V5n => Class::Godan('ぬ'),
+            V5b => Class::Godan('ぶ'),
+            V5m => Class::Godan('む'),
+            V5r => Class::Godan('る'),
+            V5kS => Class::Iku,
+            V5rI => Class::Aru,
+            V5aru => Class::Aru5,
+            V5uS => Class::USpecial,
+            Vk => Class::Kuru,
+            Vs => Class::SuruNoun,
+            VsI | VsS => Class::Suru,
+            AdjI => Class::AdjI,
+            AdjIx => Class::AdjIx,
+            AdjNa => Class::AdjNa,
+            _ => return None,
+        })
+    }
+
+    fn forms(self) -> &'static [ConjugationForm] {
+        use ConjugationForm::*;
+        match self {
+            Class::AdjI | Class::AdjIx | Class::AdjNa => &[
+                NonPast, NonPastPolite, Negative, NegativePolite, Past, PastPolite, PastNegative, TeForm,
+            ],
+            _ => &[
+                NonPast, NonPastPolite, Negative, NegativePolite, Past, PastPolite, PastNegative, TeForm,
+                Potential, Passive, Causative, Volitional, Imperative,
+            ],
+        }
+    }
+}
+
+/// Returns true if `conjugate` can generate forms for this part of speech.
+pub fn is_conjugable(pos: &PartOfSpeech) -> bool {
+    Class::from_pos(pos).is_some()
+}
+
+/// Generates the conjugation table for a verb or adjective.
+///
+/// The headword and the reading are conjugated in parallel, so `headword` can be a kanji form
+/// (食べる) or the same kana string as `reading` for kana-only words.
+/// For `PartOfSpeech::Vs` the headword is the bare noun (勉強), for `VsI`/`VsS` it includes する.
+///
+/// # Arguments
+/// * `headword` - The dictionary form as written, e.g. "行く".
+/// * `reading` - The dictionary form in kana, e.g. "いく".
+/// * `pos` - The part of speech that decides the conjugation class.
+///
+/// # Returns
+/// A ConjugationTable, empty if the part of speech does not conjugate.
+pub fn conjugate(headword: &str, reading: &str, pos: PartOfSpeech) -> ConjugationTable {
+    let forms = match Class::from_pos(&pos) {
+        Some(class) => class
+            .forms()
+            .iter()
+            .map(|&form| {
+                let conjugated = Conjugated {
+                    kanji: conjugate_str(headword, class, form),
+                    kana: conjugate_str(reading, class, form),
+                };
+                (form, conjugated)
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    ConjugationTable { pos, forms }
+}
+
+/// Removes the last `n` characters of `s` and appends `suffix`.
+fn replace_tail(s: &str, n: usize, suffix: &str) -> String {
+    let keep = s.chars().count().saturating_sub(n);
+    let mut out: String = s.chars().take(keep).collect();
+    out.push_str(suffix);
+    out
+}
+
+/// Returns the (a, i, e, o) row kana for a godan dictionary-form ending.
+fn godan_row(ending: char) -> (char, char, char, char) {
+    match ending {
+        'う' => ('わ', 'い', 'え', 'お'),
+        'く' => ('か', 'き', 'け', 'こ'),
+        'ぐ' => ('が', 'ぎ', 'げ', 'ご'),
+        'す' => ('さ', 'し', 'せ', 'そ'),
+        'つ' => ('た', 'ち', 'て', 'と'),
+        'ぬ' => ('な', 'に', 'ね', 'の'),
+        'ぶ' => ('ば', 'び', 'べ', 'ぼ'),
+        'む' => ('ま', 'み', 'め', 'も'),
+        _ => ('ら', 'り', 'れ', 'ろ'),
+    }
+}
+
+/// Returns the te-form and past endings (て/た, で/だ) for a godan dictionary-form ending.
+fn godan_te_ta(ending: char) -> (&'static str, &'static str) {
+    match ending {
+        'く' => ("いて", "いた"),
+        'ぐ' => ("いで", "いだ"),
+        'す' => ("して", "した"),
+        'ぬ' | 'ぶ' | 'む' => ("んで", "んだ"),
+        _ => ("って", "った"),
+    }
+}
+
+fn ichidan_suffix(form: ConjugationForm) -> &'static str {
+    use ConjugationForm::*;
+    match form {
+        NonPast => "る",
+        NonPastPolite => "ます",
+        Negative => "ない",
+        NegativePolite => "ません",
+        Past => "た",
+        PastPolite => "ました",
+        PastNegative => "なかった",
+        TeForm => "て",
+        Potential | Passive => "られる",
+        Causative => "させる",
+        Volitional => "よう",
+        Imperative => "ろ",
+    }
+}
+
+fn suru_suffix(form: ConjugationForm) -> &'static str {
+    use ConjugationForm::*;
+    match form {
+        NonPast => "する",
+        NonPastPolite => "します",
+        Negative => "しない",
+        NegativePolite => "しません",
+        Past => "した",
+        PastPolite => "しました",
+        PastNegative => "しなかった",
+        TeForm => "して",
+        Potential => "できる",
+        Passive => "される",
+        Causative => "させる",
+        Volitional => "しよう",
+        Imperative => "しろ",
+    }
+}
+
+fn kuru_suffix(form: ConjugationForm) -> &'static str {
+    use ConjugationForm::*;
+    match form {
+        NonPast => "くる",
+        NonPastPolite => "きます",
+        Negative => "こない",
+        NegativePolite => "きません",
+        Past => "きた",
+        PastPolite => "きました",
+        PastNegative => "こなかった",
+        TeForm => "きて",
+        Potential | Passive => "こられる",
+        Causative => "こさせる",
+        Volitional => "こよう",
+        Imperative => "こい",
+    }
+}
+
+fn adj_i_suffix(form: ConjugationForm) -> &'static str {
+    use ConjugationForm::*;
+    match form {
+        NonPast => "い",
+        NonPastPolite => "いです",
+        Negative => "くない",
+        NegativePolite => "くありません",
+        Past => "かった",
+        PastPolite => "かったです",
+        PastNegative => "くなかった",
+        _ => "くて",
+    }
+}
+
+fn adj_na_suffix(form: ConjugationForm) -> &'static str {
+    use ConjugationForm::*;
+    match form {
+        NonPast => "だ",
+        NonPastPolite => "です",
+        Negative => "ではない",
+        NegativePolite => "ではありません",
+        Past => "だった",
+        PastPolite => "でした",
+        PastNegative => "ではなかった",
+        _ => "で",
+    }
+}
+
+fn godan_suffix(ending: char, form: ConjugationForm) -> String {
+    use ConjugationForm::*;
+    let (a, i, e, o) = godan_row(ending);
+    let (te, ta) = godan_te_ta(ending);
+    match form {
+        NonPast => ending.to_string(),
+        NonPastPolite => format!("{i}ます"),
+        Negative => format!("{a}ない"),
+        NegativePolite => format!("{i}ません"),
+        Past => ta.to_string(),
+        PastPolite => format!("{i}ました"),
+        PastNegative => format!("{a}なかった"),
+        TeForm => te.to_string(),
+        Potential => format!("{e}る"),
+        Passive => format!("{a}れる"),
+        Causative => format!("{a}せる"),
+        Volitional => format!("{o}う"),
+        Imperative => e.to_string(),
+    }
+}
+
+/// Conjugates one spelling (headword or reading) of a word.
+fn conjugate_str(s: &str, class: Class, form: ConjugationForm) -> String {
+    use ConjugationForm::*;
+    match class {
+        Class::Ichidan => replace_tail(s, 1, ichidan_suffix(form)),
+        Class::Kureru => match form {
+            Imperative => replace_tail(s, 1, ""),
+            _ => replace_tail(s, 1, ichidan_suffix(form)),
+        },
+        // 信ずる conjugates like the ichidan 信じる
+        Class::Zuru => match form {
+            NonPast => s.to_string(),
+            _ => replace_tail(s, 2, &format!("じ{}", ichidan_suffix(form))),
+        },
+        Class::Godan(ending) => replace_tail(s, 1, &godan_suffix(ending, form)),
+        Class::Iku => match form {
+            TeForm => replace_tail(s, 1, "って"),
+            Past => replace_tail(s, 1, "った"),
+            _ => replace_tail(s, 1, &godan_suffix('く', form)),
+        },
+        Class::Aru => match form {
+            Negative => replace_tail(s, 2, "ない"),
+            PastNegative => replace_tail(s, 2, "なかった"),
+            _ => replace_tail(s, 1, &godan_suffix('る', form)),
+        },
+        Class::Aru5 => match form {
+            NonPastPolite => replace_tail(s, 1, "います"),
+            NegativePolite => replace_tail(s, 1, "いません"),
+            PastPolite => replace_tail(s, 1, "いました"),
+            Imperative => replace_tail(s, 1, "い"),
+            _ => replace_tail(s, 1, &godan_suffix('る', form)),
+        },
+        Class::USpecial => match form {
+            TeForm => replace_tail(s, 0, "て"),
+            Past => replace_tail(s, 0, "た"),
+            _ => replace_tail(s, 1, &godan_suffix('う', form)),
+        },
+        Class::Kuru => {
+            let suffix = kuru_suffix(form);
+            if s.ends_with("くる") {
+                replace_tail(s, 2, suffix)
+            } else {
+                // 来る: the kanji stays, only the okurigana changes
+                let okurigana: String = suffix.chars().skip(1).collect();
+                replace_tail(s, 1, &okurigana)
+            }
+        }
+        Class::SuruNoun => replace_tail(s, 0, suru_suffix(form)),
+        Class::Suru => replace_tail(s, 2, suru_suffix(form)),
+        Class::AdjI => replace_tail(s, 1, adj_i_suffix(form)),
+        Class::AdjIx => {
+            // いい only survives in the non-past, everything else is built on よ
+            if s.ends_with("いい") && !matches!(form, NonPast | NonPastPolite) {
+                replace_tail(s, 2, &format!("よ{}", adj_i_suffix(form)))
+            } else {
+                replace_tail(s, 1, adj_i_suffix(form))
+            }
+        }
+        Class::AdjNa => replace_tail(s, 0, adj_na_suffix(form)),
+    }
+}
+
+impl EntryParsed {
+    /// Generates the conjugation table for the primary headword and reading,
+    /// using the first conjugable part of speech found in the senses.
+    ///
+    /// # Returns
+    /// None if no sense carries a verb or adjective part of speech.
+    pub fn conjugations(&self) -> Option<ConjugationTable> {
+        let reading = self.rebs.first()?;
+        let headword = self
+            .kebs
+            .as_ref()
+            .and_then(|k| k.first())
+            .unwrap_or(reading);
+        let pos = self
+            .senses
+            .iter()
+            .flat_map(|s| s.pos.iter())
+            .map(|p| PartOfSpeech::parse(p))
+            .find(is_conjugable)?;
+        Some(conjugate(headword, reading, pos))
+    }
+
+    /// The conjugated forms of every keb and reb, with the part of speech `conjugations`
+    /// uses, for looking up an inflected word: 食べない, たべない, 食べた, ... for 食べる.
+    /// Each form once, in table order, without the entry's own kebs and rebs; empty for
+    /// words that don't conjugate.
+    pub fn inflected_forms(&self) -> Vec<String> {
+        let Some(pos) = self.senses.iter().flat_map(|s| s.pos.iter()).map(|p| PartOfSpeech::parse(p)).find(is_conjugable) else {
+            return Vec::new();
+        };
+        let kebs = self.kebs.iter().flatten().map(|k| (k, self.reading_for(k).or(self.rebs.first().map(String::as_str))));
+        let rebs = self.rebs.iter().map(|r| (r, Some(r.as_str())));
+        let mut forms: Vec<String> = Vec::new();
+        for (form, reading) in kebs.chain(rebs) {
+            let table = conjugate(form, reading.unwrap_or(form), pos.clone());
+            for (_, c) in table.forms {
+                for s in [c.kanji, c.kana] {
+                    if !forms.contains(&s) && !self.rebs.contains(&s) && !self.kebs.iter().flatten().any(|k| *k == s) {
+                        forms.push(s);
+                    }
+                }
+            }
+        }
+        forms
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use crate::{DictDb, Entry, Result};
+
+/// Bumped whenever `canonical_entry` changes, which changes every hash.
+pub const CONTENT_HASH_VERSION: u32 = 1;
+
+/// Appends one `tag value` line, with `\`, line breaks and tabs in the value escaped.
+fn push_line(out: &mut String, tag: &str, value: &str) {
+    out.push_str(tag);
+    out.push(' ');
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('\n');
+}
+
+/// The canonical serialization of an entry that `content_hash` hashes.
+///
+/// One `tag value` line per value, in document order, so it only depends on the entry:
+///
+/// ```text
+/// v 1                   CONTENT_HASH_VERSION
+/// ent_seq 1358280
+/// keb 食べる            per k_ele, followed by
+/// ke_pri ichi1          its priority tags and
+/// ke_inf ...            its info tags
+/// reb たべる            per r_ele, followed by
+/// re_pri ichi1          its priority tags and
+/// re_restr 食べる       the kebs it is restricted to
+/// sense                 per sense, followed by its
+///                       stagk, stagr, pos, xref, misc and gloss lines,
+/// gloss.ger essen       then the other glosses, tagged with their language
+/// ```
+///
+/// Values are written as-is (no trimming or Unicode normalization), with `\` as `\\` and
+/// line breaks and tabs as `\n`, `\r` and `\t`. Every line ends with a single `\n`.
+pub fn canonical_entry(e: &Entry) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "v", &CONTENT_HASH_VERSION.to_string());
+    push_line(&mut out, "ent_seq", &e.ent_seq);
+    for k in e.k_ele.iter().flatten() {
+        push_line(&mut out, "keb", &k.keb);
+        for pri in &k.ke_pri {
+            push_line(&mut out, "ke_pri", pri);
+        }
+        for inf in k.ke_inf.iter().flatten() {
+            push_line(&mut out, "ke_inf", inf);
+        }
+    }
+    for r in &e.r_ele {
+        push_line(&mut out, "reb", &r.reb);
+        for pri in &r.re_pri {
+            push_line(&mut out, "re_pri", pri);
+        }
+        for restr in &r.re_restr {
+            push_line(&mut out, "re_restr", restr);
+        }
+    }
+    for s in &e.sense {
+        out.push_str("sense\n");
+        let fields = [
+            ("stagk", &s.stagk),
+            ("stagr", &s.stagr),
+            ("pos", &s.pos),
+            ("xref", &s.xref),
+            ("misc", &s.misc),
+            ("field", &s.field),
+            ("dial", &s.dial),
+            ("gloss", &s.gloss),
+        ];
+        for (tag, values) in fields {
+            for value in values {
+                push_line(&mut out, tag, value);
+            }
+        }
+        for g in &s.other_glosses {
+            push_line(&mut out, &format!("gloss.{}", g.lang), &g.text);
+        }
+    }
+    out
+}
+
+/// 64-bit FNV-1a, simple enough to stay the same on every platform and crate version.
+pub(crate) fn fnv1a_64(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// The content hash of an entry, 16 lowercase hex digits of the FNV-1a hash of its
+/// `canonical_entry`. Stored on insert and returned as `EntryParsed::content_hash`.
+pub fn content_hash(e: &Entry) -> String {
+    format!("{:016x}", fnv1a_64(canonical_entry(e).as_bytes()))
+}
+
+/// The content hash of every entry, None for entries stored before hashes were.
+async fn hashes(db: &DictDb) -> Result<HashMap<i64, Option<String>>> {
+    let rows: Vec<(i64, Option<String>)> =
+        sqlx::query_as("SELECT ent_seq, content_hash FROM entries").fetch_all(&db.pool).await?;
+    Ok(rows.into_iter().collect())
+}
+
+impl DictDb {
+    /// The entries that differ between `other`, e.g. the previous dictionary version, and
+    /// this database, by ent_seq: added, removed, or with another content hash.
+    ///
+    /// Entries without a hash (stored by an older version of the crate) count as changed.
+    pub async fn changed_since(&self, other: &DictDb) -> Result<Vec<i64>> {
+        let ours = hashes(self).await?;
+        let theirs = hashes(other).await?;
+        let mut changed: Vec<i64> = ours
+            .iter()
+            .filter(|(ent_seq, hash)| hash.is_none() || theirs.get(ent_seq) != Some(hash))
+            .map(|(ent_seq, _)| *ent_seq)
+            .chain(theirs.keys().filter(|ent_seq| !ours.contains_key(ent_seq)).copied())
+            .collect();
+        changed.sort();
+        Ok(changed)
+    }
+}
@@ -0,0 +1,177 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::guards::non_empty;
+use crate::kana::{hiragana_to_katakana, is_kana_str, katakana_to_hiragana};
+use crate::romaji::romaji_to_kana;
+use crate::{DictDb, Result};
+
+/// How `DictDb::suggest_corrections` came up with a suggestion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionStrategy {
+    /// The query in the other kana script, タベル for たべる and the other way round.
+    KanaConversion,
+    /// The query read as romaji, たべる for "taberu".
+    Romaji,
+    /// A reading one insertion, deletion or substitution away, たべる for たべう.
+    EditDistance,
+    /// The longest start of the query that is a headword, 食べ物 for 食べ物屋さん.
+    PrefixTruncation,
+}
+
+/// A "did you mean" suggestion for a lookup without results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Correction {
+    /// The keb or reb to search for instead.
+    pub suggestion: String,
+    pub strategy: CorrectionStrategy,
+    /// The number of entries with `suggestion` as a keb or reb.
+    pub result_count: i64,
+}
+
+/// The number of entries per headword in the JSON array ?1. Headwords without entries are missing.
+const COUNT_HEADWORDS_SQL: &str = r#"
+    SELECT f.form, COUNT(DISTINCT f.ent_seq) FROM (
+        SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.rebs) je
+        UNION ALL
+        SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.kebs) je WHERE e.kebs IS NOT NULL
+    ) f
+    WHERE f.form IN (SELECT value FROM json_each(?1))
+    GROUP BY f.form
+"#;
+
+/// The readings of ?1 to ?2 characters with their number of entries.
+const READINGS_BY_LENGTH_SQL: &str = r#"
+    SELECT je.value, COUNT(DISTINCT e.ent_seq) FROM entries e, json_each(e.rebs) je
+    WHERE length(je.value) BETWEEN ?1 AND ?2
+    GROUP BY je.value
+"#;
+
+/// `READINGS_BY_LENGTH_SQL` for spellfix1, keeping only readings that may be one edit away
+/// from the hiragana ?3 or the katakana ?4: `editdist3()` costs 100 per insertion or deletion
+/// and 150 per substitution. Readings mixing both scripts are left to `one_edit_apart`.
+const NEAR_READINGS_SQL: &str = r#"
+    SELECT je.value, COUNT(DISTINCT e.ent_seq) FROM entries e, json_each(e.rebs) je
+    WHERE length(je.value) BETWEEN ?1 AND ?2
+      AND (editdist3(?3, je.value) <= 150 OR editdist3(?4, je.value) <= 150
+           OR (je.value GLOB '*[ぁ-ゖ]*' AND je.value GLOB '*[ァ-ヺ]*'))
+    GROUP BY je.value
+"#;
+
+/// Returns true if `a` and `b` differ by exactly one inserted, deleted or replaced character.
+fn one_edit_apart(a: &[char], b: &[char]) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(long).take_while(|(x, y)| x == y).count();
+    if short.len() == long.len() {
+        // One substitution: everything after the first difference is equal
+        prefix < short.len() && short[prefix + 1..] == long[prefix + 1..]
+    } else {
+        short[prefix..] == long[prefix + 1..]
+    }
+}
+
+impl DictDb {
+    /// "Did you mean" suggestions for a kanji or kana query that is not a headword, best
+    /// strategy first: the other kana script, the query read as romaji, readings one edit
+    /// away, then the longest start of the query that is a headword.
+    ///
+    /// Returns nothing if the query is a keb or reb itself. Meant to be called only after
+    /// a lookup came back empty, the successful lookups don't pay for it.
+    pub async fn suggest_corrections(&self, query: &str, limit: usize) -> Result<Vec<Correction>> {
+        self.observe("suggest_corrections", query, || format!("limit {}", limit), async {
+            let query = non_empty(query)?;
+            if limit == 0 {
+                return Ok(Vec::new());
+            }
+
+            let mut candidates: Vec<(String, CorrectionStrategy)> = Vec::new();
+            // Readings the edit distance strategy starts from, in hiragana
+            let mut kana_forms: Vec<String> = Vec::new();
+            if is_kana_str(query) {
+                let hiragana = katakana_to_hiragana(query);
+                for converted in [hiragana.clone(), hiragana_to_katakana(query)] {
+                    candidates.push((converted, CorrectionStrategy::KanaConversion));
+                }
+                kana_forms.push(hiragana);
+            } else if query.chars().any(|c| c.is_ascii_alphabetic()) {
+                let kana = romaji_to_kana(query);
+                if is_kana_str(&kana) {
+                    candidates.push((kana.clone(), CorrectionStrategy::Romaji));
+                    candidates.push((hiragana_to_katakana(&kana), CorrectionStrategy::Romaji));
+                    kana_forms.push(kana);
+                }
+            }
+            let chars: Vec<char> = query.chars().collect();
+            for len in (1..chars.len()).rev() {
+                candidates.push((chars[..len].iter().collect(), CorrectionStrategy::PrefixTruncation));
+            }
+
+            // One query counts the query itself and every fixed candidate
+            let mut forms: Vec<&str> = candidates.iter().map(|(c, _)| c.as_str()).collect();
+            forms.push(query);
+            let counts: HashMap<String, i64> = sqlx::query_as(COUNT_HEADWORDS_SQL)
+                .bind(serde_json::to_string(&forms)?)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .collect();
+            if counts.contains_key(query) {
+                return Ok(Vec::new());
+            }
+
+            let mut corrections: Vec<Correction> = Vec::new();
+            let mut add = |suggestion: String, strategy, result_count| {
+                if suggestion != query {
+                    corrections.push(Correction { suggestion, strategy, result_count });
+                }
+            };
+            let mut truncated = false;
+            for (candidate, strategy) in candidates {
+                let Some(&count) = counts.get(&candidate) else { continue };
+                if strategy == CorrectionStrategy::PrefixTruncation {
+                    // Only the longest start that matches
+                    if truncated {
+                        continue;
+                    }
+                    truncated = true;
+                }
+                add(candidate, strategy, count);
+            }
+
+            for kana in &kana_forms {
+                let len = kana.chars().count();
+                let sql = if self.extensions.spellfix1 { NEAR_READINGS_SQL } else { READINGS_BY_LENGTH_SQL };
+                let mut readings = sqlx::query_as::<_, (String, i64)>(sql).bind(len.saturating_sub(1) as i64).bind(len as i64 + 1);
+                if self.extensions.spellfix1 {
+                    readings = readings.bind(kana).bind(hiragana_to_katakana(kana));
+                }
+                let readings = readings.fetch_all(&self.pool).await?;
+                let kana: Vec<char> = kana.chars().collect();
+                let mut near: Vec<(String, i64)> = readings
+                    .into_iter()
+                    .filter(|(reading, _)| {
+                        let reading: Vec<char> = katakana_to_hiragana(reading).chars().collect();
+                        one_edit_apart(&reading, &kana)
+                    })
+                    .collect();
+                near.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                for (reading, count) in near {
+                    add(reading, CorrectionStrategy::EditDistance, count);
+                }
+            }
+
+            // Strategy order, each suggestion under the best strategy that found it
+            corrections.sort_by_key(|c| c.strategy as u8);
+            let mut seen = HashSet::new();
+            corrections.retain(|c| seen.insert(c.suggestion.clone()));
+            corrections.truncate(limit);
+            Ok(corrections)
+        })
+        .await
+    }
+}
@@ -0,0 +1,158 @@
+use sqlx::{Sqlite, Transaction};
+
+use crate::{DictDb, Entry, EntryParsed, EntryRow, KEle, REle, Result, Sense};
+
+/// The first ent_seq assigned to custom entries. Official JMdict entries stay below it.
+pub const CUSTOM_ENT_SEQ_START: i64 = 9_000_000;
+
+/// The `source` of entries added with `DictDb::insert_custom_entry`.
+pub const SOURCE_CUSTOM: &str = "custom";
+
+/// A sense of a custom entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomSense {
+    /// The part of speech, e.g. "noun (common) (futsuumeishi)" or any label you like.
+    pub pos: Vec<String>,
+    /// The meanings.
+    pub gloss: Vec<String>,
+}
+
+/// A user-defined entry, e.g. a domain term that is not in JMdict.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomEntry {
+    /// The kanji forms, may be empty.
+    pub kebs: Vec<String>,
+    /// The readings, at least one is required.
+    pub rebs: Vec<String>,
+    pub senses: Vec<CustomSense>,
+}
+
+impl CustomEntry {
+    /// Converts into the XML entry shape used by the insert path.
+    fn to_entry(&self, ent_seq: i64) -> Entry {
+        Entry {
+            ent_seq: ent_seq.to_string(),
+            k_ele: (!self.kebs.is_empty()).then(|| {
+                self.kebs
+                    .iter()
+                    .map(|keb| KEle { keb: keb.clone(), ..Default::default() })
+                    .collect()
+            }),
+            r_ele: self
+                .rebs
+                .iter()
+                .map(|reb| REle { reb: reb.clone(), ..Default::default() })
+                .collect(),
+            sense: self
+                .senses
+                .iter()
+                .map(|s| Sense { pos: s.pos.clone(), gloss: s.gloss.clone(), ..Default::default() })
+                .collect(),
+        }
+    }
+}
+
+impl From<&EntryParsed> for CustomEntry {
+    fn from(e: &EntryParsed) -> Self {
+        CustomEntry {
+            kebs: e.kebs.clone().unwrap_or_default(),
+            rebs: e.rebs.clone(),
+            senses: e
+                .senses
+                .iter()
+                .map(|s| CustomSense { pos: s.pos.clone(), gloss: s.gloss.clone() })
+                .collect(),
+        }
+    }
+}
+
+impl EntryParsed {
+    /// Returns true if this is a user-defined entry rather than official JMdict data.
+    pub fn is_custom(&self) -> bool {
+        self.ent_seq >= CUSTOM_ENT_SEQ_START
+    }
+}
+
+impl DictDb {
+    /// Inserts a user-defined entry and returns the ent_seq assigned to it.
+    ///
+    /// Custom entries get ent_seq values from `CUSTOM_ENT_SEQ_START` upwards and are stored
+    /// with source "custom", so they show up in every search like official entries.
+    pub async fn insert_custom_entry(&self, entry: &CustomEntry) -> Result<i64> {
+        self.write_entries(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let ent_seq = Self::insert_custom_entry_tx(&mut tx, entry).await?;
+            tx.commit().await?;
+            Ok(ent_seq)
+        })
+        .await
+    }
+
+    /// Deletes a custom entry.
+    ///
+    /// # Returns
+    /// false if there is no custom entry with this ent_seq (official entries are never deleted).
+    pub async fn delete_custom_entry(&self, ent_seq: i64) -> Result<bool> {
+        self.write_entries(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let deleted = Self::delete_custom_entry_tx(&mut tx, ent_seq).await?;
+            tx.commit().await?;
+            Ok(deleted)
+        })
+        .await
+    }
+
+    pub(crate) async fn insert_custom_entry_tx(tx: &mut Transaction<'_, Sqlite>, entry: &CustomEntry) -> Result<i64> {
+        if entry.rebs.is_empty() {
+            return Err("a custom entry needs at least one reading".into());
+        }
+
+        let ent_seq: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(ent_seq) + 1, ?1) FROM entries WHERE ent_seq >= ?1")
+            .bind(CUSTOM_ENT_SEQ_START)
+            .fetch_one(&mut **tx)
+            .await?;
+        Self::upsert_entry_tx(tx, &entry.to_entry(ent_seq), SOURCE_CUSTOM).await?;
+        Ok(ent_seq)
+    }
+
+    pub(crate) async fn delete_custom_entry_tx(tx: &mut Transaction<'_, Sqlite>, ent_seq: i64) -> Result<bool> {
+        let is_custom: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM entries WHERE ent_seq = ?1 AND source = ?2)")
+            .bind(ent_seq)
+            .bind(SOURCE_CUSTOM)
+            .fetch_one(&mut **tx)
+            .await?;
+        if !is_custom {
+            return Ok(false);
+        }
+        Self::delete_entry_tx(tx, ent_seq).await
+    }
+
+    /// Returns all custom entries, ordered by ent_seq.
+    pub async fn list_custom_entries(&self) -> Result<Vec<EntryParsed>> {
+        let rows = sqlx::query_as::<_, EntryRow>("SELECT * FROM entries WHERE source = ?1 ORDER BY ent_seq")
+            .bind(SOURCE_CUSTOM)
+            .fetch_all(&self.pool)
+            .await?;
+        self.hydrate_entries(rows).await
+    }
+
+    /// Writes custom entries back with their original ent_seq, e.g. after a rebuild.
+    ///
+    /// `build_db` reads the custom entries with `list_custom_entries` before resetting
+    /// the database and restores them with this method afterwards.
+    pub async fn restore_custom_entries(&self, entries: &[EntryParsed]) -> Result<usize> {
+        self.write_entries(move || async move {
+            let mut tx = self.pool.begin().await?;
+            for e in entries {
+                if !e.is_custom() {
+                    return Err(format!("ent_seq {} is not in the custom entry range", e.ent_seq).into());
+                }
+                let entry = CustomEntry::from(e).to_entry(e.ent_seq);
+                Self::upsert_entry_tx(&mut tx, &entry, SOURCE_CUSTOM).await?;
+            }
+            tx.commit().await?;
+            Ok(entries.len())
+        })
+        .await
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use crate::{fold_gloss, EntryParsed};
+
+/// The share of glosses two entries must have in common to be merged.
+const MIN_GLOSS_OVERLAP: f64 = 0.5;
+
+/// An entry together with the near-duplicate entries merged into it, see
+/// `dedupe_by_reading_and_gloss`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedResult {
+    /// The first of the merged entries in the input order, the best ranked.
+    pub primary: EntryParsed,
+    /// The other entries, in input order.
+    pub variants: Vec<EntryParsed>,
+}
+
+/// The default glosses of all senses, folded as for gloss search.
+fn gloss_set(entry: &EntryParsed) -> HashSet<String> {
+    entry.senses.iter().flat_map(|s| s.default_glosses()).map(fold_gloss).collect()
+}
+
+/// Whether `b` is a near-duplicate of `a`: the same first reading and at least half of
+/// the glosses of the entry with fewer glosses also glosses of the other. Entries without
+/// glosses are never duplicates.
+fn is_duplicate(a: &EntryParsed, a_glosses: &HashSet<String>, b: &EntryParsed, b_glosses: &HashSet<String>) -> bool {
+    let fewer = a_glosses.len().min(b_glosses.len());
+    fewer > 0
+        && a.rebs.first().is_some_and(|r| b.rebs.first() == Some(r))
+        && a_glosses.intersection(b_glosses).count() as f64 / fewer as f64 >= MIN_GLOSS_OVERLAP
+}
+
+/// Groups of near-duplicates built up one item at a time, in rank order, see
+/// `dedupe_by_reading_and_gloss`. `entry` gives the entry of an item.
+pub(crate) struct DuplicateGroups<T, F> {
+    entry: F,
+    groups: Vec<(T, HashSet<String>, Vec<T>)>,
+}
+
+impl<T, F: Fn(&T) -> &EntryParsed> DuplicateGroups<T, F> {
+    pub(crate) fn new(entry: F) -> Self {
+        Self { entry, groups: Vec::new() }
+    }
+
+    /// Adds an item to the first group whose primary it duplicates, or starts a group.
+    pub(crate) fn push(&mut self, item: T) {
+        let glosses = gloss_set((self.entry)(&item));
+        let entry = &self.entry;
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|(primary, primary_glosses, _)| is_duplicate(entry(primary), primary_glosses, entry(&item), &glosses));
+        match group {
+            Some((_, _, variants)) => variants.push(item),
+            None => self.groups.push((item, glosses, Vec::new())),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// The primaries with their variants.
+    pub(crate) fn into_groups(self) -> Vec<(T, Vec<T>)> {
+        self.groups.into_iter().map(|(primary, _, variants)| (primary, variants)).collect()
+    }
+}
+
+/// Merges near-duplicate entries, such as variant spellings JMdict keeps as separate
+/// entries: an entry with the same first reading as an earlier one and at least half of
+/// the glosses in common (of the entry with fewer glosses, compared as `fold_gloss`
+/// folds them) becomes a variant of it.
+///
+/// Each entry is compared with the primaries of the groups so far in order and joins the
+/// first it duplicates, so the result only depends on the input order: the primaries keep
+/// it, each is the best ranked entry of its group.
+pub fn dedupe_by_reading_and_gloss(results: Vec<EntryParsed>) -> Vec<MergedResult> {
+    let mut groups = DuplicateGroups::new(|e: &EntryParsed| e);
+    for entry in results {
+        groups.push(entry);
+    }
+    groups
+        .into_groups()
+        .into_iter()
+        .map(|(primary, variants)| MergedResult { primary, variants })
+        .collect()
+}
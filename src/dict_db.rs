@@ -1,22 +1,155 @@
-use sqlx::{Sqlite, SqlitePool, Transaction, migrate::MigrateDatabase};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction, migrate::MigrateDatabase};
 use std::fs;
+use std::str::FromStr;
+use std::sync::Arc;
 
-use crate::{Entry}; // from src/lib.rs
+use crate::attach::AttachRegistry;
+use crate::ranking::nf_bucket;
+use crate::query_stats::QueryMonitor;
+use crate::suggest::SuggestCache;
+use crate::entry_cache::EntryCache;
+use crate::extensions::Extensions;
+use crate::guards::non_empty;
+use crate::complexity::entry_complexity;
+use crate::tags::TagCountCache;
+use crate::generation::{read_build_id, GenerationCheck, BUILD_ID_SQL};
+use crate::health::{HealthCache, SCHEMA_VERSION, SCHEMA_VERSION_SQL};
+use crate::insert::InsertOptions;
+use crate::build_lock::BuildLock;
+#[cfg(feature = "userdata")]
+use crate::history::LookupFailures;
+use crate::bulk::release_connection;
+use crate::timeout::{arm_connection, SenseLimits};
+use crate::{content_hash, fold_gloss, BorrowedGlosses, sense_uids, DictDbOptions, QueryGuards, Entry, Gloss, PartOfSpeech, RetryPolicy, Xref, CUSTOM_ENT_SEQ_START, DEFAULT_LANG}; // from src/lib.rs
 
 type AnyError = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, AnyError>;
 
-/// Returns true if the string contains any kanji (CJK Unified Ideographs).
+/// Whether connections with these options open the database read-only: with `mode=ro` or
+/// `immutable` in the URL, or a file the process may not write, which SQLite then opens
+/// read-only as well.
+fn opens_read_only(db_url: &str, options: &SqliteConnectOptions) -> bool {
+    let query = db_url.split_once('?').map_or("", |(_, query)| query);
+    let in_url = query.split('&').any(|param| matches!(param, "mode=ro" | "immutable=1" | "immutable=true"));
+    in_url || fs::metadata(options.get_filename()).is_ok_and(|meta| meta.permissions().readonly())
+}
+
+/// Returns true if the character is a kanji: a CJK Unified Ideograph (including the
+/// extension blocks) or a CJK Compatibility Ideograph, or the iteration mark 々.
+pub fn is_kanji(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'       // CJK Unified Ideographs
+        | '\u{3400}'..='\u{4DBF}'     // Extension A
+        | '\u{F900}'..='\u{FAFF}'     // Compatibility Ideographs
+        | '\u{20000}'..='\u{3134F}'   // Extensions B to G and Compatibility Supplement
+        | '々')
+}
+
+/// Returns true if the string contains any kanji, see `is_kanji`.
 pub fn contains_kanji(s: &str) -> bool {
-    s.chars().any(|c| ('\u{4E00}'..='\u{9FFF}').contains(&c))
+    s.chars().any(is_kanji)
 }
 
-/// The URL to the SQLite database file.
+/// Returns the distinct kanji of the string, in order of first appearance.
+pub fn extract_kanji(s: &str) -> Vec<char> {
+    let mut kanji = Vec::new();
+    for c in s.chars().filter(|&c| is_kanji(c)) {
+        if !kanji.contains(&c) {
+            kanji.push(c);
+        }
+    }
+    kanji
+}
+
+/// The URL to the SQLite database file, relative to the working directory.
+///
+/// Kept for backward compatibility; the binaries use `default_db_url()`.
 pub const DB_URL: &str = "sqlite:data/jmdict_e.db"; 
 
+/// The `source` of entries built from the JMdict XML.
+pub const SOURCE_JMDICT: &str = "jmdict";
+
+/// Entries with a reb equal to ?1, by ent_seq.
+pub(crate) const SEARCH_BY_READING_SQL: &str = r#"
+    SELECT * FROM entries
+    WHERE EXISTS (
+        SELECT 1 FROM json_each(entries.rebs) je WHERE je.value = ?1
+    )
+    ORDER BY ent_seq
+"#;
+
+/// Entries with a keb equal to ?1, by ent_seq.
+pub(crate) const SEARCH_BY_KANJI_SQL: &str = r#"
+    SELECT * FROM entries
+    WHERE kebs IS NOT NULL
+      AND EXISTS (
+        SELECT 1 FROM json_each(entries.kebs) je WHERE je.value = ?1
+      )
+    ORDER BY ent_seq
+"#;
+
+/// The priority tags of all kanji and reading elements, without duplicates.
+fn priority_tags(e: &Entry) -> Vec<&str> {
+    let mut priority: Vec<&str> = Vec::new();
+    let ke_pri = e.k_ele.iter().flatten().flat_map(|k| k.ke_pri.iter());
+    let re_pri = e.r_ele.iter().flat_map(|r| r.re_pri.iter());
+    for tag in ke_pri.chain(re_pri) {
+        if !priority.contains(&tag.as_str()) {
+            priority.push(tag);
+        }
+    }
+    priority
+}
+
+/// Parses stored cross-references, see `Xref::from_str`.
+fn parse_xrefs(raw: &[String]) -> Result<Vec<Xref>> {
+    raw.iter().map(|x| Ok(x.parse::<Xref>()?)).collect()
+}
+
+/// Sets `applies_to_query` on every sense from its restrictions to the searched forms.
+pub(crate) fn mark_applicable(entries: &mut [EntryParsed], keb: Option<&str>, reb: Option<&str>) {
+    for sense in entries.iter_mut().flat_map(|e| e.senses.iter_mut()) {
+        sense.applies_to_query = sense.applies_to(keb, reb);
+    }
+}
+
 /// Represents the dictionary database connection and operations.
+#[derive(Debug)]
 pub struct DictDb {
-    pool: SqlitePool,
+    pub(crate) pool: SqlitePool,
+    /// Databases attached with `attach`, re-attached on every pooled connection.
+    pub(crate) attachments: AttachRegistry,
+    /// How writes are retried while the database is busy.
+    pub(crate) retry: RetryPolicy,
+    /// The in-memory headword index of `build_suggest_index`.
+    pub(crate) suggest: SuggestCache,
+    /// The counts of `distinct_pos` and the other tag lists.
+    pub(crate) tag_counts: TagCountCache,
+    /// The entries of `entry_shared`, also cleared by the commits of `DictTx`.
+    pub(crate) entry_cache: Arc<EntryCache>,
+    /// Slow query logging and timings, None unless a threshold was set.
+    pub(crate) monitor: Option<QueryMonitor>,
+    /// The build id read on connect, for `check_generation`.
+    pub(crate) generation: GenerationCheck,
+    /// What `health_check` keeps between calls.
+    pub(crate) health: HealthCache,
+    /// The lock given to `hold_build_lock`.
+    pub(crate) build_lock: Option<BuildLock>,
+    /// The shortest queries the substring searches accept.
+    pub(crate) guards: QueryGuards,
+    /// Whether `suggest_fast` gives its suggestions hints.
+    pub(crate) suggestion_hints: bool,
+    /// The optional SQLite extensions found on connect.
+    pub(crate) extensions: Extensions,
+    /// Whether `search_sentences` ignores non-ASCII case itself: LIKE only knows ASCII case
+    /// unless ICU replaced it, and neither with `case_sensitive_like`, which also puts
+    /// back the built-in LIKE.
+    pub(crate) fold_sentence_case: bool,
+    /// The `record_lookup` calls that could not be recorded.
+    #[cfg(feature = "userdata")]
+    pub(crate) lookup_failures: LookupFailures,
 }
 
 
@@ -24,15 +157,82 @@ pub struct DictDb {
 impl DictDb {
     /// Connects to the JMdict SQLite database and initializes the schema if needed.
     /// 
+    /// Opened read-only, e.g. with "sqlite://jmdict.db?mode=ro", nothing is written to the
+    /// file, not even on connect.
+    ///
     /// # Arguments
     /// * `db_url` - The database URL, e.g. "sqlite://jmdict.db"
     pub async fn connect(db_url: &str) -> Result<Self> {
-        let pool = SqlitePool::connect(db_url).await?;
-        let db = Self { pool };
-        db.init_schema().await?;
+        Self::connect_with(DictDbOptions::new(db_url)).await
+    }
+
+    /// Connects with the given settings and initializes the schema if needed.
+    pub async fn connect_with(opts: DictDbOptions) -> Result<Self> {
+        // The busy timeout also covers the statements sqlx runs while opening a connection
+        let options = SqliteConnectOptions::from_str(&opts.db_url)?.busy_timeout(opts.pragmas.busy_timeout);
+        #[cfg(feature = "sqlite-extensions")]
+        let options = opts.extensions.iter().fold(options, |options, path| options.extension(path.clone()));
+        let read_only = opens_read_only(&opts.db_url, &options);
+        // The hooks set the per-connection pragmas, arm connections for `with_query_options`
+        // timeouts and clean up after dropped `bulk_lookup` streams
+        let pragmas = opts.pragmas;
+        let pool_options = SqlitePoolOptions::new()
+            .after_connect(move |conn, _| {
+                Box::pin(async move {
+                    pragmas.apply(conn).await?;
+                    arm_connection(conn).await
+                })
+            })
+            .before_acquire(|conn, _| Box::pin(async move { arm_connection(conn).await.map(|()| true) }))
+            .after_release(|conn, _| release_connection(conn));
+        let pool = if opts.db_url.contains(":memory:") {
+            // An in-memory database only lives as long as one of its connections
+            pool_options
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(options)
+                .await?
+        } else {
+            pool_options.connect_with(options).await?
+        };
+        let mut db = Self {
+            pool,
+            attachments: AttachRegistry::default(),
+            retry: opts.retry,
+            suggest: SuggestCache::default(),
+            tag_counts: TagCountCache::default(),
+            entry_cache: Arc::new(EntryCache::new(opts.entry_cache)),
+            monitor: opts.slow_query.map(QueryMonitor::new),
+            generation: GenerationCheck::new(&opts.db_url, String::new(), opts.generation_check),
+            health: HealthCache::default(),
+            build_lock: None,
+            guards: opts.guards,
+            suggestion_hints: opts.suggestion_hints,
+            extensions: Extensions::default(),
+            fold_sentence_case: false,
+            #[cfg(feature = "userdata")]
+            lookup_failures: LookupFailures::default(),
+        };
+        db.extensions = Extensions::probe(&db.pool).await?;
+        db.fold_sentence_case = match opts.pragmas.case_sensitive_like {
+            None => !db.extensions.icu,
+            Some(case_sensitive) => !case_sensitive,
+        };
+        if !read_only {
+            db.init_schema().await?;
+        }
+        let build_id = read_build_id(&mut *db.pool.acquire().await?, db.generation.db_path().as_deref()).await;
+        let build_id = build_id.unwrap_or_default();
+        db.generation = GenerationCheck::new(&opts.db_url, build_id, opts.generation_check);
         Ok(db)
     }
 
+    /// Closes all connections of the pool, waiting for them to finish.
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
     /// Resets the database by dropping it and creating a new one.
     /// 
     /// # Arguments
@@ -61,16 +261,29 @@ impl DictDb {
     }
 
     
-    /// Initializes the database schema.
+    /// Initializes the database schema, migrating files written by older versions.
+    ///
+    /// Only runs on connections that may write: a read-only database is used as it is, and
+    /// `health_check` tells if its schema is older.
     async fn init_schema(&self) -> Result<()> {
-        // JSON arrays for all list fields
-        let sql = r#"
-        PRAGMA foreign_keys = ON;
+        let had_glosses = self.has_table("glosses").await?;
+        let had_kanji_index = self.has_table("kanji_index").await?;
+        let had_reading_index = self.has_table("reading_index").await?;
+        let had_punct_index = self.has_table("punct_index").await?;
 
+        // JSON arrays for all list fields. foreign_keys is set on every connection by
+        // `ConnectionPragmas`, not here
+        let sql = r#"
         CREATE TABLE IF NOT EXISTS entries (
           ent_seq  INTEGER PRIMARY KEY,
           rebs     TEXT NOT NULL, -- JSON array of readings
-          kebs     TEXT NULL      -- JSON array of kanji or NULL
+          kebs     TEXT NULL,     -- JSON array of kanji or NULL
+          priority TEXT NOT NULL DEFAULT '[]', -- JSON array of ke_pri/re_pri tags
+          nf_bucket INTEGER NULL, -- the best nfNN priority tag, 1 for the 500 most frequent words
+          source   TEXT NOT NULL DEFAULT 'jmdict', -- 'jmdict' or 'custom'
+          content_hash TEXT NULL, -- content_hash() of the entry as inserted
+          re_restr TEXT NOT NULL DEFAULT '[]', -- JSON array of the re_restr of each reb, '[]' if none is restricted
+          complexity INTEGER NULL -- complexity_score() of the entry
         );
 
         CREATE TABLE IF NOT EXISTS senses (
@@ -79,32 +292,368 @@ impl DictDb {
           sense_order  INTEGER NOT NULL,
           pos          TEXT,  -- JSON array of strings
           xref         TEXT,  -- JSON array of strings
-          gloss        TEXT   -- JSON array of strings
+          gloss        TEXT,  -- JSON array of strings
+          stagk        TEXT NOT NULL DEFAULT '[]', -- JSON array of kebs the sense is restricted to
+          stagr        TEXT NOT NULL DEFAULT '[]', -- JSON array of rebs the sense is restricted to
+          misc         TEXT NOT NULL DEFAULT '[]', -- JSON array of misc tags, e.g. "uk" expanded
+          sense_uid    TEXT NULL, -- sense_uids() of the entry as inserted
+          pos_inherited  INTEGER NOT NULL DEFAULT 0, -- pos is the previous sense's, the XML has none
+          misc_inherited INTEGER NOT NULL DEFAULT 0, -- likewise for misc
+          field        TEXT NOT NULL DEFAULT '[]', -- JSON array of field tags, e.g. "comp" expanded
+          dial         TEXT NOT NULL DEFAULT '[]'  -- JSON array of dialect tags
         );
 
         CREATE INDEX IF NOT EXISTS idx_senses_entry ON senses(ent_seq);
+
+        -- Every gloss in every language; senses.gloss keeps the English ones for display
+        CREATE TABLE IF NOT EXISTS glosses (
+          sense_id  INTEGER NOT NULL REFERENCES senses(id) ON DELETE CASCADE,
+          lang      TEXT NOT NULL,    -- ISO 639-2, 'eng' without xml:lang
+          position  INTEGER NOT NULL, -- order within the sense and language
+          text      TEXT NOT NULL,
+          folded    TEXT NULL,        -- case-folded text for case-insensitive lookup
+          norm      TEXT NULL         -- normalized text, set by normalize_glosses
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_glosses_sense ON glosses(sense_id);
+        CREATE INDEX IF NOT EXISTS idx_glosses_lang_text ON glosses(lang, text COLLATE NOCASE);
+        CREATE INDEX IF NOT EXISTS idx_glosses_lang_norm ON glosses(lang, norm);
+
+        -- Which entries have a kanji in one of their kebs
+        CREATE TABLE IF NOT EXISTS kanji_index (
+          ch       TEXT NOT NULL,
+          ent_seq  INTEGER NOT NULL REFERENCES entries(ent_seq) ON DELETE CASCADE,
+          PRIMARY KEY (ch, ent_seq) -- also the index on ch
+        ) WITHOUT ROWID;
+
+        CREATE INDEX IF NOT EXISTS idx_kanji_index_entry ON kanji_index(ent_seq);
+
+        -- The rebs of every entry after normalize_kana with READING_NORMALIZATION, and after
+        -- fold_vowel_length
+        CREATE TABLE IF NOT EXISTS reading_index (
+          norm     TEXT NOT NULL,
+          ent_seq  INTEGER NOT NULL REFERENCES entries(ent_seq) ON DELETE CASCADE,
+          folded   TEXT NULL,
+          PRIMARY KEY (norm, ent_seq)
+        ) WITHOUT ROWID;
+
+        CREATE INDEX IF NOT EXISTS idx_reading_index_entry ON reading_index(ent_seq);
+
+        -- The kebs and rebs with punctuation, by their form after strip_headword_punctuation
+        CREATE TABLE IF NOT EXISTS punct_index (
+          plain    TEXT NOT NULL,
+          ent_seq  INTEGER NOT NULL REFERENCES entries(ent_seq) ON DELETE CASCADE,
+          form     TEXT NOT NULL,
+          PRIMARY KEY (plain, ent_seq, form)
+        ) WITHOUT ROWID;
+
+        CREATE INDEX IF NOT EXISTS idx_punct_index_entry ON punct_index(ent_seq);
+
+        CREATE TABLE IF NOT EXISTS frequency (
+          ent_seq  INTEGER NOT NULL REFERENCES entries(ent_seq) ON DELETE CASCADE,
+          source   TEXT NOT NULL,    -- name of the frequency list
+          rank     INTEGER NOT NULL, -- 1 is the most frequent
+          PRIMARY KEY (ent_seq, source)
+        );
+
+        CREATE TABLE IF NOT EXISTS furigana (
+          ent_seq        INTEGER NOT NULL REFERENCES entries(ent_seq) ON DELETE CASCADE,
+          keb            TEXT NOT NULL,
+          reb            TEXT NOT NULL,
+          segments_json  TEXT NOT NULL, -- JSON array of {text, ruby}
+          PRIMARY KEY (ent_seq, keb, reb)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_furigana_forms ON furigana(keb, reb);
+
+        CREATE TABLE IF NOT EXISTS jlpt (
+          ent_seq  INTEGER PRIMARY KEY REFERENCES entries(ent_seq) ON DELETE CASCADE,
+          level    INTEGER NOT NULL -- 5 for N5 ... 1 for N1
+        );
+
+        CREATE TABLE IF NOT EXISTS sentences (
+          id          INTEGER PRIMARY KEY AUTOINCREMENT,
+          japanese    TEXT NOT NULL,
+          english     TEXT NOT NULL,
+          tatoeba_id  TEXT NULL  -- the #ID= part of the A line
+        );
+
+        CREATE TABLE IF NOT EXISTS sentence_words (
+          id           INTEGER PRIMARY KEY AUTOINCREMENT,
+          sentence_id  INTEGER NOT NULL REFERENCES sentences(id) ON DELETE CASCADE,
+          position     INTEGER NOT NULL,
+          headword     TEXT NOT NULL,
+          reading      TEXT NULL,
+          sense        INTEGER NULL, -- 1-based sense number from the B line
+          surface      TEXT NULL,
+          checked      INTEGER NOT NULL DEFAULT 0,
+          ent_seq      INTEGER NULL  -- the linked entry, NULL if no entry matched
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_sentence_words_entry ON sentence_words(ent_seq);
+        CREATE INDEX IF NOT EXISTS idx_sentence_words_headword ON sentence_words(headword);
+
+        CREATE TABLE IF NOT EXISTS meta (
+          key   TEXT PRIMARY KEY,
+          value TEXT NOT NULL
+        );
+
+        -- The <entry> elements of a build with BuildOptions::store_raw_xml, zstd-compressed
+        CREATE TABLE IF NOT EXISTS raw_xml (
+          ent_seq  INTEGER PRIMARY KEY,
+          xml      BLOB NOT NULL
+        );
         "#;
 
         sqlx::query(sql).execute(&self.pool).await?;
+        // Random per database file, so that a rebuilt file can be told apart, see check_generation
+        if sqlx::query_scalar::<_, String>(BUILD_ID_SQL).fetch_optional(&self.pool).await?.is_none() {
+            sqlx::query("INSERT OR IGNORE INTO meta (key, value) VALUES ('build_id', lower(hex(randomblob(16))))")
+                .execute(&self.pool)
+                .await?;
+        }
+
+        // Word lists only reference entries by ent_seq, so they outlive entry updates
+        #[cfg(feature = "userdata")]
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS wordlists (
+              id          INTEGER PRIMARY KEY AUTOINCREMENT,
+              name        TEXT NOT NULL UNIQUE,
+              created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS wordlist_items (
+              list_id   INTEGER NOT NULL REFERENCES wordlists(id) ON DELETE CASCADE,
+              ent_seq   INTEGER NOT NULL,
+              sense_uid TEXT NULL, -- a sense of the entry, see sense_uids()
+              note      TEXT NULL,
+              added_at  TEXT NOT NULL DEFAULT (datetime('now')),
+              PRIMARY KEY (list_id, ent_seq)
+            );
+
+            CREATE TABLE IF NOT EXISTS lookup_stats (
+              ent_seq  INTEGER PRIMARY KEY,
+              count    INTEGER NOT NULL,
+              last_at  TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_lookup_stats_last_at ON lookup_stats(last_at);
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Columns added after the first release, for databases built by older versions
+        self.ensure_column("entries", "priority", "TEXT NOT NULL DEFAULT '[]'").await?;
+        self.ensure_column("entries", "source", "TEXT NOT NULL DEFAULT 'jmdict'").await?;
+        // Left NULL for existing entries, the XML they were built from is gone
+        self.ensure_column("entries", "content_hash", "TEXT NULL").await?;
+        self.ensure_column("entries", "re_restr", "TEXT NOT NULL DEFAULT '[]'").await?;
+        if self.ensure_column("entries", "nf_bucket", "INTEGER NULL").await? {
+            // Older databases already have the priority tags to derive it from
+            sqlx::query(
+                r#"
+                UPDATE entries SET nf_bucket = (
+                  SELECT MIN(CAST(substr(je.value, 3) AS INTEGER)) FROM json_each(entries.priority) je
+                  WHERE je.value GLOB 'nf[0-9][0-9]'
+                )
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_entries_nf_bucket ON entries(nf_bucket)")
+            .execute(&self.pool)
+            .await?;
+        self.ensure_column("senses", "stagk", "TEXT NOT NULL DEFAULT '[]'").await?;
+        self.ensure_column("senses", "stagr", "TEXT NOT NULL DEFAULT '[]'").await?;
+        self.ensure_column("senses", "misc", "TEXT NOT NULL DEFAULT '[]'").await?;
+        // Left NULL like content_hash, until the entry is stored again
+        self.ensure_column("senses", "sense_uid", "TEXT NULL").await?;
+        // Old rows keep the empty pos of the XML until the entry is stored again
+        self.ensure_column("senses", "pos_inherited", "INTEGER NOT NULL DEFAULT 0").await?;
+        self.ensure_column("senses", "misc_inherited", "INTEGER NOT NULL DEFAULT 0").await?;
+        self.ensure_column("senses", "field", "TEXT NOT NULL DEFAULT '[]'").await?;
+        self.ensure_column("senses", "dial", "TEXT NOT NULL DEFAULT '[]'").await?;
+        #[cfg(feature = "userdata")]
+        self.ensure_column("wordlist_items", "sense_uid", "TEXT NULL").await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_senses_uid ON senses(sense_uid)")
+            .execute(&self.pool)
+            .await?;
+        if !had_kanji_index {
+            self.rebuild_kanji_index().await?;
+        }
+        if self.ensure_column("reading_index", "folded", "TEXT NULL").await? || !had_reading_index {
+            self.rebuild_reading_index().await?;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_reading_index_folded ON reading_index(folded)")
+            .execute(&self.pool)
+            .await?;
+        if !had_punct_index {
+            self.rebuild_punctuation_index().await?;
+        }
+        if !had_glosses {
+            // Databases built before the glosses table only have English glosses
+            sqlx::query(
+                r#"
+                INSERT INTO glosses (sense_id, lang, position, text)
+                SELECT s.id, 'eng', je.key, je.value FROM senses s, json_each(s.gloss) je
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        if self.ensure_column("glosses", "folded", "TEXT NULL").await? || !had_glosses {
+            // SQLite's lower() only knows ASCII, so older rows are folded here
+            self.fill_folded_glosses().await?;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_glosses_lang_folded ON glosses(lang, folded)")
+            .execute(&self.pool)
+            .await?;
+        let added_complexity = self.ensure_column("entries", "complexity", "INTEGER NULL").await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_entries_complexity ON entries(complexity)")
+            .execute(&self.pool)
+            .await?;
+        if added_complexity {
+            // Computed from the hydrated entries, once the glosses are there; stored
+            // entries get theirs on insert
+            self.fill_complexity().await?;
+        }
+        // Never lowered, a file migrated by a newer version keeps its version
+        let schema_version: Option<i64> = sqlx::query_scalar(SCHEMA_VERSION_SQL).fetch_optional(&self.pool).await?;
+        if schema_version.is_none_or(|version| version < SCHEMA_VERSION) {
+            sqlx::query("INSERT OR REPLACE INTO meta (key, value) VALUES ('schema_version', ?1)")
+                .bind(SCHEMA_VERSION.to_string())
+                .execute(&self.pool)
+                .await?;
+        }
         Ok(())
     }
 
-    // Batch insert all entries in one transaction
-    pub async fn insert_entries(&self, entries: &[Entry]) -> Result<()> {
-        let mut tx = self.pool.begin().await?;
-        for e in entries {
-            Self::upsert_entry_tx(&mut tx, e).await?;
+    /// Returns true if the main database has a table or index with this name.
+    async fn has_table(&self, name: &str) -> Result<bool> {
+        Ok(sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = ?1)")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    /// Adds a column to a table if it does not exist yet, returns true if it was added.
+    async fn ensure_column(&self, table: &str, column: &str, decl: &str) -> Result<bool> {
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?1)")
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await?;
+        if !columns.iter().any(|c| c == column) {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl))
+                .execute(&self.pool)
+                .await?;
+            return Ok(true);
         }
-        tx.commit().await?;
+        Ok(false)
+    }
+
+    /// Inserts or replaces the entries, committing every `DEFAULT_INSERT_CHUNK` of them.
+    ///
+    /// See `insert_entries_with` for progress, other chunk sizes, one transaction for all
+    /// and resuming after a failure.
+    pub async fn insert_entries(&self, entries: &[Entry]) -> Result<()> {
+        self.insert_entries_with(entries, &InsertOptions::default(), |_| {}).await?;
         Ok(())
     }
 
+    /// Inserts or replaces a single entry and its senses, in its own transaction.
+    ///
+    /// Useful to patch individual entries without a full rebuild.
+    ///
+    /// # Returns
+    /// true if a row was written, false if the entry was stored unchanged already.
+    pub async fn upsert_entry(&self, entry: &Entry) -> Result<bool> {
+        self.write_entries(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let written = Self::upsert_entry_tx(&mut tx, entry, SOURCE_JMDICT).await?;
+            tx.commit().await?;
+            Ok(written)
+        })
+        .await
+    }
+
+    /// Deletes an entry together with its senses, frequency ranks, JLPT level and furigana.
+    ///
+    /// # Returns
+    /// true if the entry existed.
+    pub async fn delete_entry(&self, ent_seq: i64) -> Result<bool> {
+        self.write_entries(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let deleted = Self::delete_entry_tx(&mut tx, ent_seq).await?;
+            tx.commit().await?;
+            Ok(deleted)
+        })
+        .await
+    }
+
+    /// Deletes several entries in one transaction, see `delete_entry`.
+    ///
+    /// # Returns
+    /// The number of entries that existed and were deleted.
+    pub async fn delete_entries(&self, ent_seqs: &[i64]) -> Result<usize> {
+        self.write_entries(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let mut deleted = 0;
+            for &ent_seq in ent_seqs {
+                if Self::delete_entry_tx(&mut tx, ent_seq).await? {
+                    deleted += 1;
+                }
+            }
+            tx.commit().await?;
+            Ok(deleted)
+        })
+        .await
+    }
+
+    /// Deletes a single entry; dependent rows go with it through ON DELETE CASCADE.
+    pub(crate) async fn delete_entry_tx(tx: &mut Transaction<'_, Sqlite>, ent_seq: i64) -> Result<bool> {
+        let deleted = sqlx::query("DELETE FROM entries WHERE ent_seq = ?1")
+            .bind(ent_seq)
+            .execute(&mut **tx)
+            .await?
+            .rows_affected();
+        // Example sentences stay, they just lose the link
+        sqlx::query("UPDATE sentence_words SET ent_seq = NULL WHERE ent_seq = ?1")
+            .bind(ent_seq)
+            .execute(&mut **tx)
+            .await?;
+        Ok(deleted > 0)
+    }
+
     /// Insert/replace a single Entry (and its senses)
-    async fn upsert_entry_tx(tx: &mut Transaction<'_, Sqlite>, e: &Entry) -> Result<()> {
+    ///
+    /// `source` is stored with the entry; official data may not use the custom ent_seq range,
+    /// so a rebuild can never overwrite a custom entry. Entries stored with the same content
+    /// hash and source are left alone, so re-importing a new dictionary version only rewrites
+    /// what changed.
+    ///
+    /// # Returns
+    /// true if the entry was written, false if it was unchanged.
+    pub(crate) async fn upsert_entry_tx(tx: &mut Transaction<'_, Sqlite>, e: &Entry, source: &str) -> Result<bool> {
         let ent_seq: i64 = e.ent_seq.parse()?;
+        if source == SOURCE_JMDICT && ent_seq >= CUSTOM_ENT_SEQ_START {
+            return Err(format!("ent_seq {} is in the range reserved for custom entries", ent_seq).into());
+        }
+        let hash = content_hash(e);
+        let stored: Option<(Option<String>, String)> =
+            sqlx::query_as("SELECT content_hash, source FROM entries WHERE ent_seq = ?1")
+                .bind(ent_seq)
+                .fetch_optional(&mut **tx)
+                .await?;
+        if stored.is_some_and(|(stored_hash, stored_source)| stored_hash.as_ref() == Some(&hash) && stored_source == source) {
+            return Ok(false);
+        }
 
         let rebs: Vec<&str> = e.r_ele.iter().map(|r| r.reb.as_str()).collect();
         let rebs_json = serde_json::to_string(&rebs)?;
+        let re_restr_json = serde_json::to_string(&e.reb_restrictions())?;
 
         let kebs_json: Option<String> = e
             .k_ele
@@ -115,37 +664,67 @@ impl DictDb {
             })
             .transpose()?;
 
+        let priority = priority_tags(e);
+        let priority_json = serde_json::to_string(&priority)?;
+        let nf = nf_bucket(&priority);
+        let tags = e.inherited_sense_tags();
+        let complexity = entry_complexity(e, &tags);
+
         // Upsert entry
         sqlx::query(
             r#"
-            INSERT INTO entries (ent_seq, rebs, kebs)
-            VALUES (?1, ?2, ?3)
+            INSERT INTO entries (ent_seq, rebs, kebs, priority, nf_bucket, source, content_hash, re_restr, complexity)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
             ON CONFLICT(ent_seq) DO UPDATE SET
               rebs = excluded.rebs,
-              kebs = excluded.kebs
+              kebs = excluded.kebs,
+              priority = excluded.priority,
+              nf_bucket = excluded.nf_bucket,
+              source = excluded.source,
+              content_hash = excluded.content_hash,
+              re_restr = excluded.re_restr,
+              complexity = excluded.complexity
             "#,
         )
         .bind(ent_seq)
         .bind(rebs_json)
         .bind(kebs_json)
+        .bind(priority_json)
+        .bind(nf.map(i64::from))
+        .bind(source)
+        .bind(&hash)
+        .bind(re_restr_json)
+        .bind(complexity as i64)
         .execute(&mut **tx)
         .await?;
 
+        let kebs: Vec<&str> = e.k_ele.iter().flatten().map(|k| k.keb.as_str()).collect();
+        Self::index_kanji_tx(tx, ent_seq, &kebs).await?;
+        Self::index_readings_tx(tx, ent_seq, &rebs).await?;
+        let forms: Vec<&str> = rebs.iter().chain(&kebs).copied().collect();
+        Self::index_punctuation_tx(tx, ent_seq, &forms).await?;
+
         // Replace senses for this entry
         sqlx::query("DELETE FROM senses WHERE ent_seq = ?1")
             .bind(ent_seq)
             .execute(&mut **tx)
             .await?;
 
-        for (i, s) in e.sense.iter().enumerate() {
-            let pos_json = serde_json::to_string(&s.pos)?;
+        let uids = sense_uids(e);
+        for (i, ((s, uid), tags)) in e.sense.iter().zip(&uids).zip(&tags).enumerate() {
+            let pos_json = serde_json::to_string(tags.pos)?;
             let xref_json = serde_json::to_string(&s.xref)?;
             let gloss_json = serde_json::to_string(&s.gloss)?;
+            let stagk_json = serde_json::to_string(&s.stagk)?;
+            let stagr_json = serde_json::to_string(&s.stagr)?;
+            let misc_json = serde_json::to_string(tags.misc)?;
+            let field_json = serde_json::to_string(&s.field)?;
+            let dial_json = serde_json::to_string(&s.dial)?;
 
-            sqlx::query(
+            let sense = sqlx::query(
                 r#"
-                INSERT INTO senses (ent_seq, sense_order, pos, xref, gloss)
-                VALUES (?1, ?2, ?3, ?4, ?5)
+                INSERT INTO senses (ent_seq, sense_order, pos, xref, gloss, stagk, stagr, misc, sense_uid, pos_inherited, misc_inherited, field, dial)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
                 "#,
             )
             .bind(ent_seq)
@@ -153,15 +732,49 @@ impl DictDb {
             .bind(pos_json)
             .bind(xref_json)
             .bind(gloss_json)
+            .bind(stagk_json)
+            .bind(stagr_json)
+            .bind(misc_json)
+            .bind(uid)
+            .bind(tags.pos_inherited)
+            .bind(tags.misc_inherited)
+            .bind(field_json)
+            .bind(dial_json)
             .execute(&mut **tx)
             .await?;
+            let sense_id = sense.last_insert_rowid();
+
+            let english = s.gloss.iter().map(|text| (DEFAULT_LANG, text));
+            let other = s.other_glosses.iter().map(|g| (g.lang.as_str(), &g.text));
+            let mut positions: Vec<(&str, i64)> = Vec::new();
+            for (lang, text) in english.chain(other) {
+                let position = match positions.iter_mut().find(|(l, _)| *l == lang) {
+                    Some((_, n)) => {
+                        *n += 1;
+                        *n
+                    }
+                    None => {
+                        positions.push((lang, 0));
+                        0
+                    }
+                };
+                sqlx::query("INSERT INTO glosses (sense_id, lang, position, text, folded) VALUES (?1, ?2, ?3, ?4, ?5)")
+                    .bind(sense_id)
+                    .bind(lang)
+                    .bind(position)
+                    .bind(text)
+                    .bind(fold_gloss(text))
+                    .execute(&mut **tx)
+                    .await?;
+            }
         }
 
-        Ok(())
+        Ok(true)
     }
 
    
-    /// Searches for entries by reading (reb) and returns all matching entries with their senses.
+    /// Searches for entries by reading (reb) and returns all matching entries with their senses,
+    /// ordered by ent_seq.
     /// 
     /// # Arguments
     /// * `reading` - The reading string to search for.
@@ -169,61 +782,24 @@ impl DictDb {
     /// # Returns
     /// A vector of EntryParsed.
     pub async fn search_entries_with_senses_by_reading(&self, reading: &str) -> Result<Vec<EntryParsed>> {
-        // Find all matching entries
-        let entry_rows = sqlx::query_as::<_, EntryRow>(
-            r#"
-            SELECT * FROM entries
-            WHERE EXISTS (
-                SELECT 1 FROM json_each(entries.rebs) je WHERE je.value = ?1
-            )
-            "#,
-        )
-        .bind(reading)
-        .fetch_all(&self.pool)
-        .await?;
+        self.observe("search_entries_with_senses_by_reading", reading, String::new, async {
+            non_empty(reading)?;
+            // Find all matching entries
+            let entry_rows = sqlx::query_as::<_, EntryRow>(SEARCH_BY_READING_SQL)
+                .bind(reading)
+                .fetch_all(&self.pool)
+                .await?;
 
-        let mut results = Vec::new();
-
-        for entry in entry_rows {
-            // Parse rebs and kebs
-            let rebs: Vec<String> = serde_json::from_str(&entry.rebs)?;
-            let kebs: Option<Vec<String>> = match &entry.kebs {
-                Some(s) => Some(serde_json::from_str(s)?),
-                None => None,
-            };
-
-            // Get and parse all senses for this entry
-            let sense_rows = sqlx::query_as::<_, SenseRow>(
-                "SELECT * FROM senses WHERE ent_seq = ? ORDER BY sense_order"
-            )
-            .bind(entry.ent_seq)
-            .fetch_all(&self.pool)
-            .await?;
-
-            let senses = sense_rows
-                .into_iter()
-                .map(|sense| {
-                    Ok(SenseParsed {
-                        sense_order: sense.sense_order,
-                        pos: serde_json::from_str(&sense.pos)?,
-                        xref: serde_json::from_str(&sense.xref)?,
-                        gloss: serde_json::from_str(&sense.gloss)?,
-                    })
-                })
-                .collect::<Result<Vec<_>>>()?;
-
-            results.push(EntryParsed {
-                ent_seq: entry.ent_seq,
-                rebs,
-                kebs,
-                senses,
-            });
-        }
-
-        Ok(results)
+            let mut entries = self.hydrate_entries(entry_rows).await?;
+            mark_applicable(&mut entries, None, Some(reading));
+            Ok(entries)
+        })
+        .await
     }
 
-    /// Searches for entries with their senses by kanji.
+    /// Searches for entries with their senses by kanji, ordered by ent_seq.
+    ///
+    /// Senses restricted (stagk) to other kanji forms are kept, with `applies_to_query` false.
     /// 
     /// # Arguments
     /// * `kanji` - The kanji string to search for.
@@ -231,62 +807,213 @@ impl DictDb {
     /// # Returns
     /// A vector of EntryParsed structs.
     pub async fn search_entries_with_senses_by_kanji(&self, kanji: &str) -> Result<Vec<EntryParsed>> {
+        self.observe("search_entries_with_senses_by_kanji", kanji, String::new, self.entries_by_kanji(kanji))
+            .await
+    }
+
+    /// `search_entries_with_senses_by_kanji` without the timing, for the other searches.
+    async fn entries_by_kanji(&self, kanji: &str) -> Result<Vec<EntryParsed>> {
+        non_empty(kanji)?;
         // Find all matching entries by kanji
-        let entry_rows = sqlx::query_as::<_, EntryRow>(
-            r#"
-            SELECT * FROM entries
-            WHERE kebs IS NOT NULL
-              AND EXISTS (
-                SELECT 1 FROM json_each(entries.kebs) je WHERE je.value = ?1
-              )
-            "#,
-        )
-        .bind(kanji)
-        .fetch_all(&self.pool)
-        .await?;
+        let entry_rows = sqlx::query_as::<_, EntryRow>(SEARCH_BY_KANJI_SQL)
+            .bind(kanji)
+            .fetch_all(&self.pool)
+            .await?;
 
-        let mut results = Vec::new();
+        let mut entries = self.hydrate_entries(entry_rows).await?;
+        mark_applicable(&mut entries, Some(kanji), None);
+        Ok(entries)
+    }
 
-        for entry in entry_rows {
-            // Parse rebs and kebs
-            let rebs: Vec<String> = serde_json::from_str(&entry.rebs)?;
-            let kebs: Option<Vec<String>> = match &entry.kebs {
-                Some(s) => Some(serde_json::from_str(s)?),
-                None => None,
-            };
+    /// Searches for entries by kanji, and by reading if given, keeping only the senses that
+    /// apply to that form, e.g. 頭 read かしら without the senses restricted to あたま.
+    ///
+    /// Entries left without an applicable sense are dropped.
+    pub async fn search_entries_with_applicable_senses(&self, kanji: &str, reading: Option<&str>) -> Result<Vec<EntryParsed>> {
+        let params = || format!("reading={:?}", reading);
+        self.observe("search_entries_with_applicable_senses", kanji, params, async {
+            let mut entries = self.entries_by_kanji(kanji).await?;
+            if let Some(reading) = reading {
+                entries.retain(|e| e.rebs.iter().any(|r| r == reading));
+                mark_applicable(&mut entries, Some(kanji), Some(reading));
+            }
+            for entry in &mut entries {
+                entry.senses.retain(|s| s.applies_to_query);
+            }
+            entries.retain(|e| !e.senses.is_empty());
+            Ok(entries)
+        })
+        .await
+    }
 
-            // Get and parse all senses for this entry
-            let sense_rows = sqlx::query_as::<_, SenseRow>(
-                "SELECT * FROM senses WHERE ent_seq = ? ORDER BY sense_order"
-            )
-            .bind(entry.ent_seq)
-            .fetch_all(&self.pool)
+    /// Fetches a single entry by its ent_seq, None if there is no such entry.
+    pub async fn entry_by_seq(&self, ent_seq: i64) -> Result<Option<EntryParsed>> {
+        let row = sqlx::query_as::<_, EntryRow>("SELECT * FROM entries WHERE ent_seq = ?")
+            .bind(ent_seq)
+            .fetch_optional(&self.pool)
             .await?;
+        match row {
+            Some(row) => Ok(Some(self.hydrate_entry(row).await?)),
+            None => Ok(None),
+        }
+    }
 
-            let senses = sense_rows
-                .into_iter()
-                .map(|sense| {
-                    Ok(SenseParsed {
-                        sense_order: sense.sense_order,
-                        pos: serde_json::from_str(&sense.pos)?,
-                        xref: serde_json::from_str(&sense.xref)?,
-                        gloss: serde_json::from_str(&sense.gloss)?,
-                    })
-                })
-                .collect::<Result<Vec<_>>>()?;
-
-            results.push(EntryParsed {
-                ent_seq: entry.ent_seq,
-                rebs,
-                kebs,
-                senses,
-            });
+    /// Loads the senses of each entry row and parses everything into EntryParsed,
+    /// keeping the order of the rows.
+    ///
+    /// Up to `QueryOptions::hydration_concurrency` rows are loaded at once, each on its own
+    /// connection; the first error drops the loads still running.
+    pub(crate) async fn hydrate_entries(&self, entry_rows: Vec<EntryRow>) -> Result<Vec<EntryParsed>> {
+        let concurrency = SenseLimits::current().concurrency();
+        if concurrency == 1 || entry_rows.len() < 2 {
+            let mut conn = self.pool.acquire().await?;
+            return Self::hydrate_entries_with(&mut conn, entry_rows).await;
         }
+        let mut hydrated: Vec<(usize, EntryParsed)> = stream::iter(entry_rows.into_iter().enumerate())
+            .map(|(i, entry)| async move { Ok::<_, AnyError>((i, self.hydrate_entry(entry).await?)) })
+            .buffer_unordered(concurrency)
+            .try_collect()
+            .await?;
+        hydrated.sort_unstable_by_key(|(i, _)| *i);
+        Ok(hydrated.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Loads the senses of a single entry row and parses it into EntryParsed.
+    pub(crate) async fn hydrate_entry(&self, entry: EntryRow) -> Result<EntryParsed> {
+        let mut conn = self.pool.acquire().await?;
+        Self::hydrate_entry_with(&mut conn, entry).await
+    }
 
+    /// `hydrate_entries` on a given connection, e.g. inside a transaction.
+    pub(crate) async fn hydrate_entries_with(conn: &mut SqliteConnection, entry_rows: Vec<EntryRow>) -> Result<Vec<EntryParsed>> {
+        let mut results = Vec::with_capacity(entry_rows.len());
+        for entry in entry_rows {
+            results.push(Self::hydrate_entry_with(conn, entry).await?);
+        }
         Ok(results)
     }
+
+    /// `hydrate_entry` on a given connection, e.g. inside a transaction.
+    pub(crate) async fn hydrate_entry_with(conn: &mut SqliteConnection, entry: EntryRow) -> Result<EntryParsed> {
+        let mut entry = Self::hydrate_entry_in(conn, "main", entry).await?;
+        if SenseLimits::current().resolve_references {
+            Self::borrow_glosses_with(conn, &mut entry).await?;
+        }
+        Ok(entry)
+    }
+
+    /// Hydrates an entry row read from the database `schema`, "main" or an attached alias.
+    ///
+    /// Entries of an attached database get the alias as their source.
+    pub(crate) async fn hydrate_entry_in(conn: &mut SqliteConnection, schema: &str, entry: EntryRow) -> Result<EntryParsed> {
+        // Parse rebs and kebs
+        let rebs: Vec<String> = serde_json::from_str(&entry.rebs)?;
+        let kebs: Option<Vec<String>> = match &entry.kebs {
+            Some(s) => Some(serde_json::from_str(s)?),
+            None => None,
+        };
+
+        // Get and parse the senses for this entry, all of them unless `with_query_options`
+        // limits them; one more than the limit tells whether there are more
+        let limits = SenseLimits::current();
+        let sense_limit = limits.max_senses.map_or(-1, |max| max as i64 + 1);
+        let mut sense_rows = sqlx::query_as::<_, SenseRow>(&format!(
+            "SELECT * FROM {}.senses WHERE ent_seq = ? ORDER BY sense_order LIMIT ?",
+            schema
+        ))
+        .bind(entry.ent_seq)
+        .bind(sense_limit)
+        .fetch_all(&mut *conn)
+        .await?;
+        let mut senses_truncated = false;
+        if let Some(max) = limits.max_senses.filter(|&max| sense_rows.len() > max) {
+            sense_rows.truncate(max);
+            senses_truncated = true;
+        }
+        let last_order = sense_rows.last().map_or(-1, |s| s.sense_order);
+
+        // Glosses in other languages than English, for all senses at once
+        let other_glosses: Vec<(i64, String, String)> = sqlx::query_as(&format!(
+            "SELECT g.sense_id, g.lang, g.text FROM {0}.glosses g JOIN {0}.senses s ON s.id = g.sense_id
+             WHERE s.ent_seq = ?1 AND g.lang <> ?2 AND s.sense_order <= ?3 ORDER BY g.sense_id, g.rowid",
+            schema
+        ))
+        .bind(entry.ent_seq)
+        .bind(DEFAULT_LANG)
+        .bind(last_order)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let max_glosses = limits.max_glosses_per_sense.unwrap_or(usize::MAX);
+        let senses = sense_rows
+            .into_iter()
+            .map(|sense| {
+                let mut gloss: Vec<String> = serde_json::from_str(&sense.gloss)?;
+                let mut other: Vec<Gloss> = Vec::new();
+                for (_, lang, text) in other_glosses.iter().filter(|(sense_id, _, _)| *sense_id == sense.id) {
+                    if other.iter().filter(|g| &g.lang == lang).count() < max_glosses {
+                        other.push(Gloss { lang: lang.clone(), text: text.clone() });
+                    } else {
+                        senses_truncated = true;
+                    }
+                }
+                if gloss.len() > max_glosses {
+                    gloss.truncate(max_glosses);
+                    senses_truncated = true;
+                }
+                Ok(SenseParsed {
+                    other_glosses: other,
+                    sense_order: sense.sense_order,
+                    pos: serde_json::from_str(&sense.pos)?,
+                    xref: parse_xrefs(&serde_json::from_str::<Vec<String>>(&sense.xref)?)?,
+                    gloss,
+                    stagk: sense.stagk.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                    stagr: sense.stagr.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                    misc: sense.misc.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                    applies_to_query: true,
+                    sense_uid: sense.sense_uid,
+                    pos_inherited: sense.pos_inherited,
+                    misc_inherited: sense.misc_inherited,
+                    field: sense.field.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                    dial: sense.dial.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                    borrowed: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Best rank across all loaded frequency lists
+        let frequency_rank: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT MIN(rank) FROM {}.frequency WHERE ent_seq = ?",
+            schema
+        ))
+        .bind(entry.ent_seq)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let jlpt_level: Option<u8> = sqlx::query_scalar(&format!("SELECT level FROM {}.jlpt WHERE ent_seq = ?", schema))
+            .bind(entry.ent_seq)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        Ok(EntryParsed {
+            ent_seq: entry.ent_seq,
+            rebs,
+            kebs,
+            priority: serde_json::from_str(&entry.priority)?,
+            nf_bucket: entry.nf_bucket.map(|b| b as u8),
+            senses,
+            frequency_rank,
+            jlpt_level,
+            source: if schema == "main" { entry.source } else { schema.to_string() },
+            content_hash: entry.content_hash,
+            senses_truncated,
+            re_restr: entry.re_restr.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+        })
+    }
 }
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
 #[derive(Debug, FromRow)]
@@ -295,6 +1022,14 @@ pub struct EntryRow {
     pub ent_seq: i64,
     pub rebs: String,         // JSON array as string
     pub kebs: Option<String>, // JSON array as string or None
+    pub priority: String,     // JSON array as string
+    #[sqlx(default)]
+    pub nf_bucket: Option<i64>, // None if there is no nfNN tag or the column is missing (attached databases)
+    pub source: String,       // 'jmdict' or 'custom'
+    #[sqlx(default)]
+    pub content_hash: Option<String>, // None for entries stored before hashes were
+    #[sqlx(default)]
+    pub re_restr: Option<String>, // JSON array of arrays as string, None for attached databases without the column
 }
 
 #[derive(Debug, FromRow)]
@@ -306,11 +1041,30 @@ pub struct SenseRow {
     pub pos: String,    // JSON array as string
     pub xref: String,   // JSON array as string
     pub gloss: String,  // JSON array as string
+    #[sqlx(default)]
+    pub stagk: Option<String>, // JSON array as string, None for databases built before restrictions were stored
+    #[sqlx(default)]
+    pub stagr: Option<String>, // JSON array as string, likewise
+    #[sqlx(default)]
+    pub misc: Option<String>, // JSON array as string, None for attached databases built before misc tags were stored
+    #[sqlx(default)]
+    pub sense_uid: Option<String>, // None for senses stored before uids were
+    #[sqlx(default)]
+    pub pos_inherited: bool, // false for senses stored before inheritance was applied
+    #[sqlx(default)]
+    pub misc_inherited: bool,
+    #[sqlx(default)]
+    pub field: Option<String>, // JSON array as string, None for attached databases built before fields were stored
+    #[sqlx(default)]
+    pub dial: Option<String>, // likewise
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 /// Represents a parsed dictionary entry.
+///
+/// Entries are equal, hashed and ordered by `ent_seq` alone, so they can go into a HashSet
+/// or BTreeSet as the entry they are; `content_eq` compares everything else too.
 pub struct EntryParsed {
     /// The entry sequence number. this directly from JMdict.
     pub ent_seq: i64,
@@ -318,19 +1072,373 @@ pub struct EntryParsed {
     pub rebs: Vec<String>,
     /// The kanji for this entry, if available.
     pub kebs: Option<Vec<String>>,
+    /// The priority tags of the kanji and reading elements, e.g. "ichi1", "news2", "nf25".
+    pub priority: Vec<String>,
+    /// The frequency bucket of the best nfNN priority tag: 1 for the 500 most frequent words
+    /// of the newspaper corpus, 2 for the next 500, up to 48.
+    pub nf_bucket: Option<u8>,
     /// The senses for this entry. It contains all the meanings and usages.
     pub senses: Vec<SenseParsed>,
+    /// The best (lowest) rank of this entry across the loaded frequency lists, if any.
+    /// `search_ranked` fills it from the selected list only.
+    pub frequency_rank: Option<i64>,
+    /// The JLPT level, 5 for N5 down to 1 for N1, if a level list was loaded.
+    pub jlpt_level: Option<u8>,
+    /// Where the entry comes from: "jmdict" or "custom" for the main database,
+    /// the alias for an attached database.
+    pub source: String,
+    /// The `content_hash` of the entry as inserted, to tell whether it changed between
+    /// dictionary versions. None for entries stored before hashes were.
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// Whether senses or glosses were left out per `QueryOptions::max_senses` or
+    /// `max_glosses_per_sense`, e.g. to show a "show all" link; `entry_by_seq` outside
+    /// `with_query_options` gives the whole entry.
+    #[serde(default)]
+    pub senses_truncated: bool,
+    /// The kebs each reb is restricted to (re_restr), in the order of `rebs`, empty for a
+    /// reb that goes with every keb. Empty altogether if no reb is restricted, see
+    /// `reading_for`.
+    #[serde(default)]
+    pub re_restr: Vec<Vec<String>>,
 }
 
-#[derive(Debug)]
+impl PartialEq for EntryParsed {
+    fn eq(&self, other: &Self) -> bool {
+        self.ent_seq == other.ent_seq
+    }
+}
+
+impl Eq for EntryParsed {}
+
+impl std::hash::Hash for EntryParsed {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.ent_seq.hash(state);
+    }
+}
+
+impl PartialOrd for EntryParsed {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EntryParsed {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.ent_seq.cmp(&other.ent_seq)
+    }
+}
+
+impl TryFrom<&Entry> for EntryParsed {
+    type Error = AnyError;
+
+    /// Converts a parsed XML entry the same way inserting and searching it would, without
+    /// a database. Frequency rank and JLPT level come from separate lists and are left empty.
+    fn try_from(e: &Entry) -> Result<Self> {
+        let priority = priority_tags(e);
+        let uids = sense_uids(e);
+        let tags = e.inherited_sense_tags();
+        Ok(EntryParsed {
+            ent_seq: e.ent_seq.parse()?,
+            rebs: e.r_ele.iter().map(|r| r.reb.clone()).collect(),
+            kebs: e.k_ele.as_ref().map(|ks| ks.iter().map(|k| k.keb.clone()).collect()),
+            nf_bucket: nf_bucket(&priority),
+            priority: priority.into_iter().map(str::to_string).collect(),
+            senses: e
+                .sense
+                .iter()
+                .zip(uids)
+                .zip(tags)
+                .enumerate()
+                .map(|(i, ((s, sense_uid), tags))| {
+                    Ok(SenseParsed {
+                        sense_order: i as i64,
+                        pos: tags.pos.to_vec(),
+                        xref: parse_xrefs(&s.xref)?,
+                        gloss: s.gloss.clone(),
+                        stagk: s.stagk.clone(),
+                        stagr: s.stagr.clone(),
+                        misc: tags.misc.to_vec(),
+                        other_glosses: s.other_glosses.clone(),
+                        applies_to_query: true,
+                        sense_uid: Some(sense_uid),
+                        pos_inherited: tags.pos_inherited,
+                        misc_inherited: tags.misc_inherited,
+                        field: s.field.clone(),
+                        dial: s.dial.clone(),
+                        borrowed: None,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            frequency_rank: None,
+            jlpt_level: None,
+            source: SOURCE_JMDICT.to_string(),
+            content_hash: Some(content_hash(e)),
+            senses_truncated: false,
+            re_restr: e.reb_restrictions(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 /// Represents a parsed sense entry.
 pub struct SenseParsed {
+    /// The position of the sense in the entry, starting at 0.
     pub sense_order: i64,
-    /// The part of speech for this sense.
+    /// The part of speech for this sense, that of the sense before it if the XML gives
+    /// none, as JMdict means it; see `pos_inherited`.
     pub pos: Vec<String>,
     /// Cross-references for this sense.
-    pub xref: Vec<String>,
-    /// Glosses (meanings) for this sense.
+    pub xref: Vec<Xref>,
+    /// English glosses (meanings) for this sense.
     pub gloss: Vec<String>,
+    /// Glosses in other languages, from multilingual builds; empty for JMdict_e.
+    pub other_glosses: Vec<Gloss>,
+    /// The kebs this sense is restricted to, empty if it applies to all of them.
+    pub stagk: Vec<String>,
+    /// The rebs this sense is restricted to, empty if it applies to all of them.
+    pub stagr: Vec<String>,
+    /// Other information about the sense, e.g. "word usually written using kana alone"
+    /// ("uk"), as the entity values of the JMdict DTD. Inherited like `pos`.
+    #[serde(default)]
+    pub misc: Vec<String>,
+    /// Whether the sense applies to the searched kanji or reading, per `stagk`/`stagr`.
+    /// Always true for lookups that don't search a form.
+    pub applies_to_query: bool,
+    /// The identifier of the sense that stays the same across rebuilds, see `sense_uids`.
+    /// None for senses stored before uids were.
+    #[serde(default)]
+    pub sense_uid: Option<String>,
+    /// Whether `pos` comes from an earlier sense, the XML having no `<pos>` for this one.
+    /// Always false for senses stored before inheritance was applied.
+    #[serde(default)]
+    pub pos_inherited: bool,
+    /// Whether `misc` comes from an earlier sense.
+    #[serde(default)]
+    pub misc_inherited: bool,
+    /// The fields of application, e.g. "computing", as the entity values of the DTD.
+    #[serde(default)]
+    pub field: Vec<String>,
+    /// The dialects, e.g. "Kansai-ben".
+    #[serde(default)]
+    pub dial: Vec<String>,
+    /// For a sense with only a cross-reference, the glosses of the sense it points to, with
+    /// `QueryOptions::resolve_references`. The sense's own `gloss` stays empty.
+    #[serde(default)]
+    pub borrowed: Option<BorrowedGlosses>,
+}
+
+impl SenseParsed {
+    /// The part of speech as the XML gives it, empty for a sense that inherits it.
+    pub fn literal_pos(&self) -> &[String] {
+        if self.pos_inherited {
+            &[]
+        } else {
+            &self.pos
+        }
+    }
+
+    /// The misc tags as the XML gives them, likewise.
+    pub fn literal_misc(&self) -> &[String] {
+        if self.misc_inherited {
+            &[]
+        } else {
+            &self.misc
+        }
+    }
+
+    /// The cross-references as JMdict writes them, e.g. "御茶・おちゃ・2".
+    pub fn xref_raw(&self) -> Vec<String> {
+        self.xref.iter().map(Xref::to_string).collect()
+    }
+
+    /// The glosses in `lang` (ISO 639-2, e.g. "ger"), "eng" for the English ones.
+    pub fn glosses_for(&self, lang: &str) -> Vec<&str> {
+        if lang == DEFAULT_LANG {
+            return self.gloss.iter().map(String::as_str).collect();
+        }
+        self.other_glosses.iter().filter(|g| g.lang == lang).map(|g| g.text.as_str()).collect()
+    }
+
+    /// The English glosses if there are any, otherwise those of the first other language.
+    pub fn default_glosses(&self) -> Vec<&str> {
+        match self.other_glosses.first() {
+            Some(first) if self.gloss.is_empty() => self.glosses_for(&first.lang),
+            _ => self.glosses_for(DEFAULT_LANG),
+        }
+    }
+
+    /// Returns true if the sense has a gloss in `lang` (ISO 639-2), "eng" for English.
+    pub fn has_lang(&self, lang: &str) -> bool {
+        if lang == DEFAULT_LANG {
+            return !self.gloss.is_empty();
+        }
+        self.other_glosses.iter().any(|g| g.lang == lang)
+    }
+
+    /// The languages this sense has glosses in, English first if present.
+    pub fn languages(&self) -> Vec<&str> {
+        let mut langs: Vec<&str> = Vec::new();
+        if !self.gloss.is_empty() {
+            langs.push(DEFAULT_LANG);
+        }
+        for g in &self.other_glosses {
+            if !langs.contains(&g.lang.as_str()) {
+                langs.push(&g.lang);
+            }
+        }
+        langs
+    }
+
+    /// Returns true if the sense is not restricted away from the given keb and/or reb.
+    pub fn applies_to(&self, keb: Option<&str>, reb: Option<&str>) -> bool {
+        let keb_ok = keb.is_none_or(|k| self.stagk.is_empty() || self.stagk.iter().any(|s| s == k));
+        let reb_ok = reb.is_none_or(|r| self.stagr.is_empty() || self.stagr.iter().any(|s| s == r));
+        keb_ok && reb_ok
+    }
+
+    /// Returns true if the sense is tagged "uk", usually written using kana alone.
+    pub fn is_usually_kana(&self) -> bool {
+        self.misc.iter().any(|m| m == USUALLY_KANA_CODE || m == USUALLY_KANA)
+    }
+
+    /// Returns true if the sense is an expression, part of speech "exp", e.g. a proverb.
+    pub fn is_expression(&self) -> bool {
+        self.pos.iter().any(|p| PartOfSpeech::parse(p) == PartOfSpeech::Exp)
+    }
+
+    /// Returns true if the sense has no gloss in any language, only cross-references: JMdict
+    /// then means "see" the referenced word.
+    pub fn is_reference_only(&self) -> bool {
+        self.gloss.is_empty() && self.other_glosses.is_empty() && !self.xref.is_empty()
+    }
+
+    /// The first `max_glosses` of `default_glosses` joined with "; ", ending in "; …" if
+    /// some were left out, e.g. "to eat; to live on (e.g. a salary); …".
+    ///
+    /// A reference-only sense reads "→ see 御飯・ごはん・1", followed by the `borrowed`
+    /// glosses in brackets if they were resolved: "→ see 御飯・ごはん・1 (cooked rice)".
+    pub fn summary(&self, max_glosses: usize) -> String {
+        if self.is_reference_only() {
+            let see = format!("{} see {}", REFERENCE_ARROW, self.xref_raw().join(", "));
+            return match &self.borrowed {
+                Some(borrowed) => format!("{} ({})", see, borrowed.summary(max_glosses)),
+                None => see,
+            };
+        }
+        let glosses = self.default_glosses();
+        let mut summary = glosses.iter().take(max_glosses).copied().collect::<Vec<_>>().join("; ");
+        if glosses.len() > max_glosses {
+            if !summary.is_empty() {
+                summary.push_str("; ");
+            }
+            summary.push_str(SUMMARY_ELLIPSIS);
+        }
+        summary
+    }
+}
+
+/// Appended by the summaries when they leave something out.
+pub const SUMMARY_ELLIPSIS: &str = "…";
+
+/// Starts the summary of a reference-only sense, see `SenseParsed::summary`.
+pub const REFERENCE_ARROW: &str = "→";
+
+/// `s` cut to at most `max_chars` characters, the last of them "…" if anything was cut.
+pub(crate) fn truncate_chars(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+    // Cutting at a char boundary never splits a multi-byte character
+    let end = s.char_indices().nth(max_chars - 1).map_or(s.len(), |(i, _)| i);
+    let kept = s[..end].trim_end_matches([' ', ';', ',']);
+    format!("{}{}", kept, SUMMARY_ELLIPSIS)
+}
+
+/// The "uk" misc tag as the JMdict DTD expands it.
+pub const USUALLY_KANA: &str = "word usually written using kana alone";
+
+/// The "uk" misc tag unexpanded, as entries parsed without the DTD store it.
+pub const USUALLY_KANA_CODE: &str = "uk";
+
+impl EntryParsed {
+    /// Compares the whole entries, senses and all, where `==` only compares ent_seq, e.g.
+    /// to tell whether an entry changed between two reads.
+    pub fn content_eq(&self, other: &Self) -> bool {
+        let EntryParsed {
+            ent_seq,
+            rebs,
+            kebs,
+            priority,
+            nf_bucket,
+            senses,
+            frequency_rank,
+            jlpt_level,
+            source,
+            content_hash,
+            senses_truncated,
+            re_restr,
+        } = self;
+        *ent_seq == other.ent_seq
+            && *rebs == other.rebs
+            && *kebs == other.kebs
+            && *priority == other.priority
+            && *nf_bucket == other.nf_bucket
+            && *senses == other.senses
+            && *frequency_rank == other.frequency_rank
+            && *jlpt_level == other.jlpt_level
+            && *source == other.source
+            && *content_hash == other.content_hash
+            && *senses_truncated == other.senses_truncated
+            && *re_restr == other.re_restr
+    }
+
+    /// Returns true if the entry has no kanji forms, e.g. する.
+    pub fn is_kana_only(&self) -> bool {
+        self.kebs.as_ref().is_none_or(|kebs| kebs.is_empty())
+    }
+
+    /// Returns true if the first sense is tagged "uk", e.g. 矢張り, which is written やはり.
+    pub fn is_usually_kana(&self) -> bool {
+        self.senses.first().is_some_and(SenseParsed::is_usually_kana)
+    }
+
+    /// Returns true if a sense is an expression, e.g. 猿も木から落ちる or よろしくお願いします.
+    pub fn is_expression(&self) -> bool {
+        self.senses.iter().any(SenseParsed::is_expression)
+    }
+
+    /// The form to show as the headword: the first reading for kana-only and "usually kana"
+    /// entries, the first keb otherwise.
+    pub fn display_form(&self) -> &str {
+        let reading = self.rebs.first().map_or("", String::as_str);
+        if self.is_usually_kana() {
+            return reading;
+        }
+        match self.kebs.as_ref().and_then(|kebs| kebs.first()) {
+            Some(keb) => keb,
+            None => reading,
+        }
+    }
+
+    /// All glosses on one line of at most `max_chars` characters, for result lists, e.g.
+    /// "(1) to eat (2) to live on (e.g. a salary); to live off; …". Senses are numbered from 1
+    /// when there are several, and the line ends in "…" if it had to be cut.
+    pub fn summary(&self, max_chars: usize) -> String {
+        let senses: Vec<(i64, String)> = self
+            .senses
+            .iter()
+            .map(|s| (s.sense_order, s.summary(usize::MAX)))
+            .filter(|(_, glosses)| !glosses.is_empty())
+            .collect();
+        let line = match senses.as_slice() {
+            [(_, only)] => only.clone(),
+            _ => senses.iter().map(|(order, glosses)| format!("({}) {}", order + 1, glosses)).collect::<Vec<_>>().join(" "),
+        };
+        truncate_chars(&line, max_chars)
+    }
 }
 
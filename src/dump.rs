@@ -0,0 +1,202 @@
+use sqlx::{Connection, Row, SqliteConnection};
+use std::io::{BufRead, Write};
+
+use crate::{DictDb, Result};
+
+/// The first line of every dump.
+pub const SQL_DUMP_HEADER: &str = "-- jmdictdb SQL dump";
+
+/// Statements executed together by `restore_sql`.
+const RESTORE_BATCH_STATEMENTS: usize = 500;
+
+/// Quotes an identifier for SQL.
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// The CREATE statement of a schema object on one line: comments dropped, line breaks
+/// turned into spaces and `IF NOT EXISTS` added, so a dump also restores over the schema
+/// `DictDb::connect` creates.
+fn one_line_create(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut quote: Option<char> = None;
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => {
+                quote = None;
+                out.push(c);
+            }
+            (Some(_), _) => out.push(c),
+            (None, '\'' | '"' | '`') => {
+                quote = Some(c);
+                out.push(c);
+            }
+            (None, '[') => {
+                quote = Some(']');
+                out.push(c);
+            }
+            (None, '-') if chars.peek() == Some(&'-') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            (None, '\n' | '\r' | '\t') => out.push(' '),
+            (None, _) => out.push(c),
+        }
+    }
+    let mut words: Vec<&str> = out.split(' ').filter(|w| !w.is_empty()).collect();
+    let object = if words.get(1).is_some_and(|w| w.eq_ignore_ascii_case("UNIQUE")) { 2 } else { 1 };
+    if !words.get(object + 1).is_some_and(|w| w.eq_ignore_ascii_case("IF")) {
+        words.splice(object + 1..object + 1, ["IF", "NOT", "EXISTS"]);
+    }
+    words.join(" ")
+}
+
+/// The SQL literal of the column: `quote()`, except for text with line breaks, written as
+/// hex so that every statement stays on one line.
+fn literal_expr(column: &str) -> String {
+    let c = quote_ident(column);
+    format!(
+        "CASE WHEN typeof({c}) = 'text' AND (instr({c}, char(10)) OR instr({c}, char(13))) \
+         THEN 'CAST(X''' || hex({c}) || ''' AS TEXT)' ELSE quote({c}) END"
+    )
+}
+
+/// The user tables and indexes of the main database with their SQL, ordered by name.
+async fn schema_objects(conn: &mut SqliteConnection, kind: &str) -> Result<Vec<(String, String)>> {
+    Ok(sqlx::query_as(
+        "SELECT name, sql FROM sqlite_master WHERE type = ?1 AND sql IS NOT NULL AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )
+    .bind(kind)
+    .fetch_all(&mut *conn)
+    .await?)
+}
+
+/// The columns of a table ordered by name, so that columns added by migrations don't change
+/// the dump, and its primary key columns in key order.
+async fn table_columns(conn: &mut SqliteConnection, table: &str) -> Result<(Vec<String>, Vec<String>)> {
+    let rows: Vec<(String, i64)> = sqlx::query_as("SELECT name, pk FROM pragma_table_info(?1) ORDER BY name")
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await?;
+    let mut pk: Vec<&(String, i64)> = rows.iter().filter(|(_, pk)| *pk > 0).collect();
+    pk.sort_by_key(|(_, pk)| *pk);
+    let pk = pk.into_iter().map(|(name, _)| name.clone()).collect();
+    Ok((rows.into_iter().map(|(name, _)| name).collect(), pk))
+}
+
+/// Restores the pragmas `restore_sql` changed, whether or not the restore worked.
+async fn reset_pragmas(conn: &mut SqliteConnection, foreign_keys: i64, synchronous: i64) -> Result<()> {
+    sqlx::query(&format!("PRAGMA foreign_keys = {}", foreign_keys)).execute(&mut *conn).await?;
+    sqlx::query(&format!("PRAGMA synchronous = {}", synchronous)).execute(&mut *conn).await?;
+    Ok(())
+}
+
+impl DictDb {
+    /// Writes the whole database as SQL text: the tables, one INSERT per row, then the
+    /// indexes, one statement per line.
+    ///
+    /// The output only depends on the data: tables, indexes and columns are ordered by name
+    /// and rows by primary key (all columns for tables without one), and the dump has no
+    /// timestamp. Two dumps of the same data are byte-identical, so releases can be diffed;
+    /// only the CREATE statements show how the schema was built up.
+    ///
+    /// # Returns
+    /// The number of rows written.
+    pub async fn dump_sql<W: Write>(&self, mut writer: W) -> Result<usize> {
+        let mut conn = self.pool.acquire().await?;
+        // One snapshot for all tables
+        let mut tx = conn.begin().await?;
+        writeln!(writer, "{}", SQL_DUMP_HEADER)?;
+        let tables = schema_objects(&mut tx, "table").await?;
+        for (_, sql) in &tables {
+            writeln!(writer, "{};", one_line_create(sql))?;
+        }
+        let mut rows = 0;
+        for (table, _) in &tables {
+            let (columns, pk) = table_columns(&mut tx, table).await?;
+            let order = if pk.is_empty() { &columns } else { &pk };
+            let select = format!(
+                "SELECT 'INSERT INTO {} ({}) VALUES (' || {} || ');' FROM {} ORDER BY {}",
+                quote_ident(table).replace('\'', "''"),
+                columns.iter().map(|c| quote_ident(c)).collect::<Vec<_>>().join(", ").replace('\'', "''"),
+                columns.iter().map(|c| literal_expr(c)).collect::<Vec<_>>().join(" || ', ' || "),
+                quote_ident(table),
+                order.iter().map(|c| quote_ident(c)).collect::<Vec<_>>().join(", "),
+            );
+            for row in sqlx::query(&select).fetch_all(&mut *tx).await? {
+                writeln!(writer, "{}", row.try_get::<String, _>(0)?)?;
+                rows += 1;
+            }
+        }
+        for (_, sql) in schema_objects(&mut tx, "index").await? {
+            writeln!(writer, "{};", one_line_create(&sql))?;
+        }
+        tx.commit().await?;
+        writer.flush()?;
+        Ok(rows)
+    }
+
+    /// Replaces the contents of the database with a `dump_sql` dump, in one transaction.
+    ///
+    /// The rows of every table are deleted first. Foreign keys are checked once at the end
+    /// rather than per row, and the commit doesn't wait for the disk (`synchronous = OFF`);
+    /// both pragmas are set back afterwards. A dump of an older version of the crate restores
+    /// too, columns it lacks get their defaults.
+    ///
+    /// # Returns
+    /// The number of rows restored.
+    pub async fn restore_sql<R: BufRead>(&self, reader: R) -> Result<usize> {
+        let mut conn = self.pool.acquire().await?;
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(&mut *conn).await?;
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous").fetch_one(&mut *conn).await?;
+        // Neither can change inside the transaction
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await?;
+        sqlx::query("PRAGMA synchronous = OFF").execute(&mut *conn).await?;
+        let restored = restore(&mut conn, reader).await;
+        self.entry_cache.clear();
+        reset_pragmas(&mut conn, foreign_keys, synchronous).await?;
+        restored
+    }
+}
+
+/// The transaction of `restore_sql`.
+async fn restore<R: BufRead>(conn: &mut SqliteConnection, reader: R) -> Result<usize> {
+    let mut tx = conn.begin().await?;
+    let mut lines = reader.lines();
+    match lines.next().transpose()? {
+        Some(header) if header == SQL_DUMP_HEADER => {}
+        _ => return Err("not a jmdictdb SQL dump: the header line is missing".into()),
+    }
+    for (table, _) in schema_objects(&mut tx, "table").await? {
+        sqlx::query(&format!("DELETE FROM {}", quote_ident(&table))).execute(&mut *tx).await?;
+    }
+    let mut rows = 0;
+    let mut batch = String::new();
+    let mut in_batch = 0;
+    for line in lines {
+        let line = line?;
+        if line.is_empty() || line.starts_with("--") {
+            continue;
+        }
+        if line.starts_with("INSERT ") {
+            rows += 1;
+        }
+        batch.push_str(&line);
+        batch.push('\n');
+        in_batch += 1;
+        if in_batch == RESTORE_BATCH_STATEMENTS {
+            sqlx::raw_sql(&batch).execute(&mut *tx).await?;
+            batch.clear();
+            in_batch = 0;
+        }
+    }
+    if !batch.is_empty() {
+        sqlx::raw_sql(&batch).execute(&mut *tx).await?;
+    }
+    let violations: Vec<(String,)> = sqlx::query_as("SELECT \"table\" FROM pragma_foreign_key_check").fetch_all(&mut *tx).await?;
+    if let Some((table,)) = violations.first() {
+        return Err(format!("the dump breaks {} foreign key(s), e.g. in {}", violations.len(), table).into());
+    }
+    tx.commit().await?;
+    Ok(rows)
+}
@@ -0,0 +1,47 @@
+//! The entities of the JMdict DTD, e.g. `adj-i` for "adjective (keiyoushi)".
+//!
+//! The build expands them with `parse_dtd_entities` of the file's own DTD; `STANDARD` has
+//! the current set built in, to show "adj-i" as "adjective (keiyoushi)" without a database.
+
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+// Captures the entity name (e.g. "n") and its value (e.g. "noun") of <!ENTITY name "value">
+static ENTITY_DECL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"<!ENTITY\s+([^\s]+)\s+"([^"]*)">"#).unwrap());
+
+/// The entity declarations of the current JMdict DTD, see `STANDARD`.
+pub const STANDARD_DTD: &str = include_str!("jmdict_entities.dtd");
+
+/// The entities of the current JMdict DTD by name, `parse_dtd_entities` of `STANDARD_DTD`:
+/// part-of-speech, field, dialect, misc and kanji and reading info tags.
+///
+/// Databases store the values, so this is for showing tags outside of one, e.g. as tooltips.
+/// ```
+/// assert_eq!(jmdictdb::entities::STANDARD["adj-i"], "adjective (keiyoushi)");
+/// ```
+pub static STANDARD: LazyLock<BTreeMap<&'static str, &'static str>> = LazyLock::new(|| declarations(STANDARD_DTD).collect());
+
+/// The (name, value) of every <!ENTITY> declaration, in document order.
+pub(crate) fn declarations(dtd: &str) -> impl Iterator<Item = (&str, &str)> {
+    ENTITY_DECL_RE.captures_iter(dtd).map(|cap| {
+        let (_, [name, value]) = cap.extract();
+        (name, value)
+    })
+}
+
+/// Reads the <!ENTITY> definitions of a DTD, or of a JMdict file with its DTD, into a map
+/// from name to value. A name declared twice keeps its first value, as in XML.
+pub fn parse_dtd_entities(dtd: &str) -> BTreeMap<String, String> {
+    let mut entities = BTreeMap::new();
+    for (name, value) in declarations(dtd) {
+        entities.entry(name.to_string()).or_insert_with(|| value.to_string());
+    }
+    entities
+}
+
+/// The name of the entity with this value in `STANDARD`, e.g. "adj-i" for "adjective
+/// (keiyoushi)", the first by name if several have it.
+pub fn standard_name(value: &str) -> Option<&'static str> {
+    STANDARD.iter().find(|(_, v)| **v == value).map(|(name, _)| *name)
+}
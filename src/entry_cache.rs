@@ -0,0 +1,148 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::timeout::SenseLimits;
+use crate::{DictDb, EntryParsed, Result};
+
+/// The use of the entry cache of `DictDbOptions::entry_cache`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EntryCacheStats {
+    /// Entries held.
+    pub entries: usize,
+    /// The most entries held, 0 when the cache is off.
+    pub capacity: usize,
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups that read the entry from the database.
+    pub misses: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// The entries with the tick of their last use.
+    entries: HashMap<i64, (Arc<EntryParsed>, u64)>,
+    /// The ent_seqs by the tick of their last use, least recently used first.
+    by_use: BTreeMap<u64, i64>,
+    tick: u64,
+    /// Bumped by every clear, so that an entry read before a write isn't stored after it.
+    epoch: u64,
+    hits: u64,
+    misses: u64,
+}
+
+/// The least recently used entries of `DictDb::entry_shared`, shared with the callers.
+#[derive(Debug, Default)]
+pub(crate) struct EntryCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl EntryCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self { capacity, state: Mutex::default() }
+    }
+
+    /// The cached entry, now the most recently used, or the epoch to `insert` it with.
+    fn get(&self, ent_seq: i64) -> std::result::Result<Arc<EntryParsed>, u64> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let CacheState { entries, by_use, .. } = &mut *state;
+        match entries.get_mut(&ent_seq) {
+            Some((entry, used)) => {
+                let entry = entry.clone();
+                by_use.remove(used);
+                by_use.insert(tick, ent_seq);
+                *used = tick;
+                state.hits += 1;
+                Ok(entry)
+            }
+            None => {
+                state.misses += 1;
+                Err(state.epoch)
+            }
+        }
+    }
+
+    /// Stores an entry read at `epoch`, evicting the least recently used one when full.
+    fn insert(&self, epoch: u64, entry: Arc<EntryParsed>) {
+        let mut state = self.state.lock().unwrap();
+        if self.capacity == 0 || state.epoch != epoch {
+            return;
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        let ent_seq = entry.ent_seq;
+        if let Some((_, used)) = state.entries.insert(ent_seq, (entry, tick)) {
+            // Another lookup of the same entry got there first
+            state.by_use.remove(&used);
+        }
+        state.by_use.insert(tick, ent_seq);
+        while state.entries.len() > self.capacity {
+            let Some((_, oldest)) = state.by_use.pop_first() else { break };
+            state.entries.remove(&oldest);
+        }
+    }
+
+    /// Drops every entry, after a write that may have changed them.
+    pub(crate) fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.by_use.clear();
+        state.epoch += 1;
+    }
+
+    fn stats(&self) -> EntryCacheStats {
+        let state = self.state.lock().unwrap();
+        EntryCacheStats { entries: state.entries.len(), capacity: self.capacity, hits: state.hits, misses: state.misses }
+    }
+}
+
+impl DictDb {
+    /// `entry_by_seq` through the entry cache of `DictDbOptions::entry_cache`: a word
+    /// looked up again and again is the same `Arc` instead of another query and a fresh
+    /// copy of its strings. For an entry of your own to change, take
+    /// `Arc::unwrap_or_clone(entry)` or `(*entry).clone()`.
+    ///
+    /// Writes through this DictDb that change entries clear the cache; after writes by
+    /// another process, call `clear_entry_cache`. Inside `with_query_options` with sense or
+    /// gloss limits or `resolve_references` the entry differs from the stored one, so it is
+    /// read from the database and not cached. Without a cache every lookup is read too.
+    pub async fn entry_shared(&self, ent_seq: i64) -> Result<Option<Arc<EntryParsed>>> {
+        if !SenseLimits::current().whole_entries() {
+            return Ok(self.entry_by_seq(ent_seq).await?.map(Arc::new));
+        }
+        let epoch = match self.entry_cache.get(ent_seq) {
+            Ok(entry) => return Ok(Some(entry)),
+            Err(epoch) => epoch,
+        };
+        let Some(entry) = self.entry_by_seq(ent_seq).await?.map(Arc::new) else {
+            return Ok(None);
+        };
+        self.entry_cache.insert(epoch, entry.clone());
+        Ok(Some(entry))
+    }
+
+    /// `with_retry` for a write that may change entries, clearing the entry cache once it
+    /// succeeded.
+    pub(crate) async fn write_entries<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let done = self.with_retry(op).await?;
+        self.entry_cache.clear();
+        Ok(done)
+    }
+
+    /// Drops the entries of the entry cache, e.g. after another process wrote to the
+    /// database.
+    pub fn clear_entry_cache(&self) {
+        self.entry_cache.clear();
+    }
+
+    /// The size and hit rate of the entry cache.
+    pub fn entry_cache_stats(&self) -> EntryCacheStats {
+        self.entry_cache.stats()
+    }
+}
@@ -0,0 +1,325 @@
+//! Exporting the dictionary for e-readers, see `DictDb::export_ereader`: Kobo's dicthtml zip
+//! and the source of a Kindle dictionary.
+//!
+//! Both index the conjugated forms of verbs and adjectives from `EntryParsed::inflected_forms`,
+//! so looking up 食べなかった on the device finds 食べる.
+
+use crc::{Crc, CRC_32_ISO_HDLC};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::annotate::escape_html;
+use crate::stardict::stardict_article;
+use crate::{DictDb, EntryParsed, Result, SearchFilter};
+
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// The most inflected forms one Kindle entry may have; kindlegen rejects entries with more,
+/// so the rest are left out.
+pub const MAX_KINDLE_IFORMS: usize = 255;
+
+/// The namespaces kindlegen expects on the content files of a dictionary.
+const KINDLE_NAMESPACES: &str = r#"xmlns:mbp="https://kindlegen.s3.amazonaws.com/AmazonKindlePublishingGuidelines.pdf" xmlns:idx="https://kindlegen.s3.amazonaws.com/AmazonKindlePublishingGuidelines.pdf""#;
+
+/// An e-reader format for `DictDb::export_ereader`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EreaderFormat {
+    /// A Kobo dicthtml zip: the entries in gzipped HTML files named after the first two
+    /// characters of their words. It has no `words` file, the marisa trie of the words that
+    /// Kobo's own dictionaries carry; tools such as dictutil can add one.
+    Kobo,
+    /// The source of a Kindle dictionary: an OPF package and XHTML content files with an
+    /// `idx:infl` inflection index, for Kindle Previewer or kindlegen to build into a MOBI.
+    KindleSource,
+}
+
+impl EreaderFormat {
+    /// Parses "kobo" or "kindle-src".
+    pub fn parse(s: &str) -> Option<EreaderFormat> {
+        match s.to_ascii_lowercase().as_str() {
+            "kobo" => Some(EreaderFormat::Kobo),
+            "kindle-src" => Some(EreaderFormat::KindleSource),
+            _ => None,
+        }
+    }
+
+    /// Where the export goes if not told otherwise: "dicthtml-ja-en.zip", the name Kobo
+    /// looks for, or the directory "jmdict-kindle".
+    pub fn default_path(self) -> &'static str {
+        match self {
+            EreaderFormat::Kobo => "dicthtml-ja-en.zip",
+            EreaderFormat::KindleSource => "jmdict-kindle",
+        }
+    }
+}
+
+/// Options for `DictDb::export_ereader`.
+#[derive(Debug, Clone)]
+pub struct EreaderOptions {
+    /// The title of the Kindle dictionary, "JMdict" by default.
+    pub title: String,
+    /// The language of the glosses, for the Kindle's DictionaryOutLanguage, "en" by default.
+    pub gloss_lang: String,
+    /// Restrictions on the entries exported.
+    pub filter: SearchFilter,
+}
+
+impl Default for EreaderOptions {
+    fn default() -> Self {
+        Self { title: "JMdict".to_string(), gloss_lang: "en".to_string(), filter: SearchFilter::default() }
+    }
+}
+
+/// What `DictDb::export_ereader` wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EreaderReport {
+    /// The zip file, or the directory of the Kindle source.
+    pub path: PathBuf,
+    /// The entries exported.
+    pub entries: usize,
+    /// The HTML files: in the zip for Kobo, next to the OPF for Kindle.
+    pub files: usize,
+    /// The inflected forms indexed, over all entries.
+    pub inflections: usize,
+}
+
+/// The file a word goes in for Kobo: its first two characters in lower case, with "a"
+/// added to a single character, or "11" if one of them is not a letter, as Kobo computes it.
+pub fn kobo_prefix(word: &str) -> String {
+    let word = word.trim().to_lowercase();
+    let mut prefix: String = word.chars().take(2).collect();
+    match prefix.chars().count() {
+        0 => return "11".to_string(),
+        1 => prefix.push('a'),
+        _ => {}
+    }
+    if prefix.chars().all(char::is_alphabetic) { prefix } else { "11".to_string() }
+}
+
+/// The other kebs and rebs of an entry, then its inflected forms, for the entry's
+/// display form to be found by.
+fn variants(entry: &EntryParsed, inflections: &[String]) -> Vec<String> {
+    let form = entry.display_form();
+    let others = entry.kebs.iter().flatten().chain(&entry.rebs).filter(|f| *f != form);
+    let mut variants: Vec<String> = Vec::new();
+    for f in others.chain(inflections) {
+        if !variants.contains(f) {
+            variants.push(f.clone());
+        }
+    }
+    variants
+}
+
+impl DictDb {
+    /// Writes the entries for an e-reader to `path`, a zip file for `EreaderFormat::Kobo`,
+    /// a directory for `EreaderFormat::KindleSource`, replacing what is there. Articles are
+    /// the HTML of `stardict_article`.
+    ///
+    /// The Kindle source is written a few hundred entries per file; the Kobo zip is built in
+    /// memory, its files grouping the entries by the first characters of their words.
+    pub async fn export_ereader(&self, format: EreaderFormat, path: impl AsRef<Path>, opts: &EreaderOptions) -> Result<EreaderReport> {
+        match format {
+            EreaderFormat::Kobo => self.export_kobo(path.as_ref(), opts).await,
+            EreaderFormat::KindleSource => self.export_kindle_source(path.as_ref(), opts).await,
+        }
+    }
+
+    async fn export_kobo(&self, path: &Path, opts: &EreaderOptions) -> Result<EreaderReport> {
+        let mut files: BTreeMap<String, String> = BTreeMap::new();
+        let (mut entries, mut inflections, mut after) = (0, 0, i64::MIN);
+        loop {
+            let batch = self.entries_after(&opts.filter, after).await?;
+            let Some(last) = batch.last() else { break };
+            after = last.ent_seq;
+            for entry in &batch {
+                let inflected = entry.inflected_forms();
+                let variants = variants(entry, &inflected);
+                let headword = entry.display_form();
+                let mut w = format!("<w><a name=\"{}\" />", escape_html(headword));
+                if !variants.is_empty() {
+                    w.push_str("<var>");
+                    for v in &variants {
+                        w.push_str(&format!("<variant name=\"{}\"/>", escape_html(v)));
+                    }
+                    w.push_str("</var>");
+                }
+                w.push_str(&stardict_article(entry));
+                w.push_str("</w>");
+                // Kobo only looks in the file of the word looked up
+                let prefixes: BTreeSet<String> = std::iter::once(headword).chain(variants.iter().map(String::as_str)).map(kobo_prefix).collect();
+                for prefix in prefixes {
+                    files.entry(prefix).or_default().push_str(&w);
+                }
+                entries += 1;
+                inflections += inflected.len();
+            }
+        }
+
+        let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+        for (prefix, body) in &files {
+            zip.add(&format!("{}.html", prefix), &gzip_stored(format!("<html>{}</html>", body).as_bytes()))?;
+        }
+        zip.finish()?.flush()?;
+        Ok(EreaderReport { path: path.to_path_buf(), entries, files: files.len(), inflections })
+    }
+
+    async fn export_kindle_source(&self, dir: &Path, opts: &EreaderOptions) -> Result<EreaderReport> {
+        std::fs::create_dir_all(dir)?;
+        let mut names = Vec::new();
+        let (mut entries, mut inflections, mut after) = (0, 0, i64::MIN);
+        loop {
+            let batch = self.entries_after(&opts.filter, after).await?;
+            let Some(last) = batch.last() else { break };
+            after = last.ent_seq;
+            let name = format!("content-{}.html", names.len() + 1);
+            let mut out = BufWriter::new(File::create(dir.join(&name))?);
+            write!(
+                out,
+                "<html {}>\n<head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=utf-8\"/></head>\n<body>\n<mbp:frameset>\n",
+                KINDLE_NAMESPACES
+            )?;
+            for entry in &batch {
+                let inflected = entry.inflected_forms();
+                let variants = variants(entry, &inflected);
+                write!(out, "<idx:entry name=\"japanese\" scriptable=\"yes\" spell=\"yes\">\n<idx:orth value=\"{}\">", escape_html(entry.display_form()))?;
+                if !variants.is_empty() {
+                    out.write_all(b"<idx:infl>")?;
+                    for v in variants.iter().take(MAX_KINDLE_IFORMS) {
+                        write!(out, "<idx:iform value=\"{}\"/>", escape_html(v))?;
+                    }
+                    out.write_all(b"</idx:infl>")?;
+                }
+                write!(out, "</idx:orth>\n{}\n</idx:entry>\n<hr/>\n", stardict_article(entry))?;
+                entries += 1;
+                inflections += inflected.len();
+            }
+            out.write_all(b"</mbp:frameset>\n</body>\n</html>\n")?;
+            out.flush()?;
+            names.push(name);
+        }
+
+        let items: String = names.iter().map(|n| format!("    <item id=\"{0}\" href=\"{0}\" media-type=\"application/xhtml+xml\"/>\n", n)).collect();
+        let itemrefs: String = names.iter().map(|n| format!("    <itemref idref=\"{}\"/>\n", n)).collect();
+        let opf = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<package unique-identifier="uid" version="2.0" xmlns="http://www.idpf.org/2007/opf">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>{title}</dc:title>
+    <dc:language>ja</dc:language>
+    <dc:identifier id="uid">jmdict</dc:identifier>
+    <dc:creator>Electronic Dictionary Research and Development Group</dc:creator>
+    <x-metadata>
+      <DictionaryInLanguage>ja</DictionaryInLanguage>
+      <DictionaryOutLanguage>{lang}</DictionaryOutLanguage>
+      <DefaultLookupIndex>japanese</DefaultLookupIndex>
+    </x-metadata>
+  </metadata>
+  <manifest>
+{items}  </manifest>
+  <spine>
+{itemrefs}  </spine>
+</package>
+"#,
+            title = escape_html(&opts.title),
+            lang = escape_html(&opts.gloss_lang),
+        );
+        std::fs::write(dir.join("jmdict.opf"), opf)?;
+        Ok(EreaderReport { path: dir.to_path_buf(), entries, files: names.len(), inflections })
+    }
+}
+
+/// `data` as a gzip file of stored (uncompressed) deflate blocks, which every reader
+/// accepts without the crate needing a compressor.
+fn gzip_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    let mut chunks = data.chunks(u16::MAX as usize).peekable();
+    if data.is_empty() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        out.push(if chunks.peek().is_none() { 1 } else { 0 });
+        let len = chunk.len() as u16;
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(chunk);
+    }
+    out.extend(CRC32.checksum(data).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Writes a zip archive of stored files with UTF-8 names.
+struct ZipWriter<W: Write> {
+    out: W,
+    offset: u64,
+    central: Vec<u8>,
+    count: usize,
+}
+
+/// Version 2.0 of the zip format, general purpose flag 11 for UTF-8 names, and the date of
+/// every file, the earliest zip can express (1980-01-01) so the same data gives the same zip.
+const ZIP_VERSION: u16 = 20;
+const ZIP_UTF8: u16 = 0x0800;
+const ZIP_DATE: u16 = 0x0021;
+
+impl<W: Write> ZipWriter<W> {
+    fn new(out: W) -> Self {
+        Self { out, offset: 0, central: Vec::new(), count: 0 }
+    }
+
+    fn add(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let too_big = || "the Kobo zip would pass 4 GiB or 65535 files";
+        let offset = u32::try_from(self.offset).map_err(|_| too_big())?;
+        let size = u32::try_from(data.len()).map_err(|_| too_big())?;
+        let crc = CRC32.checksum(data);
+        // The fields local and central headers share: version needed, flags, method (0,
+        // stored), time, date, crc, sizes and the name length
+        let mut common = Vec::new();
+        for v in [ZIP_VERSION, ZIP_UTF8, 0, 0, ZIP_DATE] {
+            common.extend(v.to_le_bytes());
+        }
+        for v in [crc, size, size] {
+            common.extend(v.to_le_bytes());
+        }
+        common.extend((name.len() as u16).to_le_bytes());
+
+        let mut local = 0x04034b50u32.to_le_bytes().to_vec();
+        local.extend(&common);
+        local.extend(0u16.to_le_bytes());
+        local.extend(name.as_bytes());
+        self.out.write_all(&local)?;
+        self.out.write_all(data)?;
+
+        self.central.extend(0x02014b50u32.to_le_bytes());
+        self.central.extend(ZIP_VERSION.to_le_bytes());
+        self.central.extend(&common);
+        // Extra field, comment, disk and internal attribute lengths, external attributes
+        self.central.extend([0; 12]);
+        self.central.extend(offset.to_le_bytes());
+        self.central.extend(name.as_bytes());
+
+        self.offset += (local.len() + data.len()) as u64;
+        self.count += 1;
+        if self.count > u16::MAX as usize {
+            return Err(too_big().into());
+        }
+        Ok(())
+    }
+
+    /// Writes the central directory, returns the writer.
+    fn finish(mut self) -> Result<W> {
+        let offset = u32::try_from(self.offset).map_err(|_| "the Kobo zip would pass 4 GiB")?;
+        self.out.write_all(&self.central)?;
+        let mut end = 0x06054b50u32.to_le_bytes().to_vec();
+        end.extend([0; 4]);
+        end.extend((self.count as u16).to_le_bytes());
+        end.extend((self.count as u16).to_le_bytes());
+        end.extend((self.central.len() as u32).to_le_bytes());
+        end.extend(offset.to_le_bytes());
+        end.extend(0u16.to_le_bytes());
+        self.out.write_all(&end)?;
+        Ok(self.out)
+    }
+}
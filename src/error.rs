@@ -0,0 +1,97 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Errors raised by the crate itself, as opposed to errors passed on from SQLite, I/O or parsing.
+///
+/// They are returned boxed like every other error; use `downcast_ref::<DictError>()` to match on them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DictError {
+    /// SQLite allows at most `limit` attached databases per connection.
+    TooManyAttachments { limit: usize },
+    /// The alias is already used by another attached database.
+    AliasInUse(String),
+    /// The alias is not a plain identifier, or is "main" or "temp".
+    InvalidAlias(String),
+    /// There is no database file at this path.
+    DatabaseNotBuilt(PathBuf),
+    /// The database at this path exists but has no entries.
+    DatabaseEmpty(PathBuf),
+    /// `DictDb::global` was called before `DictDb::init_global`.
+    GlobalNotInitialized,
+    /// `DictDb::init_global` was called more than once.
+    GlobalAlreadyInitialized,
+    /// A write still found the database busy or locked after `attempts` attempts.
+    Busy { attempts: u32, message: String },
+    /// An encoded payload was written by an incompatible version of the crate.
+    UnsupportedPayloadVersion { found: u32, supported: u32 },
+    /// A cross-reference that is not in the "keb・reb・sense" format.
+    InvalidXref(String),
+    /// A search run with `QueryOptions::timeout` was stopped after this long.
+    QueryTimeout(Duration),
+    /// A tag or attribute name for `DictDb::highlight_html` that is not letters, digits and
+    /// hyphens starting with a letter.
+    InvalidMarkupName(String),
+    /// The database file at this URL was replaced, e.g. rebuilt by another process, since
+    /// the DictDb connected to it, see `DictDb::check_generation`.
+    DatabaseReplaced(String),
+    /// The query is empty or only whitespace (or punctuation, for the searches that ignore
+    /// it), so there is nothing to search for.
+    EmptyQuery,
+    /// A substring search needs at least `min` characters, see `QueryGuards`.
+    QueryTooShort { min: usize },
+    /// `DictDb::insert_entries_with` failed after committing its first `entries_committed`
+    /// entries in `chunks_committed` transactions; `message` is the error of the next chunk.
+    InsertInterrupted { chunks_committed: usize, entries_committed: usize, message: String },
+    /// Another build of the database holds its `BuildLock`: the process `pid`, since
+    /// `started_at` in seconds since the Unix epoch (0 if the lock file doesn't say).
+    BuildInProgress { pid: u32, started_at: u64, lock: PathBuf },
+}
+
+impl fmt::Display for DictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DictError::TooManyAttachments { limit } => write!(f, "at most {} databases can be attached", limit),
+            DictError::AliasInUse(alias) => write!(f, "a database is already attached as {:?}", alias),
+            DictError::InvalidAlias(alias) => write!(f, "invalid alias {:?}", alias),
+            DictError::DatabaseNotBuilt(path) => write!(
+                f,
+                "database not built yet, run `cargo run --bin build_db` to create {} (or set JMDICTDB_PATH)",
+                path.display()
+            ),
+            DictError::DatabaseEmpty(path) => write!(
+                f,
+                "database {} is empty, run `cargo run --bin build_db` to fill it",
+                path.display()
+            ),
+            DictError::GlobalNotInitialized => write!(f, "DictDb::init_global has not been called"),
+            DictError::GlobalAlreadyInitialized => write!(f, "DictDb::init_global was already called"),
+            DictError::Busy { attempts, message } => write!(f, "{} (gave up after {} attempts)", message, attempts),
+            DictError::UnsupportedPayloadVersion { found, supported } => {
+                write!(f, "payload version {} is not supported, expected {}", found, supported)
+            }
+            DictError::InvalidXref(xref) => write!(f, "invalid cross-reference {:?}", xref),
+            DictError::QueryTimeout(timeout) => write!(f, "query stopped after the timeout of {:?}", timeout),
+            DictError::InvalidMarkupName(name) => write!(f, "invalid tag or attribute name {:?}", name),
+            DictError::DatabaseReplaced(url) => {
+                write!(f, "the database {} was replaced since it was opened, connect again to read the new one", url)
+            }
+            DictError::EmptyQuery => write!(f, "the query is empty"),
+            DictError::QueryTooShort { min } => write!(f, "the query needs at least {} characters", min),
+            DictError::BuildInProgress { pid, started_at, lock } => write!(
+                f,
+                "another build is in progress (pid {}, started_at {}); remove {} if it is not",
+                pid,
+                started_at,
+                lock.display()
+            ),
+            DictError::InsertInterrupted { chunks_committed, entries_committed, message } => write!(
+                f,
+                "{} (the first {} entries were committed in {} chunks)",
+                message, entries_committed, chunks_committed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DictError {}
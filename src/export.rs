@@ -0,0 +1,78 @@
+use futures_util::StreamExt;
+use std::io::Write;
+use std::pin::pin;
+
+use crate::{DictDb, FlatRow, FlatRowOptions, Result};
+
+/// The column names, in the order the rows are written.
+pub const FLAT_COLUMNS: [&str; 6] = ["ent_seq", "keb", "reb", "sense_order", "pos", "gloss"];
+
+/// A delimited text format for `DictDb::export_flat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated, fields quoted as in RFC 4180 when they need it.
+    Csv,
+    /// Tab-separated, without quoting; tabs and line breaks in fields become spaces.
+    Tsv,
+}
+
+impl ExportFormat {
+    /// Parses "csv" or "tsv".
+    pub fn parse(s: &str) -> Option<ExportFormat> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "tsv" => Some(ExportFormat::Tsv),
+            _ => None,
+        }
+    }
+
+    fn field(self, value: &str) -> String {
+        match self {
+            ExportFormat::Csv if value.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            }
+            ExportFormat::Csv => value.to_string(),
+            ExportFormat::Tsv => value.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+
+    /// One line of the export, with its line break.
+    pub fn line<'a>(self, fields: impl IntoIterator<Item = &'a str>) -> String {
+        let sep = match self {
+            ExportFormat::Csv => ",",
+            ExportFormat::Tsv => "\t",
+        };
+        let mut line = fields.into_iter().map(|f| self.field(f)).collect::<Vec<_>>().join(sep);
+        line.push_str(if self == ExportFormat::Csv { "\r\n" } else { "\n" });
+        line
+    }
+
+    /// The line of a row.
+    pub fn row_line(self, row: &FlatRow) -> String {
+        let ent_seq = row.ent_seq.to_string();
+        let sense_order = row.sense_order.to_string();
+        self.line([
+            ent_seq.as_str(),
+            row.keb.as_deref().unwrap_or(""),
+            &row.reb,
+            &sense_order,
+            &row.pos_joined,
+            &row.gloss,
+        ])
+    }
+}
+
+impl DictDb {
+    /// Writes `flat_rows` to `out` with a header line, returns the number of rows written.
+    pub async fn export_flat<W: Write>(&self, opts: &FlatRowOptions, format: ExportFormat, mut out: W) -> Result<usize> {
+        out.write_all(format.line(FLAT_COLUMNS).as_bytes())?;
+        let mut rows = pin!(self.flat_rows(opts));
+        let mut written = 0;
+        while let Some(row) = rows.next().await {
+            out.write_all(format.row_line(&row?).as_bytes())?;
+            written += 1;
+        }
+        out.flush()?;
+        Ok(written)
+    }
+}
@@ -0,0 +1,34 @@
+use sqlx::{QueryBuilder, Sqlite};
+
+use crate::{extract_kanji, DictDb, EntryParsed, EntryRow, Result, SearchFilter};
+
+impl DictDb {
+    /// Expressions (entries with an `exp` sense) with a keb or reb containing `word`, e.g. the
+    /// proverb 猿も木から落ちる for 猿 or 木, most frequent (by nfNN bucket) first.
+    ///
+    /// Meant for a separate "expressions" section next to the normal results, which can leave
+    /// them out with `SearchFilter::without_expressions`. Kanji in `word` are looked up in the
+    /// kanji index first, so only their entries are scanned; shorter queries than the
+    /// `QueryGuards` allow are `DictError::QueryTooShort`.
+    pub async fn search_expressions_containing(&self, word: &str) -> Result<Vec<EntryParsed>> {
+        self.observe("search_expressions_containing", word, String::new, async {
+            let word = self.substring_query(word)?;
+            let mut qb = QueryBuilder::<Sqlite>::new("SELECT e.* FROM entries e WHERE 1");
+            for ch in extract_kanji(word) {
+                qb.push(" AND e.ent_seq IN (SELECT k.ent_seq FROM kanji_index k WHERE k.ch = ");
+                qb.push_bind(ch.to_string());
+                qb.push(")");
+            }
+            qb.push(" AND (EXISTS (SELECT 1 FROM json_each(e.kebs) k WHERE instr(k.value, ");
+            qb.push_bind(word.to_string());
+            qb.push(") > 0) OR EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE instr(r.value, ");
+            qb.push_bind(word.to_string());
+            qb.push(") > 0))");
+            SearchFilter::new().only_expressions().push_conditions(&mut qb, "e.ent_seq");
+            qb.push(" ORDER BY e.nf_bucket IS NULL, e.nf_bucket, e.ent_seq");
+            let rows = qb.build_query_as::<EntryRow>().fetch_all(&self.pool).await?;
+            self.hydrate_entries(rows).await
+        })
+        .await
+    }
+}
@@ -0,0 +1,63 @@
+//! Optional SQLite extensions: ICU and spellfix1.
+//!
+//! Nothing needs them. Loaded with `DictDbOptions::extension` (the `sqlite-extensions`
+//! feature), they are found on connect and used where they do better than the plain SQL:
+//!
+//! - ICU replaces LIKE with one that ignores case beyond ASCII, so `search_sentences` no
+//!   longer folds non-ASCII queries in Rust.
+//! - spellfix1's `editdist3()` narrows the readings the edit distance strategy of
+//!   `suggest_corrections` checks, instead of checking all readings of about the right length.
+//!
+//! The results are the same either way.
+
+use sqlx::SqlitePool;
+
+use crate::{DictDb, Result};
+
+/// Whether a function or virtual table module of that name is registered on a connection.
+const HAS_FUNCTION_OR_MODULE_SQL: &str = r#"
+    SELECT EXISTS (SELECT 1 FROM pragma_function_list WHERE name = ?1)
+        OR EXISTS (SELECT 1 FROM pragma_module_list WHERE name = ?1)
+"#;
+
+/// What the extensions found on connect make available.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Extensions {
+    /// The ICU extension, with a LIKE that knows Unicode case.
+    pub(crate) icu: bool,
+    /// The spellfix1 extension, with `editdist3()`.
+    pub(crate) spellfix1: bool,
+}
+
+/// The function or module telling whether an extension is loaded: ICU has no module and
+/// overrides built-in functions, so its collation loader stands in for it.
+fn probe_name(name: &str) -> &str {
+    match name {
+        "icu" => "icu_load_collation",
+        "spellfix" | "spellfix1" => "spellfix1",
+        other => other,
+    }
+}
+
+async fn has_function_or_module(pool: &SqlitePool, name: &str) -> Result<bool> {
+    Ok(sqlx::query_scalar(HAS_FUNCTION_OR_MODULE_SQL).bind(probe_name(name)).fetch_one(pool).await?)
+}
+
+impl Extensions {
+    /// Looks for the extensions on a connection of the pool, they are loaded on all of them.
+    pub(crate) async fn probe(pool: &SqlitePool) -> Result<Self> {
+        Ok(Self { icu: has_function_or_module(pool, "icu").await?, spellfix1: has_function_or_module(pool, "spellfix1").await? })
+    }
+}
+
+impl DictDb {
+    /// Returns true if the SQLite extension `name` is loaded: "icu" and "spellfix1" (or
+    /// "spellfix") by name, anything else as the name of a SQL function or virtual table
+    /// module it registers.
+    ///
+    /// Without the `sqlite-extensions` feature only what SQLite has built in is there, and
+    /// the searches use their fallbacks.
+    pub async fn has_extension(&self, name: &str) -> Result<bool> {
+        has_function_or_module(&self.pool, name).await
+    }
+}
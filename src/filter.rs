@@ -0,0 +1,249 @@
+use sqlx::{QueryBuilder, Sqlite};
+use std::ops::RangeInclusive;
+
+use crate::tags::{TagCategory, TAG_COLUMNS};
+use crate::{is_kanji, EntryParsed, PartOfSpeech, TagInfo};
+
+/// Restrictions applied in SQL to the entries returned by a search.
+///
+/// The default filter lets everything through.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilter {
+    /// Only entries with a JLPT level in this range, e.g. `3..=5` for N5 to N3.
+    pub jlpt: Option<RangeInclusive<u8>>,
+    /// Some(false) drops expressions (entries with an `exp` sense, see `EntryParsed::is_expression`),
+    /// Some(true) keeps only them.
+    pub expressions: Option<bool>,
+    /// Only entries with a sense of this part of speech.
+    pub pos: Option<PartOfSpeech>,
+    /// The shortest form kept, in characters (not morae: きょう is 3).
+    pub min_len: Option<usize>,
+    /// The longest form kept, in characters.
+    pub max_len: Option<usize>,
+    /// Only entries without kebs, see `EntryParsed::is_kana_only`.
+    pub kana_only: bool,
+    /// Only forms with this many kanji, e.g. `2..=2` for two-kanji compounds. Checked after
+    /// the query, SQLite cannot count them.
+    pub kanji_count: Option<RangeInclusive<usize>>,
+    /// Only entries with a gloss in this language (ISO 639-2, e.g. "ger"), and of those only
+    /// the senses that have one, see `SenseParsed::has_lang`.
+    pub lang: Option<String>,
+    /// Only entries with a sense having each of these tags, in the column of its category;
+    /// tags of unknown category in any of them. The database keeps no kanji or reading
+    /// info, so those tags match nothing.
+    pub tags: Vec<TagInfo>,
+    /// Only entries with a `complexity_score` of at most this, e.g. 15 for words with a
+    /// sense or two and few glosses.
+    pub max_complexity: Option<u32>,
+}
+
+impl SearchFilter {
+    /// Creates a filter that lets everything through.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keeps entries whose JLPT level (5 for N5 ... 1 for N1) is in `levels`.
+    /// Entries without a level are dropped.
+    pub fn jlpt(mut self, levels: RangeInclusive<u8>) -> Self {
+        self.jlpt = Some(levels);
+        self
+    }
+
+    /// Drops expressions such as 猿も木から落ちる, for searches that should only list words.
+    pub fn without_expressions(mut self) -> Self {
+        self.expressions = Some(false);
+        self
+    }
+
+    /// Only keeps expressions.
+    pub fn only_expressions(mut self) -> Self {
+        self.expressions = Some(true);
+        self
+    }
+
+    /// Only keeps entries with a sense of this part of speech.
+    pub fn pos(mut self, pos: PartOfSpeech) -> Self {
+        self.pos = Some(pos);
+        self
+    }
+
+    /// Only keeps forms of at least `len` characters.
+    pub fn min_len(mut self, len: usize) -> Self {
+        self.min_len = Some(len);
+        self
+    }
+
+    /// Only keeps forms of at most `len` characters.
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = Some(len);
+        self
+    }
+
+    /// Only keeps forms with a length in `lens`, e.g. `4..=4` for a word game.
+    pub fn len(self, lens: RangeInclusive<usize>) -> Self {
+        let (min, max) = lens.into_inner();
+        self.min_len(min).max_len(max)
+    }
+
+    /// Only keeps entries written in kana only, such as する.
+    pub fn kana_only(mut self) -> Self {
+        self.kana_only = true;
+        self
+    }
+
+    /// Only keeps forms with a number of kanji in `counts`.
+    pub fn kanji_count(mut self, counts: RangeInclusive<usize>) -> Self {
+        self.kanji_count = Some(counts);
+        self
+    }
+
+    /// Only keeps the senses with a gloss in `lang`, e.g. "ger" for a German user, who has
+    /// no use for the Russian-only senses of a multilingual build. Entries left without a
+    /// sense are dropped.
+    pub fn lang(mut self, lang: &str) -> Self {
+        self.lang = Some(lang.to_string());
+        self
+    }
+
+    /// Only keeps entries with a sense having this tag, given by name, e.g. "uk", by
+    /// description, as a `PartOfSpeech` or as the `TagInfo` of `lookup_tag`.
+    pub fn tag(mut self, tag: impl Into<TagInfo>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Only keeps entries with a `complexity_score` of at most `max`.
+    pub fn max_complexity(mut self, max: u32) -> Self {
+        self.max_complexity = Some(max);
+        self
+    }
+
+    /// Drops the senses of a hydrated entry without a gloss in `lang`.
+    pub(crate) fn retain_senses(&self, entry: &mut EntryParsed) {
+        if let Some(lang) = &self.lang {
+            entry.senses.retain(|s| s.has_lang(lang));
+        }
+    }
+
+    /// Returns true if the filter restricts the forms, see `matches_form`.
+    pub(crate) fn has_form_conditions(&self) -> bool {
+        self.min_len.is_some() || self.max_len.is_some() || self.kanji_count.is_some()
+    }
+
+    /// Whether a form passes the form conditions: `min_len`, `max_len` and `kanji_count`, for
+    /// the check after the query.
+    pub(crate) fn matches_form(&self, form: &str) -> bool {
+        let len = form.chars().count();
+        self.min_len.is_none_or(|min| len >= min)
+            && self.max_len.is_none_or(|max| len <= max)
+            && self
+                .kanji_count
+                .as_ref()
+                .is_none_or(|counts| counts.contains(&form.chars().filter(|&c| is_kanji(c)).count()))
+    }
+
+    /// Appends the length conditions as `AND ...` conditions on the form column `form_col`,
+    /// for searches that know which form matched.
+    pub(crate) fn push_form_conditions(&self, qb: &mut QueryBuilder<'_, Sqlite>, form_col: &str) {
+        if let Some(min) = self.min_len {
+            qb.push(format!(" AND length({}) >= ", form_col));
+            qb.push_bind(min as i64);
+        }
+        if let Some(max) = self.max_len {
+            qb.push(format!(" AND length({}) <= ", form_col));
+            qb.push_bind(max as i64);
+        }
+    }
+
+    /// Appends the filter as `AND ...` conditions on the entry sequence column `ent_seq_col`.
+    /// The form conditions are left to `push_form_conditions` or `push_headword_conditions`,
+    /// and `matches_form`.
+    pub(crate) fn push_conditions(&self, qb: &mut QueryBuilder<'_, Sqlite>, ent_seq_col: &str) {
+        if let Some(levels) = &self.jlpt {
+            qb.push(format!(
+                " AND EXISTS (SELECT 1 FROM jlpt j WHERE j.ent_seq = {} AND j.level BETWEEN ",
+                ent_seq_col
+            ));
+            qb.push_bind(*levels.start() as i64);
+            qb.push(" AND ");
+            qb.push_bind(*levels.end() as i64);
+            qb.push(")");
+        }
+        if let Some(expressions) = self.expressions {
+            qb.push(format!(
+                " AND {}EXISTS (SELECT 1 FROM senses s, json_each(s.pos) p WHERE s.ent_seq = {} AND p.value IN (",
+                if expressions { "" } else { "NOT " },
+                ent_seq_col
+            ));
+            qb.push_bind(PartOfSpeech::Exp.code().to_string());
+            qb.push(", ");
+            qb.push_bind(PartOfSpeech::Exp.description().to_string());
+            qb.push("))");
+        }
+        if let Some(pos) = &self.pos {
+            qb.push(format!(
+                " AND EXISTS (SELECT 1 FROM senses s, json_each(s.pos) p WHERE s.ent_seq = {} AND p.value IN (",
+                ent_seq_col
+            ));
+            qb.push_bind(pos.code().to_string());
+            qb.push(", ");
+            qb.push_bind(pos.description().to_string());
+            qb.push("))");
+        }
+        if let Some(lang) = &self.lang {
+            qb.push(format!(
+                " AND EXISTS (SELECT 1 FROM senses s JOIN glosses g ON g.sense_id = s.id WHERE s.ent_seq = {} AND g.lang = ",
+                ent_seq_col
+            ));
+            qb.push_bind(lang.clone());
+            qb.push(")");
+        }
+        for tag in &self.tags {
+            let columns: &[&str] = match (tag.category, tag.category.column()) {
+                (_, Some(column)) => &[column],
+                (TagCategory::Unknown, None) => &TAG_COLUMNS,
+                (_, None) => &[],
+            };
+            qb.push(" AND (0");
+            for column in columns {
+                qb.push(format!(
+                    " OR EXISTS (SELECT 1 FROM senses s, json_each(s.{}) t WHERE s.ent_seq = {} AND t.value IN (",
+                    column, ent_seq_col
+                ));
+                qb.push_bind(tag.name.to_string());
+                qb.push(", ");
+                qb.push_bind(tag.description.to_string());
+                qb.push("))");
+            }
+            qb.push(")");
+        }
+        if let Some(max) = self.max_complexity {
+            qb.push(format!(" AND EXISTS (SELECT 1 FROM entries c WHERE c.ent_seq = {} AND c.complexity <= ", ent_seq_col));
+            qb.push_bind(max as i64);
+            qb.push(")");
+        }
+        if self.kana_only {
+            qb.push(format!(
+                " AND EXISTS (SELECT 1 FROM entries k WHERE k.ent_seq = {} AND COALESCE(json_array_length(k.kebs), 0) = 0)",
+                ent_seq_col
+            ));
+        }
+    }
+
+    /// Appends the length conditions as an `AND EXISTS ...` condition on the headwords of the
+    /// entry `ent_seq_col` (its kebs, or its rebs for kana-only entries), for listings that
+    /// match no form.
+    pub(crate) fn push_headword_conditions(&self, qb: &mut QueryBuilder<'_, Sqlite>, ent_seq_col: &str) {
+        if self.min_len.is_none() && self.max_len.is_none() {
+            return;
+        }
+        qb.push(format!(
+            " AND EXISTS (SELECT 1 FROM entries h, json_each(CASE WHEN COALESCE(json_array_length(h.kebs), 0) = 0 \
+             THEN h.rebs ELSE h.kebs END) hf WHERE h.ent_seq = {}",
+            ent_seq_col
+        ));
+        self.push_form_conditions(qb, "hf.value");
+        qb.push(")");
+    }
+}
@@ -0,0 +1,85 @@
+//! Deterministic dictionary data for tests, with the `test-utils` feature.
+//!
+//! Building the full dictionary is impractical in CI, so the crate embeds a JMdict-format
+//! sample of about 240 common words. It covers kana-only entries, entries with several
+//! kebs, stagk/stagr restrictions, cross-references, priority tags including nfNN buckets,
+//! and suru and kuru verbs. The ent_seq values of the sample are not guaranteed to match the
+//! current JMdict release.
+//!
+//! ```no_run
+//! # async fn demo() {
+//! let db = jmdictdb::fixtures::build_sample_db().await;
+//! let results = db.search_entries_with_senses_by_reading("たべる").await.unwrap();
+//! assert_eq!(results[0].ent_seq, 1358280);
+//! # }
+//! ```
+
+use crate::{parse_jmdict_str, DictDb, JMDict, Result};
+
+/// The embedded sample, as a complete JMdict XML document with its DTD entities.
+pub fn sample_xml() -> &'static str {
+    include_str!("../fixtures/jmdict_sample.xml")
+}
+
+/// The sample parsed into entries.
+///
+/// # Panics
+/// Never for the embedded sample; it is covered by the crate's tests.
+pub fn sample_dict() -> JMDict {
+    parse_jmdict_str(sample_xml()).expect("the embedded sample is valid JMdict XML")
+}
+
+/// A few entries as the multilingual JMdict has them, with senses in several languages,
+/// some without English glosses.
+pub fn multilingual_xml() -> &'static str {
+    include_str!("../fixtures/jmdict_multilingual.xml")
+}
+
+/// Builds an in-memory database holding the sample. Every call returns a separate database.
+///
+/// # Panics
+/// If SQLite fails to create or fill the database.
+pub async fn build_sample_db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.expect("in-memory database");
+    db.insert_entries(&sample_dict().entry).await.expect("inserting the sample");
+    db
+}
+
+/// Builds an in-memory database holding `multilingual_xml`.
+///
+/// # Panics
+/// If SQLite fails to create or fill the database.
+pub async fn build_multilingual_db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.expect("in-memory database");
+    let dict = parse_jmdict_str(multilingual_xml()).expect("the embedded sample is valid JMdict XML");
+    db.insert_entries(&dict.entry).await.expect("inserting the sample");
+    db
+}
+
+/// Runs a query that keeps SQLite busy counting to `n` and returns `n`, for tests of
+/// timeouts; a few hundred million take seconds.
+pub async fn count_slowly(db: &DictDb, n: i64) -> Result<i64> {
+    Ok(sqlx::query_scalar(
+        "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < ?1) SELECT COUNT(*) FROM c",
+    )
+    .bind(n)
+    .fetch_one(&db.pool)
+    .await?)
+}
+
+/// The temporary tables on every connection of the pool, e.g. to check that a dropped stream
+/// cleaned up after itself.
+pub async fn temp_tables(db: &DictDb) -> Result<Vec<String>> {
+    let mut conns = Vec::new();
+    let mut names = Vec::new();
+    for _ in 0..db.pool.size() {
+        let mut conn = db.pool.acquire().await?;
+        let tables: Vec<String> = sqlx::query_scalar("SELECT name FROM temp.sqlite_master WHERE type = 'table'")
+            .fetch_all(&mut *conn)
+            .await?;
+        names.extend(tables);
+        // Held until all were checked, so every connection is
+        conns.push(conn);
+    }
+    Ok(names)
+}
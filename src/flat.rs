@@ -0,0 +1,122 @@
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, QueryBuilder, Sqlite};
+use std::collections::VecDeque;
+
+use crate::{Collation, DictDb, Result, SearchFilter};
+
+/// Entries read per query by `flat_rows`.
+const FLAT_BATCH_ENTRIES: i64 = 500;
+
+/// One gloss of one sense of an entry, for spreadsheet-style consumers, see `DictDb::flat_rows`.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct FlatRow {
+    pub ent_seq: i64,
+    /// The first keb, or each keb in turn with `FlatRowOptions::all_kebs`. None for kana-only entries.
+    pub keb: Option<String>,
+    /// The first reb.
+    pub reb: String,
+    pub sense_order: i64,
+    /// The parts of speech of the sense, joined with "; ".
+    pub pos_joined: String,
+    /// One English gloss.
+    pub gloss: String,
+}
+
+/// Options for `DictDb::flat_rows`.
+#[derive(Debug, Clone, Default)]
+pub struct FlatRowOptions {
+    /// Restrictions on the entries. The form conditions (lengths, kanji count) apply to the
+    /// keb of each row, or its reb for kana-only entries.
+    pub filter: SearchFilter,
+    /// Repeat the rows of an entry for each of its kebs instead of only the first one.
+    pub all_kebs: bool,
+    /// Order the rows by gloss with this collation, then by ent_seq and sense, instead of
+    /// by entry. All rows are read before the first is returned.
+    pub collation: Option<Collation>,
+}
+
+/// Reads up to FLAT_BATCH_ENTRIES entries after `after` and returns their rows.
+async fn flat_batch(db: &DictDb, opts: &FlatRowOptions, after: i64) -> Result<Vec<FlatRow>> {
+    let keb = if opts.all_kebs { "k.value" } else { "json_extract(e.kebs, '$[0]')" };
+    let mut qb = QueryBuilder::<Sqlite>::new(format!(
+        r#"
+        SELECT e.ent_seq, {} AS keb, json_extract(e.rebs, '$[0]') AS reb, s.sense_order,
+               (SELECT COALESCE(group_concat(p.value, '; '), '') FROM json_each(s.pos) p) AS pos_joined,
+               g.value AS gloss
+        FROM entries e
+        {}
+        JOIN senses s ON s.ent_seq = e.ent_seq
+        JOIN json_each(s.gloss) g
+        WHERE e.ent_seq IN (SELECT f.ent_seq FROM entries f WHERE f.ent_seq > "#,
+        keb,
+        if opts.all_kebs { "LEFT JOIN json_each(e.kebs) k" } else { "" },
+    ));
+    qb.push_bind(after);
+    opts.filter.push_conditions(&mut qb, "f.ent_seq");
+    opts.filter.push_headword_conditions(&mut qb, "f.ent_seq");
+    qb.push(" ORDER BY f.ent_seq LIMIT ");
+    qb.push_bind(FLAT_BATCH_ENTRIES);
+    qb.push(")");
+    qb.push(if opts.all_kebs {
+        " ORDER BY e.ent_seq, k.key, s.sense_order, g.key"
+    } else {
+        " ORDER BY e.ent_seq, s.sense_order, g.key"
+    });
+    Ok(qb.build_query_as::<FlatRow>().fetch_all(&db.pool).await?)
+}
+
+/// Where `flat_rows` is: the rows read but not yet returned, and the last entry read.
+struct FlatCursor {
+    buffered: VecDeque<FlatRow>,
+    /// The rows read so far when sorting by gloss.
+    sorted: Vec<FlatRow>,
+    after: i64,
+    done: bool,
+}
+
+impl DictDb {
+    /// All entries as one row per (entry, sense, gloss), ordered by ent_seq, sense and gloss,
+    /// or by gloss with `FlatRowOptions::collation`.
+    ///
+    /// Entries are read a few hundred at a time as the stream is polled, so memory use
+    /// doesn't grow with the dictionary and no connection is held between batches. Sorted
+    /// by gloss, all batches are read and sorted on the first poll.
+    pub fn flat_rows<'a>(&'a self, opts: &'a FlatRowOptions) -> impl Stream<Item = Result<FlatRow>> + Send + 'a {
+        let cursor = FlatCursor { buffered: VecDeque::new(), sorted: Vec::new(), after: i64::MIN, done: false };
+        stream::unfold(cursor, move |mut cursor| async move {
+            loop {
+                if let Some(row) = cursor.buffered.pop_front() {
+                    return Some((Ok(row), cursor));
+                }
+                if cursor.done {
+                    return None;
+                }
+                match flat_batch(self, opts, cursor.after).await {
+                    Ok(rows) if rows.is_empty() => {
+                        let collation = opts.collation.filter(|_| !cursor.sorted.is_empty())?;
+                        // Stable, so rows with the same gloss stay by entry and sense
+                        collation.sort_by(&mut cursor.sorted, |r| &r.gloss);
+                        cursor.buffered = std::mem::take(&mut cursor.sorted).into();
+                        cursor.done = true;
+                    }
+                    Ok(mut rows) => {
+                        cursor.after = rows.last().map_or(cursor.after, |r| r.ent_seq);
+                        if opts.filter.has_form_conditions() {
+                            rows.retain(|r| opts.filter.matches_form(r.keb.as_deref().unwrap_or(&r.reb)));
+                        }
+                        if opts.collation.is_some() {
+                            cursor.sorted.extend(rows);
+                        } else {
+                            cursor.buffered = rows.into();
+                        }
+                    }
+                    Err(err) => {
+                        cursor.done = true;
+                        return Some((Err(err), cursor));
+                    }
+                }
+            }
+        })
+    }
+}
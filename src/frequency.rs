@@ -0,0 +1,256 @@
+use futures_util::TryStreamExt;
+use sqlx::{QueryBuilder, Sqlite};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::{DictDb, EntryParsed, EntryRow, Result};
+
+/// Ranks inserted per statement by `store_corpus_frequency`, three parameters each.
+const CORPUS_INSERT_ROWS: usize = 300;
+
+/// The line format of an external frequency list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyFormat {
+    /// One `term<TAB>rank` pair per line.
+    TermRank,
+    /// One term per line, already sorted; the rank is the line number (starting at 1).
+    Ranked,
+}
+
+/// How often the entries occur in a corpus, from `count_corpus_occurrences`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorpusCounts {
+    /// The occurrences of each entry, by ent_seq.
+    pub counts: HashMap<i64, u64>,
+    /// The occurrences of the tokens no entry has as keb or reb.
+    pub unresolved: HashMap<String, u64>,
+}
+
+/// The outcome of loading a frequency list.
+#[derive(Debug, Clone, Default)]
+pub struct FrequencyLoadReport {
+    /// The name the list was stored under.
+    pub source: String,
+    /// How many terms were read from the file.
+    pub terms_read: usize,
+    /// How many entries received a rank.
+    pub entries_ranked: usize,
+    /// Terms that did not match any keb or reb, in file order.
+    pub unmatched: Vec<String>,
+}
+
+impl DictDb {
+    /// Loads an external frequency list (e.g. BCCWJ or a subtitle corpus) and stores the ranks
+    /// in the `frequency` table under `source`.
+    ///
+    /// Terms are matched against every keb and reb. An entry matched by several terms keeps
+    /// the best (lowest) rank. Loading a source again replaces its previous ranks, other sources
+    /// are left untouched. Empty lines and lines starting with `#` are skipped.
+    ///
+    /// # Arguments
+    /// * `source` - The name of the list, used to select it at query time.
+    /// * `reader` - The list contents.
+    /// * `format` - The line format of the list.
+    ///
+    /// # Returns
+    /// A FrequencyLoadReport, including the terms that did not match any entry.
+    pub async fn load_frequency_list<R: Read>(
+        &self,
+        source: &str,
+        reader: R,
+        format: FrequencyFormat,
+    ) -> Result<FrequencyLoadReport> {
+        let mut terms: Vec<(String, i64)> = Vec::new();
+        for (line_no, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match format {
+                FrequencyFormat::TermRank => {
+                    let (term, rank) = line
+                        .split_once('\t')
+                        .ok_or_else(|| format!("line {}: expected term<TAB>rank", line_no + 1))?;
+                    let rank: i64 = rank
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("line {}: invalid rank {:?}", line_no + 1, rank))?;
+                    terms.push((term.trim().to_string(), rank));
+                }
+                FrequencyFormat::Ranked => {
+                    let rank = terms.len() as i64 + 1;
+                    terms.push((line.to_string(), rank));
+                }
+            }
+        }
+
+        // Temp tables are per connection, so everything runs on the transaction's connection
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("CREATE TEMP TABLE IF NOT EXISTS freq_terms (term TEXT NOT NULL, rank INTEGER NOT NULL)")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM temp.freq_terms").execute(&mut *tx).await?;
+
+        for (term, rank) in &terms {
+            sqlx::query("INSERT INTO temp.freq_terms (term, rank) VALUES (?1, ?2)")
+                .bind(term)
+                .bind(rank)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS temp.idx_freq_terms ON freq_terms(term)")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM frequency WHERE source = ?1")
+            .bind(source)
+            .execute(&mut *tx)
+            .await?;
+
+        let ranked = sqlx::query(
+            r#"
+            INSERT INTO frequency (ent_seq, source, rank)
+            SELECT f.ent_seq, ?1, MIN(t.rank)
+            FROM (
+                SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.rebs) je
+                UNION ALL
+                SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.kebs) je
+                WHERE e.kebs IS NOT NULL
+            ) f
+            JOIN temp.freq_terms t ON t.term = f.form
+            GROUP BY f.ent_seq
+            "#,
+        )
+        .bind(source)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let unmatched: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT t.term FROM temp.freq_terms t
+            WHERE t.term NOT IN (
+                SELECT je.value FROM entries e, json_each(e.rebs) je
+                UNION ALL
+                SELECT je.value FROM entries e, json_each(e.kebs) je
+                WHERE e.kebs IS NOT NULL
+            )
+            ORDER BY t.rowid
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        sqlx::query("DROP TABLE temp.freq_terms").execute(&mut *tx).await?;
+        tx.commit().await?;
+        self.entry_cache.clear();
+
+        Ok(FrequencyLoadReport {
+            source: source.to_string(),
+            terms_read: terms.len(),
+            entries_ranked: ranked as usize,
+            unmatched,
+        })
+    }
+
+    /// Counts how often each entry occurs in a tokenized corpus, e.g. the base forms a
+    /// morphological analyzer gives for a set of books.
+    ///
+    /// Tokens are resolved with `bulk_lookup`, against every keb and reb, and a token of
+    /// several entries counts for each of them. Pass the counts to `store_corpus_frequency`
+    /// to rank by them.
+    pub async fn count_corpus_occurrences(&self, tokens: impl Iterator<Item = String>) -> Result<CorpusCounts> {
+        let mut tokens_seen: HashMap<String, u64> = HashMap::new();
+        for token in tokens {
+            let token = token.trim();
+            if !token.is_empty() {
+                *tokens_seen.entry(token.to_string()).or_default() += 1;
+            }
+        }
+
+        let mut counts: HashMap<i64, u64> = HashMap::new();
+        let mut resolved: Vec<String> = Vec::new();
+        let mut pairs = std::pin::pin!(self.bulk_lookup(tokens_seen.keys().cloned().collect::<Vec<_>>().into_iter()));
+        while let Some((token, ent_seq)) = pairs.try_next().await? {
+            *counts.entry(ent_seq).or_default() += tokens_seen[&token];
+            resolved.push(token);
+        }
+        for token in resolved {
+            tokens_seen.remove(&token);
+        }
+        Ok(CorpusCounts { counts, unresolved: tokens_seen })
+    }
+
+    /// Stores corpus counts as the frequency list `source`, so that `RankOptions::frequency_source`
+    /// can rank by them like by a loaded list. The most frequent entry gets rank 1, entries
+    /// with the same count share a rank; entries never seen get none.
+    ///
+    /// Storing a source again replaces its previous ranks, counts of ent_seqs not in the
+    /// database are skipped.
+    ///
+    /// # Returns
+    /// How many entries received a rank.
+    pub async fn store_corpus_frequency(&self, source: &str, counts: &HashMap<i64, u64>) -> Result<usize> {
+        let mut sorted: Vec<(i64, u64)> = counts.iter().map(|(&seq, &n)| (seq, n)).filter(|&(_, n)| n > 0).collect();
+        sorted.sort_by(|(a_seq, a), (b_seq, b)| b.cmp(a).then(a_seq.cmp(b_seq)));
+        let mut ranks: Vec<(i64, i64)> = Vec::with_capacity(sorted.len());
+        for (position, &(ent_seq, count)) in sorted.iter().enumerate() {
+            let rank = match position.checked_sub(1).map(|p| sorted[p].1) {
+                Some(previous) if previous == count => ranks[position - 1].1,
+                _ => position as i64 + 1,
+            };
+            ranks.push((ent_seq, rank));
+        }
+
+        self.write_entries(|| async {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("DELETE FROM frequency WHERE source = ?1").bind(source).execute(&mut *tx).await?;
+            let mut stored = 0;
+            for chunk in ranks.chunks(CORPUS_INSERT_ROWS) {
+                let mut qb = QueryBuilder::<Sqlite>::new(
+                    "INSERT INTO frequency (ent_seq, source, rank) SELECT v.column1, v.column2, v.column3 FROM (",
+                );
+                qb.push_values(chunk, |mut row, (ent_seq, rank)| {
+                    row.push_bind(ent_seq).push_bind(source).push_bind(rank);
+                });
+                qb.push(") v WHERE v.column1 IN (SELECT ent_seq FROM entries)");
+                stored += qb.build().execute(&mut *tx).await?.rows_affected() as usize;
+            }
+            tx.commit().await?;
+            Ok(stored)
+        })
+        .await
+    }
+
+    /// Returns the names of all loaded frequency lists.
+    pub async fn frequency_sources(&self) -> Result<Vec<String>> {
+        let sources = sqlx::query_scalar("SELECT DISTINCT source FROM frequency ORDER BY source")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(sources)
+    }
+
+    /// Returns the rank of an entry in the given frequency list.
+    pub async fn frequency_rank(&self, ent_seq: i64, source: &str) -> Result<Option<i64>> {
+        let rank = sqlx::query_scalar("SELECT rank FROM frequency WHERE ent_seq = ?1 AND source = ?2")
+            .bind(ent_seq)
+            .bind(source)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(rank)
+    }
+
+    /// Returns the entries with an nfNN priority tag, most frequent bucket first, e.g. to
+    /// build a core vocabulary list. Entries within a bucket are ordered by ent_seq.
+    pub async fn top_frequency_entries(&self, limit: usize) -> Result<Vec<EntryParsed>> {
+        let rows = sqlx::query_as::<_, EntryRow>(
+            "SELECT * FROM entries WHERE nf_bucket IS NOT NULL ORDER BY nf_bucket, ent_seq LIMIT ?1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        self.hydrate_entries(rows).await
+    }
+}
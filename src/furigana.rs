@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+use crate::kana::{is_kana, katakana_to_hiragana};
+use crate::{DictDb, EntryParsed, Result};
+
+/// A piece of a headword with its reading, for ruby rendering.
+///
+/// 食べ物 / たべもの is split into 食[た] べ 物[もの].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FuriganaSegment {
+    /// The text as written in the headword.
+    pub text: String,
+    /// The reading of `text`, None for kana that needs no ruby.
+    pub ruby: Option<String>,
+}
+
+/// The outcome of loading the JmdictFurigana dataset.
+#[derive(Debug, Clone, Default)]
+pub struct FuriganaLoadReport {
+    /// How many records were read from the file.
+    pub records_read: usize,
+    /// How many (ent_seq, keb, reb) rows were stored.
+    pub rows_stored: usize,
+    /// Records whose text/reading pair did not match any entry.
+    pub unmatched: usize,
+}
+
+/// A record of the JmdictFurigana JSON file.
+#[derive(Debug, Deserialize)]
+struct FuriganaRecord {
+    text: String,
+    reading: String,
+    furigana: Vec<FuriganaRecordPart>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FuriganaRecordPart {
+    ruby: String,
+    rt: Option<String>,
+}
+
+/// Aligns a headword with its reading by matching the kana in the headword (okurigana)
+/// against the reading, from the ends inward.
+///
+/// Every run of non-kana characters becomes one segment with ruby, so 食べ物 / たべもの gives
+/// 食[た] べ 物[もの]. Consecutive kanji are not split further. Kana in the headword is compared
+/// regardless of hiragana/katakana.
+///
+/// Punctuation and spaces, as in expressions like 〜を付ける or 「ア・ラ・カルト」, get no
+/// ruby and are skipped in the reading, which usually leaves them out.
+///
+/// # Returns
+/// None if the kana in the headword cannot be found in the reading.
+pub fn align_furigana(keb: &str, reb: &str) -> Option<Vec<FuriganaSegment>> {
+    let runs = split_runs(keb);
+    let reading: Vec<char> = reb.chars().collect();
+    align_runs(&runs, &reading)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunKind {
+    Kana,
+    Punctuation,
+    /// Kanji and whatever else needs a reading.
+    Other,
+}
+
+/// Punctuation, symbols and spaces, which have no reading: 〜, ・, 、, 「」, ！ and the like.
+/// The iteration marks 々 and 〆 and the zero 〇 are read like kanji and left out.
+fn is_punctuation(c: char) -> bool {
+    c.is_whitespace()
+        || c.is_ascii_punctuation()
+        || matches!(c,
+            '\u{3000}'..='\u{3004}' | '\u{3008}'..='\u{303A}' | '\u{303C}'..='\u{303F}'
+            | '・'
+            | '\u{FF01}'..='\u{FF0F}' | '\u{FF1A}'..='\u{FF20}' | '\u{FF3B}'..='\u{FF40}' | '\u{FF5B}'..='\u{FF65}')
+}
+
+/// Splits a headword into runs of kana, punctuation and other characters.
+fn split_runs(s: &str) -> Vec<(RunKind, String)> {
+    let mut runs: Vec<(RunKind, String)> = Vec::new();
+    for c in s.chars() {
+        let kind = if is_kana(c) {
+            RunKind::Kana
+        } else if is_punctuation(c) {
+            RunKind::Punctuation
+        } else {
+            RunKind::Other
+        };
+        match runs.last_mut() {
+            Some((k, run)) if *k == kind => run.push(c),
+            _ => runs.push((kind, c.to_string())),
+        }
+    }
+    runs
+}
+
+fn kana_eq(a: &[char], b: &str) -> bool {
+    katakana_to_hiragana(&a.iter().collect::<String>()) == katakana_to_hiragana(b)
+}
+
+fn align_runs(runs: &[(RunKind, String)], reading: &[char]) -> Option<Vec<FuriganaSegment>> {
+    let Some(((kind, text), rest)) = runs.split_first() else {
+        return reading.is_empty().then(Vec::new);
+    };
+
+    let len = text.chars().count();
+    let starts_with_text = reading.len() >= len && kana_eq(&reading[..len], text);
+    match kind {
+        RunKind::Kana if !starts_with_text => return None,
+        RunKind::Kana => {
+            let mut segments = vec![FuriganaSegment { text: text.clone(), ruby: None }];
+            segments.extend(align_runs(rest, &reading[len..])?);
+            return Some(segments);
+        }
+        RunKind::Punctuation => {
+            // Taken from the reading if it is there, passed over otherwise
+            let tail = starts_with_text
+                .then(|| align_runs(rest, &reading[len..]))
+                .flatten()
+                .or_else(|| align_runs(rest, reading))?;
+            let mut segments = vec![FuriganaSegment { text: text.clone(), ruby: None }];
+            segments.extend(tail);
+            return Some(segments);
+        }
+        RunKind::Other => {}
+    }
+
+    // The last run takes whatever reading is left, punctuation in the reading belongs
+    // to the headword's
+    if rest.is_empty() {
+        if reading.is_empty() || reading.iter().any(|&c| is_punctuation(c)) {
+            return None;
+        }
+        return Some(vec![FuriganaSegment {
+            text: text.clone(),
+            ruby: Some(reading.iter().collect()),
+        }]);
+    }
+
+    // Otherwise try every split that leaves the following kana run at the right place,
+    // shortest reading first. All of it can be this run's when only punctuation follows.
+    for split in 1..=reading.len() {
+        if is_punctuation(reading[split - 1]) {
+            break;
+        }
+        if let Some(mut tail) = align_runs(rest, &reading[split..]) {
+            let mut segments = vec![FuriganaSegment {
+                text: text.clone(),
+                ruby: Some(reading[..split].iter().collect()),
+            }];
+            segments.append(&mut tail);
+            return Some(segments);
+        }
+    }
+    None
+}
+
+impl DictDb {
+    /// Loads the JmdictFurigana JSON dataset into the `furigana` table.
+    ///
+    /// Each record is matched to the entries that have its text as a keb and its reading as a reb.
+    /// Records that do not match any entry are counted in the report.
+    ///
+    /// # Arguments
+    /// * `reader` - The contents of JmdictFurigana.json.
+    pub async fn load_furigana<R: Read>(&self, reader: R) -> Result<FuriganaLoadReport> {
+        let records: Vec<FuriganaRecord> = serde_json::from_reader(reader)?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "CREATE TEMP TABLE IF NOT EXISTS furigana_import (keb TEXT NOT NULL, reb TEXT NOT NULL, segments_json TEXT NOT NULL)",
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM temp.furigana_import").execute(&mut *tx).await?;
+
+        for record in &records {
+            let segments: Vec<FuriganaSegment> = record
+                .furigana
+                .iter()
+                .map(|p| FuriganaSegment { text: p.ruby.clone(), ruby: p.rt.clone() })
+                .collect();
+            sqlx::query("INSERT INTO temp.furigana_import (keb, reb, segments_json) VALUES (?1, ?2, ?3)")
+                .bind(&record.text)
+                .bind(&record.reading)
+                .bind(serde_json::to_string(&segments)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS temp.idx_furigana_import ON furigana_import(keb)")
+            .execute(&mut *tx)
+            .await?;
+
+        let stored = sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO furigana (ent_seq, keb, reb, segments_json)
+            SELECT e.ent_seq, t.keb, t.reb, t.segments_json
+            FROM entries e, json_each(e.kebs) k
+            JOIN temp.furigana_import t ON t.keb = k.value
+            WHERE e.kebs IS NOT NULL
+              AND EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE r.value = t.reb)
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let unmatched: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM temp.furigana_import t
+            WHERE NOT EXISTS (SELECT 1 FROM furigana f WHERE f.keb = t.keb AND f.reb = t.reb)
+            "#,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("DROP TABLE temp.furigana_import").execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(FuriganaLoadReport {
+            records_read: records.len(),
+            rows_stored: stored as usize,
+            unmatched: unmatched as usize,
+        })
+    }
+
+    /// Returns the furigana segmentation of a keb/reb pair of an entry.
+    ///
+    /// Uses the loaded JmdictFurigana data when available, and `align_furigana` otherwise.
+    ///
+    /// # Returns
+    /// None if there is no precomputed data and the heuristic aligner cannot align the pair.
+    pub async fn furigana_for(&self, ent_seq: i64, keb: &str, reb: &str) -> Result<Option<Vec<FuriganaSegment>>> {
+        let stored: Option<String> = sqlx::query_scalar(
+            "SELECT segments_json FROM furigana WHERE ent_seq = ?1 AND keb = ?2 AND reb = ?3",
+        )
+        .bind(ent_seq)
+        .bind(keb)
+        .bind(reb)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match stored {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(align_furigana(keb, reb)),
+        }
+    }
+}
+
+impl EntryParsed {
+    /// The reading of the whole of `keb`, one of the entry's kebs: the first reb that is not
+    /// restricted (re_restr) to other kebs, e.g. きをつける for 気を付ける.
+    ///
+    /// # Returns
+    /// None if `keb` is not a keb of the entry, or every reb is restricted to other kebs.
+    pub fn reading_for(&self, keb: &str) -> Option<&str> {
+        if !self.kebs.iter().flatten().any(|k| k == keb) {
+            return None;
+        }
+        self.rebs
+            .iter()
+            .enumerate()
+            .find(|&(i, _)| self.re_restr.get(i).is_none_or(|restr| restr.is_empty() || restr.iter().any(|k| k == keb)))
+            .map(|(_, reb)| reb.as_str())
+    }
+
+    /// Returns the furigana segmentation of the primary keb and its reading (`reading_for`),
+    /// using the heuristic aligner.
+    ///
+    /// Kana-only entries give a single segment without ruby. Use `DictDb::furigana_for`
+    /// to prefer the precomputed JmdictFurigana data.
+    pub fn furigana(&self) -> Option<Vec<FuriganaSegment>> {
+        match self.kebs.as_ref().and_then(|k| k.first()) {
+            Some(keb) => align_furigana(keb, self.reading_for(keb)?),
+            None => Some(vec![FuriganaSegment { text: self.rebs.first()?.clone(), ruby: None }]),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection, SqliteConnection};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use crate::{DictDb, DictError, Result};
+
+/// The build id of the database file, random for every newly created file.
+pub(crate) const BUILD_ID_SQL: &str = "SELECT value FROM meta WHERE key = 'build_id'";
+
+/// The build id of a database file that has none stored, e.g. one built by an older
+/// version and opened read-only: made from the file's inode and modification time, which
+/// change when the file is replaced or written.
+pub(crate) fn file_build_id(path: &Path) -> Option<String> {
+    let meta = std::fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    #[cfg(unix)]
+    let file = std::os::unix::fs::MetadataExt::ino(&meta);
+    #[cfg(not(unix))]
+    let file = meta.len();
+    Some(format!("file-{:x}-{:x}", file, modified))
+}
+
+/// The stored build id of the database on `conn`, or the `file_build_id` of `path` if it has
+/// none (or no meta table).
+pub(crate) async fn read_build_id(conn: &mut SqliteConnection, path: Option<&Path>) -> Option<String> {
+    let stored: Option<String> = sqlx::query_scalar(BUILD_ID_SQL).fetch_optional(conn).await.ok().flatten();
+    stored.or_else(|| file_build_id(path?))
+}
+
+/// What `DictDb::check_generation` compares against.
+#[derive(Debug)]
+pub(crate) struct GenerationCheck {
+    /// The database URL, None for in-memory databases, which no other process can replace.
+    url: Option<String>,
+    /// The build id read on connect.
+    build_id: String,
+    /// How often searches check, None to only check when asked.
+    interval: Option<Duration>,
+    last_checked: Mutex<Instant>,
+}
+
+impl GenerationCheck {
+    pub(crate) fn new(url: &str, build_id: String, interval: Option<Duration>) -> Self {
+        Self {
+            url: (!url.contains(":memory:")).then(|| url.to_string()),
+            build_id,
+            interval,
+            last_checked: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// The database file, None for in-memory databases.
+    pub(crate) fn db_path(&self) -> Option<PathBuf> {
+        let options = SqliteConnectOptions::from_str(self.url.as_deref()?).ok()?;
+        Some(options.get_filename().to_path_buf())
+    }
+}
+
+impl DictDb {
+    /// The random id the database file got when it was created, e.g. to tell which build a
+    /// server is reading. Read once on connect; a file without one that is opened read-only
+    /// gets an id made from its inode and modification time.
+    pub fn build_id(&self) -> &str {
+        &self.generation.build_id
+    }
+
+    /// Checks that the database file is still the one this DictDb connected to.
+    ///
+    /// When another process rebuilds the database, e.g. with `build_db`, the file is
+    /// replaced, and the connections of the pool keep reading the old one or fail halfway.
+    /// This opens the file anew and compares its `build_id`. Searches also check every
+    /// `DictDbOptions::check_generation_every` and after an error.
+    ///
+    /// # Errors
+    /// `DictError::DatabaseReplaced` if the file was replaced or removed: connect again, or
+    /// see `ReloadableDictDb::reload_if_replaced`.
+    pub async fn check_generation(&self) -> Result<()> {
+        let Some(url) = &self.generation.url else {
+            return Ok(());
+        };
+        *self.generation.last_checked.lock().unwrap() = Instant::now();
+        let replaced = || DictError::DatabaseReplaced(url.clone()).into();
+        // A database that cannot be opened is not ours either
+        let Ok(mut conn) = SqliteConnectOptions::from_str(url)?.read_only(true).connect().await else {
+            return Err(replaced());
+        };
+        let found = read_build_id(&mut conn, self.generation.db_path().as_deref()).await;
+        conn.close().await?;
+        match found {
+            Some(found) if found == self.generation.build_id => Ok(()),
+            _ => Err(replaced()),
+        }
+    }
+
+    /// Runs `check_generation` if `DictDbOptions::check_generation_every` has passed since
+    /// the last check.
+    pub(crate) async fn check_generation_if_due(&self) -> Result<()> {
+        let Some(interval) = self.generation.interval else {
+            return Ok(());
+        };
+        let due = self.generation.last_checked.lock().unwrap().elapsed() >= interval;
+        if due {
+            self.check_generation().await?;
+        }
+        Ok(())
+    }
+
+    /// The result of a search, with its error replaced by `DictError::DatabaseReplaced` if
+    /// the failure comes from the database having been replaced, when periodic checks are on.
+    pub(crate) async fn explain_failure<T>(&self, result: Result<T>) -> Result<T> {
+        match result {
+            Err(err) if self.generation.interval.is_some() && err.downcast_ref::<DictError>().is_none() => {
+                self.check_generation().await?;
+                Err(err)
+            }
+            result => result,
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use tokio::sync::OnceCell;
+
+use crate::{DictDb, DictDbOptions, DictError, Result};
+
+static GLOBAL: OnceCell<DictDb> = OnceCell::const_new();
+
+impl DictDb {
+    /// Connects the process-wide instance returned by `global`.
+    ///
+    /// This is optional sugar for apps that would rather not pass a DictDb around;
+    /// the instance API stays the primary one. Concurrent callers connect only once.
+    ///
+    /// # Errors
+    /// `DictError::GlobalAlreadyInitialized` if it was initialized before (or by a concurrent
+    /// call), and any connection error, after which initialization can be tried again.
+    pub async fn init_global(opts: DictDbOptions) -> Result<&'static DictDb> {
+        let mut initialized_here = false;
+        let db = GLOBAL
+            .get_or_try_init(|| async {
+                initialized_here = true;
+                DictDb::connect_with(opts).await
+            })
+            .await?;
+        if !initialized_here {
+            return Err(DictError::GlobalAlreadyInitialized.into());
+        }
+        Ok(db)
+    }
+
+    /// Returns the instance connected by `init_global`.
+    ///
+    /// # Errors
+    /// `DictError::GlobalNotInitialized` if `init_global` has not succeeded yet.
+    pub fn global() -> Result<&'static DictDb> {
+        GLOBAL.get().ok_or_else(|| DictError::GlobalNotInitialized.into())
+    }
+}
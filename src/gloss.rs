@@ -0,0 +1,504 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, QueryBuilder, Sqlite};
+use std::collections::BTreeMap;
+
+use crate::guards::non_empty;
+use crate::{porter_stem, DictDb, DictError, EntryParsed, EntryRow, Result, DEFAULT_LANG};
+
+/// Trims a gloss and collapses runs of whitespace into single spaces.
+pub fn clean_gloss(gloss: &str) -> String {
+    gloss.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Unicode case folding, so strings that differ only in case compare equal: "CAFÉ" and
+/// "café" both become "café", "Straße" becomes "strasse".
+///
+/// This is lowercasing plus the full case foldings that lowercasing misses (ß, ς, the
+/// Latin ligatures); it does not normalize composed and decomposed accents.
+pub fn fold_case(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            'ﬀ' => folded.push_str("ff"),
+            'ﬁ' => folded.push_str("fi"),
+            'ﬂ' => folded.push_str("fl"),
+            'ﬃ' => folded.push_str("ffi"),
+            'ﬄ' => folded.push_str("ffl"),
+            'ﬅ' | 'ﬆ' => folded.push_str("st"),
+            _ => folded.extend(c.to_lowercase()),
+        }
+    }
+    folded
+}
+
+/// The case-insensitive form of a gloss stored at build time, `fold_case` of the cleaned gloss.
+pub fn fold_gloss(gloss: &str) -> String {
+    fold_case(&clean_gloss(gloss))
+}
+
+/// The form of a gloss used by reverse lookup: cleaned, case-folded, without surrounding
+/// parentheses and trailing periods, so "Coffee", "coffee." and " (coffee)" are all "coffee".
+pub fn normalize_gloss(gloss: &str) -> String {
+    let mut s = fold_gloss(gloss);
+    loop {
+        let trimmed = s.trim_end_matches('.').trim();
+        let trimmed = match trimmed.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+            // Only if the parentheses enclose the whole gloss, not "(to be) large"
+            Some(inner) if !inner.contains(['(', ')']) => inner.trim(),
+            _ => trimmed,
+        };
+        if trimmed.len() == s.len() {
+            return s;
+        }
+        s = trimmed.to_string();
+    }
+}
+
+/// The words of a gloss for keyword search: case-folded and split at everything that is
+/// not a letter or digit, so "Fire-engine (vehicle)" is `["fire", "engine", "vehicle"]`.
+pub fn gloss_words(gloss: &str) -> Vec<String> {
+    fold_case(gloss).split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_string).collect()
+}
+
+/// A keyword query for `DictDb::search_entries_by_gloss_query`.
+///
+/// A sense matches if each word of `all` is a word of one of its glosses, and the words of
+/// `phrase` follow each other in a single gloss. Punctuation between words is ignored, so the
+/// phrase "engine vehicle" matches "fire engine (vehicle)".
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlossQuery {
+    /// Words that must all occur, in any order.
+    pub all: Vec<String>,
+    /// Words that must occur together in this order.
+    pub phrase: Option<String>,
+    /// Compare the words of the query and the glosses by their `porter_stem`, so "running"
+    /// matches "to run" and "dictionaries" matches "dictionary". Irregular forms don't
+    /// stem alike ("ate" is not found by "eat"), and unrelated words sometimes do
+    /// ("university" and "universe"), hence off by default.
+    pub stemmed: bool,
+    /// Only match senses up to this `sense_order`, e.g. 0 for the first sense, where the
+    /// main meaning of a word usually is.
+    pub max_sense_order: Option<i64>,
+    /// Order entries matching in an earlier sense first, then by frequency bucket; the
+    /// later matches stay in the results.
+    pub boost_early_senses: bool,
+}
+
+impl GlossQuery {
+    /// Parses the query syntax: words separated by spaces must all occur, a part in double
+    /// quotes is the phrase, e.g. `"fire engine" red`. Words of further quoted parts are
+    /// added to `all`, an unclosed quote runs to the end.
+    pub fn parse(query: &str) -> Self {
+        let mut parsed = GlossQuery::default();
+        for (i, part) in query.split('"').enumerate() {
+            let quoted = i % 2 == 1;
+            if quoted && parsed.phrase.is_none() && !gloss_words(part).is_empty() {
+                parsed.phrase = Some(part.trim().to_string());
+            } else {
+                parsed.all.extend(gloss_words(part));
+            }
+        }
+        parsed
+    }
+
+    /// Turns stemming on or off, see `stemmed`.
+    pub fn stemmed(mut self, stemmed: bool) -> Self {
+        self.stemmed = stemmed;
+        self
+    }
+
+    /// Only matches senses up to `max_sense_order`, see there.
+    pub fn max_sense_order(mut self, max_sense_order: i64) -> Self {
+        self.max_sense_order = Some(max_sense_order);
+        self
+    }
+
+    /// Turns ordering by the first matching sense on or off, see `boost_early_senses`.
+    pub fn boost_early_senses(mut self, boost: bool) -> Self {
+        self.boost_early_senses = boost;
+        self
+    }
+
+    /// The words of a query part or a gloss as compared: stemmed if the query is.
+    fn words(&self, text: &str) -> Vec<String> {
+        let words = gloss_words(text);
+        if self.stemmed {
+            words.iter().map(|w| porter_stem(w)).collect()
+        } else {
+            words
+        }
+    }
+
+    /// Returns true if the query has no words, it would match nothing.
+    pub fn is_empty(&self) -> bool {
+        self.all.iter().all(|w| gloss_words(w).is_empty()) && self.phrase.as_deref().is_none_or(|p| gloss_words(p).is_empty())
+    }
+
+    /// Returns true if a sense with these glosses, as `words`, matches.
+    fn matches(&self, glosses: &[Vec<String>]) -> bool {
+        let all_found = self.all.iter().flat_map(|w| self.words(w)).all(|w| glosses.iter().any(|words| words.contains(&w)));
+        let phrase_found = match &self.phrase {
+            Some(phrase) => {
+                let phrase = self.words(phrase);
+                phrase.is_empty() || glosses.iter().any(|words| words.windows(phrase.len()).any(|w| w == phrase.as_slice()))
+            }
+            None => true,
+        };
+        all_found && phrase_found
+    }
+}
+
+/// The outcome of `DictDb::normalize_glosses`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlossNormalizeReport {
+    /// How many senses were processed.
+    pub senses: usize,
+    /// Glosses dropped because an earlier gloss of the same sense normalizes the same way.
+    pub duplicates_removed: usize,
+}
+
+/// A reverse lookup result together with where the gloss matched.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct GlossMatch {
+    pub entry: EntryParsed,
+    /// The `sense_order` of every sense with a matching gloss, ascending.
+    pub matched_senses: Vec<i64>,
+    /// The first matching gloss, as displayed.
+    pub matched_gloss: String,
+    /// The byte range of the query within `matched_gloss` (compared case-insensitively),
+    /// e.g. to bold "coffee" in "(coffee).". None if the gloss differs by more than case.
+    pub highlight: Option<(usize, usize)>,
+}
+
+/// Finds `needle`, already case-folded, in `haystack` ignoring case, as a byte range of `haystack`.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return None;
+    }
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut expected = needle.chars().peekable();
+        for (offset, c) in haystack[start..].char_indices() {
+            for folded in fold_case(c.encode_utf8(&mut [0; 4])).chars() {
+                if expected.next() != Some(folded) {
+                    return None;
+                }
+            }
+            if expected.peek().is_none() {
+                return Some((start, start + offset + c.len_utf8()));
+            }
+        }
+        None
+    })
+}
+
+/// Where a query matched a gloss, see `EntryParsed::find_senses_matching`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MatchSpan {
+    /// The gloss within `SenseParsed::gloss`.
+    pub gloss_index: usize,
+    /// Byte offsets into the gloss, for slicing it in Rust: `&gloss[byte_start..byte_end]`.
+    pub byte_start: usize,
+    pub byte_end: usize,
+    /// The same range in chars (Unicode scalar values), e.g. for a UI counting characters.
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+/// Every non-overlapping occurrence of `needle`, already case-folded, in `haystack`
+/// ignoring case, as byte ranges of `haystack`.
+fn find_all_ignore_case(haystack: &str, needle: &str) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some((start, end)) = find_ignore_case(&haystack[from..], needle) {
+        found.push((from + start, from + end));
+        from += end;
+    }
+    found
+}
+
+#[derive(Debug, FromRow)]
+struct GlossMatchRow {
+    #[sqlx(flatten)]
+    entry: EntryRow,
+    matched_senses: String, // JSON array of sense orders
+    matched_gloss: String,
+}
+
+#[derive(Debug, FromRow)]
+struct GlossRow {
+    rowid: i64,
+    sense_id: i64,
+    lang: String,
+    text: String,
+}
+
+impl DictDb {
+    /// The optional build-time normalization pass over all glosses.
+    ///
+    /// Cleans the whitespace of every gloss, drops glosses that duplicate an earlier gloss of
+    /// the same sense and language once normalized, and stores the normalized forms next to
+    /// the originals for `search_entries_by_gloss`. The displayed glosses keep their case and
+    /// punctuation. Run it again after patching entries, their glosses are not normalized on insert.
+    pub async fn normalize_glosses(&self) -> Result<GlossNormalizeReport> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let rows = sqlx::query_as::<_, GlossRow>(
+                "SELECT rowid, sense_id, lang, text FROM glosses ORDER BY sense_id, lang, position",
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let senses: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM senses").fetch_one(&mut *tx).await?;
+            let mut report = GlossNormalizeReport { senses: senses as usize, ..Default::default() };
+            // The normalized glosses seen so far in the current sense and language
+            let mut current: Option<(i64, String)> = None;
+            let mut seen: Vec<String> = Vec::new();
+            for row in rows {
+                let key = (row.sense_id, row.lang);
+                if current.as_ref() != Some(&key) {
+                    current = Some(key);
+                    seen.clear();
+                }
+                let norm = normalize_gloss(&row.text);
+                if seen.contains(&norm) {
+                    sqlx::query("DELETE FROM glosses WHERE rowid = ?1").bind(row.rowid).execute(&mut *tx).await?;
+                    report.duplicates_removed += 1;
+                    continue;
+                }
+                sqlx::query("UPDATE glosses SET text = ?1, folded = ?2, norm = ?3 WHERE rowid = ?4")
+                    .bind(clean_gloss(&row.text))
+                    .bind(fold_gloss(&row.text))
+                    .bind(&norm)
+                    .bind(row.rowid)
+                    .execute(&mut *tx)
+                    .await?;
+                seen.push(norm);
+            }
+
+            // Bring the displayed English glosses in line
+            sqlx::query(
+                r#"
+                UPDATE senses SET gloss = (
+                  SELECT json_group_array(text) FROM (
+                    SELECT text FROM glosses WHERE sense_id = senses.id AND lang = ?1 ORDER BY position
+                  )
+                )
+                "#,
+            )
+            .bind(DEFAULT_LANG)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            Ok(report)
+        })
+        .await
+    }
+
+    /// Reverse lookup: entries with a gloss in `lang` equal to `gloss` once both are normalized,
+    /// e.g. "Coffee", "coffee." and " coffee" all find コーヒー. Use `DEFAULT_LANG` ("eng")
+    /// for English, the only language of JMdict_e.
+    ///
+    /// Glosses the normalization pass has not processed are compared case-insensitively
+    /// as they are, folding the query like the stored glosses (see `fold_case`). Results are ordered by frequency bucket.
+    pub async fn search_entries_by_gloss(&self, gloss: &str, lang: &str) -> Result<Vec<EntryParsed>> {
+        self.observe("search_entries_by_gloss", gloss, || format!("lang={}", lang), async {
+            let query = normalize_gloss(gloss);
+            non_empty(&query)?;
+            let rows = sqlx::query_as::<_, EntryRow>(
+                r#"
+                SELECT * FROM entries
+                WHERE ent_seq IN (
+                    SELECT s.ent_seq FROM glosses g JOIN senses s ON s.id = g.sense_id
+                    WHERE g.lang = ?2 AND g.norm = ?1
+                    UNION
+                    SELECT s.ent_seq FROM glosses g JOIN senses s ON s.id = g.sense_id
+                    WHERE g.lang = ?2 AND g.norm IS NULL AND g.folded = ?1
+                )
+                ORDER BY nf_bucket IS NULL, nf_bucket, ent_seq
+                "#,
+            )
+            .bind(query)
+            .bind(lang)
+            .fetch_all(&self.pool)
+            .await?;
+            self.hydrate_entries(rows).await
+        })
+        .await
+    }
+
+    /// `search_entries_by_gloss`, also telling which senses matched and with which gloss.
+    /// `Collation::sort_by` puts them in the order of `matched_gloss` instead of frequency.
+    pub async fn search_gloss_matches(&self, gloss: &str, lang: &str) -> Result<Vec<GlossMatch>> {
+        self.observe("search_gloss_matches", gloss, || format!("lang={}", lang), async {
+            let query = normalize_gloss(gloss);
+            non_empty(&query)?;
+            let rows = sqlx::query_as::<_, GlossMatchRow>(
+                r#"
+                WITH m AS (
+                    SELECT s.ent_seq, s.sense_order, g.position, g.text FROM glosses g JOIN senses s ON s.id = g.sense_id
+                    WHERE g.lang = ?2 AND g.norm = ?1
+                    UNION ALL
+                    SELECT s.ent_seq, s.sense_order, g.position, g.text FROM glosses g JOIN senses s ON s.id = g.sense_id
+                    WHERE g.lang = ?2 AND g.norm IS NULL AND g.folded = ?1
+                )
+                SELECT e.*,
+                    (SELECT json_group_array(sense_order) FROM (
+                        SELECT DISTINCT sense_order FROM m WHERE m.ent_seq = e.ent_seq ORDER BY sense_order
+                    )) AS matched_senses,
+                    (SELECT text FROM m WHERE m.ent_seq = e.ent_seq ORDER BY sense_order, position LIMIT 1) AS matched_gloss
+                FROM entries e
+                WHERE e.ent_seq IN (SELECT ent_seq FROM m)
+                ORDER BY e.nf_bucket IS NULL, e.nf_bucket, e.ent_seq
+                "#,
+            )
+            .bind(&query)
+            .bind(lang)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let mut conn = self.pool.acquire().await?;
+            let mut matches = Vec::with_capacity(rows.len());
+            for row in rows {
+                let highlight = find_ignore_case(&row.matched_gloss, &query);
+                matches.push(GlossMatch {
+                    entry: Self::hydrate_entry_with(&mut conn, row.entry).await?,
+                    matched_senses: serde_json::from_str(&row.matched_senses)?,
+                    matched_gloss: row.matched_gloss,
+                    highlight,
+                });
+            }
+            Ok(matches)
+        })
+        .await
+    }
+
+    /// Keyword reverse lookup: entries with a sense whose glosses in `lang` contain all the
+    /// words of the query, e.g. `GlossQuery::parse("fire engine")` finds 消防車 but not every
+    /// entry with "fire". Words are compared whole and case-insensitively, see `GlossQuery`.
+    ///
+    /// Results are ordered by frequency bucket, like `search_entries_by_gloss`, or by the
+    /// first sense that matched with `GlossQuery::boost_early_senses`.
+    pub async fn search_entries_by_gloss_query(&self, query: &GlossQuery, lang: &str) -> Result<Vec<EntryParsed>> {
+        self.observe("search_entries_by_gloss_query", &format!("{:?}", query), || format!("lang={}", lang), async {
+            let mut words: Vec<String> = query.all.iter().flat_map(|w| gloss_words(w)).collect();
+            words.extend(query.phrase.iter().flat_map(|p| gloss_words(p)));
+            words.sort();
+            words.dedup();
+            if words.is_empty() {
+                return Err(DictError::EmptyQuery.into());
+            }
+
+            // The start of the words glossed with the same stem, e.g. "happ" for "happy" and
+            // "happiness"; stemming turns a final y into i
+            if query.stemmed {
+                words = words
+                    .iter()
+                    .map(|w| porter_stem(w).trim_end_matches(['e', 'i']).to_string())
+                    .filter(|w| !w.is_empty())
+                    .collect();
+            }
+
+            // Senses with a gloss containing each word as a substring, the words are checked below
+            let mut qb = QueryBuilder::<Sqlite>::new(
+                "SELECT s.id, s.ent_seq, s.sense_order, g.folded FROM senses s JOIN glosses g ON g.sense_id = s.id AND g.lang = ",
+            );
+            qb.push_bind(lang);
+            qb.push(" WHERE 1");
+            if let Some(max) = query.max_sense_order {
+                qb.push(" AND s.sense_order <= ");
+                qb.push_bind(max);
+            }
+            for word in &words {
+                qb.push(" AND EXISTS (SELECT 1 FROM glosses t WHERE t.sense_id = s.id AND t.lang = ");
+                qb.push_bind(lang);
+                qb.push(" AND instr(t.folded, ");
+                qb.push_bind(word.clone());
+                qb.push(") > 0)");
+            }
+            qb.push(" ORDER BY s.id, g.position");
+            let rows: Vec<(i64, i64, i64, String)> = qb.build_query_as().fetch_all(&self.pool).await?;
+
+            let mut senses: BTreeMap<i64, (i64, i64, Vec<Vec<String>>)> = BTreeMap::new();
+            for (sense_id, ent_seq, sense_order, folded) in rows {
+                senses.entry(sense_id).or_insert_with(|| (ent_seq, sense_order, Vec::new())).2.push(query.words(&folded));
+            }
+            // The first matching sense of each entry
+            let mut first_match: BTreeMap<i64, i64> = BTreeMap::new();
+            for (ent_seq, sense_order, _) in senses.into_values().filter(|(_, _, glosses)| query.matches(glosses)) {
+                first_match.entry(ent_seq).and_modify(|first| *first = sense_order.min(*first)).or_insert(sense_order);
+            }
+            if first_match.is_empty() {
+                return Ok(Vec::new());
+            }
+            let ent_seqs: Vec<i64> = first_match.keys().copied().collect();
+
+            let mut rows = sqlx::query_as::<_, EntryRow>(
+                "SELECT * FROM entries WHERE ent_seq IN (SELECT value FROM json_each(?1))
+                 ORDER BY nf_bucket IS NULL, nf_bucket, ent_seq",
+            )
+            .bind(serde_json::to_string(&ent_seqs)?)
+            .fetch_all(&self.pool)
+            .await?;
+            if query.boost_early_senses {
+                rows.sort_by_key(|row| first_match[&row.ent_seq]);
+            }
+            self.hydrate_entries(rows).await
+        })
+        .await
+    }
+
+    /// Fills in the case-folded form of glosses stored before it existed.
+    pub(crate) async fn fill_folded_glosses(&self) -> Result<()> {
+        let rows: Vec<(i64, String)> = sqlx::query_as("SELECT rowid, text FROM glosses WHERE folded IS NULL")
+            .fetch_all(&self.pool)
+            .await?;
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await?;
+        for (rowid, text) in rows {
+            sqlx::query("UPDATE glosses SET folded = ?1 WHERE rowid = ?2")
+                .bind(fold_gloss(&text))
+                .bind(rowid)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The languages of the stored glosses, e.g. `["eng"]` for JMdict_e.
+    pub async fn available_languages(&self) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar("SELECT DISTINCT lang FROM glosses ORDER BY lang")
+            .fetch_all(&self.pool)
+            .await?)
+    }
+}
+
+impl EntryParsed {
+    /// The senses with an English gloss containing `query`, ignoring case (see
+    /// `fold_case`), with where it occurs, e.g. to highlight the sense of 食べる that
+    /// matched "salary" on a detail page. Works on the entry as loaded, without the
+    /// database.
+    ///
+    /// # Returns
+    /// One (index into `senses`, span) pair per occurrence, in sense and gloss order.
+    /// Nothing for a query that is empty or only whitespace.
+    pub fn find_senses_matching(&self, query: &str) -> Vec<(usize, MatchSpan)> {
+        let needle = fold_case(query.trim());
+        let mut matches = Vec::new();
+        for (sense_index, sense) in self.senses.iter().enumerate() {
+            for (gloss_index, gloss) in sense.gloss.iter().enumerate() {
+                for (byte_start, byte_end) in find_all_ignore_case(gloss, &needle) {
+                    let char_start = gloss[..byte_start].chars().count();
+                    let char_end = char_start + gloss[byte_start..byte_end].chars().count();
+                    matches.push((sense_index, MatchSpan { gloss_index, byte_start, byte_end, char_start, char_end }));
+                }
+            }
+        }
+        matches
+    }
+}
@@ -0,0 +1,62 @@
+//! Rejecting queries that can't be searched, see `DictError::EmptyQuery` and
+//! `DictError::QueryTooShort`.
+//!
+//! A search returning an empty Vec found nothing; a query with nothing to search for, or too
+//! short for a substring search to be worth scanning every headword or sentence, is an error
+//! instead, so a UI can tell "no results" from "type more".
+
+use crate::{is_kanji, DictDb, DictError, Result};
+
+/// The shortest queries the substring searches accept, see `DictDbOptions::query_guards`.
+///
+/// Applies to `search_expressions_containing`, `compounds_containing` and
+/// `search_sentences`, which can't use an index for kana or Latin text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryGuards {
+    /// In characters for queries without kanji, 2 by default: a single kana appears in
+    /// most headwords.
+    pub min_substring_len: usize,
+    /// In characters for queries with a kanji, 1 by default: the kanji index narrows the
+    /// search to the entries with it.
+    pub min_kanji_substring_len: usize,
+}
+
+impl Default for QueryGuards {
+    fn default() -> Self {
+        Self { min_substring_len: 2, min_kanji_substring_len: 1 }
+    }
+}
+
+impl QueryGuards {
+    /// No minimum beyond a non-empty query.
+    pub fn none() -> Self {
+        Self { min_substring_len: 1, min_kanji_substring_len: 1 }
+    }
+
+    /// The minimum length for this substring query.
+    pub fn min_len(&self, query: &str) -> usize {
+        if query.chars().any(is_kanji) { self.min_kanji_substring_len } else { self.min_substring_len }
+    }
+}
+
+/// The query without surrounding whitespace, `DictError::EmptyQuery` if nothing is left.
+pub(crate) fn non_empty(query: &str) -> Result<&str> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(DictError::EmptyQuery.into());
+    }
+    Ok(query)
+}
+
+impl DictDb {
+    /// `non_empty`, then `DictError::QueryTooShort` if the query is shorter than the
+    /// `QueryGuards` allow for a substring search.
+    pub(crate) fn substring_query<'q>(&self, query: &'q str) -> Result<&'q str> {
+        let query = non_empty(query)?;
+        let min = self.guards.min_len(query);
+        if query.chars().count() < min {
+            return Err(DictError::QueryTooShort { min }.into());
+        }
+        Ok(query)
+    }
+}
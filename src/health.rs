@@ -0,0 +1,80 @@
+//! A cheap readiness probe for services that embed the crate, see `DictDb::health_check`.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::{DictDb, DictError, Result};
+
+/// The layout of the tables `DictDb::connect` creates, stored in the meta table. Raised when
+/// a release changes it; a file whose version differs was migrated by another version of
+/// the crate, or is not a jmdictdb database.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// The schema version of the database file, None if it has none.
+pub(crate) const SCHEMA_VERSION_SQL: &str = "SELECT CAST(value AS INTEGER) FROM meta WHERE key = 'schema_version'";
+
+/// How long `DictDb::health_check` waits before giving up.
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The result of `DictDb::health_check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Health {
+    /// The database answered and its schema is the one this version of the crate writes.
+    pub ok: bool,
+    /// The number of entries when the DictDb was first checked.
+    pub entry_count: i64,
+    /// The schema version of the file, None if it has none.
+    pub schema_version: Option<i64>,
+    /// The database file, None for in-memory databases.
+    pub db_path: Option<PathBuf>,
+    /// How long the check took.
+    pub latency: Duration,
+}
+
+/// The entry count of the first `health_check`, so that later ones stay cheap.
+#[derive(Debug, Default)]
+pub(crate) struct HealthCache {
+    entry_count: OnceLock<i64>,
+}
+
+impl DictDb {
+    /// Checks that the database can be read, e.g. for a `/healthz` endpoint.
+    ///
+    /// Runs `SELECT 1`, reads the schema version and, on the first call only, counts the
+    /// entries. All of these only read, so WAL writers don't hold them up. The check also
+    /// makes sure the file is still the one the pool opened, as `check_generation` does.
+    ///
+    /// # Errors
+    /// `DictError::DatabaseReplaced` if the file was removed or replaced since connecting,
+    /// `DictError::QueryTimeout` if the check takes longer than `HEALTH_CHECK_TIMEOUT`, e.g.
+    /// while every connection of the pool is busy.
+    pub async fn health_check(&self) -> Result<Health> {
+        let started = Instant::now();
+        match crate::rt::timeout(HEALTH_CHECK_TIMEOUT, self.health_check_inner(started)).await {
+            Some(health) => health,
+            None => Err(DictError::QueryTimeout(HEALTH_CHECK_TIMEOUT).into()),
+        }
+    }
+
+    async fn health_check_inner(&self, started: Instant) -> Result<Health> {
+        self.check_generation().await?;
+        let one: i64 = sqlx::query_scalar("SELECT 1").fetch_one(&self.pool).await?;
+        let schema_version: Option<i64> = sqlx::query_scalar(SCHEMA_VERSION_SQL).fetch_optional(&self.pool).await?;
+        let entry_count = match self.health.entry_count.get() {
+            Some(count) => *count,
+            None => {
+                let count = self.entry_count().await?;
+                *self.health.entry_count.get_or_init(|| count)
+            }
+        };
+        let db_path = self.generation.db_path();
+        Ok(Health {
+            ok: one == 1 && schema_version == Some(SCHEMA_VERSION),
+            entry_count,
+            schema_version,
+            db_path,
+            latency: started.elapsed(),
+        })
+    }
+}
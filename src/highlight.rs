@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::annotate::escape_html;
+use crate::error::DictError;
+use crate::kana::{is_kana, normalize_kana, READING_NORMALIZATION};
+use crate::ranking::priority_score;
+use crate::{is_kanji, DictDb, Result};
+
+/// The longest keb or reb `DictDb::scan_text` looks for, in characters.
+const MAX_MATCH_CHARS: usize = 16;
+
+/// The priority tags JMdict marks an entry as common with, the (P) of most dictionaries.
+pub(crate) const COMMON_TAGS: &[&str] = &["news1", "ichi1", "spec1", "spec2", "gai1"];
+
+/// What `DictDb::scan_text` looks for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanOptions {
+    /// Matches shorter than this many characters are left out, e.g. 2 to skip particles
+    /// and single kanji.
+    pub min_len: usize,
+    /// Only match the forms of common entries, see `TextMatch::common`.
+    pub common_only: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions { min_len: 1, common_only: false }
+    }
+}
+
+/// A dictionary form found in a text, see `DictDb::scan_text`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextMatch {
+    /// The byte offsets of the match in the text.
+    pub start: usize,
+    pub end: usize,
+    /// The text matched, a keb or reb of the entry.
+    pub surface: String,
+    pub ent_seq: i64,
+    /// Whether the entry has one of the priority tags JMdict marks common words with
+    /// (news1, ichi1, spec1, spec2, gai1).
+    pub common: bool,
+}
+
+/// How `DictDb::highlight_html` marks up the matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighlightOptions {
+    /// The element wrapping each match.
+    pub tag: String,
+    /// The attribute holding the match's ent_seq.
+    pub attribute: String,
+    pub scan: ScanOptions,
+}
+
+impl Default for HighlightOptions {
+    fn default() -> Self {
+        HighlightOptions { tag: "span".to_string(), attribute: "data-entseq".to_string(), scan: ScanOptions::default() }
+    }
+}
+
+/// Whether a tag or attribute name can go into markup as is: ASCII letters, digits and
+/// hyphens, starting with a letter.
+fn is_markup_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// A form of the text looked up through the indexes, see `DictDb::scan_text`.
+#[derive(Serialize)]
+struct Term {
+    term: String,
+    /// The normalized reading for kana-only forms.
+    norm: Option<String>,
+    /// The first kanji for the others.
+    ch: Option<String>,
+}
+
+/// The entry a form stands for: the most common one of those having it, as
+/// (common, priority score, nf bucket, ent_seq).
+type FormEntry = (bool, i64, Option<i64>, i64);
+
+/// Whether `a` is the better entry for a form than `b`.
+fn better(a: &FormEntry, b: &FormEntry) -> bool {
+    let key = |e: &FormEntry| (std::cmp::Reverse(e.1), e.2.unwrap_or(i64::MAX), e.3);
+    key(a) < key(b)
+}
+
+impl DictDb {
+    /// The dictionary words of a text, e.g. for highlighting or a vocabulary list, in text
+    /// order and never overlapping.
+    ///
+    /// Every substring of up to `MAX_MATCH_CHARS` characters equal to a keb, or kana-only
+    /// and equal to a reb, is a candidate; the forms are looked up through the kanji and
+    /// reading indexes as in `bulk_lookup`, the rest of the text (ASCII, punctuation,
+    /// emoji) never matches. Overlapping candidates are resolved longest first, then
+    /// leftmost first, so 日本語 wins over 日本 and 今日本 gives 今日 and 本. A form of
+    /// several entries stands for the most common one, ranked by `priority_score`, then
+    /// the nfNN bucket, then ent_seq. Conjugated forms are not matched.
+    pub async fn scan_text(&self, text: &str, opts: &ScanOptions) -> Result<Vec<TextMatch>> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut seen = HashSet::new();
+        let mut terms = Vec::new();
+        for start in 0..chars.len() {
+            let window = &chars[start..chars.len().min(start + MAX_MATCH_CHARS)];
+            for len in opts.min_len.max(1)..=window.len() {
+                let term: String = window[..len].iter().map(|&(_, c)| c).collect();
+                if !seen.insert(term.clone()) {
+                    continue;
+                }
+                if let Some(&(_, ch)) = window[..len].iter().find(|&&(_, c)| is_kanji(c)) {
+                    terms.push(Term { term, norm: None, ch: Some(ch.to_string()) });
+                } else if window[..len].iter().all(|&(_, c)| is_kana(c)) {
+                    let norm = normalize_kana(&term, READING_NORMALIZATION);
+                    terms.push(Term { term, norm: Some(norm), ch: None });
+                }
+            }
+        }
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<(String, i64, String, Option<i64>)> = sqlx::query_as(
+            r#"
+            WITH terms(term, norm, ch) AS (
+                SELECT json_extract(value, '$.term'), json_extract(value, '$.norm'), json_extract(value, '$.ch')
+                FROM json_each(?1)
+            )
+            SELECT t.term, e.ent_seq, e.priority, e.nf_bucket FROM terms t
+            JOIN reading_index r ON r.norm = t.norm
+            JOIN entries e ON e.ent_seq = r.ent_seq
+            WHERE EXISTS (SELECT 1 FROM json_each(e.rebs) je WHERE je.value = t.term)
+            UNION
+            SELECT t.term, e.ent_seq, e.priority, e.nf_bucket FROM terms t
+            JOIN kanji_index k ON k.ch = t.ch
+            JOIN entries e ON e.ent_seq = k.ent_seq
+            WHERE EXISTS (SELECT 1 FROM json_each(e.kebs) je WHERE je.value = t.term)
+            "#,
+        )
+        .bind(serde_json::to_string(&terms)?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut forms: HashMap<String, FormEntry> = HashMap::new();
+        for (term, ent_seq, priority, nf_bucket) in rows {
+            let priority: Vec<String> = serde_json::from_str(&priority)?;
+            let common = priority.iter().any(|tag| COMMON_TAGS.contains(&tag.as_str()));
+            if opts.common_only && !common {
+                continue;
+            }
+            let candidate = (common, priority_score(&priority), nf_bucket, ent_seq);
+            match forms.get(&term) {
+                Some(best) if !better(&candidate, best) => {}
+                _ => {
+                    forms.insert(term, candidate);
+                }
+            }
+        }
+
+        // Every candidate as (start, length in chars), longest first, then leftmost
+        let mut candidates = Vec::new();
+        for start in 0..chars.len() {
+            let window = &chars[start..chars.len().min(start + MAX_MATCH_CHARS)];
+            for len in opts.min_len.max(1)..=window.len() {
+                let term: String = window[..len].iter().map(|&(_, c)| c).collect();
+                if let Some(&entry) = forms.get(&term) {
+                    candidates.push((start, len, term, entry));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let mut taken = vec![false; chars.len()];
+        let mut matches = Vec::new();
+        for (start, len, surface, (common, _, _, ent_seq)) in candidates {
+            if taken[start..start + len].iter().any(|&t| t) {
+                continue;
+            }
+            taken[start..start + len].iter_mut().for_each(|t| *t = true);
+            let end = chars.get(start + len).map_or(text.len(), |&(i, _)| i);
+            matches.push(TextMatch { start: chars[start].0, end, surface, ent_seq, common });
+        }
+        matches.sort_by_key(|m| m.start);
+        Ok(matches)
+    }
+
+    /// The text as HTML with every match of `scan_text` wrapped in an element, by default
+    /// `<span data-entseq="1469800">猫</span>`. The rest of the text is escaped, so any
+    /// input gives valid markup; emoji and other characters outside the BMP pass through.
+    ///
+    /// Fails with `DictError::InvalidMarkupName` for a tag or attribute name other than ASCII
+    /// letters, digits and hyphens starting with a letter.
+    pub async fn highlight_html(&self, text: &str, opts: &HighlightOptions) -> Result<String> {
+        for name in [&opts.tag, &opts.attribute] {
+            if !is_markup_name(name) {
+                return Err(DictError::InvalidMarkupName(name.clone()).into());
+            }
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut at = 0;
+        for m in self.scan_text(text, &opts.scan).await? {
+            out.push_str(&escape_html(&text[at..m.start]));
+            out.push_str(&format!(
+                "<{tag} {attr}=\"{seq}\">{surface}</{tag}>",
+                tag = opts.tag,
+                attr = opts.attribute,
+                seq = m.ent_seq,
+                surface = escape_html(&m.surface)
+            ));
+            at = m.end;
+        }
+        out.push_str(&escape_html(&text[at..]));
+        Ok(out)
+    }
+}
@@ -0,0 +1,93 @@
+use sqlx::{FromRow, SqlitePool};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::{DictDb, EntryParsed, Result};
+
+/// An entry of the lookup history, see `DictDb::lookup_history`.
+#[derive(Debug)]
+pub struct LookupRecord {
+    pub entry: EntryParsed,
+    /// How often `record_lookup` recorded the entry.
+    pub count: i64,
+    /// The last lookup, UTC, formatted as "YYYY-MM-DD HH:MM:SS.SSS".
+    pub last_at: String,
+}
+
+#[derive(FromRow)]
+struct LookupRow {
+    ent_seq: i64,
+    count: i64,
+    last_at: String,
+}
+
+/// Lookups that could not be recorded, shared with the background writes.
+pub(crate) type LookupFailures = Arc<AtomicU64>;
+
+async fn upsert_lookup(pool: &SqlitePool, ent_seq: i64) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO lookup_stats (ent_seq, count, last_at) VALUES (?1, 1, strftime('%Y-%m-%d %H:%M:%f', 'now'))
+        ON CONFLICT(ent_seq) DO UPDATE SET count = count + 1, last_at = excluded.last_at
+        "#,
+    )
+    .bind(ent_seq)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+impl DictDb {
+    /// Records that the user looked up an entry, for `lookup_history` and the
+    /// `MatchContext::lookup_count` of custom rankers.
+    ///
+    /// The write happens in the background and this returns at once, so recording never
+    /// holds up or fails a search. A write that fails, or a call outside the async runtime,
+    /// only counts in `lookup_failures`.
+    pub fn record_lookup(&self, ent_seq: i64) {
+        let pool = self.pool.clone();
+        let failures = self.lookup_failures.clone();
+        let spawned = crate::rt::spawn_detached(async move {
+            if upsert_lookup(&pool, ent_seq).await.is_err() {
+                failures.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        if !spawned {
+            self.lookup_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// How many `record_lookup` calls could not be recorded so far.
+    pub fn lookup_failures(&self) -> u64 {
+        self.lookup_failures.load(Ordering::Relaxed)
+    }
+
+    /// The `limit` most recently looked up entries, most recent first.
+    ///
+    /// Lookups are keyed on ent_seq only, like word list items; lookups of entries removed
+    /// from JMdict are kept but skipped here.
+    pub async fn lookup_history(&self, limit: usize) -> Result<Vec<LookupRecord>> {
+        let rows = sqlx::query_as::<_, LookupRow>(
+            "SELECT ent_seq, count, last_at FROM lookup_stats ORDER BY last_at DESC, ent_seq LIMIT ?1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut records = Vec::with_capacity(rows.len());
+        for row in rows {
+            if let Some(entry) = self.entry_by_seq(row.ent_seq).await? {
+                records.push(LookupRecord { entry, count: row.count, last_at: row.last_at });
+            }
+        }
+        Ok(records)
+    }
+
+    /// Deletes the whole lookup history and returns how many entries it had.
+    pub async fn clear_history(&self) -> Result<u64> {
+        self.with_retry(move || async move {
+            let deleted = sqlx::query("DELETE FROM lookup_stats").execute(&self.pool).await?.rows_affected();
+            Ok(deleted)
+        })
+        .await
+    }
+}
@@ -0,0 +1,242 @@
+use std::io::{BufRead, Read};
+
+use sqlx::FromRow;
+
+use crate::kana::katakana_to_hiragana;
+use crate::ranking::priority_score;
+use crate::{DictDb, MatchKind, RankOptions, Result};
+
+/// A term of an imported file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportTerm {
+    /// The line of the term in a TSV file, its index in a JSON array, both starting at 1.
+    pub position: usize,
+    pub term: String,
+    /// None if the file has no reading for the term.
+    pub reading: Option<String>,
+}
+
+/// A term that more than one entry matched equally well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmbiguousTerm {
+    pub term: ImportTerm,
+    /// The entry added to the list, the one with the best priority.
+    pub chosen: i64,
+    /// The other entries, best first.
+    pub alternatives: Vec<i64>,
+}
+
+/// The outcome of importing a word list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    /// The id of the new word list.
+    pub list_id: i64,
+    /// How many terms were added to the list. Terms of the same entry count once each.
+    pub imported: usize,
+    /// Terms no entry matched, in file order.
+    pub unresolved: Vec<ImportTerm>,
+    /// Terms several entries matched, in file order.
+    pub ambiguous: Vec<AmbiguousTerm>,
+}
+
+/// An entry with the term as keb or reb and the reading as reb, before ranking.
+#[derive(FromRow)]
+struct ImportCandidate {
+    ent_seq: i64,
+    priority: String,
+    nf_bucket: Option<i64>,
+}
+
+impl DictDb {
+    /// Imports an Anki deck exported as "Notes in Plain Text" into a new word list `name`.
+    ///
+    /// The first field of each note is the term and the second, if any, its reading; HTML
+    /// tags are dropped and Anki furigana such as `食[た]べる` gives both. Empty lines and
+    /// the `#separator:tab` style header lines are skipped.
+    ///
+    /// # Returns
+    /// An ImportReport, see `import_terms` for how terms are resolved.
+    pub async fn import_anki_tsv<R: BufRead>(&self, reader: R, name: &str) -> Result<ImportReport> {
+        let mut terms = Vec::new();
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t').map(anki_field);
+            let Some(first) = fields.next().filter(|f| !f.is_empty()) else {
+                continue;
+            };
+            let (term, furigana) = split_furigana(&first);
+            let reading = match fields.next().filter(|f| !f.is_empty()) {
+                Some(second) => Some(split_furigana(&second).1.unwrap_or_else(|| second.clone())),
+                None => furigana,
+            };
+            terms.push(ImportTerm { position: line_no + 1, term, reading });
+        }
+        self.import_terms(name, terms).await
+    }
+
+    /// Imports a Yomitan word list into a new word list `name`.
+    ///
+    /// The JSON is an array of terms, each either an array starting with the term and its
+    /// reading, as the rows of a Yomitan term bank, or an object with a `term` (or
+    /// `expression`) and a `reading`. An empty reading is taken as none.
+    ///
+    /// # Returns
+    /// An ImportReport, see `import_terms` for how terms are resolved.
+    pub async fn import_yomitan_json<R: Read>(&self, reader: R, name: &str) -> Result<ImportReport> {
+        let items: Vec<serde_json::Value> = serde_json::from_reader(reader)?;
+        let mut terms = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let (term, reading) = match item {
+                serde_json::Value::Array(row) => (row.first(), row.get(1)),
+                serde_json::Value::Object(object) => {
+                    (object.get("term").or_else(|| object.get("expression")), object.get("reading"))
+                }
+                _ => (None, None),
+            };
+            let term = term
+                .and_then(|t| t.as_str())
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .ok_or_else(|| format!("item {}: expected a term", index + 1))?;
+            let reading = reading.and_then(|r| r.as_str()).map(str::trim).filter(|r| !r.is_empty());
+            terms.push(ImportTerm { position: index + 1, term: term.to_string(), reading: reading.map(str::to_string) });
+        }
+        self.import_terms(name, terms).await
+    }
+
+    /// Creates the word list `name` with the entries of `terms`.
+    ///
+    /// A term resolves to the entries that have it as keb or reb and its reading as reb. If
+    /// there are none, the unified search is tried, `search_ranked_outcome`, keeping its exact
+    /// matches with a reb equal to the reading, katakana and hiragana alike. Of several
+    /// entries the one with the best priority is added: `priority_score` first, then the
+    /// nfNN bucket, then ent_seq; the others are listed in `ImportReport::ambiguous`.
+    pub async fn import_terms(&self, name: &str, terms: Vec<ImportTerm>) -> Result<ImportReport> {
+        let mut resolved = Vec::new();
+        let mut unresolved = Vec::new();
+        let mut ambiguous = Vec::new();
+        for term in terms {
+            let mut matches = self.resolve_import_term(&term).await?;
+            if matches.is_empty() {
+                unresolved.push(term);
+                continue;
+            }
+            let chosen = matches.remove(0);
+            resolved.push(chosen);
+            if !matches.is_empty() {
+                ambiguous.push(AmbiguousTerm { term, chosen, alternatives: matches });
+            }
+        }
+
+        let list_id = self
+            .with_retry(|| async {
+                let mut tx = self.pool.begin().await?;
+                let id = Self::create_list_tx(&mut tx, name).await?;
+                for &ent_seq in &resolved {
+                    Self::add_to_list_tx(&mut tx, id, ent_seq, None).await?;
+                }
+                tx.commit().await?;
+                Ok(id)
+            })
+            .await?;
+        Ok(ImportReport { list_id, imported: resolved.len(), unresolved, ambiguous })
+    }
+
+    /// The entries a term resolves to, best first.
+    async fn resolve_import_term(&self, term: &ImportTerm) -> Result<Vec<i64>> {
+        let candidates = sqlx::query_as::<_, ImportCandidate>(
+            r#"
+            SELECT e.ent_seq, e.priority, e.nf_bucket FROM entries e
+            WHERE (EXISTS (SELECT 1 FROM json_each(e.kebs) k WHERE k.value = ?1)
+                   OR EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE r.value = ?1))
+              AND (?2 IS NULL OR EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE r.value = ?2))
+            "#,
+        )
+        .bind(&term.term)
+        .bind(&term.reading)
+        .fetch_all(&self.pool)
+        .await?;
+
+        if !candidates.is_empty() {
+            let mut ranked = candidates
+                .into_iter()
+                .map(|c| Ok((priority_score(&serde_json::from_str::<Vec<String>>(&c.priority)?), c)))
+                .collect::<Result<Vec<_>>>()?;
+            ranked.sort_by(|(a_score, a), (b_score, b)| {
+                b_score
+                    .cmp(a_score)
+                    .then(a.nf_bucket.unwrap_or(i64::MAX).cmp(&b.nf_bucket.unwrap_or(i64::MAX)))
+                    .then(a.ent_seq.cmp(&b.ent_seq))
+            });
+            return Ok(ranked.into_iter().map(|(_, c)| c.ent_seq).collect());
+        }
+
+        let reading = term.reading.as_deref().map(katakana_to_hiragana);
+        let outcome = self.search_ranked_outcome(&term.term, &RankOptions::default()).await?;
+        Ok(outcome
+            .results
+            .iter()
+            .filter(|r| r.match_kind == MatchKind::Exact)
+            .filter(|r| match &reading {
+                Some(reading) => r.entry.rebs.iter().any(|reb| katakana_to_hiragana(reb) == *reading),
+                None => true,
+            })
+            .map(|r| r.entry.ent_seq)
+            .collect())
+    }
+}
+
+/// A field of an Anki plain text export: unquoted, without HTML tags and entities.
+fn anki_field(field: &str) -> String {
+    let field = field.trim();
+    let field = match field.strip_prefix('"').and_then(|f| f.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => field.to_string(),
+    };
+    let mut text = String::with_capacity(field.len());
+    let mut in_tag = false;
+    for c in field.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&").trim().to_string()
+}
+
+/// Splits Anki furigana, e.g. `食[た]べ 物[もの]`, into the text and its reading, None for
+/// text without furigana. The reading in brackets stands for the text before it back to the
+/// last space or closing bracket.
+fn split_furigana(s: &str) -> (String, Option<String>) {
+    if !s.contains('[') {
+        return (s.to_string(), None);
+    }
+    let mut text = String::new();
+    let mut reading = String::new();
+    // The characters the next bracket reads, not yet in the reading
+    let mut base = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                text.push_str(&base);
+                base.clear();
+                reading.extend(chars.by_ref().take_while(|&c| c != ']'));
+            }
+            ' ' => {
+                text.push_str(&base);
+                reading.push_str(&base);
+                base.clear();
+            }
+            _ => base.push(c),
+        }
+    }
+    text.push_str(&base);
+    reading.push_str(&base);
+    (text, Some(reading))
+}
@@ -0,0 +1,83 @@
+//! Storing many entries in chunks, each committed on its own, see `DictDb::insert_entries_with`.
+
+use crate::{DictDb, DictError, Entry, Result, SOURCE_JMDICT};
+
+/// Entries committed per transaction by `insert_entries`.
+pub const DEFAULT_INSERT_CHUNK: usize = 5000;
+
+/// Options for `DictDb::insert_entries_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsertOptions {
+    /// Entries per transaction, `DEFAULT_INSERT_CHUNK` by default. A failure only rolls
+    /// back its own chunk, and the journal only grows to the size of one chunk.
+    pub chunk_size: usize,
+    /// Store all entries in one transaction, so that a failure leaves the database as it
+    /// was; the progress callback is called once, after the commit.
+    pub single_transaction: bool,
+}
+
+impl Default for InsertOptions {
+    fn default() -> Self {
+        Self { chunk_size: DEFAULT_INSERT_CHUNK, single_transaction: false }
+    }
+}
+
+/// How far `DictDb::insert_entries_with` got, passed to its callback after each commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsertProgress {
+    /// The transactions committed so far.
+    pub chunks_committed: usize,
+    /// The entries stored by them, the first ones of the slice.
+    pub entries_committed: usize,
+    /// The length of the slice.
+    pub entries_total: usize,
+}
+
+impl DictDb {
+    /// Inserts or replaces the entries a chunk at a time, see `InsertOptions`, calling
+    /// `on_progress` after each chunk is committed, e.g. to show a progress bar.
+    ///
+    /// Each chunk is retried on its own while the database is busy.
+    ///
+    /// # Errors
+    /// `DictError::InsertInterrupted` with how many entries were committed before the
+    /// failing chunk, 0 if it was the first: retry with `&entries[entries_committed..]` to
+    /// resume.
+    pub async fn insert_entries_with(
+        &self,
+        entries: &[Entry],
+        opts: &InsertOptions,
+        mut on_progress: impl FnMut(InsertProgress),
+    ) -> Result<InsertProgress> {
+        let chunk_size = if opts.single_transaction { entries.len() } else { opts.chunk_size };
+        let mut progress = InsertProgress { chunks_committed: 0, entries_committed: 0, entries_total: entries.len() };
+        for chunk in entries.chunks(chunk_size.max(1)) {
+            let stored = self
+                .write_entries(move || async move {
+                    let mut tx = self.pool.begin().await?;
+                    for e in chunk {
+                        Self::upsert_entry_tx(&mut tx, e, SOURCE_JMDICT).await?;
+                    }
+                    tx.commit().await?;
+                    Ok(())
+                })
+                .await;
+            match stored {
+                Ok(()) => {
+                    progress.chunks_committed += 1;
+                    progress.entries_committed += chunk.len();
+                    on_progress(progress);
+                }
+                Err(err) => {
+                    return Err(DictError::InsertInterrupted {
+                        chunks_committed: progress.chunks_committed,
+                        entries_committed: progress.entries_committed,
+                        message: err.to_string(),
+                    }
+                    .into())
+                }
+            }
+        }
+        Ok(progress)
+    }
+}
@@ -0,0 +1,164 @@
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::ranking::priority_score;
+use crate::{DictDb, Result};
+
+/// An ambiguous line of a JLPT level list: several entries share the term and reading.
+#[derive(Debug, Clone)]
+pub struct JlptAmbiguity {
+    pub term: String,
+    pub reading: Option<String>,
+    /// The entry that received the level (the one with the highest priority).
+    pub chosen: i64,
+    /// The other matching entries, which were left without a level.
+    pub others: Vec<i64>,
+}
+
+/// The outcome of loading a JLPT level list.
+#[derive(Debug, Clone, Default)]
+pub struct JlptLoadReport {
+    /// How many lines were read from the file.
+    pub terms_read: usize,
+    /// How many entries received a level.
+    pub entries_tagged: usize,
+    /// Lines that matched several entries.
+    pub ambiguous: Vec<JlptAmbiguity>,
+    /// Lines that did not match any entry, as (term, reading).
+    pub unmatched: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, FromRow)]
+struct JlptMatch {
+    line: i64,
+    ent_seq: i64,
+    priority: String,
+}
+
+/// Parses a JLPT level written as "N5", "n5" or "5".
+fn parse_level(s: &str) -> Option<u8> {
+    let digits = s.trim().trim_start_matches(['N', 'n']);
+    digits.parse().ok().filter(|l| (1..=5).contains(l))
+}
+
+impl DictDb {
+    /// Loads a JLPT level list into the `jlpt` table, replacing any previously loaded levels.
+    ///
+    /// Each line is `term<TAB>reading<TAB>level`, where the reading may be empty and the level is
+    /// written as "N5" or "5". A line matches the entries that have the term as a keb or reb
+    /// and, if given, the reading as a reb, which tells homographs apart.
+    /// When several entries still match, the one with the highest priority gets the level and
+    /// the rest are listed in the report. Empty lines and lines starting with `#` are skipped.
+    ///
+    /// # Arguments
+    /// * `reader` - The level list contents.
+    ///
+    /// # Returns
+    /// A JlptLoadReport with the ambiguous and unmatched lines.
+    pub async fn load_jlpt_levels<R: Read>(&self, reader: R) -> Result<JlptLoadReport> {
+        let mut lines: Vec<(String, Option<String>, u8)> = Vec::new();
+        for (line_no, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let (term, reading, level) = match fields.as_slice() {
+                [term, level] => (*term, "", *level),
+                [term, reading, level] => (*term, *reading, *level),
+                _ => return Err(format!("line {}: expected term<TAB>reading<TAB>level", line_no + 1).into()),
+            };
+            let level = parse_level(level)
+                .ok_or_else(|| format!("line {}: invalid JLPT level {:?}", line_no + 1, level))?;
+            let reading = Some(reading.trim().to_string()).filter(|r| !r.is_empty());
+            lines.push((term.trim().to_string(), reading, level));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "CREATE TEMP TABLE IF NOT EXISTS jlpt_import (line INTEGER PRIMARY KEY, term TEXT NOT NULL, reading TEXT)",
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM temp.jlpt_import").execute(&mut *tx).await?;
+        for (i, (term, reading, _)) in lines.iter().enumerate() {
+            sqlx::query("INSERT INTO temp.jlpt_import (line, term, reading) VALUES (?1, ?2, ?3)")
+                .bind(i as i64)
+                .bind(term)
+                .bind(reading)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS temp.idx_jlpt_import ON jlpt_import(term)")
+            .execute(&mut *tx)
+            .await?;
+
+        let matches = sqlx::query_as::<_, JlptMatch>(
+            r#"
+            SELECT DISTINCT t.line, e.ent_seq, e.priority
+            FROM (
+                SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.rebs) je
+                UNION ALL
+                SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.kebs) je
+                WHERE e.kebs IS NOT NULL
+            ) f
+            JOIN temp.jlpt_import t ON t.term = f.form
+            JOIN entries e ON e.ent_seq = f.ent_seq
+            WHERE t.reading IS NULL
+               OR EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE r.value = t.reading)
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // line -> [(ent_seq, priority score)]
+        let mut by_line: BTreeMap<i64, Vec<(i64, i64)>> = BTreeMap::new();
+        for m in matches {
+            let priority: Vec<String> = serde_json::from_str(&m.priority)?;
+            by_line.entry(m.line).or_default().push((m.ent_seq, priority_score(&priority)));
+        }
+
+        sqlx::query("DELETE FROM jlpt").execute(&mut *tx).await?;
+
+        let mut report = JlptLoadReport { terms_read: lines.len(), ..Default::default() };
+        for (i, (term, reading, level)) in lines.iter().enumerate() {
+            let Some(candidates) = by_line.get_mut(&(i as i64)) else {
+                report.unmatched.push((term.clone(), reading.clone()));
+                continue;
+            };
+            // highest priority first, lowest ent_seq on ties
+            candidates.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            let chosen = candidates[0].0;
+            if candidates.len() > 1 {
+                report.ambiguous.push(JlptAmbiguity {
+                    term: term.clone(),
+                    reading: reading.clone(),
+                    chosen,
+                    others: candidates[1..].iter().map(|c| c.0).collect(),
+                });
+            }
+            // An entry listed at several levels keeps the easiest one
+            sqlx::query(
+                r#"
+                INSERT INTO jlpt (ent_seq, level) VALUES (?1, ?2)
+                ON CONFLICT(ent_seq) DO UPDATE SET level = MAX(level, excluded.level)
+                "#,
+            )
+            .bind(chosen)
+            .bind(*level as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        report.entries_tagged = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM jlpt")
+            .fetch_one(&mut *tx)
+            .await? as usize;
+
+        sqlx::query("DROP TABLE temp.jlpt_import").execute(&mut *tx).await?;
+        tx.commit().await?;
+        self.entry_cache.clear();
+        Ok(report)
+    }
+}
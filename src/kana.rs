@@ -0,0 +1,135 @@
+/// Returns true if the character is hiragana (including the small forms and ゔ).
+pub fn is_hiragana(c: char) -> bool {
+    ('\u{3041}'..='\u{3096}').contains(&c)
+}
+
+/// Returns true if the character is katakana (including ー and the small forms).
+pub fn is_katakana(c: char) -> bool {
+    ('\u{30A1}'..='\u{30FA}').contains(&c) || c == 'ー'
+}
+
+/// Returns true if the character is hiragana or katakana.
+pub fn is_kana(c: char) -> bool {
+    is_hiragana(c) || is_katakana(c)
+}
+
+/// Returns true if the string is non-empty and only contains kana.
+pub fn is_kana_str(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(is_kana)
+}
+
+/// Converts katakana to hiragana, leaving every other character (including ー) untouched.
+pub fn katakana_to_hiragana(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Converts hiragana to katakana, leaving every other character untouched.
+pub fn hiragana_to_katakana(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\u{3041}'..='\u{3096}' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// How `normalize_kana` folds spelling variants of a reading, on top of converting katakana
+/// to hiragana.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KanaNormalization {
+    /// Replace ー with the vowel of the kana before it (らーめん → らあめん). Kept after ん, っ
+    /// and anything but kana.
+    pub resolve_long_vowels: bool,
+    /// Fold small kana into the full-size ones (うぉ → うお, っ → つ).
+    pub fold_small_kana: bool,
+}
+
+/// The normalization of `search_entries_by_reading_normalized` and its stored readings.
+pub const READING_NORMALIZATION: KanaNormalization =
+    KanaNormalization { resolve_long_vowels: true, fold_small_kana: true };
+
+/// Hyphens and dashes typed for ー, e.g. ラ-メン with an ASCII hyphen.
+const DASHES: [char; 10] = ['-', '‐', '‑', '‒', '–', '—', '―', '−', '－', 'ｰ'];
+
+/// The vowel (あいうえお) a hiragana ends in, None for ん, っ and anything else.
+fn vowel_of(c: char) -> Option<char> {
+    const ROWS: [(char, &str); 5] = [
+        ('あ', "あぁかがゕさざただなはばぱまやゃらわゎ"),
+        ('い', "いぃきぎしじちぢにひびぴみりゐ"),
+        ('う', "うぅくぐすずつづぬふぶぷむゆゅるゔ"),
+        ('え', "えぇけげゖせぜてでねへべぺめれゑ"),
+        ('お', "おぉこごそぞとどのほぼぽもよょろを"),
+    ];
+    ROWS.iter().find(|(_, row)| row.contains(c)).map(|(vowel, _)| *vowel)
+}
+
+/// The full-size form of a small hiragana.
+fn full_size(c: char) -> char {
+    match c {
+        'ぁ' => 'あ',
+        'ぃ' => 'い',
+        'ぅ' => 'う',
+        'ぇ' => 'え',
+        'ぉ' => 'お',
+        'っ' => 'つ',
+        'ゃ' => 'や',
+        'ゅ' => 'ゆ',
+        'ょ' => 'よ',
+        'ゎ' => 'わ',
+        'ゕ' => 'か',
+        'ゖ' => 'け',
+        _ => c,
+    }
+}
+
+/// Normalizes a reading so that spelling variants compare equal: katakana become hiragana and
+/// hyphens or dashes after a kana become ー, then `opts` decides on ー and small kana.
+///
+/// With `READING_NORMALIZATION`, ラーメン, らーめん and ラ-メン are all らあめん, and ウォ and
+/// ウオ both うお. Clients can run their own corpora through the same function to match the
+/// stored readings.
+pub fn normalize_kana(s: &str, opts: KanaNormalization) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut prev: Option<char> = None;
+    for c in katakana_to_hiragana(s).chars() {
+        let c = if DASHES.contains(&c) && prev.is_some_and(is_kana) { 'ー' } else { c };
+        let c = match (c, prev.and_then(vowel_of)) {
+            ('ー', Some(vowel)) if opts.resolve_long_vowels => vowel,
+            _ => c,
+        };
+        prev = Some(c);
+        out.push(if opts.fold_small_kana { full_size(c) } else { c });
+    }
+    out
+}
+
+/// Folds the vowel length of a reading away, for `RankOptions::ignore_vowel_length`: after
+/// `normalize_kana` with `READING_NORMALIZATION`, drops ー and every vowel that lengthens
+/// the kana before it, the same vowel or い after an e-row kana and う after an o-row one.
+///
+/// サッカー and サッカ fold to さつか, おかあさん and おかさん to おかさん, せんせい to
+/// せんせ. So do ビール and ビル, which are different words: this is for finding what a
+/// learner may have meant, not for deciding two readings are the same.
+pub fn fold_vowel_length(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut prev_vowel: Option<char> = None;
+    for c in normalize_kana(s, READING_NORMALIZATION).chars() {
+        let lengthens = match (prev_vowel, c) {
+            (_, 'ー') => true,
+            (Some(vowel), c) if vowel == c => true,
+            (Some('え'), 'い') | (Some('お'), 'う') => true,
+            _ => false,
+        };
+        if lengthens {
+            continue;
+        }
+        prev_vowel = vowel_of(c);
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Sqlite, Transaction};
+
+use crate::query_stats::RowCount;
+use crate::{extract_kanji, DictDb, EntryParsed, EntryRow, Result};
+
+/// The words using a kanji, for a kanji page, see `DictDb::kanji_detail`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KanjiDetail {
+    pub ch: char,
+    /// Entries with a keb starting with the kanji, e.g. 食事 for 食.
+    pub at_start: Vec<EntryParsed>,
+    /// The other entries using it, e.g. 飲食 for 食.
+    pub elsewhere: Vec<EntryParsed>,
+}
+
+impl RowCount for KanjiDetail {
+    fn row_count(&self) -> usize {
+        self.at_start.len() + self.elsewhere.len()
+    }
+}
+
+impl DictDb {
+    /// Replaces the kanji_index rows of an entry with the kanji of its kebs.
+    pub(crate) async fn index_kanji_tx(tx: &mut Transaction<'_, Sqlite>, ent_seq: i64, kebs: &[&str]) -> Result<()> {
+        sqlx::query("DELETE FROM kanji_index WHERE ent_seq = ?1")
+            .bind(ent_seq)
+            .execute(&mut **tx)
+            .await?;
+        for ch in extract_kanji(&kebs.concat()) {
+            sqlx::query("INSERT INTO kanji_index (ch, ent_seq) VALUES (?1, ?2)")
+                .bind(ch.to_string())
+                .bind(ent_seq)
+                .execute(&mut **tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the kanji_index table from the kebs of all entries.
+    ///
+    /// `upsert_entry` keeps the index up to date, this is for databases built before it existed.
+    pub(crate) async fn rebuild_kanji_index(&self) -> Result<()> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let rows: Vec<(i64, String)> = sqlx::query_as("SELECT ent_seq, kebs FROM entries WHERE kebs IS NOT NULL")
+                .fetch_all(&mut *tx)
+                .await?;
+            for (ent_seq, kebs) in rows {
+                let kebs: Vec<String> = serde_json::from_str(&kebs)?;
+                let kebs: Vec<&str> = kebs.iter().map(String::as_str).collect();
+                Self::index_kanji_tx(&mut tx, ent_seq, &kebs).await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Returns up to `limit` entries with the kanji `ch` in one of their kebs, most frequent
+    /// (by nfNN bucket) first, e.g. all the words using 食.
+    pub async fn entries_containing_char(&self, ch: char, limit: usize) -> Result<Vec<EntryParsed>> {
+        let query = ch.to_string();
+        self.observe("entries_containing_char", &query, || format!("limit={}", limit), async {
+            let rows = sqlx::query_as::<_, EntryRow>(
+                r#"
+                SELECT e.* FROM kanji_index k JOIN entries e ON e.ent_seq = k.ent_seq
+                WHERE k.ch = ?1
+                ORDER BY e.nf_bucket IS NULL, e.nf_bucket, e.ent_seq
+                LIMIT ?2
+                "#,
+            )
+            .bind(&query)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+            self.hydrate_entries(rows).await
+        })
+        .await
+    }
+
+    /// The words using the kanji `ch` in one call: up to `start_limit` entries with a keb
+    /// starting with it and up to `elsewhere_limit` of the others, each most frequent (by nfNN
+    /// bucket) first like `entries_containing_char`.
+    pub async fn kanji_detail(&self, ch: char, start_limit: usize, elsewhere_limit: usize) -> Result<KanjiDetail> {
+        let query = ch.to_string();
+        self.observe("kanji_detail", &query, || format!("limits {} {}", start_limit, elsewhere_limit), async {
+            let mut lists = Vec::with_capacity(2);
+            for (at_start, limit) in [(true, start_limit), (false, elsewhere_limit)] {
+                let rows = sqlx::query_as::<_, EntryRow>(
+                    r#"
+                    SELECT e.* FROM kanji_index k JOIN entries e ON e.ent_seq = k.ent_seq
+                    WHERE k.ch = ?1
+                      AND EXISTS (SELECT 1 FROM json_each(e.kebs) je WHERE substr(je.value, 1, 1) = ?1) = ?2
+                    ORDER BY e.nf_bucket IS NULL, e.nf_bucket, e.ent_seq
+                    LIMIT ?3
+                    "#,
+                )
+                .bind(&query)
+                .bind(at_start)
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await?;
+                lists.push(self.hydrate_entries(rows).await?);
+            }
+            let elsewhere = lists.pop().unwrap_or_default();
+            let at_start = lists.pop().unwrap_or_default();
+            Ok(KanjiDetail { ch, at_start, elsewhere })
+        })
+        .await
+    }
+}
@@ -1,5 +1,148 @@
 pub mod models;
+pub mod parse;
+pub mod error;
 pub mod dict_db;
+pub mod options;
+pub mod global;
+pub mod retry;
+pub mod query_stats;
+pub mod timeout;
+mod rt;
+pub mod paths;
+pub mod pos;
+pub mod entities;
+pub mod browse;
+pub mod build;
+pub mod build_lock;
+pub mod raw_xml;
+pub mod suffix;
+pub mod bulk;
+pub mod compounds;
+pub mod related;
+pub mod content_hash;
+pub mod sense_uid;
+pub mod corrections;
+pub mod expressions;
+pub mod flat;
+pub mod export;
+pub mod stardict;
+pub mod ereader;
+pub mod dump;
+pub mod conjugation;
+pub mod okurigana;
+pub mod tags;
+pub mod complexity;
+pub mod kana;
+pub mod kanji_index;
+pub mod reading_index;
+pub mod punctuation;
+pub mod suggest;
+pub mod entry_cache;
+pub mod romaji;
+pub mod xref;
+pub mod frequency;
+pub mod gloss;
+pub mod collation;
+pub mod stem;
+pub mod ranking;
+pub mod outcome;
+pub mod dedupe;
+pub mod schema;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod furigana;
+pub mod annotate;
+pub mod highlight;
+pub mod render;
+pub mod filter;
+pub mod jlpt;
+pub mod tanaka;
+pub mod custom;
+pub mod transaction;
+pub mod insert;
+pub mod maintenance;
+pub mod extensions;
+pub mod guards;
+pub mod warmup;
+pub mod reload;
+pub mod generation;
+pub mod health;
+#[cfg(feature = "test-utils")]
+pub mod fixtures;
+#[cfg(feature = "test-utils")]
+pub mod snapshot;
+pub mod attach;
+#[cfg(feature = "bundled-db")]
+pub mod bundled;
+#[cfg(feature = "userdata")]
+pub mod wordlists;
+#[cfg(feature = "userdata")]
+pub mod history;
+#[cfg(feature = "userdata")]
+pub mod import;
 
 pub use models::*;
-pub use dict_db::*;
\ No newline at end of file
+pub use parse::{parse_entry_xml, parse_jmdict_reader, parse_jmdict_str};
+pub use error::DictError;
+pub use dict_db::*;
+pub use options::{ConnectionPragmas, DictDbOptions};
+pub use retry::RetryPolicy;
+pub use query_stats::{MethodStats, QueryStats, LATENCY_BUCKETS_MS};
+pub use timeout::{QueryOptions, DEFAULT_HYDRATION_CONCURRENCY};
+pub use reload::{DbInfo, ReloadableDictDb};
+pub use paths::*;
+pub use pos::*;
+pub use conjugation::*;
+pub use okurigana::{verb_stem_candidates, StemCandidate};
+pub use kana::{fold_vowel_length, normalize_kana, KanaNormalization, READING_NORMALIZATION};
+pub use punctuation::{has_headword_punctuation, strip_headword_punctuation, HEADWORD_PUNCTUATION};
+pub use romaji::{romanize, romanize_with, romaji_to_kana};
+pub use xref::{sense_number_of, sense_order_of, BorrowedGlosses, Xref, XrefTarget, XREF_SEPARATOR};
+pub use frequency::*;
+pub use gloss::*;
+pub use collation::Collation;
+pub use stem::porter_stem;
+pub use outcome::{SearchOutcome, Transformation};
+pub use dedupe::{dedupe_by_reading_and_gloss, MergedResult};
+pub use ranking::{DefaultRanker, MatchContext, MatchKind, RankOptions, Ranker, ScoreBreakdown, ScoredEntry};
+pub use suggest::{Suggestion, SuggestIndexStats, SuggestionHint, HINT_GLOSS_CHARS};
+pub use entry_cache::EntryCacheStats;
+pub use corrections::{Correction, CorrectionStrategy};
+pub use content_hash::{canonical_entry, content_hash, CONTENT_HASH_VERSION};
+pub use sense_uid::{sense_uids, LocatedSense, SenseUidStatus, SENSE_UID_VERSION};
+pub use schema::{schema, schema_with, JsonStyle};
+#[cfg(feature = "msgpack")]
+pub use msgpack::{decode_entries, encode_entries, PAYLOAD_VERSION};
+pub use furigana::*;
+pub use annotate::{RubySegment, RubyText};
+pub use highlight::{HighlightOptions, ScanOptions, TextMatch};
+pub use related::RelatedEntry;
+pub use kanji_index::KanjiDetail;
+pub use filter::SearchFilter;
+pub use tags::{known_tags, lookup_tag, TagCategory, TagInfo};
+pub use complexity::complexity_score;
+pub use browse::BrowseOrder;
+pub use flat::{FlatRow, FlatRowOptions};
+pub use export::{ExportFormat, FLAT_COLUMNS};
+pub use stardict::{StarDictOptions, StarDictReport};
+pub use ereader::{EreaderFormat, EreaderOptions, EreaderReport};
+pub use dump::SQL_DUMP_HEADER;
+pub use build::{BuildOptions, BuildReport, PhaseDuration, SkippedEntry};
+pub use build_lock::{build_lock_path, BuildLock, BUILD_LOCK_SUFFIX};
+pub use suffix::{split_leading_number, SuffixMatch, PLACEHOLDER_TILDES};
+pub use jlpt::*;
+pub use tanaka::*;
+pub use custom::*;
+pub use transaction::DictTx;
+pub use insert::{InsertOptions, InsertProgress, DEFAULT_INSERT_CHUNK};
+pub use maintenance::*;
+pub use warmup::{WarmupOptions, WarmupReport};
+pub use guards::QueryGuards;
+pub use health::{Health, HEALTH_CHECK_TIMEOUT, SCHEMA_VERSION};
+pub use attach::{Attachment, MAX_ATTACHED};
+#[cfg(feature = "userdata")]
+pub use wordlists::*;
+#[cfg(feature = "userdata")]
+pub use history::LookupRecord;
+#[cfg(feature = "userdata")]
+pub use import::{AmbiguousTerm, ImportReport, ImportTerm};
@@ -0,0 +1,265 @@
+use sqlx::FromRow;
+use std::fmt;
+use std::path::Path;
+
+use crate::{DictDb, Result};
+
+// SQLite result codes that mean the file is damaged
+const SQLITE_CORRUPT: &str = "11";
+const SQLITE_NOTADB: &str = "26";
+
+/// The result of `DictDb::integrity_check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityReport {
+    /// `PRAGMA integrity_check` found no problems.
+    Ok,
+    /// The problems reported by SQLite, one per message.
+    Corrupt(Vec<String>),
+}
+
+impl IntegrityReport {
+    /// Returns true if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, IntegrityReport::Ok)
+    }
+}
+
+/// The space a table takes in the database file, see `DictDb::size_report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSize {
+    pub name: String,
+    /// The bytes of the table's own pages.
+    pub table_bytes: u64,
+    /// The bytes of the pages of its indexes, including the implicit ones of UNIQUE and
+    /// PRIMARY KEY constraints.
+    pub index_bytes: u64,
+}
+
+impl TableSize {
+    /// The table with its indexes.
+    pub fn total_bytes(&self) -> u64 {
+        self.table_bytes + self.index_bytes
+    }
+}
+
+/// Where the bytes of the database file go, from `DictDb::size_report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeReport {
+    /// The size of the file, see `DictDb::database_size`.
+    pub total_bytes: u64,
+    /// The bytes of unused pages, given back by VACUUM.
+    pub freelist_bytes: u64,
+    /// The tables, the largest first.
+    pub tables: Vec<TableSize>,
+    /// The table sizes are estimated from the lengths of the stored values because SQLite
+    /// was built without the dbstat table; the totals are exact either way.
+    pub estimated: bool,
+}
+
+impl fmt::Display for SizeReport {
+    /// A table of the sizes in KiB, as `jmdict stats` and `build_db` print it.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kib = |bytes: u64| (bytes + 512) / 1024;
+        writeln!(f, "{:>10}  {:>10}  {:>10}  table", "total KiB", "table", "indexes")?;
+        for t in &self.tables {
+            writeln!(f, "{:>10}  {:>10}  {:>10}  {}", kib(t.total_bytes()), kib(t.table_bytes), kib(t.index_bytes), t.name)?;
+        }
+        writeln!(f, "{:>10}  free pages", kib(self.freelist_bytes))?;
+        write!(f, "{:>10}  file", kib(self.total_bytes))?;
+        if self.estimated {
+            write!(f, "\n(table sizes estimated, this SQLite has no dbstat)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A table or index of the schema with its table.
+#[derive(FromRow)]
+struct SchemaObject {
+    name: String,
+    tbl_name: String,
+    #[sqlx(rename = "type")]
+    kind: String,
+}
+
+impl DictDb {
+    /// Writes a consistent copy of the database to `path` with `VACUUM INTO`.
+    ///
+    /// The copy is taken inside a read transaction, so other readers keep going while it runs.
+    /// `path` must not exist yet.
+    pub async fn backup_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Err(format!("backup destination {} already exists", path.display()).into());
+        }
+        let path = path.to_str().ok_or("backup destination is not valid UTF-8")?;
+        sqlx::query("VACUUM INTO ?1").bind(path).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Runs `PRAGMA integrity_check` over the whole database.
+    ///
+    /// A file so damaged that SQLite refuses to read it is reported as corrupt as well,
+    /// other errors (e.g. a closed pool) are returned as errors.
+    pub async fn integrity_check(&self) -> Result<IntegrityReport> {
+        let messages = match sqlx::query_scalar::<_, String>("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await
+        {
+            Ok(messages) => messages,
+            Err(sqlx::Error::Database(err))
+                if matches!(err.code().as_deref(), Some(SQLITE_CORRUPT | SQLITE_NOTADB)) =>
+            {
+                return Ok(IntegrityReport::Corrupt(vec![err.message().to_string()]));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        if messages.len() == 1 && messages[0] == "ok" {
+            Ok(IntegrityReport::Ok)
+        } else {
+            Ok(IntegrityReport::Corrupt(messages))
+        }
+    }
+
+    /// The number of entries in the main database, attached databases are not counted.
+    pub async fn entry_count(&self) -> Result<i64> {
+        Ok(sqlx::query_scalar("SELECT COUNT(*) FROM entries").fetch_one(&self.pool).await?)
+    }
+
+    /// The number of senses in the main database, attached databases are not counted.
+    pub async fn sense_count(&self) -> Result<i64> {
+        Ok(sqlx::query_scalar("SELECT COUNT(*) FROM senses").fetch_one(&self.pool).await?)
+    }
+
+    /// The number of entries in the main and all attached databases.
+    pub async fn entry_count_all(&self) -> Result<i64> {
+        self.count_all("entries").await
+    }
+
+    /// The number of senses in the main and all attached databases.
+    pub async fn sense_count_all(&self) -> Result<i64> {
+        self.count_all("senses").await
+    }
+
+    /// The size of the main database in bytes, pages not yet written back from the WAL
+    /// included.
+    pub async fn database_size(&self) -> Result<u64> {
+        let size: i64 = sqlx::query_scalar("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(size as u64)
+    }
+
+    /// The bytes each table of the main database takes with its indexes, plus the free pages,
+    /// e.g. to see what an optional data set such as the example sentences costs.
+    ///
+    /// The pages are counted with SQLite's `dbstat` table. Without it the table sizes are
+    /// those of `estimate_size_report` and `SizeReport::estimated` is set.
+    pub async fn size_report(&self) -> Result<SizeReport> {
+        let pages: Vec<(String, i64)> =
+            match sqlx::query_as("SELECT name, SUM(pgsize) FROM dbstat('main') GROUP BY name").fetch_all(&self.pool).await {
+                Ok(pages) => pages,
+                Err(sqlx::Error::Database(err)) if err.message().contains("dbstat") => {
+                    return self.estimate_size_report().await;
+                }
+                Err(err) => return Err(err.into()),
+            };
+        let objects = self.schema_objects().await?;
+        let mut tables: Vec<TableSize> = Vec::new();
+        for (name, bytes) in pages {
+            // sqlite_schema does not list itself
+            let (table, is_index) = match objects.iter().find(|o| o.name == name) {
+                Some(object) => (object.tbl_name.clone(), object.kind == "index"),
+                None => (name, false),
+            };
+            let size = table_size(&mut tables, &table);
+            if is_index {
+                size.index_bytes += bytes as u64;
+            } else {
+                size.table_bytes += bytes as u64;
+            }
+        }
+        self.finish_size_report(tables, false).await
+    }
+
+    /// `size_report` with the table sizes estimated from the lengths of the stored values
+    /// instead of counted, for SQLite builds without `dbstat`. Slower, as it reads every row,
+    /// and only a rough guide: page overhead and the b-tree structure are left out.
+    pub async fn estimate_size_report(&self) -> Result<SizeReport> {
+        let mut tables: Vec<TableSize> = Vec::new();
+        for object in self.schema_objects().await? {
+            let columns: Vec<String> = if object.kind == "index" {
+                sqlx::query_scalar("SELECT name FROM pragma_index_info(?1) WHERE name IS NOT NULL")
+            } else {
+                sqlx::query_scalar("SELECT name FROM pragma_table_info(?1)")
+            }
+            .bind(&object.name)
+            .fetch_all(&self.pool)
+            .await?;
+            if columns.is_empty() {
+                continue;
+            }
+            let lengths: Vec<String> =
+                columns.iter().map(|c| format!("COALESCE(length(CAST(\"{}\" AS BLOB)), 0)", c.replace('"', "\"\""))).collect();
+            let sql = format!("SELECT COALESCE(SUM({}), 0) FROM \"{}\"", lengths.join(" + "), object.tbl_name.replace('"', "\"\""));
+            let bytes: i64 = sqlx::query_scalar(&sql).fetch_one(&self.pool).await?;
+            let size = table_size(&mut tables, &object.tbl_name);
+            if object.kind == "index" {
+                size.index_bytes += bytes as u64;
+            } else {
+                size.table_bytes += bytes as u64;
+            }
+        }
+        self.finish_size_report(tables, true).await
+    }
+
+    /// The tables and indexes of the main database, the automatic indexes included.
+    async fn schema_objects(&self) -> Result<Vec<SchemaObject>> {
+        Ok(sqlx::query_as::<_, SchemaObject>(
+            "SELECT name, tbl_name, type FROM sqlite_schema WHERE type IN ('table', 'index') AND name NOT LIKE 'sqlite_stat%'",
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Sorts the tables and adds the totals.
+    async fn finish_size_report(&self, mut tables: Vec<TableSize>, estimated: bool) -> Result<SizeReport> {
+        tables.sort_by(|a, b| b.total_bytes().cmp(&a.total_bytes()).then(a.name.cmp(&b.name)));
+        let freelist: i64 = sqlx::query_scalar("SELECT freelist_count * page_size FROM pragma_freelist_count(), pragma_page_size()")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(SizeReport { total_bytes: self.database_size().await?, freelist_bytes: freelist as u64, tables, estimated })
+    }
+
+    /// Returns true if the main database has no entries, e.g. `build_db` has not run yet.
+    pub async fn is_empty(&self) -> Result<bool> {
+        Ok(!sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM entries)")
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    /// Sums the rows of `table` over every database of an attached connection.
+    async fn count_all(&self, table: &str) -> Result<i64> {
+        let mut conn = self.attached_connection().await?;
+        let mut total = 0;
+        for schema in Self::schemas(&mut conn).await? {
+            let n: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}.{}", schema, table))
+                .fetch_one(&mut *conn)
+                .await?;
+            total += n;
+        }
+        Ok(total)
+    }
+}
+
+/// The entry of `table` in `tables`, added if it is not there yet.
+fn table_size<'a>(tables: &'a mut Vec<TableSize>, table: &str) -> &'a mut TableSize {
+    match tables.iter().position(|t| t.name == table) {
+        Some(i) => &mut tables[i],
+        None => {
+            tables.push(TableSize { name: table.to_string(), table_bytes: 0, index_bytes: 0 });
+            tables.last_mut().expect("just pushed")
+        }
+    }
+}
@@ -1,5 +1,9 @@
 
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The language of glosses without an xml:lang attribute, as in the JMdict DTD.
+pub const DEFAULT_LANG: &str = "eng";
 
 #[derive(Debug, Deserialize)]
 /// Represents the JMDict xml structure. This is used for Serde deserialization.
@@ -8,7 +12,7 @@ pub struct JMDict {
 }
 
 /// Represents a JMDict entry.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct Entry {
     pub ent_seq: String, // ent_seq is a string representing the entry sequence
     pub k_ele: Option<Vec<KEle>>, // k_ele is a vector of strings for kanji elements
@@ -16,11 +20,59 @@ pub struct Entry {
     pub sense: Vec<Sense>, // sense is a vector of Sense structs for senses
 }
 
+/// The part of speech and misc tags a sense has as JMdict means them, see
+/// `Entry::inherited_sense_tags`.
+pub(crate) struct SenseTags<'a> {
+    pub pos: &'a [String],
+    pub misc: &'a [String],
+    /// Whether `pos` comes from an earlier sense, the sense itself having no `<pos>`.
+    pub pos_inherited: bool,
+    pub misc_inherited: bool,
+}
+
+impl Entry {
+    /// The tags of each sense after JMdict's inheritance rule: a sense without `<pos>` has
+    /// the part of speech of the sense before it, and the DTD says the same of `<misc>`.
+    /// The `Sense`s themselves keep what the XML says.
+    pub(crate) fn inherited_sense_tags(&self) -> Vec<SenseTags<'_>> {
+        let mut tags: Vec<SenseTags<'_>> = Vec::with_capacity(self.sense.len());
+        for s in &self.sense {
+            let previous = tags.last();
+            let (pos, pos_inherited) = match previous {
+                Some(p) if s.pos.is_empty() => (p.pos, !p.pos.is_empty()),
+                _ => (s.pos.as_slice(), false),
+            };
+            let (misc, misc_inherited) = match previous {
+                Some(p) if s.misc.is_empty() => (p.misc, !p.misc.is_empty()),
+                _ => (s.misc.as_slice(), false),
+            };
+            tags.push(SenseTags { pos, misc, pos_inherited, misc_inherited });
+        }
+        tags
+    }
+
+    /// The re_restr of each reb, in order, or nothing if no reb is restricted, as
+    /// `EntryParsed::re_restr` has them.
+    pub(crate) fn reb_restrictions(&self) -> Vec<Vec<String>> {
+        if self.r_ele.iter().all(|r| r.re_restr.is_empty()) {
+            return Vec::new();
+        }
+        self.r_ele.iter().map(|r| r.re_restr.clone()).collect()
+    }
+}
+
 /// Represents a reading element in the JMDict entry.
-#[derive(Debug, Deserialize)]
+///
+/// re_pri "priority tags for the reading element", same values as ke_pri
+///
+/// re_restr "the reading only applies to these kebs", e.g. かしら of 頭 but not of 首
+#[derive(Debug, Default, Deserialize)]
 pub struct REle {
     pub reb: String, // the reading element
-
+    #[serde(default)]
+    pub re_pri: Vec<String>, // the priority for the reading element
+    #[serde(default)]
+    pub re_restr: Vec<String>, // the kebs the reading is restricted to, empty for all of them
 }
 
 /// Represents a kanji element in the JMDict entry.
@@ -35,7 +87,7 @@ pub struct REle {
 /// - oK "word containing out-dated kanji or kanji usage"
 /// - rK "rarely used kanji form"
 /// - sK "search-only kanji form"
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct KEle {
     pub keb: String,
     #[serde(default)]
@@ -45,9 +97,10 @@ pub struct KEle {
 }
 
 /// Represents a sense in the JMDict entry.
+///  - stagk/stagr "the sense only applies to these kanji/reading elements", empty for all
 ///  - pos "part of speech"
 ///  - xref "cross-references"
-/// - gloss "the English definition/glossary"
+/// - gloss "the English definition/glossary", other languages are marked with xml:lang
 /// 
 /// 
 /// for pos, the following values are used:
@@ -143,17 +196,96 @@ pub struct KEle {
 /// - vs-s "suru verb - special class
 /// - vt "transitive verb
 /// - vz "Ichidan verb - zuru verb (alternative form of -jiru verbs)"
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(from = "SenseXml")]
 pub struct Sense {
+    #[serde(default)]
+    /// The kebs the sense is restricted to, empty if it applies to all of them.
+    pub stagk: Vec<String>,
+    #[serde(default)]
+    /// The rebs the sense is restricted to, empty if it applies to all of them.
+    pub stagr: Vec<String>,
     #[serde(default)]
     /// The part of speech for the sense.
     pub pos: Vec<String>,
     #[serde(default)]
     /// Cross-references for the sense.
     pub xref: Vec<String>,
+    #[serde(default)]
+    /// Other information about the sense, e.g. "word usually written using kana alone".
+    pub misc: Vec<String>,
+    #[serde(default)]
+    /// The fields of application, e.g. "computing".
+    pub field: Vec<String>,
+    #[serde(default)]
+    /// The dialects the sense belongs to, e.g. "Kansai-ben".
+    pub dial: Vec<String>,
 
     #[serde(default)]
     /// translation of the Japanese word.
     pub gloss: Vec<String>,
 
+    /// Translations in languages other than English, from multilingual JMdict files.
+    pub other_glosses: Vec<Gloss>,
+}
+
+/// A gloss together with its language, an ISO 639-2 code such as "ger" or "fre".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Gloss {
+    pub lang: String,
+    pub text: String,
+}
+
+/// A `<gloss>` element as it appears in the XML.
+#[derive(Debug, Deserialize)]
+struct GlossXml {
+    #[serde(rename = "@xml:lang", default)]
+    lang: Option<String>,
+    #[serde(rename = "$text", default)]
+    text: String,
+}
+
+/// A `<sense>` element as it appears in the XML, before the glosses are split by language.
+#[derive(Debug, Deserialize)]
+struct SenseXml {
+    #[serde(default)]
+    stagk: Vec<String>,
+    #[serde(default)]
+    stagr: Vec<String>,
+    #[serde(default)]
+    pos: Vec<String>,
+    #[serde(default)]
+    xref: Vec<String>,
+    #[serde(default)]
+    misc: Vec<String>,
+    #[serde(default)]
+    field: Vec<String>,
+    #[serde(default)]
+    dial: Vec<String>,
+    #[serde(default)]
+    gloss: Vec<GlossXml>,
+}
+
+impl From<SenseXml> for Sense {
+    fn from(x: SenseXml) -> Self {
+        let mut gloss = Vec::new();
+        let mut other_glosses = Vec::new();
+        for g in x.gloss {
+            match g.lang {
+                Some(lang) if lang != DEFAULT_LANG => other_glosses.push(Gloss { lang, text: g.text }),
+                _ => gloss.push(g.text),
+            }
+        }
+        Sense {
+            stagk: x.stagk,
+            stagr: x.stagr,
+            pos: x.pos,
+            xref: x.xref,
+            misc: x.misc,
+            field: x.field,
+            dial: x.dial,
+            gloss,
+            other_glosses,
+        }
+    }
 }
\ No newline at end of file
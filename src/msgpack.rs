@@ -0,0 +1,37 @@
+use serde::de::IgnoredAny;
+use serde::Serialize;
+
+use crate::{DictError, EntryParsed, Result};
+
+/// Identifies payloads written by `encode_entries`.
+const PAYLOAD_MAGIC: &str = "jmdictdb";
+
+/// The version of the payload layout, bumped whenever `EntryParsed` changes incompatibly.
+pub const PAYLOAD_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct EnvelopeRef<'a>(&'a str, u32, &'a [EntryParsed]);
+
+/// Encodes entries as compact MessagePack, wrapped in a versioned envelope.
+///
+/// Fields are written by position rather than by name, so the payload is much smaller than
+/// JSON; `decode_entries` checks the version before reading the entries.
+pub fn encode_entries(entries: &[EntryParsed]) -> Result<Vec<u8>> {
+    Ok(rmp_serde::to_vec(&EnvelopeRef(PAYLOAD_MAGIC, PAYLOAD_VERSION, entries))?)
+}
+
+/// Decodes a payload written by `encode_entries`.
+///
+/// # Errors
+/// `DictError::UnsupportedPayloadVersion` if the payload has another layout version.
+pub fn decode_entries(bytes: &[u8]) -> Result<Vec<EntryParsed>> {
+    let (magic, version, _): (String, u32, IgnoredAny) = rmp_serde::from_slice(bytes)?;
+    if magic != PAYLOAD_MAGIC {
+        return Err("not a jmdictdb payload".into());
+    }
+    if version != PAYLOAD_VERSION {
+        return Err(DictError::UnsupportedPayloadVersion { found: version, supported: PAYLOAD_VERSION }.into());
+    }
+    let (_, _, entries): (IgnoredAny, IgnoredAny, Vec<EntryParsed>) = rmp_serde::from_slice(bytes)?;
+    Ok(entries)
+}
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use crate::guards::non_empty;
+use crate::{contains_kanji, DictDb, EntryParsed, PartOfSpeech, Result};
+
+use PartOfSpeech::*;
+
+/// The okurigana a dictionary form may end in after a verb stem, with the verb classes
+/// that end that way.
+const ENDINGS: &[(&str, &[PartOfSpeech])] = &[
+    ("る", &[V1, V1S, V5r, V5rI, V5aru]),
+    ("う", &[V5u, V5uS]),
+    ("く", &[V5k, V5kS]),
+    ("ぐ", &[V5g]),
+    ("す", &[V5s]),
+    ("つ", &[V5t]),
+    ("ぬ", &[V5n]),
+    ("ぶ", &[V5b]),
+    ("む", &[V5m]),
+    ("する", &[VsI, VsS]),
+];
+
+/// A dictionary form a verb stem may belong to, see `verb_stem_candidates`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StemCandidate {
+    /// The dictionary form, e.g. 食べる for 食べ.
+    pub headword: String,
+    /// The parts of speech of the verbs with this dictionary form and stem; an entry with
+    /// the headword is only a match with one of them.
+    pub pos: &'static [PartOfSpeech],
+}
+
+/// The dictionary forms a verb stem, as simple tokenizers cut them, may belong to: the stem
+/// with each okurigana ending, 食べ to 食べる (ichidan) and 書 to 書く (godan), and for a stem
+/// ending in し the する-verb, 勉強し to 勉強 (`vs`) and 愛し to 愛する. Tried in this order.
+pub fn verb_stem_candidates(stem: &str) -> Vec<StemCandidate> {
+    if stem.is_empty() {
+        return Vec::new();
+    }
+    let mut candidates: Vec<StemCandidate> = ENDINGS
+        .iter()
+        .map(|&(ending, pos)| StemCandidate { headword: format!("{}{}", stem, ending), pos })
+        .collect();
+    if let Some(noun) = stem.strip_suffix('し').filter(|noun| !noun.is_empty()) {
+        candidates.push(StemCandidate { headword: noun.to_string(), pos: &[Vs] });
+        candidates.push(StemCandidate { headword: format!("{}する", noun), pos: &[VsI, VsS] });
+    }
+    candidates
+}
+
+impl DictDb {
+    /// The verbs `stem` is the stem of, e.g. 食べる for 食べ, 書く for 書 and 勉強 for 勉強し.
+    ///
+    /// Each of the `verb_stem_candidates` is looked up as a keb (or reb, for kana stems) and
+    /// only kept if a sense has one of its parts of speech, so 食べ doesn't find a noun 食べす.
+    /// The entries come in candidate order, each once, the query marked as in
+    /// `search_entries_with_senses_by_kanji`.
+    pub async fn search_verb_stem(&self, stem: &str) -> Result<Vec<EntryParsed>> {
+        self.observe("search_verb_stem", stem, String::new, async {
+            let mut seen = HashSet::new();
+            let mut entries = Vec::new();
+            for (_, entry) in self.verb_stem_matches(non_empty(stem)?).await? {
+                if seen.insert(entry.ent_seq) {
+                    entries.push(entry);
+                }
+            }
+            Ok(entries)
+        })
+        .await
+    }
+
+    /// The entries of the stem's candidates with a fitting part of speech, with the
+    /// headword each was found by.
+    pub(crate) async fn verb_stem_matches(&self, stem: &str) -> Result<Vec<(String, EntryParsed)>> {
+        let mut matches = Vec::new();
+        for candidate in verb_stem_candidates(stem) {
+            let entries = if contains_kanji(&candidate.headword) {
+                self.search_entries_with_senses_by_kanji(&candidate.headword).await?
+            } else {
+                self.search_entries_with_senses_by_reading(&candidate.headword).await?
+            };
+            for entry in entries {
+                let fits = entry
+                    .senses
+                    .iter()
+                    .flat_map(|s| &s.pos)
+                    .any(|p| candidate.pos.contains(&PartOfSpeech::parse(p)));
+                if fits {
+                    matches.push((candidate.headword.clone(), entry));
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
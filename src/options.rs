@@ -0,0 +1,189 @@
+use sqlx::SqliteConnection;
+use std::time::Duration;
+
+use crate::{DictDb, QueryGuards, Result, RetryPolicy};
+
+/// The pragmas set on every connection the pool opens, see `DictDbOptions::pragmas`.
+///
+/// Pragmas are per connection and the pool opens connections as needed, so they are applied
+/// when each one is opened, not once for the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionPragmas {
+    /// On by default; the cascading deletes of `delete_entry` rely on it.
+    pub foreign_keys: bool,
+    /// How long SQLite waits for a lock before reporting the database as busy, 5 seconds by
+    /// default.
+    pub busy_timeout: Duration,
+    /// Whether LIKE, e.g. in `search_sentences`, tells ASCII letter case apart. None keeps
+    /// SQLite's default, which doesn't.
+    pub case_sensitive_like: Option<bool>,
+    /// How many bytes of the database file SQLite reads through a memory mapping instead of
+    /// read calls, which saves copying pages already in the OS page cache. None keeps
+    /// SQLite's default, no mapping.
+    pub mmap_size: Option<u64>,
+}
+
+impl Default for ConnectionPragmas {
+    fn default() -> Self {
+        Self { foreign_keys: true, busy_timeout: Duration::from_secs(5), case_sensitive_like: None, mmap_size: None }
+    }
+}
+
+impl ConnectionPragmas {
+    /// The pragma statements, in the order they are run.
+    pub fn statements(&self) -> Vec<String> {
+        let on_off = |on: bool| if on { "ON" } else { "OFF" };
+        let mut statements = vec![
+            format!("PRAGMA foreign_keys = {}", on_off(self.foreign_keys)),
+            format!("PRAGMA busy_timeout = {}", self.busy_timeout.as_millis().min(i32::MAX as u128)),
+        ];
+        if let Some(case_sensitive) = self.case_sensitive_like {
+            statements.push(format!("PRAGMA case_sensitive_like = {}", on_off(case_sensitive)));
+        }
+        if let Some(bytes) = self.mmap_size {
+            statements.push(format!("PRAGMA mmap_size = {}", bytes.min(i64::MAX as u64)));
+        }
+        statements
+    }
+
+    /// Runs the pragma statements on a new connection, the pool's `after_connect` hook.
+    pub(crate) async fn apply(&self, conn: &mut SqliteConnection) -> std::result::Result<(), sqlx::Error> {
+        for statement in self.statements() {
+            sqlx::query(&statement).execute(&mut *conn).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Connection settings for `DictDb::connect_with`.
+///
+/// `DictDb::connect(url)` is the same as `DictDb::connect_with(DictDbOptions::new(url))`.
+#[derive(Debug, Clone)]
+pub struct DictDbOptions {
+    pub(crate) db_url: String,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) pragmas: ConnectionPragmas,
+    pub(crate) slow_query: Option<Duration>,
+    pub(crate) generation_check: Option<Duration>,
+    pub(crate) entry_cache: usize,
+    pub(crate) guards: QueryGuards,
+    pub(crate) suggestion_hints: bool,
+    #[cfg(feature = "sqlite-extensions")]
+    pub(crate) extensions: Vec<String>,
+}
+
+impl DictDbOptions {
+    /// Default settings for the database at `db_url`, e.g. "sqlite:data/jmdict_e.db".
+    pub fn new(db_url: impl Into<String>) -> Self {
+        Self {
+            db_url: db_url.into(),
+            retry: RetryPolicy::default(),
+            pragmas: ConnectionPragmas::default(),
+            slow_query: None,
+            generation_check: None,
+            entry_cache: 0,
+            guards: QueryGuards::default(),
+            suggestion_hints: false,
+            #[cfg(feature = "sqlite-extensions")]
+            extensions: Vec::new(),
+        }
+    }
+
+    /// The database URL.
+    pub fn db_url(&self) -> &str {
+        &self.db_url
+    }
+
+    /// How writes are retried while the database is busy, `RetryPolicy::default()` if not set.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// How long SQLite itself waits for a lock before reporting the database as busy,
+    /// default 5 seconds. The retry policy only kicks in after that.
+    pub fn busy_timeout(mut self, timeout: Duration) -> Self {
+        self.pragmas.busy_timeout = timeout;
+        self
+    }
+
+    /// Whether LIKE tells ASCII letter case apart on every connection, see
+    /// `ConnectionPragmas::case_sensitive_like`.
+    pub fn case_sensitive_like(mut self, case_sensitive: bool) -> Self {
+        self.pragmas.case_sensitive_like = Some(case_sensitive);
+        self
+    }
+
+    /// Memory-maps up to `bytes` of the database on every connection, see
+    /// `ConnectionPragmas::mmap_size`. Together with `DictDb::warmup` the first lookups read
+    /// from memory.
+    pub fn mmap_size(mut self, bytes: u64) -> Self {
+        self.pragmas.mmap_size = Some(bytes);
+        self
+    }
+
+    /// Replaces all the per-connection pragmas, `ConnectionPragmas::default()` if not set.
+    pub fn pragmas(mut self, pragmas: ConnectionPragmas) -> Self {
+        self.pragmas = pragmas;
+        self
+    }
+
+    /// The pragmas set on every new connection.
+    pub fn connection_pragmas(&self) -> &ConnectionPragmas {
+        &self.pragmas
+    }
+
+    /// Logs searches taking at least `threshold` as "slow query" tracing events (target
+    /// `jmdictdb::slow_query`) and collects the timings returned by `DictDb::stats`.
+    /// Off by default.
+    pub fn slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_query = Some(threshold);
+        self
+    }
+
+    /// Makes searches run `DictDb::check_generation` when `interval` has passed since the
+    /// last check, and after a search fails, so that a database rebuilt by another process
+    /// gives `DictError::DatabaseReplaced` instead of stale or broken rows. Off by default.
+    pub fn check_generation_every(mut self, interval: Duration) -> Self {
+        self.generation_check = Some(interval);
+        self
+    }
+
+    /// Keeps up to `entries` of the entries read by `DictDb::entry_shared` in memory, the
+    /// least recently used going first. Off (0) by default.
+    pub fn entry_cache(mut self, entries: usize) -> Self {
+        self.entry_cache = entries;
+        self
+    }
+
+    /// The shortest queries the substring searches accept, `QueryGuards::default()` if not
+    /// set; shorter ones are `DictError::QueryTooShort`.
+    pub fn query_guards(mut self, guards: QueryGuards) -> Self {
+        self.guards = guards;
+        self
+    }
+
+    /// Gives every `suggest_fast` suggestion a `SuggestionHint`: the reading, first gloss
+    /// and part of speech of its entry, so that a list can show "生 (なま) — raw" and
+    /// "生 (せい) — life" instead of 生 twice. Off by default, as the hints about double the
+    /// memory of `build_suggest_index`.
+    pub fn suggestion_hints(mut self, hints: bool) -> Self {
+        self.suggestion_hints = hints;
+        self
+    }
+
+    /// Loads a SQLite extension on every connection, by the path of its shared library
+    /// without the suffix, e.g. "/usr/lib/sqlite3/libSqliteIcu" or "./spellfix". ICU and
+    /// spellfix1 are used when they are there, see `DictDb::has_extension`; connecting
+    /// fails if the library can't be loaded.
+    #[cfg(feature = "sqlite-extensions")]
+    pub fn extension(mut self, path: impl Into<String>) -> Self {
+        self.extensions.push(path.into());
+        self
+    }
+
+    /// Connects with these settings, see `DictDb::connect_with`.
+    pub async fn connect(self) -> Result<DictDb> {
+        DictDb::connect_with(self).await
+    }
+}
@@ -0,0 +1,110 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::kana::{is_kana_str, katakana_to_hiragana};
+use crate::{normalize_kana, romaji_to_kana, verb_stem_candidates, DictDb, MatchKind, RankOptions, Result, ScoredEntry, READING_NORMALIZATION};
+
+/// A change `search_ranked_outcome` made to the query, in the order they were made.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Transformation {
+    /// Leading and trailing whitespace was removed.
+    Trimmed,
+    /// The query was read as romaji, e.g. "neko" as ねこ.
+    RomajiConverted,
+    /// Katakana were searched as hiragana, e.g. ネコ as ねこ.
+    KatakanaFolded,
+    /// The query was taken as a verb stem, `chain` going from it to the dictionary form the
+    /// results have, e.g. ["食べ", "食べる"]. See `RankOptions::verb_stems`.
+    Deinflected { chain: Vec<String> },
+    /// The query was searched as a spelling variant of a reading, e.g. らあめん for らーめん,
+    /// see `normalize_kana`.
+    VariantExpanded { variant: String },
+}
+
+/// The results of `search_ranked_outcome` with what was actually searched.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SearchOutcome {
+    /// The query as given.
+    pub original_query: String,
+    /// The query the results are for, e.g. for "showing results for ねこ".
+    pub effective_query: String,
+    pub transformations: Vec<Transformation>,
+    pub results: Vec<ScoredEntry>,
+}
+
+impl SearchOutcome {
+    /// Whether the results are for another query than the one given, whitespace aside.
+    pub fn query_changed(&self) -> bool {
+        self.effective_query != self.original_query.trim()
+    }
+}
+
+impl DictDb {
+    /// `search_ranked`, reporting how the query was changed along the way.
+    ///
+    /// When the query finds nothing it is tried again as romaji (if it has ASCII letters),
+    /// with katakana folded to hiragana, then as a spelling variant of a stored reading,
+    /// each step building on the one before, until one finds something. Verb stems are
+    /// reported as `Transformation::Deinflected` when `opts.verb_stems` brought them in.
+    pub async fn search_ranked_outcome(&self, query: &str, opts: &RankOptions) -> Result<SearchOutcome> {
+        let mut transformations = Vec::new();
+        let mut effective = query.trim().to_string();
+        if effective != query {
+            transformations.push(Transformation::Trimmed);
+        }
+        let mut results = self.search_ranked(&effective, opts).await?;
+
+        if results.is_empty() && effective.chars().any(|c| c.is_ascii_alphabetic()) {
+            let kana = romaji_to_kana(&effective);
+            if is_kana_str(&kana) {
+                results = self.search_ranked(&kana, opts).await?;
+                effective = kana;
+                transformations.push(Transformation::RomajiConverted);
+            }
+        }
+        if results.is_empty() {
+            let hiragana = katakana_to_hiragana(&effective);
+            if hiragana != effective {
+                results = self.search_ranked(&hiragana, opts).await?;
+                effective = hiragana;
+                transformations.push(Transformation::KatakanaFolded);
+            }
+        }
+        if results.is_empty() && is_kana_str(&effective) {
+            // The stored reading the normalized query stands for, if any
+            let norm = normalize_kana(&effective, READING_NORMALIZATION);
+            let found = self.search_entries_by_reading_normalized(&effective).await?;
+            let variant = found
+                .iter()
+                .flat_map(|e| &e.rebs)
+                .find(|reb| **reb != effective && normalize_kana(reb, READING_NORMALIZATION) == norm)
+                .cloned();
+            if let Some(variant) = variant {
+                results = self.search_ranked(&variant, opts).await?;
+                effective = variant.clone();
+                transformations.push(Transformation::VariantExpanded { variant });
+            }
+        }
+
+        if opts.verb_stems {
+            // Exact matches without a form equal to the query came in as verbs of the stem
+            let mut chains: Vec<Vec<String>> = Vec::new();
+            for result in results.iter().filter(|r| r.match_kind == MatchKind::Exact) {
+                let entry = &result.entry;
+                let forms: Vec<&String> = entry.kebs.iter().flatten().chain(&entry.rebs).collect();
+                if forms.iter().any(|f| **f == effective) {
+                    continue;
+                }
+                if let Some(candidate) = verb_stem_candidates(&effective).into_iter().find(|c| forms.contains(&&c.headword)) {
+                    let chain = vec![effective.clone(), candidate.headword];
+                    if !chains.contains(&chain) {
+                        chains.push(chain);
+                    }
+                }
+            }
+            transformations.extend(chains.into_iter().map(|chain| Transformation::Deinflected { chain }));
+        }
+
+        Ok(SearchOutcome { original_query: query.to_string(), effective_query: effective, transformations, results })
+    }
+}
@@ -0,0 +1,140 @@
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::sync::LazyLock;
+
+use crate::entities::parse_dtd_entities;
+use crate::{Entry, JMDict, PartOfSpeech, Result};
+
+// An entity reference such as &v5k-s;
+static ENTITY_REF_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"&([A-Za-z][\w.-]*);").unwrap());
+
+/// The entities XML itself defines, left for the XML parser.
+const XML_ENTITIES: [&str; 5] = ["amp", "lt", "gt", "quot", "apos"];
+
+/// Replaces the JMdict entity references with their values.
+///
+/// quick-xml doesn't read DTDs, so `&v1;` has to become "Ichidan verb" before parsing.
+/// Entities missing from `entities` fall back to the part-of-speech table, and are replaced
+/// by their bare name (e.g. "uk") if they are unknown there too.
+///
+/// # Returns
+/// The expanded XML and the number of references replaced.
+pub(crate) fn expand_entities(xml: &str, entities: &BTreeMap<String, String>) -> (String, usize) {
+    let mut replaced = 0;
+    let xml = ENTITY_REF_RE
+        .replace_all(xml, |cap: &Captures| {
+            let name = &cap[1];
+            if XML_ENTITIES.contains(&name) {
+                return cap[0].to_string();
+            }
+            replaced += 1;
+            let value = match entities.get(name) {
+                Some(value) => value.clone(),
+                None => PartOfSpeech::from_code(name).map_or_else(|| name.to_string(), |pos| pos.description().to_string()),
+            };
+            value.replace('&', "&amp;").replace('<', "&lt;")
+        })
+        .into_owned();
+    (xml, replaced)
+}
+
+/// Parses a complete JMdict XML document, expanding the entities of its DTD.
+pub fn parse_jmdict_str(xml: &str) -> Result<JMDict> {
+    let entities = parse_dtd_entities(xml);
+    let (xml, _) = expand_entities(xml, &entities);
+    Ok(quick_xml::de::from_str(&xml)?)
+}
+
+/// Reads and parses a complete JMdict XML document, see `parse_jmdict_str`.
+pub fn parse_jmdict_reader<R: BufRead>(mut reader: R) -> Result<JMDict> {
+    let mut xml = String::new();
+    reader.read_to_string(&mut xml)?;
+    parse_jmdict_str(&xml)
+}
+
+/// Parses a single `<entry>` element.
+///
+/// There is no DTD to read, so part-of-speech entities are expanded from the built-in
+/// table and other entities are replaced by their name.
+pub fn parse_entry_xml(xml: &str) -> Result<Entry> {
+    let (xml, _) = expand_entities(xml.trim(), &BTreeMap::new());
+    Ok(quick_xml::de::from_str(&xml)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE JMdict [
+<!ENTITY v5k-s "Godan verb - Iku/Yuku special class">
+<!ENTITY vi "intransitive verb">
+<!ENTITY uk "word usually written using kana alone">
+]>
+<JMdict>
+<entry>
+<ent_seq>1578850</ent_seq>
+<k_ele><keb>行く</keb><ke_pri>ichi1</ke_pri></k_ele>
+<r_ele><reb>いく</reb></r_ele>
+<sense><pos>&v5k-s;</pos><pos>&vi;</pos><xref>来る</xref><gloss>to go</gloss></sense>
+</entry>
+<entry>
+<ent_seq>1000</ent_seq>
+<r_ele><reb>R&amp;B</reb></r_ele>
+<sense><gloss>rhythm &amp; blues</gloss></sense>
+</entry>
+</JMdict>"#;
+
+    #[test]
+    fn document_entities_are_expanded() {
+        let dict = parse_jmdict_str(DOCUMENT).unwrap();
+        assert_eq!(dict.entry.len(), 2);
+        let iku = &dict.entry[0];
+        assert_eq!(iku.ent_seq, "1578850");
+        assert_eq!(iku.sense[0].pos, ["Godan verb - Iku/Yuku special class", "intransitive verb"]);
+        assert_eq!(iku.k_ele.as_ref().unwrap()[0].ke_pri, ["ichi1"]);
+        // XML's own entities are left to the parser
+        assert_eq!(dict.entry[1].r_ele[0].reb, "R&B");
+        assert_eq!(dict.entry[1].sense[0].gloss, ["rhythm & blues"]);
+    }
+
+    #[test]
+    fn reader_matches_str() {
+        let dict = parse_jmdict_reader(DOCUMENT.as_bytes()).unwrap();
+        assert_eq!(dict.entry.len(), 2);
+    }
+
+    #[test]
+    fn single_entry_with_several_entities() {
+        let entry = parse_entry_xml(
+            "<entry><ent_seq>1358280</ent_seq>\
+             <k_ele><keb>食べる</keb></k_ele><r_ele><reb>たべる</reb></r_ele>\
+             <sense><pos>&v1;</pos><pos>&vt;</pos><gloss>to eat</gloss></sense>\
+             <sense><pos>&n;</pos><pos>&uk;</pos><gloss>food</gloss></sense></entry>",
+        )
+        .unwrap();
+        assert_eq!(entry.sense[0].pos, ["Ichidan verb", "transitive verb"]);
+        assert_eq!(entry.sense[1].pos, ["noun (common) (futsuumeishi)", "uk"]);
+        assert!(entry.k_ele.is_some());
+    }
+
+    #[test]
+    fn glosses_are_split_by_language() {
+        let entry = parse_entry_xml(
+            "<entry><ent_seq>1</ent_seq><r_ele><reb>ねこ</reb></r_ele>\
+             <sense><gloss>cat</gloss><gloss xml:lang=\"eng\">puss</gloss>\
+             <gloss xml:lang=\"ger\">Katze</gloss><gloss xml:lang=\"fre\">chat</gloss></sense></entry>",
+        )
+        .unwrap();
+        let sense = &entry.sense[0];
+        assert_eq!(sense.gloss, ["cat", "puss"]);
+        let other: Vec<(&str, &str)> = sense.other_glosses.iter().map(|g| (g.lang.as_str(), g.text.as_str())).collect();
+        assert_eq!(other, [("ger", "Katze"), ("fre", "chat")]);
+    }
+
+    #[test]
+    fn malformed_entry_is_an_error() {
+        assert!(parse_entry_xml("<entry><ent_seq>1</ent_seq>").is_err());
+    }
+}
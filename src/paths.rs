@@ -0,0 +1,89 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::{DictDb, DictError, Result};
+
+/// Overrides the database location, set it to the path of the database file.
+pub const DB_PATH_ENV: &str = "JMDICTDB_PATH";
+
+/// The name of the database file inside the data directory.
+const DB_FILE_NAME: &str = "jmdict_e.db";
+
+/// The per-platform data directory of the crate:
+/// - Linux and others: `$XDG_DATA_HOME/jmdictdb`, or `~/.local/share/jmdictdb`
+/// - macOS: `~/Library/Application Support/jmdictdb`
+/// - Windows: `%APPDATA%\jmdictdb`
+///
+/// Returns None if the home directory can't be determined.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+    };
+    base.map(|dir| dir.join("jmdictdb"))
+}
+
+/// The per-platform cache directory of the crate:
+/// - Linux and others: `$XDG_CACHE_HOME/jmdictdb`, or `~/.cache/jmdictdb`
+/// - macOS: `~/Library/Caches/jmdictdb`
+/// - Windows: `%LOCALAPPDATA%\jmdictdb`
+///
+/// Returns None if the home directory can't be determined.
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Caches"))
+    } else {
+        env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    base.map(|dir| dir.join("jmdictdb"))
+}
+
+/// Resolves where the database file lives.
+///
+/// `JMDICTDB_PATH` wins if set, then the file in `data_dir()`. Without a home directory
+/// it falls back to `data/jmdict_e.db` relative to the working directory, like `DB_URL`.
+pub fn default_db_path() -> PathBuf {
+    if let Some(path) = env::var_os(DB_PATH_ENV).filter(|p| !p.is_empty()) {
+        return PathBuf::from(path);
+    }
+    match data_dir() {
+        Some(dir) => dir.join(DB_FILE_NAME),
+        None => PathBuf::from("data").join(DB_FILE_NAME),
+    }
+}
+
+/// `default_db_path()` as a database URL for `DictDb::connect`.
+pub fn default_db_url() -> String {
+    format!("sqlite:{}", default_db_path().display())
+}
+
+impl DictDb {
+    /// Connects to the database at `default_db_path()`.
+    ///
+    /// # Errors
+    /// `DictError::DatabaseNotBuilt` if the file does not exist yet, `DictError::DatabaseEmpty`
+    /// if it has no entries (e.g. a build was interrupted).
+    pub async fn connect_default() -> Result<Self> {
+        let path = default_db_path();
+        if !path.exists() {
+            return Err(DictError::DatabaseNotBuilt(path).into());
+        }
+        let db = Self::connect(&default_db_url()).await?;
+        if db.is_empty().await? {
+            db.close().await;
+            return Err(DictError::DatabaseEmpty(path).into());
+        }
+        Ok(db)
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Declares the `PartOfSpeech` enum together with its JMdict entity code and
+/// the description the entity expands to (which is what gets stored in the db).
+macro_rules! parts_of_speech {
+    ($($variant:ident => ($code:literal, $desc:literal),)*) => {
+        /// A JMdict part-of-speech tag.
+        ///
+        /// Each variant maps to the entity code used in the JMdict DTD (e.g. `v5k`)
+        /// and to the description it expands to (e.g. "Godan verb with 'ku' ending").
+        /// The database stores the description, so both forms are accepted when parsing.
+        /// Tags that are not known to this crate are kept as `Other`.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum PartOfSpeech {
+            $($variant,)*
+            /// A tag not known to this crate, kept verbatim.
+            Other(String),
+        }
+
+        impl PartOfSpeech {
+            /// All known parts of speech, in DTD order.
+            pub const ALL: &'static [PartOfSpeech] = &[$(PartOfSpeech::$variant,)*];
+
+            /// Returns the JMdict entity code, e.g. "v5k".
+            pub fn code(&self) -> &str {
+                match self {
+                    $(PartOfSpeech::$variant => $code,)*
+                    PartOfSpeech::Other(s) => s,
+                }
+            }
+
+            /// Returns the description the entity expands to, e.g. "Godan verb with 'ku' ending".
+            pub fn description(&self) -> &str {
+                match self {
+                    $(PartOfSpeech::$variant => $desc,)*
+                    PartOfSpeech::Other(s) => s,
+                }
+            }
+
+            /// Looks up a part of speech by its entity code.
+            pub fn from_code(code: &str) -> Option<Self> {
+                match code {
+                    $($code => Some(PartOfSpeech::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// Looks up a part of speech by its expanded description, as stored in the db.
+            pub fn from_description(desc: &str) -> Option<Self> {
+                match desc {
+                    $($desc => Some(PartOfSpeech::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+parts_of_speech! {
+    AdjF => ("adj-f", "noun or verb acting prenominally"),
+    AdjI => ("adj-i", "adjective (keiyoushi)"),
+    AdjIx => ("adj-ix", "adjective (keiyoushi) - yoi/ii class"),
+    AdjKari => ("adj-kari", "'kari' adjective (archaic)"),
+    AdjKu => ("adj-ku", "'ku' adjective (archaic)"),
+    AdjNa => ("adj-na", "adjectival nouns or quasi-adjectives (keiyodoshi)"),
+    AdjNari => ("adj-nari", "archaic/formal form of na-adjective"),
+    AdjNo => ("adj-no", "nouns which may take the genitive case particle 'no'"),
+    AdjPn => ("adj-pn", "pre-noun adjectival (rentaishi)"),
+    AdjShiku => ("adj-shiku", "'shiku' adjective (archaic)"),
+    AdjT => ("adj-t", "'taru' adjective"),
+    Adv => ("adv", "adverb (fukushi)"),
+    AdvTo => ("adv-to", "adverb taking the 'to' particle"),
+    Aux => ("aux", "auxiliary"),
+    AuxAdj => ("aux-adj", "auxiliary adjective"),
+    AuxV => ("aux-v", "auxiliary verb"),
+    Conj => ("conj", "conjunction"),
+    Cop => ("cop", "copula"),
+    Ctr => ("ctr", "counter"),
+    Exp => ("exp", "expressions (phrases, clauses, etc.)"),
+    Int => ("int", "interjection (kandoushi)"),
+    Noun => ("n", "noun (common) (futsuumeishi)"),
+    NounAdv => ("n-adv", "adverbial noun (fukushitekimeishi)"),
+    NounProper => ("n-pr", "proper noun"),
+    NounPrefix => ("n-pref", "noun, used as a prefix"),
+    NounSuffix => ("n-suf", "noun, used as a suffix"),
+    NounTemporal => ("n-t", "noun (temporal) (jisoumeishi)"),
+    Num => ("num", "numeric"),
+    Pronoun => ("pn", "pronoun"),
+    Prefix => ("pref", "prefix"),
+    Particle => ("prt", "particle"),
+    Suffix => ("suf", "suffix"),
+    Unclassified => ("unc", "unclassified"),
+    VUnspec => ("v-unspec", "verb unspecified"),
+    V1 => ("v1", "Ichidan verb"),
+    V1S => ("v1-s", "Ichidan verb - kureru special class"),
+    V2aS => ("v2a-s", "Nidan verb with 'u' ending (archaic)"),
+    V2bK => ("v2b-k", "Nidan verb (upper class) with 'bu' ending (archaic)"),
+    V2bS => ("v2b-s", "Nidan verb (lower class) with 'bu' ending (archaic)"),
+    V2dK => ("v2d-k", "Nidan verb (upper class) with 'dzu' ending (archaic)"),
+    V2dS => ("v2d-s", "Nidan verb (lower class) with 'dzu' ending (archaic)"),
+    V2gK => ("v2g-k", "Nidan verb (upper class) with 'gu' ending (archaic)"),
+    V2gS => ("v2g-s", "Nidan verb (lower class) with 'gu' ending (archaic)"),
+    V2hK => ("v2h-k", "Nidan verb (upper class) with 'hu/fu' ending (archaic)"),
+    V2hS => ("v2h-s", "Nidan verb (lower class) with 'hu/fu' ending (archaic)"),
+    V2kK => ("v2k-k", "Nidan verb (upper class) with 'ku' ending (archaic)"),
+    V2kS => ("v2k-s", "Nidan verb (lower class) with 'ku' ending (archaic)"),
+    V2mK => ("v2m-k", "Nidan verb (upper class) with 'mu' ending (archaic)"),
+    V2mS => ("v2m-s", "Nidan verb (lower class) with 'mu' ending (archaic)"),
+    V2nS => ("v2n-s", "Nidan verb (lower class) with 'nu' ending (archaic)"),
+    V2rK => ("v2r-k", "Nidan verb (upper class) with 'ru' ending (archaic)"),
+    V2rS => ("v2r-s", "Nidan verb (lower class) with 'ru' ending (archaic)"),
+    V2sS => ("v2s-s", "Nidan verb (lower class) with 'su' ending (archaic)"),
+    V2tK => ("v2t-k", "Nidan verb (upper class) with 'tsu' ending (archaic)"),
+    V2tS => ("v2t-s", "Nidan verb (lower class) with 'tsu' ending (archaic)"),
+    V2wS => ("v2w-s", "Nidan verb (lower class) with 'u' ending and 'we' conjugation (archaic)"),
+    V2yK => ("v2y-k", "Nidan verb (upper class) with 'yu' ending (archaic)"),
+    V2yS => ("v2y-s", "Nidan verb (lower class) with 'yu' ending (archaic)"),
+    V2zS => ("v2z-s", "Nidan verb (lower class) with 'zu' ending (archaic)"),
+    V4b => ("v4b", "Yodan verb with 'bu' ending (archaic)"),
+    V4g => ("v4g", "Yodan verb with 'gu' ending (archaic)"),
+    V4h => ("v4h", "Yodan verb with 'hu/fu' ending (archaic)"),
+    V4k => ("v4k", "Yodan verb with 'ku' ending (archaic)"),
+    V4m => ("v4m", "Yodan verb with 'mu' ending (archaic)"),
+    V4n => ("v4n", "Yodan verb with 'nu' ending (archaic)"),
+    V4r => ("v4r", "Yodan verb with 'ru' ending (archaic)"),
+    V4s => ("v4s", "Yodan verb with 'su' ending (archaic)"),
+    V4t => ("v4t", "Yodan verb with 'tsu' ending (archaic)"),
+    V5aru => ("v5aru", "Godan verb - -aru special class"),
+    V5b => ("v5b", "Godan verb with 'bu' ending"),
+    V5g => ("v5g", "Godan verb with 'gu' ending"),
+    V5k => ("v5k", "Godan verb with 'ku' ending"),
+    V5kS => ("v5k-s", "Godan verb - Iku/Yuku special class"),
+    V5m => ("v5m", "Godan verb with 'mu' ending"),
+    V5n => ("v5n", "Godan verb with 'nu' ending"),
+    V5r => ("v5r", "Godan verb with 'ru' ending"),
+    V5rI => ("v5r-i", "Godan verb with 'ru' ending (irregular verb)"),
+    V5s => ("v5s", "Godan verb with 'su' ending"),
+    V5t => ("v5t", "Godan verb with 'tsu' ending"),
+    V5u => ("v5u", "Godan verb with 'u' ending"),
+    V5uS => ("v5u-s", "Godan verb with 'u' ending (special class)"),
+    V5uru => ("v5uru", "Godan verb - Uru old class verb (old form of Eru)"),
+    Vi => ("vi", "intransitive verb"),
+    Vk => ("vk", "Kuru verb - special class"),
+    Vn => ("vn", "irregular nu verb"),
+    Vr => ("vr", "irregular ru verb, plain form ends with -ri"),
+    Vs => ("vs", "noun or participle which takes the aux. verb suru"),
+    VsC => ("vs-c", "su verb - precursor to the modern suru"),
+    VsI => ("vs-i", "suru verb - included"),
+    VsS => ("vs-s", "suru verb - special class"),
+    Vt => ("vt", "transitive verb"),
+    Vz => ("vz", "Ichidan verb - zuru verb (alternative form of -jiru verbs)"),
+}
+
+impl PartOfSpeech {
+    /// Parses a stored pos string, accepting either the entity code or its description.
+    /// Unknown strings become `PartOfSpeech::Other`.
+    pub fn parse(s: &str) -> Self {
+        Self::from_description(s)
+            .or_else(|| Self::from_code(s))
+            .unwrap_or_else(|| PartOfSpeech::Other(s.to_string()))
+    }
+}
+
+impl FromStr for PartOfSpeech {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+impl fmt::Display for PartOfSpeech {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
@@ -0,0 +1,66 @@
+//! Matching headwords regardless of the punctuation in them.
+//!
+//! Some kebs and rebs separate their parts, データ・ベース, ジャン＝ポール, which nobody
+//! types. `strip_headword_punctuation` removes the separators; the punct_index table keeps
+//! the stripped form of every punctuated headword for `RankOptions::ignore_punctuation` and
+//! `DictDb::suggest_fast`.
+
+use sqlx::{Sqlite, Transaction};
+
+use crate::{DictDb, Result};
+
+/// The characters `strip_headword_punctuation` removes: the middle dots ・ and ･, the double
+/// hyphens ＝, = and ゠, and ASCII and ideographic spaces.
+pub const HEADWORD_PUNCTUATION: [char; 7] = ['・', '･', '＝', '=', '゠', ' ', '\u{3000}'];
+
+/// Returns true if the string contains one of `HEADWORD_PUNCTUATION`.
+pub fn has_headword_punctuation(s: &str) -> bool {
+    s.contains(HEADWORD_PUNCTUATION)
+}
+
+/// Removes `HEADWORD_PUNCTUATION`, データ・ベース becomes データベース.
+pub fn strip_headword_punctuation(s: &str) -> String {
+    s.replace(HEADWORD_PUNCTUATION, "")
+}
+
+impl DictDb {
+    /// Replaces the punct_index rows of an entry with its punctuated kebs and rebs.
+    pub(crate) async fn index_punctuation_tx(tx: &mut Transaction<'_, Sqlite>, ent_seq: i64, forms: &[&str]) -> Result<()> {
+        sqlx::query("DELETE FROM punct_index WHERE ent_seq = ?1")
+            .bind(ent_seq)
+            .execute(&mut **tx)
+            .await?;
+        for form in forms.iter().filter(|f| has_headword_punctuation(f)) {
+            sqlx::query("INSERT OR IGNORE INTO punct_index (plain, ent_seq, form) VALUES (?1, ?2, ?3)")
+                .bind(strip_headword_punctuation(form))
+                .bind(ent_seq)
+                .bind(form)
+                .execute(&mut **tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the punct_index table from the kebs and rebs of all entries.
+    ///
+    /// `upsert_entry` keeps the index up to date, this is for databases built before it existed.
+    pub(crate) async fn rebuild_punctuation_index(&self) -> Result<()> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let rows: Vec<(i64, Option<String>, String)> = sqlx::query_as("SELECT ent_seq, kebs, rebs FROM entries")
+                .fetch_all(&mut *tx)
+                .await?;
+            for (ent_seq, kebs, rebs) in rows {
+                let mut forms: Vec<String> = serde_json::from_str(&rebs)?;
+                if let Some(kebs) = kebs {
+                    forms.extend(serde_json::from_str::<Vec<String>>(&kebs)?);
+                }
+                let forms: Vec<&str> = forms.iter().map(String::as_str).collect();
+                Self::index_punctuation_tx(&mut tx, ent_seq, &forms).await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+}
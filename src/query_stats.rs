@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{DictDb, Result};
+
+/// The upper bounds of the latency histogram buckets, the last bucket is everything slower.
+pub const LATENCY_BUCKETS_MS: [u64; 7] = [1, 5, 10, 50, 100, 500, 1000];
+
+/// The longest query text put in a slow query event.
+const MAX_LOGGED_QUERY_CHARS: usize = 80;
+
+/// Timings of one search method, see `DictDb::stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodStats {
+    pub calls: u64,
+    /// Calls that took at least the slow query threshold.
+    pub slow: u64,
+    pub total: Duration,
+    pub max: Duration,
+    /// Calls per latency bucket: `buckets[i]` counts calls up to `LATENCY_BUCKETS_MS[i]`
+    /// (and above the previous bound), the last entry those slower than a second.
+    pub buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+impl MethodStats {
+    /// The mean duration of a call.
+    pub fn mean(&self) -> Duration {
+        if self.calls == 0 { Duration::ZERO } else { self.total / self.calls as u32 }
+    }
+
+    fn record(&mut self, elapsed: Duration, slow: bool) {
+        self.calls += 1;
+        self.slow += slow as u64;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        let ms = elapsed.as_millis();
+        let bucket = LATENCY_BUCKETS_MS.iter().position(|&bound| ms < bound as u128).unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+}
+
+/// Timings per search method, e.g. "search_ranked".
+pub type QueryStats = BTreeMap<&'static str, MethodStats>;
+
+/// The slow query threshold and the timings collected so far.
+#[derive(Debug)]
+pub(crate) struct QueryMonitor {
+    threshold: Duration,
+    stats: Mutex<QueryStats>,
+}
+
+impl QueryMonitor {
+    pub(crate) fn new(threshold: Duration) -> Self {
+        Self { threshold, stats: Mutex::default() }
+    }
+}
+
+/// How many results a search returned, for the slow query events.
+pub(crate) trait RowCount {
+    fn row_count(&self) -> usize;
+}
+
+impl<T> RowCount for Vec<T> {
+    fn row_count(&self) -> usize {
+        self.len()
+    }
+}
+
+/// The query text as logged: trimmed, whitespace collapsed and cut to a sane length.
+fn normalize_query(query: &str) -> String {
+    let collapsed = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    match collapsed.char_indices().nth(MAX_LOGGED_QUERY_CHARS) {
+        Some((end, _)) => format!("{}…", &collapsed[..end]),
+        None => collapsed,
+    }
+}
+
+impl DictDb {
+    /// Runs a search, timing it when a slow query threshold is set.
+    ///
+    /// `params` summarizes the other arguments and is only called for slow queries.
+    pub(crate) async fn observe<T, F, P>(&self, method: &'static str, query: &str, params: P, search: F) -> Result<T>
+    where
+        T: RowCount,
+        F: Future<Output = Result<T>>,
+        P: FnOnce() -> String,
+    {
+        self.check_generation_if_due().await?;
+        let Some(monitor) = &self.monitor else {
+            return self.explain_failure(search.await).await;
+        };
+        let started = Instant::now();
+        let result = self.explain_failure(search.await).await;
+        let elapsed = started.elapsed();
+
+        let slow = elapsed >= monitor.threshold;
+        if let Ok(mut stats) = monitor.stats.lock() {
+            stats.entry(method).or_default().record(elapsed, slow);
+        }
+        if slow {
+            tracing::warn!(
+                target: "jmdictdb::slow_query",
+                method,
+                query = %normalize_query(query),
+                params = %params(),
+                rows = result.as_ref().map_or(0, |rows| rows.row_count()),
+                elapsed_ms = elapsed.as_secs_f64() * 1000.0,
+                ok = result.is_ok(),
+                "slow query"
+            );
+        }
+        result
+    }
+
+    /// The timings of the search methods called so far, by method name.
+    ///
+    /// Only collected when a threshold was set with `DictDbOptions::slow_query_threshold`,
+    /// otherwise empty.
+    pub fn stats(&self) -> QueryStats {
+        self.monitor
+            .as_ref()
+            .and_then(|monitor| monitor.stats.lock().ok().map(|stats| stats.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Clears the timings returned by `stats`.
+    pub fn reset_stats(&self) {
+        if let Some(stats) = self.monitor.as_ref().and_then(|monitor| monitor.stats.lock().ok()).as_mut() {
+            stats.clear();
+        }
+    }
+}
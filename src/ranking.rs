@@ -0,0 +1,462 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, QueryBuilder, Sqlite};
+use std::cmp::Ordering;
+
+use crate::dedupe::DuplicateGroups;
+use crate::guards::non_empty;
+use crate::punctuation::strip_headword_punctuation;
+use crate::{fold_vowel_length, DictDb, EntryParsed, Result, SearchFilter};
+
+/// How a ranked search result matched the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    /// A keb or reb is identical to the query.
+    Exact,
+    /// A keb or reb starts with the query.
+    Prefix,
+    /// A reb is the query once `fold_vowel_length` folds the vowel length of both, with
+    /// `RankOptions::ignore_vowel_length`: サッカ for サッカー, but also ビル for ビール.
+    LengthFolded,
+}
+
+/// Options for `DictDb::search_ranked`.
+#[derive(Debug, Clone)]
+pub struct RankOptions {
+    /// The frequency list used as a tiebreaker, by name. If None, the best rank across all
+    /// loaded lists is used.
+    pub frequency_source: Option<String>,
+    /// The maximum number of results, default 20.
+    pub limit: usize,
+    /// Restrictions on the returned entries. The form conditions (lengths, kanji count) apply
+    /// to the keb or reb that matched the query.
+    pub filter: SearchFilter,
+    /// Attach a `ScoreBreakdown` to each result, to see why it ranks where it does.
+    pub explain: bool,
+    /// Also take the query as a verb stem, see `DictDb::search_verb_stem`: the verbs it is
+    /// the stem of rank as exact matches, 食べ finds 食べる before 食べ物.
+    pub verb_stems: bool,
+    /// Merge near-duplicate entries into the best ranked one, see
+    /// `dedupe_by_reading_and_gloss`; the others end up in its `variants`. `limit` then
+    /// counts the merged results.
+    pub dedupe: bool,
+    /// Compare kebs and rebs with the query after `strip_headword_punctuation`, so that
+    /// データベース finds データ・ベース and データ・ベース finds データベース.
+    pub ignore_punctuation: bool,
+    /// Also match readings that differ from the query in vowel length only, see
+    /// `fold_vowel_length`, for learners who mistype it: おかさん finds おかあさん. Such
+    /// results have `ScoredEntry::length_folded` set and rank below the exact matches, as
+    /// they may be a different word (ビル, building, for ビール, beer); off by default.
+    pub ignore_vowel_length: bool,
+}
+
+impl Default for RankOptions {
+    fn default() -> Self {
+        Self {
+            frequency_source: None,
+            limit: 20,
+            filter: SearchFilter::default(),
+            explain: false,
+            verb_stems: false,
+            dedupe: false,
+            ignore_punctuation: false,
+            ignore_vowel_length: false,
+        }
+    }
+}
+
+/// A search result together with its ranking score.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ScoredEntry {
+    pub entry: EntryParsed,
+    /// How the entry matched the query.
+    pub match_kind: MatchKind,
+    /// The ranking score, higher is better.
+    pub score: f64,
+    /// How the score and position came about, with `RankOptions::explain` only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<ScoreBreakdown>,
+    /// The near-duplicates merged into this result with `RankOptions::dedupe`, best first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<ScoredEntry>,
+    /// The entry matched only with `RankOptions::ignore_vowel_length`, its reading is longer
+    /// or shorter than the query: show it as "did you mean" rather than as the word searched.
+    #[serde(default)]
+    pub length_folded: bool,
+}
+
+/// The parts a ranked result's position is computed from, see `RankOptions::explain`.
+///
+/// `search_ranked` orders its results with this very value, by `total`, then
+/// `nf_bucket` (lower first, entries without one last), then `priority_component`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScoreBreakdown {
+    pub match_kind: MatchKind,
+    /// 100 for exact matches, 75 for length-folded ones, 50 for prefix matches.
+    pub match_component: f64,
+    /// In (0, 10], 10 for rank 1 and lower for rarer words, 0 for words missing from the
+    /// frequency lists.
+    pub frequency_component: f64,
+    /// The nfNN bucket of the entry, the first tiebreaker.
+    pub nf_bucket: Option<i64>,
+    /// `priority_score` of the priority tags, the second tiebreaker.
+    pub priority_component: i64,
+    /// `match_component + frequency_component`, the score.
+    pub total: f64,
+}
+
+impl ScoreBreakdown {
+    /// Computes the score of a match together with its tiebreakers.
+    pub fn compute(match_kind: MatchKind, frequency_rank: Option<i64>, nf_bucket: Option<i64>, priority: &[String]) -> Self {
+        let match_component = match match_kind {
+            MatchKind::Exact => 100.0,
+            MatchKind::LengthFolded => 75.0,
+            MatchKind::Prefix => 50.0,
+        };
+        let frequency_component = match frequency_rank {
+            Some(rank) => 10.0 / (1.0 + (rank.max(1) as f64).log10()),
+            None => 0.0,
+        };
+        Self {
+            match_kind,
+            match_component,
+            frequency_component,
+            nf_bucket,
+            priority_component: priority_score(priority),
+            total: match_component + frequency_component,
+        }
+    }
+
+    /// Orders the better ranked match first.
+    pub fn rank_cmp(&self, other: &Self) -> Ordering {
+        // Entries with a bucket before those without, lower buckets first
+        let bucket_key = |b: &Self| b.nf_bucket.unwrap_or(i64::MAX);
+        other
+            .total
+            .total_cmp(&self.total)
+            .then(bucket_key(self).cmp(&bucket_key(other)))
+            .then(other.priority_component.cmp(&self.priority_component))
+    }
+}
+
+/// What a ranked search knows about a candidate, the input of a `Ranker`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchContext {
+    pub ent_seq: i64,
+    /// How a keb or reb of the entry matched the query.
+    pub match_kind: MatchKind,
+    /// The priority tags of the entry, e.g. "ichi1", "nf02".
+    pub priority: Vec<String>,
+    /// The best rank of the entry in the loaded frequency lists, 1 is the most frequent.
+    pub frequency_rank: Option<i64>,
+    /// The nfNN bucket of the entry.
+    pub nf_bucket: Option<i64>,
+    /// The JLPT level of the entry, 5 for N5 ... 1 for N1, if a level list was loaded.
+    pub jlpt_level: Option<u8>,
+    /// The length in characters of the keb or reb that matched, the shortest if several did.
+    pub headword_len: usize,
+    /// How often the user looked the entry up, see `DictDb::record_lookup`.
+    #[cfg(feature = "userdata")]
+    pub lookup_count: i64,
+}
+
+/// Scores search candidates for `DictDb::search_ranked_with`, higher ranks first.
+pub trait Ranker {
+    fn score(&self, ctx: &MatchContext) -> f64;
+}
+
+/// The ranking of `search_ranked`: `ScoreBreakdown::compute(..).total`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultRanker;
+
+impl Ranker for DefaultRanker {
+    fn score(&self, ctx: &MatchContext) -> f64 {
+        score(ctx.match_kind, ctx.frequency_rank)
+    }
+}
+
+/// Computes the ranking score of a match.
+///
+/// The match kind dominates (exact matches always rank above length-folded and prefix
+/// matches, length-folded ones above prefix matches), the frequency
+/// rank only breaks ties within the same match kind.
+pub fn score(match_kind: MatchKind, frequency_rank: Option<i64>) -> f64 {
+    ScoreBreakdown::compute(match_kind, frequency_rank, None, &[]).total
+}
+
+/// Scores how common an entry is from its priority tags.
+///
+/// The "1" lists (news1, ichi1, spec1, gai1) count twice as much as the "2" lists,
+/// entries without priority tags score 0.
+pub fn priority_score(priority: &[String]) -> i64 {
+    priority
+        .iter()
+        .map(|tag| match tag.as_str() {
+            "news1" | "ichi1" | "spec1" | "gai1" => 2,
+            "news2" | "ichi2" | "spec2" | "gai2" => 1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Returns the bucket of the best (lowest) nfNN tag, e.g. 25 for "nf25".
+///
+/// Each bucket holds 500 words of the frequency-sorted newspaper corpus, so this is finer
+/// than the news/ichi/spec flags.
+pub fn nf_bucket<S: AsRef<str>>(priority: &[S]) -> Option<u8> {
+    priority
+        .iter()
+        .filter_map(|tag| tag.as_ref().strip_prefix("nf")?.parse::<u8>().ok())
+        .filter(|b| (1..=48).contains(b))
+        .min()
+}
+
+#[derive(Debug, FromRow)]
+struct Candidate {
+    ent_seq: i64,
+    exact: bool,
+    rank: Option<i64>,
+    /// The rebs of entries whose reading_index matched the folded query, as a JSON array.
+    folded_forms: String,
+    nf_bucket: Option<i64>,
+    priority: String,
+    jlpt_level: Option<u8>,
+    #[cfg(feature = "userdata")]
+    lookup_count: i64,
+    /// The forms that matched, as a JSON array.
+    forms: String,
+}
+
+/// Escapes `%`, `_` and `\` for use in a LIKE pattern with `ESCAPE '\'`.
+pub(crate) fn escape_like(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+impl DictDb {
+    /// Searches kebs and rebs for exact and prefix matches and returns the results ranked.
+    ///
+    /// Exact matches come first, then prefix matches; within each group more frequent
+    /// entries (per the loaded frequency lists) rank higher. Ties are broken by the nfNN
+    /// bucket, then the other priority tags, then ent_seq.
+    ///
+    /// # Arguments
+    /// * `query` - The kanji or kana string to search for.
+    /// * `opts` - Frequency list selection and result limit.
+    ///
+    /// # Returns
+    /// A vector of ScoredEntry, best first.
+    pub async fn search_ranked(&self, query: &str, opts: &RankOptions) -> Result<Vec<ScoredEntry>> {
+        self.observe("search_ranked", query, || format!("{:?}", opts), async {
+            let stripped;
+            let mut query = non_empty(query)?;
+            if opts.ignore_punctuation {
+                stripped = strip_headword_punctuation(query);
+                query = non_empty(&stripped)?;
+            }
+
+            let mut candidates = self.rank_candidates(query, opts).await?;
+            if opts.verb_stems {
+                for (headword, verb) in self.verb_stem_matches(query).await? {
+                    if let Some(ctx) = candidates.iter_mut().find(|c| c.ent_seq == verb.ent_seq) {
+                        ctx.match_kind = MatchKind::Exact;
+                        continue;
+                    }
+                    // Not a prefix match of the stem, e.g. 勉強 for 勉強し
+                    let found = self.rank_candidates(&headword, opts).await?;
+                    candidates.extend(found.into_iter().filter(|c| c.ent_seq == verb.ent_seq && c.match_kind == MatchKind::Exact));
+                }
+            }
+            let mut scored: Vec<(MatchContext, ScoreBreakdown)> = candidates
+                .into_iter()
+                .map(|ctx| {
+                    let breakdown = ScoreBreakdown::compute(ctx.match_kind, ctx.frequency_rank, ctx.nf_bucket, &ctx.priority);
+                    (ctx, breakdown)
+                })
+                .collect();
+            scored.sort_by(|a, b| a.1.rank_cmp(&b.1).then(a.0.ent_seq.cmp(&b.0.ent_seq)));
+            if !opts.dedupe {
+                scored.truncate(opts.limit);
+            }
+
+            let mut results = Vec::with_capacity(scored.len().min(opts.limit));
+            let mut groups = DuplicateGroups::new(|r: &ScoredEntry| &r.entry);
+            for (ctx, breakdown) in scored {
+                // Variants ranked below the last result are left out
+                if opts.dedupe && groups.len() == opts.limit {
+                    break;
+                }
+                if let Some(mut entry) = self.entry_by_seq(ctx.ent_seq).await? {
+                    entry.frequency_rank = ctx.frequency_rank;
+                    opts.filter.retain_senses(&mut entry);
+                    let result = ScoredEntry {
+                        entry,
+                        match_kind: breakdown.match_kind,
+                        score: breakdown.total,
+                        length_folded: breakdown.match_kind == MatchKind::LengthFolded,
+                        explain: opts.explain.then_some(breakdown),
+                        variants: Vec::new(),
+                    };
+                    if opts.dedupe {
+                        groups.push(result);
+                    } else {
+                        results.push(result);
+                    }
+                }
+            }
+            if opts.dedupe {
+                results = groups
+                    .into_groups()
+                    .into_iter()
+                    .map(|(primary, variants)| ScoredEntry { variants, ..primary })
+                    .collect();
+            }
+            Ok(results)
+        })
+        .await
+    }
+
+    /// Up to `limit` exact and prefix matches of `query` in kebs and rebs, ranked by `ranker`.
+    ///
+    /// Like `search_ranked` with the default options, but each result's score is
+    /// `ranker.score`; ties are broken as in `search_ranked`, by the nfNN bucket, the other
+    /// priority tags, then ent_seq, so `DefaultRanker` gives the same results. The
+    /// `MatchContext` of every candidate comes from one query, a ranker needs none of its own.
+    pub async fn search_ranked_with<R: Ranker + ?Sized>(&self, query: &str, ranker: &R, limit: usize) -> Result<Vec<ScoredEntry>> {
+        self.observe("search_ranked_with", query, || format!("limit {}", limit), async {
+            let query = non_empty(query)?;
+
+            let mut scored: Vec<(MatchContext, f64)> = self
+                .rank_candidates(query, &RankOptions::default())
+                .await?
+                .into_iter()
+                .map(|ctx| {
+                    let score = ranker.score(&ctx);
+                    (ctx, score)
+                })
+                .collect();
+            let bucket_key = |ctx: &MatchContext| ctx.nf_bucket.unwrap_or(i64::MAX);
+            scored.sort_by(|(a, a_score), (b, b_score)| {
+                b_score
+                    .total_cmp(a_score)
+                    .then(bucket_key(a).cmp(&bucket_key(b)))
+                    .then(priority_score(&b.priority).cmp(&priority_score(&a.priority)))
+                    .then(a.ent_seq.cmp(&b.ent_seq))
+            });
+            scored.truncate(limit);
+
+            let mut results = Vec::with_capacity(scored.len());
+            for (ctx, score) in scored {
+                if let Some(mut entry) = self.entry_by_seq(ctx.ent_seq).await? {
+                    entry.frequency_rank = ctx.frequency_rank;
+                    let length_folded = ctx.match_kind == MatchKind::LengthFolded;
+                    results.push(ScoredEntry { entry, match_kind: ctx.match_kind, score, explain: None, variants: Vec::new(), length_folded });
+                }
+            }
+            Ok(results)
+        })
+        .await
+    }
+
+    /// The candidates of a ranked search with what they are ranked by, in no particular order.
+    /// The form conditions of the filter apply to the matched forms, whose shortest gives
+    /// `headword_len`. With `ignore_punctuation` the stripped forms of punct_index match too,
+    /// with `ignore_vowel_length` the rebs whose folded form in reading_index is the query's.
+    async fn rank_candidates(&self, query: &str, opts: &RankOptions) -> Result<Vec<MatchContext>> {
+        let (frequency_source, filter) = (opts.frequency_source.as_deref(), &opts.filter);
+        let folded_query = opts.ignore_vowel_length.then(|| fold_vowel_length(query));
+        // Only cheap columns here; entries are hydrated after ranking and truncation
+        let mut qb = QueryBuilder::<Sqlite>::new("SELECT f.ent_seq, f.nf_bucket, f.priority, MAX(f.form = ");
+        qb.push_bind(query);
+        qb.push(
+            r#") AS exact,
+                   (SELECT MIN(fr.rank) FROM frequency fr
+                    WHERE fr.ent_seq = f.ent_seq AND ("#,
+        );
+        qb.push_bind(frequency_source);
+        qb.push(" IS NULL OR fr.source = ");
+        qb.push_bind(frequency_source);
+        qb.push(
+            r#")) AS rank,
+                   (SELECT j.level FROM jlpt j WHERE j.ent_seq = f.ent_seq) AS jlpt_level, "#,
+        );
+        #[cfg(feature = "userdata")]
+        qb.push("(SELECT COALESCE(MAX(ls.count), 0) FROM lookup_stats ls WHERE ls.ent_seq = f.ent_seq) AS lookup_count, ");
+        qb.push(
+            r#"json_group_array(f.form) FILTER (WHERE NOT f.folded) AS forms,
+                   json_group_array(f.form) FILTER (WHERE f.folded) AS folded_forms
+            FROM (
+                SELECT e.ent_seq, e.nf_bucket, e.priority, je.value AS form, 0 AS folded FROM entries e, json_each(e.rebs) je
+                UNION ALL
+                SELECT e.ent_seq, e.nf_bucket, e.priority, je.value AS form, 0 AS folded FROM entries e, json_each(e.kebs) je
+                WHERE e.kebs IS NOT NULL"#,
+        );
+        if opts.ignore_punctuation {
+            qb.push(
+                r#"
+                UNION ALL
+                SELECT e.ent_seq, e.nf_bucket, e.priority, p.plain AS form, 0 AS folded FROM punct_index p JOIN entries e ON e.ent_seq = p.ent_seq"#,
+            );
+        }
+        if let Some(folded_query) = &folded_query {
+            // Every reb of the entry, the ones that fold to the query are picked below
+            qb.push(
+                r#"
+                UNION ALL
+                SELECT e.ent_seq, e.nf_bucket, e.priority, je.value AS form, 1 AS folded
+                FROM reading_index r JOIN entries e ON e.ent_seq = r.ent_seq, json_each(e.rebs) je
+                WHERE r.folded = "#,
+            );
+            qb.push_bind(folded_query.clone());
+        }
+        qb.push(
+            r#"
+            ) f
+            WHERE (f.folded OR f.form = "#,
+        );
+        qb.push_bind(query);
+        qb.push(" OR f.form LIKE ");
+        qb.push_bind(format!("{}%", escape_like(query)));
+        qb.push(" ESCAPE '\\')");
+        filter.push_form_conditions(&mut qb, "f.form");
+        filter.push_conditions(&mut qb, "f.ent_seq");
+        qb.push(" GROUP BY f.ent_seq");
+
+        let candidates = qb.build_query_as::<Candidate>().fetch_all(&self.pool).await?;
+        let mut contexts = Vec::with_capacity(candidates.len());
+        for c in candidates {
+            let mut forms: Vec<String> = serde_json::from_str(&c.forms)?;
+            forms.retain(|f| filter.matches_form(f));
+            let mut folded: Vec<String> = serde_json::from_str(&c.folded_forms)?;
+            folded.retain(|f| folded_query.as_deref() == Some(fold_vowel_length(f).as_str()) && filter.matches_form(f));
+            let exact = c.exact && forms.iter().any(|f| f == query);
+            let (match_kind, matched) = match (exact, folded.is_empty()) {
+                (false, false) => (MatchKind::LengthFolded, &folded),
+                (true, _) => (MatchKind::Exact, &forms),
+                (false, true) => (MatchKind::Prefix, &forms),
+            };
+            let Some(headword_len) = matched.iter().map(|f| f.chars().count()).min() else {
+                continue;
+            };
+            contexts.push(MatchContext {
+                ent_seq: c.ent_seq,
+                match_kind,
+                priority: serde_json::from_str(&c.priority)?,
+                frequency_rank: c.rank,
+                nf_bucket: c.nf_bucket,
+                jlpt_level: c.jlpt_level,
+                headword_len,
+                #[cfg(feature = "userdata")]
+                lookup_count: c.lookup_count,
+            });
+        }
+        Ok(contexts)
+    }
+}
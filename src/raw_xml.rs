@@ -0,0 +1,78 @@
+//! The `<entry>` elements of the XML as a build read them, see `BuildOptions::store_raw_xml`.
+
+use crate::{DictDb, Result};
+
+/// The zstd level of the stored elements, zstd's default.
+#[cfg(feature = "raw-xml")]
+const RAW_XML_LEVEL: i32 = 3;
+
+impl DictDb {
+    /// Returns true if the last build stored the XML of its entries, so that `raw_xml` has
+    /// something to return. False for files built without `BuildOptions::store_raw_xml`.
+    pub async fn has_raw_xml(&self) -> Result<bool> {
+        let value: Option<String> = sqlx::query_scalar("SELECT value FROM meta WHERE key = 'raw_xml'")
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(value.as_deref() == Some("1"))
+    }
+
+    /// Records in the meta table whether the XML of the entries is stored, dropping the
+    /// elements of an earlier build if it isn't.
+    pub(crate) async fn set_raw_xml_available(&self, available: bool) -> Result<()> {
+        self.with_retry(|| async {
+            let mut tx = self.pool.begin().await?;
+            if !available {
+                sqlx::query("DELETE FROM raw_xml").execute(&mut *tx).await?;
+            }
+            sqlx::query("INSERT OR REPLACE INTO meta (key, value) VALUES ('raw_xml', ?1)")
+                .bind(if available { "1" } else { "0" })
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Stores the `<entry>` elements by ent_seq, compressing each on its own.
+    #[cfg(feature = "raw-xml")]
+    pub(crate) async fn store_raw_xml(&self, elements: &[(i64, &str)]) -> Result<()> {
+        let compressed = elements
+            .iter()
+            .map(|&(ent_seq, xml)| Ok((ent_seq, zstd::bulk::compress(xml.as_bytes(), RAW_XML_LEVEL)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let compressed = &compressed;
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("DELETE FROM raw_xml").execute(&mut *tx).await?;
+            for (ent_seq, xml) in compressed {
+                sqlx::query("INSERT OR REPLACE INTO raw_xml (ent_seq, xml) VALUES (?1, ?2)")
+                    .bind(ent_seq)
+                    .bind(xml)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// The `<entry>` element of an entry exactly as the XML of the last build had it,
+    /// entity references such as `&v1;` included. None if the entry isn't in that XML or
+    /// the build didn't store it, see `has_raw_xml`.
+    ///
+    /// # Errors
+    /// The I/O error of zstd if the stored element doesn't decompress.
+    #[cfg(feature = "raw-xml")]
+    pub async fn raw_xml(&self, ent_seq: i64) -> Result<Option<String>> {
+        let xml: Option<Vec<u8>> = sqlx::query_scalar("SELECT xml FROM raw_xml WHERE ent_seq = ?1")
+            .bind(ent_seq)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(xml) = xml else {
+            return Ok(None);
+        };
+        Ok(Some(String::from_utf8(zstd::decode_all(xml.as_slice())?)?))
+    }
+}
@@ -0,0 +1,75 @@
+use sqlx::{Sqlite, Transaction};
+
+use crate::dict_db::mark_applicable;
+use crate::guards::non_empty;
+use crate::kana::{fold_vowel_length, normalize_kana, READING_NORMALIZATION};
+use crate::{DictDb, EntryParsed, EntryRow, Result};
+
+impl DictDb {
+    /// Replaces the reading_index rows of an entry with its normalized and length-folded rebs.
+    pub(crate) async fn index_readings_tx(tx: &mut Transaction<'_, Sqlite>, ent_seq: i64, rebs: &[&str]) -> Result<()> {
+        sqlx::query("DELETE FROM reading_index WHERE ent_seq = ?1")
+            .bind(ent_seq)
+            .execute(&mut **tx)
+            .await?;
+        for reb in rebs {
+            sqlx::query("INSERT OR IGNORE INTO reading_index (norm, ent_seq, folded) VALUES (?1, ?2, ?3)")
+                .bind(normalize_kana(reb, READING_NORMALIZATION))
+                .bind(ent_seq)
+                .bind(fold_vowel_length(reb))
+                .execute(&mut **tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the reading_index table from the rebs of all entries.
+    ///
+    /// `upsert_entry` keeps the index up to date, this is for databases built before it existed
+    /// or had the folded readings.
+    pub(crate) async fn rebuild_reading_index(&self) -> Result<()> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let rows: Vec<(i64, String)> = sqlx::query_as("SELECT ent_seq, rebs FROM entries")
+                .fetch_all(&mut *tx)
+                .await?;
+            for (ent_seq, rebs) in rows {
+                let rebs: Vec<String> = serde_json::from_str(&rebs)?;
+                let rebs: Vec<&str> = rebs.iter().map(String::as_str).collect();
+                Self::index_readings_tx(&mut tx, ent_seq, &rebs).await?;
+            }
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Searches for entries by reading like `search_entries_with_senses_by_reading`, but
+    /// comparing both sides after `normalize_kana` with `READING_NORMALIZATION`, so katakana,
+    /// long vowels and small kana don't have to be typed as in the dictionary: らーめん and
+    /// ラ-メン find ラーメン, ウオ finds ウォ.
+    ///
+    /// Senses are marked applicable against the reb that matched.
+    pub async fn search_entries_by_reading_normalized(&self, reading: &str) -> Result<Vec<EntryParsed>> {
+        self.observe("search_entries_by_reading_normalized", reading, String::new, async {
+            let norm = normalize_kana(non_empty(reading)?, READING_NORMALIZATION);
+            let rows = sqlx::query_as::<_, EntryRow>(
+                r#"
+                SELECT e.* FROM reading_index r JOIN entries e ON e.ent_seq = r.ent_seq
+                WHERE r.norm = ?1
+                ORDER BY e.nf_bucket IS NULL, e.nf_bucket, e.ent_seq
+                "#,
+            )
+            .bind(&norm)
+            .fetch_all(&self.pool)
+            .await?;
+            let mut entries = self.hydrate_entries(rows).await?;
+            for entry in &mut entries {
+                let reb = entry.rebs.iter().find(|reb| normalize_kana(reb, READING_NORMALIZATION) == norm).cloned();
+                mark_applicable(std::slice::from_mut(entry), None, reb.as_deref());
+            }
+            Ok(entries)
+        })
+        .await
+    }
+}
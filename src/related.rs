@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::{HashMap, HashSet};
+
+use crate::kana::{normalize_kana, READING_NORMALIZATION};
+use crate::ranking::priority_score;
+use crate::{extract_kanji, DictDb, EntryParsed, Result};
+
+/// What a reading-prefix match adds to the score, less than all but the most common kanji.
+const READING_PREFIX_WEIGHT: f64 = 1.0;
+
+/// An entry related to another one, see `DictDb::related_entries`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelatedEntry {
+    pub entry: EntryParsed,
+    /// The kanji of the entry's kebs also in the kebs of the source entry, in the source's
+    /// order. Empty for entries related by reading only.
+    pub shared_chars: Vec<char>,
+    /// The relatedness, higher is closer.
+    pub score: f64,
+}
+
+/// An entry sharing a kanji or a reading prefix with the source entry, before ranking.
+#[derive(FromRow)]
+struct RelatedCandidate {
+    ent_seq: i64,
+    /// The shared kanji, None for a reading-prefix match.
+    ch: Option<String>,
+    priority: String,
+    nf_bucket: Option<i64>,
+}
+
+/// The candidates of one entry added up.
+#[derive(Default)]
+struct Relatedness {
+    score: f64,
+    shared: Vec<char>,
+    priority_score: i64,
+    nf_bucket: Option<i64>,
+}
+
+/// The reading-prefix stem of a kana-only word: the reading without its last kana, which
+/// inflects for verbs and adjectives, when that leaves at least two, else the whole reading.
+/// None for a single kana, which too many readings start with.
+fn kana_stem(reb: &str) -> Option<String> {
+    let chars: Vec<char> = reb.chars().collect();
+    match chars.len() {
+        0 | 1 => None,
+        2 => Some(reb.to_string()),
+        n => Some(chars[..n - 1].iter().collect()),
+    }
+}
+
+impl DictDb {
+    /// Up to `limit` entries related to the entry `ent_seq`, e.g. 食べ物 and 食事 for 食べる,
+    /// for a "related words" panel. Empty if there is no such entry.
+    ///
+    /// Entries are related by the kanji of their kebs: each kanji shared with the source
+    /// scores `ln(1 + kanji entries / entries using it)`, so a rare kanji counts for more
+    /// than 人 or 日. For a kana-only source, entries with a reading starting with its stem
+    /// (see `kana_stem`) score `READING_PREFIX_WEIGHT` too; readings are compared normalized,
+    /// as in `search_entries_by_reading_normalized`. The source itself is left out. Ranked by
+    /// score, then `priority_score`, then the nfNN bucket, then ent_seq.
+    pub async fn related_entries(&self, ent_seq: i64, limit: usize) -> Result<Vec<RelatedEntry>> {
+        let query = ent_seq.to_string();
+        self.observe("related_entries", &query, || format!("limit {}", limit), async {
+            let Some(source) = self.entry_by_seq(ent_seq).await? else {
+                return Ok(Vec::new());
+            };
+            if limit == 0 {
+                return Ok(Vec::new());
+            }
+            let mut chars = extract_kanji(&source.kebs.clone().unwrap_or_default().concat());
+            let mut seen = HashSet::new();
+            chars.retain(|c| seen.insert(*c));
+            let chars_json = serde_json::to_string(&chars.iter().map(char::to_string).collect::<Vec<_>>())?;
+
+            let mut candidates = sqlx::query_as::<_, RelatedCandidate>(
+                r#"
+                SELECT k.ent_seq, k.ch, e.priority, e.nf_bucket
+                FROM kanji_index k JOIN entries e ON e.ent_seq = k.ent_seq
+                WHERE k.ch IN (SELECT value FROM json_each(?1)) AND k.ent_seq <> ?2
+                "#,
+            )
+            .bind(&chars_json)
+            .bind(ent_seq)
+            .fetch_all(&self.pool)
+            .await?;
+            let stem = source.kebs.is_none().then(|| source.rebs.first().and_then(|r| kana_stem(r))).flatten();
+            if let Some(stem) = stem {
+                let norm = normalize_kana(&stem, READING_NORMALIZATION);
+                candidates.extend(
+                    sqlx::query_as::<_, RelatedCandidate>(
+                        r#"
+                        SELECT DISTINCT e.ent_seq, NULL AS ch, e.priority, e.nf_bucket
+                        FROM reading_index r JOIN entries e ON e.ent_seq = r.ent_seq
+                        WHERE r.norm >= ?1 AND r.norm < ?1 || char(1114111) AND r.ent_seq <> ?2
+                        "#,
+                    )
+                    .bind(&norm)
+                    .bind(ent_seq)
+                    .fetch_all(&self.pool)
+                    .await?,
+                );
+            }
+
+            // How many entries use each kanji, for its weight
+            let total: i64 = sqlx::query_scalar("SELECT COUNT(DISTINCT ent_seq) FROM kanji_index").fetch_one(&self.pool).await?;
+            let counts: HashMap<String, i64> = sqlx::query_as(
+                "SELECT ch, COUNT(*) FROM kanji_index WHERE ch IN (SELECT value FROM json_each(?1)) GROUP BY ch",
+            )
+            .bind(&chars_json)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .collect();
+            let weight = |ch: &str| (1.0 + total as f64 / counts.get(ch).copied().unwrap_or(1).max(1) as f64).ln();
+
+            let mut related: HashMap<i64, Relatedness> = HashMap::new();
+            for c in candidates {
+                let slot = related.entry(c.ent_seq).or_default();
+                slot.priority_score = priority_score(&serde_json::from_str::<Vec<String>>(&c.priority)?);
+                slot.nf_bucket = c.nf_bucket;
+                match c.ch {
+                    Some(ch) => {
+                        slot.score += weight(&ch);
+                        slot.shared.extend(ch.chars());
+                    }
+                    None => slot.score += READING_PREFIX_WEIGHT,
+                }
+            }
+            let mut ranked: Vec<(i64, Relatedness)> = related.into_iter().collect();
+            ranked.sort_by(|(a_seq, a), (b_seq, b)| {
+                b.score
+                    .total_cmp(&a.score)
+                    .then(b.priority_score.cmp(&a.priority_score))
+                    .then(a.nf_bucket.unwrap_or(i64::MAX).cmp(&b.nf_bucket.unwrap_or(i64::MAX)))
+                    .then(a_seq.cmp(b_seq))
+            });
+            ranked.truncate(limit);
+
+            let mut entries = Vec::with_capacity(ranked.len());
+            for (seq, r) in ranked {
+                if let Some(entry) = self.entry_by_seq(seq).await? {
+                    let shared_chars = chars.iter().copied().filter(|c| r.shared.contains(c)).collect();
+                    entries.push(RelatedEntry { entry, shared_chars, score: r.score });
+                }
+            }
+            Ok(entries)
+        })
+        .await
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use crate::{DictDb, DictDbOptions, DictError, Result};
+
+/// The tables a database must have to be swapped in, as for `DictDb::attach`.
+const REQUIRED_TABLES: [&str; 5] = ["entries", "senses", "glosses", "frequency", "jlpt"];
+
+/// The database a `ReloadableDictDb` is serving.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DbInfo {
+    pub path: PathBuf,
+    /// 1 for the database opened first, one more on every swap.
+    pub generation: u64,
+    /// `DictDb::entry_count` when the database was opened.
+    pub entries: i64,
+    /// `DictDb::build_id`, which changes when the file is rebuilt.
+    pub build_id: String,
+}
+
+struct Active {
+    db: Arc<DictDb>,
+    info: DbInfo,
+}
+
+/// A DictDb that can be switched to another database file while it is used, e.g. by a
+/// server rebuilding the dictionary in the background.
+///
+/// Queries run on the DictDb of `current`, which stays usable until the caller drops it:
+/// searches started before a swap finish on the old database, later ones go to the new
+/// one. The old database is closed once the last query holding it is done.
+pub struct ReloadableDictDb {
+    /// The settings every database is opened with, the URL aside.
+    opts: DictDbOptions,
+    active: RwLock<Arc<Active>>,
+}
+
+impl ReloadableDictDb {
+    /// Opens the database at `path` with the settings of `opts`, whose URL is ignored.
+    ///
+    /// # Errors
+    /// As `swap_to`.
+    pub async fn open(path: impl AsRef<Path>, opts: DictDbOptions) -> Result<Self> {
+        let active = Self::open_active(&opts, path.as_ref(), 1).await?;
+        Ok(Self { opts, active: RwLock::new(Arc::new(active)) })
+    }
+
+    /// The database being served. Hold on to it for the length of a query or a
+    /// request, not longer: the database it belongs to stays open until it is dropped.
+    pub fn current(&self) -> Arc<DictDb> {
+        self.active.read().unwrap_or_else(|poisoned| poisoned.into_inner()).db.clone()
+    }
+
+    /// Which database `current` is.
+    pub fn db_info(&self) -> DbInfo {
+        self.active.read().unwrap_or_else(|poisoned| poisoned.into_inner()).info.clone()
+    }
+
+    /// Switches to the database at `path`, e.g. a freshly built copy next to the one being
+    /// served, and returns its info. The new database is opened and checked before
+    /// anything changes, then replaces the old one at once: a query sees one or the other,
+    /// never a mix. On error the old database keeps serving.
+    ///
+    /// # Errors
+    /// `DictError::DatabaseNotBuilt` if there is no file at `path`, an error if it is not a
+    /// jmdictdb database, `DictError::DatabaseEmpty` if it has no entries.
+    pub async fn swap_to(&self, path: impl AsRef<Path>) -> Result<DbInfo> {
+        let mut active = Self::open_active(&self.opts, path.as_ref(), 0).await?;
+        let mut slot = self.active.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Numbered under the lock so that concurrent swaps still count up
+        active.info.generation = slot.info.generation + 1;
+        let info = active.info.clone();
+        *slot = Arc::new(active);
+        Ok(info)
+    }
+
+    /// Reopens the database if the file was rebuilt since it was opened, as
+    /// `DictDb::check_generation` tells, e.g. from a timer or after a search failed with
+    /// `DictError::DatabaseReplaced`.
+    ///
+    /// # Returns
+    /// The new info if the database was reopened, None if it is still the same file.
+    ///
+    /// # Errors
+    /// As `swap_to`, the old database staying in place.
+    pub async fn reload_if_replaced(&self) -> Result<Option<DbInfo>> {
+        let err = match self.current().check_generation().await {
+            Ok(()) => return Ok(None),
+            Err(err) => err,
+        };
+        if !matches!(err.downcast_ref::<DictError>(), Some(DictError::DatabaseReplaced(_))) {
+            return Err(err);
+        }
+        let path = self.db_info().path;
+        self.swap_to(path).await.map(Some)
+    }
+
+    /// Checks the database at `path` without changing it, then connects to it.
+    async fn open_active(opts: &DictDbOptions, path: &Path, generation: u64) -> Result<Active> {
+        if !path.exists() {
+            return Err(DictError::DatabaseNotBuilt(path.to_path_buf()).into());
+        }
+        let url = format!("sqlite:{}", path.display());
+        // Read-only first: connecting a DictDb would add the schema to any SQLite file
+        let mut conn = SqliteConnectOptions::from_str(&url)?.read_only(true).connect().await?;
+        let tables: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN (SELECT value FROM json_each(?1))",
+        )
+        .bind(serde_json::to_string(&REQUIRED_TABLES)?)
+        .fetch_one(&mut conn)
+        .await?;
+        if tables < REQUIRED_TABLES.len() as i64 {
+            conn.close().await?;
+            return Err(format!("{} is not a jmdictdb database", path.display()).into());
+        }
+        let entries: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM entries").fetch_one(&mut conn).await?;
+        conn.close().await?;
+        if entries == 0 {
+            return Err(DictError::DatabaseEmpty(path.to_path_buf()).into());
+        }
+
+        let mut opts = opts.clone();
+        opts.db_url = url;
+        let db = DictDb::connect_with(opts).await?;
+        let build_id = db.build_id().to_string();
+        Ok(Active { db: Arc::new(db), info: DbInfo { path: path.to_path_buf(), generation, entries, build_id } })
+    }
+}
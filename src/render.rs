@@ -0,0 +1,191 @@
+//! Rendering entries for a terminal, see `terminal`.
+//!
+//! Shared by the binaries: `jmdict search --pretty` prints its results with it.
+
+use std::io::IsTerminal;
+
+use crate::entities::standard_name;
+use crate::furigana::FuriganaSegment;
+use crate::highlight::COMMON_TAGS;
+use crate::{EntryParsed, PartOfSpeech, SenseParsed};
+
+const POS_COLOR: &str = "\x1b[36m";
+const TAG_COLOR: &str = "\x1b[33m";
+const NUMBER_STYLE: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+// Each style is ended on its own, so a colored tag doesn't end the dimming of its entry
+const DEFAULT_COLOR: &str = "\x1b[39m";
+const NORMAL_INTENSITY: &str = "\x1b[22m";
+
+/// How `terminal` renders an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalOptions {
+    /// Color the part-of-speech and other tags and dim entries that are not common, with
+    /// ANSI escape codes.
+    pub color: bool,
+    /// The terminal shows kana and kanji two columns wide, so furigana can go on a line of its
+    /// own above the headword. Without it they follow each kanji in brackets, 食[た]べる.
+    pub wide_chars: bool,
+}
+
+impl Default for TerminalOptions {
+    /// No color, furigana above the headword.
+    fn default() -> Self {
+        Self { color: false, wide_chars: true }
+    }
+}
+
+impl TerminalOptions {
+    /// The options for printing to stdout: color if it is a terminal and NO_COLOR is not set,
+    /// furigana above the headword unless the locale is not UTF-8 or the terminal is the
+    /// Linux console, which has no CJK font.
+    pub fn detect() -> Self {
+        let term = std::env::var("TERM").unwrap_or_default();
+        let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() && term != "dumb";
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let utf8 = locale.contains("utf-8") || locale.contains("utf8");
+        Self { color, wide_chars: utf8 && term != "linux" && term != "dumb" }
+    }
+}
+
+/// The columns a terminal takes to show `s`: two for kana, kanji and other East Asian wide
+/// and fullwidth characters, one for the rest.
+pub fn display_width(s: &str) -> usize {
+    s.chars().map(|c| if is_wide(c) { 2 } else { 1 }).sum()
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{303E}'
+        | '\u{3041}'..='\u{33FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{A000}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}'
+        | '\u{20000}'..='\u{3FFFD}')
+}
+
+/// Renders an entry for a terminal: the headword with furigana from `align_furigana`, then
+/// the senses numbered from 1 with their tags, e.g.
+///
+/// ```text
+///  た
+/// 食べる
+///  1. [v1 vt] to eat
+///  2. [v1 vt] to live on (e.g. a salary); to live off; to subsist on
+/// ```
+///
+/// Each line ends in a newline. Use `terminal_with_furigana` for the precomputed furigana of
+/// `DictDb::furigana_for`.
+pub fn terminal(entry: &EntryParsed, opts: &TerminalOptions) -> String {
+    let furigana = if shows_keb(entry) { entry.furigana() } else { None };
+    terminal_with_furigana(entry, furigana.as_deref(), opts)
+}
+
+/// `terminal` with the given furigana for the display form, e.g. from
+/// `DictDb::furigana_for`; None shows the reading after the headword instead,
+/// 食べる【たべる】. Ignored for kana display forms.
+pub fn terminal_with_furigana(entry: &EntryParsed, furigana: Option<&[FuriganaSegment]>, opts: &TerminalOptions) -> String {
+    let form = entry.display_form();
+    let furigana = furigana.filter(|segments| shows_keb(entry) && segments.iter().any(|s| s.ruby.is_some()));
+    let mut lines = Vec::new();
+    let mut shown = vec![form];
+    match furigana {
+        Some(segments) if opts.wide_chars => {
+            lines.push(ruby_line(segments));
+            lines.push(form.to_string());
+            shown.extend(entry.reading_for(form));
+        }
+        Some(segments) => {
+            lines.push(segments.iter().map(|s| s.ruby.as_ref().map_or_else(|| s.text.clone(), |r| format!("{}[{}]", s.text, r))).collect());
+            shown.extend(entry.reading_for(form));
+        }
+        None if shows_keb(entry) => {
+            let reading = entry.reading_for(form).unwrap_or_default();
+            lines.push(format!("{}【{}】", form, reading));
+            shown.push(reading);
+        }
+        None => lines.push(form.to_string()),
+    }
+
+    let others: Vec<&str> = entry.kebs.iter().flatten().chain(&entry.rebs).map(String::as_str).filter(|f| !shown.contains(f)).collect();
+    if !others.is_empty() {
+        let headword = lines.pop().unwrap_or_default();
+        lines.push(format!("{}  also {}", headword, others.join("、")));
+    }
+
+    let number_width = entry.senses.len().to_string().len();
+    for (n, sense) in entry.senses.iter().enumerate() {
+        let number = format!("{:>width$}.", n + 1, width = number_width);
+        let number = if opts.color { format!("{}{}{}", NUMBER_STYLE, number, NORMAL_INTENSITY) } else { number };
+        let tags = sense_tags(sense, opts);
+        let glosses = sense.summary(usize::MAX);
+        lines.push(match tags.is_empty() {
+            true => format!(" {} {}", number, glosses),
+            false => format!(" {} [{}] {}", number, tags, glosses),
+        });
+    }
+
+    let dim = opts.color && !is_common(entry);
+    let mut out = String::new();
+    for line in lines {
+        if dim {
+            // After the bold sense number, dimming has to be set again
+            out.push_str(DIM);
+            out.push_str(&line.replace(NORMAL_INTENSITY, &format!("{}{}", NORMAL_INTENSITY, DIM)));
+            out.push_str(NORMAL_INTENSITY);
+        } else {
+            out.push_str(&line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Returns true if the display form is a keb, which gets furigana.
+fn shows_keb(entry: &EntryParsed) -> bool {
+    let form = entry.display_form();
+    entry.kebs.iter().flatten().any(|k| k == form)
+}
+
+/// Returns true if the entry has a priority tag JMdict marks common words with.
+fn is_common(entry: &EntryParsed) -> bool {
+    entry.priority.iter().any(|tag| COMMON_TAGS.contains(&tag.as_str()))
+}
+
+/// The readings of the segments on one line, each centered over its text where it fits and
+/// pushed right where the one before it is too long.
+fn ruby_line(segments: &[FuriganaSegment]) -> String {
+    let mut line = String::new();
+    let (mut column, mut end) = (0, 0);
+    for segment in segments {
+        let width = display_width(&segment.text);
+        if let Some(ruby) = &segment.ruby {
+            let ruby_width = display_width(ruby);
+            let start = (column + width.saturating_sub(ruby_width) / 2).max(end);
+            line.push_str(&" ".repeat(start - end));
+            line.push_str(ruby);
+            end = start + ruby_width;
+        }
+        column += width;
+    }
+    line
+}
+
+/// The part of speech, misc, field and dialect tags of a sense as their JMdict codes, e.g.
+/// "v1 vt uk", the part of speech in color.
+fn sense_tags(sense: &SenseParsed, opts: &TerminalOptions) -> String {
+    let paint = |color: &str, tag: &str| if opts.color { format!("{}{}{}", color, tag, DEFAULT_COLOR) } else { tag.to_string() };
+    let pos = sense.pos.iter().map(|p| paint(POS_COLOR, PartOfSpeech::parse(p).code()));
+    let other = sense.misc.iter().chain(&sense.field).chain(&sense.dial).map(|t| paint(TAG_COLOR, standard_name(t).unwrap_or(t)));
+    pos.chain(other).collect::<Vec<_>>().join(" ")
+}
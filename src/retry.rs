@@ -0,0 +1,102 @@
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{DictDb, DictError, Result};
+
+// Primary SQLite result codes, extended codes keep them in the low byte
+const SQLITE_BUSY: i64 = 5;
+const SQLITE_LOCKED: i64 = 6;
+
+/// How write operations are retried when SQLite reports the database as busy or locked.
+///
+/// Set it with `DictDbOptions::retry`. Only busy/locked errors are retried, with exponential
+/// backoff: the n-th retry waits about `base_delay * 2^(n-1)`, capped at `max_delay`, and with
+/// jitter a random duration between half and all of that. Reads are never retried, and neither
+/// are transactions started with `DictDb::begin`, whose caller decides what to redo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first one, 1 disables retrying.
+    pub max_attempts: u32,
+    /// The wait before the first retry.
+    pub base_delay: Duration,
+    /// The longest wait between two attempts.
+    pub max_delay: Duration,
+    /// Randomizes the waits so concurrent writers don't retry in lockstep.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    /// 5 attempts, waiting 20 ms up to 1 s, with jitter.
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(20),
+            max_delay: Duration::from_secs(1),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// The wait after the `attempt`-th failed attempt (1-based, 0 counts as 1).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = self.base_delay.saturating_mul(1u32 << attempt.saturating_sub(1).min(16));
+        let delay = exp.min(self.max_delay);
+        if self.jitter {
+            delay.mul_f64(0.5 + 0.5 * random_fraction())
+        } else {
+            delay
+        }
+    }
+}
+
+/// A pseudo-random number in [0, 1), good enough to spread out retries.
+fn random_fraction() -> f64 {
+    let mut x = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0) | 1;
+    // xorshift64
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Returns true for SQLITE_BUSY and SQLITE_LOCKED, including their extended codes.
+pub(crate) fn is_busy(err: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    match err.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::Database(db_err)) => db_err
+            .code()
+            .and_then(|code| code.parse::<i64>().ok())
+            .is_some_and(|code| matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED)),
+        _ => false,
+    }
+}
+
+impl DictDb {
+    /// Runs a write operation, retrying it per the retry policy while the database is busy.
+    ///
+    /// `op` must redo the whole operation, i.e. begin and commit its own transaction.
+    pub(crate) async fn with_retry<T, F, Fut>(&self, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(err) if is_busy(err.as_ref()) => {
+                    if attempt >= self.retry.max_attempts {
+                        return Err(DictError::Busy { attempts: attempt, message: err.to_string() }.into());
+                    }
+                    crate::rt::sleep(self.retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
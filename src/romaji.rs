@@ -0,0 +1,271 @@
+use crate::EntryParsed;
+use crate::kana::katakana_to_hiragana;
+
+/// Two-kana combinations (yōon and katakana extensions), checked before single kana.
+const DIGRAPHS: &[(&str, &str)] = &[
+    ("きゃ", "kya"), ("きゅ", "kyu"), ("きょ", "kyo"),
+    ("ぎゃ", "gya"), ("ぎゅ", "gyu"), ("ぎょ", "gyo"),
+    ("しゃ", "sha"), ("しゅ", "shu"), ("しょ", "sho"), ("しぇ", "she"),
+    ("じゃ", "ja"), ("じゅ", "ju"), ("じょ", "jo"), ("じぇ", "je"),
+    ("ちゃ", "cha"), ("ちゅ", "chu"), ("ちょ", "cho"), ("ちぇ", "che"),
+    ("ぢゃ", "ja"), ("ぢゅ", "ju"), ("ぢょ", "jo"),
+    ("にゃ", "nya"), ("にゅ", "nyu"), ("にょ", "nyo"),
+    ("ひゃ", "hya"), ("ひゅ", "hyu"), ("ひょ", "hyo"),
+    ("びゃ", "bya"), ("びゅ", "byu"), ("びょ", "byo"),
+    ("ぴゃ", "pya"), ("ぴゅ", "pyu"), ("ぴょ", "pyo"),
+    ("みゃ", "mya"), ("みゅ", "myu"), ("みょ", "myo"),
+    ("りゃ", "rya"), ("りゅ", "ryu"), ("りょ", "ryo"),
+    ("ふぁ", "fa"), ("ふぃ", "fi"), ("ふぇ", "fe"), ("ふぉ", "fo"), ("ふゅ", "fyu"),
+    ("ゔぁ", "va"), ("ゔぃ", "vi"), ("ゔぇ", "ve"), ("ゔぉ", "vo"), ("ゔゅ", "vyu"),
+    ("てぃ", "ti"), ("でぃ", "di"), ("とぅ", "tu"), ("どぅ", "du"),
+    ("てゅ", "tyu"), ("でゅ", "dyu"),
+    ("うぃ", "wi"), ("うぇ", "we"), ("うぉ", "wo"),
+    ("つぁ", "tsa"), ("つぃ", "tsi"), ("つぇ", "tse"), ("つぉ", "tso"),
+    ("いぇ", "ye"),
+];
+
+/// Single kana. ん and っ are handled separately.
+const MONOGRAPHS: &[(&str, &str)] = &[
+    ("あ", "a"), ("い", "i"), ("う", "u"), ("え", "e"), ("お", "o"),
+    ("か", "ka"), ("き", "ki"), ("く", "ku"), ("け", "ke"), ("こ", "ko"),
+    ("が", "ga"), ("ぎ", "gi"), ("ぐ", "gu"), ("げ", "ge"), ("ご", "go"),
+    ("さ", "sa"), ("し", "shi"), ("す", "su"), ("せ", "se"), ("そ", "so"),
+    ("ざ", "za"), ("じ", "ji"), ("ず", "zu"), ("ぜ", "ze"), ("ぞ", "zo"),
+    ("た", "ta"), ("ち", "chi"), ("つ", "tsu"), ("て", "te"), ("と", "to"),
+    ("だ", "da"), ("ぢ", "ji"), ("づ", "zu"), ("で", "de"), ("ど", "do"),
+    ("な", "na"), ("に", "ni"), ("ぬ", "nu"), ("ね", "ne"), ("の", "no"),
+    ("は", "ha"), ("ひ", "hi"), ("ふ", "fu"), ("へ", "he"), ("ほ", "ho"),
+    ("ば", "ba"), ("び", "bi"), ("ぶ", "bu"), ("べ", "be"), ("ぼ", "bo"),
+    ("ぱ", "pa"), ("ぴ", "pi"), ("ぷ", "pu"), ("ぺ", "pe"), ("ぽ", "po"),
+    ("ま", "ma"), ("み", "mi"), ("む", "mu"), ("め", "me"), ("も", "mo"),
+    ("や", "ya"), ("ゆ", "yu"), ("よ", "yo"),
+    ("ら", "ra"), ("り", "ri"), ("る", "ru"), ("れ", "re"), ("ろ", "ro"),
+    ("わ", "wa"), ("ゐ", "i"), ("ゑ", "e"), ("を", "o"), ("ゔ", "vu"),
+    ("ぁ", "a"), ("ぃ", "i"), ("ぅ", "u"), ("ぇ", "e"), ("ぉ", "o"),
+    ("ゃ", "ya"), ("ゅ", "yu"), ("ょ", "yo"), ("ゎ", "wa"), ("ゕ", "ka"), ("ゖ", "ke"),
+];
+
+fn macron(v: char) -> char {
+    match v {
+        'a' => 'ā',
+        'i' => 'ī',
+        'u' => 'ū',
+        'e' => 'ē',
+        'o' => 'ō',
+        _ => v,
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+/// Romanizes a kana reading using modified Hepburn, without macrons.
+///
+/// Long vowels are written out (とうきょう → toukyou, スーパー → suupaa).
+/// See `romanize_with` for the macron variant.
+pub fn romanize(reading: &str) -> String {
+    romanize_with(reading, false)
+}
+
+/// Romanizes a kana reading using modified Hepburn.
+///
+/// - しゃ → sha, ち → chi, つ → tsu, ふ → fu, じ/ぢ → ji, を → o
+/// - っ doubles the following consonant (っち → tchi)
+/// - ん → n, written n' before a vowel or y
+/// - ー extends the previous vowel
+///
+/// Characters that are not kana are copied through unchanged.
+///
+/// # Arguments
+/// * `reading` - The hiragana or katakana string to romanize.
+/// * `macrons` - If true, long vowels (ああ, うう, おう, おお, ええ and ー) are written with macrons
+///   (とうきょう → tōkyō). いい is always written ii.
+pub fn romanize_with(reading: &str, macrons: bool) -> String {
+    let hira: Vec<char> = katakana_to_hiragana(reading).chars().collect();
+    let mut out = String::new();
+    let mut sokuon = false;
+    let mut i = 0;
+
+    while i < hira.len() {
+        let c = hira[i];
+
+        if c == 'っ' {
+            sokuon = true;
+            i += 1;
+            continue;
+        }
+
+        if c == 'ー' {
+            match out.chars().last() {
+                Some(v) if is_vowel(v) && macrons => {
+                    out.pop();
+                    out.push(macron(v));
+                }
+                Some(v) if is_vowel(v) => out.push(v),
+                // already written as a long vowel
+                Some(v) if "āīūēō".contains(v) => {}
+                _ => out.push('-'),
+            }
+            sokuon = false;
+            i += 1;
+            continue;
+        }
+
+        if c == 'ん' {
+            out.push('n');
+            let next = hira.get(i + 1).copied();
+            if let Some(next) = next {
+                let next_romaji = syllable_at(&hira, i + 1).map(|(r, _)| r);
+                if next_romaji.is_some_and(|r| r.starts_with(|c: char| is_vowel(c) || c == 'y'))
+                    && next != 'ん'
+                {
+                    out.push('\'');
+                }
+            }
+            sokuon = false;
+            i += 1;
+            continue;
+        }
+
+        match syllable_at(&hira, i) {
+            Some((romaji, len)) => {
+                if sokuon {
+                    if romaji.starts_with("ch") {
+                        out.push('t');
+                    } else if let Some(first) = romaji.chars().next().filter(|c| !is_vowel(*c)) {
+                        out.push(first);
+                    }
+                    sokuon = false;
+                }
+
+                let last = out.chars().last();
+                let long = macrons
+                    && len == 1
+                    && matches!(
+                        (last, romaji),
+                        (Some('a'), "a") | (Some('u'), "u") | (Some('o'), "u") | (Some('o'), "o") | (Some('e'), "e")
+                    );
+                if long {
+                    let v = out.pop().unwrap_or_default();
+                    out.push(macron(v));
+                } else {
+                    out.push_str(romaji);
+                }
+                i += len;
+            }
+            None => {
+                sokuon = false;
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+/// Returns the romaji for the kana starting at `i` and how many kana it consumed.
+fn syllable_at(hira: &[char], i: usize) -> Option<(&'static str, usize)> {
+    if let Some(&next) = hira.get(i + 1) {
+        let pair: String = [hira[i], next].iter().collect();
+        if let Some((_, r)) = DIGRAPHS.iter().find(|(k, _)| *k == pair) {
+            return Some((r, 2));
+        }
+    }
+    let single = hira[i].to_string();
+    MONOGRAPHS
+        .iter()
+        .find(|(k, _)| *k == single)
+        .map(|(_, r)| (*r, 1))
+}
+
+/// Converts Hepburn romaji to hiragana.
+///
+/// This is the inverse of `romanize` where the mapping is unambiguous. Macron vowels become
+/// a doubled kana (ō → おう), doubled consonants become っ, and n' or n before a consonant
+/// become ん. Characters that cannot be converted are copied through unchanged.
+pub fn romaji_to_kana(romaji: &str) -> String {
+    let expanded = romaji
+        .to_lowercase()
+        .replace('ā', "aa")
+        .replace('ī', "ii")
+        .replace('ū', "uu")
+        .replace('ē', "ee")
+        .replace('ō', "ou");
+    let chars: Vec<char> = expanded.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '-' {
+            out.push('ー');
+            i += 1;
+            continue;
+        }
+
+        if c == 'n' {
+            let next = chars.get(i + 1).copied();
+            match next {
+                Some(n) if is_vowel(n) || n == 'y' => {}
+                Some('\'') => {
+                    out.push('ん');
+                    i += 2;
+                    continue;
+                }
+                // n before a consonant (including a second n, as in onna) or at the end
+                _ => {
+                    out.push('ん');
+                    i += 1;
+                    continue;
+                }
+            }
+        }
+
+        // doubled consonant, or t before ch
+        if let Some(&next) = chars.get(i + 1) {
+            let doubled = c == next && c.is_ascii_alphabetic() && !is_vowel(c) && c != 'n';
+            let tch = c == 't' && next == 'c' && chars.get(i + 2) == Some(&'h');
+            if doubled || tch {
+                out.push('っ');
+                i += 1;
+                continue;
+            }
+        }
+
+        let mut matched = false;
+        for len in (1..=3).rev() {
+            if i + len > chars.len() {
+                continue;
+            }
+            let chunk: String = chars[i..i + len].iter().collect();
+            let kana = DIGRAPHS
+                .iter()
+                .chain(MONOGRAPHS.iter())
+                .filter(|(k, _)| !matches!(*k, "ぢ" | "づ" | "ゐ" | "ゑ" | "を" | "ぢゃ" | "ぢゅ" | "ぢょ"))
+                .filter(|(k, _)| !k.starts_with(|c: char| "ぁぃぅぇぉゃゅょゎゕゖ".contains(c)))
+                .find(|(_, r)| *r == chunk);
+            if let Some((k, _)) = kana {
+                out.push_str(k);
+                i += len;
+                matched = true;
+                break;
+            }
+        }
+        if !matched {
+            out.push(c);
+            i += 1;
+        }
+    }
+
+    out
+}
+
+impl EntryParsed {
+    /// Romanizes the primary reading using modified Hepburn, without macrons.
+    pub fn romaji(&self) -> String {
+        self.rebs.first().map(|r| romanize(r)).unwrap_or_default()
+    }
+}
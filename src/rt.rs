@@ -0,0 +1,230 @@
+//! The little the crate needs from an async runtime, built on std threads so it works
+//! under any executor. The database itself runs on the runtime selected by the
+//! `runtime-*` feature, through sqlx.
+
+use futures_util::future::{self, Either};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+#[cfg(not(any(feature = "runtime-tokio", feature = "runtime-async-std")))]
+compile_error!(
+    "jmdictdb needs an async runtime for sqlx: enable `runtime-tokio` (on by default) or `runtime-async-std`"
+);
+
+#[cfg(all(feature = "runtime-tokio", feature = "runtime-async-std"))]
+compile_error!(
+    "jmdictdb runs on one async runtime: enable only one of `runtime-tokio` and `runtime-async-std`, \
+     with default-features = false for async-std"
+);
+
+#[cfg(feature = "bundled-db")]
+pub(crate) use blocking::spawn_blocking;
+
+#[cfg(feature = "bundled-db")]
+mod blocking {
+    use std::future::Future;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+
+    type Outcome<T> = thread::Result<T>;
+
+    /// The result of a blocking task and the waker of the task waiting for it.
+    struct Shared<T> {
+        outcome: Option<Outcome<T>>,
+        waker: Option<Waker>,
+    }
+
+    /// Resolves once the thread started by `spawn_blocking` is done.
+    struct Blocking<T> {
+        shared: Arc<Mutex<Shared<T>>>,
+    }
+
+    impl<T> Future for Blocking<T> {
+        type Output = T;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+            let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+            match shared.outcome.take() {
+                Some(Ok(value)) => Poll::Ready(value),
+                // A panic in the task is a panic of the caller, as with a direct call
+                Some(Err(payload)) => panic::resume_unwind(payload),
+                None => {
+                    shared.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    /// Runs `f` on its own thread, so blocking I/O doesn't hold up the executor.
+    pub(crate) fn spawn_blocking<T, F>(f: F) -> impl Future<Output = T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(Shared { outcome: None, waker: None }));
+        let done = shared.clone();
+        thread::spawn(move || {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(f));
+            let mut done = done.lock().unwrap_or_else(|e| e.into_inner());
+            done.outcome = Some(outcome);
+            if let Some(waker) = done.waker.take() {
+                waker.wake();
+            }
+        });
+        Blocking { shared }
+    }
+}
+
+/// The wakers of the pending `sleep`s by deadline, woken by one timer thread for the
+/// whole process.
+struct Timers {
+    wakers: Mutex<BTreeMap<(Instant, u64), Waker>>,
+    /// Tells the sleeps apart that end at the same instant.
+    next_key: AtomicU64,
+    thread: Thread,
+}
+
+fn timers() -> &'static Timers {
+    static TIMERS: OnceLock<Timers> = OnceLock::new();
+    TIMERS.get_or_init(|| {
+        // The thread waits in `timers()` until the Timers it belongs to are stored
+        let thread = thread::Builder::new()
+            .name("jmdictdb-timer".to_string())
+            .spawn(|| run_timers(timers()))
+            .expect("failed to start the timer thread");
+        Timers { wakers: Mutex::default(), next_key: AtomicU64::new(0), thread: thread.thread().clone() }
+    })
+}
+
+/// Wakes each sleep once its deadline passed, parked until the next one or a new earlier one.
+fn run_timers(timers: &Timers) {
+    loop {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        let next = {
+            let mut wakers = timers.wakers.lock().unwrap_or_else(|e| e.into_inner());
+            while let Some(entry) = wakers.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+                due.push(entry.remove());
+            }
+            wakers.first_key_value().map(|(&(deadline, _), _)| deadline)
+        };
+        due.into_iter().for_each(Waker::wake);
+        match next {
+            Some(deadline) => thread::park_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => thread::park(),
+        }
+    }
+}
+
+/// Resolves at its deadline, see `sleep`.
+struct Sleep {
+    deadline: Instant,
+    /// Its key among the wakers of the timer thread, once it waited.
+    key: Option<u64>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        if Instant::now() >= this.deadline {
+            return Poll::Ready(());
+        }
+        let timers = timers();
+        let key = *this.key.get_or_insert_with(|| timers.next_key.fetch_add(1, Ordering::Relaxed));
+        let mut wakers = timers.wakers.lock().unwrap_or_else(|e| e.into_inner());
+        let earliest = wakers.first_key_value().is_none_or(|(&(deadline, _), _)| this.deadline < deadline);
+        wakers.insert((this.deadline, key), cx.waker().clone());
+        drop(wakers);
+        if earliest {
+            timers.thread.unpark();
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            timers().wakers.lock().unwrap_or_else(|e| e.into_inner()).remove(&(self.deadline, key));
+        }
+    }
+}
+
+/// Waits for `duration` without blocking the executor. All sleeps share one timer thread.
+pub(crate) fn sleep(duration: Duration) -> impl Future<Output = ()> {
+    Sleep { deadline: Instant::now() + duration, key: None }
+}
+
+/// Runs `fut` for at most `duration`, None if it took longer; `fut` is dropped then.
+pub(crate) async fn timeout<F: Future>(duration: Duration, fut: F) -> Option<F::Output> {
+    match future::select(pin!(fut), pin!(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+/// Runs `fut` in the background on the runtime sqlx uses, for work nobody waits for.
+///
+/// Returns false, without running it, when called outside of that runtime.
+#[cfg(all(feature = "userdata", feature = "runtime-tokio"))]
+pub(crate) fn spawn_detached<F>(fut: F) -> bool
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn(fut);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Runs `fut` in the background on async-std's global executor, which is always there.
+#[cfg(all(feature = "userdata", feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub(crate) fn spawn_detached<F>(fut: F) -> bool
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    async_std::task::spawn(fut);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sleeps_end_at_their_deadlines() {
+        let started = Instant::now();
+        let sleeps = (1..=100).rev().map(|ms| sleep(Duration::from_millis(ms)));
+        future::join_all(sleeps).await;
+        let took = started.elapsed();
+        assert!(took >= Duration::from_millis(100), "{:?}", took);
+        assert!(took < Duration::from_secs(2), "{:?}", took);
+    }
+
+    #[tokio::test]
+    async fn timeouts_drop_what_took_too_long() {
+        assert_eq!(timeout(Duration::from_secs(5), async { 1 }).await, Some(1));
+        assert_eq!(timeout(Duration::from_millis(10), sleep(Duration::from_secs(60))).await, None);
+        // The minute-long sleep is no longer waited for
+        let timers = timers();
+        assert!(timers.wakers.lock().unwrap().keys().all(|&(deadline, _)| deadline < Instant::now() + Duration::from_secs(30)));
+    }
+}
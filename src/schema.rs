@@ -0,0 +1,134 @@
+use schemars::generate::SchemaSettings;
+use serde_json::{json, Value};
+
+use crate::{EntryParsed, MatchKind, ScoredEntry, SenseParsed};
+
+/// How the JSON of the search results names its fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// As the Rust fields are named, `ent_seq`, `sense_order`.
+    #[default]
+    Snake,
+    /// `entSeq`, `senseOrder`, e.g. for a TypeScript frontend.
+    Camel,
+}
+
+impl JsonStyle {
+    /// The name of a field in this style.
+    pub fn field_name(self, name: &str) -> String {
+        match self {
+            JsonStyle::Snake => name.to_string(),
+            JsonStyle::Camel => {
+                let mut out = String::with_capacity(name.len());
+                let mut upper = false;
+                for c in name.chars() {
+                    match c {
+                        '_' if !out.is_empty() => upper = true,
+                        _ if upper => {
+                            out.extend(c.to_uppercase());
+                            upper = false;
+                        }
+                        _ => out.push(c),
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    /// Renames every object key of a serialized result. The result types have no maps
+    /// keyed by data, so every key is a field name.
+    fn rename_keys(self, value: Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (self.field_name(&k), self.rename_keys(v))).collect()),
+            Value::Array(items) => Value::Array(items.into_iter().map(|v| self.rename_keys(v)).collect()),
+            other => other,
+        }
+    }
+
+    /// Renames the property names of a schema, in `properties` and `required`, leaving
+    /// the type names under `$defs` and the enum values as they are.
+    fn rename_properties(self, value: Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| {
+                        let v = match (k.as_str(), v) {
+                            ("properties", Value::Object(props)) => Value::Object(
+                                props.into_iter().map(|(name, prop)| (self.field_name(&name), self.rename_properties(prop))).collect(),
+                            ),
+                            ("required", Value::Array(names)) => Value::Array(
+                                names
+                                    .into_iter()
+                                    .map(|name| match name {
+                                        Value::String(name) => Value::String(self.field_name(&name)),
+                                        other => other,
+                                    })
+                                    .collect(),
+                            ),
+                            (_, v) => self.rename_properties(v),
+                        };
+                        (k, v)
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.into_iter().map(|v| self.rename_properties(v)).collect()),
+            other => other,
+        }
+    }
+}
+
+/// Returns a JSON Schema (draft 2020-12) describing the JSON form of the search results.
+///
+/// The types are under `$defs`: `EntryParsed`, `SenseParsed`, `ScoredEntry`, `ScoreBreakdown` and `MatchKind`.
+/// A golden test pins the document, so changing it is a deliberate, visible change.
+pub fn schema() -> Value {
+    schema_with(JsonStyle::Snake)
+}
+
+/// `schema()` for the JSON of `to_json_value_with(style)`, pinned by its own golden test.
+pub fn schema_with(style: JsonStyle) -> Value {
+    let mut generator = SchemaSettings::draft2020_12().into_generator();
+    generator.subschema_for::<EntryParsed>();
+    generator.subschema_for::<SenseParsed>();
+    generator.subschema_for::<ScoredEntry>();
+    generator.subschema_for::<MatchKind>();
+
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "jmdictdb search results",
+        "anyOf": [
+            { "$ref": "#/$defs/EntryParsed" },
+            { "$ref": "#/$defs/ScoredEntry" },
+        ],
+        "$defs": generator.definitions(),
+    });
+    style.rename_properties(schema)
+}
+
+impl EntryParsed {
+    /// Converts the entry to JSON in the layout described by `schema()`.
+    pub fn to_json_value(&self) -> Value {
+        // Plain data, serializing it can't fail
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Converts the entry to JSON with the field names of `style`, as `schema_with(style)`
+    /// describes it.
+    pub fn to_json_value_with(&self, style: JsonStyle) -> Value {
+        style.rename_keys(self.to_json_value())
+    }
+}
+
+impl ScoredEntry {
+    /// Converts the result to JSON in the layout described by `schema()`.
+    pub fn to_json_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Converts the result to JSON with the field names of `style`, see
+    /// `EntryParsed::to_json_value_with`.
+    pub fn to_json_value_with(&self, style: JsonStyle) -> Value {
+        style.rename_keys(self.to_json_value())
+    }
+}
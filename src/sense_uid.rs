@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use crate::content_hash::fnv1a_64;
+use crate::{fold_gloss, DictDb, Entry, EntryParsed, Result, SenseParsed};
+
+/// Bumped whenever the input of `sense_uids` changes, which changes every uid.
+pub const SENSE_UID_VERSION: u32 = 1;
+
+/// A sense found by its uid, see `DictDb::find_sense_by_uid`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocatedSense {
+    pub entry: EntryParsed,
+    pub sense: SenseParsed,
+}
+
+/// What became of a sense uid in a rebuilt database, see `DictDb::reconcile_sense_uid`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SenseUidStatus {
+    /// The sense is still there under the same uid.
+    Unchanged,
+    /// The glosses of the sense were edited; this is the uid of the edited sense.
+    Replaced(String),
+    /// The sense, or its entry, is gone, or the uid is unknown in both databases.
+    Removed,
+}
+
+/// The stable identifiers of the senses of an entry, in sense order: 16 lowercase hex
+/// digits of the FNV-1a hash of the ent_seq and the glosses of the sense, English first,
+/// then the other languages in document order, like `content_hash`.
+///
+/// Unlike `senses.id`, which is assigned anew on every build, and the sense order, which
+/// changes when JMdict inserts or reorders senses, the uid only changes when the sense's
+/// glosses do (one is added, removed or reworded) or the sense moves to another entry.
+/// Part of speech, misc tags, cross-references and restrictions are left out, they are
+/// edited more often than the meaning. For those rare cases `DictDb::reconcile_sense_uid`
+/// finds the edited sense. Senses with the same glosses as an earlier one of the entry are
+/// numbered apart.
+pub fn sense_uids(e: &Entry) -> Vec<String> {
+    let mut seen: Vec<String> = Vec::new();
+    e.sense
+        .iter()
+        .map(|s| {
+            let mut canonical = format!("v {}\nent_seq {}\n", SENSE_UID_VERSION, e.ent_seq);
+            for gloss in &s.gloss {
+                canonical.push_str(&format!("gloss {}\n", gloss.replace('\\', "\\\\").replace('\n', "\\n")));
+            }
+            for g in &s.other_glosses {
+                canonical.push_str(&format!("gloss.{} {}\n", g.lang, g.text.replace('\\', "\\\\").replace('\n', "\\n")));
+            }
+            let duplicates = seen.iter().filter(|c| **c == canonical).count();
+            seen.push(canonical.clone());
+            if duplicates > 0 {
+                canonical.push_str(&format!("duplicate {}\n", duplicates));
+            }
+            format!("{:016x}", fnv1a_64(canonical.as_bytes()))
+        })
+        .collect()
+}
+
+/// The folded glosses of a sense, in all languages.
+fn folded_glosses(sense: &SenseParsed) -> HashSet<String> {
+    sense.gloss.iter().chain(sense.other_glosses.iter().map(|g| &g.text)).map(|g| fold_gloss(g)).collect()
+}
+
+impl DictDb {
+    /// The sense with this `SenseParsed::sense_uid` and its entry, None if there is none.
+    ///
+    /// Senses stored by versions of the crate before uids have none until the entry is
+    /// stored again, e.g. by a rebuild.
+    pub async fn find_sense_by_uid(&self, uid: &str) -> Result<Option<LocatedSense>> {
+        let found: Option<(i64, i64)> = sqlx::query_as("SELECT ent_seq, sense_order FROM senses WHERE sense_uid = ?1")
+            .bind(uid)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some((ent_seq, sense_order)) = found else {
+            return Ok(None);
+        };
+        let Some(entry) = self.entry_by_seq(ent_seq).await? else {
+            return Ok(None);
+        };
+        let sense = entry.senses.iter().find(|s| s.sense_order == sense_order).cloned();
+        Ok(sense.map(|sense| LocatedSense { entry, sense }))
+    }
+
+    /// What became of a sense uid of `previous`, e.g. the database before a rebuild, in
+    /// this one, for data keyed on sense uids such as word list items.
+    ///
+    /// A uid gone from this database belongs to a sense whose glosses were edited, if the
+    /// entry still has a sense that is not in `previous` sharing a gloss with it: the one
+    /// sharing the most, the closest in sense order on a tie.
+    pub async fn reconcile_sense_uid(&self, previous: &DictDb, uid: &str) -> Result<SenseUidStatus> {
+        if self.find_sense_by_uid(uid).await?.is_some() {
+            return Ok(SenseUidStatus::Unchanged);
+        }
+        let Some(old) = previous.find_sense_by_uid(uid).await? else {
+            return Ok(SenseUidStatus::Removed);
+        };
+        let Some(entry) = self.entry_by_seq(old.entry.ent_seq).await? else {
+            return Ok(SenseUidStatus::Removed);
+        };
+        let old_uids: HashSet<&String> = old.entry.senses.iter().filter_map(|s| s.sense_uid.as_ref()).collect();
+        let old_glosses = folded_glosses(&old.sense);
+        let best = entry
+            .senses
+            .iter()
+            .filter(|s| s.sense_uid.as_ref().is_some_and(|u| !old_uids.contains(u)))
+            .map(|s| (folded_glosses(s).intersection(&old_glosses).count(), s))
+            .filter(|(shared, _)| *shared > 0)
+            .max_by_key(|(shared, s)| (*shared, std::cmp::Reverse((s.sense_order - old.sense.sense_order).abs())));
+        Ok(match best.and_then(|(_, s)| s.sense_uid.clone()) {
+            Some(uid) => SenseUidStatus::Replaced(uid),
+            None => SenseUidStatus::Removed,
+        })
+    }
+}
@@ -0,0 +1,166 @@
+//! Snapshots of search results, with the `test-utils` feature.
+//!
+//! `tests/snapshots.rs` runs a curated list of queries against the full dictionary and
+//! compares the top results with the stored ones, so that ranking and normalization changes
+//! that reorder what users see show up in review. The files are plain text, one query per
+//! line: `kind<TAB>query` for the queries, followed by `ent_seq headword` columns for the
+//! results.
+
+use std::fmt::Write;
+
+use crate::{DictDb, EntryParsed, RankOptions, Result};
+
+/// The search a snapshot query goes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SnapshotKind {
+    /// `search_ranked_outcome` with the default options, so romaji and kana variants too.
+    Ranked,
+    /// `search_entries_by_gloss` in English.
+    Gloss,
+    /// `search_expressions_containing`.
+    Expressions,
+}
+
+impl SnapshotKind {
+    /// The name in the files.
+    pub fn name(self) -> &'static str {
+        match self {
+            SnapshotKind::Ranked => "ranked",
+            SnapshotKind::Gloss => "gloss",
+            SnapshotKind::Expressions => "expressions",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [SnapshotKind::Ranked, SnapshotKind::Gloss, SnapshotKind::Expressions]
+            .into_iter()
+            .find(|k| k.name() == name)
+    }
+}
+
+/// A query of the snapshot suite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotQuery {
+    pub kind: SnapshotKind,
+    pub query: String,
+}
+
+/// The top results of a query, as `(ent_seq, display_form)`. Only the ent_seqs are compared,
+/// the headwords are there for the reader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub query: SnapshotQuery,
+    pub results: Vec<(i64, String)>,
+}
+
+/// Parses a query list: `kind<TAB>query` lines, skipping empty lines and `#` comments.
+pub fn parse_queries(text: &str) -> Result<Vec<SnapshotQuery>> {
+    let mut queries = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (kind, query) = parse_query(line).ok_or_else(|| format!("line {}: expected kind<TAB>query", line_no + 1))?;
+        queries.push(SnapshotQuery { kind, query: query.to_string() });
+    }
+    Ok(queries)
+}
+
+fn parse_query(line: &str) -> Option<(SnapshotKind, &str)> {
+    let mut columns = line.split('\t');
+    let kind = SnapshotKind::from_name(columns.next()?)?;
+    Some((kind, columns.next().filter(|q| !q.is_empty())?))
+}
+
+/// Parses snapshots as `render_snapshots` writes them.
+pub fn parse_snapshots(text: &str) -> Result<Vec<Snapshot>> {
+    let mut snapshots = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || format!("line {}: expected kind<TAB>query<TAB>ent_seq headword...", line_no + 1);
+        let (kind, query) = parse_query(line).ok_or_else(invalid)?;
+        let mut results = Vec::new();
+        for column in line.split('\t').skip(2) {
+            let (ent_seq, headword) = column.split_once(' ').unwrap_or((column, ""));
+            results.push((ent_seq.parse().map_err(|_| invalid())?, headword.to_string()));
+        }
+        snapshots.push(Snapshot { query: SnapshotQuery { kind, query: query.to_string() }, results });
+    }
+    Ok(snapshots)
+}
+
+/// Writes snapshots for `parse_snapshots`, one line each.
+pub fn render_snapshots(snapshots: &[Snapshot]) -> String {
+    let mut text = String::new();
+    for snapshot in snapshots {
+        text.push_str(snapshot.query.kind.name());
+        text.push('\t');
+        text.push_str(&snapshot.query.query);
+        for (ent_seq, headword) in &snapshot.results {
+            let _ = write!(text, "\t{} {}", ent_seq, headword);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Runs the queries and keeps the first `top` results of each.
+pub async fn take_snapshots(db: &DictDb, queries: &[SnapshotQuery], top: usize) -> Result<Vec<Snapshot>> {
+    let mut snapshots = Vec::with_capacity(queries.len());
+    for query in queries {
+        let entries: Vec<EntryParsed> = match query.kind {
+            SnapshotKind::Ranked => {
+                let opts = RankOptions { limit: top, ..Default::default() };
+                db.search_ranked_outcome(&query.query, &opts).await?.results.into_iter().map(|r| r.entry).collect()
+            }
+            SnapshotKind::Gloss => db.search_entries_by_gloss(&query.query, "eng").await?,
+            SnapshotKind::Expressions => db.search_expressions_containing(&query.query).await?,
+        };
+        let results = entries.iter().take(top).map(|e| (e.ent_seq, e.display_form().to_string())).collect();
+        snapshots.push(Snapshot { query: query.clone(), results });
+    }
+    Ok(snapshots)
+}
+
+/// A readable account of the queries whose results differ, None if none do. Queries are
+/// matched by kind and text; one missing on either side counts as changed.
+pub fn diff_snapshots(expected: &[Snapshot], actual: &[Snapshot]) -> Option<String> {
+    let seqs = |s: &Snapshot| s.results.iter().map(|(seq, _)| *seq).collect::<Vec<_>>();
+    let mut changed = Vec::new();
+    for snapshot in actual {
+        let before = expected.iter().find(|e| e.query == snapshot.query);
+        if before.is_none_or(|before| seqs(before) != seqs(snapshot)) {
+            changed.push((before, Some(snapshot)));
+        }
+    }
+    for snapshot in expected {
+        if !actual.iter().any(|a| a.query == snapshot.query) {
+            changed.push((Some(snapshot), None));
+        }
+    }
+    if changed.is_empty() {
+        return None;
+    }
+
+    let missing = changed.iter().filter(|(_, after)| after.is_none()).count();
+    let mut text = format!("{} of {} queries changed\n", changed.len(), actual.len() + missing);
+    let column = |results: Option<&Snapshot>, i: usize| match results {
+        None => "(missing)".to_string(),
+        Some(s) => s.results.get(i).map_or_else(|| "-".to_string(), |(seq, headword)| format!("{} {}", seq, headword)),
+    };
+    for (before, after) in changed {
+        let query = &before.or(after).expect("one side is there").query;
+        let _ = writeln!(text, "\n{} {:?}:", query.kind.name(), query.query);
+        let rows = before.map_or(0, |s| s.results.len()).max(after.map_or(0, |s| s.results.len())).max(1);
+        let width = (0..rows).map(|i| column(before, i).chars().count()).max().unwrap_or(0);
+        for i in 0..rows {
+            let (was, now) = (column(before, i), column(after, i));
+            let marker = if was == now { ' ' } else { '!' };
+            let pad = width - was.chars().count();
+            let _ = writeln!(text, "  {} {:>2}. {}{}  | {}", marker, i + 1, was, " ".repeat(pad), now);
+        }
+    }
+    Some(text)
+}
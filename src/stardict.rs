@@ -0,0 +1,167 @@
+//! Exporting the dictionary for StarDict readers (GoldenDict, KOReader and others), see
+//! `DictDb::export_stardict`.
+//!
+//! A StarDict dictionary is three files with the same stem: the .ifo with the book's
+//! metadata, the .dict with the articles one after the other, and the .idx with the
+//! headwords in StarDict's order, each with the offset and size of its article in the .dict.
+
+use sqlx::{QueryBuilder, Sqlite};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::annotate::escape_html;
+use crate::entities::standard_name;
+use crate::{DictDb, EntryParsed, EntryRow, PartOfSpeech, Result, SearchFilter};
+
+/// Entries read and rendered per query by `export_stardict`.
+const STARDICT_BATCH_ENTRIES: i64 = 500;
+
+/// StarDict keeps words shorter than this many bytes; longer forms are left out of the index.
+pub const MAX_WORD_BYTES: usize = 256;
+
+/// Options for `DictDb::export_stardict`.
+#[derive(Debug, Clone)]
+pub struct StarDictOptions {
+    /// The file name of the three files, without extension, "jmdict" by default.
+    pub file_stem: String,
+    /// The name readers show for the dictionary, "JMdict" by default.
+    pub book_name: String,
+    /// The description in the .ifo, by default the attribution the JMdict licence asks for.
+    pub description: String,
+    /// Restrictions on the entries exported.
+    pub filter: SearchFilter,
+}
+
+impl Default for StarDictOptions {
+    fn default() -> Self {
+        Self {
+            file_stem: "jmdict".to_string(),
+            book_name: "JMdict".to_string(),
+            description: "JMdict, the Japanese-Multilingual Dictionary of the Electronic Dictionary Research and \
+                          Development Group, used under the Creative Commons Attribution-ShareAlike licence."
+                .to_string(),
+            filter: SearchFilter::default(),
+        }
+    }
+}
+
+/// What `DictDb::export_stardict` wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarDictReport {
+    /// The .ifo file; the .idx and .dict are next to it.
+    pub ifo_path: PathBuf,
+    /// The articles in the .dict, one per entry.
+    pub entries: usize,
+    /// The words in the .idx, the `wordcount` of the .ifo: each distinct keb and reb of each
+    /// entry, so a form shared by several entries is there once for each.
+    pub words: usize,
+    /// The size of the .dict in bytes.
+    pub dict_size: u64,
+}
+
+/// The order of the .idx that readers binary search: bytes compared with ASCII letters
+/// folded to lower case, ties broken by the plain bytes (StarDict's `stardict_strcmp`).
+pub fn stardict_cmp(a: &str, b: &str) -> Ordering {
+    let folded = |s: &str| s.bytes().map(|b| b.to_ascii_lowercase()).collect::<Vec<_>>();
+    folded(a).cmp(&folded(b)).then_with(|| a.cmp(b))
+}
+
+/// The article of an entry as simple HTML: the headword in bold with its readings, the other
+/// forms, then the senses as a numbered list with their tags in italics.
+pub fn stardict_article(entry: &EntryParsed) -> String {
+    let form = entry.display_form();
+    let mut html = format!("<b>{}</b>", escape_html(form));
+    let readings: Vec<&str> = entry.rebs.iter().map(String::as_str).filter(|r| *r != form).collect();
+    if !readings.is_empty() {
+        html.push_str(&format!(" 【{}】", escape_html(&readings.join("、"))));
+    }
+    let others: Vec<&str> = entry.kebs.iter().flatten().map(String::as_str).filter(|k| *k != form).collect();
+    if !others.is_empty() {
+        html.push_str(&format!("<br>also {}", escape_html(&others.join("、"))));
+    }
+    html.push_str("<ol>");
+    for sense in &entry.senses {
+        let pos = sense.pos.iter().map(|p| PartOfSpeech::parse(p).code().to_string());
+        let other = sense.misc.iter().chain(&sense.field).chain(&sense.dial).map(|t| standard_name(t).unwrap_or(t).to_string());
+        let tags: Vec<String> = pos.chain(other).collect();
+        html.push_str("<li>");
+        if !tags.is_empty() {
+            html.push_str(&format!("<i>{}</i> ", escape_html(&tags.join(", "))));
+        }
+        html.push_str(&escape_html(&sense.summary(usize::MAX)));
+        html.push_str("</li>");
+    }
+    html.push_str("</ol>");
+    html
+}
+
+impl DictDb {
+    /// Writes the entries as a StarDict dictionary into `out_dir`: `<stem>.ifo`, `<stem>.idx`
+    /// and `<stem>.dict`, replacing any files by those names, with `stardict_article` as the
+    /// article of each entry and its kebs and rebs as the words leading to it.
+    ///
+    /// Entries are read a few hundred at a time; the .idx is built in memory, about 30 bytes
+    /// per form. Fails if the .dict would pass 4 GiB, the most 32-bit offsets can address.
+    pub async fn export_stardict(&self, out_dir: impl AsRef<Path>, opts: &StarDictOptions) -> Result<StarDictReport> {
+        let out_dir = out_dir.as_ref();
+        let path = |ext: &str| out_dir.join(format!("{}.{}", opts.file_stem, ext));
+        let mut dict = BufWriter::new(File::create(path("dict"))?);
+        let mut words: Vec<(String, u32, u32)> = Vec::new();
+        let (mut entries, mut offset, mut after) = (0, 0u64, i64::MIN);
+        loop {
+            let batch = self.entries_after(&opts.filter, after).await?;
+            let Some(last) = batch.last() else { break };
+            after = last.ent_seq;
+            for entry in &batch {
+                let article = stardict_article(entry);
+                let size = article.len() as u32;
+                let start = u32::try_from(offset).ok().filter(|o| o.checked_add(size).is_some());
+                let start = start.ok_or("the StarDict .dict would pass 4 GiB")?;
+                dict.write_all(article.as_bytes())?;
+                let mut forms: Vec<&String> = entry.kebs.iter().flatten().chain(&entry.rebs).collect();
+                forms.sort();
+                forms.dedup();
+                words.extend(forms.into_iter().filter(|f| !f.is_empty() && f.len() < MAX_WORD_BYTES).map(|f| (f.clone(), start, size)));
+                offset += size as u64;
+                entries += 1;
+            }
+        }
+        dict.flush()?;
+
+        words.sort_by(|a, b| stardict_cmp(&a.0, &b.0).then(a.1.cmp(&b.1)));
+        let mut idx = Vec::new();
+        for (word, start, size) in &words {
+            idx.extend_from_slice(word.as_bytes());
+            idx.push(0);
+            idx.extend_from_slice(&start.to_be_bytes());
+            idx.extend_from_slice(&size.to_be_bytes());
+        }
+        std::fs::write(path("idx"), &idx)?;
+
+        // Each value is one line, so line breaks in the options would end it early
+        let one_line = |s: &str| s.replace(['\r', '\n'], " ");
+        let ifo = format!(
+            "StarDict's dict ifo file\nversion=2.4.2\nbookname={}\nwordcount={}\nidxfilesize={}\nsametypesequence=h\ndescription={}\n",
+            one_line(&opts.book_name),
+            words.len(),
+            idx.len(),
+            one_line(&opts.description),
+        );
+        std::fs::write(path("ifo"), ifo)?;
+        Ok(StarDictReport { ifo_path: path("ifo"), entries, words: words.len(), dict_size: offset })
+    }
+
+    /// Up to STARDICT_BATCH_ENTRIES entries after `after` passing the filter, by ent_seq;
+    /// the exports read all entries with it a batch at a time.
+    pub(crate) async fn entries_after(&self, filter: &SearchFilter, after: i64) -> Result<Vec<EntryParsed>> {
+        let mut qb = QueryBuilder::<Sqlite>::new("SELECT e.* FROM entries e WHERE e.ent_seq > ");
+        qb.push_bind(after);
+        filter.push_conditions(&mut qb, "e.ent_seq");
+        qb.push(" ORDER BY e.ent_seq LIMIT ");
+        qb.push_bind(STARDICT_BATCH_ENTRIES);
+        let rows = qb.build_query_as::<EntryRow>().fetch_all(&self.pool).await?;
+        self.hydrate_entries(rows).await
+    }
+}
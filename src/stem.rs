@@ -0,0 +1,189 @@
+//! The Porter stemmer (M.F. Porter, "An algorithm for suffix stripping", 1980), for
+//! comparing English gloss words by their stem in `GlossQuery::stemmed`.
+
+/// Step 2 suffixes and their replacements, for stems with a measure above 0. For suffixes
+/// ending the same way the longest comes first; the first one that ends the word is the only one
+/// tried, as in the original algorithm.
+const STEP2: &[(&str, &str)] = &[
+    ("ational", "ate"),
+    ("tional", "tion"),
+    ("enci", "ence"),
+    ("anci", "ance"),
+    ("izer", "ize"),
+    ("abli", "able"),
+    ("alli", "al"),
+    ("entli", "ent"),
+    ("eli", "e"),
+    ("ousli", "ous"),
+    ("ization", "ize"),
+    ("ation", "ate"),
+    ("ator", "ate"),
+    ("alism", "al"),
+    ("iveness", "ive"),
+    ("fulness", "ful"),
+    ("ousness", "ous"),
+    ("aliti", "al"),
+    ("iviti", "ive"),
+    ("biliti", "ble"),
+];
+
+/// Step 3, likewise.
+const STEP3: &[(&str, &str)] = &[
+    ("icate", "ic"),
+    ("ative", ""),
+    ("alize", "al"),
+    ("iciti", "ic"),
+    ("ical", "ic"),
+    ("ful", ""),
+    ("ness", ""),
+];
+
+/// Step 4 suffixes, removed from stems with a measure above 1. "ion" only goes after s or t.
+const STEP4: &[&str] = &[
+    "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ion", "ou", "ism", "ate", "iti",
+    "ous", "ive", "ize",
+];
+
+/// Whether the letter at `i` is a consonant: not a vowel, and y only after a vowel.
+fn is_consonant(w: &[u8], i: usize) -> bool {
+    match w[i] {
+        b'a' | b'e' | b'i' | b'o' | b'u' => false,
+        b'y' => i == 0 || !is_consonant(w, i - 1),
+        _ => true,
+    }
+}
+
+/// The measure m of a stem written [C](VC)^m[V]: how many vowel-consonant sequences it has.
+fn measure(w: &[u8]) -> usize {
+    let n = w.len();
+    let mut i = 0;
+    while i < n && is_consonant(w, i) {
+        i += 1;
+    }
+    let mut m = 0;
+    loop {
+        while i < n && !is_consonant(w, i) {
+            i += 1;
+        }
+        if i >= n {
+            return m;
+        }
+        while i < n && is_consonant(w, i) {
+            i += 1;
+        }
+        m += 1;
+    }
+}
+
+fn has_vowel(w: &[u8]) -> bool {
+    (0..w.len()).any(|i| !is_consonant(w, i))
+}
+
+/// Whether the word ends in a double consonant, e.g. "-tt".
+fn ends_double_consonant(w: &[u8]) -> bool {
+    let n = w.len();
+    n >= 2 && w[n - 1] == w[n - 2] && is_consonant(w, n - 1)
+}
+
+/// Whether the word ends consonant-vowel-consonant, the last not w, x or y, e.g. "-hop".
+fn ends_cvc(w: &[u8]) -> bool {
+    let n = w.len();
+    n >= 3
+        && is_consonant(w, n - 3)
+        && !is_consonant(w, n - 2)
+        && is_consonant(w, n - 1)
+        && !matches!(w[n - 1], b'w' | b'x' | b'y')
+}
+
+/// The stem before `suffix` if the word ends with it.
+fn stem_of<'a>(w: &'a [u8], suffix: &str) -> Option<&'a [u8]> {
+    w.strip_suffix(suffix.as_bytes())
+}
+
+/// Replaces the first suffix of `rules` that ends the word, if its stem has a measure above `min`.
+fn replace_first(w: &mut Vec<u8>, rules: &[(&str, &str)], min: usize) {
+    if let Some((suffix, replacement)) = rules.iter().find(|(suffix, _)| w.ends_with(suffix.as_bytes())) {
+        let stem_len = w.len() - suffix.len();
+        if measure(&w[..stem_len]) > min {
+            w.truncate(stem_len);
+            w.extend_from_slice(replacement.as_bytes());
+        }
+    }
+}
+
+fn step1ab(w: &mut Vec<u8>) {
+    if w.ends_with(b"sses") || w.ends_with(b"ies") {
+        w.truncate(w.len() - 2);
+    } else if w.ends_with(b"s") && !w.ends_with(b"ss") {
+        w.pop();
+    }
+
+    if let Some(stem) = stem_of(w, "eed") {
+        if measure(stem) > 0 {
+            w.pop();
+        }
+        return;
+    }
+    let Some(stem_len) = ["ed", "ing"].iter().find_map(|s| stem_of(w, s).filter(|stem| has_vowel(stem)).map(<[u8]>::len))
+    else {
+        return;
+    };
+    w.truncate(stem_len);
+    if w.ends_with(b"at") || w.ends_with(b"bl") || w.ends_with(b"iz") {
+        w.push(b'e');
+    } else if ends_double_consonant(w) && !matches!(w[w.len() - 1], b'l' | b's' | b'z') {
+        w.pop();
+    } else if measure(w) == 1 && ends_cvc(w) {
+        w.push(b'e');
+    }
+}
+
+fn step1c(w: &mut [u8]) {
+    let n = w.len();
+    if w.ends_with(b"y") && has_vowel(&w[..n - 1]) {
+        w[n - 1] = b'i';
+    }
+}
+
+fn step4(w: &mut Vec<u8>) {
+    let Some(suffix) = STEP4.iter().find(|s| w.ends_with(s.as_bytes())) else {
+        return;
+    };
+    let stem = &w[..w.len() - suffix.len()];
+    let allowed = *suffix != "ion" || matches!(stem.last(), Some(b's' | b't'));
+    if allowed && measure(stem) > 1 {
+        w.truncate(stem.len());
+    }
+}
+
+fn step5(w: &mut Vec<u8>) {
+    if let Some(stem) = stem_of(w, "e") {
+        let m = measure(stem);
+        if m > 1 || (m == 1 && !ends_cvc(stem)) {
+            w.pop();
+        }
+    }
+    if w.ends_with(b"ll") && measure(w) > 1 {
+        w.pop();
+    }
+}
+
+/// The Porter stem of a lowercase English word: "running" and "runs" are "run",
+/// "dictionary" and "dictionaries" are "dictionari".
+///
+/// Stems are only for comparing words, they are often no word themselves. Irregular forms
+/// keep their own stem ("ate" is not "eat"), and words of two letters or less and words
+/// with other characters than a-z are returned unchanged.
+pub fn porter_stem(word: &str) -> String {
+    if word.len() <= 2 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return word.to_string();
+    }
+    let mut w = word.as_bytes().to_vec();
+    step1ab(&mut w);
+    step1c(&mut w);
+    replace_first(&mut w, STEP2, 0);
+    replace_first(&mut w, STEP3, 0);
+    step4(&mut w);
+    step5(&mut w);
+    String::from_utf8(w).unwrap_or_else(|_| word.to_string())
+}
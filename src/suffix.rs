@@ -0,0 +1,142 @@
+//! Looking up the suffix or counter of a text selection such as 3ヶ月, see
+//! `DictDb::search_suffix_entry`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::guards::non_empty;
+use crate::query_stats::RowCount;
+use crate::{DictDb, EntryParsed, EntryRow, PartOfSpeech, Result};
+
+/// The placeholder tildes of suffix headwords and glosses, 〜中 or ～によって.
+pub const PLACEHOLDER_TILDES: [char; 3] = ['〜', '～', '~'];
+
+/// The suffix or counter entry of a selection, see `DictDb::search_suffix_entry`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuffixMatch {
+    pub entry: EntryParsed,
+    /// The number in front of the suffix, 3 for 3ヶ月 and 5 for 五人.
+    pub number: Option<u64>,
+    /// The part of the selection that was looked up, ヶ月 for 3ヶ月.
+    pub suffix: String,
+}
+
+impl RowCount for Option<SuffixMatch> {
+    fn row_count(&self) -> usize {
+        usize::from(self.is_some())
+    }
+}
+
+/// The value of an ASCII, fullwidth or kanji digit.
+fn digit_value(c: char) -> Option<u64> {
+    match c {
+        '0'..='9' => Some(c as u64 - '0' as u64),
+        '０'..='９' => Some(c as u64 - '０' as u64),
+        _ => "〇一二三四五六七八九".chars().position(|d| d == c).map(|d| d as u64),
+    }
+}
+
+/// The multiplier of a kanji numeral, 10 for 十; 万 and 億 end a group of four digits.
+fn unit_value(c: char) -> Option<u64> {
+    match c {
+        '十' => Some(10),
+        '百' => Some(100),
+        '千' => Some(1_000),
+        '万' => Some(10_000),
+        '億' => Some(100_000_000),
+        _ => None,
+    }
+}
+
+/// Reads a number written with digits, 2023 or ２０２３, or kanji numerals, 二〇二三 or
+/// 二千二十三. None if it doesn't fit in a u64.
+fn parse_number(s: &str) -> Option<u64> {
+    let (mut total, mut group, mut digits) = (0u64, 0u64, None::<u64>);
+    for c in s.chars() {
+        if let Some(d) = digit_value(c) {
+            digits = Some(digits.unwrap_or(0).checked_mul(10)?.checked_add(d)?);
+        } else if let Some(unit @ (10 | 100 | 1_000)) = unit_value(c) {
+            group = group.checked_add(digits.take().unwrap_or(1).checked_mul(unit)?)?;
+        } else if let Some(unit) = unit_value(c) {
+            let value = group.checked_add(digits.take().unwrap_or(0))?;
+            total = total.checked_add(if value == 0 { 1 } else { value }.checked_mul(unit)?)?;
+            group = 0;
+        }
+    }
+    total.checked_add(group)?.checked_add(digits.unwrap_or(0))
+}
+
+/// Splits a selection into its leading number and the rest, dropping the placeholder
+/// tildes and spaces around the rest: `(Some(3), "ヶ月")` for "3ヶ月", `(None, "によって")`
+/// for "〜によって". Only a selection starting with a number has one. Any leading numeral
+/// counts, so 一緒 gives `(Some(1), "緒")`; `DictDb::search_suffix_entry` falls back to
+/// the whole selection when the rest is no counter.
+pub fn split_leading_number(selection: &str) -> (Option<u64>, &str) {
+    let selection = selection.trim().trim_start_matches(PLACEHOLDER_TILDES);
+    let end = selection
+        .char_indices()
+        .find(|&(_, c)| digit_value(c).is_none() && unit_value(c).is_none() && c != ',')
+        .map_or(selection.len(), |(i, _)| i);
+    let (number, rest) = selection.split_at(end);
+    let rest = rest.trim().trim_start_matches(PLACEHOLDER_TILDES).trim();
+    let digits = number.replace(',', "");
+    ((!digits.is_empty()).then(|| parse_number(&digits)).flatten(), rest)
+}
+
+/// Whether a sense is a suffix or counter, for any of its parts of speech.
+fn is_suffix_pos(pos: &[String]) -> bool {
+    pos.iter().any(|p| matches!(PartOfSpeech::parse(p), PartOfSpeech::Ctr | PartOfSpeech::Suffix | PartOfSpeech::NounSuffix))
+}
+
+/// Entries with a keb or reb equal to ?1, tildes around it aside.
+const SEARCH_SUFFIX_SQL: &str = r#"
+    SELECT * FROM entries e
+    WHERE EXISTS (
+        SELECT 1 FROM json_each(e.rebs) je WHERE trim(je.value, '〜～~') = ?1
+    ) OR (e.kebs IS NOT NULL AND EXISTS (
+        SELECT 1 FROM json_each(e.kebs) je WHERE trim(je.value, '〜～~') = ?1
+    ))
+    ORDER BY e.nf_bucket IS NULL, e.nf_bucket, e.ent_seq
+"#;
+
+impl DictDb {
+    /// The suffix or counter entry of a text selection, for lookups from selected text:
+    /// the ヶ月 entry for 3ヶ月, 人 for 五人, 年 for 2023年 and によって for 〜によって.
+    ///
+    /// The leading number (digits, fullwidth digits or kanji numerals) and placeholder
+    /// tildes are split off, see `split_leading_number`, and the rest is matched against
+    /// the kebs and rebs with their own tildes left out, so 中 finds 〜中. Entries with a
+    /// suffix or counter sense come first, then the most frequent (by nfNN bucket). If the
+    /// rest after a number has no suffix or counter entry, the whole selection is looked up
+    /// instead, so words that start with a numeral, 一緒, 千葉 or 万年筆, find themselves
+    /// with no number. None if nothing matches, or if the selection is only a number.
+    pub async fn search_suffix_entry(&self, selection: &str) -> Result<Option<SuffixMatch>> {
+        self.observe("search_suffix_entry", selection, String::new, async {
+            let (number, suffix) = split_leading_number(non_empty(selection)?);
+            if suffix.is_empty() {
+                return Ok(None);
+            }
+            let mut entries = self.suffix_candidates(suffix).await?;
+            let best = entries.iter().position(|e| e.senses.iter().any(|s| is_suffix_pos(&s.pos)));
+            if best.is_none() && number.is_some() {
+                // No counter after the number, so maybe a word starting with a numeral
+                let whole = selection.trim().trim_matches(PLACEHOLDER_TILDES).trim();
+                let mut whole_entries = self.suffix_candidates(whole).await?;
+                if !whole_entries.is_empty() {
+                    return Ok(Some(SuffixMatch { entry: whole_entries.swap_remove(0), number: None, suffix: whole.to_string() }));
+                }
+            }
+            let best = best.or((!entries.is_empty()).then_some(0));
+            Ok(best.map(|i| SuffixMatch { entry: entries.swap_remove(i), number, suffix: suffix.to_string() }))
+        })
+        .await
+    }
+
+    /// The entries with `term` as a keb or reb, most frequent first.
+    async fn suffix_candidates(&self, term: &str) -> Result<Vec<EntryParsed>> {
+        let rows = sqlx::query_as::<_, EntryRow>(SEARCH_SUFFIX_SQL)
+            .bind(term)
+            .fetch_all(&self.pool)
+            .await?;
+        self.hydrate_entries(rows).await
+    }
+}
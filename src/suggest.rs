@@ -0,0 +1,285 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::sync::{Arc, RwLock};
+
+use crate::dict_db::truncate_chars;
+use crate::guards::non_empty;
+use crate::punctuation::strip_headword_punctuation;
+use crate::{DictDb, PartOfSpeech, Result};
+
+/// Characters of the first gloss kept in a `SuggestionHint`.
+pub const HINT_GLOSS_CHARS: usize = 30;
+
+/// A headword (reb or keb) completing a prefix, see `DictDb::suggest_fast`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Suggestion {
+    pub headword: String,
+    pub ent_seq: i64,
+    /// What tells the entry apart from others with the same headword, None unless
+    /// `DictDbOptions::suggestion_hints` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<SuggestionHint>,
+}
+
+/// A few words on the entry of a suggestion, so that the three 生 can be told apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SuggestionHint {
+    /// The first reb the headword may be read as, None if the headword is a reb.
+    pub reading: Option<String>,
+    /// The first gloss of the first sense, cut to `HINT_GLOSS_CHARS`; None if the sense
+    /// has no gloss of its own.
+    pub gloss: Option<String>,
+    /// The code of the first part of speech of the first sense, e.g. "n" or "v1".
+    pub pos: Option<String>,
+}
+
+impl Suggestion {
+    /// The suggestion as a list shows it: "生 (なま) — raw" with a hint, else the headword.
+    pub fn label(&self) -> String {
+        let mut label = self.headword.clone();
+        let Some(hint) = &self.hint else {
+            return label;
+        };
+        if let Some(reading) = &hint.reading {
+            label.push_str(&format!(" ({})", reading));
+        }
+        if let Some(gloss) = &hint.gloss {
+            label.push_str(&format!(" — {}", gloss));
+        }
+        label
+    }
+}
+
+/// The size of the in-memory suggestion index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SuggestIndexStats {
+    /// Distinct (headword, entry) pairs.
+    pub headwords: usize,
+    /// Approximate heap memory used, in bytes.
+    pub bytes: usize,
+}
+
+/// All headwords with their entry, sorted by headword then ent_seq, so the completions of
+/// a prefix are one contiguous run found by binary search. The punctuated ones are also in
+/// `plain`, sorted by their stripped form.
+#[derive(Debug, Default)]
+pub(crate) struct SuggestIndex {
+    headwords: Vec<(Box<str>, i64)>,
+    plain: Vec<(Box<str>, Box<str>, i64)>,
+    /// The hints of `headwords`, in the same order; empty without `suggestion_hints`.
+    hints: Vec<SuggestionHint>,
+}
+
+/// The suggestion index of a DictDb, None until `build_suggest_index` is called.
+pub(crate) type SuggestCache = RwLock<Option<Arc<SuggestIndex>>>;
+
+impl SuggestIndex {
+    fn stats(&self) -> SuggestIndexStats {
+        let strings: usize = self.headwords.iter().map(|(h, _)| h.len()).sum();
+        let plain_strings: usize = self.plain.iter().map(|(p, h, _)| p.len() + h.len()).sum();
+        let len = |s: &Option<String>| s.as_ref().map_or(0, String::capacity);
+        let hint_strings: usize = self.hints.iter().map(|h| len(&h.reading) + len(&h.gloss) + len(&h.pos)).sum();
+        SuggestIndexStats {
+            headwords: self.headwords.len(),
+            bytes: self.headwords.capacity() * std::mem::size_of::<(Box<str>, i64)>()
+                + self.plain.capacity() * std::mem::size_of::<(Box<str>, Box<str>, i64)>()
+                + self.hints.capacity() * std::mem::size_of::<SuggestionHint>()
+                + strings
+                + plain_strings
+                + hint_strings,
+        }
+    }
+
+    fn suggest(&self, prefix: &str, limit: usize) -> Vec<Suggestion> {
+        let completions = |prefix: &str| {
+            let start = self.headwords.partition_point(|(h, _)| &**h < prefix);
+            self.headwords[start..]
+                .iter()
+                .take_while(move |(h, _)| h.starts_with(prefix))
+                .take(limit)
+                .map(|(h, ent_seq)| (&**h, *ent_seq))
+                .collect::<Vec<_>>()
+        };
+        let mut found = completions(prefix);
+        let stripped = strip_headword_punctuation(prefix);
+        // A prefix of only punctuation completes as typed
+        if !stripped.is_empty() {
+            if stripped != prefix {
+                found.extend(completions(&stripped));
+            }
+            let start = self.plain.partition_point(|(p, _, _)| **p < *stripped);
+            found.extend(
+                self.plain[start..]
+                    .iter()
+                    .take_while(|(p, _, _)| p.starts_with(stripped.as_str()))
+                    .take(limit)
+                    .map(|(_, h, ent_seq)| (&**h, *ent_seq)),
+            );
+        }
+        found.sort_unstable();
+        found.dedup();
+        found.truncate(limit);
+        found
+            .into_iter()
+            .map(|(h, ent_seq)| {
+                // Every punctuated headword is in `headwords` too
+                let at = self.headwords.binary_search_by(|(other, seq)| (&**other, *seq).cmp(&(h, ent_seq)));
+                let hint = at.ok().and_then(|at| self.hints.get(at)).cloned();
+                Suggestion { headword: h.to_string(), ent_seq, hint }
+            })
+            .collect()
+    }
+}
+
+/// A suggestion with the columns of its hint, see `with_hints`.
+#[derive(FromRow)]
+struct HintedRow {
+    headword: String,
+    ent_seq: i64,
+    reading: Option<String>,
+    gloss: Option<String>,
+    pos: Option<String>,
+}
+
+impl HintedRow {
+    /// The headword and its entry, and the hint.
+    fn split(self) -> ((String, i64), SuggestionHint) {
+        let hint = SuggestionHint {
+            reading: self.reading,
+            gloss: self.gloss.filter(|g| !g.is_empty()).map(|g| truncate_chars(&g, HINT_GLOSS_CHARS)),
+            pos: self.pos.map(|p| PartOfSpeech::parse(&p).code().to_string()),
+        };
+        ((self.headword, self.ent_seq), hint)
+    }
+
+    fn into_suggestion(self) -> Suggestion {
+        let ((headword, ent_seq), hint) = self.split();
+        Suggestion { headword, ent_seq, hint: Some(hint) }
+    }
+}
+
+/// The (headword, ent_seq) rows of `suggestions` with the hint of each, joined in the same
+/// query: the first reb not restricted to other kebs unless the headword is a reb itself,
+/// and the first gloss and part of speech of the first sense.
+fn with_hints(suggestions: &str) -> String {
+    format!(
+        r#"
+        SELECT m.headword, m.ent_seq,
+            CASE WHEN EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE r.value = m.headword) THEN NULL ELSE (
+                SELECT r.value FROM json_each(e.rebs) r
+                WHERE e.re_restr = '[]'
+                   OR json_array_length(e.re_restr, '$[' || r.key || ']') = 0
+                   OR EXISTS (SELECT 1 FROM json_each(e.re_restr, '$[' || r.key || ']') x WHERE x.value = m.headword)
+                ORDER BY r.key LIMIT 1
+            ) END AS reading,
+            json_extract(s.gloss, '$[0]') AS gloss,
+            json_extract(s.pos, '$[0]') AS pos
+        FROM ({}) m
+        JOIN entries e ON e.ent_seq = m.ent_seq
+        LEFT JOIN senses s ON s.ent_seq = m.ent_seq AND s.sense_order = 0
+        ORDER BY m.headword, m.ent_seq
+        "#,
+        suggestions
+    )
+}
+
+// Both sides of the UNION give (headword, ent_seq); ordering TEXT with the default BINARY
+// collation compares the UTF-8 bytes, the same order as Rust's str.
+const HEADWORDS_SQL: &str = r#"
+    SELECT je.value AS headword, e.ent_seq FROM entries e, json_each(e.rebs) je
+    UNION
+    SELECT je.value AS headword, e.ent_seq FROM entries e, json_each(e.kebs) je
+"#;
+
+// The punctuated headwords by their stripped form, see `strip_headword_punctuation`
+const PLAIN_HEADWORDS_SQL: &str = "SELECT DISTINCT plain, form AS headword, ent_seq FROM punct_index";
+
+impl DictDb {
+    /// Loads all rebs and kebs into memory for `suggest_fast`, replacing an earlier index.
+    ///
+    /// The index is a snapshot: call it again after inserting or deleting entries. With
+    /// `DictDbOptions::suggestion_hints` it holds the hint of every headword as well, which
+    /// about doubles its size.
+    pub async fn build_suggest_index(&self) -> Result<SuggestIndexStats> {
+        let (rows, hints): (Vec<(String, i64)>, Vec<SuggestionHint>) = if self.suggestion_hints {
+            let rows: Vec<HintedRow> = sqlx::query_as(&with_hints(HEADWORDS_SQL)).fetch_all(&self.pool).await?;
+            rows.into_iter().map(HintedRow::split).unzip()
+        } else {
+            let rows = sqlx::query_as(&format!("{} ORDER BY headword, ent_seq", HEADWORDS_SQL)).fetch_all(&self.pool).await?;
+            (rows, Vec::new())
+        };
+        let plain: Vec<(String, String, i64)> = sqlx::query_as(&format!("{} ORDER BY plain, headword, ent_seq", PLAIN_HEADWORDS_SQL))
+            .fetch_all(&self.pool)
+            .await?;
+        let index = SuggestIndex {
+            headwords: rows.into_iter().map(|(h, ent_seq)| (h.into_boxed_str(), ent_seq)).collect(),
+            plain: plain.into_iter().map(|(p, h, ent_seq)| (p.into_boxed_str(), h.into_boxed_str(), ent_seq)).collect(),
+            hints,
+        };
+        let stats = index.stats();
+        *self.suggest.write().map_err(|_| "suggestion index poisoned")? = Some(Arc::new(index));
+        Ok(stats)
+    }
+
+    /// The size of the suggestion index, None if it hasn't been built.
+    pub fn suggest_index_stats(&self) -> Option<SuggestIndexStats> {
+        self.suggest.read().ok()?.as_ref().map(|index| index.stats())
+    }
+
+    /// Frees the suggestion index, `suggest_fast` goes back to SQL.
+    pub fn drop_suggest_index(&self) {
+        if let Ok(mut index) = self.suggest.write() {
+            *index = None;
+        }
+    }
+
+    /// Up to `limit` headwords of the main database starting with `prefix`, in code point
+    /// order with their entry, for autocompletion.
+    ///
+    /// Punctuation in headwords and in the prefix is skipped as by
+    /// `strip_headword_punctuation`: データベ completes to データ・ベース and データ・ベ to
+    /// データベース.
+    ///
+    /// Answered from memory once `build_suggest_index` was called, otherwise with the same
+    /// results from SQL.
+    pub async fn suggest_fast(&self, prefix: &str, limit: usize) -> Result<Vec<Suggestion>> {
+        self.observe("suggest_fast", prefix, || format!("limit={}", limit), async {
+            non_empty(prefix)?;
+            if limit == 0 {
+                return Ok(Vec::new());
+            }
+            let index = self.suggest.read().map_err(|_| "suggestion index poisoned")?.clone();
+            if let Some(index) = index {
+                return Ok(index.suggest(prefix, limit));
+            }
+            let completions = format!(
+                r#"
+                SELECT headword, ent_seq FROM ({}) WHERE substr(headword, 1, length(?1)) = ?1 OR substr(headword, 1, length(?3)) = ?3
+                UNION
+                SELECT headword, ent_seq FROM ({}) WHERE substr(plain, 1, length(?3)) = ?3
+                ORDER BY headword, ent_seq LIMIT ?2
+                "#,
+                HEADWORDS_SQL, PLAIN_HEADWORDS_SQL
+            );
+            let stripped = Some(strip_headword_punctuation(prefix)).filter(|s| !s.is_empty());
+            if self.suggestion_hints {
+                let rows: Vec<HintedRow> = sqlx::query_as(&with_hints(&completions))
+                    .bind(prefix)
+                    .bind(limit as i64)
+                    .bind(stripped)
+                    .fetch_all(&self.pool)
+                    .await?;
+                return Ok(rows.into_iter().map(HintedRow::into_suggestion).collect());
+            }
+            let rows: Vec<(String, i64)> = sqlx::query_as(&completions)
+                .bind(prefix)
+                .bind(limit as i64)
+                .bind(stripped)
+                .fetch_all(&self.pool)
+                .await?;
+            Ok(rows.into_iter().map(|(headword, ent_seq)| Suggestion { headword, ent_seq, hint: None }).collect())
+        })
+        .await
+    }
+}
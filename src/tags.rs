@@ -0,0 +1,324 @@
+//! The tags of senses: what category each is in, see `lookup_tag`, and how many senses
+//! have it, see `DictDb::distinct_pos`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, RwLock};
+
+use crate::entities::{declarations, STANDARD_DTD};
+use crate::{DictDb, PartOfSpeech, Result, SenseParsed};
+
+/// The element of the JMdict DTD a tag is used in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagCategory {
+    /// `<pos>`, e.g. "v1".
+    PartOfSpeech,
+    /// `<misc>`, e.g. "uk".
+    Misc,
+    /// `<field>`, e.g. "comp".
+    Field,
+    /// `<dial>`, e.g. "ksb".
+    Dialect,
+    /// `<ke_inf>`, e.g. "iK".
+    KanjiInfo,
+    /// `<re_inf>`, e.g. "ok".
+    ReadingInfo,
+    /// A tag not in the built-in DTD, e.g. one added by a later JMdict release.
+    Unknown,
+}
+
+impl TagCategory {
+    /// The categories of known tags, in the order of the columns and XML elements.
+    pub const KNOWN: [TagCategory; 6] = [
+        TagCategory::PartOfSpeech,
+        TagCategory::Misc,
+        TagCategory::Field,
+        TagCategory::Dialect,
+        TagCategory::KanjiInfo,
+        TagCategory::ReadingInfo,
+    ];
+
+    /// The XML element of the category, e.g. "pos"; None for `Unknown`.
+    pub fn element(&self) -> Option<&'static str> {
+        match self {
+            TagCategory::PartOfSpeech => Some("pos"),
+            TagCategory::Misc => Some("misc"),
+            TagCategory::Field => Some("field"),
+            TagCategory::Dialect => Some("dial"),
+            TagCategory::KanjiInfo => Some("ke_inf"),
+            TagCategory::ReadingInfo => Some("re_inf"),
+            TagCategory::Unknown => None,
+        }
+    }
+
+    /// The category of an XML element, e.g. `Misc` for "misc".
+    pub fn from_element(element: &str) -> Option<Self> {
+        Self::KNOWN.into_iter().find(|c| c.element() == Some(element))
+    }
+
+    /// The sense column holding the tags, None for the kanji and reading info the
+    /// database doesn't keep, and for `Unknown`.
+    pub(crate) fn column(&self) -> Option<&'static str> {
+        match self {
+            TagCategory::PartOfSpeech => Some(TAG_COLUMNS[0]),
+            TagCategory::Misc => Some(TAG_COLUMNS[1]),
+            TagCategory::Field => Some(TAG_COLUMNS[2]),
+            TagCategory::Dialect => Some(TAG_COLUMNS[3]),
+            _ => None,
+        }
+    }
+}
+
+/// What a tag is, for showing and filtering by it: its entity name, category, a short label
+/// for chips and lists, and the description the DTD expands it to, which the database stores.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TagInfo {
+    /// The entity name, e.g. "uk".
+    pub name: Cow<'static, str>,
+    pub category: TagCategory,
+    /// e.g. "usually kana".
+    pub label: Cow<'static, str>,
+    /// e.g. "word usually written using kana alone".
+    pub description: Cow<'static, str>,
+}
+
+impl TagInfo {
+    /// A tag not in the built-in DTD, with its text as the name, label and description, so it
+    /// is shown and filtered by as it is.
+    pub fn unknown(name: impl Into<String>) -> Self {
+        let name: String = name.into();
+        Self {
+            label: Cow::Owned(name.clone()),
+            description: Cow::Owned(name.clone()),
+            name: Cow::Owned(name),
+            category: TagCategory::Unknown,
+        }
+    }
+
+    /// Returns true if the tag is in the built-in DTD.
+    pub fn is_known(&self) -> bool {
+        self.category != TagCategory::Unknown
+    }
+}
+
+impl From<&str> for TagInfo {
+    /// `lookup_tag`.
+    fn from(tag: &str) -> Self {
+        lookup_tag(tag)
+    }
+}
+
+impl From<String> for TagInfo {
+    fn from(tag: String) -> Self {
+        lookup_tag(&tag)
+    }
+}
+
+impl From<&PartOfSpeech> for TagInfo {
+    fn from(pos: &PartOfSpeech) -> Self {
+        lookup_tag(pos.code())
+    }
+}
+
+impl From<PartOfSpeech> for TagInfo {
+    fn from(pos: PartOfSpeech) -> Self {
+        lookup_tag(pos.code())
+    }
+}
+
+/// Short labels where cutting the description (see `default_label`) gives a poor one.
+const LABELS: &[(&str, &str)] = &[
+    ("adj-f", "prenominal"),
+    ("adj-i", "i-adjective"),
+    ("adj-ix", "i-adjective (yoi/ii)"),
+    ("adj-na", "na-adjective"),
+    ("adj-nari", "nari-adjective"),
+    ("adj-no", "no-adjective"),
+    ("adj-pn", "pre-noun adjectival"),
+    ("adv-to", "to-adverb"),
+    ("exp", "expression"),
+    ("n-adv", "adverbial noun"),
+    ("n-pref", "noun prefix"),
+    ("n-suf", "noun suffix"),
+    ("n-t", "temporal noun"),
+    ("v1-s", "Ichidan verb (kureru)"),
+    ("v5aru", "Godan verb (-aru)"),
+    ("v5k-s", "Godan verb (iku/yuku)"),
+    ("v5r-i", "Godan verb (-ru, irregular)"),
+    ("v5u-s", "Godan verb (-u, special)"),
+    ("v5uru", "Godan verb (uru)"),
+    ("vk", "kuru verb"),
+    ("vr", "irregular ru verb"),
+    ("vs", "suru verb"),
+    ("vs-c", "su verb"),
+    ("vs-i", "suru verb"),
+    ("vs-s", "suru verb (special)"),
+    ("vz", "zuru verb"),
+    ("form", "formal"),
+    ("hon", "honorific"),
+    ("hum", "humble"),
+    ("id", "idiom"),
+    ("on-mim", "onomatopoeia"),
+    ("person", "person"),
+    ("pol", "polite"),
+    ("uk", "usually kana"),
+    ("work", "work name"),
+    ("X", "rude"),
+    ("iK", "irregular kanji"),
+    ("ik", "irregular kana"),
+    ("io", "irregular okurigana"),
+    ("oK", "outdated kanji"),
+    ("ok", "outdated kana"),
+    ("rK", "rare kanji"),
+    ("rk", "rare kana"),
+    ("sK", "search-only kanji"),
+    ("sk", "search-only kana"),
+];
+
+/// Tags the DTD declares under another element first: `ik` is listed with the kanji info
+/// too, but is about readings.
+const CATEGORIES: &[(&str, TagCategory)] = &[("ik", TagCategory::ReadingInfo)];
+
+// The section comments of the built-in DTD, "<!-- <misc> (miscellaneous) entities -->"
+static SECTION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<!--\s*<(\w+)>").unwrap());
+// A parenthesized part of a description, "(keiyoushi)"
+static PARENS_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s*\([^)]*\)").unwrap());
+// The verb classes by ending, "Godan verb with 'ku' ending"
+static ENDING_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(.+ verb) with '([^']+)' ending").unwrap());
+
+/// The label of a description without an entry in `LABELS`: without its parenthesized parts,
+/// up to the first comma or semicolon, and the ending of verb classes shortened, so
+/// "Godan verb with 'ku' ending" is "Godan verb (-ku)".
+fn default_label(description: &str) -> String {
+    let label = PARENS_RE.replace_all(description, "");
+    let label = label.split([',', ';']).next().unwrap_or_default().trim();
+    match ENDING_RE.captures(label) {
+        Some(cap) => format!("{} (-{})", &cap[1], &cap[2]),
+        None => label.to_string(),
+    }
+}
+
+/// Every tag of the built-in DTD by name and by description.
+struct Registry {
+    by_name: BTreeMap<&'static str, TagInfo>,
+    by_description: HashMap<&'static str, &'static str>,
+}
+
+/// Built from the sections of `STANDARD_DTD` with `LABELS` and `CATEGORIES` applied.
+static REGISTRY: LazyLock<Registry> = LazyLock::new(|| {
+    let mut registry = Registry { by_name: BTreeMap::new(), by_description: HashMap::new() };
+    let mut category = TagCategory::Unknown;
+    for line in STANDARD_DTD.lines() {
+        if let Some(cap) = SECTION_RE.captures(line) {
+            category = TagCategory::from_element(&cap[1]).unwrap_or(TagCategory::Unknown);
+        }
+        for (name, description) in declarations(line) {
+            let category = CATEGORIES.iter().find(|(n, _)| *n == name).map_or(category, |(_, c)| *c);
+            let label = match LABELS.iter().find(|(n, _)| *n == name) {
+                Some((_, label)) => Cow::Borrowed(*label),
+                None => Cow::Owned(default_label(description)),
+            };
+            let info = TagInfo { name: Cow::Borrowed(name), category, label, description: Cow::Borrowed(description) };
+            registry.by_name.entry(name).or_insert(info);
+            registry.by_description.entry(description).or_insert(name);
+        }
+    }
+    registry
+});
+
+/// The tag with this entity name, e.g. "uk", or this description, e.g. "word usually
+/// written using kana alone", as the database stores them. `TagInfo::unknown` if it is
+/// neither, e.g. a tag of a later JMdict release.
+///
+/// ```
+/// use jmdictdb::{lookup_tag, TagCategory};
+///
+/// let uk = lookup_tag("uk");
+/// assert_eq!(uk.category, TagCategory::Misc);
+/// assert_eq!(uk.label, "usually kana");
+/// assert_eq!(lookup_tag("word usually written using kana alone"), uk);
+/// ```
+pub fn lookup_tag(tag: &str) -> TagInfo {
+    let registry = &*REGISTRY;
+    let name = registry.by_description.get(tag).copied().unwrap_or(tag);
+    registry.by_name.get(name).cloned().unwrap_or_else(|| TagInfo::unknown(tag))
+}
+
+/// Every tag of the built-in DTD, by name.
+pub fn known_tags() -> impl Iterator<Item = &'static TagInfo> {
+    REGISTRY.by_name.values()
+}
+
+impl SenseParsed {
+    /// The part of speech, misc, field and dialect tags of the sense in that order, unknown
+    /// ones included as `TagInfo::unknown`.
+    pub fn tags(&self) -> Vec<TagInfo> {
+        self.pos.iter().chain(&self.misc).chain(&self.field).chain(&self.dial).map(|t| lookup_tag(t)).collect()
+    }
+}
+
+/// The sense columns holding tags, in the order of the methods counting them.
+pub(crate) const TAG_COLUMNS: [&str; 4] = ["pos", "misc", "field", "dial"];
+
+/// Which senses a count was made for: the last sense id handed out and the number of
+/// senses. Storing an entry inserts senses and deleting one removes them, so either
+/// changes with every write.
+type SensesVersion = (i64, i64);
+
+/// The counts of `DictDb::distinct_pos` and the others by column, with the version of the
+/// senses they were counted on.
+pub(crate) type TagCountCache = RwLock<HashMap<&'static str, (SensesVersion, Vec<(String, i64)>)>>;
+
+impl DictDb {
+    /// Every part of speech of the senses in the main database with the number of senses
+    /// having it, most frequent first, then by tag, e.g. for a filter dropdown. Tags are
+    /// the entity values of the DTD ("Ichidan verb"), inherited ones included, see
+    /// `SenseParsed::pos`.
+    ///
+    /// Counting reads every sense, so the counts are kept until the senses change, by
+    /// this or any other connection.
+    pub async fn distinct_pos(&self) -> Result<Vec<(String, i64)>> {
+        self.distinct_tags(TAG_COLUMNS[0]).await
+    }
+
+    /// The misc tags, e.g. "word usually written using kana alone", see `distinct_pos`.
+    pub async fn distinct_misc(&self) -> Result<Vec<(String, i64)>> {
+        self.distinct_tags(TAG_COLUMNS[1]).await
+    }
+
+    /// The fields of application, e.g. "computing", see `distinct_pos`.
+    pub async fn distinct_fields(&self) -> Result<Vec<(String, i64)>> {
+        self.distinct_tags(TAG_COLUMNS[2]).await
+    }
+
+    /// The dialects, e.g. "Kansai-ben", see `distinct_pos`.
+    pub async fn distinct_dialects(&self) -> Result<Vec<(String, i64)>> {
+        self.distinct_tags(TAG_COLUMNS[3]).await
+    }
+
+    /// The tags of one of the `TAG_COLUMNS` with their sense counts, from the cache while
+    /// the senses are the same.
+    async fn distinct_tags(&self, column: &'static str) -> Result<Vec<(String, i64)>> {
+        let version: SensesVersion = sqlx::query_as(
+            "SELECT COALESCE((SELECT seq FROM sqlite_sequence WHERE name = 'senses'), 0), (SELECT COUNT(*) FROM senses)",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if let Some((_, counts)) = self.tag_counts.read().unwrap().get(column).filter(|(cached, _)| *cached == version) {
+            return Ok(counts.clone());
+        }
+        // A sense listing a tag twice counts once
+        let counts: Vec<(String, i64)> = sqlx::query_as(&format!(
+            "SELECT je.value, COUNT(DISTINCT s.id) AS n FROM senses s, json_each(s.{}) je
+             GROUP BY je.value ORDER BY n DESC, je.value",
+            column
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        self.tag_counts.write().unwrap().insert(column, (version, counts.clone()));
+        Ok(counts)
+    }
+}
@@ -0,0 +1,310 @@
+use futures_util::TryStreamExt;
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::ranking::priority_score;
+use crate::{fold_case, DictDb, Result};
+
+/// A word reference from a Tanaka Corpus B line, e.g. `事(こと)[01]{こと}~`.
+///
+/// - `headword` the dictionary form, a keb or reb
+/// - `(reading)` the reading, given when the headword is ambiguous
+/// - `[NN]` the 1-based sense number the word is used in
+/// - `{form}` the form as it appears in the sentence, when it differs from the headword
+/// - `~` marks the sentence as a good example for this word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TanakaWord {
+    pub headword: String,
+    pub reading: Option<String>,
+    /// The 1-based sense number, as written in the corpus.
+    pub sense: Option<u32>,
+    pub surface: Option<String>,
+    pub checked: bool,
+}
+
+impl FromStr for TanakaWord {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut word = TanakaWord {
+            headword: String::new(),
+            reading: None,
+            sense: None,
+            surface: None,
+            checked: false,
+        };
+
+        let mut chars = s.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if matches!(c, '(' | '[' | '{' | '~') {
+                break;
+            }
+            word.headword.push(c);
+            chars.next();
+        }
+        if word.headword.is_empty() {
+            return Err(format!("missing headword in {:?}", s));
+        }
+
+        // Reads up to the closing delimiter
+        let group = |close: char, chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut out = String::new();
+            for c in chars.by_ref() {
+                if c == close {
+                    return Ok(out);
+                }
+                out.push(c);
+            }
+            Err(format!("unclosed {:?} in {:?}", close, s))
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '(' => word.reading = Some(group(')', &mut chars)?),
+                '[' => {
+                    let n = group(']', &mut chars)?;
+                    word.sense = Some(n.parse().map_err(|_| format!("invalid sense {:?} in {:?}", n, s))?);
+                }
+                '{' => word.surface = Some(group('}', &mut chars)?),
+                '~' => word.checked = true,
+                _ => return Err(format!("unexpected {:?} in {:?}", c, s)),
+            }
+        }
+        Ok(word)
+    }
+}
+
+/// Parses the word references of a B line (with or without the leading `B: `).
+pub fn parse_tanaka_b_line(line: &str) -> std::result::Result<Vec<TanakaWord>, String> {
+    let line = line.trim();
+    let line = line.strip_prefix("B:").unwrap_or(line);
+    line.split_whitespace().map(TanakaWord::from_str).collect()
+}
+
+/// Parses an A line into (japanese, english, sentence id).
+fn parse_a_line(line: &str) -> Option<(String, String, Option<String>)> {
+    let rest = line.strip_prefix("A:")?.trim();
+    let (japanese, english) = rest.split_once('\t')?;
+    let (english, id) = match english.split_once("#ID=") {
+        Some((en, id)) => (en, Some(id.trim().to_string())),
+        None => (english, None),
+    };
+    Some((japanese.trim().to_string(), english.trim().to_string(), id))
+}
+
+/// A Japanese/English sentence pair from the Tanaka Corpus.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct ExampleSentence {
+    pub id: i64,
+    pub japanese: String,
+    pub english: String,
+}
+
+/// The outcome of loading the Tanaka Corpus.
+#[derive(Debug, Clone, Default)]
+pub struct TanakaLoadReport {
+    pub sentences: usize,
+    pub words: usize,
+    /// Word references that were linked to an entry.
+    pub words_linked: usize,
+    /// Lines that could not be parsed, as (line number, error).
+    pub errors: Vec<(usize, String)>,
+}
+
+#[derive(Debug, FromRow)]
+struct WordMatch {
+    word_id: i64,
+    ent_seq: i64,
+    priority: String,
+}
+
+impl DictDb {
+    /// Loads the Tanaka Corpus (examples.utf) from a file, see `load_tanaka_corpus_reader`.
+    pub async fn load_tanaka_corpus(&self, path: impl AsRef<Path>) -> Result<TanakaLoadReport> {
+        let file = File::open(path)?;
+        self.load_tanaka_corpus_reader(BufReader::new(file)).await
+    }
+
+    /// Loads the Tanaka Corpus, replacing any previously loaded sentences.
+    ///
+    /// Sentences come from the A lines, the B line that follows each A line lists the words
+    /// used in it. Each word is linked to the entry that has the headword as a keb or reb
+    /// (and the reading as a reb, if given); the most common entry wins when several match.
+    /// Lines that fail to parse are skipped and listed in the report.
+    pub async fn load_tanaka_corpus_reader<R: BufRead>(&self, reader: R) -> Result<TanakaLoadReport> {
+        let mut report = TanakaLoadReport::default();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM sentence_words").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM sentences").execute(&mut *tx).await?;
+
+        let mut current: Option<i64> = None;
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.starts_with("A:") {
+                let Some((japanese, english, tatoeba_id)) = parse_a_line(&line) else {
+                    report.errors.push((line_no + 1, "malformed A line".to_string()));
+                    current = None;
+                    continue;
+                };
+                let id = sqlx::query("INSERT INTO sentences (japanese, english, tatoeba_id) VALUES (?1, ?2, ?3)")
+                    .bind(japanese)
+                    .bind(english)
+                    .bind(tatoeba_id)
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_rowid();
+                current = Some(id);
+                report.sentences += 1;
+            } else if line.starts_with("B:") {
+                let Some(sentence_id) = current.take() else {
+                    report.errors.push((line_no + 1, "B line without A line".to_string()));
+                    continue;
+                };
+                let words = match parse_tanaka_b_line(&line) {
+                    Ok(words) => words,
+                    Err(err) => {
+                        report.errors.push((line_no + 1, err));
+                        continue;
+                    }
+                };
+                for (position, w) in words.iter().enumerate() {
+                    sqlx::query(
+                        r#"
+                        INSERT INTO sentence_words (sentence_id, position, headword, reading, sense, surface, checked)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                        "#,
+                    )
+                    .bind(sentence_id)
+                    .bind(position as i64)
+                    .bind(&w.headword)
+                    .bind(&w.reading)
+                    .bind(w.sense.map(|s| s as i64))
+                    .bind(&w.surface)
+                    .bind(w.checked)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                report.words += words.len();
+            }
+        }
+
+        // Link words to entries
+        let matches = sqlx::query_as::<_, WordMatch>(
+            r#"
+            SELECT DISTINCT w.id AS word_id, e.ent_seq, e.priority
+            FROM (
+                SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.rebs) je
+                UNION ALL
+                SELECT e.ent_seq, je.value AS form FROM entries e, json_each(e.kebs) je
+                WHERE e.kebs IS NOT NULL
+            ) f
+            JOIN sentence_words w ON w.headword = f.form
+            JOIN entries e ON e.ent_seq = f.ent_seq
+            WHERE w.reading IS NULL
+               OR EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE r.value = w.reading)
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // word id -> (ent_seq, priority score) of the best match
+        let mut best: BTreeMap<i64, (i64, i64)> = BTreeMap::new();
+        for m in matches {
+            let priority: Vec<String> = serde_json::from_str(&m.priority)?;
+            let candidate = (m.ent_seq, priority_score(&priority));
+            best.entry(m.word_id)
+                .and_modify(|b| {
+                    if candidate.1 > b.1 || (candidate.1 == b.1 && candidate.0 < b.0) {
+                        *b = candidate;
+                    }
+                })
+                .or_insert(candidate);
+        }
+        for (word_id, (ent_seq, _)) in &best {
+            sqlx::query("UPDATE sentence_words SET ent_seq = ?1 WHERE id = ?2")
+                .bind(ent_seq)
+                .bind(word_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        report.words_linked = best.len();
+
+        tx.commit().await?;
+        Ok(report)
+    }
+
+    /// Returns example sentences using the given entry, sentences marked as good examples first.
+    ///
+    /// # Arguments
+    /// * `ent_seq` - The entry sequence number.
+    /// * `limit` - The maximum number of sentences.
+    pub async fn example_sentences(&self, ent_seq: i64, limit: usize) -> Result<Vec<ExampleSentence>> {
+        let sentences = sqlx::query_as::<_, ExampleSentence>(
+            r#"
+            SELECT s.id, s.japanese, s.english
+            FROM sentences s
+            JOIN sentence_words w ON w.sentence_id = s.id
+            WHERE w.ent_seq = ?1
+            GROUP BY s.id
+            ORDER BY MAX(w.checked) DESC, s.id
+            LIMIT ?2
+            "#,
+        )
+        .bind(ent_seq)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(sentences)
+    }
+
+    /// Searches the Japanese and English sides of the example sentences for a substring.
+    ///
+    /// # Arguments
+    /// * `substring` - The text to look for.
+    /// * `limit` - The maximum number of sentences.
+    ///
+    /// Letter case is ignored as by LIKE, for all of Unicode (CAFÉ finds café) with the ICU
+    /// extension; without it such queries are compared after `fold_case`, reading every
+    /// sentence. Shorter substrings than the `QueryGuards` allow are
+    /// `DictError::QueryTooShort`.
+    pub async fn search_sentences(&self, substring: &str, limit: usize) -> Result<Vec<ExampleSentence>> {
+        let substring = self.substring_query(substring)?;
+        if self.fold_sentence_case && substring.chars().any(|c| !c.is_ascii() && (c.is_uppercase() || c.is_lowercase())) {
+            return self.search_sentences_folded(substring, limit).await;
+        }
+        let pattern = format!("%{}%", crate::ranking::escape_like(substring));
+        let sentences = sqlx::query_as::<_, ExampleSentence>(
+            r#"
+            SELECT id, japanese, english FROM sentences
+            WHERE japanese LIKE ?1 ESCAPE '\' OR english LIKE ?1 ESCAPE '\'
+            ORDER BY id
+            LIMIT ?2
+            "#,
+        )
+        .bind(pattern)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(sentences)
+    }
+
+    /// `search_sentences` comparing case-folded text in Rust, for queries with non-ASCII
+    /// letters when LIKE only folds ASCII.
+    async fn search_sentences_folded(&self, substring: &str, limit: usize) -> Result<Vec<ExampleSentence>> {
+        let needle = fold_case(substring);
+        let mut rows = sqlx::query_as::<_, ExampleSentence>("SELECT id, japanese, english FROM sentences ORDER BY id").fetch(&self.pool);
+        let mut sentences = Vec::new();
+        while sentences.len() < limit {
+            let Some(sentence) = rows.try_next().await? else { break };
+            if fold_case(&sentence.japanese).contains(&needle) || fold_case(&sentence.english).contains(&needle) {
+                sentences.push(sentence);
+            }
+        }
+        Ok(sentences)
+    }
+}
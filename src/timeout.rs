@@ -0,0 +1,274 @@
+use futures_util::future::BoxFuture;
+use sqlx::SqliteConnection;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::{DictDb, DictError, Result};
+
+/// SQLite virtual machine instructions between two deadline checks, a few microseconds.
+const PROGRESS_OPS: i32 = 1000;
+
+/// SQLite's result code for a statement stopped by the progress handler.
+const SQLITE_INTERRUPT: i64 = 9;
+
+/// Per-call settings for `DictDb::with_query_options`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryOptions {
+    /// Stop the SQLite statements of the call once this much time has passed since it started.
+    pub timeout: Option<Duration>,
+    /// Return at most this many senses per entry, the first ones; see
+    /// `EntryParsed::senses_truncated`.
+    pub max_senses: Option<usize>,
+    /// Return at most this many glosses per sense and language, the first ones.
+    pub max_glosses_per_sense: Option<usize>,
+    /// Load the senses of up to this many matched entries at once, each on a connection of
+    /// the pool; `DEFAULT_HYDRATION_CONCURRENCY` if None, 1 loads them one after another.
+    pub hydration_concurrency: Option<usize>,
+    /// Fill `SenseParsed::borrowed` of the senses that only have a cross-reference with
+    /// the glosses of the sense it points to.
+    pub resolve_references: bool,
+}
+
+impl QueryOptions {
+    /// Options with a timeout.
+    pub fn timeout(timeout: Duration) -> Self {
+        Self { timeout: Some(timeout), ..Self::default() }
+    }
+
+    fn limits(&self) -> SenseLimits {
+        SenseLimits {
+            max_senses: self.max_senses,
+            max_glosses_per_sense: self.max_glosses_per_sense,
+            hydration_concurrency: self.hydration_concurrency,
+            resolve_references: self.resolve_references,
+        }
+    }
+}
+
+/// How many entries `hydrate_entries` loads at once outside `with_query_options`, or with
+/// `QueryOptions::hydration_concurrency` unset.
+pub const DEFAULT_HYDRATION_CONCURRENCY: usize = 4;
+
+/// The sense and gloss limits of the `with_query_options` call being polled, read when
+/// entries are hydrated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct SenseLimits {
+    pub max_senses: Option<usize>,
+    pub max_glosses_per_sense: Option<usize>,
+    pub hydration_concurrency: Option<usize>,
+    pub resolve_references: bool,
+}
+
+impl SenseLimits {
+    /// The limits of the current call, none outside `with_query_options`.
+    pub(crate) fn current() -> Self {
+        LIMITS.with(|l| l.get())
+    }
+
+    /// Whether entries are hydrated as stored, without sense or gloss limits or borrowed
+    /// glosses.
+    pub(crate) fn whole_entries(&self) -> bool {
+        self.max_senses.is_none() && self.max_glosses_per_sense.is_none() && !self.resolve_references
+    }
+
+    /// How many entries to hydrate at once, at least 1.
+    pub(crate) fn concurrency(&self) -> usize {
+        self.hydration_concurrency.unwrap_or(DEFAULT_HYDRATION_CONCURRENCY).max(1)
+    }
+}
+
+/// The deadline of one `with_query_options` call, shared with the progress handlers of the
+/// connections it used.
+#[derive(Debug)]
+struct Deadline {
+    at: Instant,
+    /// Set when the call returned, its handlers then never interrupt.
+    finished: AtomicBool,
+    state: Mutex<DeadlineState>,
+}
+
+#[derive(Debug, Default)]
+struct DeadlineState {
+    /// The call was dropped before it returned.
+    abandoned: bool,
+    /// Progress handlers still installed for this deadline.
+    handlers: usize,
+}
+
+/// Installed handlers of abandoned calls. Their statements are stopped at the deadline, but
+/// afterwards the handlers would stop other statements, so connections acquired outside a
+/// call remove them while this is not 0.
+static STALE_HANDLERS: AtomicUsize = AtomicUsize::new(0);
+
+impl Deadline {
+    fn expired(&self) -> bool {
+        !self.finished.load(Ordering::Relaxed) && Instant::now() >= self.at
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DeadlineState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Keeps the handler count of a deadline, owned by the progress handler closure; SQLite drops
+/// the closure when the handler is replaced or removed, or the connection closes.
+struct HandlerToken(Arc<Deadline>);
+
+impl HandlerToken {
+    fn new(deadline: Arc<Deadline>) -> Self {
+        deadline.lock().handlers += 1;
+        Self(deadline)
+    }
+}
+
+impl Drop for HandlerToken {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.handlers -= 1;
+        if state.abandoned {
+            STALE_HANDLERS.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+thread_local! {
+    /// The deadline of the call being polled on this thread, if any.
+    static CURRENT: RefCell<Option<Arc<Deadline>>> = const { RefCell::new(None) };
+    /// The sense limits of the call being polled on this thread.
+    static LIMITS: Cell<SenseLimits> = const { Cell::new(SenseLimits { max_senses: None, max_glosses_per_sense: None, hydration_concurrency: None, resolve_references: false }) };
+}
+
+/// Makes `deadline` and `limits` the current ones while `inner` is polled, so the
+/// connections it acquires get armed by `arm_connection` and the entries it hydrates are
+/// cut to the limits.
+struct WithOptions<F> {
+    deadline: Option<Arc<Deadline>>,
+    limits: SenseLimits,
+    inner: Pin<Box<F>>,
+    done: bool,
+}
+
+impl<F: Future> Future for WithOptions<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let previous = CURRENT.with(|c| c.replace(self.deadline.clone()));
+        let previous_limits = LIMITS.with(|l| l.replace(self.limits));
+        let poll = self.inner.as_mut().poll(cx);
+        CURRENT.with(|c| *c.borrow_mut() = previous);
+        LIMITS.with(|l| l.set(previous_limits));
+        if poll.is_ready() {
+            self.done = true;
+        }
+        poll
+    }
+}
+
+impl<F> Drop for WithOptions<F> {
+    fn drop(&mut self) {
+        let Some(deadline) = &self.deadline else {
+            return;
+        };
+        if self.done {
+            deadline.finished.store(true, Ordering::Relaxed);
+            return;
+        }
+        // A statement may still be running, leave its handler armed until someone removes it
+        let mut state = deadline.lock();
+        state.abandoned = true;
+        STALE_HANDLERS.fetch_add(state.handlers, Ordering::Relaxed);
+    }
+}
+
+/// The pool's `after_connect` and `before_acquire` hook: inside `with_query_options`, installs
+/// a progress handler that interrupts the connection's statements past the call's deadline.
+/// Outside, removes the handler an abandoned call may have left.
+pub(crate) fn arm_connection(conn: &mut SqliteConnection) -> BoxFuture<'_, std::result::Result<(), sqlx::Error>> {
+    // Read now, the hook is called while the acquiring future is polled
+    let deadline = CURRENT.with(|c| c.borrow().clone());
+    Box::pin(async move {
+        match deadline {
+            Some(deadline) => {
+                let token = HandlerToken::new(deadline);
+                conn.lock_handle().await?.set_progress_handler(PROGRESS_OPS, move || !token.0.expired());
+            }
+            None if STALE_HANDLERS.load(Ordering::Relaxed) > 0 => conn.lock_handle().await?.remove_progress_handler(),
+            None => {}
+        }
+        Ok(())
+    })
+}
+
+/// Returns true if the error is a statement interrupted by the progress handler.
+fn is_interrupt(err: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    match err.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::Database(db_err)) => db_err
+            .code()
+            .and_then(|code| code.parse::<i64>().ok())
+            .is_some_and(|code| code & 0xff == SQLITE_INTERRUPT),
+        _ => false,
+    }
+}
+
+impl DictDb {
+    /// Runs a search of this database with per-call options, e.g. a timeout for a substring
+    /// search the user may abandon:
+    ///
+    /// ```no_run
+    /// # async fn demo(db: &jmdictdb::DictDb) -> jmdictdb::Result<()> {
+    /// use jmdictdb::QueryOptions;
+    /// use std::time::Duration;
+    ///
+    /// let opts = QueryOptions::timeout(Duration::from_millis(200));
+    /// let sentences = db.with_query_options(&opts, db.search_sentences("ている", 50)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Statements still running at the deadline are interrupted by SQLite itself and the call
+    /// returns `DictError::QueryTimeout`; their connections go back to the pool ready for the
+    /// next query. Dropping the future instead, e.g. when the user keeps typing, doesn't stop
+    /// a statement already running at once: it goes on on its connection thread until it is
+    /// done or the deadline passes, whichever is first, then the connection is returned.
+    ///
+    /// With `max_senses` or `max_glosses_per_sense` every entry the search returns is cut
+    /// to its first senses and glosses, e.g. for a list view that shows one sense per
+    /// word: the senses are read with a LIMIT, the glosses cut as they are parsed. Fetch
+    /// the entry again with `entry_by_seq` outside the call for all of it. Searches that
+    /// pick senses after reading them, e.g. by `stagk`, pick among the first ones only.
+    ///
+    /// `hydration_concurrency` sets how many of the matched entries get their senses loaded
+    /// at once, e.g. 1 on a pool other callers need too; the entries keep their order.
+    ///
+    /// `resolve_references` looks up the target of each sense that only has a cross-reference
+    /// and puts its glosses in `SenseParsed::borrowed`, one query per such sense.
+    pub async fn with_query_options<T, F>(&self, opts: &QueryOptions, search: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let limits = opts.limits();
+        let Some(timeout) = opts.timeout else {
+            if limits == SenseLimits::default() {
+                return search.await;
+            }
+            return WithOptions { deadline: None, limits, inner: Box::pin(search), done: false }.await;
+        };
+        let deadline = Arc::new(Deadline {
+            at: Instant::now() + timeout,
+            finished: AtomicBool::new(false),
+            state: Mutex::default(),
+        });
+        let result = WithOptions { deadline: Some(deadline.clone()), limits, inner: Box::pin(search), done: false }.await;
+        match result {
+            Err(err) if is_interrupt(err.as_ref()) && Instant::now() >= deadline.at => {
+                Err(DictError::QueryTimeout(timeout).into())
+            }
+            result => result,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use sqlx::{Sqlite, Transaction};
+use std::sync::Arc;
+
+use crate::guards::non_empty;
+use crate::dict_db::{mark_applicable, SEARCH_BY_KANJI_SQL, SEARCH_BY_READING_SQL};
+use crate::entry_cache::EntryCache;
+use crate::{CustomEntry, DictDb, Entry, EntryParsed, EntryRow, Result, SOURCE_JMDICT};
+
+/// A database transaction for composing several writes atomically.
+///
+/// Nothing is visible to other connections until `commit`; dropping a DictTx without
+/// committing rolls everything back. Reads on the transaction see its own writes.
+pub struct DictTx {
+    tx: Transaction<'static, Sqlite>,
+    /// The entry cache of the DictDb, cleared on commit.
+    entry_cache: Arc<EntryCache>,
+}
+
+impl DictDb {
+    /// Starts a transaction, see `DictTx`.
+    pub async fn begin(&self) -> Result<DictTx> {
+        Ok(DictTx { tx: self.pool.begin().await?, entry_cache: self.entry_cache.clone() })
+    }
+}
+
+impl DictTx {
+    /// Makes the writes of the transaction permanent.
+    pub async fn commit(self) -> Result<()> {
+        self.tx.commit().await?;
+        self.entry_cache.clear();
+        Ok(())
+    }
+
+    /// Discards the writes of the transaction.
+    pub async fn rollback(self) -> Result<()> {
+        self.tx.rollback().await?;
+        Ok(())
+    }
+
+    /// See `DictDb::upsert_entry`.
+    pub async fn upsert_entry(&mut self, entry: &Entry) -> Result<bool> {
+        DictDb::upsert_entry_tx(&mut self.tx, entry, SOURCE_JMDICT).await
+    }
+
+    /// See `DictDb::delete_entry`.
+    pub async fn delete_entry(&mut self, ent_seq: i64) -> Result<bool> {
+        DictDb::delete_entry_tx(&mut self.tx, ent_seq).await
+    }
+
+    /// See `DictDb::delete_entries`.
+    pub async fn delete_entries(&mut self, ent_seqs: &[i64]) -> Result<usize> {
+        let mut deleted = 0;
+        for &ent_seq in ent_seqs {
+            if DictDb::delete_entry_tx(&mut self.tx, ent_seq).await? {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// See `DictDb::insert_custom_entry`.
+    pub async fn insert_custom_entry(&mut self, entry: &CustomEntry) -> Result<i64> {
+        DictDb::insert_custom_entry_tx(&mut self.tx, entry).await
+    }
+
+    /// See `DictDb::delete_custom_entry`.
+    pub async fn delete_custom_entry(&mut self, ent_seq: i64) -> Result<bool> {
+        DictDb::delete_custom_entry_tx(&mut self.tx, ent_seq).await
+    }
+
+    /// See `DictDb::create_list`.
+    #[cfg(feature = "userdata")]
+    pub async fn create_list(&mut self, name: &str) -> Result<i64> {
+        DictDb::create_list_tx(&mut self.tx, name).await
+    }
+
+    /// See `DictDb::add_to_list`.
+    #[cfg(feature = "userdata")]
+    pub async fn add_to_list(&mut self, list_id: i64, ent_seq: i64, note: Option<&str>) -> Result<()> {
+        DictDb::add_to_list_tx(&mut self.tx, list_id, ent_seq, note).await
+    }
+
+    /// See `DictDb::add_sense_to_list`.
+    #[cfg(feature = "userdata")]
+    pub async fn add_sense_to_list(&mut self, list_id: i64, sense_uid: &str, note: Option<&str>) -> Result<()> {
+        DictDb::add_sense_to_list_tx(&mut self.tx, list_id, sense_uid, note).await
+    }
+
+    /// See `DictDb::remove_from_list`.
+    #[cfg(feature = "userdata")]
+    pub async fn remove_from_list(&mut self, list_id: i64, ent_seq: i64) -> Result<bool> {
+        DictDb::remove_from_list_tx(&mut self.tx, list_id, ent_seq).await
+    }
+
+    /// Fetches a single entry by its ent_seq, including uncommitted changes.
+    pub async fn entry(&mut self, ent_seq: i64) -> Result<Option<EntryParsed>> {
+        let row = sqlx::query_as::<_, EntryRow>("SELECT * FROM entries WHERE ent_seq = ?")
+            .bind(ent_seq)
+            .fetch_optional(&mut *self.tx)
+            .await?;
+        match row {
+            Some(row) => Ok(Some(DictDb::hydrate_entry_with(&mut self.tx, row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// See `DictDb::search_entries_with_senses_by_reading`, including uncommitted changes.
+    pub async fn search_entries_with_senses_by_reading(&mut self, reading: &str) -> Result<Vec<EntryParsed>> {
+        non_empty(reading)?;
+        let entry_rows = sqlx::query_as::<_, EntryRow>(SEARCH_BY_READING_SQL)
+            .bind(reading)
+            .fetch_all(&mut *self.tx)
+            .await?;
+        let mut entries = DictDb::hydrate_entries_with(&mut self.tx, entry_rows).await?;
+        mark_applicable(&mut entries, None, Some(reading));
+        Ok(entries)
+    }
+
+    /// See `DictDb::search_entries_with_senses_by_kanji`, including uncommitted changes.
+    pub async fn search_entries_with_senses_by_kanji(&mut self, kanji: &str) -> Result<Vec<EntryParsed>> {
+        non_empty(kanji)?;
+        let entry_rows = sqlx::query_as::<_, EntryRow>(SEARCH_BY_KANJI_SQL)
+            .bind(kanji)
+            .fetch_all(&mut *self.tx)
+            .await?;
+        let mut entries = DictDb::hydrate_entries_with(&mut self.tx, entry_rows).await?;
+        mark_applicable(&mut entries, Some(kanji), None);
+        Ok(entries)
+    }
+}
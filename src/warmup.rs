@@ -0,0 +1,85 @@
+use std::time::Instant;
+
+use crate::{DictDb, PhaseDuration, Result, SuggestIndexStats};
+
+/// The b-trees the searches read, as (table, index), None for the table itself. Counting
+/// the rows of each reads all of its pages.
+const HOT_BTREES: &[(&str, Option<&str>)] = &[
+    ("entries", None),
+    ("entries", Some("idx_entries_nf_bucket")),
+    ("senses", None),
+    ("senses", Some("idx_senses_entry")),
+    ("glosses", Some("idx_glosses_sense")),
+    ("glosses", Some("idx_glosses_lang_folded")),
+    ("glosses", Some("idx_glosses_lang_norm")),
+    ("kanji_index", None),
+    ("kanji_index", Some("idx_kanji_index_entry")),
+    ("reading_index", None),
+    ("reading_index", Some("idx_reading_index_entry")),
+    ("punct_index", None),
+];
+
+/// Options for `DictDb::warmup`.
+#[derive(Debug, Clone, Default)]
+pub struct WarmupOptions {
+    /// Also build the in-memory index of `suggest_fast`, see `DictDb::build_suggest_index`.
+    pub suggest_index: bool,
+}
+
+/// What `DictDb::warmup` did and how long it took.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmupReport {
+    /// One phase per table or index read, e.g. "entries" or "idx_senses_entry", and
+    /// "suggest_index" if it was built, in the order they ran.
+    pub durations: Vec<PhaseDuration>,
+    /// The suggestion index, if it was built.
+    pub suggest_index: Option<SuggestIndexStats>,
+    /// How long the whole warmup took.
+    pub total_millis: u64,
+}
+
+impl DictDb {
+    /// Reads the tables and indexes the searches use, so the first lookups don't wait for
+    /// the disk, e.g. behind a splash screen.
+    ///
+    /// Every page of them goes through the OS page cache, shared by all connections (and the
+    /// `mmap_size` mapping, see `DictDbOptions::mmap_size`). These are plain reads, so searches
+    /// running at the same time only share the disk with it, and nothing needs it to have
+    /// run. Indexes missing from older databases are skipped.
+    pub async fn warmup(&self, opts: &WarmupOptions) -> Result<WarmupReport> {
+        let started = Instant::now();
+        let mut report = WarmupReport { durations: Vec::new(), suggest_index: None, total_millis: 0 };
+        for &(table, index) in HOT_BTREES {
+            let name = index.unwrap_or(table);
+            let kind = if index.is_some() { "index" } else { "table" };
+            let exists: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = ?1 AND name = ?2")
+                .bind(kind)
+                .bind(name)
+                .fetch_one(&self.pool)
+                .await?;
+            if !exists {
+                continue;
+            }
+            let phase = Instant::now();
+            let sql = match index {
+                Some(index) => format!("SELECT COUNT(*) FROM {} INDEXED BY {}", table, index),
+                None => format!("SELECT COUNT(*) FROM {} NOT INDEXED", table),
+            };
+            sqlx::query_scalar::<_, i64>(&sql).fetch_one(&self.pool).await?;
+            report.push(name, phase);
+        }
+        if opts.suggest_index {
+            let phase = Instant::now();
+            report.suggest_index = Some(self.build_suggest_index().await?);
+            report.push("suggest_index", phase);
+        }
+        report.total_millis = started.elapsed().as_millis() as u64;
+        Ok(report)
+    }
+}
+
+impl WarmupReport {
+    fn push(&mut self, phase: &str, started: Instant) {
+        self.durations.push(PhaseDuration { phase: phase.to_string(), millis: started.elapsed().as_millis() as u64 });
+    }
+}
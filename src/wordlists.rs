@@ -0,0 +1,254 @@
+use sqlx::{FromRow, Sqlite, Transaction};
+
+use crate::{DictDb, EntryParsed, Result, SenseParsed};
+
+/// A named word list, e.g. "saved words" or a flashcard deck.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct WordList {
+    pub id: i64,
+    pub name: String,
+    /// UTC, formatted as "YYYY-MM-DD HH:MM:SS".
+    pub created_at: String,
+}
+
+/// An item of a word list as stored, without the entry itself.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct WordListItem {
+    pub ent_seq: i64,
+    /// The sense the item is about, see `DictDb::add_sense_to_list`; None for the whole entry.
+    pub sense_uid: Option<String>,
+    pub note: Option<String>,
+    /// UTC, formatted as "YYYY-MM-DD HH:MM:SS".
+    pub added_at: String,
+}
+
+/// An entry of a word list together with the user's note.
+#[derive(Debug)]
+pub struct WordListEntry {
+    pub entry: EntryParsed,
+    /// The sense of the item, None for whole-entry items and for senses the entry no longer
+    /// has under their uid, see `DictDb::reconcile_sense_uid`.
+    pub sense: Option<SenseParsed>,
+    pub note: Option<String>,
+    /// UTC, formatted as "YYYY-MM-DD HH:MM:SS".
+    pub added_at: String,
+}
+
+impl DictDb {
+    /// Creates an empty word list and returns its id. Names must be unique.
+    pub async fn create_list(&self, name: &str) -> Result<i64> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let id = Self::create_list_tx(&mut tx, name).await?;
+            tx.commit().await?;
+            Ok(id)
+        })
+        .await
+    }
+
+    /// Returns all word lists, ordered by id.
+    pub async fn wordlists(&self) -> Result<Vec<WordList>> {
+        let lists = sqlx::query_as::<_, WordList>("SELECT id, name, created_at FROM wordlists ORDER BY id")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(lists)
+    }
+
+    /// Deletes a word list and its items.
+    ///
+    /// # Returns
+    /// false if there is no list with this id.
+    pub async fn delete_list(&self, list_id: i64) -> Result<bool> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query("DELETE FROM wordlist_items WHERE list_id = ?1")
+                .bind(list_id)
+                .execute(&mut *tx)
+                .await?;
+            let deleted = sqlx::query("DELETE FROM wordlists WHERE id = ?1")
+                .bind(list_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            tx.commit().await?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+
+    /// Adds an entry to a word list. Adding an entry that is already in the list replaces its note
+    /// and makes it a whole-entry item again.
+    ///
+    /// # Arguments
+    /// * `list_id` - The id returned by `create_list`.
+    /// * `ent_seq` - The entry to add.
+    /// * `note` - An optional note, e.g. where the word was seen.
+    pub async fn add_to_list(&self, list_id: i64, ent_seq: i64, note: Option<&str>) -> Result<()> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            Self::add_to_list_tx(&mut tx, list_id, ent_seq, note).await?;
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Adds one sense of an entry to a word list, by its `SenseParsed::sense_uid`, e.g. the
+    /// one meaning of 見る the user studies. The item is keyed on the entry like the others,
+    /// so this replaces the entry's item in the list, sense and note.
+    pub async fn add_sense_to_list(&self, list_id: i64, sense_uid: &str, note: Option<&str>) -> Result<()> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            Self::add_sense_to_list_tx(&mut tx, list_id, sense_uid, note).await?;
+            tx.commit().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Removes an entry from a word list.
+    ///
+    /// # Returns
+    /// false if the entry was not in the list.
+    pub async fn remove_from_list(&self, list_id: i64, ent_seq: i64) -> Result<bool> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let removed = Self::remove_from_list_tx(&mut tx, list_id, ent_seq).await?;
+            tx.commit().await?;
+            Ok(removed)
+        })
+        .await
+    }
+
+    /// Returns the stored items of a word list in the order they were added.
+    pub async fn list_items(&self, list_id: i64) -> Result<Vec<WordListItem>> {
+        let items = sqlx::query_as::<_, WordListItem>(
+            "SELECT ent_seq, sense_uid, note, added_at FROM wordlist_items WHERE list_id = ?1 ORDER BY added_at, rowid",
+        )
+        .bind(list_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(items)
+    }
+
+    /// Returns the entries of a word list with their notes, in the order they were added.
+    ///
+    /// Items are keyed on ent_seq only, so they follow the entry through dictionary updates;
+    /// items whose entry was removed from JMdict are kept but skipped here.
+    pub async fn list_entries(&self, list_id: i64) -> Result<Vec<WordListEntry>> {
+        let mut results = Vec::new();
+        for item in self.list_items(list_id).await? {
+            if let Some(entry) = self.entry_by_seq(item.ent_seq).await? {
+                let sense = item
+                    .sense_uid
+                    .as_ref()
+                    .and_then(|uid| entry.senses.iter().find(|s| s.sense_uid.as_ref() == Some(uid)).cloned());
+                results.push(WordListEntry { entry, sense, note: item.note, added_at: item.added_at });
+            }
+        }
+        Ok(results)
+    }
+
+    pub(crate) async fn create_list_tx(tx: &mut Transaction<'_, Sqlite>, name: &str) -> Result<i64> {
+        let id = sqlx::query("INSERT INTO wordlists (name) VALUES (?1)")
+            .bind(name)
+            .execute(&mut **tx)
+            .await?
+            .last_insert_rowid();
+        Ok(id)
+    }
+
+    pub(crate) async fn add_to_list_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        list_id: i64,
+        ent_seq: i64,
+        note: Option<&str>,
+    ) -> Result<()> {
+        let list_exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM wordlists WHERE id = ?1)")
+            .bind(list_id)
+            .fetch_one(&mut **tx)
+            .await?;
+        if !list_exists {
+            return Err(format!("no word list with id {}", list_id).into());
+        }
+        let entry_exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM entries WHERE ent_seq = ?1)")
+            .bind(ent_seq)
+            .fetch_one(&mut **tx)
+            .await?;
+        if !entry_exists {
+            return Err(format!("no entry with ent_seq {}", ent_seq).into());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO wordlist_items (list_id, ent_seq, note) VALUES (?1, ?2, ?3)
+            ON CONFLICT(list_id, ent_seq) DO UPDATE SET note = excluded.note, sense_uid = NULL
+            "#,
+        )
+        .bind(list_id)
+        .bind(ent_seq)
+        .bind(note)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    pub(crate) async fn add_sense_to_list_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        list_id: i64,
+        sense_uid: &str,
+        note: Option<&str>,
+    ) -> Result<()> {
+        let ent_seq: Option<i64> = sqlx::query_scalar("SELECT ent_seq FROM senses WHERE sense_uid = ?1")
+            .bind(sense_uid)
+            .fetch_optional(&mut **tx)
+            .await?;
+        let ent_seq = ent_seq.ok_or_else(|| format!("no sense with uid {}", sense_uid))?;
+        Self::add_to_list_tx(tx, list_id, ent_seq, note).await?;
+        sqlx::query("UPDATE wordlist_items SET sense_uid = ?3 WHERE list_id = ?1 AND ent_seq = ?2")
+            .bind(list_id)
+            .bind(ent_seq)
+            .bind(sense_uid)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    pub(crate) async fn remove_from_list_tx(tx: &mut Transaction<'_, Sqlite>, list_id: i64, ent_seq: i64) -> Result<bool> {
+        let deleted = sqlx::query("DELETE FROM wordlist_items WHERE list_id = ?1 AND ent_seq = ?2")
+            .bind(list_id)
+            .bind(ent_seq)
+            .execute(&mut **tx)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+
+    /// Recreates a word list with its items as returned by `list_items`, keeping the timestamps.
+    ///
+    /// `build_db` uses this to carry the word lists over to a rebuilt database.
+    pub async fn restore_list(&self, list: &WordList, items: &[WordListItem]) -> Result<i64> {
+        self.with_retry(move || async move {
+            let mut tx = self.pool.begin().await?;
+            let id = sqlx::query("INSERT INTO wordlists (name, created_at) VALUES (?1, ?2)")
+                .bind(&list.name)
+                .bind(&list.created_at)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+            for item in items {
+                sqlx::query("INSERT INTO wordlist_items (list_id, ent_seq, sense_uid, note, added_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+                    .bind(id)
+                    .bind(item.ent_seq)
+                    .bind(&item.sense_uid)
+                    .bind(&item.note)
+                    .bind(&item.added_at)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            Ok(id)
+        })
+        .await
+    }
+}
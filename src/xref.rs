@@ -0,0 +1,226 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::kana::is_kana_str;
+use crate::{DictDb, DictError, EntryParsed, Result, SenseParsed, SUMMARY_ELLIPSIS};
+
+/// The separator between the parts of a JMdict cross-reference.
+pub const XREF_SEPARATOR: char = '・';
+
+/// The 0-based `sense_order` of the sense JMdict references as number `number`, counted
+/// from 1 ("見る・2" is the sense with sense_order 1). None for 0, which names no sense.
+pub fn sense_order_of(number: u32) -> Option<i64> {
+    number.checked_sub(1).map(i64::from)
+}
+
+/// The 1-based number JMdict references the sense with `sense_order` by, the inverse of
+/// `sense_order_of`. None for a negative sense_order.
+pub fn sense_number_of(sense_order: i64) -> Option<u32> {
+    u32::try_from(sense_order).ok()?.checked_add(1)
+}
+
+/// A cross-reference to another entry, optionally to one of its senses.
+///
+/// JMdict writes them as "keb・reb・sense" with the later parts optional, or starting with
+/// the reb for kana-only words: "御茶・おちゃ・2", "為る・する", "それ・1", "ＤＶＤ".
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct Xref {
+    /// The kanji form of the target, None if the target is referenced by reading only.
+    pub keb: Option<String>,
+    /// The reading of the target.
+    pub reb: Option<String>,
+    /// The 1-based sense number in the target entry, `sense_order_of` gives its sense_order.
+    pub sense: Option<u32>,
+}
+
+impl Xref {
+    /// The form the reference is written with: the keb if present, otherwise the reb.
+    pub fn headword(&self) -> &str {
+        self.keb.as_deref().or(self.reb.as_deref()).unwrap_or_default()
+    }
+
+    /// The reference as stored in JMdict, same as `to_string()`.
+    pub fn raw(&self) -> String {
+        self.to_string()
+    }
+
+    /// The `sense_order` of the referenced sense, see `sense_order_of`.
+    pub fn sense_order(&self) -> Option<i64> {
+        self.sense.and_then(sense_order_of)
+    }
+}
+
+/// The entry a cross-reference points to, see `DictDb::resolve_xref`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct XrefTarget {
+    pub entry: EntryParsed,
+    /// The referenced sense, None if the reference names none.
+    pub sense: Option<SenseParsed>,
+}
+
+/// The glosses a reference-only sense borrows from the sense its cross-reference points to,
+/// see `QueryOptions::resolve_references`; they are that sense's, not this one's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BorrowedGlosses {
+    /// The cross-reference the glosses were borrowed through.
+    pub from: Xref,
+    /// The entry lent them.
+    pub ent_seq: i64,
+    /// The sense lent them: the one the reference names, or the first.
+    pub sense_order: i64,
+    /// The English glosses of that sense.
+    pub gloss: Vec<String>,
+}
+
+impl BorrowedGlosses {
+    /// The first `max_glosses` glosses joined with "; ", like `SenseParsed::summary`.
+    pub fn summary(&self, max_glosses: usize) -> String {
+        let mut summary = self.gloss.iter().take(max_glosses).map(String::as_str).collect::<Vec<_>>().join("; ");
+        if self.gloss.len() > max_glosses {
+            if !summary.is_empty() {
+                summary.push_str("; ");
+            }
+            summary.push_str(SUMMARY_ELLIPSIS);
+        }
+        summary
+    }
+}
+
+/// The first entry with keb ?1 (if not NULL) and reb ?2 (if not NULL), as `resolve_xref`
+/// picks it, and the English glosses of its sense ?3.
+const BORROWED_GLOSSES_SQL: &str = r#"
+    SELECT s.ent_seq, s.gloss FROM senses s
+    WHERE s.sense_order = ?3 AND s.ent_seq = (
+        SELECT e.ent_seq FROM entries e
+        WHERE (?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(e.kebs) k WHERE k.value = ?1))
+          AND (?2 IS NULL OR EXISTS (SELECT 1 FROM json_each(e.rebs) r WHERE r.value = ?2))
+        ORDER BY e.ent_seq LIMIT 1
+    )
+"#;
+
+impl FromStr for Xref {
+    type Err = DictError;
+
+    /// Parses the one-, two- and three-part forms.
+    ///
+    /// A lone form is a reb if it is all kana and a keb otherwise. In two-part forms a
+    /// numeric second part is a sense number ("それ・1"), anything else is the reb ("為る・する").
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || DictError::InvalidXref(s.to_string());
+        // A keb (or reb, for kana-only words) without further parts
+        let form = |part: &str| {
+            if is_kana_str(part) {
+                (None, Some(part.to_string()))
+            } else {
+                (Some(part.to_string()), None)
+            }
+        };
+        let parts: Vec<&str> = s.trim().split(XREF_SEPARATOR).collect();
+        if parts.iter().any(|p| p.is_empty()) {
+            return Err(invalid());
+        }
+        let (keb, reb, sense) = match parts.as_slice() {
+            [first] => {
+                let (keb, reb) = form(first);
+                (keb, reb, None)
+            }
+            [first, second] => match second.parse::<u32>() {
+                Ok(sense) => {
+                    let (keb, reb) = form(first);
+                    (keb, reb, Some(sense))
+                }
+                Err(_) => (Some(first.to_string()), Some(second.to_string()), None),
+            },
+            [keb, reb, sense] => {
+                let sense = sense.parse::<u32>().map_err(|_| invalid())?;
+                (Some(keb.to_string()), Some(reb.to_string()), Some(sense))
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(Xref { keb, reb, sense })
+    }
+}
+
+impl fmt::Display for Xref {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts: Vec<String> = Vec::with_capacity(3);
+        parts.extend(self.keb.clone());
+        parts.extend(self.reb.clone());
+        parts.extend(self.sense.map(|n| n.to_string()));
+        write!(f, "{}", parts.join(&XREF_SEPARATOR.to_string()))
+    }
+}
+
+impl DictDb {
+    /// The sense with `sense_order` of the entry `ent_seq`. sense_order counts from 0 like
+    /// `SenseParsed::sense_order`; convert JMdict's 1-based sense numbers with
+    /// `sense_order_of`. None if there is no such entry or sense.
+    pub async fn get_sense(&self, ent_seq: i64, sense_order: i64) -> Result<Option<SenseParsed>> {
+        if sense_order < 0 {
+            return Ok(None);
+        }
+        Ok(self
+            .entry_by_seq(ent_seq)
+            .await?
+            .and_then(|entry| entry.senses.into_iter().find(|s| s.sense_order == sense_order)))
+    }
+
+    /// Fills `borrowed` for the reference-only senses of the entry, from the first of their
+    /// cross-references that points to a sense with English glosses. A sense pointing
+    /// through "sense 0" or at itself borrows nothing.
+    pub(crate) async fn borrow_glosses_with(conn: &mut SqliteConnection, entry: &mut EntryParsed) -> Result<()> {
+        for sense in entry.senses.iter_mut().filter(|s| s.is_reference_only()) {
+            for xref in &sense.xref {
+                let sense_order = match xref.sense {
+                    Some(number) => match sense_order_of(number) {
+                        Some(order) => order,
+                        None => continue,
+                    },
+                    None => 0,
+                };
+                let target: Option<(i64, String)> = sqlx::query_as(BORROWED_GLOSSES_SQL)
+                    .bind(&xref.keb)
+                    .bind(&xref.reb)
+                    .bind(sense_order)
+                    .fetch_optional(&mut *conn)
+                    .await?;
+                let Some((ent_seq, gloss)) = target else { continue };
+                let gloss: Vec<String> = serde_json::from_str(&gloss)?;
+                if gloss.is_empty() || (ent_seq, sense_order) == (entry.ent_seq, sense.sense_order) {
+                    continue;
+                }
+                sense.borrowed = Some(BorrowedGlosses { from: xref.clone(), ent_seq, sense_order, gloss });
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// The entry a cross-reference points to: the first entry with its keb and reb, and the
+    /// sense it names, through `get_sense`. None if there is no such entry, or the entry
+    /// has no sense with that number.
+    pub async fn resolve_xref(&self, xref: &Xref) -> Result<Option<XrefTarget>> {
+        let candidates = match (&xref.keb, &xref.reb) {
+            (Some(keb), _) => self.search_entries_with_senses_by_kanji(keb).await?,
+            (None, Some(reb)) => self.search_entries_with_senses_by_reading(reb).await?,
+            (None, None) => return Ok(None),
+        };
+        let reb_matches = |e: &EntryParsed| xref.reb.as_ref().is_none_or(|reb| e.rebs.contains(reb));
+        let Some(entry) = candidates.into_iter().find(reb_matches) else {
+            return Ok(None);
+        };
+        let sense = match xref.sense_order() {
+            Some(order) => match self.get_sense(entry.ent_seq, order).await? {
+                Some(sense) => Some(sense),
+                None => return Ok(None),
+            },
+            // Sense 0 names no sense
+            None if xref.sense.is_some() => return Ok(None),
+            None => None,
+        };
+        Ok(Some(XrefTarget { entry, sense }))
+    }
+}
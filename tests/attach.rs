@@ -0,0 +1,89 @@
+use jmdictdb::{CustomEntry, CustomSense, DictDb, DictError, Entry, REle, Sense, MAX_ATTACHED};
+use std::path::{Path, PathBuf};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jmdictdb-{}-{}.db", name, std::process::id()))
+}
+
+fn url(path: &Path) -> String {
+    format!("sqlite:{}", path.display())
+}
+
+async fn build_main(path: &Path) -> DictDb {
+    DictDb::reset_database(&url(path)).await.unwrap();
+    let db = DictDb::connect(&url(path)).await.unwrap();
+    let entry = Entry {
+        ent_seq: "1000".to_string(),
+        r_ele: vec![REle { reb: "ねこ".to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec!["cat".to_string()], ..Default::default() }],
+        ..Default::default()
+    };
+    db.insert_entries(&[entry]).await.unwrap();
+    db
+}
+
+async fn build_user(path: &Path) {
+    DictDb::reset_database(&url(path)).await.unwrap();
+    let db = DictDb::connect(&url(path)).await.unwrap();
+    let entry = CustomEntry {
+        rebs: vec!["ねこ".to_string()],
+        senses: vec![CustomSense { gloss: vec!["my cat's name".to_string()], ..Default::default() }],
+        ..Default::default()
+    };
+    db.insert_custom_entry(&entry).await.unwrap();
+    db.close().await;
+}
+
+#[tokio::test]
+async fn attached_entries_survive_a_rebuild_of_the_main_db() {
+    let main = temp_path("attach-main");
+    let user = temp_path("attach-user");
+    build_user(&user).await;
+
+    let db = build_main(&main).await;
+    let attachment = db.attach(&user, "user").await.unwrap();
+    let sources: Vec<String> = db.search_all_by_reading("ねこ").await.unwrap().into_iter().map(|e| e.source).collect();
+    assert_eq!(sources, ["jmdict", "user"]);
+    drop(attachment);
+    assert_eq!(db.search_all_by_reading("ねこ").await.unwrap().len(), 1);
+    db.close().await;
+
+    // Rebuild the main database, the user data lives in its own file
+    let db = build_main(&main).await;
+    let _attachment = db.attach(&user, "user").await.unwrap();
+    let results = db.search_all_by_reading("ねこ").await.unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[1].senses[0].gloss, ["my cat's name"]);
+    assert_eq!(db.search_entries_with_senses_by_reading("ねこ").await.unwrap().len(), 1);
+
+    db.close().await;
+    let _ = std::fs::remove_file(&main);
+    let _ = std::fs::remove_file(&user);
+}
+
+#[tokio::test]
+async fn attach_limits_are_typed_errors() {
+    let main = temp_path("limit-main");
+    let user = temp_path("limit-user");
+    build_user(&user).await;
+    let db = build_main(&main).await;
+
+    let mut attachments = Vec::new();
+    for i in 0..MAX_ATTACHED {
+        attachments.push(db.attach(&user, &format!("user{}", i)).await.unwrap());
+    }
+    let err = db.attach(&user, "one_too_many").await.unwrap_err();
+    assert_eq!(err.downcast_ref::<DictError>(), Some(&DictError::TooManyAttachments { limit: MAX_ATTACHED }));
+    assert_eq!(db.search_all_by_reading("ねこ").await.unwrap().len(), MAX_ATTACHED + 1);
+
+    attachments.pop();
+    let err = db.attach(&user, "user0").await.unwrap_err();
+    assert_eq!(err.downcast_ref::<DictError>(), Some(&DictError::AliasInUse("user0".to_string())));
+    let err = db.attach(&user, "main").await.unwrap_err();
+    assert_eq!(err.downcast_ref::<DictError>(), Some(&DictError::InvalidAlias("main".to_string())));
+    assert!(db.attach(&user, "again").await.is_ok());
+
+    db.close().await;
+    let _ = std::fs::remove_file(&main);
+    let _ = std::fs::remove_file(&user);
+}
@@ -0,0 +1,71 @@
+use jmdictdb::{DictDb, Entry, IntegrityReport, REle, Sense};
+use std::path::{Path, PathBuf};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jmdictdb-{}-{}.db", name, std::process::id()))
+}
+
+async fn fixture_db(path: &Path) -> DictDb {
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    let entries: Vec<Entry> = (1..=500)
+        .map(|i| Entry {
+            ent_seq: i.to_string(),
+            r_ele: vec![REle { reb: format!("よみ{}", i), ..Default::default() }],
+            sense: vec![Sense { gloss: vec![format!("meaning number {}", i)], ..Default::default() }],
+            ..Default::default()
+        })
+        .collect();
+    db.insert_entries(&entries).await.unwrap();
+    db
+}
+
+#[tokio::test]
+async fn backup_is_a_consistent_copy() {
+    let path = temp_path("backup-src");
+    let copy = temp_path("backup-copy");
+    let _ = std::fs::remove_file(&copy);
+    let db = fixture_db(&path).await;
+
+    db.backup_to(&copy).await.unwrap();
+    assert!(db.backup_to(&copy).await.is_err(), "an existing destination is not overwritten");
+
+    let backup = DictDb::connect(&format!("sqlite:{}", copy.display())).await.unwrap();
+    assert_eq!(backup.integrity_check().await.unwrap(), IntegrityReport::Ok);
+    assert_eq!(backup.search_entries_with_senses_by_reading("よみ42").await.unwrap().len(), 1);
+
+    backup.close().await;
+    db.close().await;
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&copy);
+}
+
+#[tokio::test]
+async fn integrity_check_reports_corruption() {
+    let path = temp_path("corrupt-src");
+    let copy = temp_path("corrupt-copy");
+    let _ = std::fs::remove_file(&copy);
+    let db = fixture_db(&path).await;
+    assert!(db.integrity_check().await.unwrap().is_ok());
+    db.backup_to(&copy).await.unwrap();
+    db.close().await;
+
+    // Overwrite the last quarter of the file, keeping the header and schema pages intact
+    let mut bytes = std::fs::read(&copy).unwrap();
+    let from = bytes.len() * 3 / 4;
+    for b in &mut bytes[from..] {
+        *b = 0xA5;
+    }
+    std::fs::write(&copy, bytes).unwrap();
+
+    let corrupt = DictDb::connect(&format!("sqlite:{}", copy.display())).await.unwrap();
+    match corrupt.integrity_check().await.unwrap() {
+        IntegrityReport::Corrupt(messages) => assert!(!messages.is_empty()),
+        IntegrityReport::Ok => panic!("corruption was not detected"),
+    }
+
+    corrupt.close().await;
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&copy);
+}
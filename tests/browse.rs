@@ -0,0 +1,75 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::{DictDb, Entry, PartOfSpeech, REle, Sense};
+
+/// The sample entries with a sense of `pos` by ent_seq, computed from the XML.
+fn expected(pos: &PartOfSpeech) -> Vec<i64> {
+    let mut seqs: Vec<i64> = sample_dict()
+        .entry
+        .iter()
+        .filter(|e| e.sense.iter().any(|s| s.pos.iter().any(|p| p == pos.description())))
+        .map(|e| e.ent_seq.parse().unwrap())
+        .collect();
+    seqs.sort();
+    seqs
+}
+
+async fn all_pages(db: &DictDb, pos: &PartOfSpeech, page_size: usize) -> Vec<i64> {
+    let mut seqs = Vec::new();
+    for page in 1.. {
+        let entries = db.entries_by_pos(pos, page, page_size).await.unwrap();
+        if entries.is_empty() {
+            break;
+        }
+        assert!(entries.len() <= page_size);
+        seqs.extend(entries.iter().map(|e| e.ent_seq));
+    }
+    seqs
+}
+
+#[tokio::test]
+async fn pages_cover_every_entry_once() {
+    let db = build_sample_db().await;
+
+    for pos in [PartOfSpeech::AdjNa, PartOfSpeech::Vs, PartOfSpeech::V1] {
+        let expected = expected(&pos);
+        assert!(expected.len() >= 2, "{:?}", pos);
+        assert_eq!(db.count_by_pos(&pos).await.unwrap(), expected.len() as i64);
+        assert_eq!(all_pages(&db, &pos, 3).await, expected, "{:?}", pos);
+        assert_eq!(all_pages(&db, &pos, 1000).await, expected, "{:?}", pos);
+    }
+
+    // Page 0 is the first page
+    let first = db.entries_by_pos(&PartOfSpeech::AdjNa, 1, 2).await.unwrap();
+    let zero = db.entries_by_pos(&PartOfSpeech::AdjNa, 0, 2).await.unwrap();
+    assert_eq!(first, zero);
+    assert!(db.entries_by_pos(&PartOfSpeech::AdjNa, 1000, 10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn several_matching_senses_count_once_and_codes_match() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let na = PartOfSpeech::AdjNa.description().to_string();
+    let sense = |pos: &str| Sense { pos: vec![pos.to_string()], gloss: vec!["x".to_string()], ..Default::default() };
+    db.insert_entries(&[
+        Entry {
+            ent_seq: "2".to_string(),
+            r_ele: vec![REle { reb: "きれい".to_string(), ..Default::default() }],
+            sense: vec![sense(&na), sense(&na)],
+            ..Default::default()
+        },
+        // Parsed without the DTD, the tag stays a code
+        Entry {
+            ent_seq: "1".to_string(),
+            r_ele: vec![REle { reb: "しずか".to_string(), ..Default::default() }],
+            sense: vec![sense("adj-na")],
+            ..Default::default()
+        },
+    ])
+    .await
+    .unwrap();
+
+    assert_eq!(db.count_by_pos(&PartOfSpeech::AdjNa).await.unwrap(), 2);
+    let seqs: Vec<i64> = db.entries_by_pos(&PartOfSpeech::AdjNa, 1, 10).await.unwrap().iter().map(|e| e.ent_seq).collect();
+    assert_eq!(seqs, [1, 2]);
+    assert_eq!(db.count_by_pos(&PartOfSpeech::Vs).await.unwrap(), 0);
+}
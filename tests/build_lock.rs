@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+
+use jmdictdb::fixtures::sample_xml;
+use jmdictdb::{build_lock_path, BuildLock, DictDb, DictError};
+
+fn db_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jmdictdb-build-lock-{}-{}.db", name, std::process::id()))
+}
+
+async fn connect(path: &Path) -> DictDb {
+    DictDb::connect(&format!("sqlite:{}?mode=rwc", path.display())).await.unwrap()
+}
+
+fn in_progress(err: &(dyn std::error::Error + Send + Sync + 'static)) -> Option<(u32, u64, PathBuf)> {
+    match err.downcast_ref::<DictError>() {
+        Some(DictError::BuildInProgress { pid, started_at, lock }) => Some((*pid, *started_at, lock.clone())),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn only_one_of_two_concurrent_builds_runs() {
+    let path = db_path("concurrent");
+    let (a, b) = (connect(&path).await, connect(&path).await);
+    let (first, second) = tokio::join!(a.build_from_reader(sample_xml().as_bytes()), b.build_from_reader(sample_xml().as_bytes()));
+    let results = [first, second];
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+    let err = results.into_iter().find_map(Result::err).unwrap();
+    let (pid, started_at, lock) = in_progress(&*err).unwrap_or_else(|| panic!("not a lock error: {}", err));
+    assert_eq!(pid, std::process::id());
+    assert!(started_at > 0);
+    assert_eq!(lock, build_lock_path(&path));
+    assert!(err.to_string().starts_with(&format!("another build is in progress (pid {}", pid)));
+
+    // Released after the build, the next one runs
+    assert!(!build_lock_path(&path).exists());
+    b.build_from_reader(sample_xml().as_bytes()).await.unwrap();
+    a.close().await;
+    b.close().await;
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn a_held_lock_covers_the_builds_of_its_database() {
+    let path = db_path("held");
+    let lock = BuildLock::acquire(&path).unwrap();
+    assert_eq!(lock.db_path(), path);
+    let mut db = connect(&path).await;
+    assert!(in_progress(&*db.build_from_reader(sample_xml().as_bytes()).await.unwrap_err()).is_some());
+    db.hold_build_lock(lock);
+    db.build_from_reader(sample_xml().as_bytes()).await.unwrap();
+    assert!(build_lock_path(&path).exists());
+    assert!(in_progress(&*BuildLock::acquire(&path).unwrap_err()).is_some());
+
+    db.close().await;
+    drop(db);
+    assert!(!build_lock_path(&path).exists());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn a_panicking_build_releases_the_lock() {
+    let path = db_path("panic");
+    let held = path.clone();
+    let panicked = std::thread::spawn(move || {
+        let _lock = BuildLock::acquire(&held).unwrap();
+        panic!("the build failed");
+    })
+    .join();
+    assert!(panicked.is_err());
+    drop(BuildLock::acquire(&path).unwrap());
+}
@@ -0,0 +1,53 @@
+use jmdictdb::fixtures::{sample_dict, sample_xml};
+use jmdictdb::{BuildReport, DictDb, SkippedEntry};
+
+#[tokio::test]
+async fn clean_build_of_the_sample() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let report = db.build_from_reader(sample_xml().as_bytes()).await.unwrap();
+    let n = sample_dict().entry.len();
+    assert_eq!(report.entries_parsed, n);
+    assert_eq!(report.entries_inserted, n);
+    assert_eq!(db.entry_count().await.unwrap() as usize, n);
+    assert!(report.entities_replaced >= n);
+    assert!(report.is_clean());
+    assert_eq!(report.exit_code(), 0);
+    assert_eq!(report.input_hash.len(), 16);
+    assert!(report.output_size > 0);
+    let phases: Vec<&str> = report.durations.iter().map(|d| d.phase.as_str()).collect();
+    assert_eq!(phases, ["read", "parse", "insert"]);
+
+    // Rebuilding over the same data writes nothing and hashes the same input alike
+    let again = db.build_from_reader(sample_xml().as_bytes()).await.unwrap();
+    assert_eq!(again.entries_inserted, 0);
+    assert_eq!(again.input_hash, report.input_hash);
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(serde_json::from_str::<BuildReport>(&json).unwrap(), report);
+}
+
+#[tokio::test]
+async fn malformed_entries_are_skipped() {
+    let bad = "<entry><ent_seq>1000001</ent_seq><r_ele><reb>こわれ</r_ele></entry>\n\
+               <entry><ent_seq>abc</ent_seq><r_ele><reb>すうじ</reb></r_ele><sense><gloss>no number</gloss></sense></entry>\n\
+               <entry><ent_seq>1000003</ent_seq><r_ele><reb>とじない</reb></r_ele>\n\
+               <entry><ent_seq>1000004</ent_seq><r_ele><reb>ぶじ</reb></r_ele><sense><pos>&n;</pos><gloss>safe</gloss></sense></entry>\n";
+    let xml = sample_xml().replace("</JMdict>", &format!("{}</JMdict>", bad));
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let report = db.build_from_reader(xml.as_bytes()).await.unwrap();
+
+    let n = sample_dict().entry.len();
+    let skipped: Vec<&str> = report.skipped.iter().map(|s| s.ent_seq.as_str()).collect();
+    assert_eq!(skipped, ["1000001", "1000003", "abc"]);
+    assert_eq!(
+        report.skipped[1],
+        SkippedEntry { ent_seq: "1000003".to_string(), reason: "entry without its closing tag".to_string() }
+    );
+    assert_eq!(report.entries_parsed, n + 2);
+    assert_eq!(report.entries_inserted, n + 1);
+    assert_eq!(report.exit_code(), 2);
+    // The entries around the broken ones made it
+    let buji = db.entry_by_seq(1000004).await.unwrap().unwrap();
+    assert_eq!(buji.senses[0].pos, ["noun (common) (futsuumeishi)"]);
+    assert!(db.entry_by_seq(1000001).await.unwrap().is_none());
+}
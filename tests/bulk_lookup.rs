@@ -0,0 +1,58 @@
+use futures_util::{pin_mut, StreamExt, TryStreamExt};
+use jmdictdb::fixtures::{build_sample_db, sample_dict, temp_tables};
+use std::time::Duration;
+
+#[tokio::test]
+async fn finds_each_term_by_keb_or_reb() {
+    let db = build_sample_db().await;
+    let terms = ["食べる", "たべる", "食べ物", "たべる", "", "ないことば", "無い言葉", "タベル"];
+    let pairs: Vec<(String, i64)> = db.bulk_lookup(terms.iter().map(|t| t.to_string())).try_collect().await.unwrap();
+    assert_eq!(
+        pairs,
+        [("たべる".to_string(), 1358280), ("食べる".to_string(), 1358280), ("食べ物".to_string(), 1358300)]
+    );
+    assert!(temp_tables(&db).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn many_more_terms_than_sqlite_parameters() {
+    let db = build_sample_db().await;
+    let dict = sample_dict();
+    let forms: Vec<String> = dict
+        .entry
+        .iter()
+        .flat_map(|e| e.k_ele.iter().flatten().map(|k| k.keb.clone()).chain(e.r_ele.iter().map(|r| r.reb.clone())))
+        .collect();
+    // Every form of the sample among 100k made-up words
+    let terms = (0..100_000).map(|i| format!("語{}", i)).chain(forms.iter().cloned());
+    let pairs: Vec<(String, i64)> = db.bulk_lookup(terms).try_collect().await.unwrap();
+
+    let mut expected: Vec<(String, i64)> = dict
+        .entry
+        .iter()
+        .flat_map(|e| {
+            let ent_seq: i64 = e.ent_seq.parse().unwrap();
+            let forms = e.k_ele.iter().flatten().map(|k| k.keb.clone()).chain(e.r_ele.iter().map(|r| r.reb.clone()));
+            forms.map(move |f| (f, ent_seq))
+        })
+        .collect();
+    expected.sort();
+    expected.dedup();
+    assert_eq!(pairs, expected);
+}
+
+#[tokio::test]
+async fn dropping_the_stream_early_removes_the_table() {
+    let db = build_sample_db().await;
+    {
+        let terms = (0..10_000).map(|i| format!("語{}", i)).chain(["食べる".to_string(), "飲む".to_string()]);
+        let stream = db.bulk_lookup(terms);
+        pin_mut!(stream);
+        assert!(stream.next().await.unwrap().is_ok());
+    }
+    // The connection goes back to the pool in the background
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(temp_tables(&db).await.unwrap().is_empty());
+    let pairs: Vec<(String, i64)> = db.bulk_lookup(["飲む".to_string()].into_iter()).try_collect().await.unwrap();
+    assert_eq!(pairs.len(), 1);
+}
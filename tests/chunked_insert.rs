@@ -0,0 +1,83 @@
+use jmdictdb::{DictDb, DictError, Entry, InsertOptions, InsertProgress, REle, Sense, DEFAULT_INSERT_CHUNK};
+
+fn entry(ent_seq: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        r_ele: vec![REle { reb: format!("かな{}", ent_seq), ..Default::default() }],
+        sense: vec![Sense { gloss: vec!["word".to_string()], ..Default::default() }],
+        ..Default::default()
+    }
+}
+
+fn entries(n: usize) -> Vec<Entry> {
+    (0..n).map(|i| entry(&(2900200 + i).to_string())).collect()
+}
+
+async fn empty_db() -> DictDb {
+    DictDb::connect("sqlite::memory:").await.unwrap()
+}
+
+fn chunks_of(chunk_size: usize) -> InsertOptions {
+    InsertOptions { chunk_size, ..InsertOptions::default() }
+}
+
+#[tokio::test]
+async fn reports_each_committed_chunk() {
+    let db = empty_db().await;
+    let mut seen = Vec::new();
+    let done = db.insert_entries_with(&entries(8), &chunks_of(3), |p| seen.push(p.entries_committed)).await.unwrap();
+    assert_eq!(seen, [3, 6, 8]);
+    assert_eq!(done, InsertProgress { chunks_committed: 3, entries_committed: 8, entries_total: 8 });
+    assert_eq!(db.entry_count().await.unwrap(), 8);
+    assert_eq!(InsertOptions::default().chunk_size, DEFAULT_INSERT_CHUNK);
+
+    let one = InsertOptions { chunk_size: 3, single_transaction: true };
+    let mut calls = 0;
+    let done = db.insert_entries_with(&entries(8), &one, |_| calls += 1).await.unwrap();
+    assert_eq!((calls, done.chunks_committed), (1, 1));
+}
+
+#[tokio::test]
+async fn a_failed_chunk_keeps_the_committed_ones() {
+    let db = empty_db().await;
+    let mut batch = entries(8);
+    batch[4] = entry("not a number");
+    let err = db.insert_entries_with(&batch, &chunks_of(3), |_| {}).await.unwrap_err();
+    let Some(DictError::InsertInterrupted { chunks_committed, entries_committed, .. }) = err.downcast_ref::<DictError>() else {
+        panic!("not interrupted: {}", err);
+    };
+    assert_eq!((*chunks_committed, *entries_committed), (1, 3));
+    // The rest of the failed chunk was rolled back with it
+    assert_eq!(db.entry_count().await.unwrap(), 3);
+
+    // Resuming after fixing the entry stores the rest
+    batch[4] = entry("2900204");
+    let done = db.insert_entries_with(&batch[*entries_committed..], &chunks_of(3), |_| {}).await.unwrap();
+    assert_eq!(done.entries_committed, 5);
+    assert_eq!(db.entry_count().await.unwrap(), 8);
+}
+
+#[tokio::test]
+async fn a_failed_first_chunk_is_interrupted_too() {
+    let db = empty_db().await;
+    let mut batch = entries(8);
+    batch[1] = entry("not a number");
+    let err = db.insert_entries_with(&batch, &chunks_of(3), |_| {}).await.unwrap_err();
+    let Some(DictError::InsertInterrupted { chunks_committed, entries_committed, message }) = err.downcast_ref::<DictError>() else {
+        panic!("not interrupted: {}", err);
+    };
+    assert_eq!((*chunks_committed, *entries_committed), (0, 0));
+    assert!(message.contains("invalid digit"), "{}", message);
+    assert_eq!(db.entry_count().await.unwrap(), 0);
+
+    // All or nothing
+    batch[1] = entry("2900201");
+    batch[7] = entry("not a number");
+    let one = InsertOptions { single_transaction: true, ..InsertOptions::default() };
+    let err = db.insert_entries_with(&batch, &one, |_| panic!("nothing committed")).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DictError>(),
+        Some(DictError::InsertInterrupted { chunks_committed: 0, entries_committed: 0, .. })
+    ));
+    assert_eq!(db.entry_count().await.unwrap(), 0);
+}
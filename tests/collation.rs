@@ -0,0 +1,150 @@
+use futures_util::TryStreamExt;
+use jmdictdb::{Collation, DictDb, Entry, FlatRow, FlatRowOptions, REle, Sense, DEFAULT_LANG};
+
+/// Glosses that sort differently under each collation: case, accents, leading notes and
+/// punctuation.
+const TRICKY: [&str; 13] = [
+    "Zebra",
+    "apple",
+    "(of a person) tall",
+    "éclair",
+    "Eclair",
+    "eclair",
+    "Zürich",
+    "zulu",
+    "\"quoted\" speech",
+    "Ångström",
+    "angle",
+    "...and so on",
+    "(unclosed note",
+];
+
+fn sorted(collation: Collation, glosses: &[&str]) -> Vec<String> {
+    let mut glosses: Vec<String> = glosses.iter().map(|g| g.to_string()).collect();
+    glosses.sort_by(|a, b| collation.compare(a, b));
+    glosses
+}
+
+async fn db_with(glosses: &[&str]) -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let entries: Vec<Entry> = glosses
+        .iter()
+        .enumerate()
+        .map(|(i, gloss)| Entry {
+            ent_seq: (2900100 + i).to_string(),
+            r_ele: vec![REle { reb: format!("かな{}", i), ..Default::default() }],
+            sense: vec![Sense { gloss: vec![gloss.to_string()], ..Default::default() }],
+            ..Default::default()
+        })
+        .collect();
+    db.insert_entries(&entries).await.unwrap();
+    db
+}
+
+#[test]
+fn binary_orders_by_bytes() {
+    let expected = [
+        "\"quoted\" speech",
+        "(of a person) tall",
+        "(unclosed note",
+        "...and so on",
+        "Eclair",
+        "Zebra",
+        "Zürich",
+        "angle",
+        "apple",
+        "eclair",
+        "zulu",
+        "Ångström",
+        "éclair",
+    ];
+    assert_eq!(sorted(Collation::Binary, &TRICKY), expected);
+}
+
+#[test]
+fn case_insensitive_folds_ascii_only() {
+    let expected = [
+        "\"quoted\" speech",
+        "(of a person) tall",
+        "(unclosed note",
+        "...and so on",
+        "angle",
+        "apple",
+        "Eclair",
+        "eclair",
+        "Zebra",
+        "zulu",
+        "Zürich",
+        "Ångström",
+        "éclair",
+    ];
+    assert_eq!(sorted(Collation::CaseInsensitive, &TRICKY), expected);
+}
+
+#[test]
+fn unicode_casefold_ignores_case_accents_and_leading_notes() {
+    let expected = [
+        "...and so on",
+        "angle",
+        "Ångström",
+        "apple",
+        "Eclair",
+        "eclair",
+        "éclair",
+        "\"quoted\" speech",
+        "(of a person) tall",
+        "(unclosed note",
+        "Zebra",
+        "zulu",
+        "Zürich",
+    ];
+    assert_eq!(sorted(Collation::UnicodeCasefold, &TRICKY), expected);
+    // Composed and decomposed accents alike
+    assert_eq!(Collation::UnicodeCasefold.sort_key("e\u{301}clair"), Collation::UnicodeCasefold.sort_key("éclair"));
+    assert_eq!(Collation::UnicodeCasefold.sort_key("Straße"), "strasse");
+    // A gloss that is all note keeps it
+    assert_eq!(Collation::UnicodeCasefold.sort_key("(archaism)"), "(archaism)");
+}
+
+#[test]
+fn parses_the_cli_names() {
+    assert_eq!(Collation::parse("binary"), Some(Collation::Binary));
+    assert_eq!(Collation::parse("NOCASE"), Some(Collation::CaseInsensitive));
+    assert_eq!(Collation::parse("casefold"), Some(Collation::UnicodeCasefold));
+    assert_eq!(Collation::parse("icu"), None);
+}
+
+#[tokio::test]
+async fn the_flat_export_sorts_by_gloss() {
+    let db = db_with(&TRICKY).await;
+    let rows = |opts: FlatRowOptions| {
+        let db = &db;
+        async move {
+            let rows: Vec<FlatRow> = db.flat_rows(&opts).try_collect().await.unwrap();
+            rows.into_iter().map(|r| r.gloss).collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(rows(FlatRowOptions::default()).await, TRICKY);
+    for collation in [Collation::Binary, Collation::CaseInsensitive, Collation::UnicodeCasefold] {
+        let opts = FlatRowOptions { collation: Some(collation), ..FlatRowOptions::default() };
+        assert_eq!(rows(opts).await, sorted(collation, &TRICKY), "{:?}", collation);
+    }
+
+    let mut csv = Vec::new();
+    let opts = FlatRowOptions { collation: Some(Collation::UnicodeCasefold), ..FlatRowOptions::default() };
+    db.export_flat(&opts, jmdictdb::ExportFormat::Csv, &mut csv).await.unwrap();
+    let lines: Vec<&str> = std::str::from_utf8(&csv).unwrap().lines().collect();
+    assert_eq!(lines[1], "2900111,,かな11,0,,...and so on");
+    assert_eq!(lines.last(), Some(&"2900106,,かな6,0,,Zürich"));
+}
+
+#[tokio::test]
+async fn reverse_lookups_sort_by_the_matched_gloss() {
+    let db = db_with(&["apple.", "Apple", "(apple)"]).await;
+    db.normalize_glosses().await.unwrap();
+    let mut matches = db.search_gloss_matches("apple", DEFAULT_LANG).await.unwrap();
+    assert_eq!(matches.len(), 3);
+    Collation::UnicodeCasefold.sort_by(&mut matches, |m| &m.matched_gloss);
+    let glosses: Vec<&str> = matches.iter().map(|m| m.matched_gloss.as_str()).collect();
+    assert_eq!(glosses, ["(apple)", "Apple", "apple."]);
+}
@@ -0,0 +1,98 @@
+use jmdictdb::fixtures::{build_sample_db, multilingual_xml};
+use jmdictdb::{complexity_score, parse_jmdict_str, BrowseOrder, DictDb, EntryParsed, PartOfSpeech, RankOptions, SearchFilter};
+use sqlx::SqlitePool;
+
+async fn first(db: &DictDb, reading: &str) -> EntryParsed {
+    db.search_entries_with_senses_by_reading(reading).await.unwrap().remove(0)
+}
+
+#[test]
+fn adds_up_senses_glosses_and_headword() {
+    assert_eq!(complexity_score(1, 1, "猫"), 9);
+    assert_eq!(complexity_score(2, 4, "食べる"), 21);
+    assert_eq!(complexity_score(1, 1, "ねこ"), 8);
+    assert_eq!(complexity_score(0, 0, ""), 0);
+    // Each part raises it
+    assert!(complexity_score(2, 1, "猫") > complexity_score(1, 1, "猫"));
+    assert!(complexity_score(1, 2, "猫") > complexity_score(1, 1, "猫"));
+    assert!(complexity_score(1, 1, "猫猫") > complexity_score(1, 1, "猫"));
+}
+
+#[tokio::test]
+async fn entries_count_their_senses_and_glosses() {
+    let db = build_sample_db().await;
+    let neko = first(&db, "ねこ").await;
+    assert_eq!((neko.sense_count(), neko.total_gloss_count(), neko.complexity_score()), (1, 1, 9));
+    let taberu = first(&db, "たべる").await;
+    assert_eq!((taberu.sense_count(), taberu.total_gloss_count(), taberu.complexity_score()), (2, 4, 21));
+    // Usually kana entries are scored by their reading
+    let ohayou = first(&db, "おはようございます").await;
+    assert_eq!(ohayou.complexity_score(), complexity_score(1, ohayou.total_gloss_count(), "おはようございます"));
+
+    // Senses without English count the glosses of their first language
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let entries = parse_jmdict_str(multilingual_xml()).unwrap().entry;
+    db.insert_entries(&entries).await.unwrap();
+    for e in &entries {
+        let parsed = db.entry_by_seq(e.ent_seq.parse().unwrap()).await.unwrap().unwrap();
+        let glosses: usize = parsed.senses.iter().map(|s| s.default_glosses().len()).sum();
+        assert!(glosses > 0);
+        assert_eq!(parsed.total_gloss_count(), glosses);
+    }
+}
+
+#[tokio::test]
+async fn filters_by_complexity() {
+    let db = build_sample_db().await;
+    let search = |max: Option<u32>| {
+        let filter = SearchFilter { max_complexity: max, ..Default::default() };
+        let opts = RankOptions { filter, ..Default::default() };
+        let db = &db;
+        async move { db.search_ranked("ねこ", &opts).await.unwrap().iter().map(|r| r.entry.ent_seq).collect::<Vec<_>>() }
+    };
+    assert!(search(None).await.contains(&1469800));
+    assert!(search(Some(9)).await.contains(&1469800));
+    assert!(!search(Some(8)).await.contains(&1469800));
+    assert_eq!(SearchFilter::new().max_complexity(9), SearchFilter { max_complexity: Some(9), ..Default::default() });
+}
+
+#[tokio::test]
+async fn browses_the_simplest_first() {
+    let db = build_sample_db().await;
+    let simplest = db.entries_by_pos_ordered(&PartOfSpeech::V1, BrowseOrder::Complexity, 1, 100).await.unwrap();
+    let by_seq = db.entries_by_pos(&PartOfSpeech::V1, 1, 100).await.unwrap();
+    assert!(simplest.len() > 5);
+    assert_eq!(simplest.len(), by_seq.len());
+    let scores: Vec<u32> = simplest.iter().map(EntryParsed::complexity_score).collect();
+    assert!(scores.is_sorted(), "{:?}", scores);
+    // Ties by ent_seq, and paging goes on where the first page stopped
+    for pair in simplest.windows(2) {
+        assert!(pair[0].complexity_score() < pair[1].complexity_score() || pair[0].ent_seq < pair[1].ent_seq);
+    }
+    let second = db.entries_by_pos_ordered(&PartOfSpeech::V1, BrowseOrder::Complexity, 2, 3).await.unwrap();
+    assert_eq!(second, simplest[3..6]);
+}
+
+#[tokio::test]
+async fn older_databases_get_the_scores_on_connect() {
+    let path = std::env::temp_dir().join(format!("jmdictdb-complexity-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(&jmdictdb::fixtures::sample_dict().entry).await.unwrap();
+    let before = db.entries_by_pos_ordered(&PartOfSpeech::Noun, BrowseOrder::Complexity, 1, 1000).await.unwrap();
+    db.close().await;
+
+    // As written by versions before the column; renamed away, DROP COLUMN trips over the
+    // comment after it
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("DROP INDEX idx_entries_complexity").execute(&pool).await.unwrap();
+    sqlx::query("ALTER TABLE entries RENAME COLUMN complexity TO unused").execute(&pool).await.unwrap();
+    pool.close().await;
+
+    let db = DictDb::connect(&url).await.unwrap();
+    let after = db.entries_by_pos_ordered(&PartOfSpeech::Noun, BrowseOrder::Complexity, 1, 1000).await.unwrap();
+    assert_eq!(after.iter().map(|e| e.ent_seq).collect::<Vec<_>>(), before.iter().map(|e| e.ent_seq).collect::<Vec<_>>());
+    db.close().await;
+    DictDb::reset_database(&url).await.unwrap();
+}
@@ -0,0 +1,52 @@
+use jmdictdb::{DictDb, Entry, KEle, REle, Sense};
+
+fn word(ent_seq: i64, keb: Option<&str>, reb: &str, pri: &[&str]) -> Entry {
+    let pri: Vec<String> = pri.iter().map(|p| p.to_string()).collect();
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: keb.map(|k| vec![KEle { keb: k.to_string(), ke_pri: pri.clone(), ..Default::default() }]),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec![format!("word {}", ent_seq)], ..Default::default() }],
+    }
+}
+
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[
+        word(1, Some("手紙"), "てがみ", &["ichi1", "news1", "nf10"]),
+        word(2, Some("置き手紙"), "おきてがみ", &["spec2"]),
+        word(3, Some("手紙を書く"), "てがみをかく", &[]),
+        word(4, Some("絵手紙"), "えてがみ", &[]),
+        word(5, Some("恋文"), "こいぶみ", &["news2", "nf30"]),
+        // Same rank as 置き手紙, but a better bucket
+        word(6, Some("手紙魔"), "てがみま", &["spec2", "nf40"]),
+        word(7, None, "てがみちゃん", &[]),
+    ])
+    .await
+    .unwrap();
+    db
+}
+
+fn seqs(entries: &[jmdictdb::EntryParsed]) -> Vec<i64> {
+    entries.iter().map(|e| e.ent_seq).collect()
+}
+
+#[tokio::test]
+async fn kanji_compounds_ranked_by_priority() {
+    let db = db().await;
+    // 絵手紙 is shorter than 手紙を書く
+    assert_eq!(seqs(&db.compounds_containing("手紙", 10).await.unwrap()), [6, 2, 4, 3]);
+    assert_eq!(seqs(&db.compounds_containing("手紙", 2).await.unwrap()), [6, 2]);
+    assert!(db.compounds_containing("置き手紙", 10).await.unwrap().is_empty());
+    assert!(db.compounds_containing("手紙", 0).await.unwrap().is_empty());
+    assert!(db.compounds_containing(" ", 10).await.is_err());
+}
+
+#[tokio::test]
+async fn kana_queries_search_the_readings() {
+    let db = db().await;
+    // てがみ itself, 手紙, is excluded; てがみちゃん has no keb
+    let found = seqs(&db.compounds_containing("てがみ", 10).await.unwrap());
+    assert_eq!(found, [6, 2, 4, 3, 7]);
+    assert_eq!(seqs(&db.compounds_containing("ぶみ", 10).await.unwrap()), [5]);
+}
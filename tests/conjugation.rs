@@ -0,0 +1,138 @@
+use jmdictdb::{conjugate, is_conjugable, ConjugationForm, PartOfSpeech};
+
+use ConjugationForm::*;
+
+/// The kana of the forms of a word, checked against the table.
+fn assert_forms(reading: &str, pos: PartOfSpeech, expected: &[(ConjugationForm, &str)]) {
+    let table = conjugate(reading, reading, pos.clone());
+    for &(form, kana) in expected {
+        let got = table.get(form).map(|c| c.kana.as_str());
+        assert_eq!(got, Some(kana), "{:?} of {} ({})", form, reading, pos.code());
+    }
+}
+
+#[test]
+fn godan_endings() {
+    use PartOfSpeech::*;
+    let cases = [
+        ("かう", V5u, "かわない", "かった", "かって", "かえる", "かおう", "かえ"),
+        ("かく", V5k, "かかない", "かいた", "かいて", "かける", "かこう", "かけ"),
+        ("およぐ", V5g, "およがない", "およいだ", "およいで", "およげる", "およごう", "およげ"),
+        ("はなす", V5s, "はなさない", "はなした", "はなして", "はなせる", "はなそう", "はなせ"),
+        ("まつ", V5t, "またない", "まった", "まって", "まてる", "まとう", "まて"),
+        ("しぬ", V5n, "しなない", "しんだ", "しんで", "しねる", "しのう", "しね"),
+        ("あそぶ", V5b, "あそばない", "あそんだ", "あそんで", "あそべる", "あそぼう", "あそべ"),
+        ("よむ", V5m, "よまない", "よんだ", "よんで", "よめる", "よもう", "よめ"),
+        ("とる", V5r, "とらない", "とった", "とって", "とれる", "とろう", "とれ"),
+    ];
+    for (reading, pos, negative, past, te, potential, volitional, imperative) in cases {
+        assert_forms(
+            reading,
+            pos,
+            &[
+                (NonPast, reading),
+                (Negative, negative),
+                (Past, past),
+                (TeForm, te),
+                (Potential, potential),
+                (Volitional, volitional),
+                (Imperative, imperative),
+            ],
+        );
+    }
+}
+
+#[test]
+fn godan_polite_forms_use_the_i_stem() {
+    assert_forms("かく", PartOfSpeech::V5k, &[(NonPastPolite, "かきます"), (PastPolite, "かきました"), (NegativePolite, "かきません")]);
+    assert_forms("かう", PartOfSpeech::V5u, &[(Passive, "かわれる"), (Causative, "かわせる"), (PastNegative, "かわなかった")]);
+}
+
+#[test]
+fn ichidan() {
+    assert_forms(
+        "たべる",
+        PartOfSpeech::V1,
+        &[
+            (NonPastPolite, "たべます"),
+            (Negative, "たべない"),
+            (Past, "たべた"),
+            (TeForm, "たべて"),
+            (Potential, "たべられる"),
+            (Passive, "たべられる"),
+            (Causative, "たべさせる"),
+            (Volitional, "たべよう"),
+            (Imperative, "たべろ"),
+        ],
+    );
+}
+
+#[test]
+fn iku_has_irregular_te_and_ta_forms() {
+    let table = conjugate("行く", "いく", PartOfSpeech::V5kS);
+    assert_eq!(table.get(TeForm).map(|c| (c.kanji.as_str(), c.kana.as_str())), Some(("行って", "いって")));
+    assert_eq!(table.get(Past).map(|c| (c.kanji.as_str(), c.kana.as_str())), Some(("行った", "いった")));
+    // The rest is regular godan
+    assert_eq!(table.get(Negative).map(|c| c.kana.as_str()), Some("いかない"));
+}
+
+#[test]
+fn suru_and_kuru_irregulars() {
+    assert_forms(
+        "くる",
+        PartOfSpeech::Vk,
+        &[
+            (NonPastPolite, "きます"),
+            (Negative, "こない"),
+            (Past, "きた"),
+            (TeForm, "きて"),
+            (Potential, "こられる"),
+            (Volitional, "こよう"),
+            (Imperative, "こい"),
+        ],
+    );
+    assert_forms(
+        "する",
+        PartOfSpeech::VsI,
+        &[
+            (NonPastPolite, "します"),
+            (Negative, "しない"),
+            (Past, "した"),
+            (TeForm, "して"),
+            (Potential, "できる"),
+            (Volitional, "しよう"),
+        ],
+    );
+    // The noun takes the forms of する after it
+    let table = conjugate("勉強", "べんきょう", PartOfSpeech::Vs);
+    assert_eq!(table.get(NonPast).map(|c| c.kanji.as_str()), Some("勉強する"));
+    assert_eq!(table.get(Past).map(|c| c.kana.as_str()), Some("べんきょうした"));
+}
+
+#[test]
+fn adjectives() {
+    assert_forms("たかい", PartOfSpeech::AdjI, &[(Negative, "たかくない"), (Past, "たかかった"), (TeForm, "たかくて")]);
+    assert_forms("いい", PartOfSpeech::AdjIx, &[(Negative, "よくない"), (Past, "よかった")]);
+    // Adjectives have no imperative
+    assert!(conjugate("たかい", "たかい", PartOfSpeech::AdjI).get(Imperative).is_none());
+}
+
+#[test]
+fn words_that_dont_conjugate_have_an_empty_table() {
+    assert!(!is_conjugable(&PartOfSpeech::Noun));
+    assert!(conjugate("猫", "ねこ", PartOfSpeech::Noun).is_empty());
+}
+
+#[test]
+fn codes_and_descriptions_round_trip() {
+    for pos in PartOfSpeech::ALL {
+        assert_eq!(PartOfSpeech::from_code(pos.code()).as_ref(), Some(pos), "{}", pos.code());
+        assert_eq!(PartOfSpeech::from_description(pos.description()).as_ref(), Some(pos), "{}", pos.description());
+        assert_eq!(&PartOfSpeech::parse(pos.code()), pos);
+        assert_eq!(&PartOfSpeech::parse(pos.description()), pos);
+        assert_eq!(pos.to_string(), pos.code());
+    }
+    let other = PartOfSpeech::parse("not a tag");
+    assert_eq!(other, PartOfSpeech::Other("not a tag".into()));
+    assert_eq!((other.code(), other.description()), ("not a tag", "not a tag"));
+}
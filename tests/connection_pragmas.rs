@@ -0,0 +1,83 @@
+use futures_util::{pin_mut, StreamExt};
+use jmdictdb::{ConnectionPragmas, DictDb, DictDbOptions, Entry, REle, Sense};
+use sqlx::SqlitePool;
+use std::io::Cursor;
+use std::time::Duration;
+
+fn entry(ent_seq: i64, reb: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: None,
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec![reb.to_string()], ..Default::default() }],
+    }
+}
+
+async fn temp_db(name: &str) -> (String, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("jmdictdb-pragmas-{}-{}.db", name, std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    (url, path)
+}
+
+async fn senses_of(url: &str, ent_seq: i64) -> i64 {
+    let pool = SqlitePool::connect(url).await.unwrap();
+    let n = sqlx::query_scalar("SELECT COUNT(*) FROM senses WHERE ent_seq = ?1").bind(ent_seq).fetch_one(&pool).await.unwrap();
+    pool.close().await;
+    n
+}
+
+/// Deletes entry 2 while the stream holds on to a connection, so the delete runs on another.
+async fn delete_on_a_second_connection(db: &DictDb) {
+    let stream = db.bulk_lookup(["いち".to_string()].into_iter());
+    pin_mut!(stream);
+    assert!(stream.next().await.unwrap().is_ok());
+    assert!(db.delete_entry(2).await.unwrap());
+}
+
+#[test]
+fn default_pragmas() {
+    let pragmas = ConnectionPragmas::default();
+    assert_eq!(pragmas.statements(), ["PRAGMA foreign_keys = ON", "PRAGMA busy_timeout = 5000"]);
+    let opts = DictDbOptions::new("sqlite::memory:").busy_timeout(Duration::from_millis(250)).case_sensitive_like(true);
+    assert_eq!(
+        opts.connection_pragmas().statements(),
+        ["PRAGMA foreign_keys = ON", "PRAGMA busy_timeout = 250", "PRAGMA case_sensitive_like = ON"]
+    );
+}
+
+#[tokio::test]
+async fn cascading_deletes_work_on_later_connections() {
+    let (url, path) = temp_db("cascade").await;
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(&[entry(1, "いち"), entry(2, "に")]).await.unwrap();
+    delete_on_a_second_connection(&db).await;
+    db.close().await;
+    assert_eq!(senses_of(&url, 2).await, 0);
+    assert_eq!(senses_of(&url, 1).await, 1);
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn pragmas_can_be_overridden() {
+    let (url, path) = temp_db("override").await;
+    let pragmas = ConnectionPragmas { foreign_keys: false, ..ConnectionPragmas::default() };
+    let db = DictDbOptions::new(&url).pragmas(pragmas).connect().await.unwrap();
+    db.insert_entries(&[entry(1, "いち"), entry(2, "に")]).await.unwrap();
+    delete_on_a_second_connection(&db).await;
+    db.close().await;
+    // Without foreign keys the senses stay behind
+    assert_eq!(senses_of(&url, 2).await, 1);
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn case_sensitive_like() {
+    let corpus = "A: 食べ物が好きです。\tI like Food.#ID=1_2\nB: 食べ物 好き\n";
+    for (case_sensitive, found) in [(false, 1), (true, 0)] {
+        let db = DictDbOptions::new("sqlite::memory:").case_sensitive_like(case_sensitive).connect().await.unwrap();
+        db.load_tanaka_corpus_reader(Cursor::new(corpus)).await.unwrap();
+        assert_eq!(db.search_sentences("food", 10).await.unwrap().len(), found);
+        assert_eq!(db.search_sentences("Food", 10).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,70 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::{canonical_entry, content_hash, parse_entry_xml, DictDb, Entry, EntryParsed};
+
+const TABERU: &str = "<entry><ent_seq>1358280</ent_seq>\
+    <k_ele><keb>食べる</keb><ke_pri>ichi1</ke_pri></k_ele>\
+    <r_ele><reb>たべる</reb><re_pri>ichi1</re_pri></r_ele>\
+    <sense><pos>&v1;</pos><gloss>to eat</gloss><gloss xml:lang=\"ger\">essen</gloss></sense>\
+    <sense><gloss>to live on (e.g. a salary)</gloss></sense></entry>";
+
+fn taberu(last_gloss: &str) -> Entry {
+    parse_entry_xml(&TABERU.replace("to live on (e.g. a salary)", last_gloss)).unwrap()
+}
+
+#[test]
+fn canonical_form_and_hash_are_pinned() {
+    let e = taberu("to live on\t(e.g. a salary)\\");
+    assert_eq!(
+        canonical_entry(&e),
+        "v 1\nent_seq 1358280\nkeb 食べる\nke_pri ichi1\nreb たべる\nre_pri ichi1\n\
+         sense\npos Ichidan verb\ngloss to eat\ngloss.ger essen\n\
+         sense\ngloss to live on\\t(e.g. a salary)\\\\\n"
+    );
+    // If this changes, so does every stored hash: bump CONTENT_HASH_VERSION
+    assert_eq!(content_hash(&e), "3acf051157833148");
+    assert_eq!(content_hash(&e).len(), 16);
+    assert_ne!(content_hash(&e), content_hash(&taberu("to live off")));
+}
+
+#[tokio::test]
+async fn stored_with_the_entry_and_unchanged_entries_are_skipped() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let e = taberu("to live off");
+    assert!(db.upsert_entry(&e).await.unwrap());
+    let stored = db.entry_by_seq(1358280).await.unwrap().unwrap();
+    assert_eq!(stored.content_hash.as_deref(), Some(content_hash(&e).as_str()));
+    assert_eq!(EntryParsed::try_from(&e).unwrap().content_hash, stored.content_hash);
+
+    assert!(!db.upsert_entry(&e).await.unwrap());
+    assert!(db.upsert_entry(&taberu("to subsist on")).await.unwrap());
+    assert_eq!(db.entry_by_seq(1358280).await.unwrap().unwrap().senses[1].gloss, ["to subsist on"]);
+}
+
+#[tokio::test]
+async fn changed_since_another_version() {
+    let old = build_sample_db().await;
+    let new = build_sample_db().await;
+    assert!(new.changed_since(&old).await.unwrap().is_empty());
+
+    // One entry edited, one removed, one added
+    let mut dict = sample_dict();
+    let edited = dict.entry.iter_mut().find(|e| e.ent_seq == "1358300").unwrap();
+    edited.sense[0].gloss.push("eats".to_string());
+    new.upsert_entry(edited).await.unwrap();
+    new.delete_entry(1157170).await.unwrap();
+    new.upsert_entry(&taberu("to live off").with_seq(1000001)).await.unwrap();
+
+    assert_eq!(new.changed_since(&old).await.unwrap(), [1000001, 1157170, 1358300]);
+    assert_eq!(old.changed_since(&new).await.unwrap(), [1000001, 1157170, 1358300]);
+}
+
+trait WithSeq {
+    fn with_seq(self, ent_seq: i64) -> Self;
+}
+
+impl WithSeq for Entry {
+    fn with_seq(mut self, ent_seq: i64) -> Self {
+        self.ent_seq = ent_seq.to_string();
+        self
+    }
+}
@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+use jmdictdb::fixtures::build_sample_db;
+
+fn tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split_whitespace().map(str::to_string)
+}
+
+#[tokio::test]
+async fn counts_and_ranks_a_corpus() {
+    let db = build_sample_db().await;
+    let corpus = db.count_corpus_occurrences(tokens("猫 が 見る 猫 ねこ 食べる 見る 猫 ぴよぴよ ぴよぴよ")).await.unwrap();
+    assert_eq!(corpus.counts[&1469800], 4);
+    assert_eq!(corpus.counts[&1225970], 2);
+    assert_eq!(corpus.counts[&1358280], 1);
+    assert_eq!(corpus.unresolved, HashMap::from([("ぴよぴよ".to_string(), 2)]));
+
+    let stored = db.store_corpus_frequency("books", &corpus.counts).await.unwrap();
+    assert_eq!(stored, corpus.counts.len());
+    assert!(db.frequency_sources().await.unwrap().contains(&"books".to_string()));
+    assert_eq!(db.frequency_rank(1469800, "books").await.unwrap(), Some(1));
+    assert_eq!(db.frequency_rank(1225970, "books").await.unwrap(), Some(2));
+}
+
+#[tokio::test]
+async fn equal_counts_share_a_rank() {
+    let db = build_sample_db().await;
+    let counts = HashMap::from([(1469800, 5), (1225970, 3), (1358280, 3), (42, 9), (1000030, 0)]);
+    // 42 is no entry and 0 occurrences are no rank
+    assert_eq!(db.store_corpus_frequency("mine", &counts).await.unwrap(), 3);
+    assert_eq!(db.frequency_rank(1469800, "mine").await.unwrap(), Some(2));
+    assert_eq!(db.frequency_rank(1225970, "mine").await.unwrap(), Some(3));
+    assert_eq!(db.frequency_rank(1358280, "mine").await.unwrap(), Some(3));
+
+    // Storing again replaces the ranks
+    db.store_corpus_frequency("mine", &HashMap::from([(1358280, 1)])).await.unwrap();
+    assert_eq!(db.frequency_rank(1469800, "mine").await.unwrap(), None);
+    assert_eq!(db.frequency_rank(1358280, "mine").await.unwrap(), Some(1));
+}
@@ -0,0 +1,53 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{Correction, CorrectionStrategy, DictDb};
+
+async fn first(db: &DictDb, query: &str) -> Correction {
+    db.suggest_corrections(query, 5).await.unwrap().into_iter().next().unwrap()
+}
+
+#[tokio::test]
+async fn each_strategy() {
+    let db = build_sample_db().await;
+    let taberu = |strategy| Correction { suggestion: "たべる".to_string(), strategy, result_count: 1 };
+
+    assert_eq!(first(&db, "タベル").await, taberu(CorrectionStrategy::KanaConversion));
+    assert_eq!(first(&db, "taberu").await, taberu(CorrectionStrategy::Romaji));
+    assert_eq!(first(&db, "TABERU").await, taberu(CorrectionStrategy::Romaji));
+    // Substitution, deletion and insertion, also from katakana and romaji
+    for query in ["たべう", "たべ", "たべるる", "タベウ", "tabe"] {
+        assert_eq!(first(&db, query).await, taberu(CorrectionStrategy::EditDistance), "{}", query);
+    }
+    assert_eq!(
+        first(&db, "食べ物屋さん").await,
+        Correction { suggestion: "食べ物".to_string(), strategy: CorrectionStrategy::PrefixTruncation, result_count: 1 }
+    );
+}
+
+#[tokio::test]
+async fn nothing_for_headwords_and_hopeless_queries() {
+    let db = build_sample_db().await;
+    for query in ["たべる", "食べる"] {
+        assert!(db.suggest_corrections(query, 5).await.unwrap().is_empty(), "{}", query);
+    }
+    assert!(db.suggest_corrections("  ", 5).await.is_err());
+    assert!(db.suggest_corrections("xyzzy", 5).await.unwrap().is_empty());
+    assert!(db.suggest_corrections("タベル", 0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn ordered_by_strategy_and_limited() {
+    let db = build_sample_db().await;
+    // いぬ and いく are one edit from いぬく, which also starts with いぬ
+    let all = db.suggest_corrections("いぬく", 10).await.unwrap();
+    let strategies: Vec<CorrectionStrategy> = all.iter().map(|c| c.strategy).collect();
+    let mut sorted = strategies.clone();
+    sorted.sort_by_key(|s| *s as u8);
+    assert_eq!(strategies, sorted);
+    assert!(all.iter().any(|c| c.suggestion == "いぬ" && c.strategy == CorrectionStrategy::EditDistance));
+    assert!(all.iter().any(|c| c.suggestion == "いく" && c.strategy == CorrectionStrategy::EditDistance));
+    // Each suggestion once, under its best strategy
+    let mut suggestions: Vec<&str> = all.iter().map(|c| c.suggestion.as_str()).collect();
+    suggestions.dedup();
+    assert_eq!(suggestions.len(), all.len());
+    assert_eq!(db.suggest_corrections("いぬく", 1).await.unwrap(), all[..1]);
+}
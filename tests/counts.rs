@@ -0,0 +1,44 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::{CustomEntry, CustomSense, DictDb, DictError, DB_PATH_ENV};
+
+#[tokio::test]
+async fn counts_cover_the_main_database_unless_asked_otherwise() {
+    let dict = sample_dict();
+    let senses: usize = dict.entry.iter().map(|e| e.sense.len()).sum();
+
+    let db = build_sample_db().await;
+    assert_eq!(db.entry_count().await.unwrap(), dict.entry.len() as i64);
+    assert_eq!(db.sense_count().await.unwrap(), senses as i64);
+    assert!(!db.is_empty().await.unwrap());
+
+    // A user dictionary with one entry of two senses
+    let path = std::env::temp_dir().join(format!("jmdictdb-counts-user-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let user = DictDb::connect(&url).await.unwrap();
+    assert!(user.is_empty().await.unwrap());
+    let sense = CustomSense { gloss: vec!["mine".to_string()], ..Default::default() };
+    let entry = CustomEntry { rebs: vec!["じぶん".to_string()], senses: vec![sense.clone(), sense], ..Default::default() };
+    user.insert_custom_entry(&entry).await.unwrap();
+    user.close().await;
+
+    let _attachment = db.attach(&path, "user").await.unwrap();
+    assert_eq!(db.entry_count().await.unwrap(), dict.entry.len() as i64);
+    assert_eq!(db.entry_count_all().await.unwrap(), dict.entry.len() as i64 + 1);
+    assert_eq!(db.sense_count_all().await.unwrap(), senses as i64 + 2);
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn connect_default_reports_an_empty_database() {
+    let path = std::env::temp_dir().join(format!("jmdictdb-counts-empty-{}.db", std::process::id()));
+    DictDb::reset_database(&format!("sqlite:{}", path.display())).await.unwrap();
+    // The only test of this binary that reads the variable
+    unsafe { std::env::set_var(DB_PATH_ENV, &path) };
+
+    let err = DictDb::connect_default().await.unwrap_err();
+    assert_eq!(err.downcast_ref::<DictError>(), Some(&DictError::DatabaseEmpty(path.clone())));
+
+    let _ = std::fs::remove_file(path);
+}
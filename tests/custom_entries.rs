@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use jmdictdb::{CustomEntry, CustomSense, DictDb, Entry, EntryParsed, KEle, REle, RankOptions, Sense, CUSTOM_ENT_SEQ_START};
+
+fn db_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jmdictdb-custom-{}-{}.db", name, std::process::id()))
+}
+
+fn url(path: &Path) -> String {
+    format!("sqlite:{}?mode=rwc", path.display())
+}
+
+fn remove(path: &Path) {
+    for suffix in ["", "-shm", "-wal"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+fn custom(keb: &str, reb: &str, gloss: &str) -> CustomEntry {
+    CustomEntry {
+        kebs: vec![keb.into()],
+        rebs: vec![reb.into()],
+        senses: vec![CustomSense { pos: vec!["noun".into()], gloss: vec![gloss.into()] }],
+    }
+}
+
+fn official(ent_seq: &str, keb: &str, reb: &str, gloss: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.into(),
+        k_ele: Some(vec![KEle { keb: keb.into(), ..Default::default() }]),
+        r_ele: vec![REle { reb: reb.into(), ..Default::default() }],
+        sense: vec![Sense { pos: vec!["n".into()], gloss: vec![gloss.into()], ..Default::default() }],
+    }
+}
+
+/// The official data of the rebuilds.
+fn dictionary() -> Vec<Entry> {
+    vec![official("1467640", "猫", "ねこ", "cat"), official("1255430", "犬", "いぬ", "dog")]
+}
+
+/// What `build_db` does with an existing database: keep the custom entries, reset, build, restore.
+async fn rebuild(path: &Path) -> DictDb {
+    let old = DictDb::connect(&url(path)).await.unwrap();
+    let custom_entries = old.list_custom_entries().await.unwrap();
+    old.close().await;
+
+    DictDb::reset_database(&url(path)).await.unwrap();
+    let db = DictDb::connect(&url(path)).await.unwrap();
+    db.insert_entries(&dictionary()).await.unwrap();
+    db.restore_custom_entries(&custom_entries).await.unwrap();
+    db
+}
+
+async fn listed(db: &DictDb, ent_seq: i64) -> EntryParsed {
+    let entries = db.list_custom_entries().await.unwrap();
+    entries.into_iter().find(|e| e.ent_seq == ent_seq).unwrap()
+}
+
+async fn glosses(db: &DictDb, ent_seq: i64) -> Vec<String> {
+    listed(db, ent_seq).await.senses.into_iter().flat_map(|s| s.gloss).collect()
+}
+
+#[tokio::test]
+async fn custom_entries_survive_a_rebuild() {
+    let path = db_path("rebuild");
+    let db = DictDb::connect(&url(&path)).await.unwrap();
+    db.insert_entries(&dictionary()).await.unwrap();
+    let jargon = db.insert_custom_entry(&custom("社内用語", "しゃないようご", "in-house jargon")).await.unwrap();
+    // Shares its headword with the official 猫
+    let cat = db.insert_custom_entry(&custom("猫", "ねこ", "the office cat")).await.unwrap();
+    assert_eq!((jargon, cat), (CUSTOM_ENT_SEQ_START, CUSTOM_ENT_SEQ_START + 1));
+    db.close().await;
+
+    let db = rebuild(&path).await;
+    let seqs: Vec<i64> = db.list_custom_entries().await.unwrap().iter().map(|e| e.ent_seq).collect();
+    assert_eq!(seqs, [jargon, cat]);
+    assert_eq!(glosses(&db, jargon).await, ["in-house jargon"]);
+    assert_eq!(glosses(&db, cat).await, ["the office cat"]);
+    let entry = listed(&db, cat).await;
+    assert!(entry.is_custom());
+
+    // Found by the searches next to the official entries
+    let found = db.search_entries_with_senses_by_kanji("猫").await.unwrap();
+    assert!(found.iter().any(|e| e.ent_seq == cat));
+    assert!(found.iter().any(|e| e.ent_seq == 1467640 && !e.is_custom()));
+    let ranked = db.search_ranked("しゃないようご", &RankOptions::default()).await.unwrap();
+    assert_eq!(ranked[0].entry.ent_seq, jargon);
+    // The next custom entry continues after the restored ones
+    assert_eq!(db.insert_custom_entry(&custom("別", "べつ", "another")).await.unwrap(), cat + 1);
+    db.close().await;
+    remove(&path);
+}
+
+#[tokio::test]
+async fn official_data_never_replaces_a_custom_entry() {
+    let path = db_path("precedence");
+    let db = DictDb::connect(&url(&path)).await.unwrap();
+    let jargon = db.insert_custom_entry(&custom("社内用語", "しゃないようご", "in-house jargon")).await.unwrap();
+    db.close().await;
+
+    let db = rebuild(&path).await;
+    // An official entry claiming the ent_seq is refused, the custom entry stays as it was
+    let claim = Entry {
+        ent_seq: jargon.to_string(),
+        k_ele: None,
+        r_ele: vec![REle { reb: "しゃないようご".into(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec!["official".into()], ..Default::default() }],
+    };
+    assert!(db.insert_entries(&[claim]).await.is_err());
+    assert_eq!(glosses(&db, jargon).await, ["in-house jargon"]);
+
+    // Rebuilding again keeps it once
+    db.close().await;
+    let db = rebuild(&path).await;
+    assert_eq!(db.list_custom_entries().await.unwrap().len(), 1);
+    assert_eq!(glosses(&db, jargon).await, ["in-house jargon"]);
+    db.close().await;
+    remove(&path);
+}
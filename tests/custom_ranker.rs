@@ -0,0 +1,51 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{DefaultRanker, MatchContext, MatchKind, RankOptions, Ranker};
+use std::sync::Mutex;
+
+/// Ranks easier JLPT words first and records what it was given.
+#[derive(Default)]
+struct EasiestFirst {
+    seen: Mutex<Vec<MatchContext>>,
+}
+
+impl Ranker for EasiestFirst {
+    fn score(&self, ctx: &MatchContext) -> f64 {
+        self.seen.lock().unwrap().push(ctx.clone());
+        ctx.jlpt_level.map_or(0.0, f64::from)
+    }
+}
+
+#[tokio::test]
+async fn default_ranker_reproduces_search_ranked() {
+    let db = build_sample_db().await;
+    for query in ["た", "学", "食べる", "み"] {
+        let ranked = db.search_ranked(query, &RankOptions::default()).await.unwrap();
+        let with = db.search_ranked_with(query, &DefaultRanker, 20).await.unwrap();
+        let key = |results: &[jmdictdb::ScoredEntry]| {
+            results.iter().map(|r| (r.entry.ent_seq, r.match_kind, r.score)).collect::<Vec<_>>()
+        };
+        assert_eq!(key(&with), key(&ranked), "{}", query);
+    }
+    assert!(db.search_ranked_with(" ", &DefaultRanker, 20).await.is_err());
+    assert_eq!(db.search_ranked_with("た", &DefaultRanker, 2).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn custom_ranker_sees_every_scoring_input() {
+    let db = build_sample_db().await;
+    db.load_jlpt_levels("学校\t\tN4\n学生\t\tN5\n".as_bytes()).await.unwrap();
+
+    let ranker = EasiestFirst::default();
+    let results = db.search_ranked_with("学", &ranker, 10).await.unwrap();
+    let seqs: Vec<i64> = results.iter().map(|r| r.entry.ent_seq).collect();
+    assert_eq!(&seqs[..2], [1198870, 1198180]);
+    assert_eq!(results[0].score, 5.0);
+
+    let seen = ranker.seen.lock().unwrap();
+    let school = seen.iter().find(|c| c.ent_seq == 1198180).unwrap();
+    assert_eq!(school.match_kind, MatchKind::Prefix);
+    assert_eq!(school.jlpt_level, Some(4));
+    assert_eq!(school.headword_len, 2);
+    assert!(school.priority.iter().any(|p| p == "ichi1"));
+    assert_eq!(seen.len(), seqs.len());
+}
@@ -0,0 +1,86 @@
+use jmdictdb::{dedupe_by_reading_and_gloss, DictDb, Entry, EntryParsed, KEle, RankOptions, REle, Sense};
+
+fn word(ent_seq: i64, keb: &str, reb: &str, glosses: &[&str], pri: &[&str]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle {
+            keb: keb.to_string(),
+            ke_pri: pri.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }]),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: glosses.iter().map(|g| g.to_string()).collect(), ..Default::default() }],
+    }
+}
+
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[
+        // A variant spelling split into an entry of its own
+        word(1, "嘘", "うそ", &["lie", "falsehood"], &["ichi1"]),
+        word(2, "噓", "うそ", &["Lie", "fib"], &[]),
+        // Same reading, other meaning
+        word(3, "鷽", "うそ", &["Eurasian bullfinch"], &[]),
+        // Half of the shorter gloss list, but another reading
+        word(4, "虚言", "きょげん", &["lie"], &[]),
+        word(5, "早い", "はやい", &["early", "premature"], &["ichi1"]),
+        word(6, "速い", "はやい", &["fast", "quick"], &["ichi1"]),
+        word(7, "夙い", "はやい", &["early", "soon", "in good time"], &[]),
+    ])
+    .await
+    .unwrap();
+    db
+}
+
+async fn entries(db: &DictDb, seqs: &[i64]) -> Vec<EntryParsed> {
+    let mut entries = Vec::new();
+    for &seq in seqs {
+        entries.push(db.entry_by_seq(seq).await.unwrap().unwrap());
+    }
+    entries
+}
+
+fn clusters(merged: &[jmdictdb::MergedResult]) -> Vec<(i64, Vec<i64>)> {
+    merged.iter().map(|m| (m.primary.ent_seq, m.variants.iter().map(|v| v.ent_seq).collect())).collect()
+}
+
+#[tokio::test]
+async fn merges_same_reading_with_shared_glosses() {
+    let db = db().await;
+    let merged = dedupe_by_reading_and_gloss(entries(&db, &[1, 2, 3, 4, 5, 6, 7]).await);
+    assert_eq!(clusters(&merged), [(1, vec![2]), (3, vec![]), (4, vec![]), (5, vec![7]), (6, vec![])]);
+    assert!(dedupe_by_reading_and_gloss(Vec::new()).is_empty());
+}
+
+#[tokio::test]
+async fn the_input_order_picks_the_primary() {
+    let db = db().await;
+    let merged = dedupe_by_reading_and_gloss(entries(&db, &[7, 6, 2, 5, 1]).await);
+    assert_eq!(clusters(&merged), [(7, vec![5]), (6, vec![]), (2, vec![1])]);
+    // The same input gives the same clusters
+    let again = dedupe_by_reading_and_gloss(entries(&db, &[7, 6, 2, 5, 1]).await);
+    assert_eq!(merged, again);
+}
+
+#[tokio::test]
+async fn ranked_search_merges_only_when_asked() {
+    let db = db().await;
+    let plain = db.search_ranked("うそ", &RankOptions::default()).await.unwrap();
+    let seqs: Vec<i64> = plain.iter().map(|r| r.entry.ent_seq).collect();
+    assert_eq!(seqs, [1, 2, 3]);
+    assert!(plain.iter().all(|r| r.variants.is_empty()));
+
+    let opts = RankOptions { dedupe: true, ..RankOptions::default() };
+    let merged = db.search_ranked("うそ", &opts).await.unwrap();
+    let seqs: Vec<i64> = merged.iter().map(|r| r.entry.ent_seq).collect();
+    assert_eq!(seqs, [1, 3]);
+    assert_eq!(merged[0].variants.len(), 1);
+    assert_eq!(merged[0].variants[0].entry.ent_seq, 2);
+    assert_eq!(merged[0].to_json_value()["variants"][0]["entry"]["ent_seq"], 2);
+    assert!(merged[1].to_json_value().get("variants").is_none());
+
+    // The limit counts merged results
+    let one = db.search_ranked("はやい", &RankOptions { limit: 1, ..opts.clone() }).await.unwrap();
+    assert_eq!(one.len(), 1);
+    assert_eq!(one[0].entry.ent_seq, 5);
+}
@@ -0,0 +1,53 @@
+use jmdictdb::{DictDb, Entry, REle, Sense};
+use sqlx::SqlitePool;
+
+fn entry(ent_seq: i64, reb: &str, gloss: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![
+            Sense { gloss: vec![gloss.to_string()], ..Default::default() },
+            Sense { gloss: vec![format!("{} (2)", gloss)], ..Default::default() },
+        ],
+        ..Default::default()
+    }
+}
+
+async fn sense_count(url: &str, ent_seq: i64) -> i64 {
+    // A separate pool, so the check does not depend on the connection that did the delete
+    let pool = SqlitePool::connect(url).await.unwrap();
+    let n = sqlx::query_scalar("SELECT COUNT(*) FROM senses WHERE ent_seq = ?1")
+        .bind(ent_seq)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    pool.close().await;
+    n
+}
+
+#[tokio::test]
+async fn delete_cascades_to_senses() {
+    let path = std::env::temp_dir().join(format!("jmdictdb-delete-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+
+    for (ent_seq, reb) in [(1, "いち"), (2, "に"), (3, "さん")] {
+        assert!(db.upsert_entry(&entry(ent_seq, reb, reb)).await.unwrap());
+    }
+    assert_eq!(sense_count(&url, 1).await, 2);
+
+    // Run the deletes concurrently so they are spread over several pooled connections
+    let (a, b) = tokio::join!(db.delete_entry(1), db.delete_entries(&[2, 3, 4]));
+    assert!(a.unwrap());
+    assert_eq!(b.unwrap(), 2);
+    assert!(!db.delete_entry(1).await.unwrap());
+
+    for ent_seq in 1..=3 {
+        assert_eq!(sense_count(&url, ent_seq).await, 0);
+    }
+    assert!(db.search_entries_with_senses_by_reading("いち").await.unwrap().is_empty());
+
+    db.close().await;
+    let _ = std::fs::remove_file(&path);
+}
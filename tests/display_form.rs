@@ -0,0 +1,54 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{parse_entry_xml, EntryParsed, USUALLY_KANA};
+
+#[tokio::test]
+async fn display_form_per_kind_of_entry() {
+    let db = build_sample_db().await;
+    let by_seq = |ent_seq| {
+        let db = &db;
+        async move { db.entry_by_seq(ent_seq).await.unwrap().unwrap() }
+    };
+
+    // Kana only
+    let suru = by_seq(1157170).await;
+    assert!(suru.is_kana_only());
+    assert!(!suru.is_usually_kana());
+    assert_eq!(suru.display_form(), "する");
+
+    // Kanji forms that are usually not used
+    let arigatou = by_seq(1394680).await;
+    assert!(!arigatou.is_kana_only());
+    assert!(arigatou.is_usually_kana());
+    assert_eq!(arigatou.senses[0].misc, [USUALLY_KANA]);
+    assert_eq!(arigatou.display_form(), "ありがとう");
+
+    // Written with kanji
+    let taberu = by_seq(1358280).await;
+    assert!(!taberu.is_kana_only() && !taberu.is_usually_kana());
+    assert!(taberu.senses.iter().all(|s| s.misc.is_empty()));
+    assert_eq!(taberu.display_form(), "食べる");
+}
+
+#[test]
+fn only_the_first_sense_decides() {
+    // Without the DTD the tag stays "uk"
+    let entry = parse_entry_xml(
+        "<entry><ent_seq>1</ent_seq><k_ele><keb>矢張り</keb></k_ele><r_ele><reb>やはり</reb></r_ele>\
+         <sense><pos>&adv;</pos><misc>&uk;</misc><gloss>as expected</gloss></sense>\
+         <sense><gloss>also</gloss></sense></entry>",
+    )
+    .unwrap();
+    let yahari = EntryParsed::try_from(&entry).unwrap();
+    assert_eq!(yahari.senses[0].misc, ["uk"]);
+    assert_eq!(yahari.display_form(), "やはり");
+
+    let entry = parse_entry_xml(
+        "<entry><ent_seq>2</ent_seq><k_ele><keb>丁度</keb></k_ele><r_ele><reb>ちょうど</reb></r_ele>\
+         <sense><gloss>exactly</gloss></sense><sense><misc>&uk;</misc><gloss>just</gloss></sense></entry>",
+    )
+    .unwrap();
+    let choudo = EntryParsed::try_from(&entry).unwrap();
+    assert!(!choudo.is_usually_kana());
+    assert!(choudo.senses[1].is_usually_kana());
+    assert_eq!(choudo.display_form(), "丁度");
+}
@@ -0,0 +1,45 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{parse_entry_xml, PartOfSpeech};
+
+fn count_of(counts: &[(String, i64)], tag: &str) -> Option<i64> {
+    counts.iter().find(|(t, _)| t == tag).map(|&(_, n)| n)
+}
+
+#[tokio::test]
+async fn counts_senses_per_tag() {
+    let db = build_sample_db().await;
+    let pos = db.distinct_pos().await.unwrap();
+    assert_eq!(pos.len(), 35);
+    assert_eq!(pos[0], (PartOfSpeech::Noun.description().to_string(), 168));
+    assert_eq!(count_of(&pos, "Ichidan verb"), Some(14));
+    assert_eq!(count_of(&pos, "adjective (keiyoushi)"), Some(33));
+    // Most frequent first, ties by tag
+    assert!(pos.windows(2).all(|w| w[0].1 > w[1].1 || (w[0].1 == w[1].1 && w[0].0 < w[1].0)));
+
+    assert_eq!(db.distinct_misc().await.unwrap(), [("word usually written using kana alone".to_string(), 11)]);
+    assert_eq!(db.distinct_fields().await.unwrap(), [("food, cooking".to_string(), 1)]);
+    assert!(db.distinct_dialects().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn follows_writes() {
+    let db = build_sample_db().await;
+    assert!(db.distinct_dialects().await.unwrap().is_empty());
+    let before = count_of(&db.distinct_pos().await.unwrap(), "interjection (kandoushi)").unwrap();
+
+    // Two senses, the second inheriting the part of speech
+    let ookini = parse_entry_xml(
+        "<entry><ent_seq>2999991</ent_seq><r_ele><reb>おおきに</reb></r_ele>\
+         <sense><pos>&int;</pos><dial>&ksb;</dial><dial>&ksb;</dial><gloss>thanks</gloss></sense>\
+         <sense><dial>&kyb;</dial><gloss>very much</gloss></sense></entry>",
+    )
+    .unwrap();
+    db.insert_entries(&[ookini]).await.unwrap();
+    let dialects = db.distinct_dialects().await.unwrap();
+    assert_eq!(dialects, [("ksb".to_string(), 1), ("kyb".to_string(), 1)]);
+    assert_eq!(count_of(&db.distinct_pos().await.unwrap(), "interjection (kandoushi)"), Some(before + 2));
+
+    db.delete_entry(2999991).await.unwrap();
+    assert!(db.distinct_dialects().await.unwrap().is_empty());
+    assert_eq!(count_of(&db.distinct_pos().await.unwrap(), "interjection (kandoushi)"), Some(before));
+}
@@ -0,0 +1,51 @@
+use jmdictdb::entities::{parse_dtd_entities, standard_name, STANDARD, STANDARD_DTD};
+use jmdictdb::fixtures::{multilingual_xml, sample_xml};
+use jmdictdb::PartOfSpeech;
+
+#[test]
+fn the_fixture_dtds_agree_with_the_standard_set() {
+    for xml in [sample_xml(), multilingual_xml()] {
+        let entities = parse_dtd_entities(xml);
+        assert!(!entities.is_empty());
+        for (name, value) in &entities {
+            assert_eq!(STANDARD.get(name.as_str()), Some(&value.as_str()), "{}", name);
+        }
+    }
+    assert_eq!(parse_dtd_entities(sample_xml())["v5k-s"], "Godan verb - Iku/Yuku special class");
+}
+
+#[test]
+fn the_standard_set_is_the_parsed_dtd() {
+    let parsed = parse_dtd_entities(STANDARD_DTD);
+    assert_eq!(parsed.len(), STANDARD.len());
+    assert!(parsed.iter().all(|(name, value)| STANDARD[name.as_str()] == value));
+    assert!(STANDARD.len() > 250, "{}", STANDARD.len());
+
+    // Every category of tags is there
+    assert_eq!(STANDARD["ksb"], "Kansai-ben");
+    assert_eq!(STANDARD["comp"], "computing");
+    assert_eq!(STANDARD["uk"], "word usually written using kana alone");
+    assert_eq!(STANDARD["iK"], "word containing irregular kanji usage");
+    assert_eq!(STANDARD["ok"], "out-dated or obsolete kana usage");
+    assert_eq!(standard_name("adjective (keiyoushi)"), Some("adj-i"));
+    assert_eq!(standard_name("no such tag"), None);
+}
+
+#[test]
+fn the_standard_set_matches_the_parts_of_speech() {
+    for pos in PartOfSpeech::ALL {
+        assert_eq!(STANDARD.get(pos.code()), Some(&pos.description()), "{}", pos.code());
+    }
+}
+
+#[test]
+fn parses_a_dtd_on_its_own() {
+    let dtd = r#"<!ENTITY n "noun">
+                 <!ENTITY  v1   "Ichidan verb">
+                 <!-- <!ENTITY commented "out"> is a declaration to a regex too -->
+                 <!ENTITY n "noun, again">"#;
+    let entities = parse_dtd_entities(dtd);
+    assert_eq!(entities["n"], "noun");
+    assert_eq!(entities["v1"], "Ichidan verb");
+    assert!(parse_dtd_entities("<JMdict></JMdict>").is_empty());
+}
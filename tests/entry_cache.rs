@@ -0,0 +1,219 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::{DictDb, DictDbOptions, EntryCacheStats, QueryOptions, ScanOptions};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the allocations of the process, for `bench_scan_text_allocations`.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The sample dictionary with an entry cache of `capacity` entries.
+async fn cached_db(capacity: usize) -> DictDb {
+    let db = DictDbOptions::new("sqlite::memory:").entry_cache(capacity).connect().await.unwrap();
+    db.insert_entries(&sample_dict().entry).await.unwrap();
+    db
+}
+
+const NEKO: i64 = 1469800;
+const TABERU: i64 = 1358280;
+const MIRU: i64 = 1225970;
+
+#[tokio::test]
+async fn lookups_share_one_entry() {
+    let db = cached_db(10).await;
+    let first = db.entry_shared(NEKO).await.unwrap().unwrap();
+    let again = db.entry_shared(NEKO).await.unwrap().unwrap();
+    assert!(Arc::ptr_eq(&first, &again));
+    assert!(first.content_eq(&db.entry_by_seq(NEKO).await.unwrap().unwrap()));
+    assert_eq!(db.entry_cache_stats(), EntryCacheStats { entries: 1, capacity: 10, hits: 1, misses: 1 });
+
+    // Missing entries aren't kept
+    assert!(db.entry_shared(1).await.unwrap().is_none());
+    assert!(db.entry_shared(1).await.unwrap().is_none());
+    assert_eq!(db.entry_cache_stats().entries, 1);
+}
+
+#[tokio::test]
+async fn the_least_recently_used_entry_goes_first() {
+    let db = cached_db(2).await;
+    let neko = db.entry_shared(NEKO).await.unwrap().unwrap();
+    let taberu = db.entry_shared(TABERU).await.unwrap().unwrap();
+    // 猫 is used again, so 食べる is the one making room for 見る
+    db.entry_shared(NEKO).await.unwrap();
+    db.entry_shared(MIRU).await.unwrap();
+    assert_eq!(db.entry_cache_stats().entries, 2);
+    assert!(Arc::ptr_eq(&neko, &db.entry_shared(NEKO).await.unwrap().unwrap()));
+    assert!(!Arc::ptr_eq(&taberu, &db.entry_shared(TABERU).await.unwrap().unwrap()));
+    assert_eq!(db.entry_cache_stats().entries, 2);
+}
+
+#[tokio::test]
+async fn writes_clear_the_cache() {
+    let db = cached_db(10).await;
+    let kept = db.entry_shared(NEKO).await.unwrap().unwrap();
+
+    let mut changed = sample_dict().entry.into_iter().find(|e| e.ent_seq == NEKO.to_string()).unwrap();
+    changed.sense[0].gloss = vec!["feline".to_string()];
+    db.upsert_entry(&changed).await.unwrap();
+    assert_eq!(db.entry_cache_stats().entries, 0);
+    let upserted = db.entry_shared(NEKO).await.unwrap().unwrap();
+    assert_eq!(upserted.senses[0].gloss, ["feline"]);
+    // Entries handed out before stay as they were
+    assert_ne!(kept.senses[0].gloss, ["feline"]);
+
+    let mut tx = db.begin().await.unwrap();
+    tx.delete_entry(NEKO).await.unwrap();
+    assert!(db.entry_shared(NEKO).await.unwrap().is_some());
+    tx.commit().await.unwrap();
+    assert!(db.entry_shared(NEKO).await.unwrap().is_none());
+
+    db.entry_shared(TABERU).await.unwrap();
+    db.load_jlpt_levels("食べる\tたべる\tN5\n".as_bytes()).await.unwrap();
+    assert_eq!(db.entry_shared(TABERU).await.unwrap().unwrap().jlpt_level, Some(5));
+    db.clear_entry_cache();
+    assert_eq!(db.entry_cache_stats().entries, 0);
+}
+
+#[tokio::test]
+async fn limited_entries_are_not_cached() {
+    let db = cached_db(10).await;
+    let opts = QueryOptions { max_senses: Some(1), ..Default::default() };
+    let cut = db.with_query_options(&opts, db.entry_shared(MIRU)).await.unwrap().unwrap();
+    assert!(cut.senses_truncated);
+    assert_eq!(db.entry_cache_stats().entries, 0);
+
+    let whole = db.entry_shared(MIRU).await.unwrap().unwrap();
+    assert!(!whole.senses_truncated);
+    let cut = db.with_query_options(&opts, db.entry_shared(MIRU)).await.unwrap().unwrap();
+    assert_eq!(cut.senses.len(), 1);
+
+    // Nor are entries with the glosses of their references
+    db.clear_entry_cache();
+    let resolving = QueryOptions { resolve_references: true, ..Default::default() };
+    db.with_query_options(&resolving, db.entry_shared(MIRU)).await.unwrap().unwrap();
+    assert_eq!(db.entry_cache_stats().entries, 0);
+}
+
+#[tokio::test]
+async fn without_a_cache_every_lookup_reads() {
+    let db = build_sample_db().await;
+    let first = db.entry_shared(NEKO).await.unwrap().unwrap();
+    let again = db.entry_shared(NEKO).await.unwrap().unwrap();
+    assert!(!Arc::ptr_eq(&first, &again));
+    assert!(first.content_eq(&again));
+    assert_eq!(db.entry_cache_stats(), EntryCacheStats { entries: 0, capacity: 0, hits: 0, misses: 2 });
+}
+
+#[tokio::test]
+async fn owned_copies_leave_the_cache_alone() {
+    let db = cached_db(10).await;
+    let shared = db.entry_shared(NEKO).await.unwrap().unwrap();
+    let mut owned = Arc::unwrap_or_clone(shared);
+    assert!(owned.content_eq(&db.entry_by_seq(NEKO).await.unwrap().unwrap()));
+    owned.senses.clear();
+    assert!(!db.entry_shared(NEKO).await.unwrap().unwrap().senses.is_empty());
+}
+
+#[tokio::test]
+async fn other_writes_keep_the_cache() {
+    let db = cached_db(10).await;
+    db.entry_shared(NEKO).await.unwrap();
+
+    let mut broken = sample_dict().entry.into_iter().find(|e| e.ent_seq == NEKO.to_string()).unwrap();
+    broken.ent_seq = "neko".to_string();
+    assert!(db.upsert_entry(&broken).await.is_err());
+    assert_eq!(db.entry_cache_stats().entries, 1);
+
+    #[cfg(feature = "userdata")]
+    {
+        let list = db.create_list("animals").await.unwrap();
+        db.add_to_list(list, NEKO, None).await.unwrap();
+        db.record_lookup(NEKO);
+        db.clear_history().await.unwrap();
+        assert_eq!(db.entry_cache_stats().entries, 1);
+    }
+}
+
+/// About a chapter of text, 400 sentences of the sample words with the common ones coming
+/// back as they do in prose.
+fn chapter() -> String {
+    let times = ["今日", "明日", "昨日", "今", "朝", "夜", "春", "冬"];
+    let people = ["私", "先生", "友達", "母", "父", "子供", "学生", "医者", "猫", "犬"];
+    let places = ["学校", "家", "駅", "部屋", "大学", "病院", "図書館", "喫茶店", "会社", "町"];
+    let things = ["本", "新聞", "お茶", "水", "ご飯", "寿司", "写真", "辞書", "漢字", "音楽", "映画", "料理"];
+    let verbs = ["食べる", "飲む", "読む", "書く", "見る", "買う", "持つ", "待つ", "聞く", "話す"];
+    let endings = ["", "天気は良い。", "とても面白い。", "少し難しい。", "時々雨。", "静かで好き。"];
+    (0..400)
+        .map(|i| {
+            format!(
+                "{}、{}は{}で{}を{}。{}",
+                times[i % times.len()],
+                people[i * 7 % people.len()],
+                places[i * 3 % places.len()],
+                things[i * 5 % things.len()],
+                verbs[i * 11 % verbs.len()],
+                endings[i % endings.len()],
+            )
+        })
+        .collect()
+}
+
+/// The allocations made by `run`, in number and bytes.
+async fn allocations<F: Future<Output = ()>>(run: F) -> (usize, usize) {
+    let (count, bytes) = (ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed));
+    run.await;
+    (ALLOCATIONS.load(Ordering::Relaxed) - count, ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes)
+}
+
+// cargo test --release --test entry_cache -- --ignored --nocapture
+#[tokio::test]
+#[ignore = "benchmark"]
+async fn bench_scan_text_allocations() {
+    let text = chapter();
+    let db = cached_db(1000).await;
+    let matches = db.scan_text(&text, &ScanOptions::default()).await.unwrap();
+    let mut distinct: Vec<i64> = matches.iter().map(|m| m.ent_seq).collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    println!("{} chars, {} matches of {} entries", text.chars().count(), matches.len(), distinct.len());
+
+    let report = |label: &str, (count, bytes): (usize, usize)| {
+        println!("{:>12}: {:>9} allocations, {:>6} KiB", label, count, bytes / 1024);
+    };
+    report("scan only", allocations(async {
+        db.scan_text(&text, &ScanOptions::default()).await.unwrap();
+    }).await);
+    report("by value", allocations(async {
+        for m in db.scan_text(&text, &ScanOptions::default()).await.unwrap() {
+            db.entry_by_seq(m.ent_seq).await.unwrap().unwrap();
+        }
+    }).await);
+    report("shared, cold", allocations(async {
+        for m in db.scan_text(&text, &ScanOptions::default()).await.unwrap() {
+            db.entry_shared(m.ent_seq).await.unwrap().unwrap();
+        }
+    }).await);
+    report("shared, warm", allocations(async {
+        for m in db.scan_text(&text, &ScanOptions::default()).await.unwrap() {
+            db.entry_shared(m.ent_seq).await.unwrap().unwrap();
+        }
+    }).await);
+}
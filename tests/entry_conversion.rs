@@ -0,0 +1,26 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::EntryParsed;
+
+#[tokio::test]
+async fn conversion_matches_database_round_trip() {
+    let dict = sample_dict();
+    let db = build_sample_db().await;
+
+    for entry in &dict.entry {
+        let reading = &entry.r_ele[0].reb;
+        let mut converted = EntryParsed::try_from(entry).unwrap();
+        // The search marks the senses restricted to other readings
+        for sense in &mut converted.senses {
+            sense.applies_to_query = sense.applies_to(None, Some(reading));
+        }
+        let found = db.search_entries_with_senses_by_reading(reading).await.unwrap();
+        let stored = found.into_iter().find(|e| e.ent_seq == converted.ent_seq).unwrap();
+        assert!(converted.content_eq(&stored), "entry {}: {:?} != {:?}", entry.ent_seq, converted, stored);
+    }
+}
+
+#[test]
+fn invalid_ent_seq_is_an_error() {
+    let entry = jmdictdb::Entry { ent_seq: "x".to_string(), ..Default::default() };
+    assert!(EntryParsed::try_from(&entry).is_err());
+}
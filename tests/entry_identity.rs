@@ -0,0 +1,53 @@
+use std::collections::{BTreeSet, HashSet};
+
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{EntryParsed, QueryOptions, RankOptions};
+
+fn seqs(entries: &[EntryParsed]) -> Vec<i64> {
+    entries.iter().map(|e| e.ent_seq).collect()
+}
+
+#[tokio::test]
+async fn entries_are_keyed_on_ent_seq() {
+    let db = build_sample_db().await;
+    let taberu = db.entry_by_seq(1358280).await.unwrap().unwrap();
+    let cut = QueryOptions { max_senses: Some(1), ..Default::default() };
+    let first_sense = db.with_query_options(&cut, db.entry_by_seq(1358280)).await.unwrap().unwrap();
+    assert_eq!(taberu, first_sense);
+    assert!(!taberu.content_eq(&first_sense));
+    assert!(taberu.content_eq(&db.entry_by_seq(1358280).await.unwrap().unwrap()));
+
+    let neko = db.entry_by_seq(1469800).await.unwrap().unwrap();
+    let set: HashSet<EntryParsed> = [taberu.clone(), first_sense, neko.clone()].into_iter().collect();
+    assert_eq!(set.len(), 2);
+    let ordered: BTreeSet<EntryParsed> = [neko, taberu].into_iter().collect();
+    assert_eq!(ordered.iter().map(|e| e.ent_seq).collect::<Vec<_>>(), [1358280, 1469800]);
+}
+
+#[tokio::test]
+async fn searches_return_the_same_order_every_time() {
+    let db = build_sample_db().await;
+    for _ in 0..2 {
+        let by_reading = db.search_entries_with_senses_by_reading("かぜ").await.unwrap();
+        assert_eq!(by_reading.len(), 2);
+        let mut sorted = seqs(&by_reading);
+        sorted.sort();
+        assert_eq!(seqs(&by_reading), sorted);
+
+        let by_kanji = db.search_entries_with_senses_by_kanji("風").await.unwrap();
+        assert!(!by_kanji.is_empty());
+        let mut sorted = seqs(&by_kanji);
+        sorted.sort();
+        assert_eq!(seqs(&by_kanji), sorted);
+    }
+
+    let opts = RankOptions::default();
+    let search = || db.search_ranked("た", &opts);
+    let (first, second) = (search().await.unwrap(), search().await.unwrap());
+    let (first, second): (Vec<EntryParsed>, Vec<EntryParsed>) =
+        (first.into_iter().map(|r| r.entry).collect(), second.into_iter().map(|r| r.entry).collect());
+    assert_eq!(seqs(&first), seqs(&second));
+    assert!(first.iter().zip(&second).all(|(a, b)| a.content_eq(b)));
+    let by_gloss = db.search_entries_by_gloss("to eat", "eng").await.unwrap();
+    assert_eq!(seqs(&by_gloss), seqs(&db.search_entries_by_gloss("to eat", "eng").await.unwrap()));
+}
@@ -0,0 +1,138 @@
+use crc::{Crc, CRC_32_ISO_HDLC};
+use jmdictdb::ereader::kobo_prefix;
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::{EreaderFormat, EreaderOptions, PartOfSpeech, SearchFilter};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+fn out_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jmdictdb-ereader-{}-{}", name, std::process::id()))
+}
+
+fn u16_at(b: &[u8], at: usize) -> usize {
+    u16::from_le_bytes([b[at], b[at + 1]]) as usize
+}
+
+fn u32_at(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(b[at..at + 4].try_into().unwrap())
+}
+
+/// The files of a zip of stored files by name, checking each checksum and that the central
+/// directory lists them all.
+fn read_zip(zip: &[u8]) -> BTreeMap<String, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let mut at = 0;
+    while u32_at(zip, at) == 0x04034b50 {
+        assert_eq!(u16_at(zip, at + 8), 0, "stored");
+        let (crc, size, name_len) = (u32_at(zip, at + 14), u32_at(zip, at + 18) as usize, u16_at(zip, at + 26));
+        let name = String::from_utf8(zip[at + 30..at + 30 + name_len].to_vec()).unwrap();
+        let data = zip[at + 30 + name_len..at + 30 + name_len + size].to_vec();
+        assert_eq!(CRC32.checksum(&data), crc, "{}", name);
+        files.insert(name, data);
+        at += 30 + name_len + size;
+    }
+    let end = zip.len() - 22;
+    assert_eq!(u32_at(zip, end), 0x06054b50);
+    assert_eq!(u16_at(zip, end + 10), files.len());
+    assert_eq!(u32_at(zip, end + 16) as usize, at, "the central directory follows the files");
+    files
+}
+
+/// The content of a gzip file of stored deflate blocks.
+fn gunzip_stored(gz: &[u8]) -> String {
+    assert_eq!(&gz[..3], &[0x1f, 0x8b, 8]);
+    let mut data = Vec::new();
+    let mut at = 10;
+    loop {
+        let last = gz[at] & 1 == 1;
+        let len = u16_at(gz, at + 1);
+        assert_eq!(len as u16, !u16::from_le_bytes([gz[at + 3], gz[at + 4]]));
+        data.extend(&gz[at + 5..at + 5 + len]);
+        at += 5 + len;
+        if last {
+            break;
+        }
+    }
+    assert_eq!(u32_at(gz, at), CRC32.checksum(&data));
+    assert_eq!(u32_at(gz, at + 4) as usize, data.len());
+    String::from_utf8(data).unwrap()
+}
+
+#[tokio::test]
+async fn inflected_forms_come_from_the_conjugation_tables() {
+    let db = build_sample_db().await;
+    let taberu = db.search_entries_with_senses_by_kanji("食べる").await.unwrap().remove(0);
+    let forms = taberu.inflected_forms();
+    for form in ["食べない", "たべない", "食べた", "食べなかった", "喰べます", "たべさせる"] {
+        assert!(forms.contains(&form.to_string()), "{} in {:?}", form, forms);
+    }
+    assert!(!forms.iter().any(|f| f == "食べる" || f == "たべる"));
+    let table = taberu.conjugations().unwrap();
+    assert!(table.forms.iter().all(|(_, c)| forms.contains(&c.kanji) || taberu.kebs.iter().flatten().any(|k| *k == c.kanji)));
+
+    let neko = db.search_entries_with_senses_by_reading("ねこ").await.unwrap().remove(0);
+    assert!(neko.inflected_forms().is_empty());
+}
+
+#[tokio::test]
+async fn kobo_files_hold_the_entries_of_their_prefix() {
+    let db = build_sample_db().await;
+    let path = out_path("kobo.zip");
+    let report = db.export_ereader(EreaderFormat::Kobo, &path, &EreaderOptions::default()).await.unwrap();
+    assert_eq!(report.entries, sample_dict().entry.len());
+    assert!(report.inflections > 0);
+
+    let files = read_zip(&std::fs::read(&path).unwrap());
+    assert_eq!(files.len(), report.files);
+    let html: BTreeMap<String, String> = files.iter().map(|(name, gz)| (name.clone(), gunzip_stored(gz))).collect();
+    for (name, body) in &html {
+        assert!(body.starts_with("<html><w>") && body.ends_with("</w></html>"), "{}", name);
+    }
+
+    // 食べる is under its own prefix and under those of its reading and inflected forms
+    let taberu = "<w><a name=\"食べる\" /><var><variant name=\"喰べる\"/><variant name=\"たべる\"/>";
+    for prefix in ["食べ", "喰べ", "たべ"] {
+        let body = &html[&format!("{}.html", prefix)];
+        assert!(body.contains(taberu), "{}", prefix);
+    }
+    assert!(html["食べ.html"].contains("<variant name=\"食べなかった\"/>"));
+    // A word of one character goes in the file with "a" after it
+    assert!(html["木a.html"].contains("<a name=\"木\" />"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn kobo_prefixes() {
+    assert_eq!(kobo_prefix("食べる"), "食べ");
+    assert_eq!(kobo_prefix("木"), "木a");
+    assert_eq!(kobo_prefix("Tシャツ"), "tシ");
+    assert_eq!(kobo_prefix("１つ"), "11");
+    assert_eq!(kobo_prefix(""), "11");
+}
+
+#[tokio::test]
+async fn the_kindle_source_has_an_inflection_index() {
+    let db = build_sample_db().await;
+    let dir = out_path("kindle");
+    let opts = EreaderOptions { filter: SearchFilter::new().pos(PartOfSpeech::V1), ..EreaderOptions::default() };
+    let report = db.export_ereader(EreaderFormat::KindleSource, &dir, &opts).await.unwrap();
+    assert_eq!(report.entries as i64, db.count_by_pos(&PartOfSpeech::V1).await.unwrap());
+    assert_eq!(report.files, 1);
+
+    let opf = std::fs::read_to_string(dir.join("jmdict.opf")).unwrap();
+    assert!(opf.contains("<DictionaryInLanguage>ja</DictionaryInLanguage>"));
+    assert!(opf.contains("<DictionaryOutLanguage>en</DictionaryOutLanguage>"));
+    assert!(opf.contains("<item id=\"content-1.html\" href=\"content-1.html\""));
+    assert!(opf.contains("<itemref idref=\"content-1.html\"/>"));
+
+    let content = std::fs::read_to_string(dir.join("content-1.html")).unwrap();
+    assert_eq!(content.matches("<idx:entry ").count(), report.entries);
+    assert!(content.contains("<idx:orth value=\"食べる\"><idx:infl><idx:iform value=\"喰べる\"/><idx:iform value=\"たべる\"/>"));
+    assert!(content.contains("<idx:iform value=\"食べなかった\"/>"));
+    assert!(content.contains("<idx:iform value=\"たべよう\"/>"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
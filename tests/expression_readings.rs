@@ -0,0 +1,76 @@
+use jmdictdb::{align_furigana, parse_entry_xml, DictDb, EntryParsed, FuriganaSegment};
+
+// A made-up entry whose readings each go with one of the kebs
+const ASHITA: &str = "<entry><ent_seq>1000020</ent_seq>\
+    <k_ele><keb>明日</keb></k_ele><k_ele><keb>翌日</keb></k_ele>\
+    <r_ele><reb>あした</reb><re_restr>明日</re_restr></r_ele>\
+    <r_ele><reb>あす</reb><re_restr>明日</re_restr></r_ele>\
+    <r_ele><reb>よくじつ</reb><re_restr>翌日</re_restr></r_ele>\
+    <sense><gloss>tomorrow</gloss></sense></entry>";
+
+const KI_WO_TSUKERU: &str = "<entry><ent_seq>1591050</ent_seq>\
+    <k_ele><keb>気を付ける</keb></k_ele><k_ele><keb>気をつける</keb></k_ele>\
+    <r_ele><reb>きをつける</reb></r_ele>\
+    <sense><pos>&exp;</pos><pos>&v1;</pos><gloss>to be careful</gloss></sense></entry>";
+
+fn seg(text: &str, ruby: Option<&str>) -> FuriganaSegment {
+    FuriganaSegment { text: text.to_string(), ruby: ruby.map(str::to_string) }
+}
+
+fn rubies(segments: &[FuriganaSegment]) -> Vec<(&str, Option<&str>)> {
+    segments.iter().map(|s| (s.text.as_str(), s.ruby.as_deref())).collect()
+}
+
+#[tokio::test]
+async fn the_reading_honors_re_restr() {
+    let entry = parse_entry_xml(ASHITA).unwrap();
+    assert_eq!(entry.r_ele[2].re_restr, ["翌日"]);
+    let converted = EntryParsed::try_from(&entry).unwrap();
+    assert_eq!(converted.reading_for("明日"), Some("あした"));
+    assert_eq!(converted.reading_for("翌日"), Some("よくじつ"));
+    assert_eq!(converted.reading_for("今日"), None);
+    assert_eq!(converted.furigana().unwrap(), [seg("明日", Some("あした"))]);
+
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[entry]).await.unwrap();
+    let stored = db.entry_by_seq(1000020).await.unwrap().unwrap();
+    assert_eq!(stored.re_restr, converted.re_restr);
+    assert_eq!(stored.reading_for("翌日"), Some("よくじつ"));
+
+    // Annotating text picks the same reading
+    let annotated = db.annotate_with_furigana("翌日").await.unwrap();
+    assert_eq!(annotated.segments[0].ruby.as_deref(), Some("よくじつ"));
+
+    // Without restrictions every reb goes with every keb
+    let expression = EntryParsed::try_from(&parse_entry_xml(KI_WO_TSUKERU).unwrap()).unwrap();
+    assert!(expression.re_restr.is_empty());
+    assert_eq!(expression.reading_for("気をつける"), Some("きをつける"));
+}
+
+#[test]
+fn expressions_keep_their_kana() {
+    let entry = EntryParsed::try_from(&parse_entry_xml(KI_WO_TSUKERU).unwrap()).unwrap();
+    let segments = entry.furigana().unwrap();
+    assert_eq!(rubies(&segments), [("気", Some("き")), ("を", None), ("付", Some("つ")), ("ける", None)]);
+
+    let segments = align_furigana("足を洗う", "あしをあらう").unwrap();
+    assert_eq!(rubies(&segments), [("足", Some("あし")), ("を", None), ("洗", Some("あら")), ("う", None)]);
+}
+
+#[test]
+fn punctuation_has_no_ruby() {
+    let segments = align_furigana("〜を皮切りに", "をかわきりに").unwrap();
+    assert_eq!(rubies(&segments), [("〜", None), ("を", None), ("皮切", Some("かわき")), ("りに", None)]);
+
+    let segments = align_furigana("〜を〜に付ける", "をにつける").unwrap();
+    assert_eq!(rubies(&segments), [("〜", None), ("を", None), ("〜", None), ("に", None), ("付", Some("つ")), ("ける", None)]);
+
+    // Kept in the reading, where it separates the readings of the kanji around it
+    let segments = align_furigana("明日、晴れ", "あした、はれ").unwrap();
+    assert_eq!(rubies(&segments), [("明日", Some("あした")), ("、", None), ("晴", Some("は")), ("れ", None)]);
+    let segments = align_furigana("頭！", "あたま").unwrap();
+    assert_eq!(rubies(&segments), [("頭", Some("あたま")), ("！", None)]);
+
+    // 々 is read like a kanji
+    assert_eq!(rubies(&align_furigana("時々", "ときどき").unwrap()), [("時々", Some("ときどき"))]);
+}
@@ -0,0 +1,72 @@
+use jmdictdb::{DictDb, Entry, KEle, PartOfSpeech, REle, RankOptions, SearchFilter, Sense};
+
+fn word(ent_seq: i64, keb: &str, reb: &str, pos: &[&str], pri: &[&str]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle {
+            keb: keb.to_string(),
+            ke_pri: pri.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }]),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense {
+            pos: pos.iter().map(|p| p.to_string()).collect(),
+            gloss: vec![format!("word {}", ent_seq)],
+            ..Default::default()
+        }],
+    }
+}
+
+async fn db() -> DictDb {
+    let exp = PartOfSpeech::Exp.description();
+    let noun = PartOfSpeech::Noun.description();
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[
+        word(1, "猿", "さる", &[noun], &["nf20"]),
+        word(2, "猿も木から落ちる", "さるもきからおちる", &[exp], &[]),
+        word(3, "猿真似", "さるまね", &[noun], &[]),
+        // Parsed without the DTD, the tag stays a code
+        word(4, "木を見て森を見ず", "きをみてもりをみず", &["exp"], &[]),
+        word(5, "猿の尻笑い", "さるのしりわらい", &[exp, noun], &["nf40"]),
+    ])
+    .await
+    .unwrap();
+    db
+}
+
+fn seqs(entries: &[jmdictdb::EntryParsed]) -> Vec<i64> {
+    entries.iter().map(|e| e.ent_seq).collect()
+}
+
+#[tokio::test]
+async fn expressions_containing_a_word() {
+    let db = db().await;
+    assert_eq!(seqs(&db.search_expressions_containing("猿").await.unwrap()), [5, 2]);
+    assert_eq!(seqs(&db.search_expressions_containing("木").await.unwrap()), [2, 4]);
+    assert_eq!(seqs(&db.search_expressions_containing("から").await.unwrap()), [2]);
+    assert_eq!(seqs(&db.search_expressions_containing("みて").await.unwrap()), [4]);
+    assert!(db.search_expressions_containing("真似").await.unwrap().is_empty());
+    assert!(db.search_expressions_containing("").await.is_err());
+
+    let entries = db.search_expressions_containing("木").await.unwrap();
+    assert!(entries.iter().all(|e| e.is_expression()));
+    let saru = db.entry_by_seq(1).await.unwrap().unwrap();
+    assert!(!saru.is_expression());
+}
+
+#[tokio::test]
+async fn search_filter_keeps_or_drops_expressions() {
+    let db = db().await;
+    let ranked = |filter: SearchFilter| {
+        let db = &db;
+        async move {
+            let opts = RankOptions { filter, ..RankOptions::default() };
+            let mut seqs: Vec<i64> = db.search_ranked("猿", &opts).await.unwrap().iter().map(|r| r.entry.ent_seq).collect();
+            seqs.sort();
+            seqs
+        }
+    };
+    assert_eq!(ranked(SearchFilter::new()).await, [1, 2, 3, 5]);
+    assert_eq!(ranked(SearchFilter::new().without_expressions()).await, [1, 3]);
+    assert_eq!(ranked(SearchFilter::new().only_expressions()).await, [2, 5]);
+}
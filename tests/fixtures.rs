@@ -0,0 +1,42 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict, sample_xml};
+
+#[test]
+fn sample_covers_the_interesting_shapes() {
+    assert!(sample_xml().starts_with("<?xml"));
+    let dict = sample_dict();
+    assert!(dict.entry.len() >= 200);
+
+    let entries = &dict.entry;
+    assert!(entries.iter().any(|e| e.k_ele.is_none()), "kana-only entries");
+    assert!(entries.iter().any(|e| e.k_ele.as_ref().is_some_and(|k| k.len() > 1)), "several kebs");
+    assert!(entries.iter().any(|e| e.r_ele.len() > 1), "several rebs");
+    let senses = || entries.iter().flat_map(|e| &e.sense);
+    assert!(senses().any(|s| !s.stagk.is_empty()), "stagk");
+    assert!(senses().any(|s| !s.stagr.is_empty()), "stagr");
+    assert!(senses().any(|s| !s.xref.is_empty()), "xrefs");
+    let tags = || entries.iter().flat_map(|e| e.r_ele.iter().flat_map(|r| &r.re_pri));
+    assert!(tags().any(|t| t == "spec1") && tags().any(|t| t == "news2"), "priority tags");
+
+    let mut seqs: Vec<&str> = entries.iter().map(|e| e.ent_seq.as_str()).collect();
+    seqs.sort();
+    seqs.dedup();
+    assert_eq!(seqs.len(), entries.len(), "unique ent_seq");
+}
+
+#[tokio::test]
+async fn sample_databases_are_separate_and_shared_by_the_pool() {
+    let db = build_sample_db().await;
+    let other = build_sample_db().await;
+    other.delete_entry(1358280).await.unwrap();
+
+    // Concurrent queries run on several pooled connections of the same in-memory database
+    let (a, b, c) = tokio::join!(
+        db.search_entries_with_senses_by_reading("たべる"),
+        db.search_entries_with_senses_by_kanji("猫"),
+        db.search_entries_with_senses_by_reading("くる"),
+    );
+    assert_eq!(a.unwrap()[0].ent_seq, 1358280);
+    assert_eq!(b.unwrap()[0].senses[0].gloss[0], "cat (esp. the domestic cat)");
+    assert_eq!(c.unwrap().len(), 1);
+    assert!(other.search_entries_with_senses_by_reading("たべる").await.unwrap().is_empty());
+}
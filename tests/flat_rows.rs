@@ -0,0 +1,92 @@
+use futures_util::{StreamExt, TryStreamExt};
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::{DictDb, Entry, ExportFormat, FlatRow, FlatRowOptions, REle, SearchFilter, Sense};
+
+async fn collect(db: &DictDb, opts: &FlatRowOptions) -> Vec<FlatRow> {
+    db.flat_rows(opts).try_collect().await.unwrap()
+}
+
+#[tokio::test]
+async fn one_row_per_gloss_and_keb() {
+    let db = build_sample_db().await;
+    let dict = sample_dict();
+    let glosses = |e: &Entry| e.sense.iter().map(|s| s.gloss.len()).sum::<usize>();
+    let kebs = |e: &Entry| e.k_ele.as_ref().map_or(1, |k| k.len().max(1));
+
+    let rows = collect(&db, &FlatRowOptions::default()).await;
+    assert_eq!(rows.len(), dict.entry.iter().map(glosses).sum::<usize>());
+    assert!(rows.windows(2).all(|w| (w[0].ent_seq, w[0].sense_order) <= (w[1].ent_seq, w[1].sense_order)));
+
+    let taberu: Vec<&FlatRow> = rows.iter().filter(|r| r.ent_seq == 1358280).collect();
+    assert_eq!(taberu[0].keb.as_deref(), Some("食べる"));
+    assert_eq!(taberu[0].reb, "たべる");
+    assert_eq!(taberu[0].gloss, "to eat");
+    assert_eq!(taberu[0].pos_joined, "Ichidan verb; transitive verb");
+    let suru = rows.iter().find(|r| r.ent_seq == 1157170).unwrap();
+    assert_eq!(suru.keb, None);
+
+    let opts = FlatRowOptions { all_kebs: true, ..FlatRowOptions::default() };
+    let all = collect(&db, &opts).await;
+    assert_eq!(all.len(), dict.entry.iter().map(|e| glosses(e) * kebs(e)).sum::<usize>());
+    assert!(all.iter().any(|r| r.ent_seq == 1358280 && r.keb.as_deref() == Some("喰べる")));
+
+    // Nothing has a JLPT level in the sample
+    let opts = FlatRowOptions { filter: SearchFilter::new().jlpt(1..=5), ..Default::default() };
+    assert!(collect(&db, &opts).await.is_empty());
+}
+
+#[tokio::test]
+async fn streams_past_a_batch() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let entries: Vec<Entry> = (1..=1234)
+        .map(|n| Entry {
+            ent_seq: n.to_string(),
+            r_ele: vec![REle { reb: format!("よみ{}", n), ..Default::default() }],
+            sense: vec![Sense { gloss: vec![format!("gloss {}", n)], ..Default::default() }],
+            ..Default::default()
+        })
+        .collect();
+    db.insert_entries(&entries).await.unwrap();
+
+    let opts = FlatRowOptions::default();
+    let mut rows = std::pin::pin!(db.flat_rows(&opts));
+    let mut n = 0;
+    while let Some(row) = rows.next().await {
+        n += 1;
+        assert_eq!(row.unwrap().ent_seq, n);
+    }
+    assert_eq!(n, 1234);
+}
+
+#[tokio::test]
+async fn csv_quotes_and_tsv_flattens() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[Entry {
+        ent_seq: "1".to_string(),
+        r_ele: vec![REle { reb: "かな".to_string(), ..Default::default() }],
+        sense: vec![Sense {
+            pos: vec!["noun".to_string()],
+            gloss: vec!["say \"hi\", then\tgo".to_string()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }])
+    .await
+    .unwrap();
+
+    let mut csv = Vec::new();
+    assert_eq!(db.export_flat(&FlatRowOptions::default(), ExportFormat::Csv, &mut csv).await.unwrap(), 1);
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "ent_seq,keb,reb,sense_order,pos,gloss\r\n1,,かな,0,noun,\"say \"\"hi\"\", then\tgo\"\r\n"
+    );
+
+    let mut tsv = Vec::new();
+    db.export_flat(&FlatRowOptions::default(), ExportFormat::Tsv, &mut tsv).await.unwrap();
+    assert_eq!(
+        String::from_utf8(tsv).unwrap(),
+        "ent_seq\tkeb\treb\tsense_order\tpos\tgloss\n1\t\tかな\t0\tnoun\tsay \"hi\", then go\n"
+    );
+    assert_eq!(ExportFormat::parse("TSV"), Some(ExportFormat::Tsv));
+    assert_eq!(ExportFormat::parse("json"), None);
+}
@@ -0,0 +1,90 @@
+use jmdictdb::{DictDb, Entry, FrequencyFormat, KEle, MatchKind, REle, RankOptions, ScoreBreakdown, Sense};
+
+fn word(ent_seq: i64, keb: &str, reb: &str, pri: &[&str]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle { keb: keb.to_string(), ke_pri: pri.iter().map(|p| p.to_string()).collect(), ..Default::default() }]),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { pos: vec!["noun".into()], gloss: vec![format!("word {}", ent_seq)], ..Default::default() }],
+    }
+}
+
+/// Three homophones without priority tags, so only the frequency list tells them apart.
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[word(1, "紙", "かみ", &[]), word(2, "髪", "かみ", &[]), word(3, "神", "かみ", &[]), word(4, "上", "うえ", &["nf05"])])
+        .await
+        .unwrap();
+    db
+}
+
+async fn ranked(db: &DictDb, query: &str, source: Option<&str>) -> Vec<i64> {
+    let opts = RankOptions { frequency_source: source.map(str::to_string), ..Default::default() };
+    db.search_ranked(query, &opts).await.unwrap().iter().map(|r| r.entry.ent_seq).collect()
+}
+
+#[test]
+fn the_frequency_component_falls_with_the_rank() {
+    let component = |rank| ScoreBreakdown::compute(MatchKind::Exact, rank, None, &[]).frequency_component;
+    assert_eq!(component(Some(1)), 10.0);
+    assert_eq!(component(Some(10)), 5.0);
+    // Ranks below 1 count as 1
+    assert_eq!(component(Some(0)), 10.0);
+    assert_eq!(component(None), 0.0);
+    let ranks = [1, 2, 10, 100, 5_000, 1_000_000];
+    assert!(ranks.windows(2).all(|w| component(Some(w[0])) > component(Some(w[1]))));
+    assert!(ranks.iter().all(|&r| component(Some(r)) > 0.0 && component(Some(r)) <= 10.0));
+    // Never enough to lift a prefix match over an exact one
+    let prefix = ScoreBreakdown::compute(MatchKind::Prefix, Some(1), None, &[]).total;
+    let exact = ScoreBreakdown::compute(MatchKind::Exact, None, None, &[]).total;
+    assert!(prefix < exact);
+}
+
+#[tokio::test]
+async fn homophones_are_ordered_by_rank() {
+    let db = db().await;
+    // Without a list, by ent_seq
+    assert_eq!(ranked(&db, "かみ", None).await, [1, 2, 3]);
+
+    let list = "神\t30\n髪\t2000\n# a comment\n\n紙\t400\nない\t1\n";
+    let report = db.load_frequency_list("corpus", list.as_bytes(), FrequencyFormat::TermRank).await.unwrap();
+    assert_eq!((report.terms_read, report.entries_ranked), (4, 3));
+    assert_eq!(report.unmatched, ["ない"]);
+    assert_eq!(db.frequency_rank(3, "corpus").await.unwrap(), Some(30));
+    assert_eq!(db.frequency_rank(4, "corpus").await.unwrap(), None);
+    assert_eq!(ranked(&db, "かみ", None).await, [3, 1, 2]);
+}
+
+#[tokio::test]
+async fn the_selected_list_decides() {
+    let db = db().await;
+    db.load_frequency_list("news", "髪\n紙\n神\n".as_bytes(), FrequencyFormat::Ranked).await.unwrap();
+    db.load_frequency_list("anime", "神\n髪\n".as_bytes(), FrequencyFormat::Ranked).await.unwrap();
+    assert_eq!(db.frequency_sources().await.unwrap(), ["anime", "news"]);
+    assert_eq!(ranked(&db, "かみ", Some("news")).await, [2, 1, 3]);
+    // 紙 is missing from the list and goes last
+    assert_eq!(ranked(&db, "かみ", Some("anime")).await, [3, 2, 1]);
+    // Without a selection, the best rank of any list: 髪 and 神 are both 1
+    assert_eq!(ranked(&db, "かみ", None).await, [2, 3, 1]);
+
+    // Loading a list again replaces its ranks only
+    db.load_frequency_list("news", "紙\n".as_bytes(), FrequencyFormat::Ranked).await.unwrap();
+    assert_eq!(db.frequency_rank(2, "news").await.unwrap(), None);
+    assert_eq!(db.frequency_rank(2, "anime").await.unwrap(), Some(2));
+    assert_eq!(ranked(&db, "かみ", Some("news")).await, [1, 2, 3]);
+}
+
+#[tokio::test]
+async fn malformed_lists_are_errors() {
+    let db = db().await;
+    assert!(db.load_frequency_list("bad", "紙 400\n".as_bytes(), FrequencyFormat::TermRank).await.is_err());
+    assert!(db.load_frequency_list("bad", "紙\tmany\n".as_bytes(), FrequencyFormat::TermRank).await.is_err());
+    assert!(db.frequency_sources().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn top_entries_by_nf_bucket() {
+    let db = db().await;
+    let top: Vec<i64> = db.top_frequency_entries(10).await.unwrap().iter().map(|e| e.ent_seq).collect();
+    assert_eq!(top, [4]);
+}
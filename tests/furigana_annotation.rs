@@ -0,0 +1,63 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{RubySegment, RubyText};
+
+fn seg(surface: &str, ruby: Option<&str>) -> RubySegment {
+    RubySegment { surface: surface.to_string(), ruby: ruby.map(str::to_string) }
+}
+
+#[tokio::test]
+async fn annotates_kanji_and_passes_the_rest_through() {
+    let db = build_sample_db().await;
+    let text = "ABC、食べ物を食べています。カタカナ!";
+    let annotated = db.annotate_with_furigana(text).await.unwrap();
+    assert_eq!(
+        annotated.segments,
+        [
+            seg("ABC、", None),
+            seg("食", Some("た")),
+            seg("べ", None),
+            seg("物", Some("もの")),
+            seg("を", None),
+            seg("食", Some("た")),
+            seg("べています。カタカナ!", None),
+        ]
+    );
+    assert_eq!(annotated.surface(), text);
+}
+
+#[tokio::test]
+async fn unknown_kanji_have_no_ruby() {
+    let db = build_sample_db().await;
+    let annotated = db.annotate_with_furigana("鬱蒼な猫").await.unwrap();
+    assert_eq!(annotated.segments, [seg("鬱蒼な", None), seg("猫", Some("ねこ"))]);
+
+    let empty = db.annotate_with_furigana("").await.unwrap();
+    assert!(empty.segments.is_empty());
+}
+
+#[tokio::test]
+async fn uses_the_furigana_table_when_loaded() {
+    let db = build_sample_db().await;
+    let aligned = db.annotate_with_furigana("学校").await.unwrap();
+    assert_eq!(aligned.segments, [seg("学校", Some("がっこう"))]);
+
+    let json = r#"[{"text":"学校","reading":"がっこう","furigana":[{"ruby":"学","rt":"がっ"},{"ruby":"校","rt":"こう"}]}]"#;
+    db.load_furigana(json.as_bytes()).await.unwrap();
+    let stored = db.annotate_with_furigana("学校へ").await.unwrap();
+    assert_eq!(stored.segments, [seg("学", Some("がっ")), seg("校", Some("こう")), seg("へ", None)]);
+}
+
+#[test]
+fn renders_html_ruby_and_anki() {
+    let text = RubyText {
+        segments: vec![seg("<b>", None), seg("食", Some("た")), seg("べ", None), seg("物", Some("もの")), seg(" & ", None), seg("猫", Some("ねこ"))],
+    };
+    assert_eq!(
+        text.to_html_ruby(),
+        "&lt;b&gt;<ruby>食<rt>た</rt></ruby>べ<ruby>物<rt>もの</rt></ruby> &amp; <ruby>猫<rt>ねこ</rt></ruby>"
+    );
+    assert_eq!(text.to_anki_format(), "<b> 食[た]べ 物[もの] & 猫[ねこ]");
+
+    let leading = RubyText { segments: vec![seg("猫", Some("ねこ")), seg("です", None)] };
+    assert_eq!(leading.to_anki_format(), "猫[ねこ]です");
+}
@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use jmdictdb::fixtures::sample_xml;
+use jmdictdb::{DictDb, DictDbOptions, DictError, ReloadableDictDb};
+
+fn db_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jmdictdb-generation-{}-{}.db", name, std::process::id()))
+}
+
+/// Builds the sample dictionary into a new file at `path`, as `build_db` does.
+async fn rebuild(path: &Path) {
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.build_from_reader(sample_xml().as_bytes()).await.unwrap();
+    db.close().await;
+}
+
+fn is_replaced(err: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    matches!(err.downcast_ref::<DictError>(), Some(DictError::DatabaseReplaced(_)))
+}
+
+#[tokio::test]
+async fn detects_a_rebuilt_file() {
+    let path = db_path("check");
+    rebuild(&path).await;
+    let db = DictDb::connect(&format!("sqlite:{}", path.display())).await.unwrap();
+    assert_eq!(db.build_id().len(), 32);
+    db.check_generation().await.unwrap();
+    let first_build = db.build_id().to_string();
+
+    rebuild(&path).await;
+    assert!(is_replaced(&*db.check_generation().await.unwrap_err()));
+    assert_eq!(db.build_id(), first_build);
+    // Without periodic checks searches go on reading what the pool has open
+    assert!(db.search_entries_with_senses_by_reading("ねこ").await.is_ok());
+
+    // A new connection reads the new build
+    let reconnected = DictDb::connect(&format!("sqlite:{}", path.display())).await.unwrap();
+    assert_ne!(reconnected.build_id(), first_build);
+    reconnected.check_generation().await.unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(is_replaced(&*reconnected.check_generation().await.unwrap_err()));
+    db.close().await;
+    reconnected.close().await;
+}
+
+#[tokio::test]
+async fn searches_report_the_replacement_instead_of_rows() {
+    let path = db_path("search");
+    rebuild(&path).await;
+    let opts = DictDbOptions::new(format!("sqlite:{}", path.display())).check_generation_every(Duration::ZERO);
+    let db = opts.connect().await.unwrap();
+    assert_eq!(db.search_entries_with_senses_by_reading("ねこ").await.unwrap().len(), 1);
+
+    rebuild(&path).await;
+    let err = db.search_entries_with_senses_by_reading("ねこ").await.unwrap_err();
+    assert!(is_replaced(&*err), "{}", err);
+    assert!(is_replaced(&*db.search_entries_by_gloss("cat", "eng").await.unwrap_err()));
+    db.close().await;
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn in_memory_databases_are_never_replaced() {
+    let db = DictDbOptions::new("sqlite::memory:").check_generation_every(Duration::ZERO).connect().await.unwrap();
+    db.check_generation().await.unwrap();
+    assert!(db.search_entries_with_senses_by_reading("ねこ").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn the_reloadable_wrapper_reconnects() {
+    let path = db_path("reload");
+    rebuild(&path).await;
+    let served = ReloadableDictDb::open(&path, DictDbOptions::new("")).await.unwrap();
+    assert_eq!(served.reload_if_replaced().await.unwrap(), None);
+    let before = served.db_info();
+    assert_eq!(before.build_id, served.current().build_id());
+
+    rebuild(&path).await;
+    let after = served.reload_if_replaced().await.unwrap().unwrap();
+    assert_eq!((after.generation, &after.path), (2, &path));
+    assert_ne!(after.build_id, before.build_id);
+    served.current().check_generation().await.unwrap();
+    assert_eq!(served.current().search_entries_with_senses_by_reading("ねこ").await.unwrap().len(), 1);
+    drop(served);
+    std::fs::remove_file(&path).unwrap();
+}
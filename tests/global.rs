@@ -0,0 +1,43 @@
+use jmdictdb::{DictDb, DictDbOptions, DictError};
+
+// The global instance is per process, so everything runs in one test
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn init_global_races_and_reuse() {
+    let err = DictDb::global().unwrap_err();
+    assert_eq!(err.downcast_ref::<DictError>(), Some(&DictError::GlobalNotInitialized));
+
+    let path = std::env::temp_dir().join(format!("jmdictdb-global-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+
+    // A failed initialization can be retried
+    assert!(DictDb::init_global(DictDbOptions::new("sqlite:/nonexistent/dir/x.db")).await.is_err());
+    assert!(DictDb::global().is_err());
+
+    let tasks: Vec<_> = (0..16)
+        .map(|_| {
+            let url = url.clone();
+            tokio::spawn(async move { DictDb::init_global(DictDbOptions::new(url)).await.map(|db| db as *const DictDb as usize) })
+        })
+        .collect();
+    let mut winners = Vec::new();
+    for task in tasks {
+        match task.await.unwrap() {
+            Ok(ptr) => winners.push(ptr),
+            Err(err) => assert_eq!(err.downcast_ref::<DictError>(), Some(&DictError::GlobalAlreadyInitialized)),
+        }
+    }
+    assert_eq!(winners.len(), 1);
+
+    let global = DictDb::global().unwrap();
+    assert_eq!(global as *const DictDb as usize, winners[0]);
+    let readers: Vec<_> = (0..8)
+        .map(|_| tokio::spawn(async { DictDb::global().unwrap().search_entries_with_senses_by_reading("ねこ").await.unwrap().len() }))
+        .collect();
+    for reader in readers {
+        assert_eq!(reader.await.unwrap(), 0);
+    }
+
+    global.close().await;
+    let _ = std::fs::remove_file(&path);
+}
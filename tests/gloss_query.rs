@@ -0,0 +1,73 @@
+use jmdictdb::{gloss_words, DictDb, Entry, EntryParsed, GlossQuery, REle, Sense, DEFAULT_LANG};
+
+fn word(ent_seq: i64, reb: &str, senses: &[&[&str]]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: senses
+            .iter()
+            .map(|glosses| Sense { gloss: glosses.iter().map(|g| g.to_string()).collect(), ..Default::default() })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[
+        word(1, "しょうぼうしゃ", &[&["fire engine (vehicle)", "fire truck"]]),
+        word(2, "ひ", &[&["fire", "flame"]]),
+        // Both words, but in different senses
+        word(3, "えんじん", &[&["engine"], &["Fire!"]]),
+        // Both words in one sense, different glosses
+        word(4, "かじ", &[&["Fire", "the engine of destruction"]]),
+        // "fire" only inside another word
+        word(5, "ほのお", &[&["firearm engineering"]]),
+    ])
+    .await
+    .unwrap();
+    db
+}
+
+async fn seqs(db: &DictDb, query: &GlossQuery) -> Vec<i64> {
+    let entries: Vec<EntryParsed> = db.search_entries_by_gloss_query(query, DEFAULT_LANG).await.unwrap();
+    entries.iter().map(|e| e.ent_seq).collect()
+}
+
+#[test]
+fn query_syntax() {
+    assert_eq!(gloss_words("Fire-engine (vehicle)."), ["fire", "engine", "vehicle"]);
+    assert_eq!(GlossQuery::parse("fire engine"), GlossQuery { all: vec!["fire".into(), "engine".into()], phrase: None, stemmed: false, ..Default::default() });
+    assert_eq!(
+        GlossQuery::parse("\"fire engine\" Red \"big\""),
+        GlossQuery { all: vec!["red".into(), "big".into()], phrase: Some("fire engine".into()), stemmed: false, ..Default::default() }
+    );
+    assert_eq!(GlossQuery::parse("\"fire engine").phrase.as_deref(), Some("fire engine"));
+    assert!(GlossQuery::parse(" \"\" ; ").is_empty());
+    assert!(!GlossQuery::parse("fire").is_empty());
+}
+
+#[tokio::test]
+async fn all_words_in_one_sense() {
+    let db = db().await;
+    assert_eq!(seqs(&db, &GlossQuery::parse("fire engine")).await, [1, 4]);
+    assert_eq!(seqs(&db, &GlossQuery::parse("FIRE")).await, [1, 2, 3, 4]);
+    assert_eq!(seqs(&db, &GlossQuery::parse("engine fire truck")).await, [1]);
+    assert!(seqs(&db, &GlossQuery::parse("fire dragon")).await.is_empty());
+    assert!(db.search_entries_by_gloss_query(&GlossQuery::default(), DEFAULT_LANG).await.is_err());
+    assert!(db.search_entries_by_gloss_query(&GlossQuery::parse("fire"), "ger").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn phrases_stay_within_a_gloss_across_punctuation() {
+    let db = db().await;
+    assert_eq!(seqs(&db, &GlossQuery::parse("\"fire engine\"")).await, [1]);
+    assert_eq!(seqs(&db, &GlossQuery::parse("\"engine (vehicle)\"")).await, [1]);
+    assert_eq!(seqs(&db, &GlossQuery::parse("\"engine vehicle\"")).await, [1]);
+    // Out of order or split over glosses
+    assert!(seqs(&db, &GlossQuery::parse("\"engine fire\"")).await.is_empty());
+    assert!(seqs(&db, &GlossQuery::parse("\"fire the engine\"")).await.is_empty());
+    // A phrase and a word
+    let query = GlossQuery { all: vec!["truck".into()], phrase: Some("fire engine".into()), stemmed: false, ..Default::default() };
+    assert_eq!(seqs(&db, &query).await, [1]);
+}
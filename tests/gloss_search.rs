@@ -0,0 +1,256 @@
+use jmdictdb::{clean_gloss, fold_case, normalize_gloss, parse_jmdict_str, DictDb, Entry, Gloss, REle, Sense, DEFAULT_LANG};
+use sqlx::SqlitePool;
+
+fn entry(ent_seq: i64, reb: &str, glosses: &[&str]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: glosses.iter().map(|g| g.to_string()).collect(), ..Default::default() }],
+        ..Default::default()
+    }
+}
+
+fn gloss(lang: &str, text: &str) -> Gloss {
+    Gloss { lang: lang.to_string(), text: text.to_string() }
+}
+
+async fn fixture_db(name: &str) -> (DictDb, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("jmdictdb-gloss-{}-{}.db", name, std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(&[
+        entry(1, "コーヒー", &["Coffee", "coffee.", "  coffee  bean "]),
+        entry(2, "こうちゃ", &["(black tea)"]),
+        entry(3, "おおきい", &["(to be) large"]),
+        Entry {
+            sense: vec![Sense {
+                gloss: vec!["cat".to_string()],
+                other_glosses: vec![gloss("ger", "Katze"), gloss("ger", "katze."), gloss("fre", "chat")],
+                ..Default::default()
+            }],
+            ..entry(4, "ねこ", &[])
+        },
+        Entry {
+            sense: vec![Sense { other_glosses: vec![gloss("ger", "Hund")], ..Default::default() }],
+            ..entry(5, "いぬ", &[])
+        },
+    ])
+    .await
+    .unwrap();
+    (db, path)
+}
+
+#[test]
+fn normalization() {
+    assert_eq!(clean_gloss("  coffee \t bean "), "coffee bean");
+    for gloss in ["Coffee", "coffee.", " coffee", "(Coffee)", "( coffee. )"] {
+        assert_eq!(normalize_gloss(gloss), "coffee", "{:?}", gloss);
+    }
+    assert_eq!(normalize_gloss("(to be) large"), "(to be) large");
+    assert_eq!(normalize_gloss(" CAFÉ. "), "café");
+}
+
+#[test]
+fn case_folding_is_unicode_aware() {
+    assert_eq!(fold_case("CAFÉ"), "café");
+    assert_eq!(fold_case("Ça Va"), "ça va");
+    assert_eq!(fold_case("Straße"), fold_case("STRASSE"));
+    assert_eq!(fold_case("ΟΔΟΣ"), fold_case("οδος"));
+    assert_eq!(fold_case("ﬁle"), "file");
+    // Kana and kanji have no case
+    assert_eq!(fold_case("コーヒー珈琲"), "コーヒー珈琲");
+}
+
+#[tokio::test]
+async fn normalized_glosses_are_searched_and_deduplicated() {
+    let (db, path) = fixture_db("pass").await;
+
+    let report = db.normalize_glosses().await.unwrap();
+    assert_eq!(report.senses, 5);
+    assert_eq!(report.duplicates_removed, 2);
+
+    for query in ["Coffee", "coffee.", " coffee"] {
+        let found = db.search_entries_by_gloss(query, DEFAULT_LANG).await.unwrap();
+        assert_eq!(found.len(), 1, "{:?}", query);
+        // The display text keeps its case, the duplicate is gone
+        assert_eq!(found[0].senses[0].gloss, ["Coffee", "coffee bean"]);
+    }
+    assert_eq!(db.search_entries_by_gloss("black tea", DEFAULT_LANG).await.unwrap()[0].ent_seq, 2);
+    assert_eq!(db.search_entries_by_gloss("(to be) large", DEFAULT_LANG).await.unwrap()[0].ent_seq, 3);
+    assert!(db.search_entries_by_gloss("tea", DEFAULT_LANG).await.unwrap().is_empty());
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn without_the_pass_glosses_match_case_insensitively() {
+    let (db, path) = fixture_db("raw").await;
+
+    assert_eq!(db.search_entries_by_gloss("COFFEE", DEFAULT_LANG).await.unwrap().len(), 1);
+    assert_eq!(db.search_entries_by_gloss("coffee.", DEFAULT_LANG).await.unwrap().len(), 1);
+    // Not normalized yet, so the parentheses are still part of the gloss
+    assert!(db.search_entries_by_gloss("black tea", DEFAULT_LANG).await.unwrap().is_empty());
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn glosses_are_stored_per_language() {
+    let (db, path) = fixture_db("lang").await;
+
+    assert_eq!(db.available_languages().await.unwrap(), ["eng", "fre", "ger"]);
+
+    let found = db.search_entries_by_gloss("katze", "ger").await.unwrap();
+    assert_eq!(found.len(), 1);
+    let sense = &found[0].senses[0];
+    assert_eq!(sense.gloss, ["cat"]);
+    assert_eq!(sense.glosses_for("ger"), ["Katze", "katze."]);
+    assert_eq!(sense.glosses_for("fre"), ["chat"]);
+    assert_eq!(sense.languages(), ["eng", "ger", "fre"]);
+    assert_eq!(sense.default_glosses(), ["cat"]);
+    assert!(db.search_entries_by_gloss("chat", "ger").await.unwrap().is_empty());
+
+    // Without English glosses the first language is the default
+    let found = db.search_entries_by_gloss("hund", "ger").await.unwrap();
+    assert_eq!(found[0].senses[0].default_glosses(), ["Hund"]);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn a_multilingual_document_keeps_english_apart() {
+    // As the full JMdict has it: English glosses without xml:lang or with "eng"
+    let dict = parse_jmdict_str(
+        "<JMdict><entry><ent_seq>1467640</ent_seq><r_ele><reb>ねこ</reb></r_ele>\
+         <sense><gloss>cat</gloss><gloss xml:lang=\"dut\">kat</gloss><gloss xml:lang=\"ger\">Katze</gloss></sense>\
+         <sense><gloss xml:lang=\"eng\">geisha</gloss><gloss xml:lang=\"rus\">гейша</gloss></sense>\
+         </entry></JMdict>",
+    )
+    .unwrap();
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&dict.entry).await.unwrap();
+    let senses = db.entry_by_seq(1467640).await.unwrap().unwrap().senses;
+    let english: Vec<&[String]> = senses.iter().map(|s| s.gloss.as_slice()).collect();
+    assert_eq!(english, [["cat"], ["geisha"]]);
+    assert_eq!(senses[0].languages(), ["eng", "dut", "ger"]);
+    assert_eq!(db.available_languages().await.unwrap(), ["dut", "eng", "ger", "rus"]);
+}
+
+#[tokio::test]
+async fn older_databases_get_the_glosses_table() {
+    let (db, path) = fixture_db("backfill").await;
+    db.close().await;
+
+    let url = format!("sqlite:{}", path.display());
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("DROP TABLE glosses").execute(&pool).await.unwrap();
+    pool.close().await;
+
+    // Rebuilt from the English glosses of the senses
+    let db = DictDb::connect(&url).await.unwrap();
+    assert_eq!(db.search_entries_by_gloss("COFFEE", DEFAULT_LANG).await.unwrap().len(), 1);
+    assert_eq!(db.search_entries_by_gloss("(to be) large", DEFAULT_LANG).await.unwrap().len(), 1);
+    assert_eq!(db.available_languages().await.unwrap(), ["eng"]);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn gloss_matches_report_the_matching_senses() {
+    let (db, path) = fixture_db("matches").await;
+    db.insert_entries(&[Entry {
+        sense: vec![
+            Sense { gloss: vec!["brown".to_string()], ..Default::default() },
+            Sense { gloss: vec!["tea".to_string(), "Tea.".to_string()], ..Default::default() },
+            Sense { gloss: vec!["(Tea)".to_string()], ..Default::default() },
+        ],
+        ..entry(6, "ちゃ", &[])
+    }])
+    .await
+    .unwrap();
+
+    let matches = db.search_gloss_matches("tea", DEFAULT_LANG).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].entry.ent_seq, 6);
+    // Only "tea" matches before the normalization pass
+    assert_eq!(matches[0].matched_senses, [1]); // sense orders start at 0
+    assert_eq!(matches[0].matched_gloss, "tea");
+    assert_eq!(matches[0].highlight, Some((0, 3)));
+
+    db.normalize_glosses().await.unwrap();
+    let matches = db.search_gloss_matches("TEA", DEFAULT_LANG).await.unwrap();
+    assert_eq!(matches[0].matched_senses, [1, 2]);
+    assert_eq!(matches[0].matched_gloss, "tea");
+    let coffee = &db.search_gloss_matches("coffee", DEFAULT_LANG).await.unwrap()[0];
+    assert_eq!((coffee.matched_gloss.as_str(), coffee.highlight), ("Coffee", Some((0, 6))));
+
+    // The pass only stores normalized forms, the displayed gloss keeps its parentheses
+    let tea = &db.search_gloss_matches("black tea", DEFAULT_LANG).await.unwrap()[0];
+    assert_eq!(tea.matched_gloss, "(black tea)");
+    let (start, end) = tea.highlight.unwrap();
+    assert_eq!(&tea.matched_gloss[start..end], "black tea");
+    assert!(db.search_gloss_matches("", DEFAULT_LANG).await.is_err());
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn mixed_case_and_accented_glosses_match_without_the_pass() {
+    let (db, path) = fixture_db("fold").await;
+    db.insert_entries(&[
+        entry(7, "カフェ", &["Café"]),
+        entry(8, "みち", &["Straße"]),
+        entry(9, "ひらめ", &["ÉCLAIR"]),
+    ])
+    .await
+    .unwrap();
+
+    for query in ["café", "CAFÉ", "Café", "cAfÉ"] {
+        let found = db.search_entries_by_gloss(query, DEFAULT_LANG).await.unwrap();
+        assert_eq!(found.len(), 1, "{:?}", query);
+        assert_eq!(found[0].senses[0].gloss, ["Café"]);
+    }
+    // Accents are not dropped, only case
+    assert!(db.search_entries_by_gloss("cafe", DEFAULT_LANG).await.unwrap().is_empty());
+    assert_eq!(db.search_entries_by_gloss("strasse", DEFAULT_LANG).await.unwrap()[0].ent_seq, 8);
+    let m = &db.search_gloss_matches("STRASSE", DEFAULT_LANG).await.unwrap()[0];
+    assert_eq!(m.highlight, Some((0, "Straße".len())));
+    assert_eq!(db.search_entries_by_gloss("éclair", DEFAULT_LANG).await.unwrap()[0].ent_seq, 9);
+    let m = &db.search_gloss_matches("éclair", DEFAULT_LANG).await.unwrap()[0];
+    assert_eq!(m.highlight, Some((0, "ÉCLAIR".len())));
+    assert_eq!(db.search_entries_by_gloss("katze", "ger").await.unwrap().len(), 1);
+
+    // The pass folds the same way, so the results don't change
+    db.normalize_glosses().await.unwrap();
+    assert_eq!(db.search_entries_by_gloss("CAFÉ", DEFAULT_LANG).await.unwrap()[0].ent_seq, 7);
+    assert_eq!(db.search_entries_by_gloss("STRASSE", DEFAULT_LANG).await.unwrap()[0].ent_seq, 8);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn older_glosses_are_folded_on_connect() {
+    let (db, path) = fixture_db("refold").await;
+    db.insert_entries(&[entry(7, "カフェ", &["Café"])]).await.unwrap();
+    db.close().await;
+
+    let url = format!("sqlite:{}", path.display());
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("DROP INDEX idx_glosses_lang_folded").execute(&pool).await.unwrap();
+    sqlx::query("ALTER TABLE glosses DROP COLUMN folded").execute(&pool).await.unwrap();
+    pool.close().await;
+
+    let db = DictDb::connect(&url).await.unwrap();
+    assert_eq!(db.search_entries_by_gloss("CAFÉ", DEFAULT_LANG).await.unwrap()[0].ent_seq, 7);
+    assert_eq!(db.search_entries_by_gloss("COFFEE", DEFAULT_LANG).await.unwrap()[0].ent_seq, 1);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
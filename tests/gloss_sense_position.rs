@@ -0,0 +1,59 @@
+use jmdictdb::{DictDb, Entry, GlossQuery, REle, Sense, DEFAULT_LANG};
+
+fn word(ent_seq: i64, reb: &str, senses: &[&str]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: senses.iter().map(|g| Sense { gloss: vec![g.to_string()], ..Default::default() }).collect(),
+        ..Default::default()
+    }
+}
+
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[
+        // "lantern" in the fifth sense
+        word(2_900_001, "ひ", &["fire", "flame", "blaze", "spark", "light (e.g. of a lantern)"]),
+        // and in the first
+        word(2_900_002, "ちょうちん", &["paper lantern", "lantern light"]),
+        word(2_900_003, "とうろう", &["garden lantern (stone)"]),
+    ])
+    .await
+    .unwrap();
+    db
+}
+
+async fn seqs(db: &DictDb, query: &GlossQuery) -> Vec<i64> {
+    db.search_entries_by_gloss_query(query, DEFAULT_LANG).await.unwrap().iter().map(|e| e.ent_seq).collect()
+}
+
+#[tokio::test]
+async fn matches_every_sense_by_default() {
+    let db = db().await;
+    assert_eq!(seqs(&db, &GlossQuery::parse("lantern")).await, [2_900_001, 2_900_002, 2_900_003]);
+    assert_eq!(seqs(&db, &GlossQuery::parse("light")).await, [2_900_001, 2_900_002]);
+}
+
+#[tokio::test]
+async fn restricts_matches_to_the_first_senses() {
+    let db = db().await;
+    assert_eq!(seqs(&db, &GlossQuery::parse("lantern").max_sense_order(0)).await, [2_900_002, 2_900_003]);
+    assert_eq!(seqs(&db, &GlossQuery::parse("lantern").max_sense_order(3)).await, [2_900_002, 2_900_003]);
+    assert_eq!(seqs(&db, &GlossQuery::parse("lantern").max_sense_order(4)).await, [2_900_001, 2_900_002, 2_900_003]);
+    // Every word has to be in one of the allowed senses
+    assert_eq!(seqs(&db, &GlossQuery::parse("\"lantern light\"").max_sense_order(0)).await, Vec::<i64>::new());
+    assert_eq!(seqs(&db, &GlossQuery::parse("\"lantern light\"").max_sense_order(1)).await, [2_900_002]);
+    assert_eq!(seqs(&db, &GlossQuery::parse("flame").max_sense_order(0)).await, Vec::<i64>::new());
+}
+
+#[tokio::test]
+async fn boosts_matches_in_earlier_senses() {
+    let db = db().await;
+    let boosted = GlossQuery::parse("lantern").boost_early_senses(true);
+    assert_eq!(seqs(&db, &boosted).await, [2_900_002, 2_900_003, 2_900_001]);
+    // The first matching sense counts, "light" is in the second sense of ちょうちん
+    assert_eq!(seqs(&db, &GlossQuery::parse("light").boost_early_senses(true)).await, [2_900_002, 2_900_001]);
+    // Both: the restriction first
+    assert_eq!(seqs(&db, &boosted.clone().max_sense_order(0)).await, [2_900_002, 2_900_003]);
+    assert_eq!(GlossQuery { max_sense_order: Some(0), boost_early_senses: true, ..GlossQuery::parse("lantern") }, boosted.max_sense_order(0));
+}
@@ -0,0 +1,111 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{porter_stem, DictDb, GlossQuery, DEFAULT_LANG};
+
+async fn seqs(db: &DictDb, query: &GlossQuery) -> Vec<i64> {
+    db.search_entries_by_gloss_query(query, DEFAULT_LANG).await.unwrap().iter().map(|e| e.ent_seq).collect()
+}
+
+#[test]
+fn porter_vocabulary() {
+    let cases = [
+        ("caresses", "caress"),
+        ("ponies", "poni"),
+        ("ties", "ti"),
+        ("caress", "caress"),
+        ("cats", "cat"),
+        ("feed", "feed"),
+        ("agreed", "agre"),
+        ("plastered", "plaster"),
+        ("bled", "bled"),
+        ("motoring", "motor"),
+        ("sing", "sing"),
+        ("conflated", "conflat"),
+        ("troubled", "troubl"),
+        ("sized", "size"),
+        ("hopping", "hop"),
+        ("tanned", "tan"),
+        ("falling", "fall"),
+        ("hissing", "hiss"),
+        ("fizzed", "fizz"),
+        ("failing", "fail"),
+        ("filing", "file"),
+        ("happy", "happi"),
+        ("sky", "sky"),
+        ("relational", "relat"),
+        ("conditional", "condit"),
+        ("rational", "ration"),
+        ("valenci", "valenc"),
+        ("hesitanci", "hesit"),
+        ("digitizer", "digit"),
+        ("conformabli", "conform"),
+        ("radicalli", "radic"),
+        ("differentli", "differ"),
+        ("vileli", "vile"),
+        ("analogousli", "analog"),
+        ("vietnamization", "vietnam"),
+        ("predication", "predic"),
+        ("operator", "oper"),
+        ("feudalism", "feudal"),
+        ("decisiveness", "decis"),
+        ("hopefulness", "hope"),
+        ("callousness", "callous"),
+        ("formaliti", "formal"),
+        ("sensitiviti", "sensit"),
+        ("sensibiliti", "sensibl"),
+        ("triplicate", "triplic"),
+        ("formative", "form"),
+        ("formalize", "formal"),
+        ("electriciti", "electr"),
+        ("electrical", "electr"),
+        ("hopeful", "hope"),
+        ("goodness", "good"),
+        ("revival", "reviv"),
+        ("allowance", "allow"),
+        ("inference", "infer"),
+        ("airliner", "airlin"),
+        ("gyroscopic", "gyroscop"),
+        ("adjustable", "adjust"),
+        ("defensible", "defens"),
+        ("irritant", "irrit"),
+        ("replacement", "replac"),
+        ("adjustment", "adjust"),
+        ("dependent", "depend"),
+        ("adoption", "adopt"),
+        ("homologou", "homolog"),
+        ("communism", "commun"),
+        ("activate", "activ"),
+        ("angulariti", "angular"),
+        ("homologous", "homolog"),
+        ("effective", "effect"),
+        ("bowdlerize", "bowdler"),
+        ("probate", "probat"),
+        ("rate", "rate"),
+        ("cease", "ceas"),
+        ("controll", "control"),
+        ("roll", "roll"),
+    ];
+    for (word, stem) in cases {
+        assert_eq!(porter_stem(word), stem, "{}", word);
+    }
+    assert_eq!(porter_stem("running"), porter_stem("run"));
+    assert_eq!(porter_stem("dictionaries"), porter_stem("dictionary"));
+    // Other scripts and short words are kept
+    assert_eq!(porter_stem("café"), "café");
+    assert_eq!(porter_stem("is"), "is");
+}
+
+#[tokio::test]
+async fn stemmed_queries_find_variants() {
+    let db = build_sample_db().await;
+    let running = GlossQuery::parse("running");
+    assert!(seqs(&db, &running).await.is_empty());
+    assert!(!running.stemmed);
+    assert_eq!(seqs(&db, &running.stemmed(true)).await, [1486670]); // 走る, "to run"
+    assert_eq!(seqs(&db, &GlossQuery::parse("Dictionaries").stemmed(true)).await, [1507570]); // 辞書
+    assert_eq!(seqs(&db, &GlossQuery::parse("\"to runs\"").stemmed(true)).await, [1486670]);
+
+    // Irregular forms don't stem to their base form
+    let eat = seqs(&db, &GlossQuery::parse("eat")).await;
+    assert!(eat.contains(&1358280));
+    assert!(!seqs(&db, &GlossQuery::parse("ate").stemmed(true)).await.contains(&1358280));
+}
@@ -0,0 +1,102 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{strip_headword_punctuation, DictDb, Entry, KEle, REle, RankOptions, Sense};
+use sqlx::SqlitePool;
+
+fn entry(ent_seq: i64, kebs: &[&str], rebs: &[&str], gloss: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: (!kebs.is_empty()).then(|| kebs.iter().map(|k| KEle { keb: k.to_string(), ..Default::default() }).collect()),
+        r_ele: rebs.iter().map(|r| REle { reb: r.to_string(), ..Default::default() }).collect(),
+        sense: vec![Sense { gloss: vec![gloss.to_string()], ..Default::default() }],
+    }
+}
+
+/// A few punctuated headwords as JMdict has them.
+fn punctuated() -> Vec<Entry> {
+    vec![
+        entry(2900001, &[], &["データベース", "データ・ベース"], "database"),
+        entry(2900002, &[], &["ラ・フランス"], "La France (pear variety)"),
+        entry(2900003, &[], &["オン・ザ・ロック", "オンザロック"], "on the rocks"),
+        entry(2900004, &["Ｘ　線"], &["エックスせん"], "X-ray"),
+    ]
+}
+
+async fn ranked(db: &DictDb, query: &str, ignore_punctuation: bool) -> Vec<i64> {
+    let opts = RankOptions { ignore_punctuation, ..Default::default() };
+    db.search_ranked(query, &opts).await.unwrap().iter().map(|r| r.entry.ent_seq).collect()
+}
+
+#[test]
+fn strips_dots_double_hyphens_and_spaces() {
+    assert_eq!(strip_headword_punctuation("データ・ベース"), "データベース");
+    assert_eq!(strip_headword_punctuation("ジャン＝ポール"), "ジャンポール");
+    assert_eq!(strip_headword_punctuation("ｺｰﾋｰ･ﾌﾞﾚｲｸ"), "ｺｰﾋｰﾌﾞﾚｲｸ");
+    assert_eq!(strip_headword_punctuation("Ｘ　線 a=b"), "Ｘ線ab");
+    assert_eq!(strip_headword_punctuation("ねこ"), "ねこ");
+}
+
+#[tokio::test]
+async fn matches_regardless_of_punctuation() {
+    let db = build_sample_db().await;
+    db.insert_entries(&punctuated()).await.unwrap();
+
+    // Off by default
+    assert!(ranked(&db, "ラフランス", false).await.is_empty());
+    assert_eq!(ranked(&db, "ラ・フランス", false).await, [2900002]);
+
+    // Stripped queries find punctuated headwords
+    assert_eq!(ranked(&db, "ラフランス", true).await, [2900002]);
+    assert_eq!(ranked(&db, "Ｘ線", true).await, [2900004]);
+    assert_eq!(ranked(&db, "オンザ", true).await, [2900003]);
+    // and punctuated queries plain ones
+    assert_eq!(ranked(&db, "ラ・フ・ランス", true).await, [2900002]);
+    assert_eq!(ranked(&db, "デー・タベース", true).await, [2900001]);
+
+    let opts = RankOptions { ignore_punctuation: true, ..Default::default() };
+    let found = db.search_ranked("データベース", &opts).await.unwrap();
+    assert_eq!((found.len(), found[0].match_kind), (1, jmdictdb::MatchKind::Exact));
+    assert!(db.search_ranked("・", &opts).await.is_err());
+
+    // Kept up to date with the entry
+    db.upsert_entry(&entry(2900002, &[], &["ラフランス"], "La France (pear variety)")).await.unwrap();
+    assert_eq!(ranked(&db, "ラ・フランス", true).await, [2900002]);
+    db.delete_entry(2900003).await.unwrap();
+    assert!(ranked(&db, "オンザ", true).await.is_empty());
+}
+
+#[tokio::test]
+async fn suggestions_skip_punctuation() {
+    let db = build_sample_db().await;
+    db.insert_entries(&punctuated()).await.unwrap();
+    let headwords = |suggestions: Vec<jmdictdb::Suggestion>| suggestions.into_iter().map(|s| s.headword).collect::<Vec<_>>();
+
+    for _ in 0..2 {
+        assert_eq!(headwords(db.suggest_fast("データベ", 10).await.unwrap()), ["データベース", "データ・ベース"]);
+        assert_eq!(headwords(db.suggest_fast("データ・ベ", 10).await.unwrap()), ["データベース", "データ・ベース"]);
+        assert_eq!(headwords(db.suggest_fast("オンザ", 10).await.unwrap()), ["オンザロック", "オン・ザ・ロック"]);
+        assert_eq!(headwords(db.suggest_fast("ラフ", 10).await.unwrap()), ["ラ・フランス"]);
+        assert!(db.suggest_fast("・", 10).await.unwrap().is_empty());
+        // The second time from memory
+        db.build_suggest_index().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn older_databases_get_the_index_on_connect() {
+    let path = std::env::temp_dir().join(format!("jmdictdb-punct-index-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(&punctuated()).await.unwrap();
+    db.close().await;
+
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("DROP TABLE punct_index").execute(&pool).await.unwrap();
+    pool.close().await;
+    let db = DictDb::connect(&url).await.unwrap();
+    assert_eq!(ranked(&db, "ラフランス", true).await, [2900002]);
+    assert_eq!(ranked(&db, "Ｘ線", true).await, [2900004]);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
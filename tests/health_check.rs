@@ -0,0 +1,81 @@
+use sqlx::{Connection, Executor, SqliteConnection};
+use std::path::{Path, PathBuf};
+
+use jmdictdb::fixtures::{build_sample_db, sample_dict, sample_xml};
+use jmdictdb::{DictDb, DictError, Entry, REle, Sense, HEALTH_CHECK_TIMEOUT, SCHEMA_VERSION};
+
+fn db_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jmdictdb-health-{}-{}.db", name, std::process::id()))
+}
+
+async fn build(path: &Path) -> DictDb {
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.build_from_reader(sample_xml().as_bytes()).await.unwrap();
+    db
+}
+
+#[tokio::test]
+async fn reports_the_database() {
+    let db = build_sample_db().await;
+    let health = db.health_check().await.unwrap();
+    assert!(health.ok);
+    assert_eq!(health.entry_count as usize, sample_dict().entry.len());
+    assert_eq!(health.schema_version, Some(SCHEMA_VERSION));
+    assert_eq!(health.db_path, None);
+    assert!(health.latency < HEALTH_CHECK_TIMEOUT);
+}
+
+#[tokio::test]
+async fn the_entry_count_is_the_first_checks() {
+    let path = db_path("count");
+    let db = build(&path).await;
+    let first = db.health_check().await.unwrap();
+    assert_eq!(first.db_path.as_deref(), Some(path.as_path()));
+    let entry = Entry {
+        ent_seq: "2900090".to_string(),
+        r_ele: vec![REle { reb: "けんこう".to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec!["health".to_string()], ..Default::default() }],
+        ..Default::default()
+    };
+    db.insert_entries(&[entry]).await.unwrap();
+    assert_eq!(db.health_check().await.unwrap().entry_count, first.entry_count);
+    assert_eq!(db.entry_count().await.unwrap(), first.entry_count + 1);
+    db.close().await;
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn another_schema_version_is_not_ok() {
+    let path = db_path("version");
+    let url = format!("sqlite:{}", path.display());
+    build(&path).await.close().await;
+    let mut conn = SqliteConnection::connect(&url).await.unwrap();
+    conn.execute("UPDATE meta SET value = '999' WHERE key = 'schema_version'").await.unwrap();
+    conn.close().await.unwrap();
+
+    // Connecting doesn't lower the version of a newer file
+    let db = DictDb::connect(&url).await.unwrap();
+    let health = db.health_check().await.unwrap();
+    assert!(!health.ok);
+    assert_eq!(health.schema_version, Some(999));
+    db.close().await;
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn answers_during_a_write_and_fails_once_the_file_is_gone() {
+    let path = db_path("gone");
+    let db = build(&path).await;
+    let mut writer = SqliteConnection::connect(&format!("sqlite:{}", path.display())).await.unwrap();
+    writer.execute("BEGIN IMMEDIATE; DELETE FROM meta WHERE key = 'nothing'").await.unwrap();
+    assert!(db.health_check().await.unwrap().ok);
+    writer.execute("ROLLBACK").await.unwrap();
+    writer.close().await.unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+    let err = db.health_check().await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<DictError>(), Some(DictError::DatabaseReplaced(_))), "{}", err);
+    db.close().await;
+}
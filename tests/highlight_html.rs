@@ -0,0 +1,66 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{DictError, Entry, HighlightOptions, KEle, REle, ScanOptions, Sense};
+
+fn surfaces(matches: &[jmdictdb::TextMatch]) -> Vec<&str> {
+    matches.iter().map(|m| m.surface.as_str()).collect()
+}
+
+#[tokio::test]
+async fn longest_then_leftmost() {
+    let db = build_sample_db().await;
+    let matches = db.scan_text("日本語の本", &ScanOptions::default()).await.unwrap();
+    assert_eq!(surfaces(&matches), ["日本語", "の", "本"]);
+    assert_eq!((matches[0].start, matches[0].end, matches[0].ent_seq), (0, 9, 1330510));
+
+    // 今日 and 日本 overlap with the same length, the leftmost wins
+    let matches = db.scan_text("今日本", &ScanOptions::default()).await.unwrap();
+    assert_eq!(surfaces(&matches), ["今日", "本"]);
+
+    let kana = db.scan_text("コーヒーとパン", &ScanOptions::default()).await.unwrap();
+    assert_eq!(surfaces(&kana), ["コーヒー", "と", "パン"]);
+}
+
+#[tokio::test]
+async fn skips_short_and_uncommon_matches() {
+    let db = build_sample_db().await;
+    let long = ScanOptions { min_len: 2, ..Default::default() };
+    assert_eq!(surfaces(&db.scan_text("日本語の本", &long).await.unwrap()), ["日本語"]);
+
+    let rare = Entry {
+        ent_seq: "2999990".to_string(),
+        k_ele: Some(vec![KEle { keb: "猫舌".to_string(), ..Default::default() }]),
+        r_ele: vec![REle { reb: "ねこじた".to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec!["sensitivity to hot food".to_string()], ..Default::default() }],
+    };
+    db.insert_entries(&[rare]).await.unwrap();
+    let all = db.scan_text("猫舌", &ScanOptions::default()).await.unwrap();
+    assert_eq!(surfaces(&all), ["猫舌"]);
+    assert!(!all[0].common);
+    let common = db.scan_text("猫舌", &ScanOptions { common_only: true, ..Default::default() }).await.unwrap();
+    assert_eq!(surfaces(&common), ["猫"]);
+    assert!(common[0].common);
+}
+
+#[tokio::test]
+async fn escapes_the_text() {
+    let db = build_sample_db().await;
+    let html = db.highlight_html("<b>猫</b> & 🐈 \"犬\"", &HighlightOptions::default()).await.unwrap();
+    assert_eq!(
+        html,
+        "&lt;b&gt;<span data-entseq=\"1469800\">猫</span>&lt;/b&gt; &amp; 🐈 &quot;<span data-entseq=\"1159180\">犬</span>&quot;"
+    );
+
+    let opts = HighlightOptions { tag: "mark".to_string(), attribute: "data-id".to_string(), ..Default::default() };
+    assert_eq!(db.highlight_html("🐈猫", &opts).await.unwrap(), "🐈<mark data-id=\"1469800\">猫</mark>");
+    assert_eq!(db.highlight_html("", &opts).await.unwrap(), "");
+}
+
+#[tokio::test]
+async fn rejects_invalid_names() {
+    let db = build_sample_db().await;
+    for (tag, attribute) in [("span onclick=x", "data-entseq"), ("span", "data\"x"), ("1b", "id")] {
+        let opts = HighlightOptions { tag: tag.to_string(), attribute: attribute.to_string(), ..Default::default() };
+        let err = db.highlight_html("猫", &opts).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<DictError>(), Some(DictError::InvalidMarkupName(_))));
+    }
+}
@@ -0,0 +1,81 @@
+use jmdictdb::{DictDb, Entry, KEle, REle, RankOptions, SearchFilter, Sense};
+
+const LEVELS: &str = include_str!("../fixtures/jlpt_sample.tsv");
+
+fn word(ent_seq: i64, keb: &str, reb: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle { keb: keb.to_string(), ke_pri: Vec::new(), ke_inf: None }]),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { pos: vec!["noun".into()], gloss: vec![format!("word {}", ent_seq)], ..Default::default() }],
+    }
+}
+
+/// The words of the level list and two that aren't in it, with the levels loaded.
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let words = [
+        word(1358280, "食べる", "たべる"),
+        word(1445160, "高い", "たかい"),
+        word(1467640, "猫", "ねこ"),
+        word(1578850, "行く", "いく"),
+        word(1597040, "立つ", "たつ"),
+        word(2900001, "堪能", "たんのう"),
+        word(2900002, "多岐", "たき"),
+    ];
+    db.insert_entries(&words).await.unwrap();
+    let report = db.load_jlpt_levels(LEVELS.as_bytes()).await.unwrap();
+    assert_eq!((report.terms_read, report.entries_tagged), (6, 5));
+    assert_eq!(report.unmatched, [("存在しない".to_string(), Some("そんざいしない".to_string()))]);
+    db
+}
+
+async fn search(db: &DictDb, query: &str, filter: SearchFilter) -> Vec<(String, Option<u8>)> {
+    let opts = RankOptions { filter, limit: 100, ..Default::default() };
+    let results = db.search_ranked(query, &opts).await.unwrap();
+    let mut found: Vec<_> = results
+        .into_iter()
+        .map(|r| (r.entry.kebs.and_then(|k| k.first().cloned()).unwrap_or_else(|| r.entry.rebs[0].clone()), r.entry.jlpt_level))
+        .collect();
+    found.sort();
+    found
+}
+
+#[tokio::test]
+async fn levels_are_stored_on_the_entries() {
+    let db = db().await;
+    assert_eq!(search(&db, "たべる", SearchFilter::new()).await, [("食べる".to_string(), Some(5))]);
+    assert_eq!(search(&db, "たんのう", SearchFilter::new()).await, [("堪能".to_string(), Some(1))]);
+    // Not in the list
+    assert_eq!(search(&db, "いく", SearchFilter::new()).await, [("行く".to_string(), None)]);
+}
+
+#[tokio::test]
+async fn filtering_keeps_only_the_matching_level() {
+    let db = db().await;
+    let all = search(&db, "た", SearchFilter::new()).await;
+    assert_eq!(all.len(), 5, "{:?}", all);
+    assert!(all.iter().any(|(_, level)| level.is_none()));
+
+    let n1 = search(&db, "た", SearchFilter::new().jlpt(1..=1)).await;
+    assert_eq!(n1, [("堪能".to_string(), Some(1)), ("多岐".to_string(), Some(1))]);
+    let n5 = search(&db, "た", SearchFilter::new().jlpt(5..=5)).await;
+    assert_eq!(n5, [("食べる".to_string(), Some(5)), ("高い".to_string(), Some(5))]);
+    assert!(search(&db, "た", SearchFilter::new().jlpt(2..=4)).await.is_empty());
+    assert_eq!(search(&db, "ねこ", SearchFilter::new().jlpt(5..=5)).await, [("猫".to_string(), Some(5))]);
+}
+
+#[tokio::test]
+async fn loading_again_replaces_the_levels() {
+    let db = db().await;
+    db.load_jlpt_levels("猫\tねこ\tN3\n".as_bytes()).await.unwrap();
+    assert_eq!(search(&db, "ねこ", SearchFilter::new().jlpt(3..=3)).await, [("猫".to_string(), Some(3))]);
+    assert!(search(&db, "た", SearchFilter::new().jlpt(1..=5)).await.is_empty());
+}
+
+#[tokio::test]
+async fn malformed_lines_are_errors() {
+    let db = db().await;
+    assert!(db.load_jlpt_levels("猫\tねこ\tN6\n".as_bytes()).await.is_err());
+    assert!(db.load_jlpt_levels("猫\n".as_bytes()).await.is_err());
+}
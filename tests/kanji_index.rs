@@ -0,0 +1,99 @@
+use jmdictdb::{extract_kanji, is_kanji, DictDb, Entry, KEle, REle, Sense};
+use sqlx::SqlitePool;
+
+fn entry(ent_seq: i64, kebs: &[&str], reb: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(kebs.iter().map(|k| KEle { keb: k.to_string(), ..Default::default() }).collect()),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec![reb.to_string()], ..Default::default() }],
+    }
+}
+
+async fn seqs(db: &DictDb, ch: char) -> Vec<i64> {
+    db.entries_containing_char(ch, 100).await.unwrap().iter().map(|e| e.ent_seq).collect()
+}
+
+#[test]
+fn kanji_are_extracted_once_in_order() {
+    assert_eq!(extract_kanji("食べ物を食べる"), ['食', '物']);
+    assert_eq!(extract_kanji("時々"), ['時', '々']);
+    assert!(extract_kanji("たべる ABC").is_empty());
+    // Extension A and B
+    assert!(is_kanji('㐂') && is_kanji('𠮷'));
+}
+
+#[tokio::test]
+async fn index_follows_upserts_and_deletes() {
+    let path = std::env::temp_dir().join(format!("jmdictdb-kanji-index-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(&[
+        entry(1, &["食べる", "喰べる"], "たべる"),
+        entry(2, &["食べ物"], "たべもの"),
+        entry(3, &["飲み物"], "のみもの"),
+    ])
+    .await
+    .unwrap();
+
+    assert_eq!(seqs(&db, '食').await, [1, 2]);
+    assert_eq!(seqs(&db, '物').await, [2, 3]);
+    assert_eq!(seqs(&db, '喰').await, [1]);
+    assert_eq!(db.entries_containing_char('物', 1).await.unwrap().len(), 1);
+
+    // Re-upserting with other kebs replaces the rows of the entry
+    db.upsert_entry(&entry(1, &["食う"], "くう")).await.unwrap();
+    assert!(seqs(&db, '喰').await.is_empty());
+    assert_eq!(seqs(&db, '食').await, [1, 2]);
+
+    db.delete_entry(2).await.unwrap();
+    assert_eq!(seqs(&db, '食').await, [1]);
+    assert_eq!(seqs(&db, '物').await, [3]);
+    db.close().await;
+
+    // Databases built before the index get it on connect
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("DROP TABLE kanji_index").execute(&pool).await.unwrap();
+    pool.close().await;
+    let db = DictDb::connect(&url).await.unwrap();
+    assert_eq!(seqs(&db, '物').await, [3]);
+    assert_eq!(seqs(&db, '食').await, [1]);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn kanji_detail_splits_by_position() {
+    let path = std::env::temp_dir().join(format!("jmdictdb-kanji-detail-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(&[
+        entry(1, &["飲む"], "のむ"),
+        entry(2, &["飲み物"], "のみもの"),
+        entry(3, &["試飲"], "しいん"),
+        entry(4, &["暴飲", "飲暴"], "ぼういん"),
+        entry(5, &["食べる"], "たべる"),
+    ])
+    .await
+    .unwrap();
+
+    let detail = db.kanji_detail('飲', 10, 10).await.unwrap();
+    assert_eq!(detail.ch, '飲');
+    // Starting one of its kebs is enough
+    assert_eq!(detail.at_start.iter().map(|e| e.ent_seq).collect::<Vec<_>>(), [1, 2, 4]);
+    assert_eq!(detail.elsewhere.iter().map(|e| e.ent_seq).collect::<Vec<_>>(), [3]);
+    assert!(!detail.at_start[0].senses.is_empty());
+
+    let limited = db.kanji_detail('飲', 1, 0).await.unwrap();
+    assert_eq!(limited.at_start.len(), 1);
+    assert!(limited.elsewhere.is_empty());
+
+    let unused = db.kanji_detail('猫', 10, 10).await.unwrap();
+    assert!(unused.at_start.is_empty() && unused.elsewhere.is_empty());
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "userdata")]
+
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{DictDb, MatchContext, Ranker};
+use std::time::Duration;
+
+/// Waits for the background write of `record_lookup` to show up in the history.
+async fn recorded(db: &DictDb, ent_seq: i64, count: i64) {
+    for _ in 0..200 {
+        let history = db.lookup_history(100).await.unwrap();
+        if history.iter().any(|r| r.entry.ent_seq == ent_seq && r.count == count) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("lookup of {} not recorded {} times", ent_seq, count);
+}
+
+/// Ranks the words looked up most often first.
+struct MostLookedUp;
+
+impl Ranker for MostLookedUp {
+    fn score(&self, ctx: &MatchContext) -> f64 {
+        ctx.lookup_count as f64
+    }
+}
+
+#[tokio::test]
+async fn records_counts_and_recency() {
+    let db = build_sample_db().await;
+    assert!(db.lookup_history(10).await.unwrap().is_empty());
+
+    db.record_lookup(1198180);
+    recorded(&db, 1198180, 1).await;
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    db.record_lookup(1198870);
+    recorded(&db, 1198870, 1).await;
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    db.record_lookup(1198180);
+    recorded(&db, 1198180, 2).await;
+
+    let history = db.lookup_history(10).await.unwrap();
+    let seen: Vec<(i64, i64)> = history.iter().map(|r| (r.entry.ent_seq, r.count)).collect();
+    assert_eq!(seen, [(1198180, 2), (1198870, 1)]);
+    assert!(history[0].last_at > history[1].last_at);
+    assert_eq!(db.lookup_history(1).await.unwrap().len(), 1);
+    assert_eq!(db.lookup_failures(), 0);
+}
+
+#[tokio::test]
+async fn lookup_counts_feed_custom_rankers() {
+    let db = build_sample_db().await;
+    let first = |results: &[jmdictdb::ScoredEntry]| results[0].entry.ent_seq;
+    let before = db.search_ranked_with("学", &MostLookedUp, 10).await.unwrap();
+    assert_eq!(before[0].score, 0.0);
+
+    let pick = before.last().unwrap().entry.ent_seq;
+    db.record_lookup(pick);
+    recorded(&db, pick, 1).await;
+    let after = db.search_ranked_with("学", &MostLookedUp, 10).await.unwrap();
+    assert_eq!(first(&after), pick);
+    assert_eq!(after[0].score, 1.0);
+}
+
+#[tokio::test]
+async fn clear_history_forgets_everything() {
+    let db = build_sample_db().await;
+    db.record_lookup(1469800);
+    recorded(&db, 1469800, 1).await;
+    assert_eq!(db.clear_history().await.unwrap(), 1);
+    assert!(db.lookup_history(10).await.unwrap().is_empty());
+    assert_eq!(db.clear_history().await.unwrap(), 0);
+}
+
+#[test]
+fn recording_outside_a_runtime_only_counts_a_failure() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let db = runtime.block_on(build_sample_db());
+    db.record_lookup(1469800);
+    assert_eq!(db.lookup_failures(), 1);
+}
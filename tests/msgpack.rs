@@ -0,0 +1,95 @@
+#![cfg(feature = "msgpack")]
+
+use jmdictdb::{decode_entries, encode_entries, DictError, EntryParsed, SenseParsed, Xref, PAYLOAD_VERSION};
+use proptest::prelude::*;
+
+fn sense() -> impl Strategy<Value = SenseParsed> {
+    (
+        0..50i64,
+        prop::collection::vec("\\PC{0,12}", 0..3),
+        prop::collection::vec(xref(), 0..2),
+        prop::collection::vec("\\PC{0,24}", 0..4),
+    )
+        .prop_map(|(sense_order, pos, xref, gloss)| SenseParsed {
+            sense_order,
+            pos,
+            xref,
+            gloss,
+            stagk: Vec::new(),
+            stagr: Vec::new(),
+            misc: Vec::new(),
+            other_glosses: Vec::new(),
+            applies_to_query: true,
+            sense_uid: None,
+            pos_inherited: false,
+            misc_inherited: false,
+            field: Vec::new(),
+            dial: Vec::new(),
+            borrowed: None,
+        })
+}
+
+fn xref() -> impl Strategy<Value = Xref> {
+    (
+        prop::option::of("[一-龯]{1,4}"),
+        prop::option::of("[ぁ-ゖ]{1,6}"),
+        prop::option::of(1..20u32),
+    )
+        .prop_map(|(keb, reb, sense)| Xref { keb, reb, sense })
+}
+
+fn entry() -> impl Strategy<Value = EntryParsed> {
+    (
+        any::<i64>(),
+        prop::collection::vec("[ぁ-ゖァ-ヺー]{1,8}", 1..3),
+        prop::option::of(prop::collection::vec("[一-龯ぁ-ゖ]{1,6}", 0..3)),
+        prop::collection::vec("(news|ichi|spec|gai)[12]|nf[0-4][0-9]", 0..3),
+        prop::collection::vec(sense(), 0..4),
+        prop::option::of(1..100_000i64),
+        prop::option::of(1..=5u8),
+        "jmdict|custom|[a-z_]{1,8}",
+        prop::option::of("[0-9a-f]{16}"),
+        prop::collection::vec(prop::collection::vec("[一-龯]{1,4}", 0..2), 0..3),
+    )
+        .prop_map(|(ent_seq, rebs, kebs, priority, senses, frequency_rank, jlpt_level, source, content_hash, re_restr)| EntryParsed {
+            ent_seq,
+            rebs,
+            kebs,
+            nf_bucket: jmdictdb::ranking::nf_bucket(&priority),
+            priority,
+            senses,
+            frequency_rank,
+            jlpt_level,
+            source,
+            content_hash,
+            senses_truncated: false,
+            re_restr,
+        })
+}
+
+proptest! {
+    #[test]
+    fn round_trip_is_lossless(entries in prop::collection::vec(entry(), 0..8)) {
+        let bytes = encode_entries(&entries).unwrap();
+        let decoded = decode_entries(&bytes).unwrap();
+        prop_assert_eq!(decoded.len(), entries.len());
+        for (decoded, entry) in decoded.iter().zip(&entries) {
+            prop_assert!(decoded.content_eq(entry), "{:?} != {:?}", decoded, entry);
+        }
+    }
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let bytes = encode_entries(&[]).unwrap();
+    // The version is the second element of the envelope array, a positive fixint
+    let mut changed = bytes.clone();
+    let pos = changed.iter().position(|&b| b == PAYLOAD_VERSION as u8).unwrap();
+    changed[pos] = PAYLOAD_VERSION as u8 + 1;
+    let err = decode_entries(&changed).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<DictError>(),
+        Some(&DictError::UnsupportedPayloadVersion { found: PAYLOAD_VERSION + 1, supported: PAYLOAD_VERSION })
+    );
+    assert!(decode_entries(b"not msgpack").is_err());
+}
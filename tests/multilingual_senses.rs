@@ -0,0 +1,40 @@
+use jmdictdb::fixtures::build_multilingual_db;
+use jmdictdb::{RankOptions, SearchFilter};
+
+#[tokio::test]
+async fn glosses_stay_in_their_sense() {
+    let db = build_multilingual_db().await;
+    let neko = db.entry_by_seq(1469800).await.unwrap().unwrap();
+    assert_eq!(neko.senses.len(), 3);
+    assert_eq!(neko.senses[0].languages(), ["eng", "dut", "ger", "rus"]);
+    assert_eq!(neko.senses[0].glosses_for("ger"), ["Katze"]);
+    assert_eq!(neko.senses[1].languages(), ["rus"]);
+    assert_eq!(neko.senses[2].glosses_for("ger"), ["Schelmin", "Spitzbübin"]);
+
+    assert!(neko.senses[0].has_lang("eng") && neko.senses[0].has_lang("ger"));
+    assert!(!neko.senses[1].has_lang("eng"));
+    assert!(!neko.senses[1].has_lang("ger"));
+    assert!(neko.senses[2].has_lang("ger"));
+}
+
+#[tokio::test]
+async fn searches_keep_the_senses_of_the_language() {
+    let db = build_multilingual_db().await;
+    let german = RankOptions { filter: SearchFilter::new().lang("ger"), ..Default::default() };
+
+    let neko = db.search_ranked("ねこ", &german).await.unwrap();
+    let senses: Vec<Vec<&str>> = neko[0].entry.senses.iter().map(|s| s.glosses_for("ger")).collect();
+    assert_eq!(senses, [vec!["Katze"], vec!["Schelmin", "Spitzbübin"]]);
+
+    let inu = db.search_ranked("いぬ", &german).await.unwrap();
+    assert_eq!(inu[0].entry.senses.len(), 1);
+
+    // Russian only
+    assert!(db.search_ranked("いいかげん", &german).await.unwrap().is_empty());
+    let russian = RankOptions { filter: SearchFilter::new().lang("rus"), ..Default::default() };
+    assert_eq!(db.search_ranked("いいかげん", &russian).await.unwrap().len(), 1);
+
+    // Without the filter every sense is there
+    let all = db.search_ranked("ねこ", &RankOptions::default()).await.unwrap();
+    assert_eq!(all[0].entry.senses.len(), 3);
+}
@@ -0,0 +1,86 @@
+use jmdictdb::ranking::nf_bucket;
+use jmdictdb::{DictDb, Entry, KEle, REle, RankOptions, Sense};
+use sqlx::SqlitePool;
+
+fn entry(ent_seq: i64, keb: &str, reb: &str, priority: &[&str]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle {
+            keb: keb.to_string(),
+            ke_pri: priority.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }]),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec![keb.to_string()], ..Default::default() }],
+    }
+}
+
+async fn fixture_db(name: &str) -> (DictDb, String, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("jmdictdb-nf-{}-{}.db", name, std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(&[
+        entry(1, "橋", "はし", &["news1", "nf30"]),
+        entry(2, "箸", "はし", &["ichi1", "news2", "nf12"]),
+        entry(3, "端", "はし", &["ichi1", "news1"]),
+        entry(4, "嘴", "はし", &[]),
+        entry(5, "食べる", "たべる", &["ichi1", "nf02", "nf25"]),
+    ])
+    .await
+    .unwrap();
+    (db, url, path)
+}
+
+#[test]
+fn bucket_is_the_best_nf_tag() {
+    assert_eq!(nf_bucket(&["ichi1", "nf25", "nf02"]), Some(2));
+    assert_eq!(nf_bucket(&["news1", "ichi1"]), None);
+    assert_eq!(nf_bucket(&["nf00", "nf49", "nfxx"]), None);
+}
+
+#[tokio::test]
+async fn top_frequency_entries_are_sorted_by_bucket() {
+    let (db, _, path) = fixture_db("top").await;
+
+    let top = db.top_frequency_entries(10).await.unwrap();
+    let seqs: Vec<(i64, Option<u8>)> = top.iter().map(|e| (e.ent_seq, e.nf_bucket)).collect();
+    assert_eq!(seqs, [(5, Some(2)), (2, Some(12)), (1, Some(30))]);
+    assert_eq!(db.top_frequency_entries(1).await.unwrap().len(), 1);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn bucket_breaks_ties_before_priority_flags() {
+    let (db, _, path) = fixture_db("rank").await;
+
+    let ranked = db.search_ranked("はし", &RankOptions::default()).await.unwrap();
+    let seqs: Vec<i64> = ranked.iter().map(|r| r.entry.ent_seq).collect();
+    // 箸 has the better bucket, 端 has stronger flags than 嘴 but no bucket
+    assert_eq!(seqs, [2, 1, 3, 4]);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn older_databases_get_the_bucket_backfilled() {
+    let (db, url, path) = fixture_db("backfill").await;
+    db.close().await;
+
+    // Turn it into a database built before the column existed
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("DROP INDEX idx_entries_nf_bucket").execute(&pool).await.unwrap();
+    sqlx::query("ALTER TABLE entries DROP COLUMN nf_bucket").execute(&pool).await.unwrap();
+    pool.close().await;
+
+    let db = DictDb::connect(&url).await.unwrap();
+    let found = db.search_entries_with_senses_by_kanji("食べる").await.unwrap();
+    assert_eq!(found[0].nf_bucket, Some(2));
+    assert_eq!(db.top_frequency_entries(10).await.unwrap().len(), 3);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
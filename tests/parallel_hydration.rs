@@ -0,0 +1,77 @@
+use jmdictdb::{DictDb, Entry, EntryParsed, QueryOptions, REle, Sense};
+use sqlx::SqlitePool;
+use std::time::Instant;
+
+/// `count` entries read こう from ent_seq 2900001 on, each with `senses` senses.
+fn homophones(count: i64, senses: usize) -> Vec<Entry> {
+    (1..=count)
+        .map(|i| Entry {
+            ent_seq: (2_900_000 + i).to_string(),
+            k_ele: None,
+            r_ele: vec![REle { reb: "こう".to_string(), ..Default::default() }],
+            sense: (0..senses).map(|s| Sense { gloss: vec![format!("meaning {} of {}", s, i)], ..Default::default() }).collect(),
+        })
+        .collect()
+}
+
+async fn fresh_db(name: &str, entries: &[Entry]) -> (DictDb, String) {
+    let path = std::env::temp_dir().join(format!("jmdictdb-{}-{}.db", name, std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(entries).await.unwrap();
+    (db, url)
+}
+
+async fn with_concurrency(db: &DictDb, concurrency: usize) -> jmdictdb::Result<Vec<EntryParsed>> {
+    let opts = QueryOptions { hydration_concurrency: Some(concurrency), ..Default::default() };
+    db.with_query_options(&opts, db.search_entries_with_senses_by_reading("こう")).await
+}
+
+#[tokio::test]
+async fn keeps_the_order_of_the_rows() {
+    let (db, _) = fresh_db("hydration-order", &homophones(120, 3)).await;
+    let sequential = with_concurrency(&db, 1).await.unwrap();
+    assert_eq!(sequential.len(), 120);
+    let seqs: Vec<i64> = sequential.iter().map(|e| e.ent_seq).collect();
+    assert_eq!(seqs, (2_900_001..=2_900_120).collect::<Vec<_>>());
+
+    for concurrency in [0, 2, 4, 32] {
+        assert_eq!(with_concurrency(&db, concurrency).await.unwrap(), sequential, "{}", concurrency);
+    }
+    // And without `with_query_options`
+    assert_eq!(db.search_entries_with_senses_by_reading("こう").await.unwrap(), sequential);
+    assert_eq!(db.search_entries_with_senses_by_kanji("こう").await.unwrap(), []);
+}
+
+#[tokio::test]
+async fn an_error_fails_the_whole_search() {
+    let (db, url) = fresh_db("hydration-error", &homophones(40, 2)).await;
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("UPDATE senses SET pos = 'not json' WHERE ent_seq = 2900020").execute(&pool).await.unwrap();
+    pool.close().await;
+
+    for concurrency in [1, 4] {
+        assert!(with_concurrency(&db, concurrency).await.is_err(), "{}", concurrency);
+    }
+    // The pool is fine afterwards
+    assert_eq!(db.entry_by_seq(2_900_001).await.unwrap().unwrap().senses.len(), 2);
+}
+
+// cargo test --release --test parallel_hydration -- --ignored --nocapture
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "benchmark"]
+async fn bench_hydration_concurrency() {
+    const RUNS: u32 = 20;
+    let (db, _) = fresh_db("hydration-bench", &homophones(300, 8)).await;
+    // The loads overlap on the connection threads, so this needs several cores to tell
+    println!("{:?} cores", std::thread::available_parallelism());
+    for concurrency in [1, 2, 4, 8] {
+        with_concurrency(&db, concurrency).await.unwrap();
+        let started = Instant::now();
+        for _ in 0..RUNS {
+            assert_eq!(with_concurrency(&db, concurrency).await.unwrap().len(), 300);
+        }
+        println!("concurrency {}: {:?} per search of 300 entries", concurrency, started.elapsed() / RUNS);
+    }
+}
@@ -0,0 +1,73 @@
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use jmdictdb::{cache_dir, data_dir, default_db_path, default_db_url, DictDb, DictError, DB_PATH_ENV};
+
+/// The tests of this binary run in parallel threads of one process, which share the
+/// environment; each holds this lock while it sets and reads variables.
+static ENV: Mutex<()> = Mutex::new(());
+
+/// Sets (or with None removes) the variables for the rest of the test.
+fn set_env(vars: &[(&str, Option<&str>)]) {
+    for (name, value) in vars {
+        // Safe, only the thread holding ENV touches the environment
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+    }
+}
+
+#[test]
+fn the_env_var_overrides_the_data_dir() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    set_env(&[(DB_PATH_ENV, Some("/srv/dict/custom.db")), ("HOME", Some("/home/someone"))]);
+    assert_eq!(default_db_path(), PathBuf::from("/srv/dict/custom.db"));
+    assert_eq!(default_db_url(), "sqlite:/srv/dict/custom.db");
+
+    // An empty value counts as unset
+    set_env(&[(DB_PATH_ENV, Some(""))]);
+    assert_eq!(default_db_path().file_name(), Some(OsStr::new("jmdict_e.db")));
+    assert!(default_db_path().starts_with(data_dir().unwrap()));
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+#[test]
+fn xdg_directories() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    set_env(&[
+        (DB_PATH_ENV, None),
+        ("HOME", Some("/home/someone")),
+        ("XDG_DATA_HOME", Some("/xdg/data")),
+        ("XDG_CACHE_HOME", Some("/xdg/cache")),
+    ]);
+    assert_eq!(data_dir(), Some(PathBuf::from("/xdg/data/jmdictdb")));
+    assert_eq!(cache_dir(), Some(PathBuf::from("/xdg/cache/jmdictdb")));
+    assert_eq!(default_db_path(), PathBuf::from("/xdg/data/jmdictdb/jmdict_e.db"));
+
+    // Unset or empty, the defaults under the home directory
+    set_env(&[("XDG_DATA_HOME", Some("")), ("XDG_CACHE_HOME", None)]);
+    assert_eq!(data_dir(), Some(PathBuf::from("/home/someone/.local/share/jmdictdb")));
+    assert_eq!(cache_dir(), Some(PathBuf::from("/home/someone/.cache/jmdictdb")));
+    assert_eq!(default_db_path(), PathBuf::from("/home/someone/.local/share/jmdictdb/jmdict_e.db"));
+
+    // Without a home directory, relative to the working directory
+    set_env(&[("HOME", None), ("XDG_DATA_HOME", None)]);
+    assert_eq!(data_dir(), None);
+    assert_eq!(cache_dir(), None);
+    assert_eq!(default_db_path(), PathBuf::from("data/jmdict_e.db"));
+}
+
+#[test]
+fn connect_default_reports_a_missing_database() {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let path = std::env::temp_dir().join(format!("jmdictdb-paths-missing-{}.db", std::process::id()));
+    set_env(&[(DB_PATH_ENV, path.to_str())]);
+    // On a runtime of its own, so the lock is not held across an await of the test harness
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let Err(err) = runtime.block_on(DictDb::connect_default()) else { panic!("connected without a database") };
+    assert_eq!(err.downcast_ref::<DictError>(), Some(&DictError::DatabaseNotBuilt(path.clone())));
+    assert!(err.to_string().contains("run `cargo run --bin build_db`"));
+    assert!(!path.exists());
+}
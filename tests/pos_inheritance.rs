@@ -0,0 +1,61 @@
+use jmdictdb::{parse_entry_xml, DictDb, EntryParsed};
+
+// 食べる as JMdict has it: the second sense has no <pos> of its own
+const TABERU: &str = "<entry><ent_seq>1358280</ent_seq>\
+    <k_ele><keb>食べる</keb><ke_pri>ichi1</ke_pri><ke_pri>news2</ke_pri><ke_pri>nf25</ke_pri></k_ele>\
+    <k_ele><keb>喰べる</keb><ke_inf>&iK;</ke_inf></k_ele>\
+    <r_ele><reb>たべる</reb><re_pri>ichi1</re_pri><re_pri>news2</re_pri></r_ele>\
+    <sense><pos>&v1;</pos><pos>&vt;</pos><gloss>to eat</gloss></sense>\
+    <sense><gloss>to live on (e.g. a salary)</gloss><gloss>to live off</gloss><gloss>to subsist on</gloss></sense>\
+    </entry>";
+
+// A made-up entry: misc is carried over too, and a sense with its own tags stops the carrying
+const TAGGED: &str = "<entry><ent_seq>1000010</ent_seq>\
+    <r_ele><reb>あそこ</reb></r_ele>\
+    <sense><pos>&pn;</pos><misc>&uk;</misc><gloss>there</gloss></sense>\
+    <sense><gloss>over there</gloss></sense>\
+    <sense><pos>&n;</pos><misc>&col;</misc><gloss>genitals</gloss></sense>\
+    <sense><gloss>that place</gloss></sense>\
+    </entry>";
+
+#[tokio::test]
+async fn later_senses_inherit_the_part_of_speech() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[parse_entry_xml(TABERU).unwrap()]).await.unwrap();
+    let stored = db.entry_by_seq(1358280).await.unwrap().unwrap();
+    assert_eq!(stored.senses[0].pos, ["Ichidan verb", "transitive verb"]);
+    assert_eq!(stored.senses[1].pos, stored.senses[0].pos);
+    assert!(!stored.senses[0].pos_inherited);
+    assert!(stored.senses[1].pos_inherited);
+
+    // The literal view is the XML's
+    assert_eq!(stored.senses[0].literal_pos(), ["Ichidan verb", "transitive verb"]);
+    assert!(stored.senses[1].literal_pos().is_empty());
+
+    // Converting without a database agrees
+    let converted = EntryParsed::try_from(&parse_entry_xml(TABERU).unwrap()).unwrap();
+    assert_eq!(converted.senses, stored.senses);
+    // The parsed XML itself is left as written
+    assert!(parse_entry_xml(TABERU).unwrap().sense[1].pos.is_empty());
+}
+
+#[tokio::test]
+async fn misc_is_inherited_until_a_sense_has_its_own() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[parse_entry_xml(TAGGED).unwrap()]).await.unwrap();
+    let senses = db.entry_by_seq(1000010).await.unwrap().unwrap().senses;
+    let pos: Vec<Vec<&str>> = senses.iter().map(|s| s.pos.iter().map(String::as_str).collect()).collect();
+    assert_eq!(pos, [["pronoun"], ["pronoun"], ["noun (common) (futsuumeishi)"], ["noun (common) (futsuumeishi)"]]);
+    let misc: Vec<Vec<&str>> = senses.iter().map(|s| s.misc.iter().map(String::as_str).collect()).collect();
+    assert_eq!(misc[0], misc[1]);
+    assert_eq!(misc[2], misc[3]);
+    assert_ne!(misc[1], misc[2]);
+    let inherited: Vec<(bool, bool)> = senses.iter().map(|s| (s.pos_inherited, s.misc_inherited)).collect();
+    assert_eq!(inherited, [(false, false), (true, true), (false, false), (true, true)]);
+    assert!(senses[1].literal_misc().is_empty());
+    assert_eq!(senses[0].literal_misc(), senses[0].misc);
+
+    // Helpers reading the tags see the inherited ones
+    assert!(senses[1].is_usually_kana());
+    assert!(!senses[3].is_usually_kana());
+}
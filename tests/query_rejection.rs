@@ -0,0 +1,74 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::{DictDb, DictDbOptions, DictError, GlossQuery, QueryGuards, RankOptions, Result, DEFAULT_LANG};
+
+fn rejection<T: std::fmt::Debug>(result: Result<T>) -> DictError {
+    let err = result.expect_err("the query should be rejected");
+    err.downcast_ref::<DictError>().cloned().unwrap_or_else(|| panic!("not a DictError: {}", err))
+}
+
+async fn sample_with(guards: QueryGuards) -> DictDb {
+    let db = DictDbOptions::new("sqlite::memory:").query_guards(guards).connect().await.unwrap();
+    db.insert_entries(&sample_dict().entry).await.unwrap();
+    db
+}
+
+#[tokio::test]
+async fn empty_queries_are_rejected_everywhere() {
+    let db = build_sample_db().await;
+    let opts = RankOptions::default();
+    for query in ["", " ", "\t\u{3000}"] {
+        assert_eq!(rejection(db.search_ranked(query, &opts).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_ranked_outcome(query, &opts).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_entries_with_senses_by_reading(query).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_entries_with_senses_by_kanji(query).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_all_by_reading(query).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_entries_by_reading_normalized(query).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_verb_stem(query).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_entries_by_gloss(query, DEFAULT_LANG).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_expressions_containing(query).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.compounds_containing(query, 10).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.search_sentences(query, 10).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.suggest_fast(query, 10).await), DictError::EmptyQuery);
+        assert_eq!(rejection(db.suggest_corrections(query, 10).await), DictError::EmptyQuery);
+    }
+    // Nothing left to search for once normalized
+    assert_eq!(rejection(db.search_entries_by_gloss(" . ", DEFAULT_LANG).await), DictError::EmptyQuery);
+    assert_eq!(rejection(db.search_entries_by_gloss_query(&GlossQuery::parse("\"\""), DEFAULT_LANG).await), DictError::EmptyQuery);
+    assert_eq!(DictError::EmptyQuery.to_string(), "the query is empty");
+}
+
+#[tokio::test]
+async fn no_results_are_not_an_error() {
+    let db = build_sample_db().await;
+    assert!(db.search_ranked("ぴよぴよ", &RankOptions::default()).await.unwrap().is_empty());
+    assert!(db.search_entries_by_gloss("no such gloss", DEFAULT_LANG).await.unwrap().is_empty());
+    assert!(db.search_expressions_containing("ぴよぴよ").await.unwrap().is_empty());
+    assert!(db.suggest_fast("ぴよ", 10).await.unwrap().is_empty());
+    // Nor is asking for no results
+    assert!(db.compounds_containing("手紙", 0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn short_substring_searches_are_rejected() {
+    let db = build_sample_db().await;
+    assert_eq!(rejection(db.search_expressions_containing("か").await), DictError::QueryTooShort { min: 2 });
+    assert_eq!(rejection(db.compounds_containing(" て ", 10).await), DictError::QueryTooShort { min: 2 });
+    assert_eq!(rejection(db.search_sentences("a", 10).await), DictError::QueryTooShort { min: 2 });
+    assert_eq!(DictError::QueryTooShort { min: 2 }.to_string(), "the query needs at least 2 characters");
+    // A single kanji goes through the kanji index, and exact and prefix searches have no minimum
+    assert!(db.search_expressions_containing("木").await.is_ok());
+    assert!(!db.search_ranked("た", &RankOptions::default()).await.unwrap().is_empty());
+    assert!(!db.suggest_fast("た", 10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn the_minimum_lengths_are_configurable() {
+    let strict = sample_with(QueryGuards { min_substring_len: 3, min_kanji_substring_len: 2 }).await;
+    assert_eq!(rejection(strict.search_expressions_containing("から").await), DictError::QueryTooShort { min: 3 });
+    assert_eq!(rejection(strict.search_expressions_containing("木").await), DictError::QueryTooShort { min: 2 });
+    assert!(strict.search_expressions_containing("木から").await.is_ok());
+
+    let lenient = sample_with(QueryGuards::none()).await;
+    assert!(lenient.search_expressions_containing("か").await.is_ok());
+    assert_eq!(rejection(lenient.search_expressions_containing(" ").await), DictError::EmptyQuery);
+}
@@ -0,0 +1,59 @@
+use jmdictdb::fixtures::{build_sample_db, count_slowly};
+use jmdictdb::{DictError, QueryOptions};
+use std::time::{Duration, Instant};
+
+// Takes well over a minute when not interrupted
+const FOREVER: i64 = 10_000_000_000;
+
+#[tokio::test]
+async fn timeout_interrupts_the_statement_and_the_connection_is_reusable() {
+    let db = build_sample_db().await;
+    let opts = QueryOptions::timeout(Duration::from_millis(50));
+
+    for _ in 0..3 {
+        let started = Instant::now();
+        let err = db.with_query_options(&opts, count_slowly(&db, FOREVER)).await.unwrap_err();
+        let elapsed = started.elapsed();
+        assert_eq!(err.downcast_ref::<DictError>(), Some(&DictError::QueryTimeout(Duration::from_millis(50))));
+        assert!(elapsed >= Duration::from_millis(50) && elapsed < Duration::from_secs(2), "{:?}", elapsed);
+
+        // Right away, and the handler left on the connection doesn't stop later queries
+        let started = Instant::now();
+        assert_eq!(db.entry_count().await.unwrap() as usize, jmdictdb::fixtures::sample_dict().entry.len());
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert_eq!(count_slowly(&db, 300_000).await.unwrap(), 300_000);
+    }
+}
+
+#[tokio::test]
+async fn fast_searches_and_no_timeout_are_unaffected() {
+    let db = build_sample_db().await;
+    let opts = QueryOptions::timeout(Duration::from_secs(10));
+    let found = db.with_query_options(&opts, db.search_entries_with_senses_by_reading("たべる")).await.unwrap();
+    assert_eq!(found[0].ent_seq, 1358280);
+    let n = db.with_query_options(&QueryOptions::default(), count_slowly(&db, 100_000)).await.unwrap();
+    assert_eq!(n, 100_000);
+    // Well past the timeout of the first call
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let opts = QueryOptions::timeout(Duration::from_millis(10));
+    assert_eq!(db.with_query_options(&opts, count_slowly(&db, 10)).await.unwrap(), 10);
+}
+
+#[tokio::test]
+async fn dropped_searches_leave_the_pool_usable() {
+    let db = build_sample_db().await;
+    let opts = QueryOptions::timeout(Duration::from_millis(300));
+    // The user typed on after 50 ms
+    let abandoned = tokio::time::timeout(Duration::from_millis(50), db.with_query_options(&opts, count_slowly(&db, FOREVER))).await;
+    assert!(abandoned.is_err());
+
+    let started = Instant::now();
+    assert!(!db.search_entries_with_senses_by_reading("たべる").await.unwrap().is_empty());
+    assert!(started.elapsed() < Duration::from_millis(250), "{:?}", started.elapsed());
+    // The abandoned statement stops at its deadline and its connection can be used again
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    // Also on that connection: its handler is past the deadline but removed when acquired
+    for _ in 0..20 {
+        assert_eq!(count_slowly(&db, 20_000).await.unwrap(), 20_000);
+    }
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "raw-xml")]
+
+use jmdictdb::fixtures::sample_xml;
+use jmdictdb::{BuildOptions, DictDb};
+
+async fn build(opts: &BuildOptions) -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.build_from_reader_with(sample_xml().as_bytes(), opts).await.unwrap();
+    db
+}
+
+#[tokio::test]
+async fn the_entry_element_is_kept_as_the_file_has_it() {
+    let db = build(&BuildOptions { store_raw_xml: true }).await;
+    assert!(db.has_raw_xml().await.unwrap());
+    let xml = db.raw_xml(1358280).await.unwrap().unwrap();
+    assert!(xml.starts_with("<entry>") && xml.ends_with("</entry>"));
+    assert!(sample_xml().contains(&xml));
+    assert!(xml.contains("<ent_seq>1358280</ent_seq>"));
+    // The entities are the references of the file, not their values
+    assert!(xml.contains("<pos>&v1;</pos>"));
+    assert_eq!(db.raw_xml(1).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn a_build_without_it_drops_the_stored_xml() {
+    let db = build(&BuildOptions::default()).await;
+    assert!(!db.has_raw_xml().await.unwrap());
+    assert_eq!(db.raw_xml(1358280).await.unwrap(), None);
+
+    db.build_from_reader_with(sample_xml().as_bytes(), &BuildOptions { store_raw_xml: true }).await.unwrap();
+    assert!(db.raw_xml(1358280).await.unwrap().is_some());
+    db.build_from_reader(sample_xml().as_bytes()).await.unwrap();
+    assert!(!db.has_raw_xml().await.unwrap());
+    assert_eq!(db.raw_xml(1358280).await.unwrap(), None);
+}
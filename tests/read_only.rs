@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use jmdictdb::fixtures::sample_xml;
+use jmdictdb::{DictDb, DictDbOptions, DictError, RankOptions, SCHEMA_VERSION};
+
+fn db_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("jmdictdb-read-only-{}-{}.db", name, std::process::id()))
+}
+
+fn remove(path: &Path) {
+    for suffix in ["", "-shm", "-wal"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+/// Builds the sample dictionary into a new file, returns its build id.
+async fn build(path: &Path) -> String {
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.build_from_reader(sample_xml().as_bytes()).await.unwrap();
+    let build_id = db.build_id().to_string();
+    db.close().await;
+    build_id
+}
+
+async fn connect_read_only(path: &Path) -> DictDb {
+    DictDb::connect(&format!("sqlite:{}?mode=ro", path.display())).await.unwrap()
+}
+
+#[tokio::test]
+async fn a_built_file_opens_read_only() {
+    let path = db_path("built");
+    let build_id = build(&path).await;
+
+    let db = connect_read_only(&path).await;
+    assert_eq!(db.build_id(), build_id);
+    let found = db.search_entries_with_senses_by_reading("たべる").await.unwrap();
+    assert_eq!(found[0].ent_seq, 1358280);
+    let ranked = db.search_ranked("食べる", &RankOptions::default()).await.unwrap();
+    assert_eq!(ranked[0].entry.ent_seq, 1358280);
+    db.check_generation().await.unwrap();
+    assert!(db.health_check().await.unwrap().ok);
+    // Writes fail
+    assert!(db.delete_entry(1358280).await.is_err());
+    db.close().await;
+    remove(&path);
+}
+
+#[tokio::test]
+async fn a_file_without_a_build_id_gets_one_from_the_file() {
+    let path = db_path("no-build-id");
+    build(&path).await;
+    // As written by versions before build ids
+    let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}", path.display())).await.unwrap();
+    sqlx::query("DELETE FROM meta WHERE key = 'build_id'").execute(&pool).await.unwrap();
+    pool.close().await;
+
+    let db = connect_read_only(&path).await;
+    assert!(db.build_id().starts_with("file-"), "{}", db.build_id());
+    assert_eq!(connect_read_only(&path).await.build_id(), db.build_id());
+    db.check_generation().await.unwrap();
+    assert_eq!(db.search_entries_with_senses_by_reading("ねこ").await.unwrap().len(), 1);
+
+    // A rebuilt file is another file
+    build(&path).await;
+    let err = db.check_generation().await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<DictError>(), Some(DictError::DatabaseReplaced(_))));
+    db.close().await;
+    remove(&path);
+}
+
+#[tokio::test]
+async fn connecting_to_a_current_file_writes_nothing() {
+    let path = db_path("current");
+    build(&path).await;
+    let url = format!("sqlite:{}", path.display());
+    // While another connection holds the write lock, any write on connect would be busy
+    let pool = sqlx::SqlitePool::connect(&url).await.unwrap();
+    let mut blocker = pool.acquire().await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut *blocker).await.unwrap();
+    let db = DictDbOptions::new(&url).busy_timeout(Duration::ZERO).connect().await.unwrap();
+    assert_eq!(db.health_check().await.unwrap().schema_version, Some(SCHEMA_VERSION));
+    sqlx::query("ROLLBACK").execute(&mut *blocker).await.unwrap();
+    db.close().await;
+    drop(blocker);
+    pool.close().await;
+    remove(&path);
+}
@@ -0,0 +1,104 @@
+use jmdictdb::{normalize_kana, DictDb, Entry, KanaNormalization, REle, Sense, READING_NORMALIZATION};
+use sqlx::SqlitePool;
+
+fn norm(s: &str) -> String {
+    normalize_kana(s, READING_NORMALIZATION)
+}
+
+fn entry(ent_seq: i64, rebs: &[&str]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: None,
+        r_ele: rebs.iter().map(|r| REle { reb: r.to_string(), ..Default::default() }).collect(),
+        sense: vec![
+            Sense { gloss: vec![format!("word {}", ent_seq)], ..Default::default() },
+            Sense { stagr: vec![rebs[0].to_string()], gloss: vec!["first reading only".to_string()], ..Default::default() },
+        ],
+    }
+}
+
+async fn seqs(db: &DictDb, reading: &str) -> Vec<i64> {
+    db.search_entries_by_reading_normalized(reading).await.unwrap().iter().map(|e| e.ent_seq).collect()
+}
+
+#[test]
+fn spelling_variants_normalize_alike() {
+    for variant in ["ラーメン", "らーめん", "ラ-メン", "ラ－メン", "らあめん"] {
+        assert_eq!(norm(variant), "らあめん", "{}", variant);
+    }
+    assert_eq!(norm("ウォ"), norm("ウオ"));
+    assert_eq!(norm("ヴァイオリン"), "ゔあいおりん");
+    assert_eq!(norm("ヴァイオリン"), norm("ゔぁいおりん"));
+    assert_eq!(norm("スーパー"), "すうぱあ");
+    assert_eq!(norm("ファックス"), "ふあつくす");
+    assert_eq!(norm("ケーキ"), "けえき");
+    assert_eq!(norm("コーヒー"), "こおひい");
+
+    // Kept where there is no vowel to resolve against
+    assert_eq!(norm("ンー"), "んー");
+    assert_eq!(norm("ッー"), "つー");
+    assert_eq!(norm("ー"), "ー");
+    assert_eq!(norm("CD-ROM"), "CD-ROM");
+    assert_eq!(norm(""), "");
+}
+
+#[test]
+fn options_choose_what_is_folded() {
+    let plain = KanaNormalization::default();
+    assert_eq!(normalize_kana("ファックス", plain), "ふぁっくす");
+    assert_eq!(normalize_kana("スー-パー", plain), "すーーぱー");
+    let long_vowels = KanaNormalization { resolve_long_vowels: true, ..plain };
+    assert_eq!(normalize_kana("ファーックス", long_vowels), "ふぁあっくす");
+    let small_kana = KanaNormalization { fold_small_kana: true, ..plain };
+    assert_eq!(normalize_kana("ファーックス", small_kana), "ふあーつくす");
+}
+
+#[tokio::test]
+async fn search_by_normalized_reading() {
+    let path = std::env::temp_dir().join(format!("jmdictdb-reading-index-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    db.insert_entries(&[
+        entry(1, &["ラーメン", "らーめん"]),
+        entry(2, &["スーパー"]),
+        entry(3, &["ファックス", "ファクス"]),
+        entry(4, &["ヴァイオリン", "バイオリン"]),
+        entry(5, &["ウォッカ"]),
+    ])
+    .await
+    .unwrap();
+
+    assert_eq!(seqs(&db, "ラ-メン").await, [1]);
+    assert_eq!(seqs(&db, "すーぱー").await, [2]);
+    assert_eq!(seqs(&db, "すうぱあ").await, [2]);
+    assert_eq!(seqs(&db, "ふぁっくす").await, [3]);
+    assert_eq!(seqs(&db, "ふぁくす").await, [3]);
+    assert_eq!(seqs(&db, "ゔあいおりん").await, [4]);
+    assert_eq!(seqs(&db, "ウオツカ").await, [5]);
+    assert!(seqs(&db, "らめん").await.is_empty());
+    assert!(db.search_entries_by_reading_normalized("").await.is_err());
+
+    // Restrictions are checked against the reb that matched
+    let found = db.search_entries_by_reading_normalized("ばいおりん").await.unwrap();
+    assert_eq!(found[0].senses.iter().map(|s| s.applies_to_query).collect::<Vec<_>>(), [true, false]);
+    let found = db.search_entries_by_reading_normalized("ヴぁいおりん").await.unwrap();
+    assert_eq!(found[0].senses.iter().map(|s| s.applies_to_query).collect::<Vec<_>>(), [true, true]);
+
+    db.upsert_entry(&entry(2, &["スーツ"])).await.unwrap();
+    assert!(seqs(&db, "すーぱー").await.is_empty());
+    db.delete_entry(1).await.unwrap();
+    assert!(seqs(&db, "らーめん").await.is_empty());
+    db.close().await;
+
+    // Databases built before the index get it on connect
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("DROP TABLE reading_index").execute(&pool).await.unwrap();
+    pool.close().await;
+    let db = DictDb::connect(&url).await.unwrap();
+    assert_eq!(seqs(&db, "すうつ").await, [2]);
+    assert_eq!(seqs(&db, "ウオッカ").await, [5]);
+
+    db.close().await;
+    let _ = std::fs::remove_file(path);
+}
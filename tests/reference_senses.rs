@@ -0,0 +1,66 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::render::{terminal, TerminalOptions};
+use jmdictdb::{DictDb, Entry, EntryParsed, QueryOptions, REle, Sense, Xref};
+
+const RESOLVE: QueryOptions = QueryOptions {
+    timeout: None,
+    max_senses: None,
+    max_glosses_per_sense: None,
+    hydration_concurrency: None,
+    resolve_references: true,
+};
+
+async fn omamma(db: &DictDb, opts: &QueryOptions) -> EntryParsed {
+    db.with_query_options(opts, db.search_entries_with_senses_by_reading("おまんま")).await.unwrap().remove(0)
+}
+
+#[tokio::test]
+async fn reference_only_senses_read_as_see() {
+    let db = build_sample_db().await;
+    let entry = omamma(&db, &QueryOptions::default()).await;
+    assert_eq!(entry.ent_seq, 2899990);
+    let [see, own] = entry.senses.as_slice() else { panic!("two senses") };
+    assert!(see.is_reference_only() && see.gloss.is_empty());
+    assert!(!own.is_reference_only());
+    assert_eq!(see.borrowed, None);
+    assert_eq!(see.summary(3), "→ see 御飯・ごはん・1");
+    assert_eq!(entry.summary(80), "(1) → see 御飯・ごはん・1 (2) one's livelihood");
+    assert!(terminal(&entry, &TerminalOptions::default()).contains(" 1. [n] → see 御飯・ごはん・1\n"));
+
+    // Senses with a gloss and a cross-reference are not reference-only
+    let ocha = db.search_entries_with_senses_by_kanji("お茶").await.unwrap().remove(0);
+    assert!(ocha.senses.iter().all(|s| !s.is_reference_only()));
+}
+
+#[tokio::test]
+async fn references_resolve_to_the_glosses_of_their_target() {
+    let db = build_sample_db().await;
+    let entry = omamma(&db, &RESOLVE).await;
+    let borrowed = entry.senses[0].borrowed.as_ref().expect("the glosses of 御飯 sense 1");
+    assert_eq!(borrowed.from, "御飯・ごはん・1".parse::<Xref>().unwrap());
+    assert_eq!((borrowed.ent_seq, borrowed.sense_order), (1331080, 0));
+    assert_eq!(borrowed.gloss, ["cooked rice"]);
+    // Borrowed, not the sense's own
+    assert!(entry.senses[0].gloss.is_empty() && entry.senses[0].is_reference_only());
+    assert_eq!(entry.senses[0].summary(3), "→ see 御飯・ごはん・1 (cooked rice)");
+    assert_eq!(entry.senses[1].borrowed, None);
+
+    // Outside the call the entry is as stored
+    assert_eq!(db.entry_by_seq(2899990).await.unwrap().unwrap().senses[0].borrowed, None);
+}
+
+#[tokio::test]
+async fn unresolvable_references_borrow_nothing() {
+    let db = build_sample_db().await;
+    let reference = |xref: &str| Sense { pos: vec!["n".to_string()], xref: vec![xref.to_string()], ..Default::default() };
+    let entry = Entry {
+        ent_seq: "2900070".to_string(),
+        r_ele: vec![REle { reb: "ぴよぴよ".to_string(), ..Default::default() }],
+        sense: vec![reference("ぴよぴよ・1"), reference("ぴよぴよ・2"), reference("ない言葉"), reference("御飯・ごはん・3")],
+        ..Default::default()
+    };
+    db.insert_entries(&[entry]).await.unwrap();
+    let found = db.with_query_options(&RESOLVE, db.entry_by_seq(2900070)).await.unwrap().unwrap();
+    // Itself, another reference-only sense, a missing word and a missing sense
+    assert!(found.senses.iter().all(|s| s.is_reference_only() && s.borrowed.is_none()));
+}
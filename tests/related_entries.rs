@@ -0,0 +1,44 @@
+use jmdictdb::fixtures::build_sample_db;
+
+#[tokio::test]
+async fn rarer_shared_kanji_rank_first() {
+    let db = build_sample_db().await;
+    // 学生: 生 is in 2 entries, 学 in 3; 大学 is more frequent than 学校
+    let related = db.related_entries(1198870, 10).await.unwrap();
+    let found: Vec<(&str, Vec<char>)> = related
+        .iter()
+        .map(|r| (r.entry.kebs.as_ref().unwrap()[0].as_str(), r.shared_chars.clone()))
+        .collect();
+    assert_eq!(found, [("先生", vec!['生']), ("大学", vec!['学']), ("学校", vec!['学'])]);
+    assert!(related[0].score > related[1].score);
+    assert_eq!(related[1].score, related[2].score);
+
+    let top = db.related_entries(1198870, 1).await.unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].entry.ent_seq, related[0].entry.ent_seq);
+}
+
+#[tokio::test]
+async fn source_is_left_out() {
+    let db = build_sample_db().await;
+    let related = db.related_entries(1358280, 10).await.unwrap(); // 食べる
+    let seqs: Vec<i64> = related.iter().map(|r| r.entry.ent_seq).collect();
+    assert_eq!(seqs, [1358300]); // 食べ物
+    assert_eq!(related[0].shared_chars, ['食']);
+}
+
+#[tokio::test]
+async fn kana_words_are_related_by_reading_prefix() {
+    let db = build_sample_db().await;
+    let related = db.related_entries(1001470, 10).await.unwrap(); // おはよう
+    assert!(!related.is_empty());
+    assert!(related.iter().all(|r| r.shared_chars.is_empty() && r.entry.ent_seq != 1001470));
+    assert!(related.iter().any(|r| r.entry.rebs.iter().any(|reb| reb == "おはようございます")));
+}
+
+#[tokio::test]
+async fn unknown_entry_has_no_related_entries() {
+    let db = build_sample_db().await;
+    assert!(db.related_entries(42, 10).await.unwrap().is_empty());
+    assert!(db.related_entries(1198870, 0).await.unwrap().is_empty());
+}
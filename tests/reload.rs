@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use jmdictdb::{DictDb, DictDbOptions, DictError, Entry, REle, ReloadableDictDb, Sense};
+
+/// A database file holding ねこ, glossed `gloss` in every sense.
+async fn build(name: &str, gloss: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("jmdictdb-reload-{}-{}.db", name, std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    if !gloss.is_empty() {
+        let db = DictDb::connect(&url).await.unwrap();
+        let sense = || Sense { gloss: vec![gloss.to_string()], ..Default::default() };
+        let neko = Entry {
+            ent_seq: "1467640".to_string(),
+            r_ele: vec![REle { reb: "ねこ".to_string(), ..Default::default() }],
+            sense: vec![sense(), sense()],
+            ..Default::default()
+        };
+        db.insert_entries(&[neko]).await.unwrap();
+        db.close().await;
+    }
+    path
+}
+
+fn glosses_of(found: &[jmdictdb::EntryParsed]) -> Vec<String> {
+    found.iter().flat_map(|e| e.senses.iter().flat_map(|s| s.gloss.clone())).collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn searches_never_see_a_half_swapped_database() {
+    let a = build("a", "cat").await;
+    let b = build("b", "feline").await;
+    let reloadable = Arc::new(ReloadableDictDb::open(&a, DictDbOptions::new("")).await.unwrap());
+    assert_eq!(reloadable.db_info().path, a);
+    assert_eq!(reloadable.db_info().generation, 1);
+    assert_eq!(reloadable.db_info().entries, 1);
+
+    let done = Arc::new(AtomicBool::new(false));
+    let searchers: Vec<_> = (0..4)
+        .map(|_| {
+            let reloadable = reloadable.clone();
+            let done = done.clone();
+            tokio::spawn(async move {
+                let mut searches = 0;
+                while !done.load(Ordering::Relaxed) {
+                    let db = reloadable.current();
+                    let found = db.search_entries_with_senses_by_reading("ねこ").await.unwrap();
+                    let glosses = glosses_of(&found);
+                    assert!(glosses == ["cat", "cat"] || glosses == ["feline", "feline"], "{:?}", glosses);
+                    searches += 1;
+                    tokio::task::yield_now().await;
+                }
+                searches
+            })
+        })
+        .collect();
+
+    for generation in 2..8 {
+        let path = if generation % 2 == 0 { &b } else { &a };
+        let info = reloadable.swap_to(path).await.unwrap();
+        assert_eq!((&info.path, info.generation), (path, generation));
+        assert_eq!(reloadable.db_info(), info);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    done.store(true, Ordering::Relaxed);
+    for searcher in searchers {
+        assert!(searcher.await.unwrap() > 0);
+    }
+
+    // Generation 7 is a
+    let found = reloadable.current().search_entries_with_senses_by_reading("ねこ").await.unwrap();
+    assert_eq!(glosses_of(&found), ["cat", "cat"]);
+    drop(reloadable);
+    for path in [a, b] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn keeps_serving_when_the_new_database_is_unusable() {
+    let a = build("keep", "cat").await;
+    let empty = build("empty", "").await;
+    let reloadable = ReloadableDictDb::open(&a, DictDbOptions::new("")).await.unwrap();
+
+    let missing = std::env::temp_dir().join("jmdictdb-reload-missing.db");
+    let err = reloadable.swap_to(&missing).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<DictError>(), Some(DictError::DatabaseNotBuilt(_))));
+    assert!(!missing.exists());
+
+    // A SQLite file without the tables is left as it is
+    let err = reloadable.swap_to(&empty).await.unwrap_err();
+    assert!(err.to_string().contains("not a jmdictdb database"));
+    let tables: i64 = {
+        let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}", empty.display())).await.unwrap();
+        let n = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master").fetch_one(&pool).await.unwrap();
+        pool.close().await;
+        n
+    };
+    assert_eq!(tables, 0);
+
+    // The schema without entries
+    DictDb::connect(&format!("sqlite:{}", empty.display())).await.unwrap().close().await;
+    let err = reloadable.swap_to(&empty).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<DictError>(), Some(DictError::DatabaseEmpty(_))));
+
+    assert_eq!(reloadable.db_info().path, a);
+    assert_eq!(reloadable.db_info().generation, 1);
+    assert!(reloadable.current().entry_by_seq(1467640).await.unwrap().is_some());
+    drop(reloadable);
+    for path in [a, empty] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
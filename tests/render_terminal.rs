@@ -0,0 +1,121 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::render::{display_width, terminal, terminal_with_furigana, TerminalOptions};
+use jmdictdb::{parse_entry_xml, DictDb, EntryParsed, FuriganaSegment};
+
+const PLAIN: TerminalOptions = TerminalOptions { color: false, wide_chars: true };
+const NARROW: TerminalOptions = TerminalOptions { color: false, wide_chars: false };
+const COLOR: TerminalOptions = TerminalOptions { color: true, wide_chars: true };
+
+async fn first(db: &DictDb, reading: &str) -> EntryParsed {
+    db.search_entries_with_senses_by_reading(reading).await.unwrap().remove(0)
+}
+
+#[tokio::test]
+async fn puts_furigana_above_the_kanji() {
+    let db = build_sample_db().await;
+    assert_eq!(
+        terminal(&first(&db, "たべもの").await, &PLAIN),
+        "\
+た  もの
+食べ物
+ 1. [n] food; provisions
+"
+    );
+    // Readings wider than their kanji push the rest right, other forms follow the headword
+    assert_eq!(
+        terminal(&first(&db, "あたま").await, &PLAIN),
+        "\
+あたま
+頭  also かしら、とう
+ 1. [n] head
+ 2. [n] brain; intellect; mind
+ 3. [n] leader; chief; boss
+ 4. [ctr] counter for large animals
+"
+    );
+}
+
+#[tokio::test]
+async fn puts_furigana_beside_the_kanji_without_wide_characters() {
+    let db = build_sample_db().await;
+    assert_eq!(
+        terminal(&first(&db, "たべる").await, &NARROW),
+        "\
+食[た]べる  also 喰べる
+ 1. [v1 vt] to eat
+ 2. [v1 vt] to live on (e.g. a salary); to live off; to subsist on
+"
+    );
+    assert_eq!(terminal(&first(&db, "たべもの").await, &NARROW), "食[た]べ物[もの]\n 1. [n] food; provisions\n");
+}
+
+#[tokio::test]
+async fn kana_headwords_have_no_furigana() {
+    let db = build_sample_db().await;
+    let suru = first(&db, "する").await;
+    assert_eq!(terminal(&suru, &PLAIN), terminal(&suru, &NARROW));
+    assert!(terminal(&suru, &PLAIN).starts_with("する\n 1. [vs-i] to do;"));
+    // Usually written in kana, with the kanji forms after it and the misc tag with the pos
+    assert_eq!(
+        terminal(&first(&db, "おはようございます").await, &PLAIN),
+        "おはようございます  also お早うございます、御早うございます\n 1. [exp int uk] good morning\n"
+    );
+}
+
+#[tokio::test]
+async fn uses_the_given_furigana() {
+    let db = build_sample_db().await;
+    let taberu = first(&db, "たべる").await;
+    let segments = [
+        FuriganaSegment { text: "食".to_string(), ruby: Some("た".to_string()) },
+        FuriganaSegment { text: "べる".to_string(), ruby: None },
+    ];
+    assert_eq!(terminal_with_furigana(&taberu, Some(&segments), &PLAIN), terminal(&taberu, &PLAIN));
+    // Without any, the reading follows the headword
+    assert!(terminal_with_furigana(&taberu, None, &PLAIN).starts_with("食べる【たべる】  also 喰べる\n"));
+}
+
+#[tokio::test]
+async fn colors_tags_and_dims_rare_entries() {
+    let db = build_sample_db().await;
+    assert_eq!(
+        terminal(&first(&db, "たべもの").await, &COLOR),
+        "た  もの\n食べ物\n \x1b[1m1.\x1b[22m [\x1b[36mn\x1b[39m] food; provisions\n"
+    );
+
+    let rare = parse_entry_xml(
+        "<entry><ent_seq>2900001</ent_seq><k_ele><keb>御座る</keb></k_ele><r_ele><reb>ござる</reb></r_ele>\
+         <sense><pos>&v5r;</pos><misc>&arch;</misc><gloss>to be</gloss></sense></entry>",
+    )
+    .unwrap();
+    db.insert_entries(&[rare]).await.unwrap();
+    let rare = db.entry_by_seq(2_900_001).await.unwrap().unwrap();
+    assert_eq!(
+        terminal(&rare, &COLOR),
+        "\x1b[2mござ\x1b[22m\n\
+         \x1b[2m御座る\x1b[22m\n\
+         \x1b[2m \x1b[1m1.\x1b[22m\x1b[2m [\x1b[36mv5r\x1b[39m \x1b[33march\x1b[39m] to be\x1b[22m\n"
+    );
+    assert_eq!(terminal(&rare, &PLAIN), "ござ\n御座る\n 1. [v5r arch] to be\n");
+}
+
+#[tokio::test]
+async fn numbers_senses_to_the_same_width() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let senses: String = (1..=10).map(|n| format!("<sense><gloss>meaning {}</gloss></sense>", n)).collect();
+    let entry = parse_entry_xml(&format!("<entry><ent_seq>2900002</ent_seq><r_ele><reb>こう</reb></r_ele>{}</entry>", senses)).unwrap();
+    db.insert_entries(&[entry]).await.unwrap();
+    let rendered = terminal(&db.entry_by_seq(2_900_002).await.unwrap().unwrap(), &PLAIN);
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines[0], "こう");
+    assert_eq!(lines[1], "  1. meaning 1");
+    assert_eq!(lines[10], " 10. meaning 10");
+}
+
+#[test]
+fn measures_columns() {
+    assert_eq!(display_width("食べ物"), 6);
+    assert_eq!(display_width("Tシャツ (T-shirt)"), 17);
+    assert_eq!(display_width("ＡＢ、ｱ"), 7);
+    assert_eq!(display_width(""), 0);
+}
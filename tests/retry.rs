@@ -0,0 +1,91 @@
+use jmdictdb::{DictDb, DictDbOptions, DictError, Entry, REle, RetryPolicy};
+use sqlx::SqlitePool;
+use std::time::Duration;
+
+fn entry(ent_seq: i64) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        r_ele: vec![REle { reb: "かぎ".to_string(), ..Default::default() }],
+        ..Default::default()
+    }
+}
+
+fn policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(40),
+        jitter: true,
+    }
+}
+
+async fn setup(name: &str, max_attempts: u32) -> (DictDb, SqlitePool, String) {
+    let path = std::env::temp_dir().join(format!("jmdictdb-retry-{}-{}.db", name, std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDbOptions::new(&url)
+        .busy_timeout(Duration::ZERO)
+        .retry(policy(max_attempts))
+        .connect()
+        .await
+        .unwrap();
+    let other = SqlitePool::connect(&url).await.unwrap();
+    (db, other, path.display().to_string())
+}
+
+#[test]
+fn delays_double_up_to_the_cap() {
+    let fixed = RetryPolicy { jitter: false, ..policy(5) };
+    // 0 is not an attempt number, but doesn't overflow
+    assert_eq!(fixed.delay(0), Duration::from_millis(10));
+    assert_eq!(fixed.delay(1), Duration::from_millis(10));
+    assert_eq!(fixed.delay(2), Duration::from_millis(20));
+    assert_eq!(fixed.delay(3), Duration::from_millis(40));
+    assert_eq!(fixed.delay(4), Duration::from_millis(40));
+    assert_eq!(fixed.delay(u32::MAX), Duration::from_millis(40));
+    // With jitter between half and all of it
+    let jittered = policy(5).delay(2);
+    assert!(jittered >= Duration::from_millis(10) && jittered <= Duration::from_millis(20), "{:?}", jittered);
+}
+
+#[tokio::test]
+async fn writes_are_retried_until_the_lock_is_released() {
+    let (db, other, path) = setup("released", 100).await;
+
+    let mut blocker = other.acquire().await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut *blocker).await.unwrap();
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        sqlx::query("COMMIT").execute(&mut *blocker).await.unwrap();
+    });
+
+    assert!(db.upsert_entry(&entry(1)).await.unwrap());
+    release.await.unwrap();
+    assert_eq!(db.search_entries_with_senses_by_reading("かぎ").await.unwrap().len(), 1);
+
+    db.close().await;
+    other.close().await;
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn exhausted_retries_report_the_attempt_count() {
+    let (db, other, path) = setup("exhausted", 3).await;
+
+    let mut blocker = other.acquire().await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut *blocker).await.unwrap();
+
+    let err = db.upsert_entry(&entry(1)).await.unwrap_err();
+    match err.downcast_ref::<DictError>() {
+        Some(DictError::Busy { attempts, .. }) => assert_eq!(*attempts, 3),
+        other => panic!("expected DictError::Busy, got {:?}", other),
+    }
+    // Reads are not blocked by the writer
+    assert!(db.search_entries_with_senses_by_reading("かぎ").await.unwrap().is_empty());
+
+    sqlx::query("ROLLBACK").execute(&mut *blocker).await.unwrap();
+    drop(blocker);
+    db.close().await;
+    other.close().await;
+    let _ = std::fs::remove_file(path);
+}
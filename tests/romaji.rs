@@ -0,0 +1,111 @@
+use jmdictdb::kana::{hiragana_to_katakana, is_hiragana, is_katakana, katakana_to_hiragana};
+use jmdictdb::{romaji_to_kana, romanize, romanize_with};
+use proptest::prelude::*;
+
+#[test]
+fn n_before_vowels_and_y() {
+    assert_eq!(romanize("きんえん"), "kin'en");
+    assert_eq!(romanize("はんい"), "han'i");
+    assert_eq!(romanize("こんや"), "kon'ya");
+    assert_eq!(romanize("しんよう"), "shin'you");
+    // Before a consonant, another ん or at the end it stays n
+    assert_eq!(romanize("しんぶん"), "shinbun");
+    assert_eq!(romanize("おんな"), "onna");
+    assert_eq!(romanize("あんない"), "annai");
+    assert_eq!(romaji_to_kana("kin'en"), "きんえん");
+    assert_eq!(romaji_to_kana("kon'ya"), "こんや");
+    assert_eq!(romaji_to_kana("shinbun"), "しんぶん");
+    assert_eq!(romaji_to_kana("onna"), "おんな");
+}
+
+#[test]
+fn small_tsu_doubles_the_consonant() {
+    assert_eq!(romanize("がっこう"), "gakkou");
+    assert_eq!(romanize("きって"), "kitte");
+    assert_eq!(romanize("まっちゃ"), "matcha");
+    assert_eq!(romanize("いっしょ"), "issho");
+    assert_eq!(romanize("ざっし"), "zasshi");
+    assert_eq!(romaji_to_kana("gakkou"), "がっこう");
+    assert_eq!(romaji_to_kana("matcha"), "まっちゃ");
+    assert_eq!(romaji_to_kana("issho"), "いっしょ");
+}
+
+#[test]
+fn long_vowels() {
+    assert_eq!(romanize("とうきょう"), "toukyou");
+    assert_eq!(romanize_with("とうきょう", true), "tōkyō");
+    assert_eq!(romanize("スーパー"), "suupaa");
+    assert_eq!(romanize_with("スーパー", true), "sūpā");
+    assert_eq!(romanize_with("おかあさん", true), "okāsan");
+    assert_eq!(romanize_with("おにいさん", true), "oniisan");
+    assert_eq!(romanize_with("せんせい", true), "sensei");
+    assert_eq!(romaji_to_kana("tōkyō"), "とうきょう");
+    assert_eq!(romaji_to_kana("sūpā"), "すうぱあ");
+    assert_eq!(romaji_to_kana("ra-men"), "らーめん");
+}
+
+#[test]
+fn digraphs_and_the_hepburn_spellings() {
+    assert_eq!(romanize("しゃしん"), "shashin");
+    assert_eq!(romanize("ちず"), "chizu");
+    assert_eq!(romanize("つき"), "tsuki");
+    assert_eq!(romanize("ふじ"), "fuji");
+    assert_eq!(romanize("ほんを"), "hon'o");
+    assert_eq!(romanize("ティー"), "tii");
+    assert_eq!(romanize("ファン"), "fan");
+    // Not kana, copied through
+    assert_eq!(romanize("ねこ3"), "neko3");
+}
+
+#[test]
+fn hiragana_and_katakana() {
+    assert_eq!(hiragana_to_katakana("ひらがな"), "ヒラガナ");
+    assert_eq!(katakana_to_hiragana("カタカナ"), "かたかな");
+    assert_eq!(katakana_to_hiragana("ヴァイオリン"), "ゔぁいおりん");
+    assert_eq!(hiragana_to_katakana("ゔぁっ"), "ヴァッ");
+    // ー and anything else stays
+    assert_eq!(katakana_to_hiragana("コーヒー1杯"), "こーひー1杯");
+    assert_eq!(hiragana_to_katakana("ラーメンと餃子"), "ラーメント餃子");
+    assert!(is_hiragana('ゖ') && !is_hiragana('ヶ'));
+    assert!(is_katakana('ー') && is_katakana('ヺ') && !is_katakana('ぁ'));
+}
+
+/// The kana that romanize to a spelling only they have: no ぢ, づ, ゐ, ゑ, を (written
+/// like じ, ず, い, え, お) and no small kana outside the digraphs.
+const SYLLABLES: &[&str] = &[
+    "あ", "い", "う", "え", "お", "か", "き", "く", "け", "こ", "が", "ぎ", "ぐ", "げ", "ご",
+    "さ", "し", "す", "せ", "そ", "ざ", "じ", "ず", "ぜ", "ぞ", "た", "ち", "つ", "て", "と",
+    "だ", "で", "ど", "な", "に", "ぬ", "ね", "の", "は", "ひ", "ふ", "へ", "ほ", "ば", "び",
+    "ぶ", "べ", "ぼ", "ぱ", "ぴ", "ぷ", "ぺ", "ぽ", "ま", "み", "む", "め", "も", "や", "ゆ",
+    "よ", "ら", "り", "る", "れ", "ろ", "わ", "ゔ", "きゃ", "きょ", "しゃ", "しゅ", "じょ",
+    "ちゃ", "ちぇ", "にゅ", "ひょ", "びゃ", "ぴゅ", "みょ", "りゃ", "ふぁ", "てぃ", "とぅ",
+    "でゅ", "うぃ", "うぉ", "つぁ", "いぇ",
+];
+
+/// A syllable, ん, or っ before a syllable it can double: not a vowel, the な row, whose
+/// doubled n reads as ん, or the y and w semivowels, which no word doubles.
+fn kana_token() -> impl Strategy<Value = String> {
+    let doubled = SYLLABLES
+        .iter()
+        .filter(|s| !s.starts_with(['あ', 'い', 'う', 'え', 'お', 'な', 'に', 'ぬ', 'ね', 'の', 'や', 'ゆ', 'よ', 'わ']))
+        .map(|s| format!("っ{}", s))
+        .collect::<Vec<_>>();
+    let mut tokens: Vec<String> = SYLLABLES.iter().map(|s| s.to_string()).collect();
+    tokens.push("ん".to_string());
+    tokens.extend(doubled);
+    prop::sample::select(tokens)
+}
+
+proptest! {
+    #[test]
+    fn kana_round_trips_through_romaji(tokens in prop::collection::vec(kana_token(), 1..8)) {
+        let kana = tokens.concat();
+        prop_assert_eq!(romaji_to_kana(&romanize(&kana)), kana.clone());
+        prop_assert_eq!(romaji_to_kana(&romanize(&hiragana_to_katakana(&kana))), kana);
+    }
+
+    #[test]
+    fn katakana_round_trips_through_hiragana(s in "[ぁ-ゖ]{0,12}") {
+        prop_assert_eq!(katakana_to_hiragana(&hiragana_to_katakana(&s)), s);
+    }
+}
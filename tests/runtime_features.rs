@@ -0,0 +1,45 @@
+//! The library on each async runtime feature. Every check builds the crate in a target
+//! directory of its own, so they only run when asked for:
+//! cargo test --test runtime_features -- --ignored
+
+use std::path::Path;
+use std::process::Output;
+
+/// `cargo check --lib` with only `features`.
+fn check_lib(features: &[&str]) -> Output {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut cargo = std::process::Command::new(env!("CARGO"));
+    cargo.current_dir(dir).args(["check", "--lib", "--quiet", "--no-default-features"]);
+    if !features.is_empty() {
+        cargo.args(["--features", &features.join(",")]);
+    }
+    cargo.arg("--target-dir").arg(dir.join("target/runtime-features"));
+    cargo.output().unwrap()
+}
+
+fn assert_builds(features: &[&str]) {
+    let out = check_lib(features);
+    assert!(out.status.success(), "{:?}:\n{}", features, String::from_utf8_lossy(&out.stderr));
+}
+
+fn assert_rejected(features: &[&str], message: &str) {
+    let out = check_lib(features);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(!out.status.success(), "{:?} built", features);
+    assert!(stderr.contains(message), "{:?}:\n{}", features, stderr);
+}
+
+#[test]
+#[ignore = "builds the crate"]
+fn builds_on_either_runtime() {
+    assert_builds(&["runtime-tokio"]);
+    assert_builds(&["runtime-async-std"]);
+    assert_builds(&["runtime-async-std", "userdata", "bundled-db", "msgpack"]);
+}
+
+#[test]
+#[ignore = "builds the crate"]
+fn needs_exactly_one_runtime() {
+    assert_rejected(&[], "jmdictdb needs an async runtime for sqlx");
+    assert_rejected(&["runtime-tokio", "runtime-async-std"], "enable only one of `runtime-tokio` and `runtime-async-std`");
+}
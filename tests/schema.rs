@@ -0,0 +1,72 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{schema_with, JsonStyle, RankOptions};
+use serde_json::Value;
+use std::path::Path;
+
+// Run with UPDATE_GOLDEN=1 to accept a deliberate change to the JSON contract
+fn assert_matches_golden(file: &str, schema: &Value) {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(file);
+    let actual = serde_json::to_string_pretty(schema).unwrap() + "\n";
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&golden, &actual).unwrap();
+    }
+    let expected = std::fs::read_to_string(&golden).unwrap();
+    assert_eq!(actual, expected, "the JSON schema changed; rerun with UPDATE_GOLDEN=1 if that is intended");
+}
+
+#[test]
+fn schema_matches_golden_file() {
+    assert_matches_golden("schema.json", &jmdictdb::schema());
+}
+
+#[test]
+fn camel_case_schema_matches_golden_file() {
+    assert_matches_golden("schema_camel.json", &schema_with(JsonStyle::Camel));
+}
+
+#[test]
+fn field_names() {
+    assert_eq!(JsonStyle::Camel.field_name("ent_seq"), "entSeq");
+    assert_eq!(JsonStyle::Camel.field_name("max_glosses_per_sense"), "maxGlossesPerSense");
+    assert_eq!(JsonStyle::Camel.field_name("source"), "source");
+    assert_eq!(JsonStyle::Snake.field_name("ent_seq"), "ent_seq");
+}
+
+/// Every key of `value` is a property of the `$defs` type `def` in `schema`, recursing into
+/// the properties that are other types.
+fn assert_described(schema: &Value, def: &str, value: &Value) {
+    let properties = &schema["$defs"][def]["properties"];
+    for (key, field) in value.as_object().unwrap() {
+        let property = &properties[key];
+        assert!(!property.is_null(), "{} has no property {}", def, key);
+        let reference = property["$ref"].as_str().or_else(|| property["items"]["$ref"].as_str());
+        let Some(inner) = reference.and_then(|r| r.strip_prefix("#/$defs/")) else { continue };
+        if schema["$defs"][inner]["properties"].is_null() {
+            continue;
+        }
+        match field {
+            Value::Array(items) => items.iter().for_each(|item| assert_described(schema, inner, item)),
+            Value::Object(_) => assert_described(schema, inner, field),
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn both_styles_serialize_as_their_schema_says() {
+    let db = build_sample_db().await;
+    let opts = RankOptions { explain: true, ..Default::default() };
+    let results = db.search_ranked("見る", &opts).await.unwrap();
+    for style in [JsonStyle::Snake, JsonStyle::Camel] {
+        let schema = schema_with(style);
+        for result in &results {
+            assert_described(&schema, "ScoredEntry", &result.to_json_value_with(style));
+            assert_described(&schema, "EntryParsed", &result.entry.to_json_value_with(style));
+        }
+    }
+    let camel = results[0].entry.to_json_value_with(JsonStyle::Camel);
+    assert_eq!(camel["entSeq"], 1225970);
+    assert!(camel["senses"][0]["senseOrder"].is_i64());
+    assert!(camel.get("ent_seq").is_none());
+    assert_eq!(results[0].entry.to_json_value_with(JsonStyle::Snake), results[0].entry.to_json_value());
+}
@@ -0,0 +1,47 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::ranking::score;
+use jmdictdb::{MatchKind, RankOptions, ScoreBreakdown};
+use std::cmp::Ordering;
+
+#[test]
+fn breakdown_total_is_the_score() {
+    for kind in [MatchKind::Exact, MatchKind::Prefix] {
+        for rank in [None, Some(1), Some(250), Some(20_000)] {
+            let b = ScoreBreakdown::compute(kind, rank, Some(3), &["ichi1".to_string(), "news2".to_string()]);
+            assert_eq!(b.total, score(kind, rank));
+            assert_eq!(b.total, b.match_component + b.frequency_component);
+            assert_eq!(b.priority_component, 3);
+        }
+    }
+    let exact = ScoreBreakdown::compute(MatchKind::Exact, None, None, &[]);
+    let prefix = ScoreBreakdown::compute(MatchKind::Prefix, Some(1), Some(1), &["ichi1".to_string()]);
+    assert_eq!(exact.rank_cmp(&prefix), Ordering::Less);
+    // Same score, the bucket decides
+    let bucketed = ScoreBreakdown::compute(MatchKind::Prefix, Some(1), Some(40), &[]);
+    assert_eq!(bucketed.rank_cmp(&prefix), Ordering::Greater);
+}
+
+#[tokio::test]
+async fn explain_shows_what_the_ranking_used() {
+    let db = build_sample_db().await;
+
+    let plain = db.search_ranked("た", &RankOptions::default()).await.unwrap();
+    assert!(plain.len() > 3);
+    assert!(plain.iter().all(|r| r.explain.is_none()));
+    assert!(plain[0].to_json_value().get("explain").is_none());
+
+    let opts = RankOptions { explain: true, ..RankOptions::default() };
+    let explained = db.search_ranked("た", &opts).await.unwrap();
+    let seqs = |results: &[jmdictdb::ScoredEntry]| results.iter().map(|r| r.entry.ent_seq).collect::<Vec<_>>();
+    assert_eq!(seqs(&explained), seqs(&plain));
+
+    let breakdowns: Vec<&ScoreBreakdown> = explained.iter().map(|r| r.explain.as_ref().unwrap()).collect();
+    for (result, b) in explained.iter().zip(&breakdowns) {
+        assert_eq!(b.total, result.score);
+        assert_eq!(b.match_kind, result.match_kind);
+        assert_eq!(b.nf_bucket, result.entry.nf_bucket.map(i64::from));
+    }
+    // The results are in the order the breakdowns give
+    assert!(breakdowns.windows(2).all(|w| w[0].rank_cmp(w[1]) != Ordering::Greater));
+    assert!(explained[0].to_json_value()["explain"]["priority_component"].is_i64());
+}
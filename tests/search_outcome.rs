@@ -0,0 +1,60 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{RankOptions, Transformation};
+
+#[tokio::test]
+async fn reports_the_fallbacks_taken() {
+    let db = build_sample_db().await;
+    let opts = RankOptions::default();
+
+    let plain = db.search_ranked_outcome("ねこ", &opts).await.unwrap();
+    assert!(plain.transformations.is_empty());
+    assert!(!plain.query_changed());
+    assert_eq!(plain.results[0].entry.ent_seq, 1469800);
+
+    let trimmed = db.search_ranked_outcome(" 猫\n", &opts).await.unwrap();
+    assert_eq!(trimmed.transformations, [Transformation::Trimmed]);
+    assert_eq!((trimmed.original_query.as_str(), trimmed.effective_query.as_str()), (" 猫\n", "猫"));
+    assert!(!trimmed.query_changed());
+
+    let romaji = db.search_ranked_outcome("neko", &opts).await.unwrap();
+    assert_eq!(romaji.transformations, [Transformation::RomajiConverted]);
+    assert_eq!(romaji.effective_query, "ねこ");
+    assert!(romaji.query_changed());
+    assert_eq!(romaji.results[0].entry.ent_seq, 1469800);
+
+    let katakana = db.search_ranked_outcome("ネコ", &opts).await.unwrap();
+    assert_eq!(katakana.transformations, [Transformation::KatakanaFolded]);
+    assert_eq!(katakana.effective_query, "ねこ");
+
+    let variant = db.search_ranked_outcome("こおひい", &opts).await.unwrap();
+    assert_eq!(variant.transformations, [Transformation::VariantExpanded { variant: "コーヒー".to_string() }]);
+    assert_eq!(variant.effective_query, "コーヒー");
+    assert!(!variant.results.is_empty());
+
+    // Found as written, katakana stay katakana
+    let loanword = db.search_ranked_outcome("コーヒー", &opts).await.unwrap();
+    assert!(loanword.transformations.is_empty());
+
+    let nothing = db.search_ranked_outcome("xyzzy", &opts).await.unwrap();
+    assert!(nothing.results.is_empty());
+    assert_eq!(nothing.effective_query, "xyzzy");
+}
+
+#[tokio::test]
+async fn reports_verb_stems() {
+    let db = build_sample_db().await;
+    let opts = RankOptions { verb_stems: true, ..Default::default() };
+    let outcome = db.search_ranked_outcome("食べ", &opts).await.unwrap();
+    assert_eq!(outcome.transformations, [Transformation::Deinflected { chain: vec!["食べ".to_string(), "食べる".to_string()] }]);
+    assert_eq!(outcome.effective_query, "食べ");
+    assert_eq!(outcome.results[0].entry.kebs.as_ref().unwrap()[0], "食べる");
+
+    // Without the option the stem is only a prefix
+    let prefix = db.search_ranked_outcome("食べ", &RankOptions::default()).await.unwrap();
+    assert!(prefix.transformations.is_empty());
+
+    // The same results as search_ranked
+    let ranked = db.search_ranked("食べ", &opts).await.unwrap();
+    let seqs = |results: &[jmdictdb::ScoredEntry]| results.iter().map(|r| r.entry.ent_seq).collect::<Vec<_>>();
+    assert_eq!(seqs(&outcome.results), seqs(&ranked));
+}
@@ -0,0 +1,60 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{parse_entry_xml, DictDb, QueryOptions};
+use std::time::Duration;
+
+fn limits(max_senses: Option<usize>, max_glosses_per_sense: Option<usize>) -> QueryOptions {
+    QueryOptions { max_senses, max_glosses_per_sense, ..Default::default() }
+}
+
+#[tokio::test]
+async fn keeps_the_first_senses_in_order() {
+    let db = build_sample_db().await;
+    let full = db.entry_by_seq(1582310).await.unwrap().unwrap();
+    assert_eq!(full.senses.len(), 4);
+    assert!(!full.senses_truncated);
+
+    let two = db.with_query_options(&limits(Some(2), None), db.entry_by_seq(1582310)).await.unwrap().unwrap();
+    assert!(two.senses_truncated);
+    assert_eq!(two.senses, full.senses[..2]);
+    let orders: Vec<i64> = two.senses.iter().map(|s| s.sense_order).collect();
+    assert_eq!(orders, [0, 1]);
+
+    // A limit the entry is within cuts nothing
+    let all = db.with_query_options(&limits(Some(4), None), db.entry_by_seq(1582310)).await.unwrap().unwrap();
+    assert!(!all.senses_truncated);
+    assert_eq!(all.senses, full.senses);
+
+    // Every entry of a search, and only within the call
+    let opts = QueryOptions { timeout: Some(Duration::from_secs(10)), ..limits(Some(1), None) };
+    let found = db.with_query_options(&opts, db.search_entries_with_senses_by_reading("みる")).await.unwrap();
+    assert!(found.iter().all(|e| e.senses.len() == 1));
+    assert!(found.iter().any(|e| e.senses_truncated));
+    assert_eq!(db.entry_by_seq(1225970).await.unwrap().unwrap().senses.len(), 3);
+}
+
+#[tokio::test]
+async fn cuts_glosses_per_sense_and_language() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let entry = parse_entry_xml(
+        "<entry><ent_seq>1358280</ent_seq><r_ele><reb>たべる</reb></r_ele>\
+         <sense><pos>&v1;</pos><gloss>to eat</gloss><gloss xml:lang=\"ger\">essen</gloss><gloss xml:lang=\"ger\">speisen</gloss></sense>\
+         <sense><gloss>to live on</gloss><gloss>to live off</gloss><gloss>to subsist on</gloss></sense></entry>",
+    )
+    .unwrap();
+    db.insert_entries(&[entry]).await.unwrap();
+
+    let cut = db.with_query_options(&limits(None, Some(1)), db.entry_by_seq(1358280)).await.unwrap().unwrap();
+    assert!(cut.senses_truncated);
+    assert_eq!(cut.senses.len(), 2);
+    assert_eq!(cut.senses[0].gloss, ["to eat"]);
+    assert_eq!(cut.senses[0].glosses_for("ger"), ["essen"]);
+    assert_eq!(cut.senses[1].gloss, ["to live on"]);
+
+    let both = db.with_query_options(&limits(Some(1), Some(2)), db.entry_by_seq(1358280)).await.unwrap().unwrap();
+    assert_eq!(both.senses.len(), 1);
+    assert_eq!(both.senses[0].glosses_for("ger"), ["essen", "speisen"]);
+
+    let roomy = db.with_query_options(&limits(Some(5), Some(5)), db.entry_by_seq(1358280)).await.unwrap().unwrap();
+    assert!(!roomy.senses_truncated);
+    assert!(roomy.content_eq(&db.entry_by_seq(1358280).await.unwrap().unwrap()));
+}
@@ -0,0 +1,54 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{Entry, EntryParsed, MatchSpan, REle, Sense};
+
+fn entry(senses: &[&[&str]]) -> EntryParsed {
+    let entry = Entry {
+        ent_seq: "1000030".to_string(),
+        r_ele: vec![REle { reb: "テスト".to_string(), ..Default::default() }],
+        sense: senses
+            .iter()
+            .map(|glosses| Sense { gloss: glosses.iter().map(|g| g.to_string()).collect(), ..Default::default() })
+            .collect(),
+        ..Default::default()
+    };
+    EntryParsed::try_from(&entry).unwrap()
+}
+
+#[tokio::test]
+async fn finds_the_sense_of_the_query() {
+    let db = build_sample_db().await;
+    let taberu = db.entry_by_seq(1358280).await.unwrap().unwrap();
+    let matches = taberu.find_senses_matching("Salary");
+    assert_eq!(matches.len(), 1);
+    let (sense, span) = matches[0];
+    assert_eq!(sense, 1);
+    let gloss = &taberu.senses[sense].gloss[span.gloss_index];
+    assert_eq!(gloss, "to live on (e.g. a salary)");
+    assert_eq!(&gloss[span.byte_start..span.byte_end], "salary");
+
+    let senses: Vec<usize> = taberu.find_senses_matching("to ").iter().map(|&(sense, _)| sense).collect();
+    assert_eq!(senses, [0, 1, 1, 1]);
+    assert!(taberu.find_senses_matching("coffee").is_empty());
+    assert!(taberu.find_senses_matching("  ").is_empty());
+}
+
+#[test]
+fn spans_count_bytes_and_chars() {
+    let cafe = entry(&[&["tea"], &["Café au lait, CAFÉ noir"]]);
+    let matches = cafe.find_senses_matching("café");
+    let spans: Vec<MatchSpan> = matches.iter().map(|&(_, span)| span).collect();
+    assert!(matches.iter().all(|&(sense, _)| sense == 1));
+    assert_eq!(
+        spans,
+        [
+            MatchSpan { gloss_index: 0, byte_start: 0, byte_end: 5, char_start: 0, char_end: 4 },
+            MatchSpan { gloss_index: 0, byte_start: 15, byte_end: 20, char_start: 14, char_end: 18 },
+        ]
+    );
+
+    // Folding that changes the length: ß matches "ss"
+    let strasse = entry(&[&["Große Straße"]]);
+    let span = strasse.find_senses_matching("STRASSE")[0].1;
+    assert_eq!(&strasse.senses[0].gloss[0][span.byte_start..span.byte_end], "Straße");
+    assert_eq!((span.char_start, span.char_end), (6, 12));
+}
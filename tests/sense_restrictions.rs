@@ -0,0 +1,50 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::EntryParsed;
+
+fn glosses(entry: &EntryParsed, applicable: bool) -> Vec<&str> {
+    entry
+        .senses
+        .iter()
+        .filter(|s| s.applies_to_query == applicable)
+        .map(|s| s.gloss[0].as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn kanji_search_flags_senses_of_other_kanji() {
+    let db = build_sample_db().await;
+
+    let found = db.search_entries_with_senses_by_kanji("空く").await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].senses[0].stagk, ["開く"]);
+    assert_eq!(glosses(&found[0], true), ["to be empty", "to end"]);
+    assert_eq!(glosses(&found[0], false), ["to open (e.g. doors)"]);
+
+    // Restrictions to readings don't matter when only the kanji is searched
+    let found = db.search_entries_with_senses_by_kanji("頭").await.unwrap();
+    assert!(found[0].senses.iter().all(|s| s.applies_to_query));
+}
+
+#[tokio::test]
+async fn applicable_senses_drop_restricted_ones() {
+    let db = build_sample_db().await;
+
+    let found = db.search_entries_with_applicable_senses("頭", Some("かしら")).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(glosses(&found[0], true), ["head", "leader"]);
+    assert!(glosses(&found[0], false).is_empty());
+
+    let found = db.search_entries_with_applicable_senses("開く", None).await.unwrap();
+    assert_eq!(glosses(&found[0], true), ["to open (e.g. doors)", "to end"]);
+
+    // A reading the entry doesn't have
+    assert!(db.search_entries_with_applicable_senses("頭", Some("あく")).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn reading_search_flags_senses_of_other_readings() {
+    let db = build_sample_db().await;
+
+    let found = db.search_entries_with_senses_by_reading("とう").await.unwrap();
+    assert_eq!(glosses(&found[0], true), ["counter for large animals"]);
+}
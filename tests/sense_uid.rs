@@ -0,0 +1,129 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{sense_uids, DictDb, Entry, EntryParsed, REle, Sense, SenseUidStatus};
+
+fn sense(pos: &str, glosses: &[&str]) -> Sense {
+    Sense { pos: vec![pos.to_string()], gloss: glosses.iter().map(|g| g.to_string()).collect(), ..Default::default() }
+}
+
+fn entry(senses: Vec<Sense>) -> Entry {
+    Entry {
+        ent_seq: "1000".to_string(),
+        k_ele: None,
+        r_ele: vec![REle { reb: "たべる".to_string(), ..Default::default() }],
+        sense: senses,
+    }
+}
+
+async fn db_with(e: &Entry) -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(std::slice::from_ref(e)).await.unwrap();
+    db
+}
+
+fn uids(entry: &EntryParsed) -> Vec<String> {
+    entry.senses.iter().map(|s| s.sense_uid.clone().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn uids_survive_rebuilds() {
+    let first = build_sample_db().await;
+    let second = build_sample_db().await;
+    let miru = first.entry_by_seq(1225970).await.unwrap().unwrap();
+    let again = second.entry_by_seq(1225970).await.unwrap().unwrap();
+    assert_eq!(uids(&miru), uids(&again));
+    assert_eq!(miru.senses.len(), 3);
+    assert!(uids(&miru).iter().all(|u| u.len() == 16 && u.chars().all(|c| c.is_ascii_hexdigit())));
+    let mut distinct = uids(&miru);
+    distinct.dedup();
+    assert_eq!(distinct.len(), 3);
+}
+
+#[test]
+fn only_the_glosses_and_the_entry_count() {
+    let eat = || sense("v1", &["to eat"]);
+    let live = || sense("v1", &["to live on (e.g. a salary)"]);
+    let base = sense_uids(&entry(vec![eat(), live()]));
+    // Reordered, and with other tags
+    let reordered = sense_uids(&entry(vec![live(), sense("vt", &["to eat"])]));
+    assert_eq!(reordered, [base[1].clone(), base[0].clone()]);
+    // Reworded
+    let edited = sense_uids(&entry(vec![sense("v1", &["to eat", "to consume"]), live()]));
+    assert_ne!(edited[0], base[0]);
+    assert_eq!(edited[1], base[1]);
+    // Another entry
+    let mut other = entry(vec![eat()]);
+    other.ent_seq = "1001".to_string();
+    assert_ne!(sense_uids(&other)[0], base[0]);
+    // Senses with the same glosses are told apart
+    let twice = sense_uids(&entry(vec![eat(), eat()]));
+    assert_eq!(twice[0], base[0]);
+    assert_ne!(twice[1], twice[0]);
+}
+
+#[tokio::test]
+async fn finds_senses_by_uid() {
+    let db = build_sample_db().await;
+    let miru = db.entry_by_seq(1225970).await.unwrap().unwrap();
+    let uid = miru.senses[1].sense_uid.clone().unwrap();
+    let found = db.find_sense_by_uid(&uid).await.unwrap().unwrap();
+    assert_eq!(found.entry.ent_seq, 1225970);
+    assert_eq!(found.sense, miru.senses[1]);
+    assert!(db.find_sense_by_uid("0000000000000000").await.unwrap().is_none());
+
+    // Parsing without a database gives the same uids
+    let xml = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/jmdict_sample.xml")).unwrap();
+    let parsed = jmdictdb::parse_jmdict_str(&xml).unwrap();
+    let miru_xml = parsed.entry.iter().find(|e| e.ent_seq == "1225970").unwrap();
+    assert_eq!(uids(&EntryParsed::try_from(miru_xml).unwrap()), uids(&miru));
+}
+
+#[tokio::test]
+async fn reconciles_uids_after_an_upstream_edit() {
+    let before = entry(vec![sense("v1", &["to eat"]), sense("v1", &["to live on", "to subsist on"])]);
+    let after = entry(vec![
+        sense("v1", &["to eat"]),
+        sense("v1", &["to bite"]),
+        sense("v1", &["to live on", "to make a living from"]),
+    ]);
+    let previous = db_with(&before).await;
+    let current = db_with(&after).await;
+    let old = uids(&previous.entry_by_seq(1000).await.unwrap().unwrap());
+    let new = uids(&current.entry_by_seq(1000).await.unwrap().unwrap());
+
+    assert_eq!(current.reconcile_sense_uid(&previous, &old[0]).await.unwrap(), SenseUidStatus::Unchanged);
+    assert_eq!(current.reconcile_sense_uid(&previous, &old[1]).await.unwrap(), SenseUidStatus::Replaced(new[2].clone()));
+    assert_eq!(current.reconcile_sense_uid(&previous, "0000000000000000").await.unwrap(), SenseUidStatus::Removed);
+
+    // Nothing in common
+    let rewritten = db_with(&entry(vec![sense("v1", &["to eat"]), sense("v1", &["to dine"])])).await;
+    assert_eq!(rewritten.reconcile_sense_uid(&previous, &old[1]).await.unwrap(), SenseUidStatus::Removed);
+}
+
+#[cfg(feature = "userdata")]
+#[tokio::test]
+async fn word_list_items_can_name_a_sense() {
+    let db = build_sample_db().await;
+    let miru = db.entry_by_seq(1225970).await.unwrap().unwrap();
+    let uid = miru.senses[2].sense_uid.clone().unwrap();
+    let list = db.create_list("meanings").await.unwrap();
+    db.add_sense_to_list(list, &uid, Some("to try")).await.unwrap();
+    db.add_to_list(list, 1358280, None).await.unwrap();
+
+    let items = db.list_items(list).await.unwrap();
+    assert_eq!(items[0].sense_uid.as_deref(), Some(uid.as_str()));
+    assert_eq!(items[1].sense_uid, None);
+    let entries = db.list_entries(list).await.unwrap();
+    assert_eq!(entries[0].sense.as_ref().unwrap().gloss, miru.senses[2].gloss);
+    assert!(entries[1].sense.is_none());
+    assert!(db.add_sense_to_list(list, "0000000000000000", None).await.is_err());
+
+    // Carried over by restore_list, as build_db does
+    let rebuilt = build_sample_db().await;
+    let lists = db.wordlists().await.unwrap();
+    let restored = rebuilt.restore_list(&lists[0], &items).await.unwrap();
+    assert_eq!(rebuilt.list_entries(restored).await.unwrap()[0].sense.as_ref().unwrap().sense_order, 2);
+
+    // Adding the entry again makes it a whole-entry item
+    db.add_to_list(list, 1225970, None).await.unwrap();
+    assert_eq!(db.list_items(list).await.unwrap()[0].sense_uid, None);
+}
@@ -0,0 +1,32 @@
+use jmdictdb::fixtures::build_sample_db;
+
+#[tokio::test]
+async fn breaks_the_file_down_by_table() {
+    let db = build_sample_db().await;
+    let report = db.size_report().await.unwrap();
+    assert!(!report.estimated, "the bundled SQLite has dbstat");
+    assert_eq!(report.total_bytes, db.database_size().await.unwrap());
+
+    let table = |name: &str| report.tables.iter().find(|t| t.name == name).unwrap_or_else(|| panic!("no {}", name));
+    assert!(table("entries").table_bytes > 0);
+    assert!(table("glosses").index_bytes > 0, "idx_glosses_sense and idx_glosses_lang_text");
+    assert!(report.tables.iter().all(|t| !t.name.starts_with("idx_")));
+    // Pages are whole, so the tables add up to the file less the free pages
+    let counted: u64 = report.tables.iter().map(|t| t.total_bytes()).sum();
+    assert_eq!(counted + report.freelist_bytes, report.total_bytes);
+    assert!(report.tables.windows(2).all(|w| w[0].total_bytes() >= w[1].total_bytes()));
+
+    let printed = report.to_string();
+    assert!(printed.contains("entries") && printed.ends_with("file"), "{}", printed);
+}
+
+#[tokio::test]
+async fn estimates_without_counting_pages() {
+    let db = build_sample_db().await;
+    let estimate = db.estimate_size_report().await.unwrap();
+    assert!(estimate.estimated);
+    let table = |name: &str| estimate.tables.iter().find(|t| t.name == name).unwrap();
+    assert!(table("glosses").table_bytes > table("jlpt").table_bytes);
+    assert!(table("glosses").index_bytes > 0);
+    assert!(estimate.to_string().contains("estimated"));
+}
@@ -0,0 +1,111 @@
+use jmdictdb::{DictDb, DictDbOptions, RankOptions, LATENCY_BUCKETS_MS, DEFAULT_LANG};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// The fields of every event, as "name=value" strings.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<Vec<String>>>>);
+
+struct Fields<'a>(&'a mut Vec<String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push(format!("{}={:?}", field.name(), value));
+    }
+}
+
+impl Subscriber for Capture {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == "jmdictdb::slow_query"
+    }
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Vec::new();
+        event.record(&mut Fields(&mut fields));
+        self.0.lock().unwrap().push(fields);
+    }
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
+
+async fn sample_db(opts: DictDbOptions) -> DictDb {
+    let db = opts.connect().await.unwrap();
+    db.insert_entries(&jmdictdb::fixtures::sample_dict().entry).await.unwrap();
+    db
+}
+
+#[tokio::test]
+async fn slow_searches_are_logged_and_counted() {
+    let capture = Capture::default();
+    let _guard = tracing::subscriber::set_default(capture.clone());
+
+    // Every search is slow with a zero threshold
+    let db = sample_db(DictDbOptions::new("sqlite::memory:").slow_query_threshold(Duration::ZERO)).await;
+    assert!(db.stats().is_empty());
+
+    let found = db.search_entries_with_senses_by_reading("たべる").await.unwrap();
+    db.search_entries_with_senses_by_reading("  いく ").await.unwrap();
+    db.search_entries_by_gloss("to eat", DEFAULT_LANG).await.unwrap();
+    db.search_ranked("たべ", &RankOptions::default()).await.unwrap();
+    // Only the outer search is counted
+    db.search_entries_with_applicable_senses("頭", Some("かしら")).await.unwrap();
+
+    let stats = db.stats();
+    let methods: Vec<&str> = stats.keys().copied().collect();
+    assert_eq!(
+        methods,
+        [
+            "search_entries_by_gloss",
+            "search_entries_with_applicable_senses",
+            "search_entries_with_senses_by_reading",
+            "search_ranked"
+        ]
+    );
+    let reading = &stats["search_entries_with_senses_by_reading"];
+    assert_eq!((reading.calls, reading.slow), (2, 2));
+    assert_eq!(reading.buckets.iter().sum::<u64>(), 2);
+    assert_eq!(reading.buckets.len(), LATENCY_BUCKETS_MS.len() + 1);
+    assert!(reading.max >= reading.mean() && reading.total >= reading.max);
+
+    let events = capture.0.lock().unwrap().clone();
+    assert_eq!(events.len(), 5);
+    let first = &events[0];
+    assert!(first.contains(&"message=slow query".to_string()), "{:?}", first);
+    assert!(first.contains(&"method=\"search_entries_with_senses_by_reading\"".to_string()), "{:?}", first);
+    assert!(first.contains(&"query=たべる".to_string()), "{:?}", first);
+    assert!(first.contains(&format!("rows={}", found.len())), "{:?}", first);
+    assert!(first.iter().any(|f| f.starts_with("elapsed_ms=")), "{:?}", first);
+    // The query text is normalized
+    assert!(events[1].contains(&"query=いく".to_string()), "{:?}", events[1]);
+    assert!(events[2].contains(&"params=lang=eng".to_string()), "{:?}", events[2]);
+    assert!(events[4].contains(&"params=reading=Some(\"かしら\")".to_string()), "{:?}", events[4]);
+
+    db.reset_stats();
+    assert!(db.stats().is_empty());
+}
+
+#[tokio::test]
+async fn fast_searches_are_only_counted() {
+    let capture = Capture::default();
+    let _guard = tracing::subscriber::set_default(capture.clone());
+
+    let db = sample_db(DictDbOptions::new("sqlite::memory:").slow_query_threshold(Duration::from_secs(60))).await;
+    db.search_entries_with_senses_by_kanji("食べる").await.unwrap();
+    let stats = db.stats();
+    assert_eq!((stats["search_entries_with_senses_by_kanji"].calls, stats["search_entries_with_senses_by_kanji"].slow), (1, 0));
+    assert!(capture.0.lock().unwrap().is_empty());
+
+    // Without a threshold nothing is collected
+    let db = sample_db(DictDbOptions::new("sqlite::memory:")).await;
+    db.search_entries_with_senses_by_kanji("食べる").await.unwrap();
+    assert!(db.stats().is_empty());
+    assert!(capture.0.lock().unwrap().is_empty());
+}
@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::snapshot::{diff_snapshots, parse_queries, parse_snapshots, render_snapshots, take_snapshots, SnapshotKind, SnapshotQuery};
+use jmdictdb::DictDb;
+
+/// The full dictionary to check, e.g. the output of `build_db`; the suite is skipped without it.
+const SNAPSHOT_DB_ENV: &str = "JMDICTDB_SNAPSHOT_DB";
+
+/// How many results of each query are kept.
+const TOP: usize = 5;
+
+fn snapshot_file(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(name)
+}
+
+// JMDICTDB_SNAPSHOT_DB=data/jmdict_e.db cargo test --test snapshots
+// Add UPDATE_SNAPSHOTS=1 to accept deliberate changes, and review the diff of results.tsv.
+#[tokio::test]
+async fn search_results_match_snapshots() {
+    let Some(path) = std::env::var_os(SNAPSHOT_DB_ENV).filter(|p| !p.is_empty()) else {
+        eprintln!("{} is not set, skipping the snapshot suite", SNAPSHOT_DB_ENV);
+        return;
+    };
+    let db = DictDb::connect(&format!("sqlite:{}", Path::new(&path).display())).await.unwrap();
+    let queries = parse_queries(&std::fs::read_to_string(snapshot_file("queries.tsv")).unwrap()).unwrap();
+    let actual = take_snapshots(&db, &queries, TOP).await.unwrap();
+
+    let results = snapshot_file("results.tsv");
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&results, render_snapshots(&actual)).unwrap();
+    }
+    let expected = match std::fs::read_to_string(&results) {
+        Ok(text) => parse_snapshots(&text).unwrap(),
+        Err(_) => panic!("no {}; rerun with UPDATE_SNAPSHOTS=1 to create it", results.display()),
+    };
+    if let Some(diff) = diff_snapshots(&expected, &actual) {
+        panic!("{}\nrerun with UPDATE_SNAPSHOTS=1 if the changes are intended", diff);
+    }
+}
+
+#[tokio::test]
+async fn the_harness_round_trips_and_diffs() {
+    let db = build_sample_db().await;
+    let queries = parse_queries("# comment\nranked\tたべる\ngloss\tcat\n\nexpressions\t木\n").unwrap();
+    assert_eq!(queries[1], SnapshotQuery { kind: SnapshotKind::Gloss, query: "cat".to_string() });
+    assert!(parse_queries("fuzzy\tたべる").is_err());
+
+    let snapshots = take_snapshots(&db, &queries, TOP).await.unwrap();
+    assert_eq!(snapshots[0].results[0], (1358280, "食べる".to_string()));
+    assert!(snapshots.iter().all(|s| s.results.len() <= TOP));
+    assert_eq!(parse_snapshots(&render_snapshots(&snapshots)).unwrap(), snapshots);
+    assert_eq!(diff_snapshots(&snapshots, &snapshots), None);
+
+    let mut changed = snapshots.clone();
+    changed[0].results[0] = (1469800, "猫".to_string());
+    changed.pop();
+    let diff = diff_snapshots(&snapshots, &changed).unwrap();
+    assert!(diff.starts_with("2 of 3 queries changed"), "{}", diff);
+    assert!(diff.contains("ranked \"たべる\":"), "{}", diff);
+    assert!(diff.contains("!  1. 1358280 食べる  | 1469800 猫"), "{}", diff);
+    assert!(diff.contains("| (missing)"), "{}", diff);
+}
@@ -0,0 +1,60 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::{DictDb, Entry, REle, Sense, SQL_DUMP_HEADER};
+
+async fn dump(db: &DictDb) -> String {
+    let mut out = Vec::new();
+    db.dump_sql(&mut out).await.unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[tokio::test]
+async fn dumps_are_deterministic_and_restore() {
+    let db = build_sample_db().await;
+    db.upsert_entry(&Entry {
+        ent_seq: "1000001".to_string(),
+        k_ele: None,
+        r_ele: vec![REle { reb: "かいぎょう".to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec!["line\nbreak 'quoted'\r\n".to_string()], ..Default::default() }],
+    })
+    .await
+    .unwrap();
+    let first = dump(&db).await;
+    assert_eq!(first, dump(&db).await);
+    assert!(first.starts_with(SQL_DUMP_HEADER));
+    assert!(first.lines().all(|l| l.starts_with("--") || l.ends_with(';')));
+    assert!(first.contains("CREATE TABLE IF NOT EXISTS entries ("));
+
+    let restored = DictDb::connect("sqlite::memory:").await.unwrap();
+    let rows = restored.restore_sql(first.as_bytes()).await.unwrap();
+    assert_eq!(rows, first.lines().filter(|l| l.starts_with("INSERT ")).count());
+    assert_eq!(restored.entry_count().await.unwrap(), db.entry_count().await.unwrap());
+    assert_eq!(restored.sense_count().await.unwrap(), db.sense_count().await.unwrap());
+    for e in sample_dict().entry.iter().step_by(10) {
+        let ent_seq = e.ent_seq.parse().unwrap();
+        let (restored, original) = (restored.entry_by_seq(ent_seq).await.unwrap(), db.entry_by_seq(ent_seq).await.unwrap());
+        assert!(restored.unwrap().content_eq(&original.unwrap()), "entry {}", ent_seq);
+    }
+    let kaigyou = restored.entry_by_seq(1000001).await.unwrap().unwrap();
+    assert_eq!(kaigyou.senses[0].gloss, ["line\nbreak 'quoted'\r\n"]);
+    // The indexes came along
+    assert_eq!(restored.search_entries_by_reading_normalized("タベル").await.unwrap()[0].ent_seq, 1358280);
+    assert_eq!(dump(&restored).await, first);
+
+    // Restoring replaces what was there, and foreign keys are back on afterwards
+    let senses = restored.sense_count().await.unwrap();
+    restored.restore_sql(first.as_bytes()).await.unwrap();
+    assert_eq!(restored.sense_count().await.unwrap(), senses);
+    restored.delete_entry(1000001).await.unwrap();
+    assert_eq!(restored.sense_count().await.unwrap(), senses - 1);
+}
+
+#[tokio::test]
+async fn rejects_other_input() {
+    let db = build_sample_db().await;
+    let count = db.entry_count().await.unwrap();
+    assert!(db.restore_sql("DELETE FROM entries;\n".as_bytes()).await.is_err());
+    let broken = format!("{}\nINSERT INTO senses (id, ent_seq, sense_order) VALUES (1, 42, 0);\n", SQL_DUMP_HEADER);
+    assert!(db.restore_sql(broken.as_bytes()).await.unwrap_err().to_string().contains("foreign key"));
+    // Rolled back
+    assert_eq!(db.entry_count().await.unwrap(), count);
+}
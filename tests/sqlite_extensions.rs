@@ -0,0 +1,88 @@
+use std::io::Cursor;
+
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{DictDb, DictDbOptions};
+
+const CORPUS: &str = "A: カフェで会いましょう。\tLet's meet at the CAFÉ.#ID=1_2\nB: 会う\n\
+                      A: 猫が好きです。\tI like cats.#ID=3_4\nB: 猫 好き\n\
+                      A: 通りを渡る。\tCross the STRASSE.#ID=5_6\nB: 渡る\n";
+
+async fn sentence_ids(db: &DictDb, substring: &str) -> Vec<i64> {
+    db.search_sentences(substring, 10).await.unwrap().iter().map(|s| s.id).collect()
+}
+
+#[tokio::test]
+async fn probes_for_extensions() {
+    let db = build_sample_db().await;
+    assert!(!db.has_extension("icu").await.unwrap());
+    assert!(!db.has_extension("spellfix1").await.unwrap());
+    assert!(!db.has_extension("no_such_extension").await.unwrap());
+    // What the bundled SQLite has built in is found by its function or module name
+    assert!(db.has_extension("fts5").await.unwrap());
+    assert!(db.has_extension("json_extract").await.unwrap());
+}
+
+#[tokio::test]
+async fn sentences_ignore_unicode_case_without_icu() {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.load_tanaka_corpus_reader(Cursor::new(CORPUS)).await.unwrap();
+    assert_eq!(sentence_ids(&db, "café").await, [1]);
+    assert_eq!(sentence_ids(&db, "Café").await, [1]);
+    assert_eq!(sentence_ids(&db, "straße").await, [3]);
+    assert_eq!(sentence_ids(&db, "cats").await, [2]);
+    assert_eq!(sentence_ids(&db, "猫").await, [2]);
+    assert_eq!(db.search_sentences("café", 0).await.unwrap().len(), 0);
+
+    // Unless LIKE is told to tell case apart
+    let db = DictDbOptions::new("sqlite::memory:").case_sensitive_like(true).connect().await.unwrap();
+    db.load_tanaka_corpus_reader(Cursor::new(CORPUS)).await.unwrap();
+    assert!(sentence_ids(&db, "café").await.is_empty());
+    assert_eq!(sentence_ids(&db, "CAFÉ").await, [1]);
+}
+
+#[cfg(feature = "sqlite-extensions")]
+mod loaded {
+    use super::*;
+    use jmdictdb::fixtures::sample_dict;
+
+    #[tokio::test]
+    async fn fails_to_connect_without_the_library() {
+        let opts = DictDbOptions::new("sqlite::memory:").extension("/nonexistent/libSqliteIcu");
+        assert!(opts.connect().await.is_err());
+    }
+
+    /// The sample dictionary and corpus with the extension at the path in `env`, None if unset.
+    async fn with_extension(env: &str) -> Option<DictDb> {
+        let path = std::env::var(env).ok().filter(|p| !p.is_empty())?;
+        let db = DictDbOptions::new("sqlite::memory:").extension(path).connect().await.unwrap();
+        db.insert_entries(&sample_dict().entry).await.unwrap();
+        db.load_tanaka_corpus_reader(Cursor::new(CORPUS)).await.unwrap();
+        Some(db)
+    }
+
+    // JMDICTDB_ICU_EXTENSION=/usr/lib/x86_64-linux-gnu/libSqliteIcu cargo test --features sqlite-extensions
+    #[tokio::test]
+    async fn icu_gives_the_same_sentences() {
+        let Some(db) = with_extension("JMDICTDB_ICU_EXTENSION").await else {
+            eprintln!("JMDICTDB_ICU_EXTENSION is not set, skipping");
+            return;
+        };
+        assert!(db.has_extension("icu").await.unwrap());
+        assert_eq!(sentence_ids(&db, "café").await, [1]);
+        assert_eq!(sentence_ids(&db, "cats").await, [2]);
+    }
+
+    // JMDICTDB_SPELLFIX_EXTENSION=./spellfix cargo test --features sqlite-extensions
+    #[tokio::test]
+    async fn spellfix_gives_the_same_corrections() {
+        let Some(db) = with_extension("JMDICTDB_SPELLFIX_EXTENSION").await else {
+            eprintln!("JMDICTDB_SPELLFIX_EXTENSION is not set, skipping");
+            return;
+        };
+        assert!(db.has_extension("spellfix").await.unwrap());
+        let sample = build_sample_db().await;
+        for query in ["たべう", "タベウ", "tabe", "いね", "ねこお"] {
+            assert_eq!(db.suggest_corrections(query, 20).await.unwrap(), sample.suggest_corrections(query, 20).await.unwrap(), "{}", query);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use jmdictdb::fixtures::{build_sample_db, sample_dict};
+use jmdictdb::stardict::stardict_cmp;
+use jmdictdb::{PartOfSpeech, SearchFilter, StarDictOptions};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+fn out_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("jmdictdb-stardict-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// The (word, offset, size) entries of an .idx file, in file order.
+fn parse_idx(idx: &[u8]) -> Vec<(String, u32, u32)> {
+    let mut words = Vec::new();
+    let mut rest = idx;
+    while !rest.is_empty() {
+        let end = rest.iter().position(|b| *b == 0).expect("a word ends in a NUL");
+        let word = String::from_utf8(rest[..end].to_vec()).unwrap();
+        let offset = u32::from_be_bytes(rest[end + 1..end + 5].try_into().unwrap());
+        let size = u32::from_be_bytes(rest[end + 5..end + 9].try_into().unwrap());
+        words.push((word, offset, size));
+        rest = &rest[end + 9..];
+    }
+    words
+}
+
+fn parse_ifo(ifo: &str) -> BTreeMap<String, String> {
+    let mut lines = ifo.lines();
+    assert_eq!(lines.next(), Some("StarDict's dict ifo file"));
+    lines.filter_map(|l| l.split_once('=')).map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[tokio::test]
+async fn the_index_points_at_each_entrys_article() {
+    let db = build_sample_db().await;
+    let dir = out_dir("all");
+    let report = db.export_stardict(&dir, &StarDictOptions::default()).await.unwrap();
+    assert_eq!(report.ifo_path, dir.join("jmdict.ifo"));
+
+    let idx = std::fs::read(dir.join("jmdict.idx")).unwrap();
+    let dict = std::fs::read(dir.join("jmdict.dict")).unwrap();
+    let ifo = parse_ifo(&std::fs::read_to_string(&report.ifo_path).unwrap());
+    let words = parse_idx(&idx);
+    assert_eq!(ifo["version"], "2.4.2");
+    assert_eq!(ifo["bookname"], "JMdict");
+    assert_eq!(ifo["sametypesequence"], "h");
+    assert_eq!(ifo["wordcount"], words.len().to_string());
+    assert_eq!(ifo["idxfilesize"], idx.len().to_string());
+    assert_eq!(report.words, words.len());
+    assert_eq!(report.dict_size, dict.len() as u64);
+
+    // Sorted in StarDict's order, without repeats
+    assert!(words.windows(2).all(|w| stardict_cmp(&w[0].0, &w[1].0).then(w[0].1.cmp(&w[1].1)).is_lt()));
+
+    // The articles tile the .dict: one per entry, back to back
+    let articles: BTreeSet<(u32, u32)> = words.iter().map(|(_, offset, size)| (*offset, *size)).collect();
+    assert_eq!(articles.len(), report.entries);
+    assert_eq!(report.entries, sample_dict().entry.len());
+    let mut end = 0;
+    for (offset, size) in &articles {
+        assert_eq!(*offset, end);
+        end += size;
+    }
+    assert_eq!(end as usize, dict.len());
+
+    // Every keb and reb leads to an article with the form in it
+    let article = |offset: u32, size: u32| std::str::from_utf8(&dict[offset as usize..(offset + size) as usize]).unwrap().to_string();
+    for entry in &sample_dict().entry {
+        let forms = entry.k_ele.iter().flatten().map(|k| &k.keb).chain(entry.r_ele.iter().map(|r| &r.reb));
+        for form in forms {
+            let found = words.iter().filter(|(w, _, _)| w == form).map(|(_, o, s)| article(*o, *s));
+            assert!(found.clone().any(|a| a.contains(form.as_str())), "no article for {}", form);
+        }
+    }
+
+    // The keb and reb of an entry share its article
+    let taberu: Vec<(u32, u32)> = words.iter().filter(|(w, _, _)| w == "食べる" || w == "たべる").map(|(_, o, s)| (*o, *s)).collect();
+    assert_eq!(taberu.len(), 2);
+    assert_eq!(taberu[0], taberu[1]);
+    let html = article(taberu[0].0, taberu[0].1);
+    assert!(html.starts_with("<b>食べる</b> 【たべる】<br>also 喰べる<ol><li><i>v1, vt</i> to eat</li>"), "{}", html);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn the_filter_and_names_apply() {
+    let db = build_sample_db().await;
+    let dir = out_dir("verbs");
+    let opts = StarDictOptions {
+        file_stem: "verbs".to_string(),
+        book_name: "JMdict verbs\nonly".to_string(),
+        filter: SearchFilter::new().pos(PartOfSpeech::V1),
+        ..StarDictOptions::default()
+    };
+    let report = db.export_stardict(&dir, &opts).await.unwrap();
+    let ichidan = db.count_by_pos(&PartOfSpeech::V1).await.unwrap();
+    assert_eq!(report.entries as i64, ichidan);
+    assert!(report.entries > 0 && report.entries < sample_dict().entry.len());
+
+    let ifo = parse_ifo(&std::fs::read_to_string(dir.join("verbs.ifo")).unwrap());
+    assert_eq!(ifo["bookname"], "JMdict verbs only");
+    let words = parse_idx(&std::fs::read(dir.join("verbs.idx")).unwrap());
+    assert!(words.iter().any(|(w, _, _)| w == "たべる"));
+    assert!(!words.iter().any(|(w, _, _)| w == "ねこ"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn the_order_folds_ascii_case_only() {
+    let mut words = vec!["b", "A", "a", "B", "あ", "ア"];
+    words.sort_by(|a, b| stardict_cmp(a, b));
+    assert_eq!(words, ["A", "a", "B", "b", "あ", "ア"]);
+}
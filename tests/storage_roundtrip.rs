@@ -0,0 +1,132 @@
+use jmdictdb::{DictDb, Entry, EntryParsed, Gloss, KEle, REle, Sense};
+use proptest::prelude::*;
+use std::sync::LazyLock;
+use tokio::runtime::Runtime;
+
+/// One runtime and database for all cases; every case upserts its own entry.
+static DB: LazyLock<(Runtime, DictDb)> = LazyLock::new(|| {
+    let rt = Runtime::new().unwrap();
+    let db = rt.block_on(DictDb::connect("sqlite::memory:")).unwrap();
+    (rt, db)
+});
+
+/// Text where the JSON encoding matters: quotes, backslashes, newlines, control characters,
+/// characters beyond the BMP, or nothing at all.
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            "\\PC",
+            Just("\"".to_string()),
+            Just("\\".to_string()),
+            Just("\n".to_string()),
+            "[\\x00-\\x1f\\x7f]",
+            "[\u{20000}-\u{2A6DF}\u{1F600}-\u{1F64F}]",
+        ],
+        0..16,
+    )
+    .prop_map(|parts| parts.concat())
+}
+
+fn kanji() -> impl Strategy<Value = String> {
+    "[一-龯々\u{20000}-\u{2A6DF}]{1,4}"
+}
+
+fn kana() -> impl Strategy<Value = String> {
+    "[ぁ-ゖァ-ヺー]{1,6}"
+}
+
+/// Cross-references in the shapes JMdict uses: "keb", "reb", "keb・reb", "keb・n", "keb・reb・n".
+fn xref() -> impl Strategy<Value = String> {
+    prop_oneof![
+        kanji(),
+        kana(),
+        (kanji(), kana()).prop_map(|(k, r)| format!("{}・{}", k, r)),
+        (kanji(), 1..30u32).prop_map(|(k, n)| format!("{}・{}", k, n)),
+        (kanji(), kana(), 1..30u32).prop_map(|(k, r, n)| format!("{}・{}・{}", k, r, n)),
+    ]
+}
+
+fn tag() -> impl Strategy<Value = String> {
+    "(news|ichi|spec|gai)[12]|nf(0[1-9]|[1-3][0-9]|4[0-8])"
+}
+
+fn sense() -> impl Strategy<Value = Sense> {
+    (
+        prop::collection::vec(kanji(), 0..2),
+        prop::collection::vec(kana(), 0..2),
+        prop::collection::vec(text(), 0..3),
+        prop::collection::vec(xref(), 0..3),
+        prop::collection::vec(text(), 0..4),
+        prop::collection::vec(("ger|fre|rus|dut", text()).prop_map(|(lang, text)| Gloss { lang, text }), 0..3),
+        prop::collection::vec(text(), 0..2),
+        prop::collection::vec(text(), 0..2),
+        prop::collection::vec(text(), 0..2),
+    )
+        .prop_map(|(stagk, stagr, pos, xref, gloss, other_glosses, misc, field, dial)| Sense {
+            stagk,
+            stagr,
+            pos,
+            xref,
+            misc,
+            field,
+            dial,
+            gloss,
+            other_glosses,
+        })
+}
+
+/// Entries within JMdict's structure: at least one reb and sense, k_ele absent or non-empty,
+/// ent_seq below the custom range.
+fn entry() -> impl Strategy<Value = Entry> {
+    (
+        1..9_000_000i64,
+        prop::option::of(prop::collection::vec(
+            (kanji(), prop::collection::vec(tag(), 0..3))
+                .prop_map(|(keb, ke_pri)| KEle { keb, ke_pri, ke_inf: None }),
+            1..3,
+        )),
+        prop::collection::vec(
+            (text(), prop::collection::vec(tag(), 0..3), prop::collection::vec(kanji(), 0..2))
+                .prop_map(|(reb, re_pri, re_restr)| REle { reb, re_pri, re_restr }),
+            1..3,
+        ),
+        prop::collection::vec(sense(), 1..4),
+    )
+        .prop_map(|(ent_seq, k_ele, r_ele, sense)| Entry { ent_seq: ent_seq.to_string(), k_ele, r_ele, sense })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn stored_entries_read_back_unchanged(entry in entry()) {
+        let (rt, db) = &*DB;
+        let stored = rt.block_on(async {
+            db.upsert_entry(&entry).await.unwrap();
+            db.entry_by_seq(entry.ent_seq.parse().unwrap()).await.unwrap().unwrap()
+        });
+        let expected = EntryParsed::try_from(&entry).unwrap();
+
+        prop_assert_eq!(&stored.ent_seq, &expected.ent_seq);
+        prop_assert_eq!(&stored.rebs, &expected.rebs);
+        prop_assert_eq!(&stored.kebs, &expected.kebs);
+        prop_assert_eq!(&stored.priority, &expected.priority);
+        prop_assert_eq!(&stored.nf_bucket, &expected.nf_bucket);
+        prop_assert_eq!(&stored.source, &expected.source);
+        prop_assert_eq!(&stored.re_restr, &expected.re_restr);
+        prop_assert_eq!(stored.senses.len(), expected.senses.len());
+        for (s, e) in stored.senses.iter().zip(&expected.senses) {
+            prop_assert_eq!(s.sense_order, e.sense_order);
+            prop_assert_eq!(&s.pos, &e.pos);
+            prop_assert_eq!(&s.xref, &e.xref);
+            prop_assert_eq!(&s.gloss, &e.gloss);
+            prop_assert_eq!(&s.other_glosses, &e.other_glosses);
+            prop_assert_eq!(&s.stagk, &e.stagk);
+            prop_assert_eq!(&s.stagr, &e.stagr);
+            prop_assert_eq!(&s.misc, &e.misc);
+            prop_assert_eq!(&s.field, &e.field);
+            prop_assert_eq!(&s.dial, &e.dial);
+        }
+        prop_assert!(stored.content_eq(&expected), "{:?} != {:?}", stored, expected);
+    }
+}
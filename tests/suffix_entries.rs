@@ -0,0 +1,102 @@
+use jmdictdb::{split_leading_number, DictDb, Entry, KEle, PartOfSpeech, REle, Sense};
+
+fn word(ent_seq: i64, kebs: &[&str], reb: &str, pos: &[PartOfSpeech], pri: &[&str]) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: (!kebs.is_empty()).then(|| {
+            kebs.iter()
+                .map(|keb| KEle { keb: keb.to_string(), ke_pri: pri.iter().map(|p| p.to_string()).collect(), ..Default::default() })
+                .collect()
+        }),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense {
+            pos: pos.iter().map(|p| p.description().to_string()).collect(),
+            gloss: vec![format!("word {}", ent_seq)],
+            ..Default::default()
+        }],
+    }
+}
+
+async fn db() -> DictDb {
+    use PartOfSpeech::*;
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[
+        word(1, &["ヶ月", "か月", "カ月", "箇月"], "かげつ", &[Suffix, Ctr], &["nf10"]),
+        word(2, &["人"], "ひと", &[Noun], &["nf01"]),
+        word(3, &["人"], "にん", &[Suffix, Ctr], &["nf20"]),
+        word(4, &["年"], "ねん", &[Noun, Ctr], &["nf02"]),
+        word(5, &[], "によって", &[Exp], &[]),
+        word(6, &["〜中"], "ちゅう", &[Suffix], &[]),
+        // Words starting with a numeral, and what is left of them without it
+        word(7, &["一緒"], "いっしょ", &[Noun], &["nf03"]),
+        word(8, &["緒"], "お", &[Noun], &[]),
+        word(9, &["千葉"], "ちば", &[Noun], &[]),
+        word(10, &["葉"], "は", &[Noun], &["nf05"]),
+        word(11, &["万年筆"], "まんねんひつ", &[Noun], &[]),
+        word(12, &["十分"], "じゅうぶん", &[Noun], &["nf04"]),
+        word(13, &["分"], "ふん", &[Suffix, Ctr], &["nf06"]),
+    ])
+    .await
+    .unwrap();
+    db
+}
+
+async fn lookup(db: &DictDb, selection: &str) -> Option<(i64, Option<u64>, String)> {
+    let found = db.search_suffix_entry(selection).await.unwrap()?;
+    Some((found.entry.ent_seq, found.number, found.suffix))
+}
+
+#[test]
+fn leading_numbers_are_split_off() {
+    assert_eq!(split_leading_number("3ヶ月"), (Some(3), "ヶ月"));
+    assert_eq!(split_leading_number("２０２３年"), (Some(2023), "年"));
+    assert_eq!(split_leading_number("1,000人"), (Some(1000), "人"));
+    assert_eq!(split_leading_number("五人"), (Some(5), "人"));
+    assert_eq!(split_leading_number("二千二十三年"), (Some(2023), "年"));
+    assert_eq!(split_leading_number("二〇二三年"), (Some(2023), "年"));
+    assert_eq!(split_leading_number("三万五千人"), (Some(35000), "人"));
+    assert_eq!(split_leading_number("十日"), (Some(10), "日"));
+    assert_eq!(split_leading_number("〜によって"), (None, "によって"));
+    assert_eq!(split_leading_number(" ～ 中 "), (None, "中"));
+    assert_eq!(split_leading_number("42"), (Some(42), ""));
+    // Any leading numeral, the lookup sorts out the words
+    assert_eq!(split_leading_number("一緒"), (Some(1), "緒"));
+    assert_eq!(split_leading_number("万年筆"), (Some(10000), "年筆"));
+    // Too large for a u64, also across 万 and 億 groups
+    assert_eq!(split_leading_number("99999999999999999999個"), (None, "個"));
+    assert_eq!(split_leading_number("千18446744073709551000万個"), (None, "個"));
+}
+
+#[tokio::test]
+async fn selections_land_on_the_suffix_entry() {
+    let db = db().await;
+    assert_eq!(lookup(&db, "3ヶ月").await, Some((1, Some(3), "ヶ月".into())));
+    assert_eq!(lookup(&db, "3か月").await, Some((1, Some(3), "か月".into())));
+    // The counter rather than the more frequent noun
+    assert_eq!(lookup(&db, "五人").await, Some((3, Some(5), "人".into())));
+    assert_eq!(lookup(&db, "2023年").await, Some((4, Some(2023), "年".into())));
+    assert_eq!(lookup(&db, "〜によって").await, Some((5, None, "によって".into())));
+    // The tilde of the headword is left out too
+    assert_eq!(lookup(&db, "中").await, Some((6, None, "中".into())));
+    // A counter after the number wins over the word
+    assert_eq!(lookup(&db, "十分").await, Some((13, Some(10), "分".into())));
+}
+
+#[tokio::test]
+async fn words_starting_with_a_numeral_find_themselves() {
+    let db = db().await;
+    // Even though 緒 and 葉 are entries, neither is a counter
+    assert_eq!(lookup(&db, "一緒").await, Some((7, None, "一緒".into())));
+    assert_eq!(lookup(&db, "千葉").await, Some((9, None, "千葉".into())));
+    assert_eq!(lookup(&db, "万年筆").await, Some((11, None, "万年筆".into())));
+    // With no entry for the whole, the rest it is
+    assert_eq!(lookup(&db, "三葉").await, Some((10, Some(3), "葉".into())));
+}
+
+#[tokio::test]
+async fn selections_without_a_suffix_entry() {
+    let db = db().await;
+    assert_eq!(lookup(&db, "3週間").await, None);
+    assert_eq!(lookup(&db, "2023").await, None);
+    assert!(db.search_suffix_entry("  ").await.is_err());
+}
@@ -0,0 +1,115 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{DictDb, Entry, KEle, REle, Sense, Suggestion};
+use std::time::Instant;
+
+fn entry(ent_seq: i64, keb: &str, reb: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle { keb: keb.to_string(), ..Default::default() }]),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec!["test".to_string()], ..Default::default() }],
+    }
+}
+
+/// The first one to three characters of the sample headwords starting with a few common characters.
+async fn prefixes(db: &DictDb) -> Vec<String> {
+    let mut prefixes = Vec::new();
+    for first in ["た", "い", "が", "食", "大", "お", "x", "ー"] {
+        for s in db.suggest_fast(first, 100).await.unwrap() {
+            let chars: Vec<char> = s.headword.chars().collect();
+            for n in 1..=chars.len().min(3) {
+                prefixes.push(chars[..n].iter().collect());
+            }
+        }
+        prefixes.push(first.to_string());
+    }
+    prefixes.sort();
+    prefixes.dedup();
+    prefixes
+}
+
+#[tokio::test]
+async fn memory_index_matches_sql() {
+    let db = build_sample_db().await;
+    assert_eq!(db.suggest_index_stats(), None);
+
+    let prefixes = prefixes(&db).await;
+    assert!(prefixes.len() > 20);
+    let mut from_sql = Vec::new();
+    for p in &prefixes {
+        from_sql.push(db.suggest_fast(p, 5).await.unwrap());
+    }
+
+    let stats = db.build_suggest_index().await.unwrap();
+    assert!(stats.headwords > 241, "{:?}", stats);
+    assert!(stats.bytes > stats.headwords * 8);
+    assert_eq!(db.suggest_index_stats(), Some(stats));
+    for (p, expected) in prefixes.iter().zip(&from_sql) {
+        assert_eq!(&db.suggest_fast(p, 5).await.unwrap(), expected, "{:?}", p);
+    }
+
+    let tabe = db.suggest_fast("たべ", 10).await.unwrap();
+    assert!(tabe.iter().any(|s| s.headword == "たべる"));
+    assert!(tabe.iter().all(|s| s.headword.starts_with("たべ")));
+    assert!(tabe.windows(2).all(|w| w[0].headword <= w[1].headword));
+    assert!(db.suggest_fast("", 10).await.is_err());
+    assert!(db.suggest_fast("たべ", 0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn index_is_a_snapshot_until_rebuilt() {
+    let db = build_sample_db().await;
+    db.build_suggest_index().await.unwrap();
+
+    db.insert_entries(&[entry(8_000_001, "試験語", "しけんご")]).await.unwrap();
+    assert!(db.suggest_fast("しけんご", 5).await.unwrap().is_empty());
+
+    let before = db.suggest_index_stats().unwrap();
+    let after = db.build_suggest_index().await.unwrap();
+    assert_eq!(after.headwords, before.headwords + 2);
+    let found = db.suggest_fast("試験", 5).await.unwrap();
+    assert_eq!(found, [Suggestion { headword: "試験語".to_string(), ent_seq: 8_000_001, hint: None }]);
+
+    // Without the index the same comes from SQL
+    db.drop_suggest_index();
+    assert_eq!(db.suggest_index_stats(), None);
+    assert_eq!(db.suggest_fast("試験", 5).await.unwrap(), found);
+}
+
+// cargo test --release --test suggest -- --ignored --nocapture
+#[tokio::test]
+#[ignore = "benchmark"]
+async fn bench_suggest_latency() {
+    const ENTRIES: i64 = 50_000;
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let kana: Vec<char> = "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよらりるれろわ".chars().collect();
+    let entries: Vec<Entry> = (0..ENTRIES)
+        .map(|i| {
+            let reb: String = [i, i / 7, i / 49, i / 343].iter().map(|n| kana[*n as usize % kana.len()]).collect();
+            entry(i + 1, &format!("{}語", reb), &reb)
+        })
+        .collect();
+    db.insert_entries(&entries).await.unwrap();
+
+    let queries: Vec<String> = (0..200).map(|i| kana[i % kana.len()].to_string() + &kana[i / 3 % kana.len()].to_string()).collect();
+    let time = |label: &str, started: Instant| {
+        let per_query = started.elapsed() / queries.len() as u32;
+        println!("{:>6}: {:?} per query", label, per_query);
+    };
+
+    let started = Instant::now();
+    for q in &queries {
+        db.suggest_fast(q, 10).await.unwrap();
+    }
+    time("sql", started);
+
+    let started = Instant::now();
+    let stats = db.build_suggest_index().await.unwrap();
+    println!(" build: {:?} for {} headwords, {} KiB", started.elapsed(), stats.headwords, stats.bytes / 1024);
+
+    let started = Instant::now();
+    for q in &queries {
+        db.suggest_fast(q, 10).await.unwrap();
+    }
+    time("memory", started);
+}
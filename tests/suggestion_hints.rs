@@ -0,0 +1,76 @@
+use jmdictdb::fixtures::sample_dict;
+use jmdictdb::{DictDb, DictDbOptions, Entry, KEle, REle, Sense, Suggestion, SuggestionHint};
+
+fn entry(ent_seq: i64, kebs: &[&str], rebs: &[(&str, &[&str])], pos: &str, gloss: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(kebs.iter().map(|k| KEle { keb: k.to_string(), ..Default::default() }).collect()),
+        r_ele: rebs
+            .iter()
+            .map(|(reb, restr)| REle { reb: reb.to_string(), re_restr: restr.iter().map(|k| k.to_string()).collect(), ..Default::default() })
+            .collect(),
+        sense: vec![Sense { pos: vec![pos.to_string()], gloss: vec![gloss.to_string()], ..Default::default() }],
+    }
+}
+
+async fn db(hints: bool) -> DictDb {
+    let db = DictDbOptions::new("sqlite::memory:").suggestion_hints(hints).connect().await.unwrap();
+    db.insert_entries(&sample_dict().entry).await.unwrap();
+    db.insert_entries(&[
+        entry(2900300, &["生"], &[("なま", &[])], "adj-no", "raw; uncooked; fresh"),
+        entry(2900301, &["生"], &[("せい", &[])], "n", "life; living"),
+        entry(2900302, &["生"], &[("き", &[])], "pref", "pure; undiluted; raw; crude; a rather long gloss"),
+        entry(2900303, &["試石", "試岩"], &[("ためしいわ", &["試岩"]), ("ためしいし", &[])], "n", "test stone"),
+    ])
+    .await
+    .unwrap();
+    db
+}
+
+fn hint(reading: Option<&str>, gloss: &str, pos: &str) -> Option<SuggestionHint> {
+    Some(SuggestionHint { reading: reading.map(str::to_string), gloss: Some(gloss.to_string()), pos: Some(pos.to_string()) })
+}
+
+#[tokio::test]
+async fn tells_homographs_apart() {
+    let db = db(true).await;
+    let found = db.suggest_fast("生", 3).await.unwrap();
+    assert_eq!(found.iter().map(|s| s.ent_seq).collect::<Vec<_>>(), [2900300, 2900301, 2900302]);
+    assert_eq!(found[0].hint, hint(Some("なま"), "raw; uncooked; fresh", "adj-no"));
+    assert_eq!(found[1].label(), "生 (せい) — life; living");
+    // Long glosses are cut
+    assert_eq!(found[2].hint, hint(Some("き"), "pure; undiluted; raw; crude…", "pref"));
+
+    // A reb needs no reading
+    let nama = db.suggest_fast("なま", 1).await.unwrap();
+    assert_eq!(nama[0].hint, hint(None, "raw; uncooked; fresh", "adj-no"));
+    assert_eq!(nama[0].label(), "なま — raw; uncooked; fresh");
+
+    // The reading is one the keb may have
+    let stones = db.suggest_fast("試", 2).await.unwrap();
+    let readings: Vec<(&str, Option<&str>)> =
+        stones.iter().map(|s| (s.headword.as_str(), s.hint.as_ref().unwrap().reading.as_deref())).collect();
+    assert_eq!(readings, [("試岩", Some("ためしいわ")), ("試石", Some("ためしいし"))]);
+}
+
+#[tokio::test]
+async fn the_memory_index_carries_the_hints() {
+    let db = db(true).await;
+    let prefixes = ["生", "な", "試", "食", "たべ", "データベ", "ねこ"];
+    let mut from_sql = Vec::new();
+    for p in prefixes {
+        from_sql.push(db.suggest_fast(p, 10).await.unwrap());
+    }
+    let with_hints = db.build_suggest_index().await.unwrap();
+    for (p, expected) in prefixes.iter().zip(&from_sql) {
+        assert_eq!(&db.suggest_fast(p, 10).await.unwrap(), expected, "{}", p);
+        assert!(expected.iter().all(|s| s.hint.is_some()));
+    }
+
+    let plain = self::db(false).await;
+    let without = plain.build_suggest_index().await.unwrap();
+    assert_eq!(without.headwords, with_hints.headwords);
+    assert!(with_hints.bytes > without.bytes);
+    let found: Vec<Suggestion> = plain.suggest_fast("生", 3).await.unwrap();
+    assert!(found.iter().all(|s| s.hint.is_none() && s.label() == "生"));
+}
@@ -0,0 +1,58 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{parse_entry_xml, EntryParsed, SUMMARY_ELLIPSIS};
+
+fn entry(senses: &str) -> EntryParsed {
+    let xml = format!("<entry><ent_seq>1</ent_seq><r_ele><reb>かな</reb></r_ele>{}</entry>", senses);
+    EntryParsed::try_from(&parse_entry_xml(&xml).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn senses_are_numbered_and_cut_at_the_limit() {
+    let db = build_sample_db().await;
+    let taberu = db.entry_by_seq(1358280).await.unwrap().unwrap();
+
+    assert_eq!(taberu.senses[0].summary(5), "to eat");
+    assert_eq!(taberu.senses[1].summary(1), "to live on (e.g. a salary); …");
+    assert_eq!(taberu.senses[1].summary(0), SUMMARY_ELLIPSIS);
+
+    let full = "(1) to eat (2) to live on (e.g. a salary); to live off; to subsist on";
+    assert_eq!(taberu.summary(200), full);
+    assert_eq!(taberu.summary(full.chars().count()), full);
+    let cut = taberu.summary(20);
+    assert_eq!(cut, "(1) to eat (2) to l…");
+    assert_eq!(cut.chars().count(), 20);
+    // No separator left dangling before the ellipsis
+    assert_eq!(taberu.summary(12), "(1) to eat…");
+    assert_eq!(taberu.summary(0), "");
+
+    // A single sense isn't numbered
+    let food = db.entry_by_seq(1358300).await.unwrap().unwrap();
+    assert_eq!(food.summary(80), "food; provisions");
+}
+
+#[test]
+fn cjk_glosses_are_cut_between_characters() {
+    let e = entry("<sense><gloss>漢字の読み方</gloss><gloss>ひらがな</gloss></sense>");
+    assert_eq!(e.summary(80), "漢字の読み方; ひらがな");
+    for max in 1..12 {
+        let cut = e.summary(max);
+        assert!(cut.chars().count() <= max, "{}: {}", max, cut);
+        assert!(cut.ends_with(SUMMARY_ELLIPSIS));
+    }
+    assert_eq!(e.summary(4), "漢字の…");
+    assert_eq!(e.summary(1), "…");
+}
+
+#[test]
+fn one_long_explanatory_gloss() {
+    let long = "expression used when something unexpected happens, \u{2018}oh dear\u{2019} or \u{2018}goodness me\u{2019}, \
+                said mostly by older speakers and in writing that imitates their speech";
+    let e = entry(&format!("<sense><gloss>{}</gloss></sense>", long));
+    assert_eq!(e.senses[0].summary(3), long);
+    let cut = e.summary(40);
+    assert_eq!(cut.chars().count(), 40);
+    assert_eq!(cut, format!("{}…", long.chars().take(39).collect::<String>()));
+    // Curly quotes are multi-byte
+    let at_quote = long.chars().position(|c| c == '\u{2018}').unwrap();
+    assert_eq!(e.summary(at_quote + 2), format!("{}\u{2018}…", long.chars().take(at_quote).collect::<String>()));
+}
@@ -0,0 +1,108 @@
+use jmdictdb::entities::STANDARD;
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{known_tags, lookup_tag, parse_jmdict_str, DictDb, PartOfSpeech, RankOptions, SearchFilter, TagCategory, TagInfo};
+
+async fn ranked_seqs(db: &DictDb, query: &str, filter: SearchFilter) -> Vec<i64> {
+    let opts = RankOptions { filter, ..Default::default() };
+    db.search_ranked(query, &opts).await.unwrap().iter().map(|r| r.entry.ent_seq).collect()
+}
+
+#[test]
+fn every_entity_has_a_category_and_label() {
+    assert_eq!(known_tags().count(), STANDARD.len());
+    for tag in known_tags() {
+        assert!(tag.is_known(), "{}", tag.name);
+        assert!(!tag.label.is_empty(), "{}", tag.name);
+        assert_eq!(STANDARD[tag.name.as_ref()], tag.description);
+        assert_eq!(&lookup_tag(&tag.name), tag);
+    }
+    for category in TagCategory::KNOWN {
+        assert!(known_tags().any(|t| t.category == category), "{:?}", category);
+        assert_eq!(TagCategory::from_element(category.element().unwrap()), Some(category));
+    }
+    for pos in PartOfSpeech::ALL {
+        assert_eq!(TagInfo::from(pos).category, TagCategory::PartOfSpeech, "{}", pos.code());
+    }
+}
+
+#[test]
+fn looks_up_names_and_descriptions() {
+    let uk = lookup_tag("uk");
+    assert_eq!((uk.category, uk.label.as_ref()), (TagCategory::Misc, "usually kana"));
+    assert_eq!(lookup_tag("word usually written using kana alone"), uk);
+    assert_eq!(lookup_tag("Kansai-ben").name, "ksb");
+    assert_eq!(lookup_tag("ksb").category, TagCategory::Dialect);
+    assert_eq!(lookup_tag("comp").category, TagCategory::Field);
+    assert_eq!(lookup_tag("iK").category, TagCategory::KanjiInfo);
+    assert_eq!(lookup_tag("ok").category, TagCategory::ReadingInfo);
+    // Declared with the kanji info first, but about readings
+    assert_eq!(lookup_tag("ik").category, TagCategory::ReadingInfo);
+
+    // Curated labels, and the others cut from the description
+    assert_eq!(lookup_tag("adj-i").label, "i-adjective");
+    assert_eq!(lookup_tag("v5k").label, "Godan verb (-ku)");
+    assert_eq!(lookup_tag("v2b-k").label, "Nidan verb (-bu)");
+    assert_eq!(lookup_tag("n").label, "noun");
+    assert_eq!(lookup_tag("joc").label, "jocular");
+    assert_eq!(lookup_tag("arch").label, "archaic");
+    assert_eq!(TagInfo::from(PartOfSpeech::V1), lookup_tag("Ichidan verb"));
+}
+
+#[test]
+fn unknown_tags_keep_their_text() {
+    let new = lookup_tag("some tag of 2030");
+    assert_eq!(new, TagInfo::unknown("some tag of 2030"));
+    assert!(!new.is_known());
+    assert_eq!((new.name.as_ref(), new.label.as_ref(), new.description.as_ref()), ("some tag of 2030", "some tag of 2030", "some tag of 2030"));
+    let json = serde_json::to_string(&new).unwrap();
+    assert_eq!(json, r#"{"name":"some tag of 2030","category":"unknown","label":"some tag of 2030","description":"some tag of 2030"}"#);
+    assert_eq!(serde_json::from_str::<TagInfo>(&json).unwrap(), new);
+    assert_eq!(serde_json::from_str::<TagInfo>(&serde_json::to_string(&lookup_tag("uk")).unwrap()).unwrap(), lookup_tag("uk"));
+}
+
+#[tokio::test]
+async fn senses_list_their_tags() {
+    let db = build_sample_db().await;
+    let ohayou = db.search_entries_with_senses_by_reading("おはようございます").await.unwrap().remove(0);
+    let names: Vec<_> = ohayou.senses[0].tags().into_iter().map(|t| t.name).collect();
+    assert_eq!(names, ["exp", "int", "uk"]);
+}
+
+#[tokio::test]
+async fn filters_by_tag_name_description_or_info() {
+    let db = build_sample_db().await;
+    let all = ranked_seqs(&db, "おはようございます", SearchFilter::new()).await;
+    assert!(!all.is_empty());
+    for filter in [
+        SearchFilter::new().tag("uk"),
+        SearchFilter::new().tag("word usually written using kana alone"),
+        SearchFilter::new().tag(lookup_tag("uk")),
+        SearchFilter::new().tag("exp").tag(PartOfSpeech::Int),
+    ] {
+        assert_eq!(ranked_seqs(&db, "おはようございます", filter).await, all);
+    }
+    assert!(ranked_seqs(&db, "おはようございます", SearchFilter::new().tag("v1")).await.is_empty());
+    assert!(ranked_seqs(&db, "たべる", SearchFilter::new().tag("uk")).await.is_empty());
+    assert!(!ranked_seqs(&db, "たべる", SearchFilter::new().tag("v1")).await.is_empty());
+    // Not stored, so nothing has them
+    assert!(ranked_seqs(&db, "たべる", SearchFilter::new().tag("iK")).await.is_empty());
+}
+
+#[tokio::test]
+async fn tags_of_later_releases_parse_and_filter() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE JMdict [
+<!ENTITY n "noun (common) (futsuumeishi)">
+<!ENTITY newtag "a tag added in 2030">
+]>
+<JMdict>
+<entry><ent_seq>2900001</ent_seq><r_ele><reb>あたらしい</reb></r_ele>
+<sense><pos>&n;</pos><misc>&newtag;</misc><gloss>novelty</gloss></sense></entry>
+</JMdict>"#;
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&parse_jmdict_str(xml).unwrap().entry).await.unwrap();
+    let entry = db.entry_by_seq(2_900_001).await.unwrap().unwrap();
+    assert_eq!(entry.senses[0].tags(), [lookup_tag("n"), TagInfo::unknown("a tag added in 2030")]);
+    assert_eq!(ranked_seqs(&db, "あたらしい", SearchFilter::new().tag("a tag added in 2030")).await, [2_900_001]);
+    assert!(ranked_seqs(&db, "あたらしい", SearchFilter::new().tag("another new tag")).await.is_empty());
+}
@@ -0,0 +1,114 @@
+use jmdictdb::{parse_tanaka_b_line, DictDb, Entry, KEle, REle, Sense, TanakaWord};
+
+fn word(headword: &str) -> TanakaWord {
+    TanakaWord { headword: headword.to_string(), reading: None, sense: None, surface: None, checked: false }
+}
+
+#[test]
+fn b_line_markers() {
+    let words = parse_tanaka_b_line("B: 事(こと)[01]{こと}~ 猫 食べる{食べた} 頭(あたま) 人[2]~").unwrap();
+    assert_eq!(
+        words,
+        [
+            TanakaWord { reading: Some("こと".into()), sense: Some(1), surface: Some("こと".into()), checked: true, ..word("事") },
+            word("猫"),
+            TanakaWord { surface: Some("食べた".into()), ..word("食べる") },
+            TanakaWord { reading: Some("あたま".into()), ..word("頭") },
+            TanakaWord { sense: Some(2), checked: true, ..word("人") },
+        ]
+    );
+    // The B: prefix is optional, whitespace is any run of it
+    assert_eq!(parse_tanaka_b_line("  猫\t 犬  ").unwrap(), [word("猫"), word("犬")]);
+    assert!(parse_tanaka_b_line("B:").unwrap().is_empty());
+}
+
+#[test]
+fn malformed_b_lines() {
+    for line in ["B: 事(こと", "B: 事[01", "B: 事{こと", "B: 事[一]", "B: (こと)", "B: ~", "B: 事(こと)x"] {
+        assert!(parse_tanaka_b_line(line).is_err(), "{}", line);
+    }
+    // One bad word fails the line
+    assert!(parse_tanaka_b_line("B: 猫 犬[").is_err());
+}
+
+const CORPUS: &str = "\
+A: 猫が魚を食べた。\tThe cat ate the fish.#ID=1_2
+B: 猫 が 魚 を 食べる{食べた}~
+A: 頭が痛い。\tMy head hurts.#ID=3_4
+B: 頭(あたま)[01] が 痛い
+A: no tab here
+B: 猫
+B: 犬
+A: 猫は小さい。\tThe cat is small.#ID=5_6
+B: 猫 は 小さい[01
+A: 犬を見る。\tI see the dog.#ID=7_8
+B: 犬 を 見る 猫(いぬ)
+";
+
+fn entry(ent_seq: i64, keb: &str, reb: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle { keb: keb.to_string(), ke_pri: Vec::new(), ke_inf: None }]),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec![format!("word {}", ent_seq)], ..Default::default() }],
+    }
+}
+
+/// The words of the corpora below, each its own entry.
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    let words = [
+        entry(1, "猫", "ねこ"),
+        entry(2, "魚", "さかな"),
+        entry(3, "食べる", "たべる"),
+        entry(4, "頭", "あたま"),
+        entry(5, "犬", "いぬ"),
+        entry(6, "見る", "みる"),
+        entry(7, "好き", "すき"),
+    ];
+    db.insert_entries(&words).await.unwrap();
+    db
+}
+
+async fn ent_seq(db: &DictDb, keb: &str) -> i64 {
+    db.search_entries_with_senses_by_kanji(keb).await.unwrap()[0].ent_seq
+}
+
+#[tokio::test]
+async fn examples_are_linked_by_ent_seq() {
+    let db = db().await;
+    let report = db.load_tanaka_corpus_reader(CORPUS.as_bytes()).await.unwrap();
+    assert_eq!(report.sentences, 4);
+    // The malformed A line, the B lines after it and without an A line, the unclosed sense
+    let lines: Vec<usize> = report.errors.iter().map(|(line, _)| *line).collect();
+    assert_eq!(lines, [5, 6, 7, 9]);
+    assert_eq!(report.words, 5 + 3 + 4);
+
+    let japanese = |s: Vec<jmdictdb::ExampleSentence>| s.into_iter().map(|s| s.japanese).collect::<Vec<_>>();
+    // Not 猫(いぬ) of the last sentence, whose reading matches no 猫 entry
+    let neko = ent_seq(&db, "猫").await;
+    assert_eq!(japanese(db.example_sentences(neko, 10).await.unwrap()), ["猫が魚を食べた。"]);
+    let taberu = ent_seq(&db, "食べる").await;
+    let examples = db.example_sentences(taberu, 10).await.unwrap();
+    assert_eq!(examples[0].english, "The cat ate the fish.");
+    // Linked through the headword and its reading
+    let atama = ent_seq(&db, "頭").await;
+    assert_eq!(japanese(db.example_sentences(atama, 10).await.unwrap()), ["頭が痛い。"]);
+    let inu = ent_seq(&db, "犬").await;
+    assert_eq!(japanese(db.example_sentences(inu, 10).await.unwrap()), ["犬を見る。"]);
+    assert_eq!(db.example_sentences(neko, 0).await.unwrap(), []);
+}
+
+#[tokio::test]
+async fn good_examples_come_first_and_loading_again_replaces() {
+    let db = db().await;
+    let corpus = "A: 猫だ。\tIt's a cat.\nB: 猫\nA: 猫が好き。\tI like cats.\nB: 猫~ 好き\n";
+    let report = db.load_tanaka_corpus_reader(corpus.as_bytes()).await.unwrap();
+    assert!(report.errors.is_empty());
+    let neko = ent_seq(&db, "猫").await;
+    let examples = db.example_sentences(neko, 10).await.unwrap();
+    assert_eq!(examples.iter().map(|s| s.english.as_str()).collect::<Vec<_>>(), ["I like cats.", "It's a cat."]);
+
+    db.load_tanaka_corpus_reader("A: 犬だ。\tIt's a dog.\nB: 犬\n".as_bytes()).await.unwrap();
+    assert!(db.example_sentences(neko, 10).await.unwrap().is_empty());
+}
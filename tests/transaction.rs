@@ -0,0 +1,62 @@
+use jmdictdb::{CustomEntry, DictDb, Entry, KEle, REle, Sense};
+
+const TABERU: i64 = 1358280;
+const IKU: i64 = 1578850;
+
+fn entry(ent_seq: i64, keb: &str, reb: &str, gloss: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle { keb: keb.into(), ..Default::default() }]),
+        r_ele: vec![REle { reb: reb.into(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec![gloss.into()], ..Default::default() }],
+    }
+}
+
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[entry(TABERU, "食べる", "たべる", "to eat"), entry(IKU, "行く", "いく", "to go")])
+        .await
+        .unwrap();
+    db
+}
+
+/// 食べる with its gloss replaced.
+fn changed_taberu() -> Entry {
+    entry(TABERU, "食べる", "たべる", "to munch")
+}
+
+async fn first_gloss(db: &DictDb) -> String {
+    let found = db.search_entries_with_senses_by_reading("たべる").await.unwrap();
+    found.into_iter().find(|e| e.ent_seq == TABERU).unwrap().senses[0].gloss[0].clone()
+}
+
+#[tokio::test]
+async fn dropping_without_commit_rolls_back() {
+    let db = db().await;
+    {
+        let mut tx = db.begin().await.unwrap();
+        assert!(tx.upsert_entry(&changed_taberu()).await.unwrap());
+        // The transaction reads its own write
+        assert_eq!(tx.entry(TABERU).await.unwrap().unwrap().senses[0].gloss[0], "to munch");
+        tx.insert_custom_entry(&CustomEntry { rebs: vec!["かりに".into()], ..Default::default() }).await.unwrap();
+    }
+    assert_eq!(first_gloss(&db).await, "to eat");
+    assert!(db.list_custom_entries().await.unwrap().is_empty());
+    assert!(db.search_entries_with_senses_by_reading("かりに").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn rollback_and_commit() {
+    let db = db().await;
+    let mut tx = db.begin().await.unwrap();
+    tx.upsert_entry(&changed_taberu()).await.unwrap();
+    tx.rollback().await.unwrap();
+    assert_eq!(first_gloss(&db).await, "to eat");
+
+    let mut tx = db.begin().await.unwrap();
+    tx.upsert_entry(&changed_taberu()).await.unwrap();
+    tx.delete_entry(IKU).await.unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(first_gloss(&db).await, "to munch");
+    assert!(db.search_entries_with_senses_by_reading("いく").await.unwrap().is_empty());
+}
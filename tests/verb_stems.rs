@@ -0,0 +1,64 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{verb_stem_candidates, MatchKind, PartOfSpeech, RankOptions};
+
+fn seqs(entries: &[jmdictdb::EntryParsed]) -> Vec<i64> {
+    entries.iter().map(|e| e.ent_seq).collect()
+}
+
+#[test]
+fn candidates_append_each_ending() {
+    let candidates = verb_stem_candidates("食べ");
+    let headwords: Vec<&str> = candidates.iter().map(|c| c.headword.as_str()).collect();
+    assert_eq!(headwords, ["食べる", "食べう", "食べく", "食べぐ", "食べす", "食べつ", "食べぬ", "食べぶ", "食べむ", "食べする"]);
+    assert!(candidates[0].pos.contains(&PartOfSpeech::V1));
+    assert!(candidates[0].pos.contains(&PartOfSpeech::V5r));
+    assert_eq!(candidates[4].pos, [PartOfSpeech::V5s]);
+
+    let suru = verb_stem_candidates("勉強し");
+    assert_eq!(suru[suru.len() - 2].headword, "勉強");
+    assert_eq!(suru[suru.len() - 2].pos, [PartOfSpeech::Vs]);
+    assert_eq!(suru[suru.len() - 1].headword, "勉強する");
+    assert!(verb_stem_candidates("").is_empty());
+    assert_eq!(verb_stem_candidates("し").len(), 10);
+}
+
+#[tokio::test]
+async fn finds_ichidan_godan_and_suru_verbs() {
+    let db = build_sample_db().await;
+    assert_eq!(seqs(&db.search_verb_stem("食べ").await.unwrap()), [1358280]);
+    assert_eq!(seqs(&db.search_verb_stem("教え").await.unwrap()), [1206220]);
+    assert_eq!(seqs(&db.search_verb_stem("書").await.unwrap()), [1313580]);
+    assert_eq!(seqs(&db.search_verb_stem("帰").await.unwrap()), [1188670]);
+    assert_eq!(seqs(&db.search_verb_stem("泳").await.unwrap()), [1573100]);
+    assert_eq!(seqs(&db.search_verb_stem("よ").await.unwrap()), [1374550]);
+    assert_eq!(seqs(&db.search_verb_stem("勉強し").await.unwrap()), [1206730]);
+    assert!(db.search_verb_stem("").await.is_err());
+}
+
+#[tokio::test]
+async fn candidates_need_a_verb_part_of_speech() {
+    let db = build_sample_db().await;
+    // おはよう is an interjection, 学生 a noun that doesn't take する
+    assert!(db.search_verb_stem("おはよ").await.unwrap().is_empty());
+    assert!(db.search_verb_stem("学生し").await.unwrap().is_empty());
+    assert!(db.search_verb_stem("猫").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn ranked_search_option_ranks_verbs_as_exact() {
+    let db = build_sample_db().await;
+    let plain = db.search_ranked("食べ", &RankOptions::default()).await.unwrap();
+    assert!(plain.iter().all(|r| r.match_kind == MatchKind::Prefix));
+
+    let opts = RankOptions { verb_stems: true, ..RankOptions::default() };
+    let stems = db.search_ranked("食べ", &opts).await.unwrap();
+    assert_eq!(stems[0].entry.ent_seq, 1358280);
+    assert_eq!(stems[0].match_kind, MatchKind::Exact);
+    assert!(stems[1..].iter().all(|r| r.match_kind == MatchKind::Prefix));
+    assert_eq!(stems.len(), plain.len());
+
+    let suru = db.search_ranked("勉強し", &opts).await.unwrap();
+    assert_eq!(suru.len(), 1);
+    assert_eq!(suru[0].entry.ent_seq, 1206730);
+    assert!(db.search_ranked("勉強し", &RankOptions::default()).await.unwrap().is_empty());
+}
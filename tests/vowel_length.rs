@@ -0,0 +1,58 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{fold_vowel_length, DictDb, Entry, MatchKind, REle, RankOptions, Sense};
+
+fn entry(ent_seq: i64, reb: &str, gloss: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        r_ele: vec![REle { reb: reb.to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec![gloss.to_string()], ..Default::default() }],
+        ..Default::default()
+    }
+}
+
+async fn db_with_long_vowels() -> DictDb {
+    let db = build_sample_db().await;
+    let entries = [
+        entry(2900080, "サッカー", "soccer"),
+        entry(2900081, "ビル", "building"),
+        entry(2900082, "ビール", "beer"),
+        entry(2900083, "おかあさん", "mother"),
+    ];
+    db.insert_entries(&entries).await.unwrap();
+    db
+}
+
+async fn ranked(db: &DictDb, query: &str, ignore_vowel_length: bool) -> Vec<(i64, MatchKind, bool)> {
+    let opts = RankOptions { ignore_vowel_length, ..Default::default() };
+    let results = db.search_ranked(query, &opts).await.unwrap();
+    results.iter().map(|r| (r.entry.ent_seq, r.match_kind, r.length_folded)).collect()
+}
+
+#[test]
+fn folds_long_vowels_and_the_choonpu() {
+    assert_eq!(fold_vowel_length("サッカー"), fold_vowel_length("サッカ"));
+    assert_eq!(fold_vowel_length("ビール"), fold_vowel_length("ビル"));
+    assert_eq!(fold_vowel_length("おかあさん"), "おかさん");
+    assert_eq!(fold_vowel_length("せんせい"), fold_vowel_length("せんせ"));
+    assert_eq!(fold_vowel_length("とうきょう"), fold_vowel_length("ときょ"));
+    // Different vowels stay
+    assert_ne!(fold_vowel_length("かお"), fold_vowel_length("か"));
+}
+
+#[tokio::test]
+async fn finds_readings_of_another_vowel_length() {
+    let db = db_with_long_vowels().await;
+    assert_eq!(ranked(&db, "サッカ", false).await, [(2900080, MatchKind::Prefix, false)]);
+    assert_eq!(ranked(&db, "サッカ", true).await, [(2900080, MatchKind::LengthFolded, true)]);
+    assert_eq!(ranked(&db, "おかさん", false).await, []);
+    assert_eq!(ranked(&db, "おかさん", true).await, [(2900083, MatchKind::LengthFolded, true)]);
+}
+
+#[tokio::test]
+async fn exact_matches_rank_above_folded_ones() {
+    let db = db_with_long_vowels().await;
+    assert_eq!(ranked(&db, "ビル", false).await, [(2900081, MatchKind::Exact, false)]);
+    let folded = ranked(&db, "ビル", true).await;
+    assert_eq!(folded, [(2900081, MatchKind::Exact, false), (2900082, MatchKind::LengthFolded, true)]);
+    assert_eq!(ranked(&db, "ビール", true).await[0], (2900082, MatchKind::Exact, false));
+}
@@ -0,0 +1,65 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{ConnectionPragmas, DictDb, DictDbOptions, RankOptions, WarmupOptions};
+use sqlx::SqlitePool;
+
+#[tokio::test]
+async fn reads_the_hot_tables_and_indexes() {
+    let db = build_sample_db().await;
+    let report = db.warmup(&WarmupOptions::default()).await.unwrap();
+    let phases: Vec<&str> = report.durations.iter().map(|d| d.phase.as_str()).collect();
+    assert_eq!(&phases[..3], ["entries", "idx_entries_nf_bucket", "senses"]);
+    assert!(phases.contains(&"reading_index"));
+    assert!(phases.contains(&"idx_glosses_lang_folded"));
+    assert!(!phases.contains(&"suggest_index"));
+    assert!(report.suggest_index.is_none());
+    assert!(db.suggest_index_stats().is_none());
+    assert!(report.total_millis >= report.durations.iter().map(|d| d.millis).max().unwrap());
+}
+
+#[tokio::test]
+async fn can_build_the_suggest_index() {
+    let db = build_sample_db().await;
+    let report = db.warmup(&WarmupOptions { suggest_index: true }).await.unwrap();
+    assert_eq!(report.durations.last().unwrap().phase, "suggest_index");
+    assert_eq!(report.suggest_index, db.suggest_index_stats());
+    assert!(report.suggest_index.unwrap().headwords > 0);
+}
+
+#[tokio::test]
+async fn runs_alongside_searches() {
+    let db = build_sample_db().await;
+    let opts = WarmupOptions { suggest_index: true };
+    let rank_opts = RankOptions::default();
+    let (report, found, ranked) = tokio::join!(
+        db.warmup(&opts),
+        db.search_entries_with_senses_by_kanji("食べる"),
+        db.search_ranked("た", &rank_opts)
+    );
+    report.unwrap();
+    assert_eq!(found.unwrap()[0].ent_seq, 1358280);
+    assert!(!ranked.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn skips_indexes_missing_from_the_database() {
+    let path = std::env::temp_dir().join(format!("jmdictdb-warmup-{}.db", std::process::id()));
+    let url = format!("sqlite:{}", path.display());
+    DictDb::reset_database(&url).await.unwrap();
+    let db = DictDb::connect(&url).await.unwrap();
+    let pool = SqlitePool::connect(&url).await.unwrap();
+    sqlx::query("DROP INDEX idx_senses_entry").execute(&pool).await.unwrap();
+    pool.close().await;
+
+    let report = db.warmup(&WarmupOptions::default()).await.unwrap();
+    assert!(report.durations.iter().all(|d| d.phase != "idx_senses_entry"));
+    assert!(report.durations.iter().any(|d| d.phase == "senses"));
+    db.close().await;
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn mmap_size_is_a_connection_pragma() {
+    assert_eq!(ConnectionPragmas::default().mmap_size, None);
+    let opts = DictDbOptions::new("sqlite::memory:").mmap_size(256 << 20);
+    assert_eq!(opts.connection_pragmas().statements().last().unwrap(), "PRAGMA mmap_size = 268435456");
+}
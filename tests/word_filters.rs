@@ -0,0 +1,53 @@
+use futures_util::TryStreamExt;
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{is_kanji, DictDb, FlatRow, FlatRowOptions, PartOfSpeech, RankOptions, SearchFilter};
+
+async fn ranked(db: &DictDb, query: &str, filter: SearchFilter) -> Vec<i64> {
+    let opts = RankOptions { filter, ..RankOptions::default() };
+    let mut seqs: Vec<i64> = db.search_ranked(query, &opts).await.unwrap().iter().map(|r| r.entry.ent_seq).collect();
+    seqs.sort();
+    seqs
+}
+
+async fn rows(db: &DictDb, filter: SearchFilter) -> Vec<FlatRow> {
+    let opts = FlatRowOptions { filter, ..FlatRowOptions::default() };
+    db.flat_rows(&opts).try_collect().await.unwrap()
+}
+
+#[tokio::test]
+async fn lengths_apply_to_the_matched_form() {
+    let db = build_sample_db().await;
+    // たべる and たべもの match by reading, whatever the length of their kebs
+    assert_eq!(ranked(&db, "たべ", SearchFilter::new().len(3..=3)).await, [1358280]);
+    assert_eq!(ranked(&db, "たべ", SearchFilter::new().min_len(4)).await, [1358300]);
+    assert_eq!(ranked(&db, "たべ", SearchFilter::new().max_len(2)).await, Vec::<i64>::new());
+    assert_eq!(ranked(&db, "食べ", SearchFilter::new().len(3..=3)).await, [1358280, 1358300]);
+}
+
+#[tokio::test]
+async fn kanji_count_is_checked_after_the_query() {
+    let db = build_sample_db().await;
+    assert_eq!(ranked(&db, "食べ", SearchFilter::new().kanji_count(2..=2)).await, [1358300]);
+    // Readings have no kanji
+    assert_eq!(ranked(&db, "たべ", SearchFilter::new().kanji_count(0..=0)).await, [1358280, 1358300]);
+
+    let compounds = rows(&db, SearchFilter::new().pos(PartOfSpeech::Noun).kanji_count(2..=2)).await;
+    assert!(compounds.iter().any(|r| r.keb.as_deref() == Some("学校")));
+    assert!(compounds.iter().all(|r| r.keb.as_ref().unwrap().chars().filter(|&c| is_kanji(c)).count() == 2));
+    assert!(!compounds.iter().any(|r| r.keb.as_deref() == Some("大丈夫")));
+}
+
+#[tokio::test]
+async fn kana_only_words_of_a_length() {
+    let db = build_sample_db().await;
+    let words = rows(&db, SearchFilter::new().kana_only().len(4..=4)).await;
+    assert!(words.iter().any(|r| r.reb == "おはよう"));
+    assert!(words.iter().all(|r| r.keb.is_none() && r.reb.chars().count() == 4));
+
+    let nouns = rows(&db, SearchFilter::new().pos(PartOfSpeech::Noun).kana_only()).await;
+    assert!(nouns.iter().all(|r| r.keb.is_none()));
+    let all_nouns = rows(&db, SearchFilter::new().pos(PartOfSpeech::Noun)).await;
+    assert!(all_nouns.len() > nouns.len());
+    let noun = PartOfSpeech::Noun.description();
+    assert!(all_nouns.iter().all(|r| all_nouns.iter().any(|n| n.ent_seq == r.ent_seq && n.pos_joined.contains(noun))));
+}
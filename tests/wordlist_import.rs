@@ -0,0 +1,62 @@
+#![cfg(feature = "userdata")]
+
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{Entry, ImportTerm, KEle, REle, Sense};
+
+#[tokio::test]
+async fn imports_an_anki_export() {
+    let db = build_sample_db().await;
+    let tsv = "#separator:tab\n#html:true\n\
+               猫\tねこ\tcat\n\
+               <b>食[た]べる</b>\t\tto eat\n\
+               \"見る\"\tみる\n\
+               コーヒー\n\
+               猫\tネコ\n\
+               \n\
+               存在しない語\tそんざいしないご\n";
+    let report = db.import_anki_tsv(tsv.as_bytes(), "deck").await.unwrap();
+    assert_eq!(report.imported, 5);
+    assert_eq!(
+        report.unresolved,
+        [ImportTerm { position: 9, term: "存在しない語".to_string(), reading: Some("そんざいしないご".to_string()) }]
+    );
+
+    let seqs: Vec<i64> = db.list_items(report.list_id).await.unwrap().iter().map(|i| i.ent_seq).collect();
+    assert!(seqs.contains(&1469800) && seqs.contains(&1358280) && seqs.contains(&1225970));
+    assert_eq!(seqs.len(), 4);
+}
+
+#[tokio::test]
+async fn imports_a_yomitan_list() {
+    let db = build_sample_db().await;
+    let json = r#"[["猫", "ねこ", "n", "", 0, ["cat"], 1469800, ""], {"expression": "見る", "reading": ""}, {"term": "neko"}, ["猫", "いぬ"]]"#;
+    let report = db.import_yomitan_json(json.as_bytes(), "yomitan").await.unwrap();
+    assert_eq!(report.imported, 3);
+    assert_eq!(report.unresolved.len(), 1);
+    assert_eq!((report.unresolved[0].position, report.unresolved[0].reading.as_deref()), (4, Some("いぬ")));
+    assert_eq!(db.list_items(report.list_id).await.unwrap().len(), 2);
+
+    assert!(db.import_yomitan_json(r#"[{"reading": "ねこ"}]"#.as_bytes(), "broken").await.is_err());
+    assert!(db.import_yomitan_json("{}".as_bytes(), "broken").await.is_err());
+}
+
+#[tokio::test]
+async fn prefers_the_higher_priority_homograph() {
+    let db = build_sample_db().await;
+    let homograph = |ent_seq: &str, priority: &[&str]| Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle { keb: "仮名".to_string(), ke_pri: priority.iter().map(|p| p.to_string()).collect(), ..Default::default() }]),
+        r_ele: vec![REle { reb: "かな".to_string(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec!["kana".to_string()], ..Default::default() }],
+    };
+    db.insert_entries(&[homograph("2900001", &[]), homograph("2900002", &["ichi1"]), homograph("2900003", &["nf40"])])
+        .await
+        .unwrap();
+
+    let report = db.import_anki_tsv("仮名\tかな\n".as_bytes(), "homographs").await.unwrap();
+    assert_eq!(report.imported, 1);
+    assert_eq!(report.ambiguous.len(), 1);
+    assert_eq!(report.ambiguous[0].chosen, 2900002);
+    assert_eq!(report.ambiguous[0].alternatives, [2900003, 2900001]);
+    assert_eq!(db.list_items(report.list_id).await.unwrap()[0].ent_seq, 2900002);
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "userdata")]
+
+use jmdictdb::{DictDb, Entry, KEle, REle, Sense};
+
+const TABERU: i64 = 1358280;
+const IKU: i64 = 1578850;
+const ATAMA: i64 = 1582310;
+
+fn entry(ent_seq: i64, keb: &str, reb: &str, gloss: &str) -> Entry {
+    Entry {
+        ent_seq: ent_seq.to_string(),
+        k_ele: Some(vec![KEle { keb: keb.into(), ..Default::default() }]),
+        r_ele: vec![REle { reb: reb.into(), ..Default::default() }],
+        sense: vec![Sense { gloss: vec![gloss.into()], ..Default::default() }],
+    }
+}
+
+async fn db() -> DictDb {
+    let db = DictDb::connect("sqlite::memory:").await.unwrap();
+    db.insert_entries(&[
+        entry(TABERU, "食べる", "たべる", "to eat"),
+        entry(IKU, "行く", "いく", "to go"),
+        entry(ATAMA, "頭", "あたま", "head"),
+    ])
+    .await
+    .unwrap();
+    db
+}
+
+fn seqs(entries: &[jmdictdb::WordListEntry]) -> Vec<i64> {
+    entries.iter().map(|e| e.entry.ent_seq).collect()
+}
+
+async fn items(db: &DictDb, list: i64) -> Vec<(i64, Option<String>)> {
+    db.list_items(list).await.unwrap().into_iter().map(|i| (i.ent_seq, i.note)).collect()
+}
+
+#[tokio::test]
+async fn lists_are_created_listed_and_deleted() {
+    let db = db().await;
+    let saved = db.create_list("saved").await.unwrap();
+    let deck = db.create_list("deck").await.unwrap();
+    // Names are unique
+    assert!(db.create_list("saved").await.is_err());
+    let names: Vec<(i64, String)> = db.wordlists().await.unwrap().into_iter().map(|l| (l.id, l.name)).collect();
+    assert_eq!(names, [(saved, "saved".to_string()), (deck, "deck".to_string())]);
+
+    db.add_to_list(saved, TABERU, None).await.unwrap();
+    db.add_to_list(deck, TABERU, None).await.unwrap();
+    assert!(db.delete_list(saved).await.unwrap());
+    assert!(!db.delete_list(saved).await.unwrap());
+    assert_eq!(db.wordlists().await.unwrap().len(), 1);
+    // The items went with the list, the other list keeps its own
+    assert!(db.list_items(saved).await.unwrap().is_empty());
+    assert_eq!(items(&db, deck).await, [(TABERU, None)]);
+}
+
+#[tokio::test]
+async fn items_keep_the_order_they_were_added_in() {
+    let db = db().await;
+    let list = db.create_list("saved").await.unwrap();
+    db.add_to_list(list, IKU, Some("on a sign")).await.unwrap();
+    db.add_to_list(list, TABERU, None).await.unwrap();
+    db.add_to_list(list, ATAMA, Some("in a song")).await.unwrap();
+    assert_eq!(seqs(&db.list_entries(list).await.unwrap()), [IKU, TABERU, ATAMA]);
+
+    // Adding again replaces the note and keeps the place
+    db.add_to_list(list, IKU, Some("twice")).await.unwrap();
+    assert_eq!(
+        items(&db, list).await,
+        [(IKU, Some("twice".to_string())), (TABERU, None), (ATAMA, Some("in a song".to_string()))]
+    );
+
+    assert!(db.remove_from_list(list, TABERU).await.unwrap());
+    assert!(!db.remove_from_list(list, TABERU).await.unwrap());
+    assert_eq!(seqs(&db.list_entries(list).await.unwrap()), [IKU, ATAMA]);
+    let entries = db.list_entries(list).await.unwrap();
+    assert_eq!(entries[1].note.as_deref(), Some("in a song"));
+    assert!(entries.iter().all(|e| e.sense.is_none() && e.added_at.len() == 19));
+
+    // Neither a missing list nor a missing entry can be added to
+    assert!(db.add_to_list(list + 1, TABERU, None).await.is_err());
+    assert!(db.add_to_list(list, 1, None).await.is_err());
+}
+
+#[tokio::test]
+async fn sense_items() {
+    let db = db().await;
+    let list = db.create_list("deck").await.unwrap();
+    let senses = db.entry_by_seq(TABERU).await.unwrap().unwrap().senses;
+    let uid = senses.last().unwrap().sense_uid.clone().unwrap();
+    db.add_sense_to_list(list, &uid, Some("the meaning")).await.unwrap();
+    let entries = db.list_entries(list).await.unwrap();
+    assert_eq!(entries[0].sense.as_ref().and_then(|s| s.sense_uid.as_ref()), Some(&uid));
+    assert!(db.add_sense_to_list(list, "no such uid", None).await.is_err());
+
+    // Adding the whole entry makes it a whole-entry item again
+    db.add_to_list(list, TABERU, None).await.unwrap();
+    assert!(db.list_entries(list).await.unwrap()[0].sense.is_none());
+}
+
+#[tokio::test]
+async fn items_outlive_their_deleted_entry() {
+    let db = db().await;
+    let list = db.create_list("saved").await.unwrap();
+    db.add_to_list(list, TABERU, Some("first")).await.unwrap();
+    db.add_to_list(list, IKU, None).await.unwrap();
+
+    assert!(db.delete_entry(TABERU).await.unwrap());
+    // Kept as stored, skipped when listing the entries
+    assert_eq!(items(&db, list).await, [(TABERU, Some("first".to_string())), (IKU, None)]);
+    assert_eq!(seqs(&db.list_entries(list).await.unwrap()), [IKU]);
+    assert!(db.add_to_list(list, TABERU, None).await.is_err());
+
+    // Back in the list once the entry is back, e.g. after a dictionary update
+    db.insert_entries(&[entry(TABERU, "食べる", "たべる", "to eat")]).await.unwrap();
+    let entries = db.list_entries(list).await.unwrap();
+    assert_eq!(seqs(&entries), [TABERU, IKU]);
+    assert_eq!(entries[0].note.as_deref(), Some("first"));
+}
@@ -0,0 +1,108 @@
+use jmdictdb::fixtures::build_sample_db;
+use jmdictdb::{parse_jmdict_str, sense_number_of, sense_order_of, DictError, Xref};
+
+fn xref(keb: Option<&str>, reb: Option<&str>, sense: Option<u32>) -> Xref {
+    Xref { keb: keb.map(str::to_string), reb: reb.map(str::to_string), sense }
+}
+
+/// A raw cross-reference and its expected keb, reb and sense.
+type Case = (&'static str, Option<&'static str>, Option<&'static str>, Option<u32>);
+
+// Cross-references as they appear in JMdict_e
+const REAL_XREFS: &[Case] = &[
+    ("御茶・おちゃ・2", Some("御茶"), Some("おちゃ"), Some(2)),
+    ("為る・する・1", Some("為る"), Some("する"), Some(1)),
+    ("来る・くる", Some("来る"), Some("くる"), None),
+    ("彼・1", Some("彼"), None, Some(1)),
+    ("それ・1", None, Some("それ"), Some(1)),
+    ("お早うございます", Some("お早うございます"), None, None),
+    ("おはよう", None, Some("おはよう"), None),
+    ("ボタン", None, Some("ボタン"), None),
+    ("ＤＶＤ", Some("ＤＶＤ"), None, None),
+    ("ｉモード", Some("ｉモード"), None, None),
+    ("一・いち・1", Some("一"), Some("いち"), Some(1)),
+    ("付く・つく・12", Some("付く"), Some("つく"), Some(12)),
+];
+
+#[test]
+fn real_xrefs_parse_and_display_unchanged() {
+    for &(raw, keb, reb, sense) in REAL_XREFS {
+        let parsed: Xref = raw.parse().unwrap();
+        assert_eq!(parsed, xref(keb, reb, sense), "{}", raw);
+        assert_eq!(parsed.to_string(), raw);
+    }
+}
+
+#[test]
+fn headword_prefers_the_keb() {
+    let parsed: Xref = "御茶・おちゃ・2".parse().unwrap();
+    assert_eq!(parsed.headword(), "御茶");
+    assert_eq!("それ・1".parse::<Xref>().unwrap().headword(), "それ");
+}
+
+#[test]
+fn malformed_xrefs_are_rejected() {
+    for raw in ["", "・", "御茶・・2", "御茶・おちゃ・二", "a・b・1・2"] {
+        assert_eq!(raw.parse::<Xref>(), Err(DictError::InvalidXref(raw.to_string())), "{:?}", raw);
+    }
+}
+
+#[test]
+fn senses_expose_structured_and_raw_xrefs() {
+    let xml = r#"<JMdict><entry><ent_seq>1001470</ent_seq><r_ele><reb>おはよう</reb></r_ele>
+        <sense><xref>お早うございます</xref><xref>御茶・おちゃ・2</xref><gloss>good morning</gloss></sense>
+        </entry></JMdict>"#;
+    let dict = parse_jmdict_str(xml).unwrap();
+    let entry = jmdictdb::EntryParsed::try_from(&dict.entry[0]).unwrap();
+    let sense = &entry.senses[0];
+    assert_eq!(sense.xref[1], xref(Some("御茶"), Some("おちゃ"), Some(2)));
+    assert_eq!(sense.xref_raw(), ["お早うございます", "御茶・おちゃ・2"]);
+}
+
+#[test]
+fn sense_numbers_count_from_one_and_sense_orders_from_zero() {
+    assert_eq!(sense_order_of(1), Some(0));
+    assert_eq!(sense_order_of(2), Some(1));
+    assert_eq!(sense_order_of(0), None);
+    assert_eq!(sense_number_of(0), Some(1));
+    assert_eq!(sense_number_of(1), Some(2));
+    assert_eq!(sense_number_of(-1), None);
+    for order in 0..20 {
+        assert_eq!(sense_order_of(sense_number_of(order).unwrap()), Some(order));
+    }
+    assert_eq!("見る・2".parse::<Xref>().unwrap().sense_order(), Some(1));
+    assert_eq!("見る".parse::<Xref>().unwrap().sense_order(), None);
+}
+
+#[tokio::test]
+async fn get_sense_by_sense_order() {
+    let db = build_sample_db().await;
+    // 見る: to see, to examine, to try
+    let second = db.get_sense(1225970, 1).await.unwrap().unwrap();
+    assert_eq!(second.sense_order, 1);
+    assert_eq!(second.gloss[0], "to examine");
+    assert_eq!(db.get_sense(1225970, 0).await.unwrap().unwrap().gloss[0], "to see");
+    assert!(db.get_sense(1225970, 3).await.unwrap().is_none());
+    assert!(db.get_sense(1225970, -1).await.unwrap().is_none());
+    assert!(db.get_sense(42, 0).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn resolve_xref_uses_sense_numbers() {
+    let db = build_sample_db().await;
+    let target = db.resolve_xref(&"見る・2".parse().unwrap()).await.unwrap().unwrap();
+    assert_eq!(target.entry.ent_seq, 1225970);
+    assert_eq!(target.sense.unwrap().gloss[0], "to examine");
+
+    let target = db.resolve_xref(&"見る・みる・3".parse().unwrap()).await.unwrap().unwrap();
+    assert_eq!(target.sense.unwrap().gloss[0], "to try");
+
+    let whole = db.resolve_xref(&"みる".parse().unwrap()).await.unwrap().unwrap();
+    assert_eq!(whole.entry.ent_seq, 1225970);
+    assert!(whole.sense.is_none());
+
+    // Out of range, sense 0, or another entry's reading
+    assert!(db.resolve_xref(&"見る・4".parse().unwrap()).await.unwrap().is_none());
+    assert!(db.resolve_xref(&"見る・0".parse().unwrap()).await.unwrap().is_none());
+    assert!(db.resolve_xref(&"見る・たべる".parse().unwrap()).await.unwrap().is_none());
+}